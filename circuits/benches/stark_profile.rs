@@ -0,0 +1,69 @@
+//! Reports the number of columns, the constraints and the cross-table lookups
+//! of each stark.
+//!
+//! Run with `cargo bench --bench stark_profile`, and add `-- --json` for a
+//! machine-readable report.
+use mozak_circuits::stark::mozak_stark::MozakStark;
+use mozak_circuits::stark::profile::StarkProfile;
+use plonky2::field::goldilocks_field::GoldilocksField;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Entry {
+    table: String,
+    #[serde(flatten)]
+    profile: StarkProfile,
+}
+
+fn main() {
+    let profile = MozakStark::<GoldilocksField, 2>::default().profile();
+    let entries: Vec<Entry> = profile
+        .with_kind()
+        .0
+        .into_iter()
+        .map(|(profile, kind)| Entry {
+            table: format!("{kind:?}"),
+            profile,
+        })
+        .collect();
+
+    if std::env::args().any(|arg| arg == "--json") {
+        println!("{}", serde_json::to_string_pretty(&entries).unwrap());
+        return;
+    }
+
+    println!(
+        "{:<24} {:>8} {:>6} {:>12} {:>7} {:>9} {:>8} {:>7} {:>11}",
+        "table",
+        "columns",
+        "public",
+        "constraints",
+        "degree",
+        "declared",
+        "lookups",
+        "ctl_zs",
+        "sub_tables"
+    );
+    for Entry { table, profile } in &entries {
+        println!(
+            "{:<24} {:>8} {:>6} {:>12} {:>7} {:>9} {:>8} {:>7} {:>11}",
+            table,
+            profile.columns,
+            profile.public_inputs,
+            profile.constraints,
+            profile.max_constraint_degree,
+            profile.declared_constraint_degree,
+            profile.lookups,
+            profile.ctl_zs,
+            profile.public_sub_tables
+        );
+    }
+    println!(
+        "{:<24} {:>8}",
+        "total",
+        entries
+            .iter()
+            .map(|entry| entry.profile.columns)
+            .sum::<usize>()
+    );
+}
@@ -8,13 +8,55 @@ use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::{
     parse_macro_input, Attribute, Data, DeriveInput, Expr, ExprLit, GenericParam, Ident, Index,
-    Lit, Member, Meta, MetaNameValue, Token, TypeParam,
+    Lit, LitStr, Member, Meta, MetaNameValue, Token, Type, TypeParam,
 };
 
-#[proc_macro_derive(StarkNameDisplay)]
+/// Converts a `PascalCase` name to `snake_case`, or with another `separator`.
+fn separate_words(name: &str, separator: char) -> String {
+    let chars = name.chars().collect_vec();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                out.push(separator);
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+/// Implements `Display` for a stark, as the name of its type.
+///
+/// The name can be fixed, so it stays stable for metrics and timing labels
+/// when the type is renamed, and put in another case:
+/// `#[StarkNameDisplay(name = "RangeCheckU8", case = "snake")]` displays
+/// `range_check_u8`. The cases are `"pascal"`, which is the default,
+/// `"snake"` and `"kebab"`.
+#[proc_macro_error]
+#[proc_macro_derive(StarkNameDisplay, attributes(StarkNameDisplay))]
 pub fn derive(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
 
+    let name = parse_str_attr(ast.attrs.clone(), "StarkNameDisplay", "name")
+        .map_or_else(|| ast.ident.to_string(), |name| name.value());
+    let name = match parse_str_attr(ast.attrs, "StarkNameDisplay", "case") {
+        None => name,
+        Some(case) => match case.value().as_str() {
+            "pascal" => name,
+            "snake" => separate_words(&name, '_'),
+            "kebab" => separate_words(&name, '-'),
+            _ => abort!(case, "'case' should be \"pascal\", \"snake\" or \"kebab\""),
+        },
+    };
+
+    abort_if_dirty();
+
     let (ident, generic_params) = (ast.ident, ast.generics.params);
 
     // Converts `<F, const D: usize>` (sans `<` and `>`) to
@@ -39,7 +81,7 @@ pub fn derive(input: TokenStream) -> TokenStream {
         /// Code generated via proc_macro `StarkNameDisplay`
         impl<#generic_params> std::fmt::Display for #ident<#generic_params_no_attr> {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                write!(f, "{}", std::stringify!(#ident))
+                write!(f, "{}", #name)
             }
         }
     )
@@ -108,11 +150,38 @@ fn parse_single_attr(attrs: Vec<Attribute>, attr_name: &str, key: &str) -> Optio
     parse_attr(val, key)
 }
 
+fn parse_str_attr(attrs: Vec<Attribute>, attr_name: &str, key: &str) -> Option<LitStr> {
+    let attr = parse_attrs(attrs, attr_name);
+    match get_attr(attr, key)? {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(attr),
+            ..
+        }) => Some(attr),
+        kind => {
+            emit_error!(kind, "'{}' should be a string literal", key);
+            None
+        }
+    }
+}
+
+fn parse_type_attr(attrs: Vec<Attribute>, attr_name: &str, key: &str) -> Option<Type> {
+    let attr = parse_str_attr(attrs, attr_name, key)?;
+    consume_err(attr.parse(), |e| {
+        emit_error!(attr, "'{}' should be a type: {}", key, e)
+    })
+}
+
 /// A derive macro which extracts metadata about a `struct` and embeds it in a
 /// `macro`.
 ///
 /// The resulting macro can be used with `tt_call` to easily generate custom
 /// code with `macro_rules`.
+///
+/// If the `struct` is also tagged with the enum of the stark kinds and an
+/// object safe trait all starks implement, eg
+/// `#[StarkSet(kind = "TableKind", dyn_stark = "StarkInfo<F, D>")]`, accessors
+/// are generated, too: `get(kind)`, `iter()` over `(kind, stark)` pairs, and
+/// `map_all(f)`, which returns an array in the order of the kinds.
 #[proc_macro_error]
 #[proc_macro_derive(StarkSet, attributes(StarkSet))]
 pub fn derive_stark_set(input: TokenStream) -> TokenStream {
@@ -124,8 +193,10 @@ pub fn derive_stark_set(input: TokenStream) -> TokenStream {
         _ => abort!(ast, "only structs are supported"),
     };
 
-    let macro_name = parse_single_attr(ast.attrs, "StarkSet", "macro_name")
+    let macro_name = parse_single_attr(ast.attrs.clone(), "StarkSet", "macro_name")
         .unwrap_or_else(|| Ident::new("stark_set", Span::mixed_site()));
+    let kind_ty = parse_type_attr(ast.attrs.clone(), "StarkSet", "kind");
+    let dyn_stark = parse_type_attr(ast.attrs, "StarkSet", "dyn_stark");
 
     let field_info = data
         .fields
@@ -156,10 +227,50 @@ pub fn derive_stark_set(input: TokenStream) -> TokenStream {
         .enumerate()
         .map(|(i, _)| Literal::usize_unsuffixed(i));
 
+    let accessors = match (kind_ty, dyn_stark) {
+        (Some(kind_ty), Some(dyn_stark)) => {
+            let ident = &ast.ident;
+            let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+            quote!(
+                /// Code generated via proc_macro `StarkSet`
+                impl #impl_generics #ident #ty_generics #where_clause {
+                    /// Returns the stark of `kind`.
+                    pub fn get(&self, kind: #kind_ty) -> &dyn #dyn_stark {
+                        match kind {
+                            #(#kind_ty::#kinds => &self.#field_ids,)*
+                        }
+                    }
+
+                    /// Iterates over the starks along with their kinds, in the
+                    /// order of the kinds.
+                    pub fn iter(&self) -> impl Iterator<Item = (#kind_ty, &dyn #dyn_stark)> + '_ {
+                        [#((#kind_ty::#kinds, &self.#field_ids as &dyn #dyn_stark),)*].into_iter()
+                    }
+
+                    /// Calls `f` on every stark along with its kind, and collects
+                    /// the results in the order of the kinds.
+                    pub fn map_all<U>(
+                        &self,
+                        mut f: impl FnMut(#kind_ty, &dyn #dyn_stark) -> U,
+                    ) -> [U; #kind_count] {
+                        [#(f(#kind_ty::#kinds, &self.#field_ids as &dyn #dyn_stark),)*]
+                    }
+                }
+            )
+        }
+        (None, None) => quote!(),
+        _ => abort!(
+            ast_span,
+            "`kind` and `dyn_stark` have to be given together to generate accessors"
+        ),
+    };
+
     abort_if_dirty();
 
     // Generate the macro
     quote!(
+        #accessors
+
         /// Code generated via proc_macro `StarkSet`
         macro_rules! #macro_name {
             {$caller:tt} => {
@@ -176,3 +287,168 @@ pub fn derive_stark_set(input: TokenStream) -> TokenStream {
     )
     .into()
 }
+
+/// A derive macro for `#[repr(C)]` structs of public inputs, generic over the
+/// type of a single input, eg `F` or `Target`.
+///
+/// It generates an index range constant per field, named after the field in
+/// upper case, and `from_public_inputs` to view the public inputs of a proof
+/// or circuit as the struct. The struct has to use `columns_view_impl!`, too.
+#[proc_macro_error]
+#[proc_macro_derive(PublicInputs)]
+pub fn derive_public_inputs(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+
+    let Data::Struct(data) = ast.data else {
+        abort!(ast.ident, "only structs are supported")
+    };
+    if !ast.attrs.iter().any(|attr| {
+        attr.path().is_ident("repr") && attr.parse_args::<Ident>().is_ok_and(|repr| repr == "C")
+    }) {
+        abort!(ast.ident, "the layout has to be fixed with `#[repr(C)]`");
+    }
+    let ident = ast.ident;
+    let fields = data
+        .fields
+        .into_iter()
+        .map(|field| match field.ident {
+            Some(ident) => ident,
+            None => abort!(field.ty, "only named fields are supported"),
+        })
+        .collect_vec();
+    let consts = fields
+        .iter()
+        .map(|field| Ident::new(&field.to_string().to_uppercase(), field.span()));
+    let ends = fields
+        .iter()
+        .skip(1)
+        .map(|next| quote!(core::mem::offset_of!(#ident<u8>, #next)))
+        .chain([quote!(core::mem::size_of::<#ident<u8>>())]);
+    let docs = fields
+        .iter()
+        .map(|field| format!("Where `{field}` is in the public inputs."));
+
+    quote!(
+        /// Code generated via proc_macro `PublicInputs`
+        impl<T> #ident<T> {
+            #(
+                #[doc = #docs]
+                pub const #consts: core::ops::Range<usize> =
+                    core::mem::offset_of!(#ident<u8>, #fields)..#ends;
+            )*
+
+            /// Views the public inputs of a proof, or the public input targets
+            /// of a circuit, as `Self`. Returns `None` if there are not as
+            /// many as `Self` has.
+            #[must_use]
+            pub fn from_public_inputs(public_inputs: &[T]) -> Option<&Self> {
+                let public_inputs: &[T; core::mem::size_of::<#ident<u8>>()] =
+                    public_inputs.try_into().ok()?;
+                Some(Self::from_array_ref(public_inputs))
+            }
+        }
+
+        /// Code generated via proc_macro `PublicInputs`
+        impl #ident<plonky2::iop::target::Target> {
+            /// Registers the targets as the public inputs of `builder`, in order.
+            pub fn register<F, const D: usize>(
+                &self,
+                builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+            ) where
+                F: plonky2::hash::hash_types::RichField + plonky2::field::extension::Extendable<D>,
+            {
+                builder.register_public_inputs(self.array_ref());
+            }
+        }
+    )
+    .into()
+}
+
+/// A derive macro for the columns of a table, which collects the columns that
+/// are looked up in another table.
+///
+/// Fields are tagged with the tables they are looked up in, eg
+/// `#[CtlColumns(looking = "rangecheck_u8")]`, and for each of those tables a
+/// method is generated, eg `rangecheck_u8_columns`, which returns the tagged
+/// fields in order. Arrays and nested views contribute all of their columns.
+///
+/// Called on the table's `COL_MAP`, this gives the columns to build the
+/// lookups from, without listing them by hand.
+#[proc_macro_error]
+#[proc_macro_derive(CtlColumns, attributes(CtlColumns))]
+pub fn derive_ctl_columns(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+
+    let Data::Struct(data) = ast.data else {
+        abort!(ast.ident, "only structs are supported")
+    };
+    let Some(column) = ast
+        .generics
+        .type_params()
+        .next()
+        .map(|param| param.ident.clone())
+    else {
+        abort!(ast.ident, "the columns have to be generic over their type")
+    };
+
+    let mut lookups: Vec<(Ident, Vec<proc_macro2::TokenStream>)> = vec![];
+    for field in data.fields {
+        let Some(ident) = field.ident else {
+            abort!(field.ty, "only named fields are supported")
+        };
+        let is_single_column = matches!(&field.ty, Type::Path(ty) if ty.path.is_ident(&column));
+        let push = if is_single_column {
+            quote!(columns.push(self.#ident);)
+        } else {
+            quote!(columns.extend(self.#ident);)
+        };
+        for looking in parse_attrs(field.attrs, "CtlColumns").filter_map(|meta| {
+            if meta.path.is_ident("looking") {
+                parse_attr(meta.value, "looking")
+            } else {
+                emit_error!(meta.path, "unknown attribute, expected 'looking'");
+                None
+            }
+        }) {
+            match lookups.iter_mut().find(|(table, _)| *table == looking) {
+                Some((_, pushes)) => pushes.push(push.clone()),
+                None => lookups.push((looking, vec![push.clone()])),
+            }
+        }
+    }
+    if lookups.is_empty() {
+        emit_warning!(
+            ast.ident,
+            r#"No lookups found, did you forget to tag fields with `#[CtlColumns(looking = "...")]`?"#
+        );
+    }
+
+    abort_if_dirty();
+
+    let ident = ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let methods = lookups.into_iter().map(|(table, pushes)| {
+        let method = Ident::new(&format!("{table}_columns"), table.span());
+        let doc = format!("The columns looked up in the `{table}` table, in order.");
+        quote!(
+            #[doc = #doc]
+            #[must_use]
+            pub fn #method(self) -> Vec<#column>
+            where
+                #column: Copy,
+            {
+                let mut columns = Vec::new();
+                #(#pushes)*
+                columns
+            }
+        )
+    });
+
+    quote!(
+        /// Code generated via proc_macro `CtlColumns`
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#methods)*
+        }
+    )
+    .into()
+}
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use expr::{Expr, ExprBuilder, StarkFrameTyped};
+use expr::{ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
 use mozak_circuits_derive::StarkNameDisplay;
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
@@ -13,7 +13,7 @@ use starky::stark::Stark;
 
 use super::columns::BitshiftView;
 use crate::columns_view::{HasNamedColumns, NumberOfColumns};
-use crate::expr::{build_ext, build_packed, ConstraintBuilder};
+use crate::expr::{build_ext, build_packed};
 use crate::unstark::NoColumns;
 
 /// Bitshift Trace Constraints
@@ -32,10 +32,10 @@ const PUBLIC_INPUTS: usize = 0;
 
 fn generate_constraints<'a, T: Copy>(
     vars: &StarkFrameTyped<BitshiftView<Expr<'a, T>>, NoColumns<Expr<'a, T>>>,
-) -> ConstraintBuilder<Expr<'a, T>> {
+) -> ConstraintCollector<'a, T> {
     let lv = vars.local_values.executed;
     let nv = vars.next_values.executed;
-    let mut constraints = ConstraintBuilder::default();
+    let mut constraints = ConstraintCollector::default();
 
     // Constraints on shift amount
     // They ensure:
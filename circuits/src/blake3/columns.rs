@@ -0,0 +1,218 @@
+use core::iter::Sum;
+
+use itertools::{chain, izip};
+use mozak_sdk::core::blake3::{BLOCK_BYTES, CV_BYTES, ROUNDS, STATE_WORDS};
+use mozak_sdk::core::reg_abi::{REG_A1, REG_A2, REG_A3, REG_A4, REG_A5, REG_A6, REG_A7};
+
+use crate::columns_view::{columns_view_impl, make_col_map, NumberOfColumns};
+use crate::cross_table_lookup::ColumnWithTypedInput;
+use crate::linear_combination::Column;
+use crate::memory::columns::MemoryCtl;
+use crate::register::RegisterCtl;
+use crate::stark::mozak_stark::{Blake3Table, TableWithTypedOutput};
+
+/// The number of bits in a word.
+pub const WORD_BITS: usize = 32;
+
+/// The number of applications of `G` in a compression.
+pub const STEPS: usize = ROUNDS * 8;
+
+/// The number of rows that fold the final state into the new chaining
+/// value, two words per row.
+pub const OUTPUT_STEPS: usize = 4;
+
+/// How the state of the next row is laid out, relative to the current row.
+///
+/// Seen as a 4x4 matrix, every row mixes column 0.  Rotating each row of the
+/// matrix one to the left lines up the next column.  After the fourth
+/// column the rows are also shifted left by their index, which lines up the
+/// diagonals as columns, and after the fourth diagonal they are shifted back.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Layout {
+    Rotate,
+    Diagonalize,
+    Undiagonalize,
+}
+
+impl Layout {
+    /// The layout of the row after step `step` of a round.
+    #[must_use]
+    pub const fn after(step: usize) -> Self {
+        match step % 8 {
+            3 => Layout::Diagonalize,
+            7 => Layout::Undiagonalize,
+            _ => Layout::Rotate,
+        }
+    }
+
+    /// The position in the current row of the word at position `i` of the
+    /// next row.
+    #[must_use]
+    pub const fn source(self, i: usize) -> usize {
+        let (row, column) = (i / 4, i % 4);
+        let column = match self {
+            Layout::Rotate => column + 1,
+            Layout::Diagonalize => column + row + 1,
+            Layout::Undiagonalize => column + 5 - row,
+        };
+        4 * row + column % 4
+    }
+}
+
+/// The intermediate words of one application of the mixing function `G`,
+/// as little-endian bits.
+///
+/// `G` updates each of its four words twice; `a1..d1` are the words after
+/// the first half, and `a2..d2` the words it returns.
+#[repr(C)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct Mix<T> {
+    pub a1: [T; WORD_BITS],
+    pub d1: [T; WORD_BITS],
+    pub c1: [T; WORD_BITS],
+    pub b1: [T; WORD_BITS],
+    pub a2: [T; WORD_BITS],
+    pub d2: [T; WORD_BITS],
+    pub c2: [T; WORD_BITS],
+    pub b2: [T; WORD_BITS],
+    /// What overflows 32 bits in the additions.
+    pub carry_a1: [T; 2],
+    pub carry_c1: T,
+    pub carry_a2: [T; 2],
+    pub carry_c2: T,
+}
+
+columns_view_impl!(Mix);
+
+/// One row per application of `G` in the BLAKE3 compression function,
+/// followed by the rows that fold the final state into the new chaining
+/// value.
+#[repr(C)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct Blake3<T> {
+    pub clk: T,
+    pub cv_addr: T,
+    pub block_addr: T,
+    pub output_addr: T,
+    pub counter_lo: T,
+    pub counter_hi: T,
+    pub block_len: T,
+    pub flags: T,
+    /// One-hot encoding of the application of `G` this row computes, all
+    /// zero on the output rows and on padding.
+    pub step: [T; STEPS],
+    /// One-hot encoding of the output row, all zero otherwise.
+    pub output_step: [T; OUTPUT_STEPS],
+    /// The state, laid out so that this row works on words 0, 4, 8 and 12.
+    pub state: [T; STATE_WORDS],
+    /// The bits of words 0, 4, 8 and 12 of the state.
+    pub bits: [[T; WORD_BITS]; 4],
+    /// The message words, rotated so that this row consumes words 0 and 1.
+    pub m: [T; STATE_WORDS],
+    pub mix: Mix<T>,
+    /// The bytes of the chaining value, on the first step.
+    pub cv_bytes: [T; CV_BYTES],
+    /// The bytes of the block, on the first step.
+    pub block_bytes: [T; BLOCK_BYTES],
+    /// The bytes of the two words of the new chaining value an output row
+    /// computes.
+    pub output_bytes: [T; 8],
+}
+
+columns_view_impl!(Blake3);
+make_col_map!(Blake3);
+
+pub const NUM_BLAKE3_COLS: usize = Blake3::<()>::NUMBER_OF_COLUMNS;
+
+impl<T: Copy + Sum> Blake3<T> {
+    pub fn is_mix(&self) -> T { self.step.into_iter().sum() }
+
+    pub fn is_output(&self) -> T { self.output_step.into_iter().sum() }
+}
+
+columns_view_impl!(Blake3Ctl);
+#[repr(C)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct Blake3Ctl<T> {
+    pub clk: T,
+}
+
+#[must_use]
+pub fn lookup_for_cpu() -> TableWithTypedOutput<Blake3Ctl<Column>> {
+    Blake3Table::new(Blake3Ctl { clk: COL_MAP.clk }, COL_MAP.step[0])
+}
+
+#[must_use]
+pub fn register_looking() -> Vec<TableWithTypedOutput<RegisterCtl<Column>>> {
+    let is_read = ColumnWithTypedInput::constant(1);
+    [
+        (COL_MAP.cv_addr, REG_A1),
+        (COL_MAP.block_addr, REG_A2),
+        (COL_MAP.output_addr, REG_A3),
+        (COL_MAP.counter_lo, REG_A4),
+        (COL_MAP.counter_hi, REG_A5),
+        (COL_MAP.block_len, REG_A6),
+        (COL_MAP.flags, REG_A7),
+    ]
+    .into_iter()
+    .map(|(value, reg)| {
+        Blake3Table::new(
+            RegisterCtl {
+                clk: COL_MAP.clk,
+                op: is_read,
+                value,
+                addr: ColumnWithTypedInput::constant(reg.into()),
+            },
+            COL_MAP.step[0],
+        )
+    })
+    .collect()
+}
+
+/// Byte `i` of `bytes` is read from, or stored to, `addr + i` on the rows
+/// `filter` selects.
+fn memory_access<const N: usize>(
+    addr: ColumnWithTypedInput<Blake3<i64>>,
+    bytes: [ColumnWithTypedInput<Blake3<i64>>; N],
+    is_store: bool,
+    filter: ColumnWithTypedInput<Blake3<i64>>,
+) -> impl Iterator<Item = TableWithTypedOutput<MemoryCtl<Column>>> {
+    izip!(0.., bytes).map(move |(i, value)| {
+        Blake3Table::new(
+            MemoryCtl {
+                clk: COL_MAP.clk,
+                is_store: ColumnWithTypedInput::constant(is_store.into()),
+                is_load: ColumnWithTypedInput::constant((!is_store).into()),
+                value,
+                addr: addr + i,
+            },
+            filter,
+        )
+    })
+}
+
+/// Reads the chaining value and the block on the first step, and writes the
+/// new chaining value over the output rows.  Output row `k` writes words
+/// `k` and `k + 4`.
+pub fn lookup_for_memory() -> impl Iterator<Item = TableWithTypedOutput<MemoryCtl<Column>>> {
+    let output_step = ColumnWithTypedInput::ascending_sum(COL_MAP.output_step);
+    let is_output: ColumnWithTypedInput<_> = COL_MAP.output_step.iter().sum();
+    let [lo @ .., _, _, _, _] = COL_MAP.output_bytes;
+    let [_, _, _, _, hi @ ..] = COL_MAP.output_bytes;
+    chain!(
+        memory_access(COL_MAP.cv_addr, COL_MAP.cv_bytes, false, COL_MAP.step[0]),
+        memory_access(
+            COL_MAP.block_addr,
+            COL_MAP.block_bytes,
+            false,
+            COL_MAP.step[0]
+        ),
+        memory_access(COL_MAP.output_addr + output_step * 4, lo, true, is_output),
+        memory_access(
+            COL_MAP.output_addr + output_step * 4 + 16,
+            hi,
+            true,
+            is_output
+        ),
+    )
+}
@@ -0,0 +1,185 @@
+use mozak_runner::blake3::Entry;
+use mozak_runner::vm::Row;
+use mozak_sdk::core::blake3::{initial_state, permute, words, BLOCK_BYTES, CV_BYTES, STATE_WORDS};
+use plonky2::hash::hash_types::RichField;
+
+use crate::blake3::columns::{Blake3, Layout, Mix, OUTPUT_STEPS, STEPS};
+use crate::utils::{from_u32, pad_trace_with_default};
+
+pub fn filter<F: RichField>(step_rows: &[Row<F>]) -> impl Iterator<Item = (u64, &Entry)> {
+    step_rows
+        .iter()
+        .filter_map(|row| Some((row.state.clk, row.aux.blake3.as_ref()?)))
+}
+
+/// The little-endian bits of `x`.
+fn bits<F: RichField, const N: usize>(x: impl Into<u64>) -> [F; N] {
+    let x = x.into();
+    core::array::from_fn(|i| F::from_bool((x >> i) & 1 == 1))
+}
+
+/// The bits of what the sum of `terms` overflows 32 bits by.
+fn carry<F: RichField, const N: usize>(terms: &[u32]) -> [F; N] {
+    bits(terms.iter().copied().map(u64::from).sum::<u64>() >> 32)
+}
+
+/// The intermediate words of `G` on `[a, b, c, d]`, with message words `mx`
+/// and `my`, and the words it returns.
+fn mix<F: RichField>([a, b, c, d]: [u32; 4], mx: u32, my: u32) -> (Mix<F>, [u32; 4]) {
+    let a1 = a.wrapping_add(b).wrapping_add(mx);
+    let d1 = (d ^ a1).rotate_right(16);
+    let c1 = c.wrapping_add(d1);
+    let b1 = (b ^ c1).rotate_right(12);
+    let a2 = a1.wrapping_add(b1).wrapping_add(my);
+    let d2 = (d1 ^ a2).rotate_right(8);
+    let c2 = c1.wrapping_add(d2);
+    let b2 = (b1 ^ c2).rotate_right(7);
+    let mix = Mix {
+        a1: bits(a1),
+        d1: bits(d1),
+        c1: bits(c1),
+        b1: bits(b1),
+        a2: bits(a2),
+        d2: bits(d2),
+        c2: bits(c2),
+        b2: bits(b2),
+        carry_a1: carry(&[a, b, mx]),
+        carry_c1: carry::<F, 1>(&[c, d1])[0],
+        carry_a2: carry(&[a1, b1, my]),
+        carry_c2: carry::<F, 1>(&[c1, d2])[0],
+    };
+    (mix, [a2, b2, c2, d2])
+}
+
+/// The words of `state` at the positions a row works on.
+fn column(state: &[u32; STATE_WORDS]) -> [u32; 4] { [0, 4, 8, 12].map(|i| state[i]) }
+
+/// The state of the next row, laid out by `layout`.
+fn rearrange(state: &[u32; STATE_WORDS], layout: Layout) -> [u32; STATE_WORDS] {
+    core::array::from_fn(|i| state[layout.source(i)])
+}
+
+/// One row per application of `G`, and the output rows.
+fn unroll<F: RichField>(clk: u64, entry: &Entry) -> Vec<Blake3<F>> {
+    let template = Blake3 {
+        clk: F::from_canonical_u64(clk),
+        cv_addr: from_u32(entry.cv_addr),
+        block_addr: from_u32(entry.block_addr),
+        output_addr: from_u32(entry.output_addr),
+        counter_lo: from_u32(entry.counter_lo),
+        counter_hi: from_u32(entry.counter_hi),
+        block_len: from_u32(entry.block_len),
+        flags: from_u32(entry.flags),
+        ..Default::default()
+    };
+    let mut m: [u32; STATE_WORDS] = words(&entry.block);
+    let mut state = initial_state(
+        &words(&entry.cv),
+        entry.counter(),
+        entry.block_len,
+        entry.flags,
+    );
+
+    let mut rows = Vec::with_capacity(STEPS + OUTPUT_STEPS);
+    for step in 0..STEPS {
+        let (mixed, [a, b, c, d]) = mix(column(&state), m[0], m[1]);
+        rows.push(Blake3 {
+            step: core::array::from_fn(|i| F::from_bool(i == step)),
+            state: state.map(from_u32),
+            bits: column(&state).map(bits),
+            m: m.map(from_u32),
+            mix: mixed,
+            cv_bytes: if step == 0 { entry.cv } else { [0; CV_BYTES] }.map(F::from_canonical_u8),
+            block_bytes: if step == 0 {
+                entry.block
+            } else {
+                [0; BLOCK_BYTES]
+            }
+            .map(F::from_canonical_u8),
+            ..template
+        });
+        [state[0], state[4], state[8], state[12]] = [a, b, c, d];
+        let layout = Layout::after(step);
+        state = rearrange(&state, layout);
+        m = core::array::from_fn(|i| m[(i + 2) % STATE_WORDS]);
+        if layout == Layout::Undiagonalize {
+            // The round consumed the message two words at a time, which took
+            // it all the way around; permute it for the next round.
+            m = permute(&m);
+        }
+    }
+
+    for output_step in 0..OUTPUT_STEPS {
+        let [a, b, c, d] = column(&state);
+        let [lo, hi] = [a ^ c, b ^ d].map(u32::to_le_bytes);
+        rows.push(Blake3 {
+            output_step: core::array::from_fn(|i| F::from_bool(i == output_step)),
+            state: state.map(from_u32),
+            bits: column(&state).map(bits),
+            output_bytes: core::array::from_fn(|i| F::from_canonical_u8([lo, hi][i / 4][i % 4])),
+            ..template
+        });
+        state = rearrange(&state, Layout::Rotate);
+    }
+    rows
+}
+
+#[must_use]
+pub fn generate_blake3_trace<F: RichField>(step_rows: &[Row<F>]) -> Vec<Blake3<F>> {
+    let trace = pad_trace_with_default(
+        filter(step_rows)
+            .flat_map(|(clk, entry)| unroll(clk, entry))
+            .collect(),
+    );
+    log::trace!("BLAKE3 trace {:#?}", trace);
+    trace
+}
+
+#[cfg(test)]
+mod tests {
+    use mozak_sdk::core::blake3::{compress, BLOCK_BYTES, CHUNK_START, INITIAL_CV};
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::Field;
+
+    use super::generate_blake3_trace;
+    use crate::blake3::columns::{Blake3, OUTPUT_STEPS, STEPS};
+    use crate::generation::MIN_TRACE_LENGTH;
+    use crate::test_utils::{create_blake3_test, Blake3Test};
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn generate_blake3_trace_rows() {
+        let block = [0x5a; BLOCK_BYTES];
+        let (_program, record) = create_blake3_test(&[Blake3Test {
+            cv: INITIAL_CV,
+            block,
+            counter: 3,
+            block_len: 64,
+            flags: CHUNK_START,
+            cv_addr: 1024,
+            block_addr: 2048,
+            output_addr: 4096,
+        }]);
+        let trace: Vec<Blake3<F>> = generate_blake3_trace(&record.executed);
+
+        assert_eq!(trace.len(), (STEPS + OUTPUT_STEPS).next_power_of_two());
+        // Output row `k` computes words `k` and `k + 4` of the new chaining
+        // value.
+        let output = compress(&INITIAL_CV, &block, 3, 64, CHUNK_START);
+        for (k, row) in trace[STEPS..STEPS + OUTPUT_STEPS].iter().enumerate() {
+            assert!(row.output_step[k].is_one());
+            let bytes: [u8; 8] = core::array::from_fn(|i| output[4 * k + 16 * (i / 4) + i % 4]);
+            assert_eq!(row.output_bytes, bytes.map(F::from_canonical_u8));
+        }
+        assert!(trace[STEPS + OUTPUT_STEPS..]
+            .iter()
+            .all(|row| row.is_mix().is_zero() && row.is_output().is_zero()));
+    }
+
+    #[test]
+    fn generate_blake3_trace_with_dummy() {
+        let trace: Vec<Blake3<F>> = generate_blake3_trace(&[]);
+        assert_eq!(trace.len(), MIN_TRACE_LENGTH);
+    }
+}
@@ -0,0 +1,333 @@
+use std::marker::PhantomData;
+
+use expr::{ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
+use itertools::{chain, izip};
+use mozak_circuits_derive::StarkNameDisplay;
+use mozak_sdk::core::blake3::{IV, MSG_PERMUTATION, STATE_WORDS};
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use starky::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use starky::evaluation_frame::StarkFrame;
+use starky::stark::Stark;
+
+use super::columns::{Blake3, Layout, NUM_BLAKE3_COLS, OUTPUT_STEPS, STEPS, WORD_BITS};
+use crate::columns_view::HasNamedColumns;
+use crate::expr::{build_ext, build_packed};
+use crate::unstark::NoColumns;
+
+#[derive(Copy, Clone, Default, StarkNameDisplay)]
+#[allow(clippy::module_name_repetitions)]
+pub struct Blake3Stark<F, const D: usize> {
+    pub _f: PhantomData<F>,
+}
+
+impl<F, const D: usize> HasNamedColumns for Blake3Stark<F, D> {
+    type Columns = Blake3<F>;
+}
+
+const COLUMNS: usize = NUM_BLAKE3_COLS;
+const PUBLIC_INPUTS: usize = 0;
+
+/// Packs little-endian `bits` into a word.
+fn pack<'a, T>(bits: [Expr<'a, T>; WORD_BITS]) -> Expr<'a, T> { Expr::reduce_with_powers(bits, 2) }
+
+/// Packs little-endian `bytes` into a word.
+fn pack_bytes<'a, T: Copy>(bytes: &[Expr<'a, T>]) -> Expr<'a, T> {
+    Expr::reduce_with_powers(bytes.iter().copied(), 256)
+}
+
+/// The xor of the words with bits `x` and `y`, rotated right by `n`.
+fn xor_rotate_right<'a, T: Copy>(
+    x: [Expr<'a, T>; WORD_BITS],
+    y: [Expr<'a, T>; WORD_BITS],
+    n: usize,
+) -> Expr<'a, T> {
+    Expr::reduce_with_powers(
+        (0..WORD_BITS).map(|i| {
+            let (x, y) = (x[(i + n) % WORD_BITS], y[(i + n) % WORD_BITS]);
+            x + y - 2 * x * y
+        }),
+        2,
+    )
+}
+
+/// The sum of the steps of every round that `step_of_round` picks out.
+fn every_round<'a, T: Copy>(step: [Expr<'a, T>; STEPS], step_of_round: usize) -> Expr<'a, T> {
+    step.into_iter().skip(step_of_round).step_by(8).sum()
+}
+
+#[allow(clippy::too_many_lines)]
+fn generate_constraints<'a, T: Copy>(
+    vars: &StarkFrameTyped<Blake3<Expr<'a, T>>, NoColumns<Expr<'a, T>>>,
+) -> ConstraintCollector<'a, T> {
+    let lv = vars.local_values;
+    let nv = vars.next_values;
+    let mix = lv.mix;
+    let mut constraints = ConstraintCollector::default();
+
+    let is_mix = lv.is_mix();
+    for bit in chain!(
+        lv.step,
+        lv.output_step,
+        [is_mix + lv.is_output()],
+        lv.bits.into_iter().flatten(),
+        mix.a1,
+        mix.d1,
+        mix.c1,
+        mix.b1,
+        mix.a2,
+        mix.d2,
+        mix.c2,
+        mix.b2,
+        mix.carry_a1,
+        [mix.carry_c1],
+        mix.carry_a2,
+        [mix.carry_c2],
+    ) {
+        constraints.always(bit.is_binary());
+    }
+
+    // The steps of a compression follow each other, and end with the output
+    // rows.
+    for (next, current) in izip!(nv.step.into_iter().skip(1), lv.step) {
+        constraints.transition(next - current);
+    }
+    constraints.transition(nv.output_step[0] - lv.step[STEPS - 1]);
+    for (next, current) in izip!(nv.output_step.into_iter().skip(1), lv.output_step) {
+        constraints.transition(next - current);
+    }
+    constraints.first_row(is_mix + lv.is_output() - lv.step[0]);
+    constraints.last_row(is_mix);
+    for output_step in &lv.output_step[..OUTPUT_STEPS - 1] {
+        constraints.last_row(*output_step);
+    }
+
+    for (word, bits) in izip!(
+        [lv.state[0], lv.state[4], lv.state[8], lv.state[12]],
+        lv.bits
+    ) {
+        constraints.always(word - pack(bits));
+    }
+
+    // `G` on words 0, 4, 8 and 12.
+    let [a, b, c, d] = lv.bits;
+    constraints.always(
+        is_mix
+            * (pack(mix.a1) + (1 << 32) * Expr::reduce_with_powers(mix.carry_a1, 2)
+                - (lv.state[0] + lv.state[4] + lv.m[0])),
+    );
+    constraints.always(is_mix * (pack(mix.d1) - xor_rotate_right(d, mix.a1, 16)));
+    constraints
+        .always(is_mix * (pack(mix.c1) + (1 << 32) * mix.carry_c1 - (lv.state[8] + pack(mix.d1))));
+    constraints.always(is_mix * (pack(mix.b1) - xor_rotate_right(b, mix.c1, 12)));
+    constraints.always(
+        is_mix
+            * (pack(mix.a2) + (1 << 32) * Expr::reduce_with_powers(mix.carry_a2, 2)
+                - (pack(mix.a1) + pack(mix.b1) + lv.m[1])),
+    );
+    constraints.always(is_mix * (pack(mix.d2) - xor_rotate_right(mix.d1, mix.a2, 8)));
+    constraints
+        .always(is_mix * (pack(mix.c2) + (1 << 32) * mix.carry_c2 - (pack(mix.c1) + pack(mix.d2))));
+    constraints.always(is_mix * (pack(mix.b2) - xor_rotate_right(mix.b1, mix.c2, 7)));
+
+    // The next row lines up the words the next application of `G` works on.
+    let mut mixed = lv.state;
+    [mixed[0], mixed[4], mixed[8], mixed[12]] =
+        [pack(mix.a2), pack(mix.b2), pack(mix.c2), pack(mix.d2)];
+    let after_columns = every_round(lv.step, 3);
+    let after_diagonals = every_round(lv.step, 7);
+    let rotates = is_mix - after_columns - after_diagonals;
+    let output_rotates: Expr<'a, T> = lv.output_step[..OUTPUT_STEPS - 1].iter().copied().sum();
+    for (i, next) in nv.state.into_iter().enumerate() {
+        constraints.transition(
+            rotates * (next - mixed[Layout::Rotate.source(i)])
+                + after_columns * (next - mixed[Layout::Diagonalize.source(i)])
+                + after_diagonals * (next - mixed[Layout::Undiagonalize.source(i)])
+                + output_rotates * (next - lv.state[Layout::Rotate.source(i)]),
+        );
+    }
+
+    // Every step consumes two message words, and every round but the last
+    // is followed by the permutation of the message.
+    let permutes = after_diagonals - lv.step[STEPS - 1];
+    let shifts = is_mix - after_diagonals;
+    for (i, next) in nv.m.into_iter().enumerate() {
+        constraints.transition(
+            shifts * (next - lv.m[(i + 2) % STATE_WORDS])
+                + permutes * (next - lv.m[(MSG_PERMUTATION[i] + 2) % STATE_WORDS]),
+        );
+    }
+
+    for (next, current) in [
+        (nv.clk, lv.clk),
+        (nv.cv_addr, lv.cv_addr),
+        (nv.block_addr, lv.block_addr),
+        (nv.output_addr, lv.output_addr),
+        (nv.counter_lo, lv.counter_lo),
+        (nv.counter_hi, lv.counter_hi),
+        (nv.block_len, lv.block_len),
+        (nv.flags, lv.flags),
+    ] {
+        constraints.transition((is_mix + output_rotates) * (next - current));
+    }
+
+    // The first step starts from the chaining value, the constants, and the
+    // parameters, and reads the message from the block.
+    let initial = chain!(
+        lv.cv_bytes.chunks_exact(4).map(pack_bytes),
+        IV[..4].iter().map(|&iv| Expr::from(i64::from(iv))),
+        [lv.counter_lo, lv.counter_hi, lv.block_len, lv.flags],
+    );
+    for (word, initial) in izip!(lv.state, initial) {
+        constraints.always(lv.step[0] * (word - initial));
+    }
+    for (word, bytes) in izip!(lv.m, lv.block_bytes.chunks_exact(4)) {
+        constraints.always(lv.step[0] * (word - pack_bytes(bytes)));
+    }
+
+    // Output row `k` folds words `k + 8` and `k + 12` of the final state into
+    // words `k` and `k + 4`.
+    let (lo, hi) = lv.output_bytes.split_at(4);
+    constraints.always(lv.is_output() * (pack_bytes(lo) - xor_rotate_right(a, c, 0)));
+    constraints.always(lv.is_output() * (pack_bytes(hi) - xor_rotate_right(b, d, 0)));
+
+    constraints
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for Blake3Stark<F, D> {
+    type EvaluationFrame<FE, P, const D2: usize> = StarkFrame<P, P::Scalar, COLUMNS, PUBLIC_INPUTS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+    type EvaluationFrameTarget =
+        StarkFrame<ExtensionTarget<D>, ExtensionTarget<D>, COLUMNS, PUBLIC_INPUTS>;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        consumer: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>, {
+        let eb = ExprBuilder::default();
+        let constraints = generate_constraints(&eb.to_typed_starkframe(vars));
+        build_packed(constraints, consumer);
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        consumer: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let eb = ExprBuilder::default();
+        let constraints = generate_constraints(&eb.to_typed_starkframe(vars));
+        build_ext(constraints, builder, consumer);
+    }
+
+    fn constraint_degree(&self) -> usize { 3 }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use mozak_sdk::core::blake3::{
+        BLOCK_BYTES, CHUNK_END, CHUNK_START, CV_BYTES, INITIAL_CV, PARENT, ROOT,
+    };
+    use plonky2::plonk::config::{GenericConfig, Poseidon2GoldilocksConfig};
+    use plonky2::util::timing::TimingTree;
+    use starky::config::StarkConfig;
+    use starky::prover::prove;
+    use starky::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use starky::verifier::verify_stark_proof;
+
+    use super::Blake3Stark;
+    use crate::blake3::generation::generate_blake3_trace;
+    use crate::stark::utils::trace_rows_to_poly_values;
+    use crate::test_utils::{create_blake3_test, Blake3Test};
+
+    const D: usize = 2;
+    type C = Poseidon2GoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type S = Blake3Stark<F, D>;
+
+    fn blake3_constraints(tests: &[Blake3Test]) -> Result<()> {
+        let _ = env_logger::try_init();
+        let mut config = StarkConfig::standard_fast_config();
+        config.fri_config.cap_height = 0;
+        config.fri_config.rate_bits = 3; // to meet the constraint degree bound
+
+        let (_program, record) = create_blake3_test(tests);
+
+        let stark = S::default();
+        let trace = generate_blake3_trace(&record.executed);
+        let trace_poly_values = trace_rows_to_poly_values(trace);
+
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace_poly_values,
+            &[],
+            &mut TimingTree::default(),
+        )?;
+        verify_stark_proof(stark, proof, &config)
+    }
+
+    #[test]
+    fn prove_blake3() -> Result<()> {
+        // "abc", as a single block.
+        let mut block = [0; BLOCK_BYTES];
+        block[..3].copy_from_slice(b"abc");
+        blake3_constraints(&[Blake3Test {
+            cv: INITIAL_CV,
+            block,
+            counter: 0,
+            block_len: 3,
+            flags: CHUNK_START | CHUNK_END | ROOT,
+            cv_addr: 1024,
+            block_addr: 2048,
+            output_addr: 4096,
+        }])
+    }
+
+    #[test]
+    fn prove_blake3_multiple() -> Result<()> {
+        blake3_constraints(&[
+            Blake3Test {
+                cv: INITIAL_CV,
+                block: [0xa5; BLOCK_BYTES],
+                counter: u64::MAX,
+                block_len: 64,
+                flags: CHUNK_START,
+                cv_addr: 1024,
+                block_addr: 2048,
+                output_addr: 4096,
+            },
+            Blake3Test {
+                cv: [0xff; CV_BYTES],
+                block: [0xff; BLOCK_BYTES],
+                counter: 0,
+                block_len: 64,
+                flags: PARENT,
+                cv_addr: 1024 + 32,
+                block_addr: 2048 + 64,
+                output_addr: 4096 + 32,
+            },
+        ])
+    }
+
+    #[test]
+    fn blake3_stark_degree() -> Result<()> {
+        let stark = S::default();
+        test_stark_low_degree(stark)
+    }
+
+    #[test]
+    fn test_circuit() -> Result<()> {
+        let stark = S::default();
+        test_stark_circuit_constraints::<F, C, S, D>(stark)
+    }
+}
@@ -24,10 +24,9 @@
 //!  2 * (x | y) := (x + y + (x ^ y))
 //! `
 
-use expr::Expr;
+use expr::{ConstraintCollector, Expr};
 
 use super::columns::CpuState;
-use crate::expr::ConstraintBuilder;
 use crate::xor::columns::XorView;
 
 /// A struct to represent the output of binary operations
@@ -92,7 +91,7 @@ pub(crate) fn xor_gadget<'a, P: Copy>(xor: &XorView<Expr<'a, P>>) -> BinaryOp<Ex
 /// just need to make sure the gadget gets assigned correct inputs and output.
 pub(crate) fn constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     let op1 = lv.op1_value;
     let op2 = lv.op2_value;
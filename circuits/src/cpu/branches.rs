@@ -1,9 +1,8 @@
 //! This module implements constraints for the branch operations.
 
-use expr::Expr;
+use expr::{ConstraintCollector, Expr};
 
 use super::columns::CpuState;
-use crate::expr::ConstraintBuilder;
 
 /// Constraints for `less_than` and `normalised_diff`
 /// For `less_than`:
@@ -16,7 +15,7 @@ use crate::expr::ConstraintBuilder;
 ///  `1` iff `r1 != r2`
 pub(crate) fn comparison_constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     let lt = lv.less_than;
     cb.always(lt.is_binary());
@@ -47,7 +46,7 @@ pub(crate) fn comparison_constraints<'a, P: Copy>(
 /// Constraints for conditional branch operations
 pub(crate) fn constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     let ops = &lv.inst.ops;
     let is_blt = ops.blt;
@@ -1,9 +1,11 @@
 use core::iter::Sum;
 use core::ops::{Add, Mul, Sub};
 
+use mozak_circuits_derive::CtlColumns;
 use mozak_runner::instruction::Op;
 
 use crate::bitshift::columns::Bitshift;
+use crate::blake3::columns::Blake3Ctl;
 use crate::columns_view::{columns_view_impl, make_col_map};
 use crate::cpu_skeleton::columns::CpuSkeletonCtl;
 use crate::cross_table_lookup::{Column, ColumnWithTypedInput};
@@ -12,6 +14,7 @@ use crate::poseidon2_sponge::columns::Poseidon2SpongeCtl;
 use crate::program::columns::ProgramRom;
 use crate::rangecheck::columns::RangeCheckCtl;
 use crate::register::RegisterCtl;
+use crate::sha256::columns::Sha256Ctl;
 use crate::stark::mozak_stark::{CpuTable, TableWithTypedOutput};
 use crate::storage_device::columns::StorageDeviceCtl;
 use crate::xor::columns::XorView;
@@ -33,6 +36,8 @@ pub struct OpSelectors<T> {
     pub mulh: T,
     /// Shift Left Logical by amount
     pub sll: T,
+    /// Rotate Left by amount
+    pub rol: T,
     /// Set Less Than
     pub slt: T,
     /// Shift Right Logical by amount
@@ -64,6 +69,20 @@ pub struct OpSelectors<T> {
     pub bge: T,
     /// Environment Call
     pub ecall: T,
+    /// Read Cycle Counter
+    pub rdcycle: T,
+    /// Read upper 32 bits of the Cycle Counter
+    pub rdcycleh: T,
+    /// CSR Read and Write
+    pub csrrw: T,
+    /// CSR Read and Set
+    pub csrrs: T,
+    /// CSR Read and Clear
+    pub csrrc: T,
+    /// Rotate Right by amount
+    pub ror: T,
+    /// Byte-Reverse
+    pub rev8: T,
 }
 
 columns_view_impl!(Instruction);
@@ -114,6 +133,14 @@ impl From<(u32, mozak_runner::instruction::Instruction)> for Instruction<u32> {
             Op::LH | Op::LHU => &mut cols.ops.lh,
             Op::LW => &mut cols.ops.lw,
             Op::SLL => &mut cols.ops.sll,
+            Op::ROL => &mut cols.ops.rol,
+            Op::ROR => &mut cols.ops.ror,
+            Op::REV8 => &mut cols.ops.rev8,
+            Op::RDCYCLE => &mut cols.ops.rdcycle,
+            Op::RDCYCLEH => &mut cols.ops.rdcycleh,
+            Op::CSRRW => &mut cols.ops.csrrw,
+            Op::CSRRS => &mut cols.ops.csrrs,
+            Op::CSRRC => &mut cols.ops.csrrc,
             Op::SLT | Op::SLTU => &mut cols.ops.slt,
             Op::SB => &mut cols.ops.sb,
             Op::SH => &mut cols.ops.sh,
@@ -134,6 +161,19 @@ impl From<(u32, mozak_runner::instruction::Instruction)> for Instruction<u32> {
             Op::XOR => &mut cols.ops.xor,
             Op::OR => &mut cols.ops.or,
             Op::AND => &mut cols.ops.and,
+            // The runner executes atomics, but we can not prove them yet.  None
+            // of the selectors are set for them, and proving a record that
+            // executes them fails, see `check_provable`.
+            Op::SC
+            | Op::AMOSWAP
+            | Op::AMOADD
+            | Op::AMOXOR
+            | Op::AMOAND
+            | Op::AMOOR
+            | Op::AMOMIN
+            | Op::AMOMAX
+            | Op::AMOMINU
+            | Op::AMOMAXU => return cols,
         } = 1;
         cols.rs1_selected = u32::from(inst.args.rs1);
         cols.rs2_selected = u32::from(inst.args.rs2);
@@ -162,13 +202,17 @@ pub struct EcallSelectors<T> {
     pub is_halt: T,
     pub is_poseidon2: T,
     pub is_self_prog_id_tape: T,
+    pub is_sha256: T,
+    pub is_blake3: T,
+    pub is_oracle_tape: T,
+    pub is_private_tape_commitment_tape: T,
 }
 
 make_col_map!(CpuState);
 columns_view_impl!(CpuState);
 /// Represents the State of the CPU, which is also a row of the trace
 #[repr(C)]
-#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug, CtlColumns)]
 pub struct CpuState<T> {
     pub clk: T,
     pub new_pc: T,
@@ -188,6 +232,10 @@ pub struct CpuState<T> {
     pub dst_value: T,
     pub dst_sign_bit: T,
 
+    /// The new value of the CSR that `CSRRW`, `CSRRS` and `CSRRC` write back
+    /// to the register `rs1`.
+    pub csr_value: T,
+
     /// `mem_access_raw` contains values fetched or stored into the memory
     /// table. These values are always unsigned by nature (as mem table does
     /// not differentiate between signed and unsigned values).
@@ -221,11 +269,20 @@ pub struct CpuState<T> {
     pub quotient_value: T, // range check u32 required
     pub quotient_sign: T,
     pub skip_check_quotient_sign: T,
-    pub remainder_value: T, // range check u32 required
+    /// The limbs (u8) of the remainder.  They are looked up directly in the
+    /// u8 rangecheck table, instead of range checking the full remainder.
+    #[CtlColumns(looking = "rangecheck_u8")]
+    pub remainder_limbs: [T; 4],
     pub remainder_sign: T,
-    /// Value of `divisor_abs - remainder_abs - 1`
-    /// Used as a helper column to check that `remainder < divisor`.
-    pub remainder_slack: T, // range check u32 required
+    /// The limbs (u8) of `divisor_abs - remainder_abs - 1`
+    /// Used as a helper to check that `remainder < divisor`.
+    #[CtlColumns(looking = "rangecheck_u8")]
+    pub remainder_slack_limbs: [T; 4],
+
+    /// The limbs (u8) of `op1_value`, that REV8 puts into `dst_value` in
+    /// reverse order.  They are looked up in the u8 rangecheck table only
+    /// for REV8.
+    pub op1_limbs: [T; 4],
 
     // Product evaluation columns
     pub op1_abs: T,
@@ -268,6 +325,31 @@ where
     /// Difference between first and second operands, which works for both pairs
     /// of signed or pairs of unsigned values.
     pub fn signed_diff(&self) -> T { self.op1_full_range() - self.op2_full_range() }
+
+    /// Value of the remainder of a division, from its limbs.
+    pub fn remainder_value(&self) -> T { combine_limbs(self.remainder_limbs) }
+
+    /// Value of `divisor_abs - remainder_abs - 1`, from its limbs.
+    pub fn remainder_slack(&self) -> T { combine_limbs(self.remainder_slack_limbs) }
+
+    /// Value of the first operand, from its limbs.
+    pub fn op1_from_limbs(&self) -> T { combine_limbs(self.op1_limbs) }
+
+    /// Value of the first operand with its bytes reversed, from its limbs.
+    pub fn op1_reversed_from_limbs(&self) -> T {
+        let [b0, b1, b2, b3] = self.op1_limbs;
+        combine_limbs([b3, b2, b1, b0])
+    }
+}
+
+fn combine_limbs<T>(limbs: [T; 4]) -> T
+where
+    T: Mul<i64, Output = T> + Sum, {
+    limbs
+        .into_iter()
+        .zip([1, 1 << 8, 1 << 16, 1 << 24])
+        .map(|(limb, base)| limb * base)
+        .sum()
 }
 
 impl<P> OpSelectors<P>
@@ -299,12 +381,11 @@ where
 pub fn rangecheck_looking() -> Vec<TableWithTypedOutput<RangeCheckCtl<Column>>> {
     let ops = &CPU.inst.ops;
     let divs = ops.div + ops.rem + ops.srl + ops.sra;
-    let muls: ColumnWithTypedInput<CpuState<i64>> = ops.mul + ops.mulh + ops.sll;
+    let muls: ColumnWithTypedInput<CpuState<i64>> =
+        ops.mul + ops.mulh + ops.sll + ops.rol + ops.ror;
 
     [
         (CPU.quotient_value, divs),
-        (CPU.remainder_value, divs),
-        (CPU.remainder_slack, divs),
         (CPU.dst_value, ops.add + ops.sub + ops.jalr),
         (CPU.inst.pc, ops.jalr),
         (CPU.abs_diff, ops.bge + ops.blt),
@@ -329,6 +410,24 @@ pub fn rangecheck_looking() -> Vec<TableWithTypedOutput<RangeCheckCtl<Column>>>
     .collect()
 }
 
+/// The byte limbs of the division columns and of the operand of REV8, looked
+/// up directly in the
+/// [`RangeCheckU8Table`](crate::stark::mozak_stark::RangeCheckU8Table).
+#[must_use]
+pub fn rangecheck_u8_looking() -> Vec<TableWithTypedOutput<RangeCheckCtl<Column>>> {
+    let ops = &CPU.inst.ops;
+    let divs = ops.div + ops.rem + ops.srl + ops.sra;
+    CPU.rangecheck_u8_columns()
+        .into_iter()
+        .map(|limb| CpuTable::new(RangeCheckCtl(limb), divs))
+        .chain(
+            CPU.op1_limbs
+                .into_iter()
+                .map(|limb| CpuTable::new(RangeCheckCtl(limb), ops.rev8)),
+        )
+        .collect()
+}
+
 /// Lookup for Xor stark.
 /// [`CpuTable`](crate::cross_table_lookup::CpuTable).
 #[must_use]
@@ -396,6 +495,8 @@ pub fn lookup_for_storage_tables() -> TableWithTypedOutput<StorageDeviceCtl<Colu
         CPU.ecall_selectors.is_events_commitment_tape,
         CPU.ecall_selectors.is_cast_list_commitment_tape,
         CPU.ecall_selectors.is_self_prog_id_tape,
+        CPU.ecall_selectors.is_oracle_tape,
+        CPU.ecall_selectors.is_private_tape_commitment_tape,
     ];
     CpuTable::new(
         StorageDeviceCtl {
@@ -411,10 +512,19 @@ pub fn lookup_for_storage_tables() -> TableWithTypedOutput<StorageDeviceCtl<Colu
 impl<T: core::ops::Add<Output = T>> OpSelectors<T> {
     #[must_use]
     pub fn ops_that_use_xor(self) -> T {
-        self.xor + self.or + self.and + self.srl + self.sll + self.sra
+        self.xor
+            + self.or
+            + self.and
+            + self.srl
+            + self.sll
+            + self.rol
+            + self.ror
+            + self.sra
+            + self.csrrs
+            + self.csrrc
     }
 
-    pub fn ops_that_shift(self) -> T { self.sll + self.srl + self.sra }
+    pub fn ops_that_shift(self) -> T { self.sll + self.rol + self.ror + self.srl + self.sra }
 
     pub fn byte_mem_ops(self) -> T { self.sb + self.lb }
 
@@ -437,26 +547,27 @@ pub fn lookup_for_program_rom() -> TableWithTypedOutput<ProgramRom<Column>> {
         ProgramRom {
             pc: inst.pc,
             // Combine columns into a single column.
-            // - ops: This is an internal opcode, not the opcode from RISC-V, and can fit within 5
+            // - ops: This is an internal opcode, not the opcode from RISC-V, and can fit within 6
             //   bits.
-            // - is_op1_signed and is_op2_signed: These fields occupy 1 bit each.
-            // - rs1_select, rs2_select, and rd_select: These fields require 5 bits each.
+            // - is_op1_signed and is_op2_signed: These fields occupy 1 bit each, and share a single
+            //   6 bit field.
+            // - rs1_select, rs2_select, and rd_select: These fields require 6 bits each, because
+            //   the registers that hold CSRs come after the 32 general purpose ones.
             // - imm_value: This field requires 32 bits.
-            // Therefore, the total bit requirement is 5 * 6 + 32 = 62 bits, which is less than the
+            // Therefore, the total bit requirement is 6 * 5 + 32 = 62 bits, which is less than the
             // size of the Goldilocks field.
-            // Note: The imm_value field, having more than 5 bits, must be positioned as the last
+            // Note: The imm_value field, having more than 6 bits, must be positioned as the last
             // column in the list to ensure the correct functioning of 'reduce_with_powers'.
             inst_data: ColumnWithTypedInput::reduce_with_powers(
                 [
                     ColumnWithTypedInput::ascending_sum(inst.ops),
-                    inst.is_op1_signed,
-                    inst.is_op2_signed,
+                    inst.is_op1_signed + inst.is_op2_signed * 2,
                     inst.rs1_selected,
                     inst.rs2_selected,
                     inst.rd_selected,
                     inst.imm_value,
                 ],
-                1 << 5,
+                1 << 6,
             ),
         },
         CPU.is_running(),
@@ -471,6 +582,16 @@ pub fn lookup_for_poseidon2_sponge() -> TableWithTypedOutput<Poseidon2SpongeCtl<
     )
 }
 
+#[must_use]
+pub fn lookup_for_sha256() -> TableWithTypedOutput<Sha256Ctl<Column>> {
+    CpuTable::new(Sha256Ctl { clk: CPU.clk }, CPU.ecall_selectors.is_sha256)
+}
+
+#[must_use]
+pub fn lookup_for_blake3() -> TableWithTypedOutput<Blake3Ctl<Column>> {
+    CpuTable::new(Blake3Ctl { clk: CPU.clk }, CPU.ecall_selectors.is_blake3)
+}
+
 #[must_use]
 pub fn register_looking() -> Vec<TableWithTypedOutput<RegisterCtl<Column>>> {
     let is_read = ColumnWithTypedInput::constant(1);
@@ -504,6 +625,15 @@ pub fn register_looking() -> Vec<TableWithTypedOutput<RegisterCtl<Column>>> {
             },
             CPU.is_running(),
         ),
+        CpuTable::new(
+            RegisterCtl {
+                clk: CPU.clk,
+                op: is_write,
+                addr: CPU.inst.rs1_selected,
+                value: CPU.csr_value,
+            },
+            CPU.inst.ops.csrrw + CPU.inst.ops.csrrs + CPU.inst.ops.csrrc,
+        ),
     ]
 }
 
@@ -0,0 +1,134 @@
+//! This module implements the constraints for reading the cycle counter, and
+//! for the CSR accesses that write both `rd` and the CSR.
+//!
+//! The other CSRs we support are kept in the register file, and most reads or
+//! writes of them are decoded as existing operations, see
+//! [`mozak_runner::csr`].
+//!
+//! `CSRRW`, `CSRRS` and `CSRRC` read the CSR held in register `rs1` as
+//! `op1_value` and put it into `rd`.  They write `csr_value` back to `rs1`
+//! with an extra register lookup.  `CSRRS` and `CSRRC` get the bitwise OR and
+//! AND of the CSR and `op2_value` from the XOR table, like the
+//! [bitwise](super::bitwise) operations do.
+
+use expr::{ConstraintCollector, Expr};
+
+use super::bitwise::{and_gadget, or_gadget};
+use super::columns::CpuState;
+
+pub(crate) fn constraints<'a, P: Copy>(
+    lv: &CpuState<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
+) {
+    let ops = &lv.inst.ops;
+    // As every instruction takes a single cycle, the cycle counter is the clock.
+    cb.always(ops.rdcycle * (lv.dst_value - lv.clk));
+    // The clock starts at 2 and goes up by one per row of the CPU skeleton, so
+    // it stays below 2^32 in any execution we can prove.
+    cb.always(ops.rdcycleh * lv.dst_value);
+
+    cb.always((ops.csrrw + ops.csrrs + ops.csrrc) * (lv.dst_value - lv.op1_value));
+    cb.always(ops.csrrw * (lv.csr_value - lv.op2_value));
+
+    let or = or_gadget(&lv.xor);
+    let and = and_gadget(&lv.xor);
+    for (selector, gadget) in [(ops.csrrs, &or), (ops.csrrc, &and)] {
+        cb.always(selector * (gadget.input_a - lv.op1_value));
+        cb.always(selector * (gadget.input_b - lv.op2_value));
+    }
+    // csr | src
+    cb.always(ops.csrrs * (or.doubled_output - 2 * lv.csr_value));
+    // csr & !src == csr - (csr & src)
+    cb.always(ops.csrrc * (2 * lv.op1_value - and.doubled_output - 2 * lv.csr_value));
+}
+
+#[cfg(test)]
+mod tests {
+    use mozak_runner::code;
+    use mozak_runner::csr::{REG_MSCRATCH, REG_SSCRATCH};
+    use mozak_runner::decode::decode_instruction;
+    use mozak_runner::instruction::{Args, Instruction, Op};
+
+    use crate::cpu::stark::CpuStark;
+    use crate::stark::mozak_stark::MozakStark;
+    use crate::test_utils::{ProveAndVerify, D, F};
+
+    #[test]
+    fn prove_rdcycle() {
+        let (program, record) = code::execute(
+            [10, 11].map(|rd| {
+                Instruction::new(Op::RDCYCLE, Args {
+                    rd,
+                    ..Args::default()
+                })
+            }),
+            &[],
+            &[],
+        );
+        let cycles = [0, 1].map(|i| record.executed[i].aux.dst_val);
+        assert_eq!(cycles[1], cycles[0] + 1);
+        CpuStark::<F, D>::prove_and_verify(&program, &record).unwrap();
+        MozakStark::<F, D>::prove_and_verify(&program, &record).unwrap();
+    }
+
+    #[test]
+    fn prove_scratch_registers() {
+        let instructions = [
+            0x3405_9073, // csrw mscratch, a1
+            0x1402_d073, // csrwi sscratch, 5
+            0x3406_2073, // csrs mscratch, a2
+            0x3401_f073, // csrci mscratch, 3
+            0x3400_2573, // csrr a0, mscratch
+            0x1400_26f3, // csrr a3, sscratch
+        ]
+        .map(|word| decode_instruction(0, word).unwrap());
+        let (program, record) = code::execute(instructions, &[], &[(11, 0b0101), (12, 0b1010)]);
+        let state = &record.last_state;
+        assert_eq!(state.get_register_value(REG_MSCRATCH), 0b1100);
+        assert_eq!(state.get_register_value(REG_SSCRATCH), 5);
+        assert_eq!(state.get_register_value(10), 0b1100);
+        assert_eq!(state.get_register_value(13), 5);
+        MozakStark::<F, D>::prove_and_verify(&program, &record).unwrap();
+    }
+
+    #[test]
+    fn prove_rdcycleh() {
+        let (program, record) = code::execute(
+            [0xc800_2573, 0xc820_25f3] // rdcycleh a0; rdinstreth a1
+                .map(|word| decode_instruction(0, word).unwrap()),
+            &[],
+            &[(10, 7), (11, 7)],
+        );
+        let state = &record.last_state;
+        assert_eq!(state.get_register_value(10), 0);
+        assert_eq!(state.get_register_value(11), 0);
+        CpuStark::<F, D>::prove_and_verify(&program, &record).unwrap();
+        MozakStark::<F, D>::prove_and_verify(&program, &record).unwrap();
+    }
+
+    #[test]
+    fn prove_csr_swaps() {
+        let instructions = [
+            0x3405_9573, // csrrw a0, mscratch, a1
+            0x1402_66f3, // csrrsi a3, sscratch, 4
+            0x3406_3773, // csrrc a4, mscratch, a2
+            0x1400_97f3, // csrrw a5, sscratch, ra
+        ]
+        .map(|word| decode_instruction(0, word).unwrap());
+        let (program, record) = code::execute(instructions, &[], &[
+            (REG_SSCRATCH, 0b0011),
+            (1, 42),
+            (11, 0b0111),
+            (12, 0b0101),
+        ]);
+        let state = &record.last_state;
+        assert_eq!(state.get_register_value(10), 0);
+        assert_eq!(state.get_register_value(13), 0b0011);
+        assert_eq!(state.get_register_value(14), 0b0111);
+        assert_eq!(state.get_register_value(15), 0b0111);
+        assert_eq!(state.get_register_value(REG_MSCRATCH), 0b0010);
+        assert_eq!(state.get_register_value(REG_SSCRATCH), 42);
+        CpuStark::<F, D>::prove_and_verify(&program, &record).unwrap();
+        MozakStark::<F, D>::prove_and_verify(&program, &record).unwrap();
+    }
+}
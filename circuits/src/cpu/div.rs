@@ -4,16 +4,15 @@
 //! Here, SRL stands for 'shift right logical'.  We can treat it as a variant of
 //! unsigned division. Same for SRA.
 
-use expr::Expr;
+use expr::{ConstraintCollector, Expr};
 
 use super::columns::CpuState;
 use crate::cpu::mul::bit_to_sign;
-use crate::expr::ConstraintBuilder;
 
 /// Constraints for DIV / REM / DIVU / REMU / SRL / SRA instructions
 pub(crate) fn constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     let ops = lv.inst.ops;
     let dividend_value = lv.op1_value;
@@ -30,9 +29,9 @@ pub(crate) fn constraints<'a, P: Copy>(
     let divisor_value_inv = lv.op2_value_inv;
     let quotient_value = lv.quotient_value;
     let quotient_sign = lv.quotient_sign;
-    let remainder_value = lv.remainder_value;
+    let remainder_value = lv.remainder_value();
     let remainder_sign = lv.remainder_sign;
-    let remainder_slack = lv.remainder_slack;
+    let remainder_slack = lv.remainder_slack();
     let quotient_full_range = quotient_value - quotient_sign * (1 << 32); // Equation (1)
     let remainder_full_range = remainder_value - remainder_sign * (1 << 32);
     let quotient_abs = bit_to_sign(quotient_sign) * quotient_full_range;
@@ -44,8 +43,11 @@ pub(crate) fn constraints<'a, P: Copy>(
     // this equation becomes |dividend| = |divisor| × |quotient| - remainder.
     cb.always(
         divisor_abs * quotient_abs
-            + (1 - ops.sra) * remainder_abs
-            + ops.sra * (bit_to_sign(dividend_sign) * remainder_full_range)
+            + Expr::select(
+                ops.sra,
+                bit_to_sign(dividend_sign) * remainder_full_range,
+                remainder_abs,
+            )
             - dividend_abs,
     );
 
@@ -100,10 +102,13 @@ pub(crate) fn constraints<'a, P: Copy>(
     // Logically, these are two independent constraints:
     //      (A) 0 <= remainder
     //      (B) remainder < divisor
-    // Part A is easy: we range-check remainder.
+    // Part A is easy: we range-check remainder, limb by limb.
     // Part B is only slightly harder: borrowing the concept of 'slack variables' from linear programming (https://en.wikipedia.org/wiki/Slack_variable) we get:
     // (B') remainder + slack + 1 = divisor
     //      with range_check(slack)
+    // Both remainder and slack are kept as u8 limbs, which we look up directly in
+    // the u8 rangecheck table.  That way division only sends the quotient through
+    // the u32 rangecheck table.
     cb.always(divisor_abs * (remainder_abs + 1 + remainder_slack - divisor_abs));
 
     // Constraints for divisor == 0.  On RISC-V:
@@ -128,7 +133,9 @@ mod tests {
     use proptest::test_runner::TestCaseError;
     use proptest::{prop_assert, proptest};
 
+    use crate::cpu::generation::generate_cpu_trace;
     use crate::cpu::stark::CpuStark;
+    use crate::rangecheck::generation::limbs_from_u32;
     use crate::stark::mozak_stark::MozakStark;
     use crate::test_utils::{inv, ProveAndVerify, D, F};
 
@@ -178,6 +185,17 @@ mod tests {
         ]
     }
 
+    #[test]
+    fn remainder_and_slack_are_split_into_limbs() {
+        let (p, q) = (0x1234_5678, 0x0001_0000);
+        let (_program, record) = code::execute(divu_remu_instructions(3), &[], &[(1, p), (2, q)]);
+        let trace = generate_cpu_trace::<F>(&record);
+        for row in &trace[..2] {
+            assert_eq!(row.remainder_limbs, limbs_from_u32(p % q));
+            assert_eq!(row.remainder_slack_limbs, limbs_from_u32(q - 1 - p % q));
+        }
+    }
+
     fn prove_divu<Stark: ProveAndVerify>(p: u32, q: u32, rd: u8) -> Result<(), TestCaseError> {
         let (program, record) = code::execute(divu_remu_instructions(rd), &[], &[(1, p), (2, q)]);
         prop_assert_eq!(
@@ -1,19 +1,19 @@
 //! This module implements the constraints for the environment call operation
 //! 'ECALL'.
 
-use expr::Expr;
+use expr::{ConstraintCollector, Expr};
 use mozak_sdk::core::ecall;
 
 use super::columns::CpuState;
-use crate::expr::ConstraintBuilder;
 
 pub(crate) fn constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     let ecalls = &lv.ecall_selectors;
-    // ECALL is used for HALT, PRIVATE_TAPE/PUBLIC_TAPE or POSEIDON2 system
-    // call. So when instruction is ECALL, only one of them will be one.
+    // ECALL is used for HALT, PRIVATE_TAPE/PUBLIC_TAPE, POSEIDON2, SHA256 or
+    // BLAKE3 system call. So when instruction is ECALL, only one of them will be
+    // one.
     for ecall in ecalls {
         cb.always(ecall.is_binary());
     }
@@ -21,11 +21,13 @@ pub(crate) fn constraints<'a, P: Copy>(
     cb.always(lv.ecall_selectors.is_halt * (lv.op1_value - i64::from(ecall::HALT)));
     storage_device_constraints(lv, cb);
     poseidon2_constraints(lv, cb);
+    sha256_constraints(lv, cb);
+    blake3_constraints(lv, cb);
 }
 
 pub(crate) fn storage_device_constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     let ecalls = &lv.ecall_selectors;
     cb.always(ecalls.is_private_tape * (lv.op1_value - i64::from(ecall::PRIVATE_TAPE)));
@@ -44,13 +46,32 @@ pub(crate) fn storage_device_constraints<'a, P: Copy>(
         lv.ecall_selectors.is_self_prog_id_tape
             * (lv.op1_value - i64::from(ecall::SELF_PROG_ID_TAPE)),
     );
+    cb.always(ecalls.is_oracle_tape * (lv.op1_value - i64::from(ecall::ORACLE_TAPE)));
+    cb.always(
+        ecalls.is_private_tape_commitment_tape
+            * (lv.op1_value - i64::from(ecall::PRIVATE_TAPE_COMMITMENT_TAPE)),
+    );
 }
 
 pub(crate) fn poseidon2_constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     cb.always(lv.ecall_selectors.is_poseidon2 * (lv.op1_value - i64::from(ecall::POSEIDON2)));
 }
 
+pub(crate) fn sha256_constraints<'a, P: Copy>(
+    lv: &CpuState<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
+) {
+    cb.always(lv.ecall_selectors.is_sha256 * (lv.op1_value - i64::from(ecall::SHA256)));
+}
+
+pub(crate) fn blake3_constraints<'a, P: Copy>(
+    lv: &CpuState<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
+) {
+    cb.always(lv.ecall_selectors.is_blake3 * (lv.op1_value - i64::from(ecall::BLAKE3)));
+}
+
 // We are already testing ecall halt with our coda of every `code::execute`.
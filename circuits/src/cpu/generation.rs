@@ -1,3 +1,4 @@
+use anyhow::{ensure, Result};
 use expr::{Evaluator, ExprBuilder};
 use itertools::Itertools;
 use log::debug;
@@ -16,6 +17,7 @@ use crate::expr::PureEvaluator;
 use crate::generation::MIN_TRACE_LENGTH;
 use crate::program::columns::ProgramRom;
 use crate::program_multiplicities::columns::ProgramMult;
+use crate::rangecheck::generation::limbs_from_u32;
 use crate::utils::{from_u32, sign_extend};
 use crate::xor::columns::XorView;
 
@@ -53,6 +55,31 @@ pub fn generate_program_mult_trace<F: RichField>(
         .collect()
 }
 
+/// Checks that the CPU table can prove every instruction executed in
+/// `record`.
+///
+/// The runner executes some instructions, like the atomics, that have no op
+/// selector in the CPU table, so no row of the table could be constrained to
+/// execute them.
+///
+/// # Errors
+/// Returns an error naming the first such instruction.
+pub fn check_provable<F: RichField>(record: &ExecutionRecord<F>) -> Result<()> {
+    for Row {
+        state, instruction, ..
+    } in &record.executed
+    {
+        let pc = state.get_pc();
+        let selectors = cpu_cols::Instruction::<u32>::from((pc, *instruction)).ops;
+        ensure!(
+            selectors.into_iter().any(|selector| selector != 0),
+            "can not prove {:?} at pc {pc:#x}: the CPU table has no constraints for it",
+            instruction.op
+        );
+    }
+    Ok(())
+}
+
 /// Converting each row of the `record` to a row represented by [`CpuState`]
 pub fn generate_cpu_trace<F: RichField>(record: &ExecutionRecord<F>) -> Vec<CpuState<F>> {
     debug!("Starting CPU Trace Generation");
@@ -107,6 +134,8 @@ pub fn generate_cpu_trace<F: RichField>(record: &ExecutionRecord<F>) -> Vec<CpuS
             io_size: F::from_canonical_usize(io.data.len()),
             ecall_selectors: EcallSelectors {
                 is_poseidon2: F::from_bool(aux.poseidon2.is_some()),
+                is_sha256: F::from_bool(aux.sha256.is_some()),
+                is_blake3: F::from_bool(aux.blake3.is_some()),
                 is_private_tape: F::from_bool(matches!(
                     (inst.op, io.op),
                     (Op::ECALL, StorageDeviceOpcode::StorePrivate)
@@ -135,6 +164,17 @@ pub fn generate_cpu_trace<F: RichField>(record: &ExecutionRecord<F>) -> Vec<CpuS
                     (inst.op, io.op),
                     (Op::ECALL, StorageDeviceOpcode::StoreSelfProgIdTape)
                 )),
+                is_oracle_tape: F::from_bool(matches!(
+                    (inst.op, io.op),
+                    (Op::ECALL, StorageDeviceOpcode::StoreOracleTape)
+                )),
+                is_private_tape_commitment_tape: F::from_bool(matches!(
+                    (inst.op, io.op),
+                    (
+                        Op::ECALL,
+                        StorageDeviceOpcode::StorePrivateTapeCommitmentTape
+                    )
+                )),
                 is_halt: F::from_bool(matches!(
                     (inst.op, state.registers[usize::from(REG_A0)]),
                     (Op::ECALL, ecall::HALT)
@@ -149,6 +189,8 @@ pub fn generate_cpu_trace<F: RichField>(record: &ExecutionRecord<F>) -> Vec<CpuS
         operands_sign_handling(&mut row, aux);
         memory_sign_handling(&mut row, inst, aux);
         generate_conditional_branch_row(&mut row);
+        generate_csr_row(&mut row, inst, aux);
+        generate_rev8_row(&mut row, inst, aux);
         trace.push(row);
     }
 
@@ -172,6 +214,25 @@ fn generate_conditional_branch_row<F: RichField>(row: &mut CpuState<F>) {
     row.normalised_diff = F::from_bool(signed_diff.is_nonzero());
 }
 
+/// Generates the new value of the CSR for the CSR accesses that write both
+/// `rd` and the CSR.
+fn generate_csr_row<F: RichField>(row: &mut CpuState<F>, inst: &Instruction, aux: &Aux<F>) {
+    let csr_value = match inst.op {
+        Op::CSRRW => aux.op2,
+        Op::CSRRS => aux.op1 | aux.op2,
+        Op::CSRRC => aux.op1 & !aux.op2,
+        _ => 0,
+    };
+    row.csr_value = from_u32(csr_value);
+}
+
+/// Generates the byte limbs of the operand of REV8.
+fn generate_rev8_row<F: RichField>(row: &mut CpuState<F>, inst: &Instruction, aux: &Aux<F>) {
+    if inst.op == Op::REV8 {
+        row.op1_limbs = limbs_from_u32(aux.op1);
+    }
+}
+
 /// Generates a bitshift row on a shift operation. This is used in the bitshift
 /// lookup table.
 fn generate_shift_row<F: RichField>(row: &mut CpuState<F>, aux: &Aux<F>) {
@@ -263,8 +324,8 @@ fn generate_div_row<F: RichField>(row: &mut CpuState<F>, inst: &Instruction, aux
         } else {
             F::ZERO
         };
-        row.remainder_value = from_u32(aux.op1);
-        row.remainder_slack = F::ZERO;
+        row.remainder_limbs = limbs_from_u32(aux.op1);
+        row.remainder_slack_limbs = limbs_from_u32(0);
         row.remainder_sign = F::from_bool(dividend_full_range.is_negative());
         row.skip_check_quotient_sign = F::ONE;
     } else {
@@ -284,9 +345,9 @@ fn generate_div_row<F: RichField>(row: &mut CpuState<F>, inst: &Instruction, aux
         }
         let remainder = dividend_full_range - quotient_full_range * divisor_full_range;
         let remainder_abs = remainder.unsigned_abs();
-        row.remainder_value = from_u32(remainder as u32);
-        row.remainder_slack =
-            F::from_noncanonical_u64(divisor_full_range.unsigned_abs() - 1 - remainder_abs);
+        row.remainder_limbs = limbs_from_u32(remainder as u32);
+        row.remainder_slack_limbs =
+            limbs_from_u32((divisor_full_range.unsigned_abs() - 1 - remainder_abs) as u32);
         row.remainder_sign = F::from_bool(remainder.is_negative());
     }
     row.op2_value_inv = from_u32::<F>(aux.op2).try_inverse().unwrap_or_default();
@@ -316,14 +377,27 @@ fn operands_sign_handling<F: RichField>(row: &mut CpuState<F>, aux: &Aux<F>) {
 
 fn generate_xor_row<F: RichField>(inst: &Instruction, state: &State<F>) -> XorView<F> {
     let a = match inst.op {
-        Op::AND | Op::OR | Op::XOR | Op::SB | Op::SH => state.get_register_value(inst.args.rs1),
-        Op::SRL | Op::SLL | Op::SRA => 0b1_1111,
+        Op::AND | Op::OR | Op::XOR | Op::SB | Op::SH | Op::CSRRS | Op::CSRRC =>
+            state.get_register_value(inst.args.rs1),
+        Op::SRL | Op::SLL | Op::ROL | Op::ROR | Op::SRA => 0b1_1111,
         _ => 0,
     };
     let b = match inst.op {
-        Op::AND | Op::OR | Op::XOR | Op::SRL | Op::SLL | Op::SRA => state
+        Op::AND
+        | Op::OR
+        | Op::XOR
+        | Op::SRL
+        | Op::SLL
+        | Op::ROL
+        | Op::SRA
+        | Op::CSRRS
+        | Op::CSRRC => state
             .get_register_value(inst.args.rs2)
             .wrapping_add(inst.args.imm),
+        Op::ROR => state
+            .get_register_value(inst.args.rs2)
+            .wrapping_add(inst.args.imm)
+            .wrapping_neg(),
         Op::SB => 0x0000_00FF,
         Op::SH => 0x0000_FFFF,
         _ => 0,
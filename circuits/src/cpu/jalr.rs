@@ -2,14 +2,13 @@
 //! JALR writes the address of the instruction following the jump, being pc + 4,
 //! And then sets the target address with sum of signed immediate and rs1.
 
-use expr::Expr;
+use expr::{ConstraintCollector, Expr};
 
 use super::columns::CpuState;
-use crate::expr::ConstraintBuilder;
 
 pub(crate) fn constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     // Save the address of the instruction following the jump (return address).
     let return_address = lv.inst.pc + 4;
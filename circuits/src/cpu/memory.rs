@@ -2,18 +2,17 @@
 //! store. Supported operators include: `SB` 'Save Byte', `LB` and `LBU` 'Load
 //! Byte' and 'Load Byte Unsigned'
 
-use expr::Expr;
+use expr::{ConstraintCollector, Expr};
 
 use super::bitwise::and_gadget;
 use super::columns::CpuState;
-use crate::expr::ConstraintBuilder;
 
 /// Ensure that `dst_value` and `mem_value_raw` only differ
 /// in case of `LB` by `0xFFFF_FF00` and for `LH` by `0xFFFF_0000`. The
 /// correctness of value presented in `dst_sign_bit` is ensured via range-check
 pub(crate) fn signed_constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     cb.always(lv.dst_sign_bit.is_binary());
     // When dst is not signed as per instruction semantics, dst_sign_bit must be 0.
@@ -39,7 +38,7 @@ pub(crate) fn signed_constraints<'a, P: Copy>(
 
 pub(crate) fn constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     // memory address is equal to rs2-value + imm (wrapping)
     cb.always(lv.inst.ops.is_mem_op() * (lv.mem_addr - lv.op2_value));
@@ -59,6 +58,22 @@ mod tests {
     use crate::stark::mozak_stark::MozakStark;
     use crate::test_utils::{ProveAndVerify, D, F};
 
+    #[test]
+    fn atomics_are_rejected() {
+        let (program, record) = code::execute(
+            [Instruction::new(Op::AMOADD, Args {
+                rd: 5,
+                rs1: 6,
+                rs2: 7,
+                ..Args::default()
+            })],
+            &[(0x100, 1)],
+            &[(6, 2), (7, 0x100)],
+        );
+        let error = MozakStark::<F, D>::prove_and_verify(&program, &record).unwrap_err();
+        assert!(error.to_string().contains("AMOADD"), "{error}");
+    }
+
     fn prove_sb<Stark: ProveAndVerify>(a: u32, b: u32) {
         let (program, record) = code::execute(
             [Instruction {
@@ -1,12 +1,14 @@
 pub mod bitwise;
 pub mod branches;
 pub mod columns;
+pub mod csr;
 pub mod div;
 pub mod ecall;
 pub mod generation;
 pub mod jalr;
 pub mod memory;
 pub mod mul;
+pub mod rev8;
 pub mod shift;
 pub mod signed_comparison;
 pub mod stark;
@@ -1,13 +1,18 @@
 //! This module implements constraints for multiplication operations, including
-//! MUL, MULH, MULHU, MULHSU and SLL instructions.
+//! MUL, MULH, MULHU, MULHSU, SLL, ROL and ROR instructions.
 //!
 //! Here, SLL stands for 'shift left logical'.  We can treat it as a variant of
 //! unsigned multiplication.
+//!
+//! ROL stands for 'rotate left'.  Multiplying by `2^amount` moves the bits
+//! that are rotated out into the high limb, and leaves zeros in the lowest
+//! `amount` bits of the low limb.  So the rotation is the sum of both limbs.
+//! ROR (rotate right) is ROL by the complementary amount, see
+//! [`super::shift`].
 
-use expr::Expr;
+use expr::{ConstraintCollector, Expr};
 
 use super::columns::CpuState;
-use crate::expr::ConstraintBuilder;
 
 /// Converts from a sign-bit to a multiplicative sign.
 ///
@@ -19,7 +24,7 @@ pub fn bit_to_sign<P: Copy>(sign_bit: Expr<'_, P>) -> Expr<'_, P> { 1 - 2 * sign
 
 pub(crate) fn constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     let op1_abs = lv.op1_abs;
     let op2_abs = lv.op2_abs;
@@ -91,6 +96,7 @@ pub(crate) fn constraints<'a, P: Copy>(
     let destination = lv.dst_value;
     cb.always((lv.inst.ops.mul + lv.inst.ops.sll) * (destination - low_limb));
     cb.always((lv.inst.ops.mulh) * (destination - high_limb));
+    cb.always((lv.inst.ops.rol + lv.inst.ops.ror) * (destination - low_limb - high_limb));
 }
 
 #[cfg(test)]
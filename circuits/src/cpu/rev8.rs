@@ -0,0 +1,61 @@
+//! This module implements the constraints for REV8, which reverses the order
+//! of the bytes of `op1_value`.
+//!
+//! We split `op1_value` into byte limbs, that we range check in the u8
+//! rangecheck table, and put them together again in reverse order.
+
+use expr::{ConstraintCollector, Expr};
+
+use super::columns::CpuState;
+
+pub(crate) fn constraints<'a, P: Copy>(
+    lv: &CpuState<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
+) {
+    let rev8 = lv.inst.ops.rev8;
+    cb.always(rev8 * (lv.op1_value - lv.op1_from_limbs()));
+    cb.always(rev8 * (lv.dst_value - lv.op1_reversed_from_limbs()));
+}
+
+#[cfg(test)]
+mod tests {
+    use mozak_runner::code;
+    use mozak_runner::instruction::{Args, Instruction, Op};
+    use mozak_runner::test_utils::{reg, u32_extra};
+    use proptest::prelude::ProptestConfig;
+    use proptest::proptest;
+
+    use crate::cpu::stark::CpuStark;
+    use crate::stark::mozak_stark::MozakStark;
+    use crate::test_utils::{ProveAndVerify, D, F};
+
+    fn prove_rev8<Stark: ProveAndVerify>(a: u32, rs1: u8, rd: u8) {
+        let (program, record) = code::execute(
+            [Instruction::new(Op::REV8, Args {
+                rd,
+                rs1,
+                ..Args::default()
+            })],
+            &[],
+            &[(rs1, a)],
+        );
+        assert_eq!(record.executed[0].aux.dst_val, a.swap_bytes());
+        Stark::prove_and_verify(&program, &record).unwrap();
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+        #[test]
+        fn prove_rev8_cpu(a in u32_extra(), rs1 in reg(), rd in reg()) {
+            prove_rev8::<CpuStark<F, D>>(a, rs1, rd);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1))]
+        #[test]
+        fn prove_rev8_mozak(a in u32_extra(), rs1 in reg(), rd in reg()) {
+            prove_rev8::<MozakStark<F, D>>(a, rs1, rd);
+        }
+    }
+}
@@ -1,20 +1,22 @@
 //! This module implements constraints for shift operations, including
-//! SRL,SRA and SLL instructions.
+//! SRL,SRA, SLL, ROL and ROR instructions.
 //!
 //! Here, SLL stands for 'shift left logical'.  We can treat it as a variant of
-//! unsigned multiplication. Same for SRL and SRA, but with division.
+//! unsigned multiplication. Same for SRL and SRA, but with division.  ROL
+//! (rotate left) is a multiplication as well, see [`super::mul`].  ROR (rotate
+//! right) by `amount` is ROL by `32 - amount`, so we look up the power of two
+//! for the negated amount.
 
-use expr::Expr;
+use expr::{ConstraintCollector, Expr};
 
 use super::bitwise::and_gadget;
 use super::columns::CpuState;
-use crate::expr::ConstraintBuilder;
 
 pub(crate) fn constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
-    let is_shift = lv.inst.ops.sll + lv.inst.ops.srl + lv.inst.ops.sra;
+    let is_shift = lv.inst.ops.ops_that_shift();
     // Check: multiplier is assigned as `2^(rs2 value & 0b1_111)`.
     // We only take lowest 5 bits of the rs2 for the shift amount.
     // This is following the RISC-V specification.
@@ -23,7 +25,14 @@ pub(crate) fn constraints<'a, P: Copy>(
     // to the multiplier.
     let and_gadget = and_gadget(&lv.xor);
     cb.always(is_shift * (and_gadget.input_a - 0b1_1111));
-    cb.always(is_shift * (and_gadget.input_b - lv.op2_value_raw - lv.inst.imm_value));
+    let ror = lv.inst.ops.ror;
+    cb.always((is_shift - ror) * (and_gadget.input_b - lv.op2_value_raw - lv.inst.imm_value));
+    // For ROR, `input_b` is `rs2` negated as a `u32`: either `0` or `2^32 - rs2`.
+    // The XOR table range checks `input_b`, which rules out `2^32` when `rs2` is
+    // zero.  Rotating right by an immediate is decoded as ROL, so ROR has none.
+    let rs2_plus_input_b = and_gadget.input_b + lv.op2_value_raw;
+    cb.always(ror * rs2_plus_input_b * (rs2_plus_input_b - (1 << 32)));
+    cb.always(ror * lv.inst.imm_value);
 
     cb.always(is_shift * (and_gadget.doubled_output - 2 * lv.bitshift.amount));
 }
@@ -120,6 +129,72 @@ mod tests {
         Stark::prove_and_verify(&program, &record).unwrap();
         Ok(())
     }
+    fn prove_rol<Stark: ProveAndVerify>(
+        p: u32,
+        q: u32,
+        rs1: u8,
+        rs2: u8,
+        rd: u8,
+    ) -> Result<(), TestCaseError> {
+        prop_assume!(rs1 != rs2);
+        prop_assume!(rs1 != rd);
+        prop_assume!(rs2 != rd);
+        let (program, record) = code::execute(
+            [
+                Instruction {
+                    op: Op::ROL,
+                    args: Args {
+                        rd,
+                        rs1,
+                        rs2,
+                        ..Args::default()
+                    },
+                },
+                Instruction {
+                    op: Op::ROL,
+                    args: Args {
+                        rd,
+                        rs1,
+                        imm: q,
+                        ..Args::default()
+                    },
+                },
+            ],
+            &[],
+            &[(rs1, p), (rs2, q)],
+        );
+        prop_assert_eq!(record.executed[0].aux.dst_val, p.rotate_left(q & 0b1_1111));
+        prop_assert_eq!(record.executed[1].aux.dst_val, p.rotate_left(q & 0b1_1111));
+        Stark::prove_and_verify(&program, &record).unwrap();
+        Ok(())
+    }
+    fn prove_ror<Stark: ProveAndVerify>(
+        p: u32,
+        q: u32,
+        rs1: u8,
+        rs2: u8,
+        rd: u8,
+    ) -> Result<(), TestCaseError> {
+        prop_assume!(rs1 != rs2);
+        prop_assume!(rs1 != rd);
+        prop_assume!(rs2 != rd);
+        let (program, record) = code::execute(
+            [Instruction {
+                op: Op::ROR,
+                args: Args {
+                    rd,
+                    rs1,
+                    rs2,
+                    ..Args::default()
+                },
+            }],
+            &[],
+            &[(rs1, p), (rs2, q)],
+        );
+        prop_assert_eq!(record.executed[0].aux.dst_val, p.rotate_right(q & 0b1_1111));
+        Stark::prove_and_verify(&program, &record).unwrap();
+        Ok(())
+    }
     fn prove_sra<Stark: ProveAndVerify>(
         p: u32,
         q: u32,
@@ -165,6 +240,14 @@ mod tests {
             prove_sll::<CpuStark<F, D>>(p, q, rs1, rs2, rd)?;
         }
         #[test]
+        fn prove_rol_cpu(p in u32_extra(), q in u32_extra(), rs1 in reg(), rs2 in reg(), rd in reg()) {
+            prove_rol::<CpuStark<F, D>>(p, q, rs1, rs2, rd)?;
+        }
+        #[test]
+        fn prove_ror_cpu(p in u32_extra(), q in u32_extra(), rs1 in reg(), rs2 in reg(), rd in reg()) {
+            prove_ror::<CpuStark<F, D>>(p, q, rs1, rs2, rd)?;
+        }
+        #[test]
         fn prove_srl_cpu(p in u32_extra(), q in u32_extra(), rs1 in reg(), rs2 in reg(), rd in reg()) {
             prove_srl::<CpuStark<F, D>>(p, q, rs1, rs2, rd)?;
         }
@@ -181,6 +264,14 @@ mod tests {
             prove_sll::<MozakStark<F, D>>(p, q, rs1, rs2, rd)?;
         }
         #[test]
+        fn prove_rol_mozak(p in u32_extra(), q in u32_extra(), rs1 in reg(), rs2 in reg(), rd in reg()) {
+            prove_rol::<MozakStark<F, D>>(p, q, rs1, rs2, rd)?;
+        }
+        #[test]
+        fn prove_ror_mozak(p in u32_extra(), q in u32_extra(), rs1 in reg(), rs2 in reg(), rd in reg()) {
+            prove_ror::<MozakStark<F, D>>(p, q, rs1, rs2, rd)?;
+        }
+        #[test]
         fn prove_srl_mozak(p in u32_extra(), q in u32_extra(), rs1 in reg(), rs2 in reg(), rd in reg()) {
             prove_srl::<MozakStark<F, D>>(p, q, rs1, rs2, rd)?;
         }
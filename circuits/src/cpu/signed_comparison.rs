@@ -1,10 +1,9 @@
 //! This module implements constraints for comparisons, SLT and SLTU.
 //! Where `SLT` means 'Set if Less Then', and 'SLTU' is the same but unsigned.
 
-use expr::Expr;
+use expr::{ConstraintCollector, Expr};
 
 use super::columns::CpuState;
-use crate::expr::ConstraintBuilder;
 
 /// # Explanation
 ///
@@ -25,7 +24,7 @@ use crate::expr::ConstraintBuilder;
 
 pub(crate) fn signed_constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     cb.always(lv.op1_sign_bit.is_binary());
     cb.always(lv.op2_sign_bit.is_binary());
@@ -38,7 +37,7 @@ pub(crate) fn signed_constraints<'a, P: Copy>(
 
 pub(crate) fn slt_constraints<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     // Check: the destination has the same value as stored in `less_than`.
     cb.always(lv.inst.ops.slt * (lv.less_than - lv.dst_value));
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use expr::{Expr, ExprBuilder, StarkFrameTyped};
+use expr::{ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
 use mozak_circuits_derive::StarkNameDisplay;
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
@@ -12,10 +12,10 @@ use starky::evaluation_frame::StarkFrame;
 use starky::stark::Stark;
 
 use super::columns::{CpuState, OpSelectors};
-use super::{bitwise, branches, div, ecall, jalr, memory, mul, signed_comparison, sub};
+use super::{bitwise, branches, csr, div, ecall, jalr, memory, mul, rev8, signed_comparison, sub};
 use crate::columns_view::{HasNamedColumns, NumberOfColumns};
 use crate::cpu::shift;
-use crate::expr::{build_ext, build_packed, ConstraintBuilder};
+use crate::expr::{build_ext, build_packed};
 use crate::unstark::NoColumns;
 
 /// A Gadget for CPU Instructions
@@ -33,7 +33,7 @@ impl<F, const D: usize> HasNamedColumns for CpuStark<F, D> {
 
 /// Ensure that if opcode is straight line, then program counter is incremented
 /// by 4.
-fn pc_ticks_up<'a, P: Copy>(lv: &CpuState<Expr<'a, P>>, cb: &mut ConstraintBuilder<Expr<'a, P>>) {
+fn pc_ticks_up<'a, P: Copy>(lv: &CpuState<Expr<'a, P>>, cb: &mut ConstraintCollector<'a, P>) {
     cb.transition(lv.inst.ops.is_straightline() * (lv.new_pc - (lv.inst.pc + 4)));
 }
 
@@ -42,7 +42,7 @@ fn pc_ticks_up<'a, P: Copy>(lv: &CpuState<Expr<'a, P>>, cb: &mut ConstraintBuild
 /// See <https://en.wikipedia.org/wiki/One-hot>
 fn binary_selectors<'a, P: Copy>(
     ops: &OpSelectors<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     // selectors have value 0 or 1.
     ops.into_iter().for_each(|s| cb.always(s.is_binary()));
@@ -56,11 +56,11 @@ fn binary_selectors<'a, P: Copy>(
 /// This may overflow.
 fn populate_op2_value<'a, P: Copy>(
     lv: &CpuState<Expr<'a, P>>,
-    cb: &mut ConstraintBuilder<Expr<'a, P>>,
+    cb: &mut ConstraintCollector<'a, P>,
 ) {
     let ops = &lv.inst.ops;
     let is_branch_operation = ops.beq + ops.bne + ops.blt + ops.bge;
-    let is_shift_operation = ops.sll + ops.srl + ops.sra;
+    let is_shift_operation = ops.ops_that_shift();
 
     cb.always(is_branch_operation * (lv.op2_value - lv.op2_value_raw));
     cb.always(is_shift_operation * (lv.op2_value - lv.bitshift.multiplier));
@@ -80,9 +80,9 @@ const PUBLIC_INPUTS: usize = 0;
 
 fn generate_constraints<'a, T: Copy>(
     vars: &StarkFrameTyped<CpuState<Expr<'a, T>>, NoColumns<Expr<'a, T>>>,
-) -> ConstraintBuilder<Expr<'a, T>> {
+) -> ConstraintCollector<'a, T> {
     let lv = &vars.local_values;
-    let mut constraints = ConstraintBuilder::default();
+    let mut constraints = ConstraintCollector::default();
 
     pc_ticks_up(lv, &mut constraints);
 
@@ -103,8 +103,10 @@ fn generate_constraints<'a, T: Copy>(
     shift::constraints(lv, &mut constraints);
     div::constraints(lv, &mut constraints);
     mul::constraints(lv, &mut constraints);
+    rev8::constraints(lv, &mut constraints);
     jalr::constraints(lv, &mut constraints);
     ecall::constraints(lv, &mut constraints);
+    csr::constraints(lv, &mut constraints);
 
     constraints
 }
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use expr::{Expr, ExprBuilder, StarkFrameTyped};
+use expr::{ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
 use mozak_circuits_derive::StarkNameDisplay;
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
@@ -13,7 +13,7 @@ use starky::stark::Stark;
 
 use super::columns::CpuSkeleton;
 use crate::columns_view::{HasNamedColumns, NumberOfColumns};
-use crate::expr::{build_ext, build_packed, ConstraintBuilder};
+use crate::expr::{build_ext, build_packed};
 use crate::stark::mozak_stark::PublicInputs;
 
 #[derive(Clone, Copy, Default, StarkNameDisplay)]
@@ -32,11 +32,11 @@ const PUBLIC_INPUTS: usize = PublicInputs::<()>::NUMBER_OF_COLUMNS;
 
 fn generate_constraints<'a, T: Copy>(
     vars: &StarkFrameTyped<CpuSkeleton<Expr<'a, T>>, PublicInputs<Expr<'a, T>>>,
-) -> ConstraintBuilder<Expr<'a, T>> {
+) -> ConstraintCollector<'a, T> {
     let lv = vars.local_values;
     let nv = vars.next_values;
     let public_inputs = vars.public_inputs;
-    let mut constraints = ConstraintBuilder::default();
+    let mut constraints = ConstraintCollector::default();
 
     constraints.first_row(lv.pc - public_inputs.entry_point);
     // Clock starts at 2. This is to differentiate
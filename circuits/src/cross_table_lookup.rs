@@ -23,7 +23,7 @@ pub use crate::linear_combination::Column;
 use crate::linear_combination::ColumnSparse;
 pub use crate::linear_combination_typed::ColumnWithTypedInput;
 use crate::public_sub_table::PublicSubTable;
-use crate::stark::mozak_stark::{all_kind, TableKind, TableKindArray, TableWithTypedOutput};
+use crate::stark::mozak_stark::{all_kind, Table, TableKind, TableKindArray, TableWithTypedOutput};
 use crate::stark::permutation::challenge::{GrandProductChallenge, GrandProductChallengeSet};
 use crate::stark::proof::{StarkProof, StarkProofTarget};
 
@@ -54,13 +54,92 @@ impl<F: Field> CtlData<F> {
     }
 }
 
+/// Number of lookups folded into one Z(x) polynomial.
+///
+/// The constraints on a Z(x) polynomial have degree one more than the number
+/// of lookups folded into it, so this can be at most one less than the
+/// constraint degree of our starks, which is 3.
+pub const LOOKUPS_PER_Z: usize = 2;
+
+/// A lookup folded into a Z(x) polynomial: the columns of the tuples looked
+/// up, and their multiplicity.
+#[derive(Clone, Debug)]
+pub struct CtlLookup {
+    pub(crate) columns: Vec<Column>,
+    pub(crate) filter_column: Column,
+}
+
+impl CtlLookup {
+    /// The lookup of `table` in the cross-table lookup numbered `tag`.
+    ///
+    /// Lookups of different cross-table lookups share Z(x) polynomials, and
+    /// their sums are only checked together.  So the tuples are prefixed with
+    /// `tag + 1`, to keep tuples of one cross-table lookup from cancelling out
+    /// those of another.  As the first column, it is the constant term of the
+    /// combined tuple, whatever the length of the tuple, and it is never zero.
+    fn tagged(table: &Table, tag: usize) -> Self {
+        let tag = Column {
+            constant: i64::try_from(tag + 1).unwrap(),
+            ..Column::default()
+        };
+        Self {
+            columns: chain!([tag], table.columns.iter().cloned()).collect(),
+            filter_column: table.filter_column.clone(),
+        }
+    }
+
+    /// The lookup of `table` on its own, as for public sub tables, whose
+    /// sums are checked against what the verifier computes.
+    pub(crate) fn untagged(table: &Table) -> Self {
+        Self {
+            columns: table.columns.clone(),
+            filter_column: table.filter_column.clone(),
+        }
+    }
+
+    /// The lookups of `kind` in `cross_table_lookups`, in groups of up to
+    /// [`LOOKUPS_PER_Z`] that share a Z(x) polynomial.
+    #[must_use]
+    pub fn batches(cross_table_lookups: &[CrossTableLookup], kind: TableKind) -> Vec<Vec<Self>> {
+        cross_table_lookups
+            .iter()
+            .enumerate()
+            .flat_map(|(tag, CrossTableLookup { looking_tables })| {
+                looking_tables
+                    .iter()
+                    .filter(move |table| table.kind == kind)
+                    .map(move |table| Self::tagged(table, tag))
+            })
+            .collect_vec()
+            .chunks(LOOKUPS_PER_Z)
+            .map(<[Self]>::to_vec)
+            .collect()
+    }
+
+    /// The lookups of each table, as laid out by [`Self::batches`], followed
+    /// by those of its public sub tables, one per Z(x) polynomial.
+    #[must_use]
+    pub fn per_table(
+        cross_table_lookups: &[CrossTableLookup],
+        public_sub_tables: &[PublicSubTable],
+    ) -> TableKindArray<Vec<Vec<CtlLookup>>> {
+        all_kind!(|kind| chain!(
+            Self::batches(cross_table_lookups, kind),
+            public_sub_tables
+                .iter()
+                .filter(|public_sub_table| public_sub_table.table.kind == kind)
+                .map(|public_sub_table| vec![Self::untagged(&public_sub_table.table)])
+        )
+        .collect())
+    }
+}
+
 /// Cross-table lookup data associated with one Z(x) polynomial.
 #[derive(Clone, Debug)]
 pub(crate) struct CtlZData<F: Field> {
     pub(crate) z: PolynomialValues<F>,
     pub(crate) challenge: GrandProductChallenge<F>,
-    pub(crate) columns: Vec<Column>,
-    pub(crate) filter_column: Column,
+    pub(crate) lookups: Vec<CtlLookup>,
 }
 
 pub(crate) fn verify_cross_table_lookups_and_public_sub_tables<
@@ -73,21 +152,22 @@ pub(crate) fn verify_cross_table_lookups_and_public_sub_tables<
     ctl_zs_lasts: &TableKindArray<Vec<F>>,
     config: &StarkConfig,
 ) -> Result<()> {
+    let num_zs = all_kind!(|kind| CrossTableLookup::num_ctl_zs(cross_table_lookups, kind, 1));
     let mut ctl_zs_openings = ctl_zs_lasts.each_ref().map(|v| v.iter().copied());
     for _ in 0..config.num_challenges {
-        for CrossTableLookup { looking_tables } in cross_table_lookups {
-            let looking_zs_sum = looking_tables
+        // The tuples are tagged with their cross-table lookup, so the sums of
+        // all of them can be checked at once.
+        let looking_zs_sum =
+            all_kind!(|kind| ctl_zs_openings[kind].by_ref().take(num_zs[kind]).sum::<F>())
                 .iter()
-                .map(|table| ctl_zs_openings[table.kind].next().unwrap())
+                .copied()
                 .sum::<F>();
 
-            ensure!(
-                looking_zs_sum == F::ZERO,
-                "Cross-table lookup verification failed for {:?} ({} != 0)",
-                looking_tables.iter().map(|table| table.kind),
-                looking_zs_sum,
-            );
-        }
+        ensure!(
+            looking_zs_sum == F::ZERO,
+            "Cross-table lookup verification failed ({} != 0)",
+            looking_zs_sum,
+        );
     }
     let mut reduced_public_sub_table_values_iter = reduced_public_sub_table_values
         .each_ref()
@@ -117,19 +197,19 @@ pub(crate) fn verify_cross_table_lookups_and_public_sub_table_circuit<
     ctl_zs_lasts: &TableKindArray<Vec<Target>>,
     config: &StarkConfig,
 ) {
+    let num_zs = all_kind!(|kind| CrossTableLookup::num_ctl_zs(cross_table_lookups, kind, 1));
     let mut ctl_zs_openings = ctl_zs_lasts.each_ref().map(|v| v.iter());
     for _ in 0..config.num_challenges {
-        for CrossTableLookup { looking_tables } in cross_table_lookups {
-            let looking_zs_sum = builder.add_many(
-                looking_tables
-                    .iter()
-                    .map(|table| *ctl_zs_openings[table.kind].next().unwrap()),
-            );
+        let looking_zs = all_kind!(|kind| ctl_zs_openings[kind]
+            .by_ref()
+            .take(num_zs[kind])
+            .copied()
+            .collect_vec());
+        let looking_zs_sum = builder.add_many(looking_zs.iter().flatten());
 
-            let zero = builder.zero();
+        let zero = builder.zero();
 
-            builder.connect(zero, looking_zs_sum);
-        }
+        builder.connect(zero, looking_zs_sum);
     }
 
     let mut reduced_public_sub_table_targets_iter = reduced_public_sub_table_targets
@@ -154,32 +234,27 @@ pub(crate) fn cross_table_lookup_data<F: RichField, const D: usize>(
     cross_table_lookups: &[CrossTableLookup],
     ctl_challenges: &GrandProductChallengeSet<F>,
 ) -> TableKindArray<CtlData<F>> {
+    let batches = all_kind!(|kind| CtlLookup::batches(cross_table_lookups, kind));
     let mut tables = iproduct!(
         &ctl_challenges.challenges,
-        cross_table_lookups
+        all_kind!(|kind| kind)
             .iter()
-            .flat_map(|CrossTableLookup { looking_tables }| looking_tables)
+            .flat_map(|&kind| batches[kind].iter().map(move |lookups| (kind, lookups)))
     )
     .collect::<Vec<_>>()
     .into_par_iter()
-    .map(|(&challenge, table)| {
-        (table.kind, CtlZData {
-            z: partial_sums(
-                &trace_poly_values[table.kind],
-                &table.columns,
-                &table.filter_column,
-                challenge,
-            ),
+    .map(|(&challenge, (kind, lookups))| {
+        (kind, CtlZData {
+            z: partial_sums(&trace_poly_values[kind], lookups, challenge),
             challenge,
-            columns: table.columns.clone(),
-            filter_column: table.filter_column.clone(),
+            lookups: lookups.clone(),
         })
     })
     .collect::<Vec<_>>()
     .into_iter()
     .into_group_map();
     all_kind!(|kind| CtlData {
-        zs_columns: tables.remove(&kind).unwrap(),
+        zs_columns: tables.remove(&kind).unwrap_or_default(),
     })
 }
 
@@ -203,8 +278,7 @@ pub fn compose_ctl_with_challenge<F: Field>(
 
 pub fn partial_sums<F: Field>(
     trace: &[PolynomialValues<F>],
-    columns: &[Column],
-    filter_column: &Column,
+    lookups: &[CtlLookup],
     challenge: GrandProductChallenge<F>,
 ) -> PolynomialValues<F> {
     // design of table looks like this
@@ -215,25 +289,39 @@ pub fn partial_sums<F: Field>(
     // (where combine(vals) = gamma + reduced_sum(vals, beta))
     // transition constraint looks like
     //       z_next = z_local + filter_local/combine_local
-
-    let filter_column = filter_column.to_field();
-    let get_multiplicity = |&i| -> F { filter_column.eval_table(trace, i) };
-
-    let columns: Vec<ColumnSparse<F>> = columns.iter().map(Column::to_field).collect();
-    let prepped = compose_ctl_with_challenge(&columns, challenge);
-    let get_data = |&i| -> F { prepped.eval_table(trace, i) };
+    // With several lookups folded into one Z(x), the terms of all of them
+    // are added up on each row.
 
     let degree = trace[0].len();
     let mut degrees = (0..degree).collect::<Vec<_>>();
     degrees.rotate_right(1);
 
-    let multiplicities: Vec<F> = degrees.iter().map(get_multiplicity).collect();
-    let data: Vec<F> = degrees.iter().map(get_data).collect();
-    let inv_data = F::batch_multiplicative_inverse(&data);
+    let mut terms = vec![F::ZERO; degree];
+    for CtlLookup {
+        columns,
+        filter_column,
+    } in lookups
+    {
+        let filter_column = filter_column.to_field();
+        let get_multiplicity = |&i| -> F { filter_column.eval_table(trace, i) };
+
+        let columns: Vec<ColumnSparse<F>> = columns.iter().map(Column::to_field).collect();
+        let prepped = compose_ctl_with_challenge(&columns, challenge);
+        let get_data = |&i| -> F { prepped.eval_table(trace, i) };
+
+        let multiplicities: Vec<F> = degrees.iter().map(get_multiplicity).collect();
+        let data: Vec<F> = degrees.iter().map(get_data).collect();
+        let inv_data = F::batch_multiplicative_inverse(&data);
+
+        for (term, multiplicity, inv) in izip!(&mut terms, multiplicities, inv_data) {
+            *term += multiplicity * inv;
+        }
+    }
 
-    izip!(multiplicities, inv_data)
-        .scan(F::ZERO, |partial_sum: &mut F, (multiplicity, inv)| {
-            *partial_sum += multiplicity * inv;
+    terms
+        .into_iter()
+        .scan(F::ZERO, |partial_sum: &mut F, term| {
+            *partial_sum += term;
             Some(*partial_sum)
         })
         .collect::<Vec<_>>()
@@ -278,12 +366,15 @@ impl<Row> CrossTableLookupWithTypedOutput<Row> {
         Self { looking_tables }
     }
 
+    /// The number of Z(x) polynomials of `table`, with up to
+    /// [`LOOKUPS_PER_Z`] lookups folded into each.
     #[must_use]
     pub fn num_ctl_zs(ctls: &[Self], table: TableKind, num_challenges: usize) -> usize {
         ctls.iter()
             .flat_map(|ctl| &ctl.looking_tables)
             .filter(|twc| twc.kind == table)
             .count()
+            .div_ceil(LOOKUPS_PER_Z)
             * num_challenges
     }
 }
@@ -297,8 +388,7 @@ where
     pub(crate) local_z: P,
     pub(crate) next_z: P,
     pub(crate) challenges: GrandProductChallenge<F>,
-    pub(crate) columns: &'a [Column],
-    pub(crate) filter_column: &'a Column,
+    pub(crate) lookups: &'a [CtlLookup],
 }
 
 impl<'a, F: RichField + Extendable<D>, const D: usize>
@@ -306,41 +396,36 @@ impl<'a, F: RichField + Extendable<D>, const D: usize>
 {
     pub(crate) fn from_proofs<C: GenericConfig<D, F = F>>(
         proofs: &TableKindArray<StarkProof<F, C, D>>,
-        cross_table_lookups: &'a [CrossTableLookup],
-        public_sub_tables: &'a [PublicSubTable],
+        cross_table_lookups: &[CrossTableLookup],
+        lookups: &'a TableKindArray<Vec<Vec<CtlLookup>>>,
         ctl_challenges: &'a GrandProductChallengeSet<F>,
     ) -> TableKindArray<Vec<Self>> {
-        let mut ctl_zs = proofs
-            .each_ref()
-            .map(|p| izip!(&p.openings.ctl_zs, &p.openings.ctl_zs_next));
-
-        let mut ctl_vars_per_table = all_kind!(|_kind| vec![]);
-        let ctl_chain = cross_table_lookups
-            .iter()
-            .flat_map(|ctl| &ctl.looking_tables);
-        for (&challenges, table) in iproduct!(&ctl_challenges.challenges, ctl_chain) {
-            let (&local_z, &next_z) = ctl_zs[table.kind].next().unwrap();
-            ctl_vars_per_table[table.kind].push(Self {
-                local_z,
-                next_z,
-                challenges,
-                columns: &table.columns,
-                filter_column: &table.filter_column,
-            });
-        }
-        for (&challenges, public_sub_table) in
-            iproduct!(&ctl_challenges.challenges, public_sub_tables)
-        {
-            let (&local_z, &next_z) = ctl_zs[public_sub_table.table.kind].next().unwrap();
-            ctl_vars_per_table[public_sub_table.table.kind].push(Self {
-                local_z,
-                next_z,
-                challenges,
-                columns: &public_sub_table.table.columns,
-                filter_column: &public_sub_table.table.filter_column,
-            });
-        }
-        ctl_vars_per_table
+        all_kind!(|kind| {
+            let (batches, public_sub_tables) =
+                lookups[kind].split_at(CrossTableLookup::num_ctl_zs(cross_table_lookups, kind, 1));
+            // Empty proofs have no Z(x) polynomials to check.
+            if proofs[kind].is_empty() {
+                vec![]
+            } else {
+                zip_eq(
+                    izip!(
+                        &proofs[kind].openings.ctl_zs,
+                        &proofs[kind].openings.ctl_zs_next
+                    ),
+                    chain!(
+                        iproduct!(&ctl_challenges.challenges, batches),
+                        iproduct!(&ctl_challenges.challenges, public_sub_tables)
+                    ),
+                )
+                .map(|((&local_z, &next_z), (&challenges, lookups))| Self {
+                    local_z,
+                    next_z,
+                    challenges,
+                    lookups,
+                })
+                .collect()
+            }
+        })
     }
 }
 
@@ -358,19 +443,32 @@ pub(crate) fn eval_cross_table_lookup_checks<F, FE, P, S, const D: usize, const
             local_z,
             next_z,
             challenges,
-            columns,
-            filter_column,
+            lookups,
         } = lookup_vars;
         let local_values = vars.get_local_values();
         let next_values = vars.get_next_values();
 
-        let combine = |lv: &[P], nv: &[P]| -> P {
-            let evals = columns.iter().map(|c| c.eval(lv, nv)).collect::<Vec<_>>();
-            challenges.combine(evals.iter())
-        };
-        let combination = combine(local_values, next_values);
-        let multiplicity = |lv: &[P], nv: &[P]| -> P { filter_column.eval(lv, nv) };
-        let multiplicity = multiplicity(local_values, next_values);
+        // The sum of `filter_i/combined_i` over the lookups, as the fraction
+        // `multiplicity/combination`.
+        let (multiplicity, combination) = lookups.iter().fold(
+            (P::ZEROS, P::ONES),
+            |(multiplicity, combination),
+             CtlLookup {
+                 columns,
+                 filter_column,
+             }| {
+                let evals: Vec<P> = columns
+                    .iter()
+                    .map(|c| c.eval(local_values, next_values))
+                    .collect();
+                let combined: P = challenges.combine(evals.iter());
+                let filter: P = filter_column.eval(local_values, next_values);
+                (
+                    multiplicity * combined + filter * combination,
+                    combination * combined,
+                )
+            },
+        );
 
         // Check value of `Z(1) = filter(w^(n-1))/combined(w^(n-1))`
         consumer.constraint_last_row(*next_z * combination - multiplicity);
@@ -385,44 +483,34 @@ pub struct CtlCheckVarsTarget<'a, const D: usize> {
     pub local_z: ExtensionTarget<D>,
     pub next_z: ExtensionTarget<D>,
     pub challenges: GrandProductChallenge<Target>,
-    pub columns: &'a [Column],
-    pub filter_column: &'a Column,
+    pub lookups: &'a [CtlLookup],
 }
 
 impl<'a, const D: usize> CtlCheckVarsTarget<'a, D> {
+    /// `lookups` are those of `table`, as given by [`CtlLookup::per_table`].
     #[must_use]
     pub fn from_proof(
         table: TableKind,
         proof: &StarkProofTarget<D>,
-        cross_table_lookups: &'a [CrossTableLookup],
-        public_sub_tables: &'a [PublicSubTable],
+        cross_table_lookups: &[CrossTableLookup],
+        lookups: &'a [Vec<CtlLookup>],
         ctl_challenges: &'a GrandProductChallengeSet<Target>,
     ) -> Vec<Self> {
         let ctl_zs = izip!(&proof.openings.ctl_zs, &proof.openings.ctl_zs_next);
-
-        let ctl_chain = cross_table_lookups
-            .iter()
-            .flat_map(|ctl| ctl.looking_tables.iter().filter(|twc| twc.kind == table));
-        let public_sub_table_chain = public_sub_tables.iter().filter_map(|twc| {
-            if twc.table.kind == table {
-                Some(&twc.table)
-            } else {
-                None
-            }
-        });
+        let (batches, public_sub_tables) =
+            lookups.split_at(CrossTableLookup::num_ctl_zs(cross_table_lookups, table, 1));
         zip_eq(
             ctl_zs,
             chain!(
-                iproduct!(&ctl_challenges.challenges, ctl_chain),
-                iproduct!(&ctl_challenges.challenges, public_sub_table_chain)
+                iproduct!(&ctl_challenges.challenges, batches),
+                iproduct!(&ctl_challenges.challenges, public_sub_tables)
             ),
         )
-        .map(|((&local_z, &next_z), (&challenges, table))| Self {
+        .map(|((&local_z, &next_z), (&challenges, lookups))| Self {
             local_z,
             next_z,
             challenges,
-            columns: &table.columns,
-            filter_column: &table.filter_column,
+            lookups,
         })
         .collect()
     }
@@ -443,28 +531,37 @@ pub fn eval_cross_table_lookup_checks_circuit<
             local_z,
             next_z,
             challenges,
-            columns,
-            filter_column,
+            lookups,
         }: &CtlCheckVarsTarget<D> = lookup_vars;
 
         let local_values = vars.get_local_values();
         let next_values = vars.get_next_values();
 
-        let evals: Vec<_> = columns
-            .iter()
-            .map(|c| c.eval_circuit(builder, local_values, next_values))
-            .collect();
-        let combined = challenges.combine_circuit(builder, &evals);
-
-        let multiplicity = filter_column.eval_circuit(builder, local_values, next_values);
+        let mut multiplicity = builder.zero_extension();
+        let mut combination = builder.one_extension();
+        for CtlLookup {
+            columns,
+            filter_column,
+        } in *lookups
+        {
+            let evals: Vec<_> = columns
+                .iter()
+                .map(|c| c.eval_circuit(builder, local_values, next_values))
+                .collect();
+            let combined = challenges.combine_circuit(builder, &evals);
+            let filter = filter_column.eval_circuit(builder, local_values, next_values);
+            let scaled = builder.mul_extension(multiplicity, combined);
+            multiplicity = builder.mul_add_extension(filter, combination, scaled);
+            combination = builder.mul_extension(combination, combined);
+        }
 
         // Check value of `Z(1) = filter(w^(n-1))/combined(w^(n-1))`
-        let last_row = builder.mul_sub_extension(*next_z, combined, multiplicity);
+        let last_row = builder.mul_sub_extension(*next_z, combination, multiplicity);
         consumer.constraint_last_row(builder, last_row);
 
         // Check `Z(gw) - Z(w) = filter(w)/combined(w)`
         let diff = builder.sub_extension(*next_z, *local_z);
-        let transition = builder.mul_sub_extension(diff, combined, multiplicity);
+        let transition = builder.mul_sub_extension(diff, combination, multiplicity);
         consumer.constraint_transition(builder, transition);
     }
 }
@@ -580,3 +677,102 @@ pub mod ctl_utils {
 }
 
 // TODO(Matthias): restore the tests from before https://github.com/0xmozak/mozak-vm/pull/1371
+
+#[cfg(test)]
+mod tests {
+    use itertools::izip;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::polynomial::PolynomialValues;
+    use plonky2::field::types::{Field, Sample};
+
+    use super::{partial_sums, Column, CrossTableLookup, CtlLookup};
+    use crate::stark::mozak_stark::{Table, TableKind};
+    use crate::stark::permutation::challenge::GrandProductChallenge;
+
+    type F = GoldilocksField;
+
+    fn column(i: usize) -> Column {
+        Column {
+            lv_linear_combination: vec![(i, 1)],
+            ..Column::default()
+        }
+    }
+
+    #[test]
+    fn folded_lookups_add_up() {
+        let trace = (0..3)
+            .map(|_| PolynomialValues::new(F::rand_vec(8)))
+            .collect::<Vec<_>>();
+        let challenge = GrandProductChallenge {
+            beta: F::rand(),
+            gamma: F::rand(),
+        };
+        let a = CtlLookup::untagged(&Table::new(TableKind::Cpu, vec![column(0)], column(1)));
+        let b = CtlLookup::untagged(&Table::new(
+            TableKind::Cpu,
+            vec![column(0), column(2)],
+            -column(1),
+        ));
+
+        let folded = partial_sums(&trace, &[a.clone(), b.clone()], challenge);
+        let separate = [a, b].map(|lookup| partial_sums(&trace, &[lookup], challenge));
+        let sums = izip!(&separate[0].values, &separate[1].values)
+            .map(|(&x, &y)| x + y)
+            .collect::<Vec<_>>();
+        assert_eq!(folded.values, sums);
+    }
+
+    #[test]
+    fn lookups_of_different_ctls_share_zs() {
+        let ctl = |looked| {
+            CrossTableLookup::new(
+                vec![Table::new(TableKind::Cpu, vec![column(0)], column(1))],
+                vec![Table::new(looked, vec![column(0)], column(1))],
+            )
+        };
+        let ctls = [
+            ctl(TableKind::RangeCheck),
+            ctl(TableKind::Xor),
+            ctl(TableKind::Xor),
+        ];
+
+        let batches = CtlLookup::batches(&ctls, TableKind::Cpu);
+        assert_eq!(batches.iter().map(Vec::len).collect::<Vec<_>>(), [2, 1]);
+        assert_eq!(CrossTableLookup::num_ctl_zs(&ctls, TableKind::Cpu, 2), 4);
+        assert_eq!(CrossTableLookup::num_ctl_zs(&ctls, TableKind::Xor, 2), 2);
+
+        // The same tuples of different cross-table lookups are told apart by
+        // their tag.
+        let tags = batches
+            .concat()
+            .iter()
+            .map(|lookup| lookup.columns.first().unwrap().constant)
+            .collect::<Vec<_>>();
+        assert_eq!(tags, [1, 2, 3]);
+    }
+
+    #[test]
+    fn tags_separate_ctls_of_different_widths() {
+        // With the tag as the last column, the tuple `(x, 1)` of the first
+        // cross-table lookup would combine to the same value as `(x)` of the
+        // second one.
+        let trace = [F::rand_vec(8), vec![F::ONE; 8], vec![F::ONE; 8]]
+            .map(PolynomialValues::new)
+            .to_vec();
+        let challenge = GrandProductChallenge {
+            beta: F::rand(),
+            gamma: F::rand(),
+        };
+        let ctl = |columns| {
+            CrossTableLookup::new(vec![Table::new(TableKind::Cpu, columns, column(1))], vec![
+                Table::new(TableKind::Xor, vec![column(0)], column(1)),
+            ])
+        };
+        let ctls = [ctl(vec![column(0), column(2)]), ctl(vec![column(0)])];
+
+        let lookups = CtlLookup::batches(&ctls, TableKind::Cpu).concat();
+        let [wide, narrow] = [&lookups[0], &lookups[1]]
+            .map(|lookup| partial_sums(&trace, std::slice::from_ref(lookup), challenge));
+        assert_ne!(wide.values, narrow.values);
+    }
+}
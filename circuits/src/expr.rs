@@ -1,56 +1,55 @@
+use std::cell::RefCell;
 use std::panic::Location;
 
+use expr::circuit::CircuitBuilderEvaluator;
+use expr::constraints::{Constraint, ConstraintType};
 pub use expr::PureEvaluator;
-use expr::{BinOp, Cached, Evaluator, Expr, UnaOp};
+use expr::{BinOp, Cached, ConstraintCollector, Evaluator, Expr, HashConsing, Simplifier, UnaOp};
+use itertools::izip;
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
+use plonky2::field::types::PrimeField64;
 use plonky2::hash::hash_types::RichField;
 use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use starky::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
 
-struct CircuitBuilderEvaluator<'a, F, const D: usize>
-where
-    F: RichField,
-    F: Extendable<D>, {
-    builder: &'a mut CircuitBuilder<F, D>,
-}
+/// Evaluator for packed fields.
+///
+/// Unlike [`PureEvaluator`], it can divide, which packed fields only support
+/// lane by lane.
+pub struct PackedFieldEvaluator<P>(fn(i64) -> P);
 
-impl<'a, F, const D: usize> Evaluator<'a, ExtensionTarget<D>> for CircuitBuilderEvaluator<'a, F, D>
+impl<'a, P> Evaluator<'a, P> for PackedFieldEvaluator<P>
 where
-    F: RichField,
-    F: Extendable<D>,
+    P: PackedField,
 {
-    fn bin_op(
-        &mut self,
-        op: BinOp,
-        left: ExtensionTarget<D>,
-        right: ExtensionTarget<D>,
-    ) -> ExtensionTarget<D> {
+    fn bin_op(&mut self, op: BinOp, left: P, right: P) -> P {
         match op {
-            BinOp::Add => self.builder.add_extension(left, right),
-            BinOp::Sub => self.builder.sub_extension(left, right),
-            BinOp::Mul => self.builder.mul_extension(left, right),
+            BinOp::Add => left + right,
+            BinOp::Sub => left - right,
+            BinOp::Mul => left * right,
+            BinOp::Div => {
+                let mut quotient = left;
+                for (q, &r) in izip!(quotient.as_slice_mut(), right.as_slice()) {
+                    *q /= r;
+                }
+                quotient
+            }
         }
     }
 
-    fn una_op(&mut self, op: UnaOp, expr: ExtensionTarget<D>) -> ExtensionTarget<D> {
+    fn una_op(&mut self, op: UnaOp, expr: P) -> P {
         match op {
-            UnaOp::Neg => {
-                let neg_one = self.builder.neg_one();
-                self.builder.scalar_mul_ext(neg_one, expr)
-            }
+            UnaOp::Neg => -expr,
         }
     }
 
-    fn constant(&mut self, value: i64) -> ExtensionTarget<D> {
-        let f = F::from_noncanonical_i64(value);
-        self.builder.constant_extension(f.into())
-    }
+    fn constant(&mut self, value: i64) -> P { (self.0)(value) }
 }
 
 #[must_use]
-pub fn packed_field_evaluator<F, FE, P, const D: usize, const D2: usize>() -> PureEvaluator<P>
+pub fn packed_field_evaluator<F, FE, P, const D: usize, const D2: usize>() -> PackedFieldEvaluator<P>
 where
     F: RichField,
     F: Extendable<D>,
@@ -64,89 +63,17 @@ where
         P: PackedField<Scalar = FE>, {
         P::from(FE::from_noncanonical_i64(value))
     }
-    PureEvaluator(convert)
-}
-
-#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
-pub struct Constraint<E> {
-    constraint_type: ConstraintType,
-    location: &'static Location<'static>,
-    term: E,
-}
-
-impl<E> Constraint<E> {
-    fn map<B, F>(self, mut f: F) -> Constraint<B>
-    where
-        F: FnMut(E) -> B, {
-        Constraint {
-            constraint_type: self.constraint_type,
-            location: self.location,
-            term: f(self.term),
-        }
-    }
-}
-
-#[derive(PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
-enum ConstraintType {
-    FirstRow,
-    #[default]
-    Always,
-    Transition,
-    LastRow,
-}
-
-pub struct ConstraintBuilder<E> {
-    constraints: Vec<Constraint<E>>,
-}
-impl<E> Default for ConstraintBuilder<E> {
-    fn default() -> Self {
-        Self {
-            constraints: Vec::default(),
-        }
-    }
-}
-
-impl<E> From<Vec<Constraint<E>>> for ConstraintBuilder<E> {
-    fn from(constraints: Vec<Constraint<E>>) -> Self { Self { constraints } }
-}
-
-impl<E> ConstraintBuilder<E> {
-    #[track_caller]
-    fn constraint(&mut self, term: E, constraint_type: ConstraintType) {
-        self.constraints.push(Constraint {
-            constraint_type,
-            location: Location::caller(),
-            term,
-        });
-    }
-
-    #[track_caller]
-    pub fn first_row(&mut self, constraint: E) {
-        self.constraint(constraint, ConstraintType::FirstRow);
-    }
-
-    #[track_caller]
-    pub fn last_row(&mut self, constraint: E) {
-        self.constraint(constraint, ConstraintType::LastRow);
-    }
-
-    #[track_caller]
-    pub fn always(&mut self, constraint: E) { self.constraint(constraint, ConstraintType::Always); }
-
-    #[track_caller]
-    pub fn transition(&mut self, constraint: E) {
-        self.constraint(constraint, ConstraintType::Transition);
-    }
+    PackedFieldEvaluator(convert)
 }
 
 pub fn build_ext<F, const D: usize>(
-    cb: ConstraintBuilder<Expr<'_, ExtensionTarget<D>>>,
+    cb: ConstraintCollector<'_, ExtensionTarget<D>>,
     circuit_builder: &mut CircuitBuilder<F, D>,
     yield_constr: &mut RecursiveConstraintConsumer<F, D>,
 ) where
     F: RichField,
     F: Extendable<D>, {
-    for constraint in cb.constraints {
+    for constraint in cb.into_iter().flat_map(Constraint::split) {
         let mut evaluator = Cached::from(CircuitBuilderEvaluator {
             builder: circuit_builder,
         });
@@ -161,21 +88,50 @@ pub fn build_ext<F, const D: usize>(
 }
 
 pub fn build_packed<F, FE, P, const D: usize, const D2: usize>(
-    cb: ConstraintBuilder<Expr<'_, P>>,
+    cb: ConstraintCollector<'_, P>,
     yield_constr: &mut ConstraintConsumer<P>,
 ) where
     F: RichField,
     F: Extendable<D>,
     FE: FieldExtension<D2, BaseField = F>,
     P: PackedField<Scalar = FE>, {
+    // Simplifying and hash-consing have to be redone for every row, as the
+    // constraints are built anew each time, so they are opt-in until profiles
+    // show that they pay off.  The recursive circuit is left as is either way,
+    // to keep its shape stable.
+    let constraints = cb.into_iter().flat_map(Constraint::split);
+    let constraints: Vec<_> = if cfg!(feature = "simplify") {
+        let mut simplifier = Simplifier::default();
+        let mut hash_consing = HashConsing::default();
+        constraints
+            .map(|c| c.map(|constraint| hash_consing.hash_cons(simplifier.simplify(constraint))))
+            .collect()
+    } else {
+        constraints.collect()
+    };
+
+    // Only the debug API records the values of the constraints, see
+    // `debug_single_trace`, which also reports the ones that fail.
+    let recording = RECORDED.with_borrow(Option::is_some);
+    let mut recorded = vec![];
     let mut evaluator = Cached::from(packed_field_evaluator());
-    let evaluated = cb
-        .constraints
-        .into_iter()
-        .map(|c| c.map(|constraint| evaluator.eval(constraint)))
-        .collect::<Vec<_>>();
-
-    for c in evaluated {
+    for c in constraints {
+        let term = c.term;
+        let c = c.map(|constraint| evaluator.eval(constraint));
+        if recording {
+            recorded.push(ConstraintValue {
+                constraint_type: c.constraint_type,
+                location: c.location,
+                annotations: term.annotations(),
+                value: c
+                    .term
+                    .as_slice()
+                    .iter()
+                    .flat_map(FieldExtension::to_basefield_array)
+                    .map(|x| x.to_canonical_u64())
+                    .collect(),
+            });
+        }
         (match c.constraint_type {
             ConstraintType::FirstRow => ConstraintConsumer::constraint_first_row,
             ConstraintType::Always => ConstraintConsumer::constraint,
@@ -183,4 +139,52 @@ pub fn build_packed<F, FE, P, const D: usize, const D2: usize>(
             ConstraintType::LastRow => ConstraintConsumer::constraint_last_row,
         })(yield_constr, c.term);
     }
+    if recording {
+        RECORDED.with_borrow_mut(|outer| outer.get_or_insert_with(Vec::new).extend(recorded));
+    }
+}
+
+/// The value of a constraint on a single row, as recorded by
+/// [`record_constraints`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintValue {
+    pub constraint_type: ConstraintType,
+    /// Where the constraint was built.
+    pub location: &'static Location<'static>,
+    /// The labels and locations of the annotated subexpressions, see
+    /// [`expr::constraint!`].
+    pub annotations: Vec<(&'static str, &'static Location<'static>)>,
+    /// The canonical value of each lane, and of each of their coefficients.
+    pub value: Vec<u64>,
+}
+
+impl ConstraintValue {
+    /// Whether the constraint fails on a row, given whether that row is the
+    /// first and whether it is the last one.
+    #[must_use]
+    pub fn fails(&self, is_first: bool, is_last: bool) -> bool {
+        let applies = match self.constraint_type {
+            ConstraintType::FirstRow => is_first,
+            ConstraintType::Always => true,
+            ConstraintType::Transition => !is_last,
+            ConstraintType::LastRow => is_last,
+        };
+        applies && self.value.iter().any(|&x| x != 0)
+    }
+}
+
+thread_local! {
+    static RECORDED: RefCell<Option<Vec<ConstraintValue>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, and records the values of all the constraints it evaluates with
+/// [`build_packed`], in order.
+///
+/// Meant for the debug API, which evaluates the constraints of a single row at
+/// a time.
+pub fn record_constraints<R>(f: impl FnOnce() -> R) -> (R, Vec<ConstraintValue>) {
+    let outer = RECORDED.replace(Some(Vec::new()));
+    let result = f();
+    let recorded = RECORDED.replace(outer).unwrap_or_default();
+    (result, recorded)
 }
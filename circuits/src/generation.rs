@@ -4,6 +4,7 @@
 use std::borrow::Borrow;
 use std::fmt::{Debug, Display};
 
+use anyhow::Result;
 use itertools::{izip, Itertools};
 use log::debug;
 use mozak_runner::elf::Program;
@@ -11,17 +12,21 @@ use mozak_runner::vm::ExecutionRecord;
 use plonky2::field::extension::Extendable;
 use plonky2::field::packed::PackedField;
 use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::{Field, PrimeField64};
 use plonky2::hash::hash_types::RichField;
 use plonky2::util::timing::TimingTree;
 use plonky2::util::transpose;
+use plonky2_maybe_rayon::join;
 use starky::constraint_consumer::ConstraintConsumer;
 use starky::evaluation_frame::StarkEvaluationFrame;
 use starky::stark::Stark;
 
 use crate::bitshift::generation::generate_shift_amount_trace;
+use crate::blake3::generation::generate_blake3_trace;
 use crate::columns_view::HasNamedColumns;
 use crate::cpu::generation::{generate_cpu_trace, generate_program_mult_trace};
 use crate::cpu_skeleton::generation::generate_cpu_skeleton_trace;
+use crate::expr::{record_constraints, ConstraintValue};
 use crate::memory::generation::generate_memory_trace;
 use crate::memory_fullword::generation::generate_fullword_memory_trace;
 use crate::memory_halfword::generation::generate_halfword_memory_trace;
@@ -35,14 +40,16 @@ use crate::program::generation::generate_program_rom_trace;
 use crate::rangecheck::generation::generate_rangecheck_trace;
 use crate::rangecheck_u8::generation::generate_rangecheck_u8_trace;
 use crate::register::generation::{generate_register_init_trace, generate_register_trace};
+use crate::sha256::generation::generate_sha256_trace;
 use crate::stark::mozak_stark::{
-    all_starks, MozakStark, PublicInputs, TableKindArray, TableKindSetBuilder,
+    all_starks, MozakStark, PublicInputs, TableKind, TableKindArray, TableKindSetBuilder,
 };
 use crate::stark::utils::trace_rows_to_poly_values;
 use crate::storage_device::generation::{
     generate_call_tape_trace, generate_cast_list_commitment_tape_trace, generate_event_tape_trace,
-    generate_events_commitment_tape_trace, generate_private_tape_trace, generate_public_tape_trace,
-    generate_self_prog_id_tape_trace,
+    generate_events_commitment_tape_trace, generate_oracle_tape_trace,
+    generate_private_tape_commitment_tape_trace, generate_private_tape_trace,
+    generate_public_tape_trace, generate_self_prog_id_tape_trace,
 };
 use crate::tape_commitments::generation::generate_tape_commitments_trace;
 use crate::xor::generation::generate_xor_trace;
@@ -55,74 +62,377 @@ pub const MIN_TRACE_LENGTH: usize = 8;
 /// ## Parameters
 /// `program`: A serialized ELF Program
 /// `record`: Non-constrained execution trace generated by the runner
-#[must_use]
-#[allow(clippy::too_many_lines)]
+///
+/// # Errors
+///
+/// Errors if the `record` can not be turned into traces, see
+/// [`generate_traces_streaming`].
 pub fn generate_traces<F: RichField + Extendable<D>, const D: usize>(
+    program: &Program,
+    record: &ExecutionRecord<F>,
+    timing: &mut TimingTree,
+) -> Result<TableKindArray<Vec<PolynomialValues<F>>>> {
+    let mut traces = TableKindArray::<Option<_>>::default();
+    generate_traces_streaming::<F, D>(program, record, timing, |kind, trace| {
+        traces[kind] = Some(trace);
+    })?;
+    Ok(traces.map(|trace| trace.expect("every table has a trace")))
+}
+
+/// Generate Constrained traces for each type of gadgets, and hand the
+/// polynomial encoding of each table to `sink` as soon as it is done.
+///
+/// The rows of a table are dropped right after the last table that depends on
+/// them has been generated, instead of holding on to the rows of every table
+/// until the end.  This keeps the memory needed for long executions down,
+/// especially when `sink` commits to each trace as it comes in.
+///
+/// Tables that do not depend on each other are generated in parallel.  Every
+/// [`TableKind`] is handed to `sink` exactly once, in no particular order.
+///
+/// ## Parameters
+/// `program`: A serialized ELF Program
+/// `record`: Non-constrained execution trace generated by the runner
+/// `sink`: Receives the polynomial encoding of each table
+///
+/// # Errors
+///
+/// Errors if more bytes of the private tape are disclosed than can be proven.
+#[allow(clippy::too_many_lines)]
+pub fn generate_traces_streaming<F: RichField + Extendable<D>, const D: usize>(
     program: &Program,
     record: &ExecutionRecord<F>,
     _timing: &mut TimingTree,
-) -> TableKindArray<Vec<PolynomialValues<F>>> {
+    mut sink: impl FnMut(TableKind, Vec<PolynomialValues<F>>),
+) -> Result<()> {
     debug!("Starting Trace Generation");
-    let cpu_rows = generate_cpu_trace::<F>(record);
-    let skeleton_rows = generate_cpu_skeleton_trace(record);
-    let add_rows = ops::add::generate(record);
-    let blt_taken_rows = ops::blt_taken::generate(record);
-    let xor_rows = generate_xor_trace(&cpu_rows);
-    let shift_amount_rows = generate_shift_amount_trace(&cpu_rows);
-    let program_rows = generate_program_rom_trace(program);
-    let program_mult_rows = generate_program_mult_trace(&skeleton_rows, &program_rows);
-
-    let memory_init = generate_memory_init_trace(program);
-    let elf_memory_init_rows = generate_elf_memory_init_trace(program);
-
-    let memory_zeroinit_rows = generate_memory_zero_init_trace(&record.executed, program);
-
-    let halfword_memory_rows = generate_halfword_memory_trace(&record.executed);
-    let fullword_memory_rows = generate_fullword_memory_trace(&record.executed);
-    let private_tape_rows = generate_private_tape_trace(&record.executed);
-    let public_tape_rows = generate_public_tape_trace(&record.executed);
-    let call_tape_rows = generate_call_tape_trace(&record.executed);
-    let event_tape_rows = generate_event_tape_trace(&record.executed);
-    let events_commitment_tape_rows = generate_events_commitment_tape_trace(&record.executed);
-    let cast_list_commitment_tape_rows = generate_cast_list_commitment_tape_trace(&record.executed);
-    let self_prog_id_tape_rows = generate_self_prog_id_tape_trace(&record.executed);
-    let poseiden2_sponge_rows = generate_poseidon2_sponge_trace(&record.executed);
-    let poseidon2_output_bytes_rows = generate_poseidon2_output_bytes_trace(&poseiden2_sponge_rows);
-    let poseidon2_rows = generate_poseidon2_trace(&record.executed);
-
-    let memory_rows = generate_memory_trace(
-        &record.executed,
-        &memory_init,
-        &memory_zeroinit_rows,
-        &halfword_memory_rows,
-        &fullword_memory_rows,
-        &private_tape_rows,
-        &public_tape_rows,
-        &call_tape_rows,
-        &event_tape_rows,
-        &events_commitment_tape_rows,
-        &cast_list_commitment_tape_rows,
-        &self_prog_id_tape_rows,
-        &poseiden2_sponge_rows,
-        &poseidon2_output_bytes_rows,
-    );
-
-    let register_init_rows = generate_register_init_trace::<F>(record);
-    let (register_zero_read_rows, register_zero_write_rows, register_rows) =
-        generate_register_trace(
-            &cpu_rows,
-            &add_rows,
-            &blt_taken_rows,
-            &poseiden2_sponge_rows,
-            &private_tape_rows,
-            &public_tape_rows,
-            &call_tape_rows,
-            &event_tape_rows,
-            &events_commitment_tape_rows,
-            &cast_list_commitment_tape_rows,
-            &self_prog_id_tape_rows,
-            &register_init_rows,
-        );
+    // Tables that only depend on the program and the execution record.
+    let (
+        ((cpu_rows, skeleton_rows, program_rows), (add_rows, blt_taken_rows)),
+        (
+            (memory_init, memory_zeroinit_rows, elf_memory_init_rows),
+            (halfword_memory_rows, fullword_memory_rows),
+        ),
+    ) = join(
+        || {
+            join(
+                || {
+                    let ((cpu_rows, skeleton_rows), program_rows) = join(
+                        || {
+                            join(
+                                || generate_cpu_trace::<F>(record),
+                                || generate_cpu_skeleton_trace(record),
+                            )
+                        },
+                        || generate_program_rom_trace(program),
+                    );
+                    (cpu_rows, skeleton_rows, program_rows)
+                },
+                || {
+                    join(
+                        || ops::add::generate(record),
+                        || ops::blt_taken::generate(record),
+                    )
+                },
+            )
+        },
+        || {
+            join(
+                || {
+                    let ((memory_init, memory_zeroinit_rows), elf_memory_init_rows) = join(
+                        || {
+                            join(
+                                || generate_memory_init_trace(program),
+                                || generate_memory_zero_init_trace(&record.executed, program),
+                            )
+                        },
+                        || generate_elf_memory_init_trace(program),
+                    );
+                    (memory_init, memory_zeroinit_rows, elf_memory_init_rows)
+                },
+                || {
+                    join(
+                        || generate_halfword_memory_trace(&record.executed),
+                        || generate_fullword_memory_trace(&record.executed),
+                    )
+                },
+            )
+        },
+    );
+    let (
+        (
+            (private_tape_rows, public_tape_rows),
+            (call_tape_rows, event_tape_rows),
+            (
+                events_commitment_tape_rows,
+                cast_list_commitment_tape_rows,
+                self_prog_id_tape_rows,
+                oracle_tape_rows,
+                private_tape_commitment_tape_rows,
+            ),
+        ),
+        (
+            (poseiden2_sponge_rows, poseidon2_rows),
+            (sha256_rows, blake3_rows),
+            (register_init_rows, tape_commitments_rows),
+        ),
+    ) = join(
+        // Storage devices
+        || {
+            let (private_and_public, (call_and_event, commitments_and_self_prog_id)) = join(
+                || {
+                    join(
+                        || generate_private_tape_trace(&record.executed),
+                        || generate_public_tape_trace(&record.executed),
+                    )
+                },
+                || {
+                    join(
+                        || {
+                            join(
+                                || generate_call_tape_trace(&record.executed),
+                                || generate_event_tape_trace(&record.executed),
+                            )
+                        },
+                        || {
+                            let (
+                                (events_commitment, cast_list_commitment),
+                                (self_prog_id, (oracle, private_tape_commitment)),
+                            ) = join(
+                                || {
+                                    join(
+                                        || generate_events_commitment_tape_trace(&record.executed),
+                                        || {
+                                            generate_cast_list_commitment_tape_trace(
+                                                &record.executed,
+                                            )
+                                        },
+                                    )
+                                },
+                                || {
+                                    join(
+                                        || generate_self_prog_id_tape_trace(&record.executed),
+                                        || {
+                                            join(
+                                                || generate_oracle_tape_trace(&record.executed),
+                                                || {
+                                                    generate_private_tape_commitment_tape_trace(
+                                                        &record.executed,
+                                                    )
+                                                },
+                                            )
+                                        },
+                                    )
+                                },
+                            );
+                            (
+                                events_commitment,
+                                cast_list_commitment,
+                                self_prog_id,
+                                oracle,
+                                private_tape_commitment,
+                            )
+                        },
+                    )
+                },
+            );
+            (
+                private_and_public,
+                call_and_event,
+                commitments_and_self_prog_id,
+            )
+        },
+        // Precompiles, and the tables driven by the record alone.
+        || {
+            let (poseidon2, (hashes, rest)) = join(
+                || {
+                    join(
+                        || generate_poseidon2_sponge_trace(&record.executed),
+                        || generate_poseidon2_trace(&record.executed),
+                    )
+                },
+                || {
+                    join(
+                        || {
+                            join(
+                                || generate_sha256_trace(&record.executed),
+                                || generate_blake3_trace(&record.executed),
+                            )
+                        },
+                        || {
+                            join(
+                                || generate_register_init_trace::<F>(record),
+                                || generate_tape_commitments_trace(record),
+                            )
+                        },
+                    )
+                },
+            );
+            (poseidon2, hashes, rest)
+        },
+    );
+    let tape_commitments_rows = tape_commitments_rows?;
+    sink(
+        TableKind::ElfMemoryInit,
+        trace_rows_to_poly_values(elf_memory_init_rows),
+    );
+    sink(
+        TableKind::TapeCommitments,
+        trace_rows_to_poly_values(tape_commitments_rows),
+    );
+    sink(
+        TableKind::Poseidon2,
+        trace_rows_to_poly_values(poseidon2_rows),
+    );
+
+    // Tables that depend on the CPU trace, or on other tables.
+    let ((xor_rows, shift_amount_rows), (program_mult_rows, poseidon2_output_bytes_rows)) = join(
+        || {
+            join(
+                || generate_xor_trace(&cpu_rows),
+                || generate_shift_amount_trace(&cpu_rows),
+            )
+        },
+        || {
+            join(
+                || generate_program_mult_trace(&skeleton_rows, &program_rows),
+                || generate_poseidon2_output_bytes_trace(&poseiden2_sponge_rows),
+            )
+        },
+    );
+    sink(TableKind::Xor, trace_rows_to_poly_values(xor_rows));
+    sink(
+        TableKind::Bitshift,
+        trace_rows_to_poly_values(shift_amount_rows),
+    );
+    sink(
+        TableKind::CpuSkeleton,
+        trace_rows_to_poly_values(skeleton_rows),
+    );
+    sink(TableKind::Program, trace_rows_to_poly_values(program_rows));
+    sink(
+        TableKind::ProgramMult,
+        trace_rows_to_poly_values(program_mult_rows),
+    );
+
+    // The memory and register tables look up most of the others.
+    let (memory_rows, (register_zero_read_rows, register_zero_write_rows, register_rows)) = join(
+        || {
+            generate_memory_trace(
+                &record.executed,
+                &memory_init,
+                &memory_zeroinit_rows,
+                &halfword_memory_rows,
+                &fullword_memory_rows,
+                &private_tape_rows,
+                &public_tape_rows,
+                &call_tape_rows,
+                &event_tape_rows,
+                &events_commitment_tape_rows,
+                &cast_list_commitment_tape_rows,
+                &self_prog_id_tape_rows,
+                &oracle_tape_rows,
+                &private_tape_commitment_tape_rows,
+                &poseiden2_sponge_rows,
+                &poseidon2_output_bytes_rows,
+                &sha256_rows,
+                &blake3_rows,
+            )
+        },
+        || {
+            generate_register_trace(
+                &cpu_rows,
+                &add_rows,
+                &blt_taken_rows,
+                &poseiden2_sponge_rows,
+                &sha256_rows,
+                &blake3_rows,
+                &private_tape_rows,
+                &public_tape_rows,
+                &call_tape_rows,
+                &event_tape_rows,
+                &events_commitment_tape_rows,
+                &cast_list_commitment_tape_rows,
+                &self_prog_id_tape_rows,
+                &oracle_tape_rows,
+                &private_tape_commitment_tape_rows,
+                &register_init_rows,
+            )
+        },
+    );
+    drop(memory_init);
+    sink(
+        TableKind::MemoryZeroInit,
+        trace_rows_to_poly_values(memory_zeroinit_rows),
+    );
+    sink(
+        TableKind::HalfWordMemory,
+        trace_rows_to_poly_values(halfword_memory_rows),
+    );
+    sink(
+        TableKind::FullWordMemory,
+        trace_rows_to_poly_values(fullword_memory_rows),
+    );
+    sink(
+        TableKind::Poseidon2OutputBytes,
+        trace_rows_to_poly_values(poseidon2_output_bytes_rows),
+    );
+
+    sink(
+        TableKind::Poseidon2Sponge,
+        trace_rows_to_poly_values(poseiden2_sponge_rows),
+    );
+    sink(TableKind::Sha256, trace_rows_to_poly_values(sha256_rows));
+    sink(TableKind::Blake3, trace_rows_to_poly_values(blake3_rows));
+    sink(
+        TableKind::StorageDevicePrivate,
+        trace_rows_to_poly_values(private_tape_rows),
+    );
+    sink(
+        TableKind::StorageDevicePublic,
+        trace_rows_to_poly_values(public_tape_rows),
+    );
+    sink(
+        TableKind::CallTape,
+        trace_rows_to_poly_values(call_tape_rows),
+    );
+    sink(
+        TableKind::EventTape,
+        trace_rows_to_poly_values(event_tape_rows),
+    );
+    sink(
+        TableKind::EventsCommitmentTape,
+        trace_rows_to_poly_values(events_commitment_tape_rows),
+    );
+    sink(
+        TableKind::CastListCommitmentTape,
+        trace_rows_to_poly_values(cast_list_commitment_tape_rows),
+    );
+    sink(
+        TableKind::SelfProgIdTape,
+        trace_rows_to_poly_values(self_prog_id_tape_rows),
+    );
+    sink(
+        TableKind::OracleTape,
+        trace_rows_to_poly_values(oracle_tape_rows),
+    );
+    sink(
+        TableKind::PrivateTapeCommitmentTape,
+        trace_rows_to_poly_values(private_tape_commitment_tape_rows),
+    );
+    sink(
+        TableKind::RegisterInit,
+        trace_rows_to_poly_values(register_init_rows),
+    );
+    sink(
+        TableKind::RegisterZeroRead,
+        trace_rows_to_poly_values(register_zero_read_rows),
+    );
+    sink(
+        TableKind::RegisterZeroWrite,
+        trace_rows_to_poly_values(register_zero_write_rows),
+    );
+
     // Generate rows for the looking values with their multiplicities.
     let rangecheck_rows = generate_rangecheck_trace::<F>(
         &cpu_rows,
@@ -133,44 +443,29 @@ pub fn generate_traces<F: RichField + Extendable<D>, const D: usize>(
     );
     // Generate a trace of values containing 0..u8::MAX, with multiplicities to be
     // looked.
-    let rangecheck_u8_rows = generate_rangecheck_u8_trace(&rangecheck_rows, &memory_rows);
-    let add_trace = ops::add::generate(record);
-    let blt_trace = ops::blt_taken::generate(record);
-    let tape_commitments_rows = generate_tape_commitments_trace(record);
-
-    TableKindSetBuilder {
-        cpu_stark: trace_rows_to_poly_values(cpu_rows),
-        rangecheck_stark: trace_rows_to_poly_values(rangecheck_rows),
-        xor_stark: trace_rows_to_poly_values(xor_rows),
-        shift_amount_stark: trace_rows_to_poly_values(shift_amount_rows),
-        program_stark: trace_rows_to_poly_values(program_rows),
-        program_mult_stark: trace_rows_to_poly_values(program_mult_rows),
-        memory_stark: trace_rows_to_poly_values(memory_rows),
-        elf_memory_init_stark: trace_rows_to_poly_values(elf_memory_init_rows),
-        memory_zeroinit_stark: trace_rows_to_poly_values(memory_zeroinit_rows),
-        rangecheck_u8_stark: trace_rows_to_poly_values(rangecheck_u8_rows),
-        halfword_memory_stark: trace_rows_to_poly_values(halfword_memory_rows),
-        fullword_memory_stark: trace_rows_to_poly_values(fullword_memory_rows),
-        private_tape_stark: trace_rows_to_poly_values(private_tape_rows),
-        public_tape_stark: trace_rows_to_poly_values(public_tape_rows),
-        call_tape_stark: trace_rows_to_poly_values(call_tape_rows),
-        event_tape_stark: trace_rows_to_poly_values(event_tape_rows),
-        events_commitment_tape_stark: trace_rows_to_poly_values(events_commitment_tape_rows),
-        cast_list_commitment_tape_stark: trace_rows_to_poly_values(cast_list_commitment_tape_rows),
-        self_prog_id_tape_stark: trace_rows_to_poly_values(self_prog_id_tape_rows),
-        register_init_stark: trace_rows_to_poly_values(register_init_rows),
-        register_stark: trace_rows_to_poly_values(register_rows),
-        register_zero_read_stark: trace_rows_to_poly_values(register_zero_read_rows),
-        register_zero_write_stark: trace_rows_to_poly_values(register_zero_write_rows),
-        poseidon2_stark: trace_rows_to_poly_values(poseidon2_rows),
-        poseidon2_sponge_stark: trace_rows_to_poly_values(poseiden2_sponge_rows),
-        poseidon2_output_bytes_stark: trace_rows_to_poly_values(poseidon2_output_bytes_rows),
-        cpu_skeleton_stark: trace_rows_to_poly_values(skeleton_rows),
-        add_stark: trace_rows_to_poly_values(add_trace),
-        blt_taken_stark: trace_rows_to_poly_values(blt_trace),
-        tape_commitments_stark: trace_rows_to_poly_values(tape_commitments_rows),
-    }
-    .build()
+    let rangecheck_u8_rows =
+        generate_rangecheck_u8_trace(&rangecheck_rows, &memory_rows, &cpu_rows);
+    sink(TableKind::Cpu, trace_rows_to_poly_values(cpu_rows));
+    sink(TableKind::Add, trace_rows_to_poly_values(add_rows));
+    sink(
+        TableKind::BltTaken,
+        trace_rows_to_poly_values(blt_taken_rows),
+    );
+    sink(
+        TableKind::Register,
+        trace_rows_to_poly_values(register_rows),
+    );
+
+    sink(
+        TableKind::RangeCheck,
+        trace_rows_to_poly_values(rangecheck_rows),
+    );
+    sink(TableKind::Memory, trace_rows_to_poly_values(memory_rows));
+    sink(
+        TableKind::RangeCheckU8,
+        trace_rows_to_poly_values(rangecheck_u8_rows),
+    );
+    Ok(())
 }
 
 pub fn ascending_sum<F: RichField, I: IntoIterator<Item = F>>(cs: I) -> F {
@@ -197,11 +492,45 @@ pub fn transpose_polys<
     .collect_vec()
 }
 
+/// A constraint that does not hold on a trace, as found by
+/// [`debug_single_trace`].
+#[derive(Clone, Debug)]
+pub struct UnsatisfiedConstraint {
+    pub kind: TableKind,
+    /// The row the constraint was evaluated on, together with the next one.
+    pub row: usize,
+    /// The failing constraint, if the stark builds its constraints with
+    /// [`build_packed`](crate::expr::build_packed).
+    pub constraint: Option<ConstraintValue>,
+    /// The columns the constraint refers to, with their values.  Columns of
+    /// the next row are prefixed with `next.`.
+    pub columns: Vec<(String, u64)>,
+}
+
+impl Display for UnsatisfiedConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Constraint ")?;
+        if let Some(constraint) = &self.constraint {
+            write!(f, "built at {} ", constraint.location)?;
+        }
+        write!(f, "failed on row {} of {:?}", self.row, self.kind)?;
+        for (label, location) in self.constraint.iter().flat_map(|c| &c.annotations) {
+            write!(f, "\n  in `{label}`, built at {location}")?;
+        }
+        for (name, value) in &self.columns {
+            write!(f, "\n  {name} = {value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for UnsatisfiedConstraint {}
+
 pub fn debug_traces<F: RichField + Extendable<D>, const D: usize>(
     traces_poly_values: &TableKindArray<Vec<PolynomialValues<F>>>,
     mozak_stark: &MozakStark<F, D>,
     public_inputs: &PublicInputs<F>,
-) {
+) -> Result<(), UnsatisfiedConstraint> {
     let public_inputs = TableKindSetBuilder::<&[_]> {
         cpu_skeleton_stark: public_inputs.borrow(),
         ..Default::default()
@@ -209,36 +538,126 @@ pub fn debug_traces<F: RichField + Extendable<D>, const D: usize>(
     .build();
 
     all_starks!(mozak_stark, |stark, kind| {
-        debug_single_trace::<F, D, _>(stark, &traces_poly_values[kind], public_inputs[kind]);
+        debug_single_trace::<F, D, _>(stark, kind, &traces_poly_values[kind], public_inputs[kind])?;
     });
+    Ok(())
 }
 
+/// Checks the constraints of `stark` on every row of `trace_rows`, and
+/// reports the first one that does not hold.
 pub fn debug_single_trace<
     F: RichField + Extendable<D> + Debug,
     const D: usize,
     S: Stark<F, D> + Display + HasNamedColumns,
 >(
     stark: &S,
+    kind: TableKind,
     trace_rows: &[PolynomialValues<F>],
     public_inputs: &[F],
-) where
+) -> Result<(), UnsatisfiedConstraint>
+where
     S::Columns: FromIterator<F> + Debug, {
-    transpose_polys::<F, D, S>(trace_rows.to_vec())
-        .iter()
-        .enumerate()
-        .circular_tuple_windows()
-        .for_each(|((lv_row, lv), (nv_row, nv))| {
-            let mut consumer = ConstraintConsumer::new_debug_api(lv_row == 0, nv_row == 0);
-            let vars =
-                StarkEvaluationFrame::from_values(lv.as_slice(), nv.as_slice(), public_inputs);
-            stark.eval_packed_generic(&vars, &mut consumer);
-            if consumer.debug_api_has_constraint_failed() {
-                let lv: S::Columns = lv.iter().copied().collect();
-                let nv: S::Columns = nv.iter().copied().collect();
-                log::error!("Debug constraints for {stark}");
-                log::error!("lv-row[{lv_row}] - values: {lv:?}");
-                log::error!("nv-row[{nv_row}] - values: {nv:?}");
-            }
-            assert!(!consumer.debug_api_has_constraint_failed());
+    let rows = transpose_polys::<F, D, S>(trace_rows.to_vec());
+    for ((lv_row, lv), (nv_row, nv)) in rows.iter().enumerate().circular_tuple_windows() {
+        let (is_first, is_last) = (lv_row == 0, nv_row == 0);
+        let eval = |lv: &[F], nv: &[F]| {
+            record_constraints(|| {
+                let mut consumer = ConstraintConsumer::new_debug_api(is_first, is_last);
+                let vars = StarkEvaluationFrame::from_values(lv, nv, public_inputs);
+                stark.eval_packed_generic(&vars, &mut consumer);
+                consumer.debug_api_has_constraint_failed()
+            })
+        };
+        let (failed, constraints) = eval(lv, nv);
+        if !failed {
+            continue;
+        }
+        log::error!("Debug constraints for {stark}");
+        let lv_columns: S::Columns = lv.iter().copied().collect();
+        let nv_columns: S::Columns = nv.iter().copied().collect();
+        log::error!("lv-row[{lv_row}] - values: {lv_columns:?}");
+        log::error!("nv-row[{nv_row}] - values: {nv_columns:?}");
+
+        let Some(index) = constraints.iter().position(|c| c.fails(is_first, is_last)) else {
+            return Err(UnsatisfiedConstraint {
+                kind,
+                row: lv_row,
+                constraint: None,
+                columns: vec![],
+            });
+        };
+        // A column is referred to by the failing constraint, iff changing it
+        // changes the value of the constraint.
+        let names = column_names::<F, S::Columns>(lv.len());
+        let changes_constraint = |lv: &[F], nv: &[F]| eval(lv, nv).1[index] != constraints[index];
+        let columns = izip!(0.., lv, &names)
+            .filter(|&(i, _, _)| {
+                let mut lv = lv.clone();
+                lv[i] += F::ONE;
+                changes_constraint(&lv, nv)
+            })
+            .map(|(_, value, name)| (name.clone(), value.to_canonical_u64()))
+            .chain(
+                izip!(0.., nv, &names)
+                    .filter(|&(i, _, _)| {
+                        let mut nv = nv.clone();
+                        nv[i] += F::ONE;
+                        changes_constraint(lv, &nv)
+                    })
+                    .map(|(_, value, name)| (format!("next.{name}"), value.to_canonical_u64())),
+            )
+            .collect();
+        return Err(UnsatisfiedConstraint {
+            kind,
+            row: lv_row,
+            constraint: Some(constraints[index].clone()),
+            columns,
         });
+    }
+    Ok(())
+}
+
+/// Names the columns of a `columns_view` by the paths of their fields, like
+/// `limbs.a[3]`.
+///
+/// The names are read off the derived `Debug` output of a row, which lists the
+/// fields in the same order as they are laid out.  Falls back to the indices
+/// of the columns, if that output has an unexpected shape.
+fn column_names<F: RichField, Columns: FromIterator<F> + Debug>(num_columns: usize) -> Vec<String> {
+    let row: Columns = (0..num_columns).map(F::from_canonical_usize).collect();
+    let mut names = vec![];
+    // The path to the current line, with the number of elements seen so far
+    // for arrays.
+    let mut path: Vec<(String, Option<usize>)> = vec![];
+    for line in format!("{row:#?}").lines() {
+        let line = line.trim().trim_end_matches(',');
+        if matches!(line, "}" | "]" | ")") {
+            path.pop();
+            continue;
+        }
+        let (name, rest) = match (line.split_once(": "), path.last_mut()) {
+            (Some((name, rest)), _) => (format!(".{name}"), rest),
+            (None, Some((_, Some(index)))) => {
+                *index += 1;
+                (format!("[{}]", *index - 1), line)
+            }
+            (None, _) => (String::new(), line),
+        };
+        if rest.ends_with('{') {
+            path.push((name, None));
+        } else if rest.ends_with('[') || rest.ends_with('(') {
+            path.push((name, Some(0)));
+        } else {
+            let full = path
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .chain([name.as_str()]);
+            names.push(full.collect::<String>().trim_start_matches('.').to_string());
+        }
+    }
+    if names.len() == num_columns {
+        names
+    } else {
+        (0..num_columns).map(|i| format!("column {i}")).collect()
+    }
 }
@@ -7,6 +7,7 @@
 #![feature(const_trait_impl)]
 
 pub mod bitshift;
+pub mod blake3;
 pub mod columns_view;
 pub mod cpu;
 pub mod cpu_skeleton;
@@ -30,8 +31,10 @@ pub mod public_sub_table;
 pub mod rangecheck;
 pub mod rangecheck_u8;
 pub mod register;
+pub mod sha256;
 pub mod stark;
 pub mod storage_device;
+pub mod system_tape;
 pub mod tape_commitments;
 #[cfg(any(feature = "test", test))]
 pub mod test_utils;
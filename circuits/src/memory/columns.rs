@@ -1,10 +1,13 @@
 use core::ops::Add;
 
 use itertools::izip;
+use mozak_circuits_derive::CtlColumns;
+use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
 use plonky2::hash::hashing::PlonkyPermutation;
 use plonky2::hash::poseidon2::Poseidon2Permutation;
 
+use crate::blake3::columns::Blake3;
 use crate::columns_view::{columns_view_impl, make_col_map};
 use crate::cross_table_lookup::Column;
 use crate::memory_fullword::columns::FullWordMemory;
@@ -14,13 +17,14 @@ use crate::memoryinit::columns::{MemoryInit, MemoryInitCtl};
 use crate::poseidon2_output_bytes::columns::{Poseidon2OutputBytes, BYTES_COUNT};
 use crate::poseidon2_sponge::columns::Poseidon2Sponge;
 use crate::rangecheck::columns::RangeCheckCtl;
+use crate::sha256::columns::Sha256;
 use crate::stark::mozak_stark::{MemoryTable, TableWithTypedOutput};
 use crate::storage_device::columns::StorageDevice;
 
 /// Represents a row of the memory trace that is transformed from read-only,
 /// read-write, halfword and fullword memories
 #[repr(C)]
-#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug, CtlColumns)]
 pub struct Memory<T> {
     /// Indicates if a the memory address is writable.
     pub is_writable: T,
@@ -45,6 +49,7 @@ pub struct Memory<T> {
     pub is_init: T,
 
     /// Value of memory access.
+    #[CtlColumns(looking = "rangecheck_u8")]
     pub value: T,
 }
 columns_view_impl!(Memory);
@@ -158,6 +163,67 @@ impl<F: RichField> From<&Poseidon2OutputBytes<F>> for Vec<Memory<F>> {
     }
 }
 
+impl<F: RichField> From<&Sha256<F>> for Vec<Memory<F>> {
+    fn from(row: &Sha256<F>) -> Self {
+        let access = |addr: F, i: usize, value: F, is_store: bool| Memory {
+            clk: row.clk,
+            addr: addr + F::from_canonical_usize(i),
+            value,
+            is_store: F::from_bool(is_store),
+            is_load: F::from_bool(!is_store),
+            ..Default::default()
+        };
+        let mut accesses = vec![];
+        if let Some(round) = row.round[..16].iter().position(Field::is_one) {
+            accesses.extend(
+                izip!(0.., row.w_bytes)
+                    .map(|(i, byte)| access(row.block_addr, 4 * round + i, byte, false)),
+            );
+        }
+        if row.round[0].is_one() {
+            accesses.extend(
+                izip!(0.., row.state_bytes).map(|(i, byte)| access(row.state_addr, i, byte, false)),
+            );
+        }
+        if row.is_output.is_one() {
+            accesses.extend(
+                izip!(0.., row.state_bytes).map(|(i, byte)| access(row.output_addr, i, byte, true)),
+            );
+        }
+        accesses
+    }
+}
+
+impl<F: RichField> From<&Blake3<F>> for Vec<Memory<F>> {
+    fn from(row: &Blake3<F>) -> Self {
+        let access = |addr: F, i: usize, value: F, is_store: bool| Memory {
+            clk: row.clk,
+            addr: addr + F::from_canonical_usize(i),
+            value,
+            is_store: F::from_bool(is_store),
+            is_load: F::from_bool(!is_store),
+            ..Default::default()
+        };
+        let mut accesses = vec![];
+        if row.step[0].is_one() {
+            accesses.extend(
+                izip!(0.., row.cv_bytes).map(|(i, byte)| access(row.cv_addr, i, byte, false)),
+            );
+            accesses.extend(
+                izip!(0.., row.block_bytes).map(|(i, byte)| access(row.block_addr, i, byte, false)),
+            );
+        }
+        if let Some(output_step) = row.output_step.iter().position(Field::is_one) {
+            // Output row `k` writes words `k` and `k + 4`.
+            accesses.extend(izip!(0.., row.output_bytes).map(|(i, byte)| {
+                let offset = 4 * output_step + 16 * (i / 4) + i % 4;
+                access(row.output_addr, offset, byte, true)
+            }));
+        }
+        accesses
+    }
+}
+
 impl<F: RichField> From<&StorageDevice<F>> for Option<Memory<F>> {
     fn from(val: &StorageDevice<F>) -> Self {
         (val.ops.is_memory_store).is_one().then(|| Memory {
@@ -198,10 +264,10 @@ pub fn rangecheck_looking() -> Vec<TableWithTypedOutput<RangeCheckCtl<Column>>>
 
 #[must_use]
 pub fn rangecheck_u8_looking() -> Vec<TableWithTypedOutput<RangeCheckCtl<Column>>> {
-    vec![MemoryTable::new(
-        RangeCheckCtl(MEM.value),
-        MEM.is_executed(),
-    )]
+    MEM.rangecheck_u8_columns()
+        .into_iter()
+        .map(|value| MemoryTable::new(RangeCheckCtl(value), MEM.is_executed()))
+        .collect()
 }
 
 columns_view_impl!(MemoryCtl);
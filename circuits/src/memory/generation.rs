@@ -5,6 +5,7 @@ use mozak_runner::instruction::Op;
 use mozak_runner::vm::Row;
 use plonky2::hash::hash_types::RichField;
 
+use crate::blake3::columns::Blake3;
 use crate::generation::MIN_TRACE_LENGTH;
 use crate::memory::columns::Memory;
 use crate::memory::trace::{get_memory_inst_addr, get_memory_inst_clk, get_memory_raw_value};
@@ -14,6 +15,7 @@ use crate::memory_zeroinit::columns::MemoryZeroInit;
 use crate::memoryinit::columns::MemoryInit;
 use crate::poseidon2_output_bytes::columns::Poseidon2OutputBytes;
 use crate::poseidon2_sponge::columns::Poseidon2Sponge;
+use crate::sha256::columns::Sha256;
 use crate::storage_device::columns::StorageDevice;
 
 /// Pad the memory trace to a power of 2.
@@ -111,6 +113,18 @@ pub fn transform_poseidon2_output_bytes<F: RichField>(
     output_bytes.iter().flat_map(Into::<Vec<Memory<F>>>::into)
 }
 
+pub fn transform_sha256<F: RichField>(
+    sha256_rows: &[Sha256<F>],
+) -> impl Iterator<Item = Memory<F>> + '_ {
+    sha256_rows.iter().flat_map(Into::<Vec<Memory<F>>>::into)
+}
+
+pub fn transform_blake3<F: RichField>(
+    blake3_rows: &[Blake3<F>],
+) -> impl Iterator<Item = Memory<F>> + '_ {
+    blake3_rows.iter().flat_map(Into::<Vec<Memory<F>>>::into)
+}
+
 /// Generates Memory trace from a memory full-word table.
 ///
 /// These need to be further interleaved with runtime memory trace generated
@@ -161,8 +175,12 @@ pub fn generate_memory_trace<F: RichField>(
     events_commitment_tape_rows: &[StorageDevice<F>],
     castlist_commitment_tape_rows: &[StorageDevice<F>],
     self_prog_id_tape_rows: &[StorageDevice<F>],
+    oracle_tape_rows: &[StorageDevice<F>],
+    private_tape_commitment_tape_rows: &[StorageDevice<F>],
     poseidon2_sponge_rows: &[Poseidon2Sponge<F>],
     poseidon2_output_bytes_rows: &[Poseidon2OutputBytes<F>],
+    sha256_rows: &[Sha256<F>],
+    blake3_rows: &[Blake3<F>],
 ) -> Vec<Memory<F>> {
     // `merged_trace` is address sorted combination of static and
     // dynamic memory trace components of program (ELF and execution)
@@ -180,8 +198,12 @@ pub fn generate_memory_trace<F: RichField>(
         transform_storage(events_commitment_tape_rows),
         transform_storage(castlist_commitment_tape_rows),
         transform_storage(self_prog_id_tape_rows),
+        transform_storage(oracle_tape_rows),
+        transform_storage(private_tape_commitment_tape_rows),
         transform_poseidon2_sponge(poseidon2_sponge_rows),
         transform_poseidon2_output_bytes(poseidon2_output_bytes_rows,),
+        transform_sha256(sha256_rows),
+        transform_blake3(blake3_rows),
     )
     .collect();
 
@@ -215,6 +237,7 @@ mod tests {
     use starky::verifier::verify_stark_proof;
 
     use super::pad_mem_trace;
+    use crate::blake3::generation::generate_blake3_trace;
     use crate::memory::columns::Memory;
     use crate::memory::stark::MemoryStark;
     use crate::memory::test_utils::memory_trace_test_case;
@@ -224,10 +247,12 @@ mod tests {
     use crate::memoryinit::generation::generate_memory_init_trace;
     use crate::poseidon2_output_bytes::generation::generate_poseidon2_output_bytes_trace;
     use crate::poseidon2_sponge::generation::generate_poseidon2_sponge_trace;
+    use crate::sha256::generation::generate_sha256_trace;
     use crate::stark::utils::trace_rows_to_poly_values;
     use crate::storage_device::generation::{
         generate_call_tape_trace, generate_cast_list_commitment_tape_trace,
         generate_event_tape_trace, generate_events_commitment_tape_trace,
+        generate_oracle_tape_trace, generate_private_tape_commitment_tape_trace,
         generate_private_tape_trace, generate_public_tape_trace, generate_self_prog_id_tape_trace,
     };
     use crate::test_utils::{fast_test_config, prep_table};
@@ -291,9 +316,15 @@ mod tests {
         let events_commitment_tape_rows = generate_events_commitment_tape_trace(&record.executed);
         let cast_list_commitment_tape_rows = generate_cast_list_commitment_tape_trace(&record.executed);
         let self_prog_id_tape_rows = generate_self_prog_id_tape_trace(&record.executed);
+        let oracle_tape_rows = generate_oracle_tape_trace(&record.executed);
+        let private_tape_commitment_tape_rows =
+            generate_private_tape_commitment_tape_trace(&record.executed);
         let poseidon2_sponge_trace = generate_poseidon2_sponge_trace(&record.executed);
         let poseidon2_output_bytes = generate_poseidon2_output_bytes_trace(&poseidon2_sponge_trace);
 
+        let sha256_rows = generate_sha256_trace(&record.executed);
+
+        let blake3_rows = generate_blake3_trace(&record.executed);
         let trace = super::generate_memory_trace::<GoldilocksField>(
             &record.executed,
             &memory_init,
@@ -307,8 +338,12 @@ mod tests {
             &events_commitment_tape_rows,
             &cast_list_commitment_tape_rows,
             &self_prog_id_tape_rows,
+            &oracle_tape_rows,
+            &private_tape_commitment_tape_rows,
             &poseidon2_sponge_trace,
             &poseidon2_output_bytes,
+            &sha256_rows,
+            &blake3_rows,
         );
         let last = u64::from(u32::MAX);
         assert_eq!(
@@ -383,8 +418,13 @@ mod tests {
         let cast_list_commitment_tape_rows =
             generate_cast_list_commitment_tape_trace(&[]);
         let self_prog_id_tape_rows = generate_self_prog_id_tape_trace(&[]);
+        let oracle_tape_rows = generate_oracle_tape_trace(&[]);
+        let private_tape_commitment_tape_rows =
+            generate_private_tape_commitment_tape_trace(&[]);
         let poseidon2_trace = generate_poseidon2_sponge_trace(&[]);
         let poseidon2_output_bytes = generate_poseidon2_output_bytes_trace(&poseidon2_trace);
+        let sha256_rows = generate_sha256_trace(&[]);
+        let blake3_rows = generate_blake3_trace(&[]);
         let trace = super::generate_memory_trace::<F>(
             &[],
             &memory_init,
@@ -398,8 +438,12 @@ mod tests {
             &events_commitment_tape_rows,
             &cast_list_commitment_tape_rows,
             &self_prog_id_tape_rows,
+            &oracle_tape_rows,
+            &private_tape_commitment_tape_rows,
             &poseidon2_trace,
             &poseidon2_output_bytes,
+            &sha256_rows,
+            &blake3_rows,
         );
 
         let last = u64::from(u32::MAX);
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use expr::{Expr, ExprBuilder, StarkFrameTyped};
+use expr::{ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
 use mozak_circuits_derive::StarkNameDisplay;
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
@@ -12,7 +12,7 @@ use starky::evaluation_frame::StarkFrame;
 use starky::stark::Stark;
 
 use crate::columns_view::{HasNamedColumns, NumberOfColumns};
-use crate::expr::{build_ext, build_packed, ConstraintBuilder};
+use crate::expr::{build_ext, build_packed};
 use crate::memory::columns::Memory;
 use crate::unstark::NoColumns;
 
@@ -31,10 +31,10 @@ const PUBLIC_INPUTS: usize = 0;
 
 fn generate_constraints<'a, T: Copy>(
     vars: &StarkFrameTyped<Memory<Expr<'a, T>>, NoColumns<Expr<'a, T>>>,
-) -> ConstraintBuilder<Expr<'a, T>> {
+) -> ConstraintCollector<'a, T> {
     let lv = vars.local_values;
     let nv = vars.next_values;
-    let mut constraints = ConstraintBuilder::default();
+    let mut constraints = ConstraintCollector::default();
 
     // Boolean constraints
     // -------------------
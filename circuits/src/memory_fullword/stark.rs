@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use expr::{Expr, ExprBuilder, StarkFrameTyped};
+use expr::{ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
 use itertools::izip;
 use mozak_circuits_derive::StarkNameDisplay;
 use plonky2::field::extension::{Extendable, FieldExtension};
@@ -13,7 +13,7 @@ use starky::evaluation_frame::StarkFrame;
 use starky::stark::Stark;
 
 use crate::columns_view::HasNamedColumns;
-use crate::expr::{build_ext, build_packed, ConstraintBuilder};
+use crate::expr::{build_ext, build_packed};
 use crate::memory_fullword::columns::{FullWordMemory, NUM_HW_MEM_COLS};
 use crate::unstark::NoColumns;
 
@@ -33,9 +33,9 @@ const PUBLIC_INPUTS: usize = 0;
 // Design description - https://docs.google.com/presentation/d/1J0BJd49BMQh3UR5TrOhe3k67plHxnohFtFVrMpDJ1oc/edit?usp=sharing
 fn generate_constraints<'a, T: Copy>(
     vars: &StarkFrameTyped<FullWordMemory<Expr<'a, T>>, NoColumns<Expr<'a, T>>>,
-) -> ConstraintBuilder<Expr<'a, T>> {
+) -> ConstraintCollector<'a, T> {
     let lv = vars.local_values;
-    let mut constraints = ConstraintBuilder::default();
+    let mut constraints = ConstraintCollector::default();
 
     constraints.always(lv.ops.is_store.is_binary());
     constraints.always(lv.ops.is_load.is_binary());
@@ -142,6 +142,15 @@ mod tests {
         }
     }
 
+    /// Accesses that straddle an aligned word, or wrap around the end of
+    /// the address space.
+    #[test]
+    fn prove_unaligned_mem_read_write_mozak() {
+        for offset in [0x0010_0003, u32::MAX - 1] {
+            prove_mem_read_write::<MozakStark<F, D>>(offset, 0, 0xAB);
+        }
+    }
+
     #[test]
     fn test_circuit() -> anyhow::Result<()> {
         type C = Poseidon2GoldilocksConfig;
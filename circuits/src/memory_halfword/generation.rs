@@ -70,6 +70,7 @@ mod tests {
     use mozak_runner::vm::ExecutionRecord;
     use plonky2::field::goldilocks_field::GoldilocksField;
 
+    use crate::blake3::generation::generate_blake3_trace;
     use crate::memory::generation::generate_memory_trace;
     use crate::memory_fullword::generation::generate_fullword_memory_trace;
     use crate::memory_halfword::generation::generate_halfword_memory_trace;
@@ -77,9 +78,11 @@ mod tests {
     use crate::memoryinit::generation::generate_memory_init_trace;
     use crate::poseidon2_output_bytes::generation::generate_poseidon2_output_bytes_trace;
     use crate::poseidon2_sponge::generation::generate_poseidon2_sponge_trace;
+    use crate::sha256::generation::generate_sha256_trace;
     use crate::storage_device::generation::{
         generate_call_tape_trace, generate_cast_list_commitment_tape_trace,
         generate_event_tape_trace, generate_events_commitment_tape_trace,
+        generate_oracle_tape_trace, generate_private_tape_commitment_tape_trace,
         generate_private_tape_trace, generate_public_tape_trace, generate_self_prog_id_tape_trace,
     };
     use crate::test_utils::prep_table;
@@ -171,9 +174,15 @@ mod tests {
         let cast_list_commitment_tape_rows =
             generate_cast_list_commitment_tape_trace(&record.executed);
         let self_prog_id_tape_rows = generate_self_prog_id_tape_trace(&record.executed);
+        let oracle_tape_rows = generate_oracle_tape_trace(&record.executed);
+        let private_tape_commitment_tape_rows =
+            generate_private_tape_commitment_tape_trace(&record.executed);
         let poseidon2_sponge_rows = generate_poseidon2_sponge_trace(&record.executed);
         let poseidon2_output_bytes = generate_poseidon2_output_bytes_trace(&poseidon2_sponge_rows);
 
+        let sha256_rows = generate_sha256_trace(&record.executed);
+
+        let blake3_rows = generate_blake3_trace(&record.executed);
         let trace = generate_memory_trace::<GoldilocksField>(
             &record.executed,
             &memory_init,
@@ -187,8 +196,12 @@ mod tests {
             &events_commitment_tape_rows,
             &cast_list_commitment_tape_rows,
             &self_prog_id_tape_rows,
+            &oracle_tape_rows,
+            &private_tape_commitment_tape_rows,
             &poseidon2_sponge_rows,
             &poseidon2_output_bytes,
+            &sha256_rows,
+            &blake3_rows,
         );
         let last = u64::from(u32::MAX);
         assert_eq!(trace,
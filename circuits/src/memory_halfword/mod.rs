@@ -2,6 +2,10 @@
 //! This Stark is used to store the VM Memory and
 //! constrains the load and store operations by the CPU
 //! using the CTL (cross table lookup) technique.
+//!
+//! Accesses need not be aligned: each byte of the halfword is looked up in the
+//! memory table at its own address, `addr + i`, wrapping around the end of the
+//! address space.
 
 pub mod columns;
 pub mod generation;
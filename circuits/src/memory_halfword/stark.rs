@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use expr::{Expr, ExprBuilder, StarkFrameTyped};
+use expr::{ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
 use mozak_circuits_derive::StarkNameDisplay;
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
@@ -12,7 +12,7 @@ use starky::evaluation_frame::StarkFrame;
 use starky::stark::Stark;
 
 use crate::columns_view::HasNamedColumns;
-use crate::expr::{build_ext, build_packed, ConstraintBuilder};
+use crate::expr::{build_ext, build_packed};
 use crate::memory_halfword::columns::{HalfWordMemory, NUM_HW_MEM_COLS};
 use crate::unstark::NoColumns;
 
@@ -29,9 +29,9 @@ impl<F, const D: usize> HasNamedColumns for HalfWordMemoryStark<F, D> {
 // Design description - https://docs.google.com/presentation/d/1J0BJd49BMQh3UR5TrOhe3k67plHxnohFtFVrMpDJ1oc/edit?usp=sharing
 fn generate_constraints<'a, T: Copy>(
     vars: &StarkFrameTyped<HalfWordMemory<Expr<'a, T>>, NoColumns<Expr<'a, T>>>,
-) -> ConstraintBuilder<Expr<'a, T>> {
+) -> ConstraintCollector<'a, T> {
     let lv = vars.local_values;
-    let mut constraints = ConstraintBuilder::default();
+    let mut constraints = ConstraintCollector::default();
 
     constraints.always(lv.ops.is_store.is_binary());
     constraints.always(lv.ops.is_load.is_binary());
@@ -144,6 +144,16 @@ mod tests {
             prove_mem_read_write::<MozakStark<F, D>>(offset, imm, content, is_unsigned);
         }
     }
+
+    /// Accesses that straddle an aligned halfword, or wrap around the end of
+    /// the address space.
+    #[test]
+    fn prove_unaligned_mem_read_write_mozak() {
+        for offset in [0x0010_0003, u32::MAX] {
+            prove_mem_read_write::<MozakStark<F, D>>(offset, 0, 0xAB, false);
+        }
+    }
+
     #[test]
     fn test_circuit() -> anyhow::Result<()> {
         type C = Poseidon2GoldilocksConfig;
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use expr::{Expr, ExprBuilder, StarkFrameTyped};
+use expr::{constraint, ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
 use mozak_circuits_derive::StarkNameDisplay;
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
@@ -13,7 +13,7 @@ use starky::stark::Stark;
 
 use super::columns::Add;
 use crate::columns_view::{HasNamedColumns, NumberOfColumns};
-use crate::expr::{build_ext, build_packed, ConstraintBuilder};
+use crate::expr::{build_ext, build_packed};
 
 #[derive(Copy, Clone, Default, StarkNameDisplay)]
 #[allow(clippy::module_name_repetitions)]
@@ -30,9 +30,9 @@ const PUBLIC_INPUTS: usize = 0;
 
 fn generate_constraints<'a, T: Copy, U>(
     vars: &StarkFrameTyped<Add<Expr<'a, T>>, Vec<U>>,
-) -> ConstraintBuilder<Expr<'a, T>> {
+) -> ConstraintCollector<'a, T> {
     let lv = vars.local_values;
-    let mut constraints = ConstraintBuilder::default();
+    let mut constraints = ConstraintCollector::default();
 
     let added = lv.op1_value + lv.op2_value + lv.inst.imm_value;
     let wrapped = added - (1 << 32);
@@ -40,7 +40,10 @@ fn generate_constraints<'a, T: Copy, U>(
     // Check: the resulting sum is wrapped if necessary.
     // As the result is range checked, this make the choice deterministic,
     // even for a malicious prover.
-    constraints.always((lv.dst_value - added) * (lv.dst_value - wrapped));
+    constraints.always(constraint!(
+        "sum is wrapped if necessary",
+        (lv.dst_value - added) * (lv.dst_value - wrapped)
+    ));
 
     constraints
 }
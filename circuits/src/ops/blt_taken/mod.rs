@@ -95,19 +95,21 @@ pub mod columns {
     pub fn lookup_for_program_rom() -> TableWithTypedOutput<ProgramRom<Column>> {
         let inst = COL_MAP.inst;
         // TODO: don't hardcode this.
-        let blt = 22;
+        let blt = 23;
         BltTakenTable::new(
             ProgramRom {
                 pc: inst.pc,
                 // Combine columns into a single column.
                 // - ops: This is an internal opcode, not the opcode from RISC-V, and can fit within
-                //   5 bits.
-                // - is_op1_signed and is_op2_signed: These fields occupy 1 bit each.
-                // - rs1_select, rs2_select, and rd_select: These fields require 5 bits each.
+                //   6 bits.
+                // - is_op1_signed and is_op2_signed: These fields occupy 1 bit each, and share a
+                //   single 6 bit field.
+                // - rs1_select, rs2_select, and rd_select: These fields require 6 bits each,
+                //   because the registers that hold CSRs come after the 32 general purpose ones.
                 // - imm_value: This field requires 32 bits.
-                // Therefore, the total bit requirement is 5 * 6 + 32 = 62 bits, which is less than
+                // Therefore, the total bit requirement is 6 * 5 + 32 = 62 bits, which is less than
                 // the size of the Goldilocks field.
-                // Note: The imm_value field, having more than 5 bits, must be positioned as the
+                // Note: The imm_value field, having more than 6 bits, must be positioned as the
                 // last column in the list to ensure the correct functioning of
                 // 'reduce_with_powers'.
                 inst_data: ColumnWithTypedInput::reduce_with_powers(
@@ -117,13 +119,12 @@ pub mod columns {
                         // TODO: use a struct here to name the components, and make IntoIterator,
                         // like we do with our stark tables.
                         ColumnWithTypedInput::constant(0),
-                        ColumnWithTypedInput::constant(0),
                         inst.rs1_selected,
                         inst.rs2_selected,
                         ColumnWithTypedInput::constant(0),
                         inst.imm_value,
                     ],
-                    1 << 5,
+                    1 << 6,
                 ),
             },
             COL_MAP.is_running,
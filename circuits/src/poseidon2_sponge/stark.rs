@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use expr::{Expr, ExprBuilder, StarkFrameTyped};
+use expr::{ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
 use mozak_circuits_derive::StarkNameDisplay;
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
@@ -15,7 +15,7 @@ use starky::stark::Stark;
 
 use super::columns::NUM_POSEIDON2_SPONGE_COLS;
 use crate::columns_view::HasNamedColumns;
-use crate::expr::{build_ext, build_packed, ConstraintBuilder};
+use crate::expr::{build_ext, build_packed};
 use crate::poseidon2_sponge::columns::Poseidon2Sponge;
 use crate::unstark::NoColumns;
 
@@ -37,7 +37,7 @@ fn generate_constraints<'a, T: Copy>(
     vars: &StarkFrameTyped<Poseidon2Sponge<Expr<'a, T>>, NoColumns<Expr<'a, T>>>,
     rate: usize,
     state_size: usize,
-) -> ConstraintBuilder<Expr<'a, T>> {
+) -> ConstraintCollector<'a, T> {
     // NOTE: clk and address will be used for CTL to CPU for is_init_permute rows
     // only, and not be used for permute rows.
     // For all non dummy rows we have CTL to Poseidon2 permute stark, with preimage
@@ -48,7 +48,7 @@ fn generate_constraints<'a, T: Copy>(
     let rate_scalar = i64::from(rate);
     let lv = vars.local_values;
     let nv = vars.next_values;
-    let mut constraints = ConstraintBuilder::default();
+    let mut constraints = ConstraintCollector::default();
 
     for val in [lv.ops.is_permute, lv.ops.is_init_permute, lv.gen_output] {
         constraints.always(val.is_binary());
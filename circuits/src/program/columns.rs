@@ -1,4 +1,5 @@
 use itertools::izip;
+use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
 
 use crate::columns_view::{columns_view_impl, make_col_map, NumberOfColumns};
@@ -19,8 +20,9 @@ pub struct ProgramRom<T> {
     pub pc: T,
     /// `inst_data` include:
     /// - ops: This is an internal opcode, not the opcode from RISC-V
-    /// - `is_op1_signed` and `is_op2_signed`
-    /// - `rs1_select`, `rs2_select`, and `rd_select`
+    /// - `is_op1_signed` and `is_op2_signed`, combined into a single field
+    /// - `rs1_select`, `rs2_select`, and `rd_select`, which include the
+    ///   registers that hold CSRs
     /// - `imm_value`
     pub inst_data: T,
 }
@@ -41,14 +43,13 @@ impl<F: RichField> From<Instruction<F>> for ProgramRom<F> {
             inst_data: reduce_with_powers(
                 [
                     ascending_sum(inst.ops),
-                    inst.is_op1_signed,
-                    inst.is_op2_signed,
+                    inst.is_op1_signed + inst.is_op2_signed * F::TWO,
                     inst.rs1_selected,
                     inst.rs2_selected,
                     inst.rd_selected,
                     inst.imm_value,
                 ],
-                1 << 5,
+                1 << 6,
             ),
         }
     }
@@ -10,8 +10,16 @@
 //! be reproduced on verifer's end. We can also reuse the challenges used for
 //! CTL to `combine`, since the procedure is preceded by commitment to trace
 //! polynomials already
+//!
+//! Tables declare their public sub tables with `XTable::public`, see
+//! `table_impl!` in [`crate::stark::mozak_stark`], and
+//! [`MozakStark`](crate::stark::mozak_stark::MozakStark) lists them.  The
+//! recursion circuit exposes their values as public inputs, at the positions
+//! given by [`PublicSubTable::public_input_ranges`].
 #![allow(clippy::module_name_repetitions)]
-use itertools::{iproduct, Itertools};
+use std::ops::Range;
+
+use itertools::{iproduct, izip, Itertools};
 use plonky2::field::extension::Extendable;
 use plonky2::field::polynomial::PolynomialValues;
 use plonky2::field::types::Field;
@@ -20,7 +28,7 @@ use plonky2::iop::target::Target;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2::plonk::plonk_common::reduce_with_powers_circuit;
 
-use crate::cross_table_lookup::{partial_sums, CtlData, CtlZData};
+use crate::cross_table_lookup::{partial_sums, CtlData, CtlLookup, CtlZData};
 use crate::stark::mozak_stark::{all_kind, Table, TableKind, TableKindArray};
 use crate::stark::permutation::challenge::{GrandProductChallenge, GrandProductChallengeSet};
 
@@ -47,6 +55,45 @@ impl PublicSubTable {
             * num_challenges
     }
 
+    /// Number of public inputs of a recursive proof taken up by the values of
+    /// `self`.
+    #[must_use]
+    pub fn num_public_inputs(&self) -> usize { self.num_rows * self.table.columns.len() }
+
+    /// Where the values of each of `public_sub_tables` are in the public
+    /// inputs of a recursive proof, given that they start at `start`.
+    ///
+    /// The recursion circuit registers the values table by table, and within
+    /// a table in the order the sub tables are declared, so that is not
+    /// necessarily the order of `public_sub_tables`.
+    #[must_use]
+    pub fn public_input_ranges(public_sub_tables: &[Self], start: usize) -> Vec<Range<usize>> {
+        let mut ranges = vec![0..0; public_sub_tables.len()];
+        let mut next = start;
+        for kind in all_kind!(|kind| kind).iter() {
+            for (range, sub_table) in izip!(&mut ranges, public_sub_tables) {
+                if sub_table.table.kind == *kind {
+                    *range = next..next + sub_table.num_public_inputs();
+                    next = range.end;
+                }
+            }
+        }
+        ranges
+    }
+
+    /// Reads the values of `self` from its part of the public inputs of a
+    /// recursive proof, see [`Self::public_input_ranges`].
+    #[must_use]
+    pub fn values_from_public_inputs<T: Copy>(
+        &self,
+        public_inputs: &[T],
+    ) -> PublicSubTableValues<T> {
+        public_inputs
+            .chunks(self.table.columns.len())
+            .map(<[T]>::to_vec)
+            .collect_vec()
+    }
+
     /// Get `PublicSubTableValues` corresponding to `self`
     #[must_use]
     pub fn get_values<F: Field>(
@@ -86,17 +133,12 @@ impl PublicSubTable {
         trace: &TableKindArray<Vec<PolynomialValues<F>>>,
         challenge: GrandProductChallenge<F>,
     ) -> CtlZData<F> {
-        let z = partial_sums(
-            &trace[self.table.kind],
-            &self.table.columns,
-            &self.table.filter_column,
-            challenge,
-        );
+        let lookups = vec![CtlLookup::untagged(&self.table)];
+        let z = partial_sums(&trace[self.table.kind], &lookups, challenge);
         CtlZData {
             z,
             challenge,
-            columns: self.table.columns.clone(),
-            filter_column: self.table.filter_column.clone(),
+            lookups,
         }
     }
 
@@ -1,12 +1,15 @@
+use mozak_circuits_derive::CtlColumns;
+
 use crate::columns_view::{columns_view_impl, make_col_map};
 use crate::cross_table_lookup::Column;
 use crate::stark::mozak_stark::{RangeCheckTable, TableWithTypedOutput};
 
 #[repr(C)]
-#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug, CtlColumns)]
 pub struct RangeCheckColumnsView<T> {
     /// The limbs (u8) of the u32 value to be range
     /// checked.
+    #[CtlColumns(looking = "rangecheck_u8")]
     pub limbs: [T; 4],
     pub multiplicity: T,
 }
@@ -32,7 +35,9 @@ pub struct RangeCheckCtl<T>(pub T);
 
 #[must_use]
 pub fn rangecheck_looking() -> Vec<TableWithTypedOutput<RangeCheckCtl<Column>>> {
-    (0..4)
-        .map(|limb| RangeCheckTable::new(RangeCheckCtl(COL_MAP.limbs[limb]), COL_MAP.multiplicity))
+    COL_MAP
+        .rangecheck_u8_columns()
+        .into_iter()
+        .map(|limb| RangeCheckTable::new(RangeCheckCtl(limb), COL_MAP.multiplicity))
         .collect()
 }
@@ -96,6 +96,7 @@ mod tests {
     use plonky2::field::types::Field;
 
     use super::*;
+    use crate::blake3::generation::generate_blake3_trace;
     use crate::cpu::generation::generate_cpu_trace;
     use crate::generation::MIN_TRACE_LENGTH;
     use crate::memory::generation::generate_memory_trace;
@@ -107,9 +108,11 @@ mod tests {
     use crate::poseidon2_output_bytes::generation::generate_poseidon2_output_bytes_trace;
     use crate::poseidon2_sponge::generation::generate_poseidon2_sponge_trace;
     use crate::register::generation::{generate_register_init_trace, generate_register_trace};
+    use crate::sha256::generation::generate_sha256_trace;
     use crate::storage_device::generation::{
         generate_call_tape_trace, generate_cast_list_commitment_tape_trace,
         generate_event_tape_trace, generate_events_commitment_tape_trace,
+        generate_oracle_tape_trace, generate_private_tape_commitment_tape_trace,
         generate_private_tape_trace, generate_public_tape_trace, generate_self_prog_id_tape_trace,
     };
 
@@ -147,8 +150,13 @@ mod tests {
         let cast_list_commitment_tape_rows =
             generate_cast_list_commitment_tape_trace(&record.executed);
         let self_prog_id_tape_rows = generate_self_prog_id_tape_trace(&record.executed);
+        let oracle_tape_rows = generate_oracle_tape_trace(&record.executed);
+        let private_tape_commitment_tape_rows =
+            generate_private_tape_commitment_tape_trace(&record.executed);
         let poseidon2_sponge_trace = generate_poseidon2_sponge_trace(&record.executed);
         let poseidon2_output_bytes = generate_poseidon2_output_bytes_trace(&poseidon2_sponge_trace);
+        let sha256_rows = generate_sha256_trace(&record.executed);
+        let blake3_rows = generate_blake3_trace(&record.executed);
         let memory_rows = generate_memory_trace::<F>(
             &record.executed,
             &memory_init,
@@ -162,8 +170,12 @@ mod tests {
             &events_commitment_tape_rows,
             &cast_list_commitment_tape_rows,
             &self_prog_id_tape_rows,
+            &oracle_tape_rows,
+            &private_tape_commitment_tape_rows,
             &poseidon2_sponge_trace,
             &poseidon2_output_bytes,
+            &sha256_rows,
+            &blake3_rows,
         );
         let register_init = generate_register_init_trace(&record);
         let (_, _, register_rows) = generate_register_trace(
@@ -171,6 +183,8 @@ mod tests {
             &add_rows,
             &blt_rows,
             &poseidon2_sponge_trace,
+            &sha256_rows,
+            &blake3_rows,
             &private_tape_rows,
             &public_tape_rows,
             &call_tape_rows,
@@ -178,6 +192,8 @@ mod tests {
             &events_commitment_tape_rows,
             &cast_list_commitment_tape_rows,
             &self_prog_id_tape_rows,
+            &oracle_tape_rows,
+            &private_tape_commitment_tape_rows,
             &register_init,
         );
         let trace = generate_rangecheck_trace::<F>(
@@ -1,6 +1,7 @@
 use itertools::Itertools;
 use plonky2::hash::hash_types::RichField;
 
+use crate::cpu::columns::CpuState;
 use crate::memory::columns::Memory;
 use crate::rangecheck::columns::RangeCheckColumnsView;
 use crate::rangecheck::generation::extract_with_mul;
@@ -14,6 +15,7 @@ use crate::stark::mozak_stark::{Lookups, RangeCheckU8LookupTable, TableKind};
 pub(crate) fn generate_rangecheck_u8_trace<F: RichField>(
     rangecheck_trace: &[RangeCheckColumnsView<F>],
     memory_trace: &[Memory<F>],
+    cpu_trace: &[CpuState<F>],
 ) -> Vec<RangeCheckU8<F>> {
     RangeCheckU8LookupTable::lookups()
         .looking_tables
@@ -21,6 +23,7 @@ pub(crate) fn generate_rangecheck_u8_trace<F: RichField>(
         .flat_map(|looking_table| match looking_table.kind {
             TableKind::RangeCheck => extract_with_mul(rangecheck_trace, &looking_table),
             TableKind::Memory => extract_with_mul(memory_trace, &looking_table),
+            TableKind::Cpu => extract_with_mul(cpu_trace, &looking_table),
             // We are trying to build this table, so we have to ignore it here.
             TableKind::RangeCheckU8 => vec![],
             other => unimplemented!("Can't range check {other:?} tables"),
@@ -44,6 +47,7 @@ mod tests {
     use plonky2::field::types::{Field, PrimeField64};
 
     use super::*;
+    use crate::blake3::generation::generate_blake3_trace;
     use crate::cpu::generation::generate_cpu_trace;
     use crate::memory::generation::generate_memory_trace;
     use crate::memory_fullword::generation::generate_fullword_memory_trace;
@@ -55,9 +59,11 @@ mod tests {
     use crate::poseidon2_sponge::generation::generate_poseidon2_sponge_trace;
     use crate::rangecheck::generation::generate_rangecheck_trace;
     use crate::register::generation::{generate_register_init_trace, generate_register_trace};
+    use crate::sha256::generation::generate_sha256_trace;
     use crate::storage_device::generation::{
         generate_call_tape_trace, generate_cast_list_commitment_tape_trace,
         generate_event_tape_trace, generate_events_commitment_tape_trace,
+        generate_oracle_tape_trace, generate_private_tape_commitment_tape_trace,
         generate_private_tape_trace, generate_public_tape_trace, generate_self_prog_id_tape_trace,
     };
 
@@ -95,8 +101,13 @@ mod tests {
         let cast_list_commitment_tape_rows =
             generate_cast_list_commitment_tape_trace(&record.executed);
         let self_prog_id_tape_rows = generate_self_prog_id_tape_trace(&record.executed);
+        let oracle_tape_rows = generate_oracle_tape_trace(&record.executed);
+        let private_tape_commitment_tape_rows =
+            generate_private_tape_commitment_tape_trace(&record.executed);
         let poseidon2_sponge_trace = generate_poseidon2_sponge_trace(&record.executed);
         let poseidon2_output_bytes = generate_poseidon2_output_bytes_trace(&poseidon2_sponge_trace);
+        let sha256_rows = generate_sha256_trace(&record.executed);
+        let blake3_rows = generate_blake3_trace(&record.executed);
         let memory_rows = generate_memory_trace::<F>(
             &record.executed,
             &memory_init,
@@ -110,8 +121,12 @@ mod tests {
             &events_commitment_tape_rows,
             &cast_list_commitment_tape_rows,
             &self_prog_id_tape_rows,
+            &oracle_tape_rows,
+            &private_tape_commitment_tape_rows,
             &poseidon2_sponge_trace,
             &poseidon2_output_bytes,
+            &sha256_rows,
+            &blake3_rows,
         );
         let register_init = generate_register_init_trace(&record);
         let (_, _, register_rows) = generate_register_trace(
@@ -119,6 +134,8 @@ mod tests {
             &add_rows,
             &blt_rows,
             &poseidon2_sponge_trace,
+            &sha256_rows,
+            &blake3_rows,
             &private_tape,
             &public_tape,
             &call_tape_rows,
@@ -126,6 +143,8 @@ mod tests {
             &events_commitment_tape_rows,
             &cast_list_commitment_tape_rows,
             &self_prog_id_tape_rows,
+            &oracle_tape_rows,
+            &private_tape_commitment_tape_rows,
             &register_init,
         );
         let rangecheck_rows = generate_rangecheck_trace::<F>(
@@ -136,7 +155,7 @@ mod tests {
             &register_rows,
         );
 
-        let trace = generate_rangecheck_u8_trace(&rangecheck_rows, &memory_rows);
+        let trace = generate_rangecheck_u8_trace(&rangecheck_rows, &memory_rows, &cpu_rows);
 
         for row in &trace {
             // TODO(bing): more comprehensive test once we rip out the old trace gen logic.
@@ -67,9 +67,9 @@ make_col_map!(Register);
 #[repr(C)]
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub struct Register<T> {
-    /// The register 'address' that indexes into 1 of our 32 registers.
-    /// Should only take values 0-31, so this column should be a running sum
-    /// from 0 to 31 (inclusive). Note that this isn't the same as memory
+    /// The register 'address' that indexes into 1 of our 34 registers.
+    /// Should only take values 0-33, so this column should be a running sum
+    /// from 0 to 33 (inclusive). Note that this isn't the same as memory
     /// address.
     pub addr: T,
 
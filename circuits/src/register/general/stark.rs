@@ -1,7 +1,8 @@
 use std::marker::PhantomData;
 
-use expr::{Expr, ExprBuilder, StarkFrameTyped};
+use expr::{ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
 use mozak_circuits_derive::StarkNameDisplay;
+use mozak_runner::csr::REG_SSCRATCH;
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
 use plonky2::hash::hash_types::RichField;
@@ -13,7 +14,7 @@ use starky::stark::Stark;
 
 use super::columns::Register;
 use crate::columns_view::{HasNamedColumns, NumberOfColumns};
-use crate::expr::{build_ext, build_packed, ConstraintBuilder};
+use crate::expr::{build_ext, build_packed};
 use crate::unstark::NoColumns;
 
 #[derive(Clone, Copy, Default, StarkNameDisplay)]
@@ -39,16 +40,17 @@ const PUBLIC_INPUTS: usize = 0;
 /// 3) Only rd changes.
 /// 4) Address changes only when `nv.is_init` == 1.
 /// 5) Address either stays the same or increments by 1.
-/// 6) Addresses go from 1 to 31.  Address 0 is handled by `RegisterZeroStark`.
+/// 6) Addresses go from 1 to 33: the general purpose registers, followed by the
+///    ones that hold CSRs.  Address 0 is handled by `RegisterZeroStark`.
 ///
 /// For more details, refer to the [Notion
 /// document](https://www.notion.so/0xmozak/Register-File-STARK-62459d68aea648a0abf4e97aa0093ea2).
 fn generate_constraints<'a, T: Copy>(
     vars: &StarkFrameTyped<Register<Expr<'a, T>>, NoColumns<Expr<'a, T>>>,
-) -> ConstraintBuilder<Expr<'a, T>> {
+) -> ConstraintCollector<'a, T> {
     let lv = vars.local_values;
     let nv = vars.next_values;
-    let mut constraints = ConstraintBuilder::default();
+    let mut constraints = ConstraintCollector::default();
 
     // Constraint 1: filter columns take 0 or 1 values only.
     constraints.always(lv.ops.is_init.is_binary());
@@ -78,9 +80,9 @@ fn generate_constraints<'a, T: Copy>(
     // Constraint 5: Address either stays the same or increments by 1.
     constraints.transition((nv.addr - lv.addr) * (nv.addr - lv.addr - 1));
 
-    // Constraint 6: addresses go from 1 to 31.
+    // Constraint 6: addresses go from 1 to 33.
     constraints.first_row(lv.addr - 1);
-    constraints.last_row(lv.addr - 31);
+    constraints.last_row(lv.addr - i64::from(REG_SSCRATCH));
 
     constraints
 }
@@ -1,9 +1,11 @@
 use std::ops::Index;
 
 use itertools::Itertools;
+use mozak_runner::csr::REG_SSCRATCH;
 use mozak_runner::vm::ExecutionRecord;
 use plonky2::hash::hash_types::RichField;
 
+use crate::blake3::columns::Blake3;
 use crate::cpu::columns::CpuState;
 use crate::ops;
 use crate::poseidon2_sponge::columns::Poseidon2Sponge;
@@ -12,6 +14,7 @@ use crate::register::init::columns::RegisterInit;
 use crate::register::zero_read::columns::RegisterZeroRead;
 use crate::register::zero_write::columns::RegisterZeroWrite;
 use crate::register::RegisterCtl;
+use crate::sha256::columns::Sha256;
 use crate::stark::mozak_stark::{Lookups, RegisterLookups, Table, TableKind};
 use crate::storage_device::columns::StorageDevice;
 use crate::utils::{pad_trace_with_default, pad_trace_with_last, pad_trace_with_row};
@@ -89,6 +92,8 @@ pub fn generate_register_trace<F: RichField>(
     add_trace: &[ops::add::columns::Add<F>],
     blt_trace: &[ops::blt_taken::columns::BltTaken<F>],
     poseidon2_sponge: &[Poseidon2Sponge<F>],
+    sha256: &[Sha256<F>],
+    blake3: &[Blake3<F>],
     mem_private: &[StorageDevice<F>],
     mem_public: &[StorageDevice<F>],
     mem_call_tape: &[StorageDevice<F>],
@@ -96,6 +101,8 @@ pub fn generate_register_trace<F: RichField>(
     mem_events_commitment_tape: &[StorageDevice<F>],
     mem_cast_list_commitment_tape: &[StorageDevice<F>],
     mem_self_prog_id_tape: &[StorageDevice<F>],
+    mem_oracle_tape: &[StorageDevice<F>],
+    mem_private_tape_commitment_tape: &[StorageDevice<F>],
     reg_init: &[RegisterInit<F>],
 ) -> (
     Vec<RegisterZeroRead<F>>,
@@ -118,8 +125,13 @@ pub fn generate_register_trace<F: RichField>(
             TableKind::CastListCommitmentTape =>
                 extract(mem_cast_list_commitment_tape, &looking_table),
             TableKind::SelfProgIdTape => extract(mem_self_prog_id_tape, &looking_table),
+            TableKind::OracleTape => extract(mem_oracle_tape, &looking_table),
+            TableKind::PrivateTapeCommitmentTape =>
+                extract(mem_private_tape_commitment_tape, &looking_table),
             TableKind::RegisterInit => extract(reg_init, &looking_table),
             TableKind::Poseidon2Sponge => extract(poseidon2_sponge, &looking_table),
+            TableKind::Sha256 => extract(sha256, &looking_table),
+            TableKind::Blake3 => extract(blake3, &looking_table),
             // We are trying to build the Register tables, so we don't have the values to extract.
             TableKind::Register | TableKind::RegisterZeroRead | TableKind::RegisterZeroWrite =>
                 vec![],
@@ -162,7 +174,7 @@ pub fn generate_register_init_trace<F: RichField>(
         .map_or(&record.last_state, |row| &row.state);
 
     pad_trace_with_last(
-        (1..32)
+        (1..=REG_SSCRATCH)
             .map(|i| RegisterInit {
                 reg_addr: F::from_canonical_u8(i),
                 value: F::from_canonical_u32(first_state.get_register_value(i)),
@@ -179,11 +191,14 @@ mod tests {
     use plonky2::field::types::Field;
 
     use super::*;
+    use crate::blake3::generation::generate_blake3_trace;
     use crate::cpu::generation::generate_cpu_trace;
     use crate::poseidon2_sponge;
+    use crate::sha256::generation::generate_sha256_trace;
     use crate::storage_device::generation::{
         generate_call_tape_trace, generate_cast_list_commitment_tape_trace,
         generate_event_tape_trace, generate_events_commitment_tape_trace,
+        generate_oracle_tape_trace, generate_private_tape_commitment_tape_trace,
         generate_private_tape_trace, generate_public_tape_trace, generate_self_prog_id_tape_trace,
     };
     use crate::test_utils::prep_table;
@@ -232,8 +247,13 @@ mod tests {
         let cast_list_commitment_tape_rows =
             generate_cast_list_commitment_tape_trace(&record.executed);
         let self_prog_id_tape_rows = generate_self_prog_id_tape_trace(&record.executed);
+        let oracle_tape_rows = generate_oracle_tape_trace(&record.executed);
+        let private_tape_commitment_tape_rows =
+            generate_private_tape_commitment_tape_trace(&record.executed);
         let poseidon2_sponge_trace =
             poseidon2_sponge::generation::generate_poseidon2_sponge_trace(&record.executed);
+        let sha256_rows = generate_sha256_trace(&record.executed);
+        let blake3_rows = generate_blake3_trace(&record.executed);
 
         let register_init = generate_register_init_trace(&record);
         let (_, _, trace) = generate_register_trace(
@@ -241,6 +261,8 @@ mod tests {
             &add_rows,
             &blt_rows,
             &poseidon2_sponge_trace,
+            &sha256_rows,
+            &blake3_rows,
             &private_tape,
             &public_tape,
             &call_tape,
@@ -248,6 +270,8 @@ mod tests {
             &events_commitment_tape_rows,
             &cast_list_commitment_tape_rows,
             &self_prog_id_tape_rows,
+            &oracle_tape_rows,
+            &private_tape_commitment_tape_rows,
             &register_init,
         );
 
@@ -290,10 +314,10 @@ mod tests {
         // Finally, append the above trace with the extra init rows with unused
         // registers.
         let mut final_init_rows = prep_table(
-            (13..33)
+            (13..65)
                 .map(|i|
                 // addr     value clk  is_init is_read is_write
-                [ i.min(31),   0,   0,       1,      0,       0])
+                [ i.min(33),   0,   0,       1,      0,       0])
                 .collect(),
         );
         expected_trace.append(&mut final_init_rows);
@@ -307,14 +331,14 @@ mod tests {
         });
 
         // Check the paddings. Important checks:
-        // 1) Padded address = 31, since it's in the last row.
+        // 1) Padded address = 33, since it's in the last row.
         // 2) is_used = is_init + is_read + is_write = 0, for CTL
         // with the `RegisterInitStark`.
         (expected_trace.len()..trace.len()).for_each(|i| {
             assert_eq!(
                 trace[i],
                 Register {
-                    addr: F::from_canonical_u8(31),
+                    addr: F::from_canonical_u8(REG_SSCRATCH),
                     ..Default::default()
                 },
                 "Padding is wrong at row {i}"
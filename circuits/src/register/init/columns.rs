@@ -9,9 +9,9 @@ make_col_map!(RegisterInit);
 #[repr(C)]
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub struct RegisterInit<T> {
-    /// The 'address' that indexes into 1 of our 32 registers. Should only
-    /// take values 0-31, so this column should be a running sum
-    /// from 0 to 31 (inclusive).
+    /// The 'address' that indexes into 1 of our 34 registers. Should only
+    /// take values 0-33, so this column should be a running sum
+    /// from 0 to 33 (inclusive).
     pub reg_addr: T,
 
     /// Value of the register.
@@ -1,7 +1,8 @@
 //! This module contains the **`Register` STARK Table**.
 //!
 //! This module emulates the 32 registers found in a RISC-V core,
-//! indexed by addresses 0..=31 instead.
+//! indexed by addresses 0..=31 instead.  They are followed by the registers
+//! that hold CSRs, at addresses 32 and 33, see [`mozak_runner::csr`].
 //!
 //! This implementation is very similar to that of the
 //! [Memory STARK](crate::memory)
@@ -0,0 +1,156 @@
+use core::iter::Sum;
+
+use itertools::{chain, izip};
+use mozak_sdk::core::reg_abi::{REG_A1, REG_A2, REG_A3};
+use mozak_sdk::core::sha256::{ROUNDS, STATE_BYTES};
+
+use crate::columns_view::{columns_view_impl, make_col_map, NumberOfColumns};
+use crate::cross_table_lookup::ColumnWithTypedInput;
+use crate::linear_combination::Column;
+use crate::memory::columns::MemoryCtl;
+use crate::register::RegisterCtl;
+use crate::stark::mozak_stark::{Sha256Table, TableWithTypedOutput};
+
+/// The number of bits in a word.
+pub const WORD_BITS: usize = 32;
+
+/// One row per round of the SHA-256 compression function, followed by one
+/// row that adds the working variables to the initial state.
+///
+/// Words are little-endian bits where the constraints need their bits, and
+/// plain values otherwise.
+#[repr(C)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct Sha256<T> {
+    pub clk: T,
+    pub state_addr: T,
+    pub block_addr: T,
+    pub output_addr: T,
+    /// One-hot encoding of the round this row computes, all zero on the
+    /// output row and on padding.
+    pub round: [T; ROUNDS],
+    /// Set on the row after the last round.
+    pub is_output: T,
+    /// The state the compression started from.
+    pub initial: [T; 8],
+    pub a: [T; WORD_BITS],
+    pub b: [T; WORD_BITS],
+    pub c: [T; WORD_BITS],
+    pub d: T,
+    pub e: [T; WORD_BITS],
+    pub f: [T; WORD_BITS],
+    pub g: [T; WORD_BITS],
+    pub h: T,
+    pub big_sigma0: T,
+    pub big_sigma1: T,
+    pub ch: T,
+    pub maj: T,
+    /// What overflows 32 bits when computing the next `a` and `e`.
+    pub carry_a: [T; 3],
+    pub carry_e: [T; 3],
+    /// The message schedule, starting with the word of this round.
+    pub w: [T; 16],
+    /// The bits of `w[1]` and `w[14]`, for the next word of the schedule.
+    pub w1: [T; WORD_BITS],
+    pub w14: [T; WORD_BITS],
+    pub small_sigma0: T,
+    pub small_sigma1: T,
+    pub carry_w: [T; 2],
+    /// The big-endian bytes of `w[0]`, read from the block in the first 16
+    /// rounds.
+    pub w_bytes: [T; 4],
+    /// The bytes of the initial state on the first round, and of the new
+    /// state on the output row.
+    pub state_bytes: [T; STATE_BYTES],
+    pub carry_output: [T; 8],
+}
+
+columns_view_impl!(Sha256);
+make_col_map!(Sha256);
+
+pub const NUM_SHA256_COLS: usize = Sha256::<()>::NUMBER_OF_COLUMNS;
+
+impl<T: Copy + Sum> Sha256<T> {
+    pub fn is_round(&self) -> T { self.round.into_iter().sum() }
+}
+
+columns_view_impl!(Sha256Ctl);
+#[repr(C)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct Sha256Ctl<T> {
+    pub clk: T,
+}
+
+#[must_use]
+pub fn lookup_for_cpu() -> TableWithTypedOutput<Sha256Ctl<Column>> {
+    Sha256Table::new(Sha256Ctl { clk: COL_MAP.clk }, COL_MAP.round[0])
+}
+
+#[must_use]
+pub fn register_looking() -> Vec<TableWithTypedOutput<RegisterCtl<Column>>> {
+    let is_read = ColumnWithTypedInput::constant(1);
+    [
+        (COL_MAP.state_addr, REG_A1),
+        (COL_MAP.block_addr, REG_A2),
+        (COL_MAP.output_addr, REG_A3),
+    ]
+    .into_iter()
+    .map(|(value, reg)| {
+        Sha256Table::new(
+            RegisterCtl {
+                clk: COL_MAP.clk,
+                op: is_read,
+                value,
+                addr: ColumnWithTypedInput::constant(reg.into()),
+            },
+            COL_MAP.round[0],
+        )
+    })
+    .collect()
+}
+
+/// Reads the block four bytes per round over the first 16 rounds, reads the
+/// initial state on the first round, and writes the new state on the output
+/// row.
+pub fn lookup_for_memory() -> impl Iterator<Item = TableWithTypedOutput<MemoryCtl<Column>>> {
+    let round = ColumnWithTypedInput::ascending_sum(COL_MAP.round);
+    let reads_block: ColumnWithTypedInput<_> = COL_MAP.round[..16].iter().sum();
+    chain!(
+        izip!(0.., COL_MAP.w_bytes).map(move |(i, value)| {
+            Sha256Table::new(
+                MemoryCtl {
+                    clk: COL_MAP.clk,
+                    is_store: ColumnWithTypedInput::constant(0),
+                    is_load: ColumnWithTypedInput::constant(1),
+                    value,
+                    addr: COL_MAP.block_addr + round * 4 + i,
+                },
+                reads_block,
+            )
+        }),
+        izip!(0.., COL_MAP.state_bytes).map(|(i, value)| {
+            Sha256Table::new(
+                MemoryCtl {
+                    clk: COL_MAP.clk,
+                    is_store: ColumnWithTypedInput::constant(0),
+                    is_load: ColumnWithTypedInput::constant(1),
+                    value,
+                    addr: COL_MAP.state_addr + i,
+                },
+                COL_MAP.round[0],
+            )
+        }),
+        izip!(0.., COL_MAP.state_bytes).map(|(i, value)| {
+            Sha256Table::new(
+                MemoryCtl {
+                    clk: COL_MAP.clk,
+                    is_store: ColumnWithTypedInput::constant(1),
+                    is_load: ColumnWithTypedInput::constant(0),
+                    value,
+                    addr: COL_MAP.output_addr + i,
+                },
+                COL_MAP.is_output,
+            )
+        }),
+    )
+}
@@ -0,0 +1,155 @@
+use mozak_runner::sha256::Entry;
+use mozak_runner::vm::Row;
+use mozak_sdk::core::sha256::{
+    big_sigma0, big_sigma1, ch, compress, maj, message_schedule, round, small_sigma0, small_sigma1,
+    words, K, ROUNDS, STATE_BYTES,
+};
+use plonky2::hash::hash_types::RichField;
+
+use crate::sha256::columns::Sha256;
+use crate::utils::{from_u32, pad_trace_with_default};
+
+pub fn filter<F: RichField>(step_rows: &[Row<F>]) -> impl Iterator<Item = (u64, &Entry)> {
+    step_rows
+        .iter()
+        .filter_map(|row| Some((row.state.clk, row.aux.sha256.as_ref()?)))
+}
+
+/// The little-endian bits of `x`.
+fn bits<F: RichField, const N: usize>(x: impl Into<u64>) -> [F; N] {
+    let x = x.into();
+    core::array::from_fn(|i| F::from_bool((x >> i) & 1 == 1))
+}
+
+/// The bits of what the sum of `terms` overflows 32 bits by.
+fn carry<F: RichField, const N: usize>(terms: &[u32]) -> [F; N] {
+    bits(terms.iter().copied().map(u64::from).sum::<u64>() >> 32)
+}
+
+/// One row per round, and the output row.
+fn unroll<F: RichField>(clk: u64, entry: &Entry) -> Vec<Sha256<F>> {
+    let initial: [u32; 8] = words(&entry.state);
+    // Each row holds the next 16 words of the schedule, so the output row
+    // needs 16 words past the last round.
+    let schedule: [u32; ROUNDS + 16] = message_schedule(&entry.block);
+    let output = compress(&entry.state, &entry.block);
+
+    let mut working = initial;
+    (0..=ROUNDS)
+        .map(|t| {
+            let [a, b, c, d, e, f, g, h] = working;
+            let w = &schedule[t..t + 16];
+            let k = K.get(t).copied();
+            let state_bytes = match t {
+                0 => entry.state,
+                ROUNDS => output,
+                _ => [0; STATE_BYTES],
+            };
+            let row = Sha256 {
+                clk: F::from_canonical_u64(clk),
+                state_addr: from_u32(entry.state_addr),
+                block_addr: from_u32(entry.block_addr),
+                output_addr: from_u32(entry.output_addr),
+                round: core::array::from_fn(|i| F::from_bool(i == t)),
+                is_output: F::from_bool(t == ROUNDS),
+                initial: initial.map(from_u32),
+                a: bits(a),
+                b: bits(b),
+                c: bits(c),
+                d: from_u32(d),
+                e: bits(e),
+                f: bits(f),
+                g: bits(g),
+                h: from_u32(h),
+                big_sigma0: from_u32(big_sigma0(a)),
+                big_sigma1: from_u32(big_sigma1(e)),
+                ch: from_u32(ch(e, f, g)),
+                maj: from_u32(maj(a, b, c)),
+                carry_a: k.map_or_else(Default::default, |k| {
+                    carry(&[
+                        h,
+                        big_sigma1(e),
+                        ch(e, f, g),
+                        k,
+                        w[0],
+                        big_sigma0(a),
+                        maj(a, b, c),
+                    ])
+                }),
+                carry_e: k.map_or_else(Default::default, |k| {
+                    carry(&[d, h, big_sigma1(e), ch(e, f, g), k, w[0]])
+                }),
+                w: core::array::from_fn(|i| from_u32(w[i])),
+                w1: bits(w[1]),
+                w14: bits(w[14]),
+                small_sigma0: from_u32(small_sigma0(w[1])),
+                small_sigma1: from_u32(small_sigma1(w[14])),
+                carry_w: carry(&[small_sigma1(w[14]), w[9], small_sigma0(w[1]), w[0]]),
+                w_bytes: w[0].to_be_bytes().map(F::from_canonical_u8),
+                state_bytes: state_bytes.map(F::from_canonical_u8),
+                carry_output: core::array::from_fn(|i| {
+                    F::from_bool(t == ROUNDS && initial[i].checked_add(working[i]).is_none())
+                }),
+            };
+            if let Some(k) = k {
+                working = round(working, k, w[0]);
+            }
+            row
+        })
+        .collect()
+}
+
+#[must_use]
+pub fn generate_sha256_trace<F: RichField>(step_rows: &[Row<F>]) -> Vec<Sha256<F>> {
+    let trace = pad_trace_with_default(
+        filter(step_rows)
+            .flat_map(|(clk, entry)| unroll(clk, entry))
+            .collect(),
+    );
+    log::trace!("SHA-256 trace {:#?}", trace);
+    trace
+}
+
+#[cfg(test)]
+mod tests {
+    use mozak_sdk::core::sha256::{compress, BLOCK_BYTES, INITIAL_STATE, ROUNDS};
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::Field;
+
+    use super::generate_sha256_trace;
+    use crate::generation::MIN_TRACE_LENGTH;
+    use crate::sha256::columns::Sha256;
+    use crate::test_utils::{create_sha256_test, Sha256Test};
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn generate_sha256_trace_rows() {
+        let block = [0x5a; BLOCK_BYTES];
+        let (_program, record) = create_sha256_test(&[Sha256Test {
+            state: INITIAL_STATE,
+            block,
+            state_addr: 1024,
+            block_addr: 2048,
+            output_addr: 4096,
+        }]);
+        let trace: Vec<Sha256<F>> = generate_sha256_trace(&record.executed);
+
+        assert_eq!(trace.len(), (ROUNDS + 1).next_power_of_two());
+        let output = trace[ROUNDS];
+        assert!(output.is_output.is_one());
+        assert_eq!(
+            output.state_bytes,
+            compress(&INITIAL_STATE, &block).map(F::from_canonical_u8)
+        );
+        assert!(trace[ROUNDS + 1..]
+            .iter()
+            .all(|row| row.is_round().is_zero() && row.is_output.is_zero()));
+    }
+
+    #[test]
+    fn generate_sha256_trace_with_dummy() {
+        let trace: Vec<Sha256<F>> = generate_sha256_trace(&[]);
+        assert_eq!(trace.len(), MIN_TRACE_LENGTH);
+    }
+}
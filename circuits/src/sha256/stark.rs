@@ -0,0 +1,355 @@
+use std::marker::PhantomData;
+
+use expr::{ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
+use itertools::{chain, izip};
+use mozak_circuits_derive::StarkNameDisplay;
+use mozak_sdk::core::sha256::{K, ROUNDS};
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use starky::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use starky::evaluation_frame::StarkFrame;
+use starky::stark::Stark;
+
+use super::columns::{Sha256, NUM_SHA256_COLS, WORD_BITS};
+use crate::columns_view::HasNamedColumns;
+use crate::expr::{build_ext, build_packed};
+use crate::unstark::NoColumns;
+
+#[derive(Copy, Clone, Default, StarkNameDisplay)]
+#[allow(clippy::module_name_repetitions)]
+pub struct Sha256Stark<F, const D: usize> {
+    pub _f: PhantomData<F>,
+}
+
+impl<F, const D: usize> HasNamedColumns for Sha256Stark<F, D> {
+    type Columns = Sha256<F>;
+}
+
+const COLUMNS: usize = NUM_SHA256_COLS;
+const PUBLIC_INPUTS: usize = 0;
+
+/// A move of the bits of a word to the right.
+#[derive(Clone, Copy)]
+enum Right {
+    Rotate(usize),
+    Shift(usize),
+}
+
+/// Packs little-endian `bits` into a word.
+fn pack<'a, T>(bits: [Expr<'a, T>; WORD_BITS]) -> Expr<'a, T> { Expr::reduce_with_powers(bits, 2) }
+
+/// Packs big-endian `bytes` into a word.
+fn pack_bytes<'a, T: Copy>(bytes: &[Expr<'a, T>]) -> Expr<'a, T> {
+    Expr::reduce_with_powers(bytes.iter().rev().copied(), 256)
+}
+
+/// Xor of bits, as a polynomial of degree `bits.len()`.
+fn xor<'a, T: Copy>(bits: &[Expr<'a, T>]) -> Expr<'a, T> {
+    match *bits {
+        [x, y] => x + y - 2 * x * y,
+        [x, y, z] => x + y + z - 2 * (x * y + y * z + z * x) + 4 * x * y * z,
+        _ => unreachable!("only two or three bits are xored"),
+    }
+}
+
+/// The xor of the moves of `x`, eg `Σ0` and `σ0` of SHA-256.
+fn xor_of_moves<'a, T: Copy>(x: [Expr<'a, T>; WORD_BITS], moves: [Right; 3]) -> Expr<'a, T> {
+    Expr::reduce_with_powers(
+        (0..WORD_BITS).map(|i| {
+            let bits: Vec<_> = moves
+                .iter()
+                .filter_map(|&right| match right {
+                    Right::Rotate(n) => Some(x[(i + n) % WORD_BITS]),
+                    Right::Shift(n) => x.get(i + n).copied(),
+                })
+                .collect();
+            xor(&bits)
+        }),
+        2,
+    )
+}
+
+#[allow(clippy::too_many_lines)]
+fn generate_constraints<'a, T: Copy>(
+    vars: &StarkFrameTyped<Sha256<Expr<'a, T>>, NoColumns<Expr<'a, T>>>,
+) -> ConstraintCollector<'a, T> {
+    let lv = vars.local_values;
+    let nv = vars.next_values;
+    let mut constraints = ConstraintCollector::default();
+
+    for bit in chain!(
+        lv.round,
+        [lv.is_output, lv.is_round() + lv.is_output],
+        lv.a,
+        lv.b,
+        lv.c,
+        lv.e,
+        lv.f,
+        lv.g,
+        lv.carry_a,
+        lv.carry_e,
+        lv.w1,
+        lv.w14,
+        lv.carry_w,
+        lv.carry_output,
+    ) {
+        constraints.always(bit.is_binary());
+    }
+
+    // The rounds of a compression follow each other, and end with the output
+    // row.
+    for (next, current) in izip!(nv.round.into_iter().skip(1), lv.round) {
+        constraints.transition(next - current);
+    }
+    constraints.transition(nv.is_output - lv.round[ROUNDS - 1]);
+    constraints.first_row(lv.is_round() - lv.round[0]);
+    constraints.first_row(lv.is_output);
+    constraints.last_row(lv.is_round());
+
+    // The functions of a round, which only depend on the current row.
+    constraints.always(lv.w[1] - pack(lv.w1));
+    constraints.always(lv.w[14] - pack(lv.w14));
+    constraints.always(lv.w[0] - pack_bytes(&lv.w_bytes));
+    constraints.always(
+        lv.big_sigma0
+            - xor_of_moves(lv.a, [
+                Right::Rotate(2),
+                Right::Rotate(13),
+                Right::Rotate(22),
+            ]),
+    );
+    constraints.always(
+        lv.big_sigma1
+            - xor_of_moves(lv.e, [
+                Right::Rotate(6),
+                Right::Rotate(11),
+                Right::Rotate(25),
+            ]),
+    );
+    constraints.always(
+        lv.small_sigma0
+            - xor_of_moves(lv.w1, [
+                Right::Rotate(7),
+                Right::Rotate(18),
+                Right::Shift(3),
+            ]),
+    );
+    constraints.always(
+        lv.small_sigma1
+            - xor_of_moves(lv.w14, [
+                Right::Rotate(17),
+                Right::Rotate(19),
+                Right::Shift(10),
+            ]),
+    );
+    constraints.always(
+        lv.ch
+            - Expr::reduce_with_powers(
+                izip!(lv.e, lv.f, lv.g).map(|(e, f, g)| e * f + (1 - e) * g),
+                2,
+            ),
+    );
+    constraints.always(
+        lv.maj
+            - Expr::reduce_with_powers(
+                izip!(lv.a, lv.b, lv.c).map(|(a, b, c)| a * b + a * c + b * c - 2 * a * b * c),
+                2,
+            ),
+    );
+
+    // A round.
+    let is_round = lv.is_round();
+    let k: Expr<'a, T> = izip!(lv.round, K)
+        .map(|(round, k)| round * i64::from(k))
+        .sum();
+    let t1 = lv.h + lv.big_sigma1 + lv.ch + k + lv.w[0];
+    let t2 = lv.big_sigma0 + lv.maj;
+    constraints.transition(
+        is_round * (pack(nv.a) + (1 << 32) * Expr::reduce_with_powers(lv.carry_a, 2) - (t1 + t2)),
+    );
+    constraints.transition(
+        is_round * (pack(nv.e) + (1 << 32) * Expr::reduce_with_powers(lv.carry_e, 2) - (lv.d + t1)),
+    );
+    for (next, current) in chain!(
+        izip!(nv.b, lv.a),
+        izip!(nv.c, lv.b),
+        izip!(nv.f, lv.e),
+        izip!(nv.g, lv.f)
+    ) {
+        constraints.transition(is_round * (next - current));
+    }
+    constraints.transition(is_round * (nv.d - pack(lv.c)));
+    constraints.transition(is_round * (nv.h - pack(lv.g)));
+
+    // The message schedule.
+    for (next, current) in izip!(nv.w, lv.w.into_iter().skip(1)) {
+        constraints.transition(is_round * (next - current));
+    }
+    constraints.transition(
+        is_round
+            * (nv.w[15] + (1 << 32) * Expr::reduce_with_powers(lv.carry_w, 2)
+                - (lv.small_sigma1 + lv.w[9] + lv.small_sigma0 + lv.w[0])),
+    );
+
+    for (next, current) in chain!(
+        [
+            (nv.clk, lv.clk),
+            (nv.state_addr, lv.state_addr),
+            (nv.block_addr, lv.block_addr),
+            (nv.output_addr, lv.output_addr),
+        ],
+        izip!(nv.initial, lv.initial),
+    ) {
+        constraints.transition(is_round * (next - current));
+    }
+
+    // The first round starts from the initial state, and the output row adds
+    // it back in.
+    let working = [
+        pack(lv.a),
+        pack(lv.b),
+        pack(lv.c),
+        lv.d,
+        pack(lv.e),
+        pack(lv.f),
+        pack(lv.g),
+        lv.h,
+    ];
+    for (i, (initial, working, carry)) in izip!(lv.initial, working, lv.carry_output).enumerate() {
+        let bytes = pack_bytes(&lv.state_bytes[4 * i..4 * i + 4]);
+        constraints.always(lv.round[0] * (bytes - initial));
+        constraints.always(lv.round[0] * (working - initial));
+        constraints.always(lv.is_output * (bytes + (1 << 32) * carry - (initial + working)));
+    }
+
+    constraints
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for Sha256Stark<F, D> {
+    type EvaluationFrame<FE, P, const D2: usize> = StarkFrame<P, P::Scalar, COLUMNS, PUBLIC_INPUTS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+    type EvaluationFrameTarget =
+        StarkFrame<ExtensionTarget<D>, ExtensionTarget<D>, COLUMNS, PUBLIC_INPUTS>;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        consumer: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>, {
+        let eb = ExprBuilder::default();
+        let constraints = generate_constraints(&eb.to_typed_starkframe(vars));
+        build_packed(constraints, consumer);
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        consumer: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let eb = ExprBuilder::default();
+        let constraints = generate_constraints(&eb.to_typed_starkframe(vars));
+        build_ext(constraints, builder, consumer);
+    }
+
+    fn constraint_degree(&self) -> usize { 3 }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use mozak_sdk::core::sha256::{BLOCK_BYTES, INITIAL_STATE, STATE_BYTES};
+    use plonky2::plonk::config::{GenericConfig, Poseidon2GoldilocksConfig};
+    use plonky2::util::timing::TimingTree;
+    use starky::config::StarkConfig;
+    use starky::prover::prove;
+    use starky::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use starky::verifier::verify_stark_proof;
+
+    use super::Sha256Stark;
+    use crate::sha256::generation::generate_sha256_trace;
+    use crate::stark::utils::trace_rows_to_poly_values;
+    use crate::test_utils::{create_sha256_test, Sha256Test};
+
+    const D: usize = 2;
+    type C = Poseidon2GoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type S = Sha256Stark<F, D>;
+
+    fn sha256_constraints(tests: &[Sha256Test]) -> Result<()> {
+        let _ = env_logger::try_init();
+        let mut config = StarkConfig::standard_fast_config();
+        config.fri_config.cap_height = 0;
+        config.fri_config.rate_bits = 3; // to meet the constraint degree bound
+
+        let (_program, record) = create_sha256_test(tests);
+
+        let stark = S::default();
+        let trace = generate_sha256_trace(&record.executed);
+        let trace_poly_values = trace_rows_to_poly_values(trace);
+
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace_poly_values,
+            &[],
+            &mut TimingTree::default(),
+        )?;
+        verify_stark_proof(stark, proof, &config)
+    }
+
+    #[test]
+    fn prove_sha256() -> Result<()> {
+        // "abc", padded to a single block.
+        let mut block = [0; BLOCK_BYTES];
+        block[..3].copy_from_slice(b"abc");
+        block[3] = 0x80;
+        block[BLOCK_BYTES - 1] = 24;
+        sha256_constraints(&[Sha256Test {
+            state: INITIAL_STATE,
+            block,
+            state_addr: 1024,
+            block_addr: 2048,
+            output_addr: 4096,
+        }])
+    }
+
+    #[test]
+    fn prove_sha256_multiple() -> Result<()> {
+        sha256_constraints(&[
+            Sha256Test {
+                state: INITIAL_STATE,
+                block: [0xa5; BLOCK_BYTES],
+                state_addr: 1024,
+                block_addr: 2048,
+                output_addr: 4096,
+            },
+            Sha256Test {
+                state: [0xff; STATE_BYTES],
+                block: [0; BLOCK_BYTES],
+                state_addr: 1024 + 32,
+                block_addr: 2048 + 64,
+                output_addr: 4096 + 32,
+            },
+        ])
+    }
+
+    #[test]
+    fn sha256_stark_degree() -> Result<()> {
+        let stark = S::default();
+        test_stark_low_degree(stark)
+    }
+
+    #[test]
+    fn test_circuit() -> Result<()> {
+        let stark = S::default();
+        test_stark_circuit_constraints::<F, C, S, D>(stark)
+    }
+}
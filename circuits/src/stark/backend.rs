@@ -0,0 +1,52 @@
+//! Where the commitments to the traces are computed.
+//!
+//! Low-degree extensions and the Merkle trees over them dominate the time
+//! spent proving.  The prover computes them through a [`ProverBackend`], so
+//! that they can be offloaded to an accelerator.  [`CpuBackend`] computes them
+//! with plonky2 on the CPU, and is what the prover uses by default.
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::polynomial::PolynomialCoeffs;
+use plonky2::fri::oracle::PolynomialBatch;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::config::GenericConfig;
+use plonky2::util::timing::TimingTree;
+
+pub trait ProverBackend<F, C, const D: usize>: Sync
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>, {
+    /// Commits to `polynomials`: evaluates them on a coset `2^rate_bits` times
+    /// larger than their degree, and builds a Merkle tree with a cap of height
+    /// `cap_height` over the evaluations.
+    ///
+    /// The result has to be the same as what
+    /// [`PolynomialBatch::from_coeffs`] computes without blinding.
+    fn commit(
+        &self,
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        rate_bits: usize,
+        cap_height: usize,
+        timing: &mut TimingTree,
+    ) -> PolynomialBatch<F, C, D>;
+}
+
+/// Computes the commitments on the CPU.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuBackend;
+
+impl<F, C, const D: usize> ProverBackend<F, C, D> for CpuBackend
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    fn commit(
+        &self,
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        rate_bits: usize,
+        cap_height: usize,
+        timing: &mut TimingTree,
+    ) -> PolynomialBatch<F, C, D> {
+        PolynomialBatch::from_coeffs(polynomials, rate_bits, false, cap_height, timing, None)
+    }
+}
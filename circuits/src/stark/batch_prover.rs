@@ -3,7 +3,7 @@
 use std::collections::HashMap;
 
 use anyhow::{ensure, Result};
-use itertools::{chain, Itertools};
+use itertools::{chain, izip, Itertools};
 use log::Level::Debug;
 use log::{debug, log_enabled};
 use mozak_runner::elf::Program;
@@ -33,7 +33,7 @@ use starky::config::StarkConfig;
 use starky::stark::{LookupConfig, Stark};
 
 use super::mozak_stark::{MozakStark, TableKind, TableKindArray, TableKindSetBuilder};
-use super::proof::{BatchProof, StarkOpeningSet, StarkProof};
+use super::proof::{AllProof, BatchProof, MultiBatchProof, StarkOpeningSet, StarkProof};
 use crate::cross_table_lookup::ctl_utils::debug_ctl;
 use crate::cross_table_lookup::{cross_table_lookup_data, CtlData};
 use crate::generation::{debug_traces, generate_traces};
@@ -59,28 +59,35 @@ pub struct BatchFriOracleIndices {
 
 #[allow(unused_assignments)]
 impl BatchFriOracleIndices {
+    /// Computes the indices for the batched tables of each program.  Within
+    /// the batch oracle, polynomials are ordered by degree first, then by
+    /// program and finally by table kind.
     fn new(
         public_table_kinds: &[TableKind],
-        poly_count: TableKindArray<usize>,
-        degree_bits: &TableKindArray<usize>,
-    ) -> Self {
+        poly_counts: Vec<TableKindArray<usize>>,
+        degree_bits: &[TableKindArray<usize>],
+    ) -> Vec<Self> {
         let sorted_degree_bits = sort_degree_bits(public_table_kinds, degree_bits);
 
         let mut poly_start_indices =
-            all_kind!(|kind| (!public_table_kinds.contains(&kind)).then_some(0));
-        let mut bmt_start_indices =
-            all_kind!(|kind| (!public_table_kinds.contains(&kind)).then_some(0));
+            vec![
+                all_kind!(|kind| (!public_table_kinds.contains(&kind)).then_some(0));
+                degree_bits.len()
+            ];
+        let mut bmt_start_indices = poly_start_indices.clone();
         let mut poly_start_index = 0;
         for deg in &sorted_degree_bits {
             let mut bmt_start_index = 0;
-            all_kind!(|kind| {
-                if !public_table_kinds.contains(&kind) && degree_bits[kind] == *deg {
-                    bmt_start_indices[kind] = Some(bmt_start_index);
-                    poly_start_indices[kind] = Some(poly_start_index);
-                    bmt_start_index += poly_count[kind];
-                    poly_start_index += poly_count[kind];
-                }
-            });
+            for (program, degree_bits) in degree_bits.iter().enumerate() {
+                all_kind!(|kind| {
+                    if !public_table_kinds.contains(&kind) && degree_bits[kind] == *deg {
+                        bmt_start_indices[program][kind] = Some(bmt_start_index);
+                        poly_start_indices[program][kind] = Some(poly_start_index);
+                        bmt_start_index += poly_counts[program][kind];
+                        poly_start_index += poly_counts[program][kind];
+                    }
+                });
+            }
         }
 
         let degree_bits_index_map: HashMap<usize, usize> = sorted_degree_bits
@@ -88,82 +95,102 @@ impl BatchFriOracleIndices {
             .enumerate()
             .map(|(index, value)| (value, index))
             .collect();
-        let degree_bits_indices = all_kind!(|kind| if public_table_kinds.contains(&kind) {
-            None
-        } else {
-            Some(degree_bits_index_map[&degree_bits[kind]])
-        });
 
-        BatchFriOracleIndices {
-            poly_count,
+        izip!(
+            poly_counts,
             bmt_start_indices,
             poly_start_indices,
-            degree_bits_indices,
-        }
+            degree_bits
+        )
+        .map(
+            |(poly_count, bmt_start_indices, poly_start_indices, degree_bits)| {
+                let degree_bits_indices = all_kind!(|kind| if public_table_kinds.contains(&kind) {
+                    None
+                } else {
+                    Some(degree_bits_index_map[&degree_bits[kind]])
+                });
+                BatchFriOracleIndices {
+                    poly_count,
+                    bmt_start_indices,
+                    poly_start_indices,
+                    degree_bits_indices,
+                }
+            },
+        )
+        .collect()
     }
 }
 
+/// Returns the distinct degree bits of the batched tables of all programs, in
+/// descending order.
 pub(crate) fn sort_degree_bits(
     public_table_kinds: &[TableKind],
-    degree_bits: &TableKindArray<usize>,
+    degree_bits: &[TableKindArray<usize>],
 ) -> Vec<usize> {
-    let mut sorted_degree_bits: Vec<usize> =
-        all_kind!(|kind| (!public_table_kinds.contains(&kind)).then_some(degree_bits[kind]))
-            .iter()
-            .filter_map(|d| *d)
-            .collect_vec();
+    let mut sorted_degree_bits: Vec<usize> = degree_bits
+        .iter()
+        .flat_map(|degree_bits| {
+            all_kind!(|kind| (!public_table_kinds.contains(&kind)).then_some(degree_bits[kind])).0
+        })
+        .flatten()
+        .collect_vec();
     sorted_degree_bits.sort_unstable();
     sorted_degree_bits.reverse();
     sorted_degree_bits.dedup();
     sorted_degree_bits
 }
 
+/// Sets up the FRI instances of the batched tables of all programs, merged
+/// by degree in the same order as the polynomials in the batch oracles.
 pub(crate) fn batch_fri_instances<F: RichField + Extendable<D>, const D: usize>(
     mozak_stark: &MozakStark<F, D>,
     public_table_kinds: &[TableKind],
-    degree_bits: &TableKindArray<usize>,
+    degree_bits: &[TableKindArray<usize>],
     sorted_degree_bits: &[usize],
     zeta: F::Extension,
     config: &StarkConfig,
-    num_ctl_zs_per_table: &TableKindArray<usize>,
+    num_ctl_zs_per_table: &[TableKindArray<usize>],
 ) -> Vec<FriInstanceInfo<F, D>> {
-    let fri_instances = all_starks!(
-        mozak_stark,
-        |stark, kind| if public_table_kinds.contains(&kind) {
-            None
-        } else {
-            Some({
-                let g = F::primitive_root_of_unity(degree_bits[kind]);
-                stark.fri_instance(
-                    zeta,
-                    g,
-                    0,
-                    vec![],
-                    config,
-                    Some(&LookupConfig {
-                        degree_bits: degree_bits[kind],
-                        num_zs: num_ctl_zs_per_table[kind],
-                    }),
-                )
-            })
-        }
-    );
-
-    let mut degree_bits_map: HashMap<usize, Vec<TableKind>> = HashMap::new();
-    all_kind!(|kind| {
-        degree_bits_map
-            .entry(degree_bits[kind])
-            .or_default()
-            .push(kind);
-    });
+    let fri_instances = izip!(degree_bits, num_ctl_zs_per_table)
+        .map(|(degree_bits, num_ctl_zs_per_table)| {
+            all_starks!(
+                mozak_stark,
+                |stark, kind| if public_table_kinds.contains(&kind) {
+                    None
+                } else {
+                    Some({
+                        let g = F::primitive_root_of_unity(degree_bits[kind]);
+                        stark.fri_instance(
+                            zeta,
+                            g,
+                            0,
+                            vec![],
+                            config,
+                            Some(&LookupConfig {
+                                degree_bits: degree_bits[kind],
+                                num_zs: num_ctl_zs_per_table[kind],
+                            }),
+                        )
+                    })
+                }
+            )
+        })
+        .collect_vec();
 
     let fri_instance_groups = sorted_degree_bits
         .iter()
         .map(|d| {
-            degree_bits_map[d]
-                .iter()
-                .filter_map(|kind| fri_instances[*kind].as_ref())
-                .collect::<Vec<_>>()
+            let mut group = vec![];
+            for (fri_instances, degree_bits) in izip!(&fri_instances, degree_bits) {
+                all_kind!(|kind| {
+                    if let Some(instance) = &fri_instances[kind] {
+                        if degree_bits[kind] == *d {
+                            group.push(instance);
+                        }
+                    }
+                });
+            }
+            group
         })
         .collect::<Vec<_>>();
 
@@ -351,6 +378,11 @@ pub(crate) fn merge_fri_instances_target<const D: usize>(
     res
 }
 
+/// Proves a single program, with all tables but `public_table_kinds` sharing
+/// one batched FRI opening proof.
+///
+/// # Errors
+/// Errors if proving fails.
 pub fn batch_prove<F, C, const D: usize>(
     program: &Program,
     record: &ExecutionRecord<F>,
@@ -364,34 +396,108 @@ where
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     <C as GenericConfig<D>>::Hasher: AlgebraicHasher<F>, {
+    let (
+        MultiBatchProof {
+            mut programs,
+            batch_stark_proof,
+        },
+        mut degree_bits,
+    ) = batch_prove_programs(
+        &[(program, record)],
+        mozak_stark,
+        public_table_kinds,
+        config,
+        &[public_inputs],
+        timing,
+    )?;
+    let AllProof {
+        proofs,
+        public_inputs,
+        public_sub_table_values,
+        program_id,
+    } = programs.remove(0);
+    Ok((
+        BatchProof {
+            proofs,
+            public_inputs,
+            public_sub_table_values,
+            program_id,
+            batch_stark_proof,
+        },
+        degree_bits.remove(0),
+    ))
+}
+
+/// Proves several programs, e.g. all programs in the cast list of a
+/// transaction, into a single [`MultiBatchProof`].
+///
+/// The public tables of every program still get a proof of their own, but
+/// all other tables of all programs are committed to in the same batch
+/// Merkle trees and share a single batched FRI opening proof.  The returned
+/// degree bits are per program, in the order of `programs`.
+///
+/// # Errors
+/// Errors if `programs` is empty, if `public_inputs` does not match
+/// `programs`, or if proving fails.
+pub fn batch_prove_programs<F, C, const D: usize>(
+    programs: &[(&Program, &ExecutionRecord<F>)],
+    mozak_stark: &MozakStark<F, D>,
+    public_table_kinds: &[TableKind],
+    config: &StarkConfig,
+    public_inputs: &[PublicInputs<F>],
+    timing: &mut TimingTree,
+) -> Result<(MultiBatchProof<F, C, D>, Vec<TableKindArray<usize>>)>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    <C as GenericConfig<D>>::Hasher: AlgebraicHasher<F>, {
+    ensure!(!programs.is_empty(), "No programs to prove");
+    ensure!(
+        programs.len() == public_inputs.len(),
+        "Expected public inputs for each of the {} programs, got {}",
+        programs.len(),
+        public_inputs.len()
+    );
     debug!("Starting Prove");
-    let traces_poly_values = generate_traces(program, record, timing);
+    let traces_poly_values = programs
+        .iter()
+        .map(|(program, record)| generate_traces(program, record, timing))
+        .collect::<Result<Vec<_>>>()?;
     if mozak_stark.debug || std::env::var("MOZAK_STARK_DEBUG").is_ok() {
-        debug_traces(&traces_poly_values, mozak_stark, &public_inputs);
-        debug_ctl(&traces_poly_values, mozak_stark);
+        for (traces_poly_values, public_inputs) in izip!(&traces_poly_values, public_inputs) {
+            debug_traces(traces_poly_values, mozak_stark, public_inputs)?;
+            debug_ctl(traces_poly_values, mozak_stark);
+        }
     }
     let rate_bits = config.fri_config.rate_bits;
     let cap_height = config.fri_config.cap_height;
-    let degree_bits = all_kind!(|kind| log2_strict(traces_poly_values[kind][0].len()));
-    let traces_poly_count = all_kind!(|kind| traces_poly_values[kind].len());
+    let degree_bits = traces_poly_values
+        .iter()
+        .map(|traces_poly_values| all_kind!(|kind| log2_strict(traces_poly_values[kind][0].len())))
+        .collect_vec();
+    let traces_poly_count = traces_poly_values
+        .iter()
+        .map(|traces_poly_values| all_kind!(|kind| traces_poly_values[kind].len()))
+        .collect_vec();
     let trace_indices =
         BatchFriOracleIndices::new(public_table_kinds, traces_poly_count, &degree_bits);
 
-    let batch_traces_poly_values = all_kind!(|kind| if public_table_kinds.contains(&kind) {
-        None
-    } else {
-        Some(&traces_poly_values[kind])
-    });
-
-    let mut batch_trace_polys: Vec<_> = batch_traces_poly_values
+    let mut batch_trace_polys: Vec<_> = traces_poly_values
         .iter()
-        .filter_map(|t| *t)
-        .flat_map(std::clone::Clone::clone)
+        .flat_map(|traces_poly_values| {
+            all_kind!(|kind| if public_table_kinds.contains(&kind) {
+                vec![]
+            } else {
+                traces_poly_values[kind].clone()
+            })
+            .0
+        })
+        .flatten()
         .collect();
     batch_trace_polys.sort_by_key(|p| std::cmp::Reverse(p.len()));
 
-    // This commitment is for all tables but public tables, in form of Field Merkle
-    // Tree (1st oracle)
+    // This commitment is for all tables of all programs but public tables, in
+    // form of Field Merkle Tree (1st oracle)
     let batch_trace_polys_len = batch_trace_polys.len();
     let batch_trace_commitments: BatchFriOracle<F, C, D> = timed!(
         timing,
@@ -411,37 +517,49 @@ where
         timing,
         "Compute trace commitments for public tables",
         traces_poly_values
-            .clone()
-            .with_kind()
-            .map(|(trace, table)| {
-                public_table_kinds.contains(&table).then(|| {
-                    timed!(
-                        timing,
-                        &format!("compute trace commitment for {table:?}"),
-                        PolynomialBatch::<F, C, D>::from_values(
-                            trace.clone(),
-                            rate_bits,
-                            false,
-                            cap_height,
-                            timing,
-                            None,
-                        )
-                    )
-                })
+            .iter()
+            .map(|traces_poly_values| {
+                traces_poly_values
+                    .clone()
+                    .with_kind()
+                    .map(|(trace, table)| {
+                        public_table_kinds.contains(&table).then(|| {
+                            timed!(
+                                timing,
+                                &format!("compute trace commitment for {table:?}"),
+                                PolynomialBatch::<F, C, D>::from_values(
+                                    trace.clone(),
+                                    rate_bits,
+                                    false,
+                                    cap_height,
+                                    timing,
+                                    None,
+                                )
+                            )
+                        })
+                    })
             })
+            .collect_vec()
     );
 
-    let trace_caps = all_kind!(|kind| trace_commitments[kind]
-        .as_ref()
-        .map(|c| c.merkle_tree.cap.clone()));
+    let trace_caps = trace_commitments
+        .iter()
+        .map(|trace_commitments| {
+            all_kind!(|kind| trace_commitments[kind]
+                .as_ref()
+                .map(|c| c.merkle_tree.cap.clone()))
+        })
+        .collect_vec();
 
     // Add trace commitments to the challenger entropy pool.
     let mut challenger = Challenger::<F, C::Hasher>::new();
-    all_kind!(|kind| {
-        if let Some(c) = trace_caps[kind].clone() {
-            challenger.observe_cap(&c);
-        }
-    });
+    for trace_caps in &trace_caps {
+        all_kind!(|kind| {
+            if let Some(c) = trace_caps[kind].clone() {
+                challenger.observe_cap(&c);
+            }
+        });
+    }
     let bmt_trace_cap = batch_trace_commitments.batch_merkle_tree.cap.clone();
     challenger.observe_cap(&bmt_trace_cap);
 
@@ -449,25 +567,33 @@ where
     let ctl_data_per_table = timed!(
         timing,
         "Compute CTL data for each table",
-        cross_table_lookup_data::<F, D>(
-            &traces_poly_values,
-            &mozak_stark.cross_table_lookups,
-            &ctl_challenges
-        )
+        traces_poly_values
+            .iter()
+            .map(|traces_poly_values| cross_table_lookup_data::<F, D>(
+                traces_poly_values,
+                &mozak_stark.cross_table_lookups,
+                &ctl_challenges
+            ))
+            .collect_vec()
     );
 
-    let (public_sub_table_data_per_table, public_sub_table_values) =
-        public_sub_table_data_and_values::<F, D>(
-            &traces_poly_values,
-            &mozak_stark.public_sub_tables,
-            &ctl_challenges,
-        );
+    let (public_sub_table_data_per_table, public_sub_table_values): (Vec<_>, Vec<_>) =
+        traces_poly_values
+            .iter()
+            .map(|traces_poly_values| {
+                public_sub_table_data_and_values::<F, D>(
+                    traces_poly_values,
+                    &mozak_stark.public_sub_tables,
+                    &ctl_challenges,
+                )
+            })
+            .unzip();
 
     let (proofs, batch_stark_proof) = batch_prove_with_commitments(
         mozak_stark,
         config,
         public_table_kinds,
-        &public_inputs,
+        public_inputs,
         &degree_bits,
         &trace_commitments,
         &trace_indices,
@@ -478,33 +604,39 @@ where
         timing,
     )?;
 
-    let program_id = get_program_id::<F, C, D>(
-        public_inputs.entry_point,
-        trace_caps[TableKind::Program]
-            .as_ref()
-            .expect("program trace cap not found"),
-        trace_caps[TableKind::ElfMemoryInit]
-            .as_ref()
-            .expect("elf memory ini trace cap not found"),
-    );
+    let programs = izip!(proofs, public_inputs, public_sub_table_values, &trace_caps)
+        .map(
+            |(proofs, public_inputs, public_sub_table_values, trace_caps)| AllProof {
+                proofs,
+                public_inputs: *public_inputs,
+                public_sub_table_values,
+                program_id: get_program_id::<F, C, D>(
+                    public_inputs.entry_point,
+                    trace_caps[TableKind::Program]
+                        .as_ref()
+                        .expect("program trace cap not found"),
+                    trace_caps[TableKind::ElfMemoryInit]
+                        .as_ref()
+                        .expect("elf memory ini trace cap not found"),
+                ),
+            },
+        )
+        .collect();
 
     if log_enabled!(Debug) {
         timing.print();
     }
     Ok((
-        BatchProof {
-            proofs,
-            public_inputs,
-            public_sub_table_values,
-            program_id,
+        MultiBatchProof {
+            programs,
             batch_stark_proof,
         },
         degree_bits,
     ))
 }
 
-/// Given the traces generated from [`generate_traces`] along with their
-/// commitments, prove a [`MozakStark`].
+/// Given the traces of each program generated from [`generate_traces`] along
+/// with their commitments, prove a [`MozakStark`] for all of them at once.
 ///
 /// # Errors
 /// Errors if proving fails.
@@ -514,16 +646,19 @@ pub fn batch_prove_with_commitments<F, C, const D: usize>(
     mozak_stark: &MozakStark<F, D>,
     config: &StarkConfig,
     public_table_kinds: &[TableKind],
-    public_inputs: &PublicInputs<F>,
-    degree_bits: &TableKindArray<usize>,
-    trace_commitments: &TableKindArray<Option<PolynomialBatch<F, C, D>>>,
-    trace_indices: &BatchFriOracleIndices,
+    public_inputs: &[PublicInputs<F>],
+    degree_bits: &[TableKindArray<usize>],
+    trace_commitments: &[TableKindArray<Option<PolynomialBatch<F, C, D>>>],
+    trace_indices: &[BatchFriOracleIndices],
     batch_trace_commitments: &BatchFriOracle<F, C, D>,
-    ctl_data_per_table: &TableKindArray<CtlData<F>>,
-    public_sub_data_per_table: &TableKindArray<CtlData<F>>,
+    ctl_data_per_table: &[TableKindArray<CtlData<F>>],
+    public_sub_data_per_table: &[TableKindArray<CtlData<F>>],
     challenger: &mut Challenger<F, C::Hasher>,
     timing: &mut TimingTree,
-) -> Result<(TableKindArray<StarkProof<F, C, D>>, StarkProof<F, C, D>)>
+) -> Result<(
+    Vec<TableKindArray<StarkProof<F, C, D>>>,
+    StarkProof<F, C, D>,
+)>
 where
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>, {
@@ -531,59 +666,88 @@ where
     let cap_height = config.fri_config.cap_height;
 
     // TODO(Matthias): Unify everything in this function with the non-batch version.
-    let cpu_skeleton_stark = [public_inputs.entry_point];
-    let public_inputs = TableKindSetBuilder::<&[_]> {
-        cpu_skeleton_stark: &cpu_skeleton_stark,
-        ..Default::default()
+    let cpu_skeleton_stark = public_inputs
+        .iter()
+        .map(|public_inputs| [public_inputs.entry_point])
+        .collect_vec();
+    let public_inputs = cpu_skeleton_stark
+        .iter()
+        .map(|cpu_skeleton_stark| {
+            TableKindSetBuilder::<&[_]> {
+                cpu_skeleton_stark,
+                ..Default::default()
+            }
+            .build()
+        })
+        .collect_vec();
+
+    // Computes separate proofs for each public table of each program.
+    let mut separate_proofs = Vec::with_capacity(public_inputs.len());
+    for (trace_commitments, public_inputs, ctl_data_per_table, public_sub_data_per_table) in izip!(
+        trace_commitments,
+        &public_inputs,
+        ctl_data_per_table,
+        public_sub_data_per_table
+    ) {
+        separate_proofs.push(all_starks!(mozak_stark, |stark, kind| {
+            if let Some(trace_commitment) = &trace_commitments[kind] {
+                Some(prove_single_table(
+                    stark,
+                    config,
+                    trace_commitment,
+                    public_inputs[kind],
+                    &ctl_data_per_table[kind],
+                    &public_sub_data_per_table[kind],
+                    challenger,
+                    timing,
+                )?)
+            } else {
+                None
+            }
+        }));
     }
-    .build();
-
-    // Computes separate proofs for each public table.
-    let separate_proofs = all_starks!(mozak_stark, |stark, kind| if let Some(trace_commitment) =
-        &trace_commitments[kind]
-    {
-        Some(prove_single_table(
-            stark,
-            config,
-            trace_commitment,
-            public_inputs[kind],
-            &ctl_data_per_table[kind],
-            &public_sub_data_per_table[kind],
-            challenger,
-            timing,
-        )?)
-    } else {
-        None
-    });
 
     // Computing ctl zs polynomials for all but those for public tables
-    let mut ctl_zs_poly_count = all_kind!(|_kind| 0);
-    let all_ctl_z_polys = all_kind!(|kind| {
-        if public_table_kinds.contains(&kind) {
-            None
-        } else {
-            Some({
-                let fri_params = config.fri_params(degree_bits[kind]);
-                assert!(
-                    fri_params.total_arities() <= degree_bits[kind] + rate_bits - cap_height,
-                    "FRI total reduction arity is too large.",
-                );
-
-                let z_poly_public_sub_table = public_sub_data_per_table[kind].z_polys();
-
-                let z_polys = vec![ctl_data_per_table[kind].z_polys(), z_poly_public_sub_table]
-                    .into_iter()
-                    .flatten()
-                    .collect_vec();
-
-                assert!(!z_polys.is_empty());
-
-                ctl_zs_poly_count[kind] = z_polys.len();
-
-                z_polys
+    let mut ctl_zs_poly_count = vec![all_kind!(|_kind| 0); public_inputs.len()];
+    let all_ctl_z_polys = izip!(
+        degree_bits,
+        ctl_data_per_table,
+        public_sub_data_per_table,
+        &mut ctl_zs_poly_count
+    )
+    .map(
+        |(degree_bits, ctl_data_per_table, public_sub_data_per_table, ctl_zs_poly_count)| {
+            all_kind!(|kind| {
+                if public_table_kinds.contains(&kind) {
+                    None
+                } else {
+                    Some({
+                        let fri_params = config.fri_params(degree_bits[kind]);
+                        assert!(
+                            fri_params.total_arities()
+                                <= degree_bits[kind] + rate_bits - cap_height,
+                            "FRI total reduction arity is too large.",
+                        );
+
+                        let z_poly_public_sub_table = public_sub_data_per_table[kind].z_polys();
+
+                        let z_polys =
+                            vec![ctl_data_per_table[kind].z_polys(), z_poly_public_sub_table]
+                                .into_iter()
+                                .flatten()
+                                .collect_vec();
+
+                        assert!(!z_polys.is_empty());
+
+                        ctl_zs_poly_count[kind] = z_polys.len();
+
+                        z_polys
+                    })
+                }
             })
-        }
-    });
+        },
+    )
+    .collect_vec();
 
     let ctl_zs_indices =
         BatchFriOracleIndices::new(public_table_kinds, ctl_zs_poly_count, degree_bits);
@@ -591,7 +755,7 @@ where
     // TODO: can we remove duplicates in the ctl polynomials?
     let mut batch_ctl_z_polys: Vec<_> = all_ctl_z_polys
         .iter()
-        .filter_map(|t| t.as_ref())
+        .flat_map(|all_ctl_z_polys| all_ctl_z_polys.iter().filter_map(Option::as_ref))
         .flat_map(|v| v.iter().cloned())
         .collect();
     batch_ctl_z_polys.sort_by_key(|b| std::cmp::Reverse(b.len()));
@@ -619,82 +783,101 @@ where
     let alphas = challenger.get_n_challenges(config.num_challenges);
     let sorted_degree_bits = sort_degree_bits(public_table_kinds, degree_bits);
 
-    let mut quotient_poly_count = all_kind!(|_kind| 0);
-    let quotient_chunks = all_starks!(mozak_stark, |stark, kind| {
-        if public_table_kinds.contains(&kind) {
-            None
-        } else {
-            let degree = 1 << degree_bits[kind];
-
-            let degree_bits_index = trace_indices.degree_bits_indices[kind].unwrap();
-            let trace_slice_start = trace_indices.bmt_start_indices[kind].unwrap();
-            let trace_slice_len = trace_indices.poly_count[kind];
-            let get_trace_values_packed = |i_start, step| -> Vec<<F as Packable>::Packing> {
-                batch_trace_commitments.get_lde_values_packed(
-                    degree_bits_index,
-                    i_start,
-                    step,
-                    trace_slice_start,
-                    trace_slice_len,
-                )
-            };
-
-            let ctl_zs_slice_start = ctl_zs_indices.bmt_start_indices[kind].unwrap();
-            let ctl_zs_slice_len = ctl_zs_indices.poly_count[kind];
-            let get_ctl_zs_values_packed = |i_start, step| -> Vec<<F as Packable>::Packing> {
-                batch_ctl_zs_commitments.get_lde_values_packed(
-                    degree_bits_index,
-                    i_start,
-                    step,
-                    ctl_zs_slice_start,
-                    ctl_zs_slice_len,
-                )
-            };
-
-            let quotient_polys = timed!(
-                timing,
-                format!("{stark}: compute quotient polynomial").as_str(),
-                compute_quotient_polys::<F, <F as Packable>::Packing, C, _, D>(
-                    stark,
-                    &get_trace_values_packed,
-                    &get_ctl_zs_values_packed,
-                    public_inputs[kind],
-                    &ctl_data_per_table[kind],
-                    &public_sub_data_per_table[kind],
-                    &alphas,
-                    degree_bits[kind],
-                    config,
-                )
-            );
-            assert!(!quotient_polys.is_empty());
-
-            let quotient_chunks: Vec<PolynomialCoeffs<F>> = timed!(
-                timing,
-                format!("{stark}: split quotient polynomial").as_str(),
-                quotient_polys
-                    .into_par_iter()
-                    .flat_map(|mut quotient_poly| {
-                        quotient_poly
-                    .trim_to_len(degree * stark.quotient_degree_factor())
-                    .expect(
-                        "Quotient has failed, the vanishing polynomial is not divisible by Z_H",
-                    );
-                        // Split quotient into degree-n chunks.
-                        quotient_poly.chunks(degree)
-                    })
-                    .collect()
-            );
-            quotient_poly_count[kind] = quotient_chunks.len();
-            Some(quotient_chunks)
-        }
-    });
+    let mut quotient_poly_count = vec![all_kind!(|_kind| 0); public_inputs.len()];
+    let mut all_quotient_chunks = Vec::with_capacity(public_inputs.len());
+    for (
+        degree_bits,
+        trace_indices,
+        ctl_zs_indices,
+        public_inputs,
+        ctl_data_per_table,
+        public_sub_data_per_table,
+        quotient_poly_count,
+    ) in izip!(
+        degree_bits,
+        trace_indices,
+        &ctl_zs_indices,
+        &public_inputs,
+        ctl_data_per_table,
+        public_sub_data_per_table,
+        &mut quotient_poly_count
+    ) {
+        all_quotient_chunks.push(all_starks!(mozak_stark, |stark, kind| {
+            if public_table_kinds.contains(&kind) {
+                None
+            } else {
+                let degree = 1 << degree_bits[kind];
+
+                let degree_bits_index = trace_indices.degree_bits_indices[kind].unwrap();
+                let trace_slice_start = trace_indices.bmt_start_indices[kind].unwrap();
+                let trace_slice_len = trace_indices.poly_count[kind];
+                let get_trace_values_packed = |i_start, step| -> Vec<<F as Packable>::Packing> {
+                    batch_trace_commitments.get_lde_values_packed(
+                        degree_bits_index,
+                        i_start,
+                        step,
+                        trace_slice_start,
+                        trace_slice_len,
+                    )
+                };
+
+                let ctl_zs_slice_start = ctl_zs_indices.bmt_start_indices[kind].unwrap();
+                let ctl_zs_slice_len = ctl_zs_indices.poly_count[kind];
+                let get_ctl_zs_values_packed = |i_start, step| -> Vec<<F as Packable>::Packing> {
+                    batch_ctl_zs_commitments.get_lde_values_packed(
+                        degree_bits_index,
+                        i_start,
+                        step,
+                        ctl_zs_slice_start,
+                        ctl_zs_slice_len,
+                    )
+                };
+
+                let quotient_polys = timed!(
+                    timing,
+                    format!("{stark}: compute quotient polynomial").as_str(),
+                    compute_quotient_polys::<F, <F as Packable>::Packing, C, _, D>(
+                        stark,
+                        &get_trace_values_packed,
+                        &get_ctl_zs_values_packed,
+                        public_inputs[kind],
+                        &ctl_data_per_table[kind],
+                        &public_sub_data_per_table[kind],
+                        &alphas,
+                        degree_bits[kind],
+                        config,
+                    )
+                );
+                assert!(!quotient_polys.is_empty());
+
+                let quotient_chunks: Vec<PolynomialCoeffs<F>> = timed!(
+                    timing,
+                    format!("{stark}: split quotient polynomial").as_str(),
+                    quotient_polys
+                        .into_par_iter()
+                        .flat_map(|mut quotient_poly| {
+                            quotient_poly
+                        .trim_to_len(degree * stark.quotient_degree_factor())
+                        .expect(
+                            "Quotient has failed, the vanishing polynomial is not divisible by Z_H",
+                        );
+                            // Split quotient into degree-n chunks.
+                            quotient_poly.chunks(degree)
+                        })
+                        .collect()
+                );
+                quotient_poly_count[kind] = quotient_chunks.len();
+                Some(quotient_chunks)
+            }
+        }));
+    }
 
     let quotient_indices =
         BatchFriOracleIndices::new(public_table_kinds, quotient_poly_count, degree_bits);
 
-    let mut batch_quotient_chunks: Vec<_> = quotient_chunks
+    let mut batch_quotient_chunks: Vec<_> = all_quotient_chunks
         .iter()
-        .filter_map(|t| t.as_ref())
+        .flat_map(|quotient_chunks| quotient_chunks.iter().filter_map(Option::as_ref))
         .flat_map(|v| v.iter().cloned())
         .collect();
     batch_quotient_chunks.sort_by_key(|b| std::cmp::Reverse(b.len()));
@@ -721,46 +904,58 @@ where
     let zeta = challenger.get_extension_challenge::<D>();
 
     // Sets up batched fri instance for all tables but the public tables.
-    let batch_openings = all_starks!(mozak_stark, |_stark, kind| if public_table_kinds
-        .contains(&kind)
-    {
-        None
-    } else {
-        // To avoid leaking witness data, we want to ensure that our opening locations,
-        // `zeta` and `g * zeta`, are not in our subgroup `H`. It suffices to check
-        // `zeta` only, since `(g * zeta)^n = zeta^n`, where `n` is the order of
-        // `g`.
-        let g = F::primitive_root_of_unity(degree_bits[kind]);
-        ensure!(
-            zeta.exp_power_of_2(degree_bits[kind]) != F::Extension::ONE,
-            "Opening point is in the subgroup."
-        );
+    let mut batch_openings = Vec::with_capacity(public_inputs.len());
+    for (degree_bits, trace_indices, ctl_zs_indices, quotient_indices) in izip!(
+        degree_bits,
+        trace_indices,
+        &ctl_zs_indices,
+        &quotient_indices
+    ) {
+        batch_openings.push(all_starks!(
+            mozak_stark,
+            |_stark, kind| if public_table_kinds.contains(&kind) {
+                None
+            } else {
+                // To avoid leaking witness data, we want to ensure that our opening locations,
+                // `zeta` and `g * zeta`, are not in our subgroup `H`. It suffices to check
+                // `zeta` only, since `(g * zeta)^n = zeta^n`, where `n` is the order of
+                // `g`.
+                let g = F::primitive_root_of_unity(degree_bits[kind]);
+                ensure!(
+                    zeta.exp_power_of_2(degree_bits[kind]) != F::Extension::ONE,
+                    "Opening point is in the subgroup."
+                );
 
-        let openings = StarkOpeningSet::batch_new(
-            zeta,
-            g,
-            [
-                trace_indices.poly_start_indices[kind].unwrap(),
-                ctl_zs_indices.poly_start_indices[kind].unwrap(),
-                quotient_indices.poly_start_indices[kind].unwrap(),
-            ],
-            [
-                trace_indices.poly_count[kind],
-                ctl_zs_indices.poly_count[kind],
-                quotient_indices.poly_count[kind],
-            ],
-            batch_trace_commitments,
-            &batch_ctl_zs_commitments,
-            &batch_quotient_commitments,
-            degree_bits[kind],
-        );
+                let openings = StarkOpeningSet::batch_new(
+                    zeta,
+                    g,
+                    [
+                        trace_indices.poly_start_indices[kind].unwrap(),
+                        ctl_zs_indices.poly_start_indices[kind].unwrap(),
+                        quotient_indices.poly_start_indices[kind].unwrap(),
+                    ],
+                    [
+                        trace_indices.poly_count[kind],
+                        ctl_zs_indices.poly_count[kind],
+                        quotient_indices.poly_count[kind],
+                    ],
+                    batch_trace_commitments,
+                    &batch_ctl_zs_commitments,
+                    &batch_quotient_commitments,
+                    degree_bits[kind],
+                );
 
-        challenger.observe_openings(&openings.to_fri_openings());
-        Some(openings)
-    });
+                challenger.observe_openings(&openings.to_fri_openings());
+                Some(openings)
+            }
+        ));
+    }
 
-    let num_ctl_zs_per_table =
-        all_kind!(|kind| ctl_data_per_table[kind].len() + public_sub_data_per_table[kind].len());
+    let num_ctl_zs_per_table = izip!(ctl_data_per_table, public_sub_data_per_table)
+        .map(|(ctl_data_per_table, public_sub_data_per_table)| {
+            all_kind!(|kind| ctl_data_per_table[kind].len() + public_sub_data_per_table[kind].len())
+        })
+        .collect_vec();
 
     let batch_fri_instances = batch_fri_instances(
         mozak_stark,
@@ -820,23 +1015,27 @@ where
         quotient_polys: vec![],
     };
     Ok((
-        all_kind!(|kind| {
-            if public_table_kinds.contains(&kind) {
-                <Option<StarkProof<F, C, D>> as Clone>::clone(&separate_proofs[kind])
-                    .expect("No Proof")
-            } else {
-                StarkProof {
-                    trace_cap: MerkleCap::default(),
-                    ctl_zs_cap: MerkleCap::default(),
-                    quotient_polys_cap: MerkleCap::default(),
-                    openings: <Option<StarkOpeningSet<F, D>> as Clone>::clone(
-                        &batch_openings[kind],
-                    )
-                    .expect("No Openings"),
-                    opening_proof: empty_fri_proof.clone(),
-                }
-            }
-        }),
+        izip!(separate_proofs, batch_openings)
+            .map(|(separate_proofs, batch_openings)| {
+                all_kind!(|kind| {
+                    if public_table_kinds.contains(&kind) {
+                        <Option<StarkProof<F, C, D>> as Clone>::clone(&separate_proofs[kind])
+                            .expect("No Proof")
+                    } else {
+                        StarkProof {
+                            trace_cap: MerkleCap::default(),
+                            ctl_zs_cap: MerkleCap::default(),
+                            quotient_polys_cap: MerkleCap::default(),
+                            openings: <Option<StarkOpeningSet<F, D>> as Clone>::clone(
+                                &batch_openings[kind],
+                            )
+                            .expect("No Openings"),
+                            opening_proof: empty_fri_proof.clone(),
+                        }
+                    }
+                })
+            })
+            .collect(),
         StarkProof {
             trace_cap: batch_trace_commitments.batch_merkle_tree.cap.clone(),
             ctl_zs_cap,
@@ -887,8 +1086,10 @@ mod tests {
     use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
     use plonky2::util::timing::TimingTree;
 
-    use crate::stark::batch_prover::{batch_prove, batch_reduction_arity_bits};
-    use crate::stark::batch_verifier::batch_verify_proof;
+    use crate::stark::batch_prover::{
+        batch_prove, batch_prove_programs, batch_reduction_arity_bits,
+    };
+    use crate::stark::batch_verifier::{batch_verify_programs, batch_verify_proof};
     use crate::stark::mozak_stark::{MozakStark, PublicInputs, PUBLIC_TABLE_KINDS};
     use crate::test_utils::fast_test_config;
     use crate::utils::from_u32;
@@ -967,4 +1168,59 @@ mod tests {
         )
         .unwrap();
     }
+
+    #[test]
+    fn batch_prove_two_programs() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let execute = |op, rs1_value, rs2_value| {
+            code::execute(
+                [Instruction {
+                    op,
+                    args: Args {
+                        rd: 5,
+                        rs1: 6,
+                        rs2: 7,
+                        ..Args::default()
+                    },
+                }],
+                &[],
+                &[(6, rs1_value), (7, rs2_value)],
+            )
+        };
+        let (add_program, add_record) = execute(Op::ADD, 3, 4);
+        let (sub_program, sub_record) = execute(Op::SUB, 100, 13);
+        let config = fast_test_config();
+
+        let stark: MozakStark<F, D> = MozakStark::default();
+        let public_inputs = [&add_program, &sub_program].map(|program| PublicInputs {
+            entry_point: from_u32(program.entry_point),
+        });
+
+        let (proof, degree_bits) = batch_prove_programs::<F, C, D>(
+            &[(&add_program, &add_record), (&sub_program, &sub_record)],
+            &stark,
+            &PUBLIC_TABLE_KINDS,
+            &config,
+            &public_inputs,
+            &mut TimingTree::default(),
+        )
+        .unwrap();
+        assert_eq!(proof.programs.len(), 2);
+        assert_ne!(proof.programs[0].program_id, proof.programs[1].program_id);
+
+        // The degree bits of every program are needed to verify.
+        assert!(batch_verify_programs(
+            &stark,
+            &PUBLIC_TABLE_KINDS,
+            proof.clone(),
+            &config,
+            &degree_bits[..1],
+        )
+        .is_err());
+
+        batch_verify_programs(&stark, &PUBLIC_TABLE_KINDS, proof, &config, &degree_bits).unwrap();
+    }
 }
@@ -2,20 +2,29 @@ use std::array::from_fn;
 use std::ops::{Index, IndexMut, Neg};
 extern crate serde;
 extern crate serde_json;
+use anyhow::Result;
 use cpu::columns::CpuState;
 use itertools::{chain, izip};
 use mozak_circuits_derive::StarkSet;
+use mozak_runner::elf::Program;
+use mozak_runner::vm::ExecutionRecord;
 use plonky2::field::extension::Extendable;
+use plonky2::field::polynomial::PolynomialValues;
 use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
+use plonky2::util::log2_strict;
+use plonky2::util::timing::TimingTree;
 #[allow(clippy::wildcard_imports)]
 use plonky2_maybe_rayon::*;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_big_array::BigArray;
+use starky::stark::Stark;
 
 use crate::bitshift::columns::{Bitshift, BitshiftView};
 use crate::bitshift::stark::BitshiftStark;
+use crate::blake3::columns::{Blake3, Blake3Ctl};
+use crate::blake3::stark::Blake3Stark;
 use crate::columns_view::columns_view_impl;
 use crate::cpu::stark::CpuStark;
 use crate::cpu_skeleton::columns::{CpuSkeleton, CpuSkeletonCtl};
@@ -23,6 +32,7 @@ use crate::cpu_skeleton::stark::CpuSkeletonStark;
 use crate::cross_table_lookup::{
     Column, ColumnWithTypedInput, CrossTableLookup, CrossTableLookupWithTypedOutput,
 };
+use crate::generation::generate_traces_streaming;
 use crate::memory::columns::{Memory, MemoryCtl};
 use crate::memory::stark::MemoryStark;
 use crate::memory_fullword::columns::FullWordMemory;
@@ -62,6 +72,8 @@ use crate::register::zero_read::stark::RegisterZeroReadStark;
 use crate::register::zero_write::columns::RegisterZeroWrite;
 use crate::register::zero_write::stark::RegisterZeroWriteStark;
 use crate::register::RegisterCtl;
+use crate::sha256::columns::{Sha256, Sha256Ctl};
+use crate::sha256::stark::Sha256Stark;
 use crate::storage_device::columns::{StorageDevice, StorageDeviceCtl};
 use crate::storage_device::stark::StorageDeviceStark;
 use crate::tape_commitments::columns::{TapeCommitmentCTL, TapeCommitments};
@@ -69,13 +81,13 @@ use crate::tape_commitments::stark::TapeCommitmentsStark;
 use crate::xor::columns::{XorColumnsView, XorView};
 use crate::xor::stark::XorStark;
 use crate::{
-    bitshift, cpu, cpu_skeleton, memory, memory_fullword, memory_halfword, memory_zeroinit,
+    bitshift, blake3, cpu, cpu_skeleton, memory, memory_fullword, memory_halfword, memory_zeroinit,
     memoryinit, ops, poseidon2_output_bytes, poseidon2_sponge, program, program_multiplicities,
-    rangecheck, register, storage_device, xor,
+    rangecheck, register, sha256, storage_device, xor,
 };
 
-const NUM_CROSS_TABLE_LOOKUP: usize = 18;
-const NUM_PUBLIC_SUB_TABLES: usize = 2;
+const NUM_CROSS_TABLE_LOOKUP: usize = 23;
+const NUM_PUBLIC_SUB_TABLES: usize = 5;
 const NUM_PUBLIC_TABLES: usize = 2;
 pub const PUBLIC_TABLE_KINDS: [TableKind; NUM_PUBLIC_TABLES] =
     [TableKind::Program, TableKind::ElfMemoryInit];
@@ -86,7 +98,11 @@ pub const PUBLIC_TABLE_KINDS: [TableKind; NUM_PUBLIC_TABLES] =
 /// `F`: The [Field] that the STARK is defined over
 /// `D`: Degree of the extension field of `F`
 #[derive(Clone, StarkSet)]
-#[StarkSet(macro_name = "mozak_stark_set")]
+#[StarkSet(
+    macro_name = "mozak_stark_set",
+    kind = "TableKind",
+    dyn_stark = "StarkInfo<F, D>"
+)]
 pub struct MozakStark<F: RichField + Extendable<D>, const D: usize> {
     #[StarkSet(stark_kind = "Cpu")]
     pub cpu_stark: CpuStark<F, D>,
@@ -133,6 +149,10 @@ pub struct MozakStark<F: RichField + Extendable<D>, const D: usize> {
     pub cast_list_commitment_tape_stark: StorageDeviceStark<F, D>,
     #[StarkSet(stark_kind = "SelfProgIdTape")]
     pub self_prog_id_tape_stark: StorageDeviceStark<F, D>,
+    #[StarkSet(stark_kind = "OracleTape")]
+    pub oracle_tape_stark: StorageDeviceStark<F, D>,
+    #[StarkSet(stark_kind = "PrivateTapeCommitmentTape")]
+    pub private_tape_commitment_tape_stark: StorageDeviceStark<F, D>,
     #[StarkSet(stark_kind = "RegisterInit")]
     pub register_init_stark: RegisterInitStark<F, D>,
     #[StarkSet(stark_kind = "Register")]
@@ -147,6 +167,10 @@ pub struct MozakStark<F: RichField + Extendable<D>, const D: usize> {
     pub poseidon2_sponge_stark: Poseidon2SpongeStark<F, D>,
     #[StarkSet(stark_kind = "Poseidon2OutputBytes")]
     pub poseidon2_output_bytes_stark: Poseidon2OutputBytesStark<F, D>,
+    #[StarkSet(stark_kind = "Sha256")]
+    pub sha256_stark: Sha256Stark<F, D>,
+    #[StarkSet(stark_kind = "Blake3")]
+    pub blake3_stark: Blake3Stark<F, D>,
     #[StarkSet(stark_kind = "CpuSkeleton")]
     pub cpu_skeleton_stark: CpuSkeletonStark<F, D>,
     #[StarkSet(stark_kind = "Add")]
@@ -158,6 +182,10 @@ pub struct MozakStark<F: RichField + Extendable<D>, const D: usize> {
     pub cross_table_lookups: [CrossTableLookup; NUM_CROSS_TABLE_LOOKUP],
     pub public_sub_tables: [PublicSubTable; NUM_PUBLIC_SUB_TABLES],
     pub debug: bool,
+    /// Whether to replace the proofs of tables that take no part in any
+    /// lookup with
+    /// [`StarkProof::empty`](crate::stark::proof::StarkProof::empty).
+    pub skip_empty_tables: bool,
 }
 
 // A macro which takes metadata about `MozakStark`
@@ -351,6 +379,51 @@ tt_call::tt_call! {
 #[serde(bound = "T: Serialize + DeserializeOwned")]
 pub struct TableKindArray<T>(#[serde(with = "BigArray")] pub [T; TableKind::COUNT]);
 
+/// The size of the trace of a table, as reported by
+/// [`MozakStark::estimate_degrees`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TableSize {
+    /// Number of rows, including padding.
+    pub rows: usize,
+    pub degree_bits: usize,
+    /// Whether the table is left out of the proof, see
+    /// [`MozakStark::skip_empty_tables`].
+    pub empty: bool,
+}
+
+impl TableKind {
+    /// Whether the proof of this table may be left out, when none of its rows
+    /// take part in a lookup.
+    ///
+    /// Leaving out the proof of a table amounts to proving it with a trace
+    /// that does not take part in any lookup.  That is only sound for tables
+    /// where such a trace is valid, and which neither have public inputs nor
+    /// public sub tables.  Tables that go into the program id may never be
+    /// left out.
+    #[must_use]
+    pub const fn may_be_empty(self) -> bool {
+        matches!(
+            self,
+            TableKind::HalfWordMemory
+                | TableKind::FullWordMemory
+                | TableKind::StorageDevicePrivate
+                | TableKind::StorageDevicePublic
+                | TableKind::CallTape
+                | TableKind::EventTape
+                | TableKind::EventsCommitmentTape
+                | TableKind::CastListCommitmentTape
+                | TableKind::SelfProgIdTape
+                | TableKind::OracleTape
+                | TableKind::PrivateTapeCommitmentTape
+                | TableKind::Poseidon2
+                | TableKind::Poseidon2Sponge
+                | TableKind::Poseidon2OutputBytes
+                | TableKind::Sha256
+                | TableKind::Blake3
+        )
+    }
+}
+
 impl<T: Default> Default for TableKindArray<T> {
     fn default() -> Self { TableKindArray(from_fn(|_| T::default())) }
 }
@@ -406,6 +479,32 @@ impl<T> TableKindArray<T> {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> { self.0.iter_mut() }
 }
 
+/// The parts of a [`Stark`] that do not depend on its types, so that starks of
+/// different kinds can be handled together, eg through [`MozakStark::get`].
+pub trait StarkInfo<F: RichField + Extendable<D>, const D: usize>: std::fmt::Display {
+    fn columns(&self) -> usize;
+    fn public_inputs(&self) -> usize;
+    fn constraint_degree(&self) -> usize;
+    fn quotient_degree_factor(&self) -> usize;
+    fn requires_ctls(&self) -> bool;
+}
+
+impl<F, S, const D: usize> StarkInfo<F, D> for S
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D> + std::fmt::Display,
+{
+    fn columns(&self) -> usize { S::COLUMNS }
+
+    fn public_inputs(&self) -> usize { S::PUBLIC_INPUTS }
+
+    fn constraint_degree(&self) -> usize { Stark::constraint_degree(self) }
+
+    fn quotient_degree_factor(&self) -> usize { Stark::quotient_degree_factor(self) }
+
+    fn requires_ctls(&self) -> bool { Stark::requires_ctls(self) }
+}
+
 columns_view_impl!(PublicInputs);
 
 #[repr(C)]
@@ -441,9 +540,13 @@ impl<F: RichField + Extendable<D>, const D: usize> Default for MozakStark<F, D>
             events_commitment_tape_stark: StorageDeviceStark::default(),
             cast_list_commitment_tape_stark: StorageDeviceStark::default(),
             self_prog_id_tape_stark: StorageDeviceStark::default(),
+            oracle_tape_stark: StorageDeviceStark::default(),
+            private_tape_commitment_tape_stark: StorageDeviceStark::default(),
             poseidon2_sponge_stark: Poseidon2SpongeStark::default(),
             poseidon2_stark: Poseidon2_12Stark::default(),
             poseidon2_output_bytes_stark: Poseidon2OutputBytesStark::default(),
+            sha256_stark: Sha256Stark::default(),
+            blake3_stark: Blake3Stark::default(),
             cpu_skeleton_stark: CpuSkeletonStark::default(),
             add_stark: AddStark::default(),
             blt_taken_stark: BltTakenStark::default(),
@@ -467,15 +570,24 @@ impl<F: RichField + Extendable<D>, const D: usize> Default for MozakStark<F, D>
                 Poseidon2SpongeCpuTable::lookups(),
                 Poseidon2Poseidon2SpongeTable::lookups(),
                 Poseidon2OutputBytesPoseidon2SpongeTable::lookups(),
+                Sha256CpuTable::lookups(),
+                Blake3CpuTable::lookups(),
                 CpuToSkeletonTable::lookups(),
                 EventCommitmentTapeIOLookupTable::lookups(),
                 CastlistCommitmentTapeIOLookupTable::lookups(),
+                OracleTapeIOLookupTable::lookups(),
+                PrivateTapeDisclosureLookupTable::lookups(),
+                PrivateTapeCommitmentTapeIOLookupTable::lookups(),
             ],
             public_sub_tables: [
                 crate::tape_commitments::columns::make_event_commitment_tape_public(),
                 crate::tape_commitments::columns::make_castlist_commitment_tape_public(),
+                crate::tape_commitments::columns::make_oracle_tape_public(),
+                crate::tape_commitments::columns::make_private_tape_disclosure_public(),
+                crate::tape_commitments::columns::make_private_tape_commitment_tape_public(),
             ],
             debug: false,
+            skip_empty_tables: false,
         }
     }
 }
@@ -488,6 +600,58 @@ impl<F: RichField + Extendable<D>, const D: usize> MozakStark<F, D> {
             ..Self::default()
         }
     }
+
+    /// Reports the number of rows and the degree bits of the trace of each
+    /// table for proving `record`, without proving it.  This is for picking a
+    /// [`StarkConfig`](starky::config::StarkConfig), or rejecting programs
+    /// that are too large to prove, before spending time on proving.
+    ///
+    /// The traces are generated to find out their sizes, which takes a lot
+    /// less time than proving them.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the traces can not be generated, see
+    /// [`generate_traces_streaming`].
+    pub fn estimate_degrees(
+        &self,
+        program: &Program,
+        record: &ExecutionRecord<F>,
+    ) -> Result<TableKindArray<TableSize>> {
+        let mut sizes = TableKindArray::default();
+        generate_traces_streaming::<F, D>(
+            program,
+            record,
+            &mut TimingTree::default(),
+            |kind, trace| {
+                let rows = trace.first().map_or(0, PolynomialValues::len);
+                sizes[kind] = TableSize {
+                    rows,
+                    degree_bits: log2_strict(rows),
+                    empty: self.is_empty_table(kind, &trace),
+                };
+            },
+        )?;
+        Ok(sizes)
+    }
+
+    /// Whether the proof of the table of `kind`, with `trace`, is to be
+    /// replaced with an empty proof: the table has to be allowed to be empty,
+    /// and none of its rows may be looked up or looking up anything.
+    #[must_use]
+    pub fn is_empty_table(&self, kind: TableKind, trace: &[PolynomialValues<F>]) -> bool {
+        self.skip_empty_tables
+            && kind.may_be_empty()
+            && self
+                .cross_table_lookups
+                .iter()
+                .flat_map(|ctl| &ctl.looking_tables)
+                .filter(|table| table.kind == kind)
+                .all(|table| {
+                    let filter = table.filter_column.to_field();
+                    (0..trace[0].len()).all(|row| filter.eval_table(trace, row).is_zero())
+                })
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -578,6 +742,22 @@ macro_rules! table_impl {
                     filter_column: filter_column.into(),
                 }
             }
+
+            /// Declares the `num_rows` rows of this table where
+            /// `filter_column` is one as public, with only `columns`
+            /// revealed.
+            pub fn public<RowIn>(
+                columns: RowIn,
+                filter_column: ColumnWithTypedInput<$input_table_type<i64>>,
+                num_rows: usize,
+            ) -> PublicSubTable
+            where
+                RowIn: IntoIterator<Item = ColumnWithTypedInput<$input_table_type<i64>>>, {
+                PublicSubTable {
+                    table: new(columns, filter_column),
+                    num_rows,
+                }
+            }
         }
     };
 }
@@ -652,6 +832,12 @@ table_impl!(
     TableKind::SelfProgIdTape,
     StorageDevice
 );
+table_impl!(OracleTapeTable, TableKind::OracleTape, StorageDevice);
+table_impl!(
+    PrivateTapeCommitmentTapeTable,
+    TableKind::PrivateTapeCommitmentTape,
+    StorageDevice
+);
 table_impl!(
     Poseidon2SpongeTable,
     TableKind::Poseidon2Sponge,
@@ -668,6 +854,8 @@ table_impl!(
     TableKind::Poseidon2OutputBytes,
     Poseidon2OutputBytes
 );
+table_impl!(Sha256Table, TableKind::Sha256, Sha256);
+table_impl!(Blake3Table, TableKind::Blake3, Blake3);
 table_impl!(SkeletonTable, TableKind::CpuSkeleton, CpuSkeleton);
 table_impl!(AddTable, TableKind::Add, Add);
 table_impl!(BltTakenTable, TableKind::BltTaken, BltTaken);
@@ -744,12 +932,16 @@ impl Lookups for IntoMemoryTable {
                 TableKind::EventsCommitmentTape,
                 TableKind::CastListCommitmentTape,
                 TableKind::SelfProgIdTape,
+                TableKind::OracleTape,
+                TableKind::PrivateTapeCommitmentTape,
             ]
             .map(storage_device::columns::lookup_for_memory),
             memory_fullword::columns::lookup_for_memory_limb(),
             memory_halfword::columns::lookup_for_memory_limb(),
             poseidon2_sponge::columns::lookup_for_input_memory(),
             poseidon2_output_bytes::columns::lookup_for_output_memory(),
+            sha256::columns::lookup_for_memory(),
+            blake3::columns::lookup_for_memory(),
         ]
         .collect();
         CrossTableLookupWithTypedOutput::new(tables, vec![memory::columns::lookup_for_cpu()])
@@ -822,6 +1014,7 @@ impl Lookups for RangeCheckU8LookupTable {
         let looking: Vec<TableWithTypedOutput<RangeCheckCtl<Column>>> = chain![
             rangecheck_looking(),
             memory::columns::rangecheck_u8_looking(),
+            cpu::columns::rangecheck_u8_looking(),
         ]
         .collect();
         CrossTableLookupWithTypedOutput::new(looking, vec![crate::rangecheck_u8::columns::lookup()])
@@ -867,6 +1060,8 @@ impl Lookups for RegisterLookups {
                 ops::blt_taken::columns::register_looking(),
                 crate::storage_device::columns::register_looking(),
                 crate::poseidon2_sponge::columns::register_looking(),
+                crate::sha256::columns::register_looking(),
+                crate::blake3::columns::register_looking(),
                 vec![crate::register::init::columns::lookup_for_register()],
             ]
             .collect(),
@@ -895,6 +1090,8 @@ impl Lookups for StorageDeviceToCpuTable {
                     TableKind::EventsCommitmentTape,
                     TableKind::CastListCommitmentTape,
                     TableKind::SelfProgIdTape,
+                    TableKind::OracleTape,
+                    TableKind::PrivateTapeCommitmentTape,
                 ],
                 0..
             )
@@ -944,6 +1141,30 @@ impl Lookups for Poseidon2OutputBytesPoseidon2SpongeTable {
     }
 }
 
+pub struct Sha256CpuTable;
+
+impl Lookups for Sha256CpuTable {
+    type Row = Sha256Ctl<Column>;
+
+    fn lookups_with_typed_output() -> CrossTableLookupWithTypedOutput<Self::Row> {
+        CrossTableLookupWithTypedOutput::new(vec![crate::sha256::columns::lookup_for_cpu()], vec![
+            crate::cpu::columns::lookup_for_sha256(),
+        ])
+    }
+}
+
+pub struct Blake3CpuTable;
+
+impl Lookups for Blake3CpuTable {
+    type Row = Blake3Ctl<Column>;
+
+    fn lookups_with_typed_output() -> CrossTableLookupWithTypedOutput<Self::Row> {
+        CrossTableLookupWithTypedOutput::new(vec![crate::blake3::columns::lookup_for_cpu()], vec![
+            crate::cpu::columns::lookup_for_blake3(),
+        ])
+    }
+}
+
 pub struct EventCommitmentTapeIOLookupTable;
 
 impl Lookups for EventCommitmentTapeIOLookupTable {
@@ -969,3 +1190,45 @@ impl Lookups for CastlistCommitmentTapeIOLookupTable {
         )
     }
 }
+
+pub struct OracleTapeIOLookupTable;
+
+impl Lookups for OracleTapeIOLookupTable {
+    type Row = TapeCommitmentCTL<Column>;
+
+    fn lookups_with_typed_output() -> CrossTableLookupWithTypedOutput<Self::Row> {
+        CrossTableLookupWithTypedOutput::new(
+            vec![crate::storage_device::columns::oracle_lookup_in_tape_commitments()],
+            vec![crate::tape_commitments::columns::lookup_for_oracle_tape()],
+        )
+    }
+}
+
+pub struct PrivateTapeDisclosureLookupTable;
+
+impl Lookups for PrivateTapeDisclosureLookupTable {
+    type Row = TapeCommitmentCTL<Column>;
+
+    fn lookups_with_typed_output() -> CrossTableLookupWithTypedOutput<Self::Row> {
+        CrossTableLookupWithTypedOutput::new(
+            vec![crate::storage_device::columns::disclosure_lookup_in_tape_commitments()],
+            vec![crate::tape_commitments::columns::lookup_for_private_tape_disclosure()],
+        )
+    }
+}
+
+pub struct PrivateTapeCommitmentTapeIOLookupTable;
+
+impl Lookups for PrivateTapeCommitmentTapeIOLookupTable {
+    type Row = TapeCommitmentCTL<Column>;
+
+    fn lookups_with_typed_output() -> CrossTableLookupWithTypedOutput<Self::Row> {
+        CrossTableLookupWithTypedOutput::new(
+            vec![
+                crate::storage_device::columns::private_tape_commitment_lookup_in_tape_commitments(
+                ),
+            ],
+            vec![crate::tape_commitments::columns::lookup_for_private_tape_commitment_tape()],
+        )
+    }
+}
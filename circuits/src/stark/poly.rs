@@ -103,8 +103,7 @@ where
                     local_z: get_ctl_zs_values_packed(i_start, step)[i],
                     next_z: get_ctl_zs_values_packed(i_next_start, step)[i],
                     challenges: zs_columns.challenge,
-                    columns: &zs_columns.columns,
-                    filter_column: &zs_columns.filter_column,
+                    lookups: &zs_columns.lookups,
                 })
                 .collect::<Vec<_>>();
             eval_vanishing_poly::<F, F, P, S, D, 1>(stark, &vars, &ctl_vars, &mut consumer);
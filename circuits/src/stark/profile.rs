@@ -0,0 +1,176 @@
+//! Profiles of the starks of a [`MozakStark`]: how wide their traces are, how
+//! many constraints they have and of which degree, and how much they take part
+//! in cross-table lookups.
+//!
+//! See `benches/stark_profile.rs` for a report of all tables.
+
+use itertools::Itertools;
+use plonky2::field::extension::Extendable;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::util::log2_strict;
+use serde::{Deserialize, Serialize};
+use starky::constraint_consumer::ConstraintConsumer;
+use starky::evaluation_frame::StarkEvaluationFrame;
+use starky::stark::Stark;
+
+use super::mozak_stark::{all_starks, MozakStark, TableKind, TableKindArray};
+use crate::cross_table_lookup::CrossTableLookup;
+
+/// Number of points the constraints are evaluated at to find their degrees.
+/// Degrees of this or above are not told apart.
+const DEGREE_POINTS: usize = 16;
+
+/// The shape of a single stark, as reported by [`MozakStark::profile`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StarkProfile {
+    pub columns: usize,
+    pub public_inputs: usize,
+    /// Number of constraints.  Constraints that vanish identically before the
+    /// first one that does not are not counted.
+    pub constraints: usize,
+    /// Highest degree of any of the constraints, not counting the first row,
+    /// last row or transition selectors.
+    pub max_constraint_degree: usize,
+    /// Degree the stark declares its constraints to be bounded by.
+    pub declared_constraint_degree: usize,
+    /// Number of times the table takes part in a cross-table lookup.
+    pub lookups: usize,
+    /// Number of CTL helper columns per challenge.
+    pub ctl_zs: usize,
+    pub public_sub_tables: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> MozakStark<F, D> {
+    /// Reports the shape of each stark.
+    ///
+    /// The constraints are counted and their degrees found by evaluating
+    /// them on a line through the trace, so this does not need a trace.
+    #[must_use]
+    pub fn profile(&self) -> TableKindArray<StarkProfile> {
+        all_starks!(self, |stark, kind| profile_stark(
+            stark,
+            kind,
+            &self.cross_table_lookups,
+            self.public_sub_tables
+                .iter()
+                .filter(|public_sub_table| public_sub_table.table.kind == kind)
+                .count(),
+        ))
+    }
+}
+
+fn profile_stark<F, S, const D: usize>(
+    stark: &S,
+    kind: TableKind,
+    ctls: &[CrossTableLookup],
+    public_sub_tables: usize,
+) -> StarkProfile
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>, {
+    let (constraints, max_constraint_degree) = constraint_degrees(stark);
+    StarkProfile {
+        columns: S::COLUMNS,
+        public_inputs: S::PUBLIC_INPUTS,
+        constraints,
+        max_constraint_degree,
+        declared_constraint_degree: stark.constraint_degree(),
+        lookups: ctls
+            .iter()
+            .flat_map(|ctl| &ctl.looking_tables)
+            .filter(|table| table.kind == kind)
+            .count(),
+        ctl_zs: CrossTableLookup::num_ctl_zs(ctls, kind, 1),
+        public_sub_tables,
+    }
+}
+
+/// Returns the number of constraints of `stark`, and their highest degree.
+///
+/// The constraints are evaluated at the points `a + b t` of a line through
+/// the trace, for `t` ranging over a subgroup of size [`DEGREE_POINTS`].
+/// Constraint `i` of `n` shows up as the coefficient of `alpha^(n - 1 - i)` in
+/// the accumulated constraints, so evaluating them at all elements `alpha` of
+/// a large enough subgroup and interpolating recovers each constraint.
+/// Interpolating a constraint over `t` then gives its degree.
+fn constraint_degrees<F, S, const D: usize>(stark: &S) -> (usize, usize)
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>, {
+    let width = 2 * S::COLUMNS + S::PUBLIC_INPUTS;
+    // Powers of a generator are as good as random points here, and keep the
+    // report deterministic.
+    let (offsets, slopes): (Vec<F>, Vec<F>) = F::MULTIPLICATIVE_GROUP_GENERATOR
+        .powers()
+        .skip(1)
+        .take(2 * width)
+        .tuples()
+        .unzip();
+    let points = F::two_adic_subgroup(log2_strict(DEGREE_POINTS));
+    let highest_nonzero = |coeffs: &[F]| coeffs.iter().rposition(|c| c.is_nonzero());
+
+    let mut num_alphas = 1 << 8;
+    loop {
+        let alphas = F::two_adic_subgroup(log2_strict(num_alphas));
+        let accumulated = points
+            .iter()
+            .map(|&t| {
+                let values = offsets
+                    .iter()
+                    .zip(&slopes)
+                    .map(|(&a, &b)| a + b * t)
+                    .collect_vec();
+                let (local_values, rest) = values.split_at(S::COLUMNS);
+                let (next_values, public_inputs) = rest.split_at(S::COLUMNS);
+                let vars =
+                    StarkEvaluationFrame::from_values(local_values, next_values, public_inputs);
+                let mut consumer = ConstraintConsumer::new(alphas.clone(), F::ONE, F::ONE, F::ONE);
+                stark.eval_packed_generic::<F, F, 1>(&vars, &mut consumer);
+                PolynomialValues::new(consumer.accumulators()).ifft().coeffs
+            })
+            .collect_vec();
+        let constraints = accumulated
+            .iter()
+            .filter_map(|coeffs| highest_nonzero(coeffs))
+            .max()
+            .map_or(0, |i| i + 1);
+        // With as many constraints as `alpha`s, they might have wrapped
+        // around.
+        if constraints < num_alphas {
+            let max_constraint_degree = (0..constraints)
+                .filter_map(|i| {
+                    let values = accumulated.iter().map(|coeffs| coeffs[i]).collect_vec();
+                    highest_nonzero(&PolynomialValues::new(values).ifft().coeffs)
+                })
+                .max()
+                .unwrap_or_default();
+            return (constraints, max_constraint_degree);
+        }
+        num_alphas *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::columns_view::NumberOfColumns;
+    use crate::test_utils::{D, F};
+    use crate::xor::columns::XorColumnsView;
+
+    #[test]
+    fn profile_of_xor() {
+        let profile = MozakStark::<F, D>::default().profile();
+        let xor = profile[TableKind::Xor];
+        assert_eq!(xor.columns, XorColumnsView::<()>::NUMBER_OF_COLUMNS);
+        assert_eq!(xor.public_inputs, 0);
+        // Binary limbs, their reconstruction, and the xor of each bit.
+        assert_eq!(xor.constraints, 3 * 32 + 3 + 32);
+        assert_eq!(xor.max_constraint_degree, 2);
+        assert!(xor.lookups > 0);
+        for profile in profile.iter() {
+            assert!(profile.max_constraint_degree <= profile.declared_constraint_degree);
+        }
+    }
+}
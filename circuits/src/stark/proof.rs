@@ -1,8 +1,10 @@
+use anyhow::{bail, ensure, Context, Result};
 use itertools::{chain, Itertools};
-use mozak_sdk::common::types::ProgramIdentifier;
+use mozak_sdk::common::types::{Poseidon2Hash, ProgramIdentifier};
 use mozak_sdk::core::constants::DIGEST_BYTES;
 use plonky2::batch_fri::oracle::BatchFriOracle;
 use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::polynomial::PolynomialCoeffs;
 use plonky2::fri::oracle::PolynomialBatch;
 use plonky2::fri::proof::{FriChallenges, FriChallengesTarget, FriProof, FriProofTarget};
 use plonky2::fri::structure::{
@@ -14,6 +16,7 @@ use plonky2::iop::challenger::{Challenger, RecursiveChallenger};
 use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::iop::target::Target;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::CircuitConfig;
 use plonky2::plonk::config::{AlgebraicHasher, GenericConfig, GenericHashOut, Hasher};
 #[allow(clippy::wildcard_imports)]
 use plonky2_maybe_rayon::*;
@@ -60,6 +63,50 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> S
 
     pub fn num_ctl_zs(&self) -> usize { self.openings.ctl_zs_last.len() }
 
+    /// The proof of a table that takes no part in any lookup, with
+    /// `num_ctl_zs` Z(x) polynomials.
+    ///
+    /// Its trace cap is all zeros, and its Z(x) polynomials all end in zero.
+    /// Everything else is left empty.
+    #[must_use]
+    pub fn empty(config: &StarkConfig, num_ctl_zs: usize) -> Self {
+        Self {
+            trace_cap: Self::empty_trace_cap(config),
+            ctl_zs_cap: MerkleCap(vec![]),
+            quotient_polys_cap: MerkleCap(vec![]),
+            openings: StarkOpeningSet {
+                local_values: vec![],
+                next_values: vec![],
+                ctl_zs: vec![],
+                ctl_zs_next: vec![],
+                ctl_zs_last: vec![F::ZERO; num_ctl_zs],
+                quotient_polys: vec![],
+            },
+            opening_proof: FriProof {
+                commit_phase_merkle_caps: vec![],
+                query_round_proofs: vec![],
+                final_poly: PolynomialCoeffs::empty(),
+                pow_witness: F::ZERO,
+            },
+        }
+    }
+
+    /// The trace cap of [`Self::empty`] proofs, which stands in for the
+    /// commitment to their trace.
+    #[must_use]
+    pub fn empty_trace_cap(config: &StarkConfig) -> MerkleCap<F, C::Hasher> {
+        let zero = <C::Hasher as Hasher<F>>::Hash::from_bytes(&vec![
+            0;
+            <C::Hasher as Hasher<F>>::HASH_SIZE
+        ]);
+        MerkleCap(vec![zero; 1 << config.fri_config.cap_height])
+    }
+
+    /// Whether this is an [`Self::empty`] proof.  Every other proof commits to
+    /// at least one Z(x) polynomial.
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.ctl_zs_cap.0.is_empty() }
+
     /// Computes all Fiat-Shamir challenges used in the STARK proof.
     pub(crate) fn get_challenges(
         &self,
@@ -383,8 +430,22 @@ pub struct BatchProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>,
     pub batch_stark_proof: StarkProof<F, C, D>,
 }
 
+/// A batch proof for several programs, e.g. all programs in the cast list of
+/// a transaction.  Each program carries its own public table proofs, public
+/// inputs and program id, while the remaining tables of all programs are
+/// opened by the one shared `batch_stark_proof`.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct MultiBatchProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+{
+    pub programs: Vec<AllProof<F, C, D>>,
+    pub batch_stark_proof: StarkProof<F, C, D>,
+}
+
 pub(crate) struct AllProofChallenges<F: RichField + Extendable<D>, const D: usize> {
-    pub stark_challenges: TableKindArray<StarkProofChallenges<F, D>>,
+    /// The challenges of each table, except for empty ones.
+    pub stark_challenges: TableKindArray<Option<StarkProofChallenges<F, D>>>,
     pub ctl_challenges: GrandProductChallengeSet<F>,
 }
 
@@ -404,7 +465,8 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> A
 
         AllProofChallenges {
             stark_challenges: all_kind!(|kind| {
-                self.proofs[kind].get_challenges(&mut challenger.clone(), config)
+                let proof = &self.proofs[kind];
+                (!proof.is_empty()).then(|| proof.get_challenges(&mut challenger.clone(), config))
             }),
             ctl_challenges,
         }
@@ -461,3 +523,437 @@ macro_rules! impl_proof_common {
 
 impl_proof_common!(AllProof);
 impl_proof_common!(BatchProof);
+
+/// The magic bytes a proof in the binary format starts with.
+pub const PROOF_MAGIC: [u8; 4] = *b"MZKP";
+
+/// The version of the binary proof format.  Bump it whenever the layout of
+/// the proofs changes, so that older proofs are rejected up front rather than
+/// misread.
+pub const PROOF_FORMAT_VERSION: u16 = 2;
+
+/// Flag of a [`ProofHeader`] whose payload is zstd compressed.
+const FLAG_ZSTD: u8 = 1;
+
+/// The length of the header of the binary format: [`PROOF_MAGIC`], the
+/// little-endian [`PROOF_FORMAT_VERSION`], the [`BinaryProofKind`], the
+/// flags, the program id and the config digest.
+const PROOF_HEADER_BYTES: usize = PROOF_MAGIC.len() + 4 + 2 * DIGEST_BYTES;
+
+/// What a proof in the binary format holds, as recorded in its header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BinaryProofKind {
+    All,
+    Batch,
+    /// A plonky2 `ProofWithPublicInputs` of the recursive verifier, in
+    /// plonky2's byte format.
+    Recursive,
+}
+
+impl BinaryProofKind {
+    const fn tag(self) -> u8 {
+        match self {
+            BinaryProofKind::All => 0,
+            BinaryProofKind::Batch => 1,
+            BinaryProofKind::Recursive => 2,
+        }
+    }
+
+    /// Reads the header of `bytes`, and returns what the proof holds, or
+    /// `None` if `bytes` are not in the binary format.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the header is malformed.  See [`ProofHeader::read`].
+    pub fn detect(bytes: &[u8]) -> Result<Option<Self>> {
+        Ok(ProofHeader::read(bytes)?.map(|header| header.kind))
+    }
+}
+
+/// The header of a proof in the binary format.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ProofHeader {
+    pub kind: BinaryProofKind,
+    /// Whether the payload is zstd compressed.
+    pub compressed: bool,
+    pub program_id: ProgramIdentifier,
+    /// Digest of the config the proof was made with.  See
+    /// [`stark_config_digest`] and [`circuit_config_digest`].
+    pub config_digest: [u8; DIGEST_BYTES],
+}
+
+impl ProofHeader {
+    /// Reads the header of `bytes`, or returns `None` if `bytes` are not in
+    /// the binary format.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the header is of another version of the format, of an
+    /// unknown kind of proof, or has unknown flags set.
+    pub fn read(bytes: &[u8]) -> Result<Option<Self>> {
+        if !bytes.starts_with(&PROOF_MAGIC) {
+            return Ok(None);
+        }
+        let header = bytes
+            .get(PROOF_MAGIC.len()..PROOF_HEADER_BYTES)
+            .context("truncated proof header")?;
+        let (version, rest) = header.split_at(2);
+        let version = u16::from_le_bytes(version.try_into()?);
+        ensure!(
+            version == PROOF_FORMAT_VERSION,
+            "proof is in version {version} of the binary format, but only version \
+             {PROOF_FORMAT_VERSION} is supported"
+        );
+        let ([tag, flags], rest) = (rest[..2].try_into()?, &rest[2..]);
+        ensure!(flags & !FLAG_ZSTD == 0, "unknown proof flags {flags:#04x}");
+        let (program_id, config_digest) = rest.split_at(DIGEST_BYTES);
+        Ok(Some(Self {
+            kind: match tag {
+                0 => BinaryProofKind::All,
+                1 => BinaryProofKind::Batch,
+                2 => BinaryProofKind::Recursive,
+                _ => bail!("unknown kind of proof {tag}"),
+            },
+            compressed: flags & FLAG_ZSTD != 0,
+            program_id: ProgramIdentifier(Poseidon2Hash(program_id.try_into()?)),
+            config_digest: config_digest.try_into()?,
+        }))
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend(PROOF_MAGIC);
+        bytes.extend(PROOF_FORMAT_VERSION.to_le_bytes());
+        bytes.push(self.kind.tag());
+        bytes.push(if self.compressed { FLAG_ZSTD } else { 0 });
+        bytes.extend(self.program_id.inner());
+        bytes.extend(self.config_digest);
+    }
+}
+
+/// Digest of the config of a STARK proof, as recorded in the header of
+/// [`ProofContainer`]s.
+///
+/// # Errors
+///
+/// Errors if the FRI config does not serialize.
+pub fn stark_config_digest<F, C, const D: usize>(
+    config: &StarkConfig,
+) -> Result<[u8; DIGEST_BYTES]>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>, {
+    let mut bytes = vec![];
+    bytes.extend(u64::try_from(config.security_bits)?.to_le_bytes());
+    bytes.extend(u64::try_from(config.num_challenges)?.to_le_bytes());
+    bytes.extend(codec::serialize(&config.fri_config)?);
+    Ok(config_digest::<F, C, D>(&bytes))
+}
+
+/// Digest of the config of the circuit a recursive proof is for, as recorded
+/// in the header of [`ProofContainer`]s.
+///
+/// # Errors
+///
+/// Errors if `config` does not serialize.
+pub fn circuit_config_digest<F, C, const D: usize>(
+    config: &CircuitConfig,
+) -> Result<[u8; DIGEST_BYTES]>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>, {
+    Ok(config_digest::<F, C, D>(&codec::serialize(config)?))
+}
+
+fn config_digest<F, C, const D: usize>(bytes: &[u8]) -> [u8; DIGEST_BYTES]
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>, {
+    let elements = bytes.iter().map(|&b| F::from_canonical_u8(b)).collect_vec();
+    <C::Hasher as Hasher<F>>::hash_pad(&elements)
+        .to_bytes()
+        .try_into()
+        .unwrap()
+}
+
+/// Serialization and compression of the payload of [`ProofContainer`]s, and
+/// of configs for their digests.
+#[cfg(feature = "container")]
+mod codec {
+    use anyhow::{Context, Result};
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    pub fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>> { Ok(bincode::serialize(value)?) }
+
+    pub fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+
+    pub fn compress(bytes: &[u8]) -> Result<Vec<u8>> {
+        Ok(zstd::encode_all(bytes, zstd::DEFAULT_COMPRESSION_LEVEL)?)
+    }
+
+    pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>> {
+        zstd::decode_all(bytes).context("corrupt compressed proof")
+    }
+}
+
+/// Without the `container` feature, only uncompressed recursive proofs can be
+/// read from the binary format, as they need neither codec.
+#[cfg(not(feature = "container"))]
+mod codec {
+    use anyhow::{bail, Result};
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    const UNSUPPORTED: &str = "the binary proof format needs the `container` feature of \
+                               mozak-circuits for anything but uncompressed recursive proofs";
+
+    pub fn serialize<T: Serialize>(_value: &T) -> Result<Vec<u8>> { bail!(UNSUPPORTED) }
+
+    pub fn deserialize<T: DeserializeOwned>(_bytes: &[u8]) -> Result<T> { bail!(UNSUPPORTED) }
+
+    pub fn compress(_bytes: &[u8]) -> Result<Vec<u8>> { bail!(UNSUPPORTED) }
+
+    pub fn decompress(_bytes: &[u8]) -> Result<Vec<u8>> { bail!(UNSUPPORTED) }
+}
+
+/// The proof a [`ProofContainer`] holds.
+#[derive(Clone, Debug)]
+pub enum ProofPayload<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    All(AllProof<F, C, D>),
+    Batch(BatchProof<F, C, D>),
+    /// A recursive proof, in plonky2's byte format.  Reading it back needs
+    /// the common data of the circuit, which the container does not hold.
+    Recursive(Vec<u8>),
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    ProofPayload<F, C, D>
+{
+    #[must_use]
+    pub const fn kind(&self) -> BinaryProofKind {
+        match self {
+            ProofPayload::All(_) => BinaryProofKind::All,
+            ProofPayload::Batch(_) => BinaryProofKind::Batch,
+            ProofPayload::Recursive(_) => BinaryProofKind::Recursive,
+        }
+    }
+}
+
+/// Any kind of proof, together with the header that lets a verifier route it
+/// without knowing up front what it holds.
+#[derive(Clone, Debug)]
+pub struct ProofContainer<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+{
+    pub program_id: ProgramIdentifier,
+    pub config_digest: [u8; DIGEST_BYTES],
+    pub payload: ProofPayload<F, C, D>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    ProofContainer<F, C, D>
+{
+    /// Encodes the container in the binary format, with the payload zstd
+    /// compressed if `compress` is set.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the payload does not serialize or compress.
+    pub fn to_bytes(&self, compress: bool) -> Result<Vec<u8>> {
+        let payload = match &self.payload {
+            ProofPayload::All(proof) => codec::serialize(proof)?,
+            ProofPayload::Batch(proof) => codec::serialize(proof)?,
+            ProofPayload::Recursive(bytes) => bytes.clone(),
+        };
+        let mut bytes = vec![];
+        self.header(compress).write(&mut bytes);
+        if compress {
+            bytes.extend(codec::compress(&payload)?);
+        } else {
+            bytes.extend(payload);
+        }
+        Ok(bytes)
+    }
+
+    /// Decodes a container written by [`Self::to_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// Errors if `bytes` are not in the binary format, or are malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let header = ProofHeader::read(bytes)?.context("not a proof in the binary format")?;
+        let payload = &bytes[PROOF_HEADER_BYTES..];
+        let payload = if header.compressed {
+            codec::decompress(payload)?
+        } else {
+            payload.to_vec()
+        };
+        Ok(Self {
+            program_id: header.program_id,
+            config_digest: header.config_digest,
+            payload: match header.kind {
+                BinaryProofKind::All => ProofPayload::All(codec::deserialize(&payload)?),
+                BinaryProofKind::Batch => ProofPayload::Batch(codec::deserialize(&payload)?),
+                BinaryProofKind::Recursive => ProofPayload::Recursive(payload),
+            },
+        })
+    }
+
+    #[must_use]
+    pub const fn header(&self, compressed: bool) -> ProofHeader {
+        ProofHeader {
+            kind: self.payload.kind(),
+            compressed,
+            program_id: self.program_id,
+            config_digest: self.config_digest,
+        }
+    }
+}
+
+macro_rules! impl_proof_codec {
+    ($struct_name:ident, $variant:ident) => {
+        impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+            $struct_name<F, C, D>
+        {
+            /// Wraps the proof in a [`ProofContainer`], for the binary
+            /// format, which is a lot more compact, and faster to read back,
+            /// than JSON.
+            ///
+            /// # Errors
+            ///
+            /// Errors if the FRI config of `config` does not serialize.
+            pub fn into_container(self, config: &StarkConfig) -> Result<ProofContainer<F, C, D>> {
+                Ok(ProofContainer {
+                    program_id: self.program_id,
+                    config_digest: stark_config_digest::<F, C, D>(config)?,
+                    payload: ProofPayload::$variant(self),
+                })
+            }
+
+            /// Decodes a proof in the binary format, or serialized as JSON.
+            ///
+            /// # Errors
+            ///
+            /// Errors if `bytes` hold neither, or another kind of proof.
+            pub fn decode(bytes: &[u8]) -> Result<Self> {
+                if BinaryProofKind::detect(bytes)?.is_none() {
+                    return Ok(serde_json::from_slice(bytes)?);
+                }
+                match ProofContainer::from_bytes(bytes)?.payload {
+                    ProofPayload::$variant(proof) => Ok(proof),
+                    payload => bail!(
+                        "expected a proof of kind {:?}, got {:?}",
+                        BinaryProofKind::$variant,
+                        payload.kind()
+                    ),
+                }
+            }
+        }
+    };
+}
+
+impl_proof_codec!(AllProof, All);
+impl_proof_codec!(BatchProof, Batch);
+
+#[cfg(all(test, feature = "container"))]
+mod tests {
+    use anyhow::Result;
+    use mozak_runner::code;
+    use mozak_runner::instruction::{Args, Instruction, Op};
+    use mozak_sdk::core::constants::DIGEST_BYTES;
+    use plonky2::util::timing::TimingTree;
+    use starky::config::StarkConfig;
+
+    use super::{
+        stark_config_digest, AllProof, BatchProof, BinaryProofKind, ProofContainer, ProofHeader,
+        ProofPayload, PROOF_FORMAT_VERSION, PROOF_MAGIC,
+    };
+    use crate::stark::mozak_stark::{MozakStark, PublicInputs};
+    use crate::stark::prover::prove;
+    use crate::stark::verifier::verify_proof;
+    use crate::test_utils::{fast_test_config, C, D, F};
+    use crate::utils::from_u32;
+
+    #[test]
+    fn binary_proof_roundtrip() -> Result<()> {
+        let stark = MozakStark::default();
+        let config = fast_test_config();
+        let (program, record) = code::execute(
+            [Instruction {
+                op: Op::ADD,
+                args: Args {
+                    rd: 5,
+                    rs1: 6,
+                    rs2: 7,
+                    ..Args::default()
+                },
+            }],
+            &[],
+            &[(6, 100), (7, 200)],
+        );
+        let public_inputs = PublicInputs {
+            entry_point: from_u32(program.entry_point),
+        };
+        let proof = prove::<F, C, D>(
+            &program,
+            &record,
+            &stark,
+            &config,
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+
+        let container = proof.clone().into_container(&config)?;
+        let binary = container.to_bytes(false)?;
+        let compressed = container.to_bytes(true)?;
+        let json = serde_json::to_vec(&proof)?;
+        assert!(binary.len() < json.len());
+        assert!(compressed.len() < binary.len());
+        assert_eq!(
+            ProofHeader::read(&compressed)?,
+            Some(ProofHeader {
+                kind: BinaryProofKind::All,
+                compressed: true,
+                program_id: proof.program_id,
+                config_digest: stark_config_digest::<F, C, D>(&config)?,
+            })
+        );
+        assert_eq!(BinaryProofKind::detect(&json)?, None);
+        assert_ne!(
+            stark_config_digest::<F, C, D>(&config)?,
+            stark_config_digest::<F, C, D>(&StarkConfig::standard_fast_config())?
+        );
+
+        // All formats decode to the same proof.
+        let from_binary = AllProof::<F, C, D>::decode(&binary)?;
+        let from_compressed = AllProof::<F, C, D>::decode(&compressed)?;
+        let from_json = AllProof::<F, C, D>::decode(&json)?;
+        assert_eq!(serde_json::to_vec(&from_binary)?, json);
+        assert_eq!(serde_json::to_vec(&from_compressed)?, json);
+        assert_eq!(serde_json::to_vec(&from_json)?, json);
+
+        // Other versions of the format, other kinds of proofs, and unknown
+        // flags are rejected.
+        let mut other_version = binary.clone();
+        other_version[PROOF_MAGIC.len()..PROOF_MAGIC.len() + 2]
+            .copy_from_slice(&(PROOF_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(AllProof::<F, C, D>::decode(&other_version).is_err());
+        assert!(BatchProof::<F, C, D>::decode(&binary).is_err());
+        let mut unknown_flags = binary.clone();
+        unknown_flags[PROOF_MAGIC.len() + 3] = 0x80;
+        assert!(ProofContainer::<F, C, D>::from_bytes(&unknown_flags).is_err());
+
+        // Recursive proofs are carried as they are.
+        let recursive = ProofContainer::<F, C, D> {
+            program_id: proof.program_id,
+            config_digest: [7; DIGEST_BYTES],
+            payload: ProofPayload::Recursive(vec![1, 2, 3]),
+        };
+        let decoded = ProofContainer::<F, C, D>::from_bytes(&recursive.to_bytes(true)?)?;
+        assert!(matches!(decoded.payload, ProofPayload::Recursive(bytes) if bytes == [1, 2, 3]));
+        assert_eq!(decoded.config_digest, [7; DIGEST_BYTES]);
+
+        verify_proof(&stark, from_binary, &config)
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(clippy::too_many_lines)]
 
 use std::fmt::Display;
+use std::ops::Range;
 
 use anyhow::{ensure, Result};
 use itertools::Itertools;
@@ -26,17 +27,22 @@ use plonky2_maybe_rayon::*;
 use starky::config::StarkConfig;
 use starky::stark::{LookupConfig, Stark};
 
+use super::backend::{CpuBackend, ProverBackend};
 use super::mozak_stark::{
     all_starks_par, MozakStark, TableKind, TableKindArray, TableKindSetBuilder,
 };
 use super::proof::{AllProof, StarkOpeningSet, StarkProof};
+use crate::cpu::generation::check_provable;
 use crate::cross_table_lookup::ctl_utils::debug_ctl;
-use crate::cross_table_lookup::{cross_table_lookup_data, CtlData};
-use crate::generation::{debug_traces, generate_traces};
+use crate::cross_table_lookup::{cross_table_lookup_data, Column, CtlData};
+use crate::generation::{debug_traces, generate_traces_streaming};
+use crate::memoryinit::generation::generate_elf_memory_init_trace;
+use crate::program::generation::generate_program_rom_trace;
 use crate::public_sub_table::public_sub_table_data_and_values;
 use crate::stark::mozak_stark::PublicInputs;
 use crate::stark::permutation::challenge::GrandProductChallengeTrait;
 use crate::stark::poly::compute_quotient_polys;
+use crate::stark::utils::get_trace_merkle_cap;
 
 /// Prove the execution of a given [Program]
 ///
@@ -59,19 +65,163 @@ where
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     <C as GenericConfig<D>>::Hasher: AlgebraicHasher<F>, {
+    prove_with_backend(
+        &CpuBackend,
+        program,
+        record,
+        mozak_stark,
+        config,
+        public_inputs,
+        timing,
+    )
+}
+
+/// Like [`prove`], but with the commitments to the traces computed by
+/// `backend`.
+///
+/// # Errors
+/// Errors if proving fails.
+pub fn prove_with_backend<F, C, B, const D: usize>(
+    backend: &B,
+    program: &Program,
+    record: &ExecutionRecord<F>,
+    mozak_stark: &MozakStark<F, D>,
+    config: &StarkConfig,
+    public_inputs: PublicInputs<F>,
+    timing: &mut TimingTree,
+) -> Result<AllProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    B: ProverBackend<F, C, D>,
+    <C as GenericConfig<D>>::Hasher: AlgebraicHasher<F>, {
+    prove_with_watches(
+        backend,
+        program,
+        record,
+        mozak_stark,
+        config,
+        public_inputs,
+        &mut [],
+        timing,
+    )
+}
+
+/// Columns of a table to watch while proving, eg to find out why a
+/// cross-table lookup does not match.  See [`prove_with_watches`].
+pub struct Watch<'a, F> {
+    pub kind: TableKind,
+    /// Linear combinations of the columns of the table, as used in
+    /// cross-table lookups.
+    pub columns: Vec<Column>,
+    /// Only rows where the filter does not vanish are reported, eg the rows
+    /// that take part in a lookup.  Without a filter, all rows are reported.
+    pub filter: Option<Column>,
+    /// Only rows in this range are reported.
+    pub rows: Range<usize>,
+    /// Called with the index of each reported row, and the values of
+    /// `columns` on it.
+    pub on_row: Box<dyn FnMut(usize, Vec<F>) + 'a>,
+}
+
+impl<'a, F: Field> Watch<'a, F> {
+    /// Watches `columns` of all rows of the table `kind`.
+    #[must_use]
+    pub fn new(
+        kind: TableKind,
+        columns: Vec<Column>,
+        on_row: impl FnMut(usize, Vec<F>) + 'a,
+    ) -> Self {
+        Self {
+            kind,
+            columns,
+            filter: None,
+            rows: 0..usize::MAX,
+            on_row: Box::new(on_row),
+        }
+    }
+
+    #[must_use]
+    pub fn with_filter(self, filter: Column) -> Self {
+        Self {
+            filter: Some(filter),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_rows(self, rows: Range<usize>) -> Self { Self { rows, ..self } }
+
+    fn observe(&mut self, trace: &[PolynomialValues<F>]) {
+        let Some(len) = trace.first().map(PolynomialValues::len) else {
+            return;
+        };
+        let filter = self.filter.as_ref().map(Column::to_field::<F>);
+        let columns = self.columns.iter().map(Column::to_field::<F>).collect_vec();
+        for row in self.rows.start..self.rows.end.min(len) {
+            if filter
+                .as_ref()
+                .map_or(true, |filter| filter.eval_table(trace, row).is_nonzero())
+            {
+                let values = columns
+                    .iter()
+                    .map(|column| column.eval_table(trace, row))
+                    .collect();
+                (self.on_row)(row, values);
+            }
+        }
+    }
+}
+
+/// Like [`prove_with_backend`], but reports the values of `watches` on the
+/// traces as soon as they are generated, before any of the checks in debug
+/// mode run.
+///
+/// # Errors
+/// Errors if proving fails.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_with_watches<F, C, B, const D: usize>(
+    backend: &B,
+    program: &Program,
+    record: &ExecutionRecord<F>,
+    mozak_stark: &MozakStark<F, D>,
+    config: &StarkConfig,
+    public_inputs: PublicInputs<F>,
+    watches: &mut [Watch<'_, F>],
+    timing: &mut TimingTree,
+) -> Result<AllProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    B: ProverBackend<F, C, D>,
+    <C as GenericConfig<D>>::Hasher: AlgebraicHasher<F>, {
     debug!("Starting Prove");
-    let traces_poly_values = timed!(
+    check_provable(record)?;
+    // Commit to each trace as soon as it is generated, so that the rows of a
+    // table do not outlive its commitment.  Empty tables are not committed to.
+    let mut traces_poly_values = TableKindArray::<Option<_>>::default();
+    let mut trace_commitments = TableKindArray::<Option<_>>::default();
+    timed!(
         timing,
-        "Generate traces",
-        generate_traces(program, record, timing)
-    );
+        "Generate and commit to traces",
+        generate_traces_streaming::<F, D>(program, record, timing, |kind, trace| {
+            for watch in watches.iter_mut().filter(|watch| watch.kind == kind) {
+                watch.observe(&trace);
+            }
+            trace_commitments[kind] = (!mozak_stark.is_empty_table(kind, &trace))
+                .then(|| commit_to_trace(backend, kind, &trace, config));
+            traces_poly_values[kind] = Some(trace);
+        })
+    )?;
+    let traces_poly_values =
+        traces_poly_values.map(|trace| trace.expect("every table has a trace"));
     debug!("Done with Trace Generation");
     if mozak_stark.debug || std::env::var("MOZAK_STARK_DEBUG").is_ok() {
         timed!(
             timing,
             "Mozak stark debug",
             debug_traces(&traces_poly_values, mozak_stark, &public_inputs)
-        );
+        )?;
         timed!(
             timing,
             "Mozak CTL debug",
@@ -81,17 +231,50 @@ where
     timed!(
         timing,
         "Prove with Traces",
-        prove_with_traces(
+        prove_with_trace_commitments(
             mozak_stark,
             config,
             public_inputs,
             &traces_poly_values,
+            trace_commitments,
             timing,
         )
     )
 }
 
-/// Given the traces generated from [`generate_traces`], prove a [`MozakStark`].
+/// Commits to the trace of a single table with `backend`.
+///
+/// The columns are interpolated one by one, so that the whole trace is never
+/// copied at once.
+pub fn commit_to_trace<F, C, B, const D: usize>(
+    backend: &B,
+    kind: TableKind,
+    trace: &[PolynomialValues<F>],
+    config: &StarkConfig,
+) -> PolynomialBatch<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    B: ProverBackend<F, C, D>, {
+    let mut timing = TimingTree::default();
+    timed!(
+        timing,
+        &format!("compute trace commitment for {kind:?}"),
+        backend.commit(
+            trace
+                .par_iter()
+                .map(|column| column.clone().ifft())
+                .collect(),
+            config.fri_config.rate_bits,
+            config.fri_config.cap_height,
+            &mut timing,
+        )
+    )
+}
+
+/// Given the traces generated from
+/// [`generate_traces`](crate::generation::generate_traces), prove a
+/// [`MozakStark`].
 ///
 /// # Errors
 /// Errors if proving fails.
@@ -106,35 +289,51 @@ where
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     <C as GenericConfig<D>>::Hasher: AlgebraicHasher<F>, {
-    let rate_bits = config.fri_config.rate_bits;
-    let cap_height = config.fri_config.cap_height;
-
     let trace_commitments = timed!(
         timing,
         "Compute trace commitments for each table",
         traces_poly_values
             .each_ref()
             .with_kind()
-            .par_map(|(trace, table)| {
-                let mut timing = TimingTree::default();
-                timed!(
-                    timing,
-                    &format!("compute trace commitment for {table:?}"),
-                    PolynomialBatch::<F, C, D>::from_values(
-                        trace.clone(),
-                        rate_bits,
-                        false,
-                        cap_height,
-                        &mut timing,
-                        None,
-                    )
-                )
+            .par_map(|(trace, kind)| {
+                (!mozak_stark.is_empty_table(kind, trace))
+                    .then(|| commit_to_trace(&CpuBackend, kind, trace, config))
             })
     );
+    prove_with_trace_commitments(
+        mozak_stark,
+        config,
+        public_inputs,
+        traces_poly_values,
+        trace_commitments,
+        timing,
+    )
+}
 
-    let trace_caps = trace_commitments
-        .each_ref()
-        .map(|c| c.merkle_tree.cap.clone());
+/// Given the traces and the commitments to them, prove a [`MozakStark`].
+///
+/// Tables without a commitment are proven with an empty proof.
+///
+/// # Errors
+/// Errors if proving fails.
+pub fn prove_with_trace_commitments<F, C, const D: usize>(
+    mozak_stark: &MozakStark<F, D>,
+    config: &StarkConfig,
+    public_inputs: PublicInputs<F>,
+    traces_poly_values: &TableKindArray<Vec<PolynomialValues<F>>>,
+    trace_commitments: TableKindArray<Option<PolynomialBatch<F, C, D>>>,
+    timing: &mut TimingTree,
+) -> Result<AllProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    <C as GenericConfig<D>>::Hasher: AlgebraicHasher<F>, {
+    let trace_caps = trace_commitments.each_ref().map(|c| {
+        c.as_ref().map_or_else(
+            || StarkProof::<F, C, D>::empty_trace_cap(config),
+            |c| c.merkle_tree.cap.clone(),
+        )
+    });
     // Add trace commitments to the challenger entropy pool.
     let mut challenger = Challenger::<F, C::Hasher>::new();
     for cap in &trace_caps {
@@ -214,6 +413,27 @@ where
     ProgramIdentifier(hashout_bytes.into())
 }
 
+/// Computes `[ProgramIdentifer]` from hash of entry point and merkle caps
+/// of `ElfMemoryInit` and `ProgramRom` tables.
+#[must_use]
+pub fn get_self_prog_id<F, C, const D: usize>(
+    program: &Program,
+    config: &StarkConfig,
+) -> ProgramIdentifier
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>, {
+    let entry_point = F::from_canonical_u32(program.entry_point);
+
+    let elf_memory_init_trace = generate_elf_memory_init_trace::<F>(program);
+    let program_rom_trace = generate_program_rom_trace::<F>(program);
+
+    let elf_memory_init_cap = get_trace_merkle_cap::<F, C, D, _>(elf_memory_init_trace, config);
+    let program_cap = get_trace_merkle_cap::<F, C, D, _>(program_rom_trace, config);
+    get_program_id::<F, C, D>(entry_point, &program_cap, &elf_memory_init_cap)
+}
+
 /// Compute proof for a single STARK table, with lookup data.
 ///
 /// # Errors
@@ -386,7 +606,8 @@ where
     })
 }
 
-/// Given the traces generated from [`generate_traces`] along with their
+/// Given the traces generated from
+/// [`generate_traces`](crate::generation::generate_traces) along with their
 /// commitments, prove a [`MozakStark`].
 ///
 /// # Errors
@@ -396,7 +617,7 @@ pub fn prove_with_commitments<F, C, const D: usize>(
     mozak_stark: &MozakStark<F, D>,
     config: &StarkConfig,
     public_inputs: &PublicInputs<F>,
-    trace_commitments: &TableKindArray<PolynomialBatch<F, C, D>>,
+    trace_commitments: &TableKindArray<Option<PolynomialBatch<F, C, D>>>,
     ctl_data_per_table: &TableKindArray<CtlData<F>>,
     public_sub_data_per_table: &TableKindArray<CtlData<F>>,
     challenger: &mut Challenger<F, C::Hasher>,
@@ -416,17 +637,24 @@ where
 
     Ok(all_starks_par!(mozak_stark, |stark, kind| {
         let mut timing = TimingTree::default();
-        prove_single_table(
-            stark,
-            config,
-            &trace_commitments[kind],
-            public_inputs[kind],
-            &ctl_data_per_table[kind],
-            &public_sub_data_per_table[kind],
-            &mut challenger.clone(),
-            &mut timing,
-        )
-        .unwrap()
+        if let Some(trace_commitment) = &trace_commitments[kind] {
+            prove_single_table(
+                stark,
+                config,
+                trace_commitment,
+                public_inputs[kind],
+                &ctl_data_per_table[kind],
+                &public_sub_data_per_table[kind],
+                &mut challenger.clone(),
+                &mut timing,
+            )
+            .unwrap()
+        } else {
+            StarkProof::empty(
+                config,
+                ctl_data_per_table[kind].len() + public_sub_data_per_table[kind].len(),
+            )
+        }
     }))
 }
 
@@ -435,13 +663,25 @@ mod tests {
 
     use mozak_runner::code;
     use mozak_runner::instruction::{Args, Instruction, Op};
+    use mozak_sdk::core::blake3::{self, CHUNK_END, CHUNK_START, INITIAL_CV, ROOT};
+    use mozak_sdk::core::sha256::{compress, BLOCK_BYTES, INITIAL_STATE, STATE_BYTES};
     use plonky2::field::goldilocks_field::GoldilocksField;
     use plonky2::field::types::Field;
     use plonky2::hash::poseidon2::Poseidon2Hash;
     use plonky2::plonk::config::{GenericHashOut, Hasher};
-
-    use crate::stark::mozak_stark::MozakStark;
-    use crate::test_utils::{create_poseidon2_test, Poseidon2Test, ProveAndVerify};
+    use plonky2::util::timing::TimingTree;
+
+    use super::{prove, prove_with_watches, Watch};
+    use crate::stark::backend::CpuBackend;
+    use crate::stark::mozak_stark::{MozakStark, PublicInputs, TableKind};
+    use crate::stark::proof::StarkProof;
+    use crate::stark::verifier::verify_proof;
+    use crate::test_utils::{
+        create_blake3_test, create_poseidon2_test, create_sha256_test, fast_test_config,
+        Blake3Test, Poseidon2Test, ProveAndVerify, Sha256Test, C, D, F,
+    };
+    use crate::utils::from_u32;
+    use crate::xor;
 
     #[test]
     fn prove_halt() {
@@ -449,6 +689,103 @@ mod tests {
         MozakStark::prove_and_verify(&program, &record).unwrap();
     }
 
+    #[test]
+    fn prove_halt_skipping_empty_tables() {
+        let (program, record) = code::execute([], &[], &[]);
+        let stark = MozakStark {
+            skip_empty_tables: true,
+            ..MozakStark::default()
+        };
+        let config = fast_test_config();
+        let public_inputs = PublicInputs {
+            entry_point: from_u32(program.entry_point),
+        };
+        let all_proof = prove::<F, C, D>(
+            &program,
+            &record,
+            &stark,
+            &config,
+            public_inputs,
+            &mut TimingTree::default(),
+        )
+        .unwrap();
+        assert!(all_proof.proofs[TableKind::Sha256].is_empty());
+        assert!(all_proof.proofs[TableKind::Blake3].is_empty());
+        assert!(!all_proof.proofs[TableKind::Cpu].is_empty());
+        verify_proof(&stark, all_proof.clone(), &config).unwrap();
+
+        // The CPU table may never be left out.
+        let mut all_proof = all_proof;
+        let num_ctl_zs = all_proof.proofs[TableKind::Cpu].num_ctl_zs();
+        all_proof.proofs[TableKind::Cpu] = StarkProof::empty(&config, num_ctl_zs);
+        assert!(verify_proof(&stark, all_proof, &config).is_err());
+    }
+
+    #[test]
+    fn watch_xor_lookups() {
+        let (program, record) = code::execute(
+            [Instruction {
+                op: Op::XOR,
+                args: Args {
+                    rs1: 5,
+                    rs2: 6,
+                    rd: 7,
+                    ..Args::default()
+                },
+            }],
+            &[],
+            &[(5, 0b1010), (6, 0b0110)],
+        );
+        let public_inputs = PublicInputs {
+            entry_point: from_u32(program.entry_point),
+        };
+        let execution = xor::columns::COL_MAP.execution;
+        let mut rows = vec![];
+        let watch = Watch::new(
+            TableKind::Xor,
+            vec![execution.a.into(), execution.b.into(), execution.out.into()],
+            |row, values| rows.push((row, values)),
+        )
+        .with_filter(xor::columns::COL_MAP.is_execution_row.into());
+        prove_with_watches::<F, C, _, D>(
+            &CpuBackend,
+            &program,
+            &record,
+            &MozakStark::default(),
+            &fast_test_config(),
+            public_inputs,
+            &mut [watch],
+            &mut TimingTree::default(),
+        )
+        .unwrap();
+        let values: Vec<F> = vec![from_u32(0b1010), from_u32(0b0110), from_u32(0b1100)];
+        assert_eq!(rows, vec![(0, values)]);
+    }
+
+    #[test]
+    fn estimated_degrees_match_the_proof() {
+        let (program, record) = code::execute([], &[], &[]);
+        let stark = MozakStark::default();
+        let config = fast_test_config();
+        let public_inputs = PublicInputs {
+            entry_point: from_u32(program.entry_point),
+        };
+        let sizes = stark.estimate_degrees(&program, &record).unwrap();
+        let all_proof = prove::<F, C, D>(
+            &program,
+            &record,
+            &stark,
+            &config,
+            public_inputs,
+            &mut TimingTree::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            sizes.map(|size| size.degree_bits),
+            all_proof.degree_bits(&config)
+        );
+    }
+
     #[test]
     fn prove_lui() {
         let lui = Instruction {
@@ -550,4 +887,79 @@ mod tests {
             },
         ]);
     }
+
+    #[test]
+    fn prove_sha256() {
+        let test_data = [
+            Sha256Test {
+                state: INITIAL_STATE,
+                block: [0; BLOCK_BYTES],
+                state_addr: 512,
+                block_addr: 1024,
+                output_addr: 2048,
+            },
+            Sha256Test {
+                state: [0xa5; STATE_BYTES],
+                block: core::array::from_fn(|i| u8::try_from(i).unwrap()),
+                state_addr: 4096,
+                block_addr: 4096 + 32,
+                // The output overwrites the state it was computed from.
+                output_addr: 4096,
+            },
+        ];
+        let (program, record) = create_sha256_test(&test_data);
+        for test_datum in &test_data {
+            let output: Vec<u8> = (test_datum.output_addr..)
+                .take(STATE_BYTES)
+                .map(|addr| record.last_state.load_u8(addr))
+                .collect();
+            assert_eq!(output, compress(&test_datum.state, &test_datum.block));
+        }
+        MozakStark::prove_and_verify(&program, &record).unwrap();
+    }
+
+    #[test]
+    fn prove_blake3() {
+        let test_data = [
+            Blake3Test {
+                cv: INITIAL_CV,
+                block: [0; blake3::BLOCK_BYTES],
+                counter: 0,
+                block_len: 0,
+                flags: CHUNK_START | CHUNK_END | ROOT,
+                cv_addr: 512,
+                block_addr: 1024,
+                output_addr: 2048,
+            },
+            Blake3Test {
+                cv: [0xa5; blake3::CV_BYTES],
+                block: core::array::from_fn(|i| u8::try_from(i).unwrap()),
+                counter: 1 << 40,
+                block_len: 64,
+                flags: CHUNK_START,
+                cv_addr: 4096,
+                block_addr: 4096 + 32,
+                // The output overwrites the chaining value it was computed from.
+                output_addr: 4096,
+            },
+        ];
+        let (program, record) = create_blake3_test(&test_data);
+        for test_datum in &test_data {
+            let output: Vec<u8> = (test_datum.output_addr..)
+                .take(blake3::CV_BYTES)
+                .map(|addr| record.last_state.load_u8(addr))
+                .collect();
+            assert_eq!(
+                output,
+                blake3::compress(
+                    &test_datum.cv,
+                    &test_datum.block,
+                    test_datum.counter,
+                    test_datum.block_len,
+                    test_datum.flags,
+                )
+            );
+        }
+        MozakStark::prove_and_verify(&program, &record).unwrap();
+    }
 }
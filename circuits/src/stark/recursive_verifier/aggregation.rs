@@ -0,0 +1,412 @@
+//! Aggregation of recursive VM proofs.
+//!
+//! An [`AggregationTree`] folds N recursive VM proofs, shrunk to
+//! [`VM_RECURSION_THRESHOLD_DEGREE_BITS`], pairwise into a single proof.  The
+//! leaves of the tree verify two VM proofs each, with their verifier keys as
+//! witnesses, and every level above verifies two proofs of the level below.
+//! Every level is padded to [`AGGREGATION_DEGREE_BITS`], so the aggregated
+//! proof has the same size no matter how many VM proofs it covers, apart from
+//! its public inputs.  Those are an [`AggregatedVMProofPublicInputs`] per VM
+//! proof, in order.
+//!
+//! The number of VM proofs is rounded up to a power of two, and the missing
+//! ones repeat the last VM proof.
+
+use std::iter::repeat;
+
+use anyhow::{ensure, Result};
+use itertools::{izip, Itertools};
+use mozak_circuits_derive::PublicInputs;
+use mozak_sdk::core::constants::DIGEST_BYTES;
+use plonky2::field::extension::Extendable;
+use plonky2::gates::noop::NoopGate;
+use plonky2::hash::hash_types::{RichField, NUM_HASH_OUT_ELTS};
+use plonky2::iop::target::Target;
+use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitData, VerifierCircuitTarget, VerifierOnlyCircuitData};
+use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
+use plonky2::plonk::proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget};
+
+use super::{
+    verify_recursive_vm_proof, VMRecursiveProofPublicInputs, VMVerificationTargets,
+    VM_PUBLIC_INPUT_SIZE, VM_RECURSION_CONFIG, VM_RECURSION_THRESHOLD_DEGREE_BITS,
+};
+use crate::columns_view::{columns_view_impl, NumberOfColumns};
+
+/// Degree bits of every level of an [`AggregationTree`], which fits the
+/// verification of two proofs.
+pub const AGGREGATION_DEGREE_BITS: usize = VM_RECURSION_THRESHOLD_DEGREE_BITS + 1;
+
+/// Number of elements in the constants and sigmas cap of the verifier key of
+/// a shrunk VM proof.
+pub const VM_VERIFIER_CAP_SIZE: usize =
+    NUM_HASH_OUT_ELTS << VM_RECURSION_CONFIG.fri_config.cap_height;
+
+/// The public inputs of an aggregated proof for each of its VM proofs.
+#[repr(C)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug, PublicInputs)]
+pub struct AggregatedVMProofPublicInputs<T> {
+    pub program_hash_as_bytes: [T; DIGEST_BYTES],
+    pub event_commitment_tape: [T; DIGEST_BYTES],
+    pub castlist_commitment_tape: [T; DIGEST_BYTES],
+    /// The verifier key of the VM proof, which the caller has to check like
+    /// for [`verify_recursive_vm_proof`].
+    pub circuit_digest: [T; NUM_HASH_OUT_ELTS],
+    pub constants_sigmas_cap: [T; VM_VERIFIER_CAP_SIZE],
+}
+
+columns_view_impl!(AggregatedVMProofPublicInputs);
+
+impl<T: Copy> AggregatedVMProofPublicInputs<T> {
+    /// Views the public inputs of an aggregated proof as the public inputs for
+    /// each of its VM proofs, in order.
+    ///
+    /// # Panics
+    /// Panics if the public inputs are not a whole number of
+    /// [`AggregatedVMProofPublicInputs`].
+    pub fn all_from_public_inputs(public_inputs: &[T]) -> impl Iterator<Item = &Self> {
+        assert_eq!(public_inputs.len() % Self::NUMBER_OF_COLUMNS, 0);
+        public_inputs
+            .chunks_exact(Self::NUMBER_OF_COLUMNS)
+            .map(|public_inputs| Self::from_public_inputs(public_inputs).unwrap())
+    }
+}
+
+impl AggregatedVMProofPublicInputs<Target> {
+    fn from_vm_verification_targets<const D: usize>(targets: &VMVerificationTargets<D>) -> Self {
+        let vm_public_inputs = VMRecursiveProofPublicInputs::from_public_inputs(
+            &targets.proof_with_pis_target.public_inputs,
+        )
+        .expect("VM proof should have VM_PUBLIC_INPUT_SIZE public inputs");
+        AggregatedVMProofPublicInputs {
+            program_hash_as_bytes: vm_public_inputs.program_hash_as_bytes,
+            event_commitment_tape: vm_public_inputs.event_commitment_tape,
+            castlist_commitment_tape: vm_public_inputs.castlist_commitment_tape,
+            circuit_digest: targets.vk_target.circuit_digest.elements,
+            constants_sigmas_cap: targets
+                .vk_target
+                .constants_sigmas_cap
+                .0
+                .iter()
+                .flat_map(|hash| hash.elements)
+                .collect_vec()
+                .try_into()
+                .unwrap(),
+        }
+    }
+}
+
+/// One level of an [`AggregationTree`], which verifies two proofs of the
+/// level below, or two VM proofs at the leaves.
+#[derive(Eq, PartialEq, Debug)]
+pub struct AggregationCircuit<F, C, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>, {
+    pub circuit: CircuitData<F, C, D>,
+    pub proof_targets: [ProofWithPublicInputsTarget<D>; 2],
+    /// The verifier keys of the VM proofs, which are only witnesses at the
+    /// leaves.  Above, the verifier key of the level below is a constant.
+    pub vk_targets: Option<[VerifierCircuitTarget; 2]>,
+}
+
+impl<F, C, const D: usize> AggregationCircuit<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    /// Builds the circuit for the leaves, which verifies two VM proofs.
+    #[must_use]
+    pub fn leaf() -> Self {
+        let mut builder = CircuitBuilder::<F, D>::new(VM_RECURSION_CONFIG);
+        let [left, right] = [(); 2].map(|()| {
+            let targets = verify_recursive_vm_proof::<F, C, D>(
+                &mut builder,
+                VM_PUBLIC_INPUT_SIZE,
+                &VM_RECURSION_CONFIG,
+                VM_RECURSION_THRESHOLD_DEGREE_BITS,
+            );
+            AggregatedVMProofPublicInputs::from_vm_verification_targets(&targets)
+                .register(&mut builder);
+            targets
+        });
+        AggregationCircuit {
+            circuit: build_padded(builder),
+            proof_targets: [left.proof_with_pis_target, right.proof_with_pis_target],
+            vk_targets: Some([left.vk_target, right.vk_target]),
+        }
+    }
+
+    /// Builds the circuit for the level above `child`, which verifies two
+    /// proofs of `child` and carries their public inputs forward.
+    #[must_use]
+    pub fn node(child: &CircuitData<F, C, D>) -> Self {
+        let mut builder = CircuitBuilder::<F, D>::new(VM_RECURSION_CONFIG);
+        let verifier_data = builder.constant_verifier_data(&child.verifier_only);
+        let proof_targets = [(); 2].map(|()| {
+            let proof = builder.add_virtual_proof_with_pis(&child.common);
+            builder.verify_proof::<C>(&proof, &verifier_data, &child.common);
+            builder.register_public_inputs(&proof.public_inputs);
+            proof
+        });
+        AggregationCircuit {
+            circuit: build_padded(builder),
+            proof_targets,
+            vk_targets: None,
+        }
+    }
+
+    /// Proves `proofs`, where `verifier_data` are the verifier keys of the VM
+    /// proofs at the leaves, and ignored above.
+    ///
+    /// # Errors
+    /// Errors if proving fails.
+    pub fn prove(
+        &self,
+        proofs: [&ProofWithPublicInputs<F, C, D>; 2],
+        verifier_data: [&VerifierOnlyCircuitData<C, D>; 2],
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        let mut inputs = PartialWitness::new();
+        for (target, proof) in izip!(&self.proof_targets, proofs) {
+            inputs.set_proof_with_pis_target(target, proof);
+        }
+        if let Some(vk_targets) = &self.vk_targets {
+            for (target, verifier_only) in izip!(vk_targets, verifier_data) {
+                inputs.set_verifier_data_target(target, verifier_only);
+            }
+        }
+        self.circuit.prove(inputs)
+    }
+}
+
+/// Pads `builder` to [`AGGREGATION_DEGREE_BITS`] and builds it.
+///
+/// # Panics
+/// Panics if the circuit does not fit into [`AGGREGATION_DEGREE_BITS`].
+fn build_padded<F, C, const D: usize>(mut builder: CircuitBuilder<F, D>) -> CircuitData<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>, {
+    // Like in `circuit_data_for_recursion`, leave room for the gates the
+    // builder adds itself.
+    let min_gates = (1 << (AGGREGATION_DEGREE_BITS - 1)) + 1;
+    while builder.num_gates() < min_gates {
+        builder.add_gate(NoopGate, vec![]);
+    }
+    let circuit = builder.build::<C>();
+    assert_eq!(
+        circuit.common.degree_bits(),
+        AGGREGATION_DEGREE_BITS,
+        "aggregation circuit does not fit into {AGGREGATION_DEGREE_BITS} degree bits"
+    );
+    circuit
+}
+
+/// The circuits to aggregate up to [`AggregationTree::capacity`] recursive
+/// VM proofs into one.
+#[derive(Eq, PartialEq, Debug)]
+pub struct AggregationTree<F, C, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>, {
+    /// The levels of the tree, starting with the leaves.
+    pub levels: Vec<AggregationCircuit<F, C, D>>,
+}
+
+impl<F, C, const D: usize> AggregationTree<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    /// Builds the circuits to aggregate `num_proofs` VM proofs.
+    ///
+    /// # Panics
+    /// Panics if `num_proofs` is zero.
+    #[must_use]
+    pub fn new(num_proofs: usize) -> Self {
+        assert!(num_proofs > 0, "nothing to aggregate");
+        let depth = num_proofs.next_power_of_two().trailing_zeros().max(1);
+        let mut levels = vec![AggregationCircuit::leaf()];
+        for _ in 1..depth {
+            let node = AggregationCircuit::node(&levels.last().unwrap().circuit);
+            levels.push(node);
+        }
+        AggregationTree { levels }
+    }
+
+    /// The number of VM proofs the tree aggregates, including padding.
+    #[must_use]
+    pub fn capacity(&self) -> usize { 1 << self.levels.len() }
+
+    /// The circuit of the aggregated proof.
+    #[must_use]
+    pub fn circuit(&self) -> &CircuitData<F, C, D> { &self.levels.last().unwrap().circuit }
+
+    /// Aggregates the shrunk VM `proofs`, each with the verifier key of its
+    /// circuit, into one proof.
+    ///
+    /// # Errors
+    /// Errors if there are no proofs, more than [`Self::capacity`] proofs, or
+    /// if proving any level fails.
+    pub fn prove(
+        &self,
+        proofs: &[(
+            &VerifierOnlyCircuitData<C, D>,
+            &ProofWithPublicInputs<F, C, D>,
+        )],
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        ensure!(!proofs.is_empty(), "nothing to aggregate");
+        ensure!(
+            proofs.len() <= self.capacity(),
+            "cannot aggregate {} proofs with a capacity of {}",
+            proofs.len(),
+            self.capacity()
+        );
+
+        let (leaves, nodes) = self.levels.split_first().unwrap();
+        let last = proofs.last().unwrap();
+        let padded = proofs
+            .iter()
+            .chain(repeat(last).take(self.capacity() - proofs.len()));
+        let mut level_proofs = padded
+            .tuples()
+            .map(|((left_vk, left), (right_vk, right))| {
+                leaves.prove([*left, *right], [*left_vk, *right_vk])
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for node in nodes {
+            let verifier_only = &node.circuit.verifier_only;
+            level_proofs = level_proofs
+                .iter()
+                .tuples()
+                .map(|(left, right)| node.prove([left, right], [verifier_only; 2]))
+                .collect::<Result<Vec<_>>>()?;
+        }
+        assert_eq!(level_proofs.len(), 1);
+        Ok(level_proofs.remove(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use mozak_runner::code;
+    use mozak_runner::instruction::{Args, Instruction, Op};
+    use plonky2::plonk::circuit_data::VerifierOnlyCircuitData;
+    use plonky2::plonk::proof::ProofWithPublicInputs;
+    use plonky2::util::timing::TimingTree;
+    use starky::config::StarkConfig;
+
+    use super::{AggregatedVMProofPublicInputs, AggregationTree, AGGREGATION_DEGREE_BITS};
+    use crate::stark::mozak_stark::{MozakStark, PublicInputs};
+    use crate::stark::prover::prove;
+    use crate::stark::recursive_verifier::{
+        recursive_mozak_stark_circuit, shrink_to_target_degree_bits_circuit,
+        VMRecursiveProofPublicInputs, VM_RECURSION_CONFIG, VM_RECURSION_THRESHOLD_DEGREE_BITS,
+    };
+    use crate::test_utils::{C, D, F};
+    use crate::utils::from_u32;
+
+    fn shrunk_vm_proof(
+        num_instructions: usize,
+    ) -> Result<(
+        VerifierOnlyCircuitData<C, D>,
+        ProofWithPublicInputs<F, C, D>,
+    )> {
+        let stark = MozakStark::<F, D>::default();
+        let config = StarkConfig::standard_fast_config();
+        let inst = Instruction {
+            op: Op::ADD,
+            args: Args {
+                rd: 5,
+                rs1: 6,
+                rs2: 7,
+                ..Args::default()
+            },
+        };
+        let (program, record) =
+            code::execute(vec![inst; num_instructions], &[], &[(6, 100), (7, 200)]);
+        let public_inputs = PublicInputs {
+            entry_point: from_u32(program.entry_point),
+        };
+        let mozak_proof = prove::<F, C, D>(
+            &program,
+            &record,
+            &stark,
+            &config,
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+        let recursion_circuit = recursive_mozak_stark_circuit::<F, C, D>(
+            &stark,
+            &mozak_proof.degree_bits(&config),
+            &VM_RECURSION_CONFIG,
+            &config,
+        );
+        let recursion_proof = recursion_circuit.prove(&mozak_proof)?;
+        let (final_circuit, final_proof) = shrink_to_target_degree_bits_circuit(
+            &recursion_circuit.circuit.verifier_only,
+            &recursion_circuit.circuit.common,
+            &VM_RECURSION_CONFIG,
+            VM_RECURSION_THRESHOLD_DEGREE_BITS,
+            &recursion_proof,
+        )?;
+        Ok((final_circuit.circuit.verifier_only, final_proof))
+    }
+
+    #[test]
+    #[ignore]
+    fn aggregate_vm_proofs() -> Result<()> {
+        let vm_proofs = [1, 128, 1]
+            .into_iter()
+            .map(shrunk_vm_proof)
+            .collect::<Result<Vec<_>>>()?;
+
+        let tree = AggregationTree::<F, C, D>::new(vm_proofs.len());
+        assert_eq!(tree.capacity(), 4);
+        let aggregated_proof = tree.prove(
+            &vm_proofs
+                .iter()
+                .map(|(verifier_only, proof)| (verifier_only, proof))
+                .collect::<Vec<_>>(),
+        )?;
+        assert_eq!(tree.circuit().common.degree_bits(), AGGREGATION_DEGREE_BITS);
+
+        // The last VM proof pads the tree.
+        let aggregated_public_inputs =
+            AggregatedVMProofPublicInputs::all_from_public_inputs(&aggregated_proof.public_inputs)
+                .collect::<Vec<_>>();
+        assert_eq!(aggregated_public_inputs.len(), tree.capacity());
+        for (aggregated, (verifier_only, proof)) in aggregated_public_inputs
+            .iter()
+            .zip(vm_proofs.iter().chain([&vm_proofs[2]]))
+        {
+            let vm =
+                VMRecursiveProofPublicInputs::from_public_inputs(&proof.public_inputs).unwrap();
+            assert_eq!(aggregated.program_hash_as_bytes, vm.program_hash_as_bytes);
+            assert_eq!(aggregated.event_commitment_tape, vm.event_commitment_tape);
+            assert_eq!(
+                aggregated.castlist_commitment_tape,
+                vm.castlist_commitment_tape
+            );
+            assert_eq!(
+                aggregated.circuit_digest,
+                verifier_only.circuit_digest.elements
+            );
+            assert!(aggregated.constants_sigmas_cap.iter().eq(verifier_only
+                .constants_sigmas_cap
+                .0
+                .iter()
+                .flat_map(|hash| &hash.elements)));
+        }
+        assert_ne!(
+            aggregated_public_inputs[0].circuit_digest,
+            aggregated_public_inputs[1].circuit_digest
+        );
+
+        tree.circuit().verify(aggregated_proof)
+    }
+}
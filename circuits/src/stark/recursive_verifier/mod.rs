@@ -3,9 +3,10 @@ use std::borrow::Borrow;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use itertools::{chain, zip_eq, Itertools};
 use log::info;
+use mozak_circuits_derive::PublicInputs;
 use mozak_sdk::core::constants::DIGEST_BYTES;
 use plonky2::field::extension::Extendable;
 use plonky2::field::types::Field;
@@ -35,9 +36,11 @@ use super::mozak_stark::{all_kind, all_starks, TableKindArray};
 use crate::columns_view::{columns_view_impl, NumberOfColumns};
 use crate::cross_table_lookup::{
     verify_cross_table_lookups_and_public_sub_table_circuit, CrossTableLookup, CtlCheckVarsTarget,
+    CtlLookup,
 };
 use crate::public_sub_table::{
-    public_sub_table_values_and_reduced_targets, PublicSubTable, PublicSubTableValuesTarget,
+    public_sub_table_values_and_reduced_targets, PublicSubTable, PublicSubTableValues,
+    PublicSubTableValuesTarget,
 };
 use crate::stark::batch_prover::{
     batch_fri_instances_target, batch_reduction_arity_bits, sort_degree_bits,
@@ -49,6 +52,9 @@ use crate::stark::proof::{
     AllProof, BatchProof, StarkOpeningSetTarget, StarkProof, StarkProofChallengesTarget,
     StarkProofTarget, StarkProofWithPublicInputsTarget,
 };
+use crate::tape_commitments::columns::{PrivateTapeDisclosure, PRIVATE_TAPE_DISCLOSURE_BYTES};
+
+pub mod aggregation;
 
 /// Plonky2's recursion threshold is 2^12 gates, but we need some extra gates
 /// for public inputs.
@@ -61,20 +67,51 @@ pub const VM_RECURSION_THRESHOLD_DEGREE_BITS: usize = 13;
 ///   `ElfMemoryInit trace cap`: 64
 ///   `event commitment_tape`: 32
 ///   `castlist_commitment_tape`: 32
+///   `oracle_tape`: 32
+///   `private_tape_disclosure`: 32 * 3
+///   `private_tape_commitment_tape`: 32
 pub const VM_PUBLIC_INPUT_SIZE: usize = VMRecursiveProofPublicInputs::<()>::NUMBER_OF_COLUMNS;
 pub const VM_RECURSION_CONFIG: CircuitConfig = CircuitConfig::standard_recursion_config();
 
 #[repr(C)]
-#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug, PublicInputs)]
 pub struct VMRecursiveProofPublicInputs<T> {
     pub entry_point: T,
     pub program_hash_as_bytes: [T; DIGEST_BYTES],
     pub event_commitment_tape: [T; DIGEST_BYTES],
     pub castlist_commitment_tape: [T; DIGEST_BYTES],
+    pub oracle_tape: [T; DIGEST_BYTES],
+    pub private_tape_disclosure: [PrivateTapeDisclosure<T>; PRIVATE_TAPE_DISCLOSURE_BYTES],
+    pub private_tape_commitment_tape: [T; DIGEST_BYTES],
 }
 
 columns_view_impl!(VMRecursiveProofPublicInputs);
 
+/// Where the values of the public sub tables start in the public inputs of a
+/// recursive VM proof.
+pub const VM_PUBLIC_SUB_TABLES_START: usize =
+    VMRecursiveProofPublicInputs::<()>::PROGRAM_HASH_AS_BYTES.end;
+
+/// Reads the values of the public sub tables of `mozak_stark` from the public
+/// inputs of a recursive VM proof, in the order of
+/// [`MozakStark::public_sub_tables`].
+#[must_use]
+pub fn public_sub_table_values_from_public_inputs<F, const D: usize, T>(
+    mozak_stark: &MozakStark<F, D>,
+    public_inputs: &[T],
+) -> Vec<PublicSubTableValues<T>>
+where
+    F: RichField + Extendable<D>,
+    T: Copy, {
+    let ranges = PublicSubTable::public_input_ranges(
+        &mozak_stark.public_sub_tables,
+        VM_PUBLIC_SUB_TABLES_START,
+    );
+    zip_eq(&mozak_stark.public_sub_tables, ranges)
+        .map(|(sub_table, range)| sub_table.values_from_public_inputs(&public_inputs[range]))
+        .collect()
+}
+
 #[derive(Eq, PartialEq, Debug)]
 pub struct MozakProofTarget<F, C, const D: usize>
 where
@@ -161,6 +198,10 @@ where
     C::Hasher: AlgebraicHasher<F>,
 {
     pub fn prove(&self, all_proof: &AllProof<F, C, D>) -> Result<ProofWithPublicInputs<F, C, D>> {
+        ensure!(
+            all_proof.proofs.iter().all(|proof| !proof.is_empty()),
+            "empty tables can not be verified recursively"
+        );
         let mut inputs = PartialWitness::new();
 
         all_kind!(|kind| {
@@ -254,12 +295,6 @@ where
     }
 }
 
-fn get_num_columns<F: RichField + Extendable<D>, S: Stark<F, D>, const D: usize>(
-    _stark: &S,
-) -> usize {
-    S::COLUMNS
-}
-
 #[must_use]
 #[allow(clippy::too_many_lines)]
 pub fn recursive_batch_stark_circuit<
@@ -280,7 +315,8 @@ where
     let zero_target = builder.zero();
     let rate_bits = inner_config.fri_config.rate_bits;
     let cap_height = inner_config.fri_config.cap_height;
-    let sorted_degree_bits = sort_degree_bits(public_table_kinds, degree_bits);
+    let sorted_degree_bits =
+        sort_degree_bits(public_table_kinds, std::slice::from_ref(degree_bits));
     let fri_params = {
         let mut p = inner_config.fri_params(sorted_degree_bits[0]);
         p.reduction_arity_bits =
@@ -303,7 +339,7 @@ where
             inner_config.num_challenges,
         );
         if !public_table_kinds.contains(&kind) {
-            num_leaves_per_oracle[0] += get_num_columns(stark);
+            num_leaves_per_oracle[0] += mozak_stark.get(kind).columns();
             num_leaves_per_oracle[1] += num_ctl_zs + num_make_row_public_zs;
             num_leaves_per_oracle[2] +=
                 stark.quotient_degree_factor() * inner_config.num_challenges;
@@ -425,12 +461,16 @@ where
         }
     };
 
+    let lookups = CtlLookup::per_table(
+        &mozak_stark.cross_table_lookups,
+        &mozak_stark.public_sub_tables,
+    );
     let table_targets = all_starks!(mozak_stark, |stark, kind| {
         let ctl_vars = CtlCheckVarsTarget::from_proof(
             kind,
             &stark_proof_with_pis_target[kind].proof,
             &mozak_stark.cross_table_lookups,
-            &mozak_stark.public_sub_tables,
+            &lookups[kind],
             &ctl_challenges,
         );
 
@@ -624,12 +664,16 @@ where
     );
 
     let state = challenger.compact(&mut builder);
+    let lookups = CtlLookup::per_table(
+        &mozak_stark.cross_table_lookups,
+        &mozak_stark.public_sub_tables,
+    );
     let table_targets = all_starks!(mozak_stark, |stark, kind| {
         let ctl_vars = CtlCheckVarsTarget::from_proof(
             kind,
             &stark_proof_with_pis_target[kind].proof,
             &mozak_stark.cross_table_lookups,
-            &mozak_stark.public_sub_tables,
+            &lookups[kind],
             &ctl_challenges,
         );
 
@@ -1105,6 +1149,71 @@ where
     }
 }
 
+pub const VM_PROOF_WRAPPER_CONFIG: CircuitConfig = CircuitConfig::standard_recursion_config();
+
+/// A last stage of recursion, which wraps a shrunk VM proof into a proof
+/// made with another config, `OuterC`.
+///
+/// The verifier key of the shrunk proof is a witness, and is exposed after
+/// the public inputs of the VM proof, as its circuit digest followed by its
+/// constants and sigmas cap.  So one wrapper circuit, and one verifier of it,
+/// serves every program, and the caller checks the verifier key like it would
+/// for [`verify_recursive_vm_proof`].
+///
+/// This is not yet a proof an EVM contract can verify cheaply: that needs an
+/// `OuterC` that commits with a hash over the BN254 scalar field, which our
+/// plonky2 fork does not have.
+pub struct VMProofWrapperCircuit<F, C, OuterC, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    OuterC: GenericConfig<D, F = F>, {
+    pub circuit: CircuitData<F, OuterC, D>,
+    pub targets: VMVerificationTargets<D>,
+    _inner: PhantomData<C>,
+}
+
+impl<F, C, OuterC, const D: usize> VMProofWrapperCircuit<F, C, OuterC, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    OuterC: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    #[must_use]
+    pub fn new(config: CircuitConfig) -> Self {
+        let mut builder = CircuitBuilder::new(config);
+        let targets = verify_recursive_vm_proof::<F, C, D>(
+            &mut builder,
+            VM_PUBLIC_INPUT_SIZE,
+            &VM_RECURSION_CONFIG,
+            VM_RECURSION_THRESHOLD_DEGREE_BITS,
+        );
+        builder.register_public_inputs(&targets.proof_with_pis_target.public_inputs);
+        builder.register_public_inputs(&targets.vk_target.circuit_digest.elements);
+        for hash in &targets.vk_target.constants_sigmas_cap.0 {
+            builder.register_public_inputs(&hash.elements);
+        }
+        let circuit = builder.build::<OuterC>();
+        VMProofWrapperCircuit {
+            circuit,
+            targets,
+            _inner: PhantomData,
+        }
+    }
+
+    pub fn prove(
+        &self,
+        verifier_only: &VerifierOnlyCircuitData<C, D>,
+        proof: &ProofWithPublicInputs<F, C, D>,
+    ) -> Result<ProofWithPublicInputs<F, OuterC, D>> {
+        let mut inputs = PartialWitness::new();
+        inputs.set_proof_with_pis_target(&self.targets.proof_with_pis_target, proof);
+        inputs.set_verifier_data_target(&self.targets.vk_target, verifier_only);
+        self.circuit.prove(inputs)
+    }
+}
+
 /// Flat hash of trace cap.
 pub fn hash_trace_cap_circuit<F, C, const D: usize>(
     builder: &mut CircuitBuilder<F, D>,
@@ -1186,18 +1295,20 @@ mod tests {
     use plonky2::iop::witness::{PartialWitness, WitnessWrite};
     use plonky2::plonk::circuit_builder::CircuitBuilder;
     use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
     use plonky2::util::timing::TimingTree;
     use starky::config::StarkConfig;
 
+    use crate::public_sub_table::PublicSubTable;
     use crate::stark::batch_prover::batch_prove;
     use crate::stark::batch_verifier::batch_verify_proof;
     use crate::stark::mozak_stark::{MozakStark, PublicInputs, PUBLIC_TABLE_KINDS};
     use crate::stark::prover::prove;
     use crate::stark::recursive_verifier::{
         recursive_batch_stark_circuit, recursive_mozak_stark_circuit,
-        shrink_to_target_degree_bits_circuit, verify_recursive_vm_proof,
-        VMRecursiveProofPublicInputs, VM_PUBLIC_INPUT_SIZE, VM_RECURSION_CONFIG,
-        VM_RECURSION_THRESHOLD_DEGREE_BITS,
+        shrink_to_target_degree_bits_circuit, verify_recursive_vm_proof, VMProofWrapperCircuit,
+        VMRecursiveProofPublicInputs, VM_PROOF_WRAPPER_CONFIG, VM_PUBLIC_INPUT_SIZE,
+        VM_PUBLIC_SUB_TABLES_START, VM_RECURSION_CONFIG, VM_RECURSION_THRESHOLD_DEGREE_BITS,
     };
     use crate::stark::verifier::verify_proof;
     use crate::test_utils::{C, D, F};
@@ -1205,6 +1316,23 @@ mod tests {
 
     type S = MozakStark<F, D>;
 
+    #[test]
+    fn public_sub_tables_match_public_inputs() {
+        let stark = S::default();
+        let ranges = PublicSubTable::public_input_ranges(
+            &stark.public_sub_tables,
+            VM_PUBLIC_SUB_TABLES_START,
+        );
+        assert_eq!(ranges, [
+            VMRecursiveProofPublicInputs::<()>::EVENT_COMMITMENT_TAPE,
+            VMRecursiveProofPublicInputs::<()>::CASTLIST_COMMITMENT_TAPE,
+            VMRecursiveProofPublicInputs::<()>::ORACLE_TAPE,
+            VMRecursiveProofPublicInputs::<()>::PRIVATE_TAPE_DISCLOSURE,
+            VMRecursiveProofPublicInputs::<()>::PRIVATE_TAPE_COMMITMENT_TAPE,
+        ]);
+        assert_eq!(ranges.last().unwrap().end, VM_PUBLIC_INPUT_SIZE);
+    }
+
     #[test]
     fn recursive_verify_mozak_starks() -> Result<()> {
         let stark = S::default();
@@ -1245,12 +1373,11 @@ mod tests {
         );
 
         let recursive_proof = mozak_stark_circuit.prove(&mozak_proof)?;
-        let public_input_slice: [F; VM_PUBLIC_INPUT_SIZE] =
-            recursive_proof.public_inputs.as_slice().try_into().unwrap();
         let expected_event_commitment_tape = [F::ZERO; DIGEST_BYTES];
         let expected_castlist_commitment_tape = [F::ZERO; DIGEST_BYTES];
-        let recursive_proof_public_inputs: &VMRecursiveProofPublicInputs<F> =
-            &public_input_slice.into();
+        let recursive_proof_public_inputs =
+            VMRecursiveProofPublicInputs::from_public_inputs(&recursive_proof.public_inputs)
+                .unwrap();
         assert_eq!(
             recursive_proof_public_inputs.event_commitment_tape, expected_event_commitment_tape,
             "Could not find expected_event_commitment_tape in recursive proof's public inputs"
@@ -1312,14 +1439,13 @@ mod tests {
         );
 
         let recursive_proof = mozak_stark_circuit.prove(&mozak_proof)?;
-        let public_input_slice: [F; VM_PUBLIC_INPUT_SIZE] =
-            recursive_proof.public_inputs.as_slice().try_into().unwrap();
 
         let expected_program_hash = mozak_proof.get_program_hash_bytes();
         let expected_event_commitment_tape = [F::ZERO; DIGEST_BYTES];
         let expected_castlist_commitment_tape = [F::ZERO; DIGEST_BYTES];
-        let recursive_proof_public_inputs: &VMRecursiveProofPublicInputs<F> =
-            &public_input_slice.into();
+        let recursive_proof_public_inputs =
+            VMRecursiveProofPublicInputs::from_public_inputs(&recursive_proof.public_inputs)
+                .unwrap();
         assert_eq!(
             recursive_proof_public_inputs.program_hash_as_bytes,
             expected_program_hash
@@ -1491,4 +1617,70 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    #[ignore]
+    fn wrap_vm_proof() -> Result<()> {
+        let stark = S::default();
+        let config = StarkConfig::standard_fast_config();
+        let (program, record) = code::execute(
+            [Instruction {
+                op: Op::ADD,
+                args: Args {
+                    rd: 5,
+                    rs1: 6,
+                    rs2: 7,
+                    ..Args::default()
+                },
+            }],
+            &[],
+            &[(6, 100), (7, 200)],
+        );
+        let public_inputs = PublicInputs {
+            entry_point: from_u32(program.entry_point),
+        };
+        let mozak_proof = prove::<F, C, D>(
+            &program,
+            &record,
+            &stark,
+            &config,
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+
+        let recursion_circuit = recursive_mozak_stark_circuit::<F, C, D>(
+            &stark,
+            &mozak_proof.degree_bits(&config),
+            &VM_RECURSION_CONFIG,
+            &config,
+        );
+        let recursion_proof = recursion_circuit.prove(&mozak_proof)?;
+        let (final_circuit, final_proof) = shrink_to_target_degree_bits_circuit(
+            &recursion_circuit.circuit.verifier_only,
+            &recursion_circuit.circuit.common,
+            &VM_RECURSION_CONFIG,
+            VM_RECURSION_THRESHOLD_DEGREE_BITS,
+            &recursion_proof,
+        )?;
+
+        let wrapper = VMProofWrapperCircuit::<F, C, PoseidonGoldilocksConfig, D>::new(
+            VM_PROOF_WRAPPER_CONFIG,
+        );
+        let verifier_only = &final_circuit.circuit.verifier_only;
+        let wrapped_proof = wrapper.prove(verifier_only, &final_proof)?;
+
+        // The VM public inputs are carried forward, followed by the verifier
+        // key of the shrunk proof.
+        let (vm_public_inputs, vk) = wrapped_proof.public_inputs.split_at(VM_PUBLIC_INPUT_SIZE);
+        assert_eq!(vm_public_inputs, final_proof.public_inputs);
+        let (circuit_digest, constants_sigmas_cap) = vk.split_at(4);
+        assert_eq!(circuit_digest, verifier_only.circuit_digest.elements);
+        assert!(constants_sigmas_cap.iter().eq(verifier_only
+            .constants_sigmas_cap
+            .0
+            .iter()
+            .flat_map(|hash| &hash.elements)));
+
+        wrapper.circuit.verify(wrapped_proof)
+    }
 }
@@ -16,7 +16,9 @@ use starky::stark::{LookupConfig, Stark};
 
 use super::mozak_stark::{all_starks, MozakStark, TableKind, TableKindSetBuilder};
 use super::proof::AllProof;
-use crate::cross_table_lookup::{verify_cross_table_lookups_and_public_sub_tables, CtlCheckVars};
+use crate::cross_table_lookup::{
+    verify_cross_table_lookups_and_public_sub_tables, CrossTableLookup, CtlCheckVars, CtlLookup,
+};
 use crate::public_sub_table::reduce_public_sub_tables_values;
 use crate::stark::poly::eval_vanishing_poly;
 use crate::stark::proof::{AllProofChallenges, StarkOpeningSet, StarkProof, StarkProofChallenges};
@@ -39,10 +41,14 @@ where
         ctl_challenges,
     } = all_proof.get_challenges(config);
 
+    let lookups = CtlLookup::per_table(
+        &mozak_stark.cross_table_lookups,
+        &mozak_stark.public_sub_tables,
+    );
     let ctl_vars_per_table = CtlCheckVars::from_proofs(
         &all_proof.proofs,
         &mozak_stark.cross_table_lookups,
-        &mozak_stark.public_sub_tables,
+        &lookups,
         &ctl_challenges,
     );
 
@@ -63,14 +69,19 @@ where
     ensure!(program_id == all_proof.program_id);
 
     all_starks!(mozak_stark, |stark, kind| {
-        verify_stark_proof_with_challenges(
-            stark,
-            &all_proof.proofs[kind],
-            &stark_challenges[kind],
-            public_inputs[kind],
-            &ctl_vars_per_table[kind],
-            config,
-        )?;
+        let proof = &all_proof.proofs[kind];
+        if let Some(challenges) = &stark_challenges[kind] {
+            verify_stark_proof_with_challenges(
+                stark,
+                proof,
+                challenges,
+                public_inputs[kind],
+                &ctl_vars_per_table[kind],
+                config,
+            )?;
+        } else {
+            verify_empty_stark_proof(mozak_stark, kind, proof, config)?;
+        }
     });
 
     verify_cross_table_lookups_and_public_sub_tables::<F, D>(
@@ -85,6 +96,33 @@ where
     Ok(())
 }
 
+/// Checks that the table of `kind` may be left out, and that its empty
+/// `proof` takes no part in any lookup.
+fn verify_empty_stark_proof<F, C, const D: usize>(
+    mozak_stark: &MozakStark<F, D>,
+    kind: TableKind,
+    proof: &StarkProof<F, C, D>,
+    config: &StarkConfig,
+) -> Result<()>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>, {
+    ensure!(kind.may_be_empty(), "{kind:?} table may not be empty");
+    let num_public_sub_tables = mozak_stark
+        .public_sub_tables
+        .iter()
+        .filter(|public_sub_table| public_sub_table.table.kind == kind)
+        .count();
+    let num_ctl_zs = CrossTableLookup::num_ctl_zs(
+        &mozak_stark.cross_table_lookups,
+        kind,
+        config.num_challenges,
+    ) + num_public_sub_tables * config.num_challenges;
+    ensure!(proof.trace_cap == StarkProof::<F, C, D>::empty_trace_cap(config));
+    ensure!(proof.openings.ctl_zs_last == vec![F::ZERO; num_ctl_zs]);
+    Ok(())
+}
+
 pub(crate) fn verify_quotient_polynomials<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -8,8 +8,9 @@ use crate::cross_table_lookup::{Column, ColumnWithTypedInput};
 use crate::memory::columns::MemoryCtl;
 use crate::register::RegisterCtl;
 use crate::stark::mozak_stark::{
-    CallTapeTable, CastListCommitmentTapeTable, EventsCommitmentTapeTable, SelfProgIdTapeTable,
-    StorageDevicePrivateTable, StorageDevicePublicTable, TableKind, TableWithTypedOutput,
+    CallTapeTable, CastListCommitmentTapeTable, EventsCommitmentTapeTable, OracleTapeTable,
+    PrivateTapeCommitmentTapeTable, SelfProgIdTapeTable, StorageDevicePrivateTable,
+    StorageDevicePublicTable, TableKind, TableWithTypedOutput,
 };
 use crate::tape_commitments::columns::TapeCommitmentCTL;
 
@@ -38,6 +39,12 @@ pub struct StorageDevice<T> {
     pub ops: Ops<T>,
     /// Helper to decrease poly degree
     pub is_lv_and_nv_are_memory_rows: T,
+    /// Index in the tape of the byte of a memory row, ie the number of bytes
+    /// read from this tape before it.
+    pub tape_offset: T,
+    /// Whether the byte of a memory row is made public, see
+    /// [`make_private_tape_disclosure_public`](crate::tape_commitments::columns::make_private_tape_disclosure_public).
+    pub is_disclosed: T,
 }
 
 columns_view_impl!(StorageDevice);
@@ -112,6 +119,8 @@ pub fn register_looking() -> Vec<TableWithTypedOutput<RegisterCtl<Column>>> {
         EventsCommitmentTapeTable::new(data, COL_MAP.ops.is_storage_device),
         CastListCommitmentTapeTable::new(data, COL_MAP.ops.is_storage_device),
         SelfProgIdTapeTable::new(data, COL_MAP.ops.is_storage_device),
+        OracleTapeTable::new(data, COL_MAP.ops.is_storage_device),
+        PrivateTapeCommitmentTapeTable::new(data, COL_MAP.ops.is_storage_device),
     ]
 }
 
@@ -134,3 +143,31 @@ pub fn castlist_commitment_lookup_in_tape_commitments(
     };
     CastListCommitmentTapeTable::new(data, COL_MAP.ops.is_memory_store)
 }
+
+#[must_use]
+pub fn oracle_lookup_in_tape_commitments() -> TableWithTypedOutput<TapeCommitmentCTL<Column>> {
+    let data = TapeCommitmentCTL {
+        byte: COL_MAP.value,
+        index: i64::try_from(DIGEST_BYTES - 1).unwrap() - COL_MAP.size,
+    };
+    OracleTapeTable::new(data, COL_MAP.ops.is_memory_store)
+}
+
+#[must_use]
+pub fn private_tape_commitment_lookup_in_tape_commitments(
+) -> TableWithTypedOutput<TapeCommitmentCTL<Column>> {
+    let data = TapeCommitmentCTL {
+        byte: COL_MAP.value,
+        index: i64::try_from(DIGEST_BYTES - 1).unwrap() - COL_MAP.size,
+    };
+    PrivateTapeCommitmentTapeTable::new(data, COL_MAP.ops.is_memory_store)
+}
+
+#[must_use]
+pub fn disclosure_lookup_in_tape_commitments() -> TableWithTypedOutput<TapeCommitmentCTL<Column>> {
+    let data = TapeCommitmentCTL {
+        byte: COL_MAP.value,
+        index: COL_MAP.tape_offset,
+    };
+    StorageDevicePrivateTable::new(data, COL_MAP.is_disclosed)
+}
@@ -38,6 +38,8 @@ fn is_storage_device_opcode<F: RichField>(op: StorageDeviceOpcode) -> F {
             | StorageDeviceOpcode::StoreEventsCommitmentTape
             | StorageDeviceOpcode::StoreCastListCommitmentTape
             | StorageDeviceOpcode::StoreSelfProgIdTape
+            | StorageDeviceOpcode::StoreOracleTape
+            | StorageDeviceOpcode::StorePrivateTapeCommitmentTape
     ))
 }
 
@@ -46,45 +48,55 @@ pub fn generate_storage_trace<F: RichField>(
     step_rows: &[Row<F>],
     which_tape: StorageDeviceOpcode,
 ) -> Vec<StorageDevice<F>> {
-    pad_mem_trace(
-        filter(step_rows, which_tape)
-            .flat_map(|s| {
-                let StorageDeviceEntry { op, data, addr }: StorageDeviceEntry =
-                    s.aux.storage_device_entry.clone().unwrap_or_default();
-                let len = data.len();
-                chain!(
-                    // initial storage-device-element
-                    [StorageDevice {
+    let mut trace = filter(step_rows, which_tape)
+        .flat_map(|s| {
+            let StorageDeviceEntry {
+                op,
+                data,
+                addr,
+                disclosed,
+            }: StorageDeviceEntry = s.aux.storage_device_entry.clone().unwrap_or_default();
+            let len = data.len();
+            chain!(
+                // initial storage-device-element
+                [StorageDevice {
+                    clk: get_memory_inst_clk(s),
+                    addr: F::from_canonical_u32(addr),
+                    size: F::from_canonical_usize(len),
+                    ops: Ops {
+                        is_storage_device: is_storage_device_opcode(op),
+                        is_memory_store: F::ZERO,
+                    },
+                    is_lv_and_nv_are_memory_rows: F::from_bool(false),
+                    ..Default::default()
+                }],
+                // extended memory elements
+                data.into_iter().enumerate().map(move |(i, local_value)| {
+                    let local_address = addr.wrapping_add(u32::try_from(i).unwrap());
+                    let local_size = len - i - 1;
+                    StorageDevice {
                         clk: get_memory_inst_clk(s),
-                        addr: F::from_canonical_u32(addr),
-                        size: F::from_canonical_usize(len),
+                        addr: F::from_canonical_u32(local_address),
+                        size: F::from_canonical_usize(local_size),
+                        value: F::from_canonical_u8(local_value),
                         ops: Ops {
-                            is_storage_device: is_storage_device_opcode(op),
-                            is_memory_store: F::ZERO,
+                            is_storage_device: F::ZERO,
+                            is_memory_store: is_storage_device_opcode(op),
                         },
-                        is_lv_and_nv_are_memory_rows: F::from_bool(false),
+                        is_lv_and_nv_are_memory_rows: F::from_bool(i + 1 != len),
+                        is_disclosed: F::from_bool(disclosed.get(i).copied().unwrap_or_default()),
                         ..Default::default()
-                    }],
-                    // extended memory elements
-                    data.into_iter().enumerate().map(move |(i, local_value)| {
-                        let local_address = addr.wrapping_add(u32::try_from(i).unwrap());
-                        let local_size = len - i - 1;
-                        StorageDevice {
-                            clk: get_memory_inst_clk(s),
-                            addr: F::from_canonical_u32(local_address),
-                            size: F::from_canonical_usize(local_size),
-                            value: F::from_canonical_u8(local_value),
-                            ops: Ops {
-                                is_storage_device: F::ZERO,
-                                is_memory_store: is_storage_device_opcode(op),
-                            },
-                            is_lv_and_nv_are_memory_rows: F::from_bool(i + 1 != len),
-                        }
-                    })
-                )
-            })
-            .collect::<Vec<StorageDevice<F>>>(),
-    )
+                    }
+                })
+            )
+        })
+        .collect::<Vec<StorageDevice<F>>>();
+    let mut tape_offset = F::ZERO;
+    for row in &mut trace {
+        row.tape_offset = tape_offset;
+        tape_offset += row.ops.is_memory_store;
+    }
+    pad_mem_trace(trace)
 }
 
 #[must_use]
@@ -127,3 +139,18 @@ pub fn generate_self_prog_id_tape_trace<F: RichField>(
 ) -> Vec<StorageDevice<F>> {
     generate_storage_trace(step_rows, StorageDeviceOpcode::StoreSelfProgIdTape)
 }
+
+#[must_use]
+pub fn generate_oracle_tape_trace<F: RichField>(step_rows: &[Row<F>]) -> Vec<StorageDevice<F>> {
+    generate_storage_trace(step_rows, StorageDeviceOpcode::StoreOracleTape)
+}
+
+#[must_use]
+pub fn generate_private_tape_commitment_tape_trace<F: RichField>(
+    step_rows: &[Row<F>],
+) -> Vec<StorageDevice<F>> {
+    generate_storage_trace(
+        step_rows,
+        StorageDeviceOpcode::StorePrivateTapeCommitmentTape,
+    )
+}
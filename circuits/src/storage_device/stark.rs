@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use expr::{Expr, ExprBuilder, StarkFrameTyped};
+use expr::{ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
 use mozak_circuits_derive::StarkNameDisplay;
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
@@ -12,7 +12,7 @@ use starky::evaluation_frame::StarkFrame;
 use starky::stark::Stark;
 
 use crate::columns_view::HasNamedColumns;
-use crate::expr::{build_ext, build_packed, ConstraintBuilder};
+use crate::expr::{build_ext, build_packed};
 use crate::storage_device::columns::{StorageDevice, NUM_STORAGE_DEVICE_COLS};
 use crate::unstark::NoColumns;
 
@@ -32,10 +32,10 @@ const PUBLIC_INPUTS: usize = 0;
 // Design description - https://docs.google.com/presentation/d/1J0BJd49BMQh3UR5TrOhe3k67plHxnohFtFVrMpDJ1oc/edit?usp=sharing
 fn generate_constraints<'a, T: Copy>(
     vars: &StarkFrameTyped<StorageDevice<Expr<'a, T>>, NoColumns<Expr<'a, T>>>,
-) -> ConstraintBuilder<Expr<'a, T>> {
+) -> ConstraintCollector<'a, T> {
     let lv = vars.local_values;
     let nv = vars.next_values;
-    let mut constraints = ConstraintBuilder::default();
+    let mut constraints = ConstraintCollector::default();
 
     constraints.always(lv.ops.is_memory_store.is_binary());
     constraints.always(lv.ops.is_storage_device.is_binary());
@@ -74,6 +74,15 @@ fn generate_constraints<'a, T: Copy>(
     //      nv.is_lv_and_nv_are_memory_rows == 1
     constraints.always(lv.ops.is_storage_device * nv.size * (nv.is_lv_and_nv_are_memory_rows - 1));
 
+    // Each memory row stores the next byte of the tape.
+    constraints.first_row(lv.tape_offset);
+    constraints.transition(
+        nv.is_executed() * (nv.tape_offset - (lv.tape_offset + lv.ops.is_memory_store)),
+    );
+    // Only bytes of the tape can be disclosed.
+    constraints.always(lv.is_disclosed.is_binary());
+    constraints.always(lv.is_disclosed * (1 - lv.ops.is_memory_store));
+
     constraints
 }
 
@@ -358,6 +367,34 @@ mod tests {
         Stark::prove_and_verify(&program, &record).unwrap();
     }
 
+    pub fn prove_oracle_tape<Stark: ProveAndVerify>(address: u32, oracle_tape: [u8; 32]) {
+        let (program, record) = execute_code_with_ro_memory(
+            // set sys-call IO_READ in x10(or a0)
+            [ECALL],
+            &[],
+            &(0..DIGEST_BYTES)
+                .map(|i| (address.wrapping_add(u32::try_from(i).unwrap()), 0_u8))
+                .collect_vec(),
+            &[
+                (REG_A0, ecall::ORACLE_TAPE),
+                (REG_A1, address),                              // A1 - address
+                (REG_A2, u32::try_from(DIGEST_BYTES).unwrap()), // A2 - size
+            ],
+            RawTapes {
+                oracle_tape,
+                ..Default::default()
+            },
+        );
+
+        assert_ne!(
+            record.last_state.oracle_tape.len(),
+            0,
+            "Proving an execution with an empty tape might make our tests pass, even if things are wrong"
+        );
+
+        Stark::prove_and_verify(&program, &record).unwrap();
+    }
+
     pub fn prove_read_explicit<Stark: ProveAndVerify>(address: u32, content: u8) {
         let (program, record) = execute_code_with_ro_memory(
             [
@@ -475,6 +512,11 @@ mod tests {
             prove_cast_list_commitment_tape::<MozakStark<F, D>>(address, [content; 32]);
         }
 
+        #[test]
+        fn prove_oracle_tape_mozak(address in u32_extra(), content in u8_extra()) {
+            prove_oracle_tape::<MozakStark<F, D>>(address, [content; 32]);
+        }
+
         #[test]
         fn prove_read_mozak_explicit(address in u32_extra(), content in u8_extra()) {
             prove_read_explicit::<MozakStark<F, D>>(address, content);
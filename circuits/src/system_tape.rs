@@ -0,0 +1,179 @@
+//! Reading the system tape of a transaction into the tapes of one of its
+//! programs.
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use itertools::{izip, Itertools};
+use log::debug;
+use mozak_runner::state::RawTapes;
+use mozak_sdk::common::merkle::merkleize;
+use mozak_sdk::common::types::{
+    CanonicalOrderedTemporalHints, Poseidon2Hash, ProgramIdentifier, SystemTape,
+};
+use mozak_sdk::core::constants::DIGEST_BYTES;
+use mozak_sdk::native::poseidon::poseidon2_hash_with_pad;
+use rkyv::rancor::{Panic, Strategy};
+use rkyv::ser::AllocSerializer;
+
+/// Deserializes a serde JSON serialized system tape binary file into a
+/// [`SystemTape`].
+///
+/// # Errors
+///
+/// Errors if reading from the binary file fails.
+pub fn deserialize_system_tape<F: std::io::Read>(mut bin: F) -> Result<SystemTape> {
+    let mut sys_tapes_bytes = Vec::new();
+    let bytes_read = bin.read_to_end(&mut sys_tapes_bytes)?;
+    debug!("Read {bytes_read} of system tape data.");
+    let deserialized: SystemTape = serde_json::from_slice(&sys_tapes_bytes)?;
+    Ok(deserialized)
+}
+
+fn length_prefixed_bytes(data: Vec<u8>, dgb_string: &str) -> Vec<u8> {
+    let data_len = data.len();
+    let mut len_prefix_bytes = Vec::with_capacity(data_len + 4);
+    len_prefix_bytes.extend_from_slice(
+        &(u32::try_from(data.len()))
+            .expect("length of data's max size shouldn't be more than u32")
+            .to_le_bytes(),
+    );
+    len_prefix_bytes.extend(data);
+    debug!(
+        "Length-Prefixed {:<15} of byte len: {:>5}, on-mem bytes: {:>5}",
+        dgb_string,
+        data_len,
+        len_prefix_bytes.len()
+    );
+    len_prefix_bytes
+}
+
+/// Returns the programs taking part in the transaction described by `sys`,
+/// in the order they are committed to.
+#[must_use]
+pub fn cast_list_from_system_tape(sys: &SystemTape) -> Vec<ProgramIdentifier> {
+    sys.call_tape
+        .writer
+        .iter()
+        .map(|msg| msg.callee)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect_vec()
+}
+
+/// Returns the events emitted by `self_prog_id`, in canonical order.
+#[must_use]
+pub fn canonical_order_temporal_hints(
+    sys: &SystemTape,
+    self_prog_id: ProgramIdentifier,
+) -> Vec<CanonicalOrderedTemporalHints> {
+    sys.event_tape
+        .writer
+        .get(&self_prog_id)
+        .cloned()
+        .unwrap_or_default()
+        .get_canonical_order_temporal_hints()
+}
+
+/// Computes the commitment of the events emitted by a program, as written to
+/// its events commitment tape.
+#[must_use]
+pub fn events_commitment(hints: &[CanonicalOrderedTemporalHints]) -> Poseidon2Hash {
+    merkleize(
+        hints
+            .iter()
+            .map(|x| {
+                (
+                    // May not be the best idea if
+                    // `addr` > goldilock's prime, cc
+                    // @Kapil
+                    u64::from_le_bytes(x.0.address.inner()),
+                    x.0.canonical_hash(),
+                )
+            })
+            .collect::<Vec<(u64, Poseidon2Hash)>>(),
+    )
+}
+
+/// Computes the commitment of a cast list, as written to the cast list
+/// commitment tape.
+#[must_use]
+pub fn cast_list_commitment(cast_list: &[ProgramIdentifier]) -> Poseidon2Hash {
+    merkleize(izip!(0.., cast_list).map(|(idx, x)| (idx, x.0)).collect())
+}
+
+/// Computes the commitment of a program's private input, as written to its
+/// private tape commitment tape.  Together with the length prefix, the private
+/// input is the whole private tape.
+#[must_use]
+pub fn private_tape_commitment(private_input: &[u8]) -> Poseidon2Hash {
+    poseidon2_hash_with_pad(private_input)
+}
+
+/// Builds the tapes `self_prog_id` reads when run as part of the transaction
+/// whose serde JSON serialized system tape is `sys`, or empty tapes without
+/// one.
+#[must_use]
+pub fn raw_tapes_from_system_tape<F: std::io::Read>(
+    sys: Option<F>,
+    self_prog_id: ProgramIdentifier,
+) -> RawTapes {
+    if sys.is_none() {
+        return RawTapes {
+            private_tape_commitment_tape: private_tape_commitment(&[]).0,
+            ..RawTapes::default()
+        };
+    }
+
+    let sys = &deserialize_system_tape(sys.unwrap()).unwrap();
+
+    let cast_list = cast_list_from_system_tape(sys);
+
+    let canonical_order_temporal_hints = canonical_order_temporal_hints(sys, self_prog_id);
+
+    let events_commitment_tape = events_commitment(&canonical_order_temporal_hints).0;
+
+    let cast_list_commitment_tape = cast_list_commitment(&cast_list).0;
+
+    debug!("Self Prog ID: {self_prog_id:#?}");
+    debug!("Found events: {:#?}", canonical_order_temporal_hints.len());
+
+    {
+        fn serialise<T>(tape: &T, dgb_string: &str) -> Vec<u8>
+        where
+            T: rkyv::Archive + rkyv::Serialize<Strategy<AllocSerializer<256>, Panic>>, {
+            let tape_bytes = rkyv::to_bytes::<_, 256, _>(tape).unwrap().into();
+            length_prefixed_bytes(tape_bytes, dgb_string)
+        }
+
+        let private_input = sys
+            .private_input_tape
+            .writer
+            .get(&self_prog_id)
+            .cloned()
+            .unwrap_or_default()
+            .0;
+        let private_tape_commitment_tape = private_tape_commitment(&private_input).0;
+
+        RawTapes {
+            private_tape: length_prefixed_bytes(private_input, "PRIVATE_TAPE"),
+            public_tape: length_prefixed_bytes(
+                sys.public_input_tape
+                    .writer
+                    .get(&self_prog_id)
+                    .cloned()
+                    .unwrap_or_default()
+                    .0,
+                "PUBLIC_TAPE",
+            ),
+            call_tape: serialise(&sys.call_tape.writer, "CALL_TAPE"),
+            event_tape: serialise(&canonical_order_temporal_hints, "EVENT_TAPE"),
+            self_prog_id_tape: self_prog_id.0 .0,
+            events_commitment_tape,
+            cast_list_commitment_tape,
+            // The system tape doesn't carry any oracle data.
+            oracle_tape: [0; DIGEST_BYTES],
+            private_tape_commitment_tape,
+            private_tape_disclosures: vec![],
+        }
+    }
+}
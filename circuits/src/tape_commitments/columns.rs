@@ -23,6 +23,30 @@ pub struct TapeCommitments<T> {
     pub event_commitment_tape_multiplicity: T,
     pub is_castlist_commitment_tape_row: T,
     pub is_event_commitment_tape_row: T,
+    pub oracle_tape_multiplicity: T,
+    pub is_oracle_tape_row: T,
+    /// Whether a disclosure row is backed by a byte of the private tape.
+    /// Unused disclosure slots have this set to zero.
+    pub private_tape_disclosure_multiplicity: T,
+    pub is_private_tape_disclosure_row: T,
+    pub private_tape_commitment_tape_multiplicity: T,
+    pub is_private_tape_commitment_tape_row: T,
+}
+
+/// Number of bytes of the private tape that can be disclosed, see
+/// [`make_private_tape_disclosure_public`].
+pub const PRIVATE_TAPE_DISCLOSURE_BYTES: usize = 32;
+
+columns_view_impl!(PrivateTapeDisclosure);
+
+/// A byte of the private tape as it appears in the public inputs.
+#[repr(C)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct PrivateTapeDisclosure<T> {
+    /// Whether this slot holds a disclosed byte.
+    pub is_disclosed: T,
+    pub index: T,
+    pub byte: T,
 }
 columns_view_impl!(CommitmentByteWithIndex);
 
@@ -62,24 +86,97 @@ pub fn lookup_for_event_tape_commitment() -> TableWithTypedOutput<TapeCommitment
     )
 }
 
+#[must_use]
+pub fn lookup_for_oracle_tape() -> TableWithTypedOutput<TapeCommitmentCTL<Column>> {
+    TapeCommitmentsTable::new(
+        TapeCommitmentCTL {
+            byte: TAPE_COMMITMENTS.commitment_byte_row.byte,
+            index: TAPE_COMMITMENTS.commitment_byte_row.index,
+        },
+        TAPE_COMMITMENTS.oracle_tape_multiplicity,
+    )
+}
+
+#[must_use]
+pub fn lookup_for_private_tape_disclosure() -> TableWithTypedOutput<TapeCommitmentCTL<Column>> {
+    TapeCommitmentsTable::new(
+        TapeCommitmentCTL {
+            byte: TAPE_COMMITMENTS.commitment_byte_row.byte,
+            index: TAPE_COMMITMENTS.commitment_byte_row.index,
+        },
+        TAPE_COMMITMENTS.private_tape_disclosure_multiplicity,
+    )
+}
+
+#[must_use]
+pub fn lookup_for_private_tape_commitment_tape() -> TableWithTypedOutput<TapeCommitmentCTL<Column>>
+{
+    TapeCommitmentsTable::new(
+        TapeCommitmentCTL {
+            byte: TAPE_COMMITMENTS.commitment_byte_row.byte,
+            index: TAPE_COMMITMENTS.commitment_byte_row.index,
+        },
+        TAPE_COMMITMENTS.private_tape_commitment_tape_multiplicity,
+    )
+}
+
 #[must_use]
 pub fn make_event_commitment_tape_public() -> PublicSubTable {
-    PublicSubTable {
-        table: TapeCommitmentsTable::new(
-            vec![TAPE_COMMITMENTS.commitment_byte_row.byte],
-            TAPE_COMMITMENTS.is_event_commitment_tape_row,
-        ),
-        num_rows: DIGEST_BYTES,
-    }
+    TapeCommitmentsTable::public(
+        [TAPE_COMMITMENTS.commitment_byte_row.byte],
+        TAPE_COMMITMENTS.is_event_commitment_tape_row,
+        DIGEST_BYTES,
+    )
 }
 
 #[must_use]
 pub fn make_castlist_commitment_tape_public() -> PublicSubTable {
-    PublicSubTable {
-        table: TapeCommitmentsTable::new(
-            vec![TAPE_COMMITMENTS.commitment_byte_row.byte],
-            TAPE_COMMITMENTS.is_castlist_commitment_tape_row,
-        ),
-        num_rows: DIGEST_BYTES,
-    }
+    TapeCommitmentsTable::public(
+        [TAPE_COMMITMENTS.commitment_byte_row.byte],
+        TAPE_COMMITMENTS.is_castlist_commitment_tape_row,
+        DIGEST_BYTES,
+    )
+}
+
+/// The oracle tape holds data attested by the node, so a verifier needs to
+/// see it to check the attestation.
+#[must_use]
+pub fn make_oracle_tape_public() -> PublicSubTable {
+    TapeCommitmentsTable::public(
+        [TAPE_COMMITMENTS.commitment_byte_row.byte],
+        TAPE_COMMITMENTS.is_oracle_tape_row,
+        DIGEST_BYTES,
+    )
+}
+
+/// The private tape stays hidden, except for the bytes that the prover chose
+/// to disclose, eg a nonce next to a secret key.  Each disclosed byte is
+/// made public along with its index in the tape, and is looked up in the
+/// private tape table, so it has to be what the program read.
+///
+/// The program also reads the whole private tape, and checks it against the
+/// commitment made public by [`make_private_tape_commitment_tape_public`], so
+/// the disclosed bytes are tied to that commitment.
+#[must_use]
+pub fn make_private_tape_disclosure_public() -> PublicSubTable {
+    TapeCommitmentsTable::public(
+        PrivateTapeDisclosure {
+            is_disclosed: TAPE_COMMITMENTS.private_tape_disclosure_multiplicity,
+            index: TAPE_COMMITMENTS.commitment_byte_row.index,
+            byte: TAPE_COMMITMENTS.commitment_byte_row.byte,
+        },
+        TAPE_COMMITMENTS.is_private_tape_disclosure_row,
+        PRIVATE_TAPE_DISCLOSURE_BYTES,
+    )
+}
+
+/// A verifier checks the disclosed bytes of the private tape against this
+/// commitment to the whole of it, eg one that was published earlier.
+#[must_use]
+pub fn make_private_tape_commitment_tape_public() -> PublicSubTable {
+    TapeCommitmentsTable::public(
+        [TAPE_COMMITMENTS.commitment_byte_row.byte],
+        TAPE_COMMITMENTS.is_private_tape_commitment_tape_row,
+        DIGEST_BYTES,
+    )
 }
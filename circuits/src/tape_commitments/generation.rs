@@ -1,9 +1,15 @@
+use std::iter::zip;
+
+use anyhow::{ensure, Result};
 use itertools::{chain, Itertools};
 use mozak_runner::state::StorageDeviceOpcode;
 use mozak_runner::vm::{ExecutionRecord, Row};
 use plonky2::hash::hash_types::RichField;
 
-use crate::tape_commitments::columns::{CommitmentByteWithIndex, TapeCommitments};
+use crate::tape_commitments::columns::{
+    CommitmentByteWithIndex, TapeCommitments, PRIVATE_TAPE_DISCLOSURE_BYTES,
+};
+use crate::utils::pad_trace_with_default;
 
 #[must_use]
 pub fn num_ecalls<F: RichField>(step_rows: &[Row<F>], which_tape: StorageDeviceOpcode) -> usize {
@@ -30,6 +36,9 @@ pub fn generate_tape_commitment_trace_with_op_code<F: RichField>(
             &execution.last_state.cast_list_commitment_tape,
         StorageDeviceOpcode::StoreEventsCommitmentTape =>
             &execution.last_state.events_commitment_tape,
+        StorageDeviceOpcode::StoreOracleTape => &execution.last_state.oracle_tape,
+        StorageDeviceOpcode::StorePrivateTapeCommitmentTape =>
+            &execution.last_state.private_tape_commitment_tape,
         _ => unreachable!(),
     };
     // theoretically, we have no restriction on number of ecalls made,
@@ -50,10 +59,23 @@ pub fn generate_tape_commitment_trace_with_op_code<F: RichField>(
         StorageDeviceOpcode::StoreEventsCommitmentTape
     ));
 
+    let is_oracle_tape_row = F::from_bool(matches!(
+        which_tape_commitment,
+        StorageDeviceOpcode::StoreOracleTape
+    ));
+
+    let is_private_tape_commitment_tape_row = F::from_bool(matches!(
+        which_tape_commitment,
+        StorageDeviceOpcode::StorePrivateTapeCommitmentTape
+    ));
+
     let castlist_commitment_tape_multiplicity =
         is_castlist_commitment_tape_row * num_tape_commitment_ecalls;
     let event_commitment_tape_multiplicity =
         is_event_commitment_tape_row * num_tape_commitment_ecalls;
+    let oracle_tape_multiplicity = is_oracle_tape_row * num_tape_commitment_ecalls;
+    let private_tape_commitment_tape_multiplicity =
+        is_private_tape_commitment_tape_row * num_tape_commitment_ecalls;
 
     tape.iter()
         .enumerate()
@@ -67,14 +89,61 @@ pub fn generate_tape_commitment_trace_with_op_code<F: RichField>(
             castlist_commitment_tape_multiplicity,
             is_castlist_commitment_tape_row,
             is_event_commitment_tape_row,
+            oracle_tape_multiplicity,
+            is_oracle_tape_row,
+            private_tape_commitment_tape_multiplicity,
+            is_private_tape_commitment_tape_row,
+            ..Default::default()
         })
         .collect_vec()
 }
 
-#[must_use]
+/// Returns one row per disclosed byte of the private tape, followed by unused
+/// slots, for [`PRIVATE_TAPE_DISCLOSURE_BYTES`] rows in total.
+///
+/// # Errors
+///
+/// Errors if more bytes are disclosed than there are slots.
+pub fn generate_private_tape_disclosure_trace<F: RichField>(
+    execution: &ExecutionRecord<F>,
+) -> Result<Vec<TapeCommitments<F>>> {
+    let mut trace = execution
+        .executed
+        .iter()
+        .filter_map(|row| row.aux.storage_device_entry.as_ref())
+        .filter(|entry| entry.op == StorageDeviceOpcode::StorePrivate)
+        .flat_map(|entry| zip(&entry.data, &entry.disclosed))
+        .enumerate()
+        .filter(|(_, (_, &disclosed))| disclosed)
+        .map(|(index, (&byte, _))| TapeCommitments {
+            commitment_byte_row: CommitmentByteWithIndex {
+                byte: F::from_canonical_u8(byte),
+                index: F::from_canonical_usize(index),
+            },
+            private_tape_disclosure_multiplicity: F::ONE,
+            is_private_tape_disclosure_row: F::ONE,
+            ..Default::default()
+        })
+        .collect_vec();
+    ensure!(
+        trace.len() <= PRIVATE_TAPE_DISCLOSURE_BYTES,
+        "can disclose at most {PRIVATE_TAPE_DISCLOSURE_BYTES} bytes of the private tape, but {} were disclosed",
+        trace.len()
+    );
+    trace.resize(PRIVATE_TAPE_DISCLOSURE_BYTES, TapeCommitments {
+        is_private_tape_disclosure_row: F::ONE,
+        ..Default::default()
+    });
+    Ok(trace)
+}
+
+/// # Errors
+///
+/// Errors if more bytes of the private tape are disclosed than can be proven,
+/// see [`generate_private_tape_disclosure_trace`].
 pub fn generate_tape_commitments_trace<F: RichField>(
     execution: &ExecutionRecord<F>,
-) -> Vec<TapeCommitments<F>> {
+) -> Result<Vec<TapeCommitments<F>>> {
     let cast_list_commitment_trace = generate_tape_commitment_trace_with_op_code(
         execution,
         StorageDeviceOpcode::StoreCastListCommitmentTape,
@@ -85,6 +154,26 @@ pub fn generate_tape_commitments_trace<F: RichField>(
         StorageDeviceOpcode::StoreEventsCommitmentTape,
     );
     log::trace!("{events_commitment_tape_trace:?}");
-    // Note that the final trace length is 64, hence no need to pad.
-    chain(cast_list_commitment_trace, events_commitment_tape_trace).collect_vec()
+    let oracle_tape_trace = generate_tape_commitment_trace_with_op_code(
+        execution,
+        StorageDeviceOpcode::StoreOracleTape,
+    );
+    log::trace!("{oracle_tape_trace:?}");
+    let private_tape_commitment_tape_trace = generate_tape_commitment_trace_with_op_code(
+        execution,
+        StorageDeviceOpcode::StorePrivateTapeCommitmentTape,
+    );
+    log::trace!("{private_tape_commitment_tape_trace:?}");
+    let private_tape_disclosure_trace = generate_private_tape_disclosure_trace(execution)?;
+    log::trace!("{private_tape_disclosure_trace:?}");
+    Ok(pad_trace_with_default(
+        chain!(
+            cast_list_commitment_trace,
+            events_commitment_tape_trace,
+            oracle_tape_trace,
+            private_tape_disclosure_trace,
+            private_tape_commitment_tape_trace
+        )
+        .collect_vec(),
+    ))
 }
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use expr::{Expr, ExprBuilder, StarkFrameTyped};
+use expr::{ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
 use mozak_circuits_derive::StarkNameDisplay;
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
@@ -13,22 +13,40 @@ use starky::stark::Stark;
 
 use super::columns::TapeCommitments;
 use crate::columns_view::{HasNamedColumns, NumberOfColumns};
-use crate::expr::{build_ext, build_packed, ConstraintBuilder};
+use crate::expr::{build_ext, build_packed};
 use crate::unstark::NoColumns;
 fn generate_constraints<'a, T: Copy>(
     vars: &StarkFrameTyped<TapeCommitments<Expr<'a, T>>, NoColumns<Expr<'a, T>>>,
-) -> ConstraintBuilder<Expr<'a, T>> {
+) -> ConstraintCollector<'a, T> {
     let lv: &TapeCommitments<Expr<'a, T>> = &vars.local_values;
-    let mut constraint = ConstraintBuilder::default();
+    let mut constraint = ConstraintCollector::default();
     constraint.always(lv.is_event_commitment_tape_row.is_binary());
     constraint.always(lv.is_castlist_commitment_tape_row.is_binary());
-    constraint
-        .always((lv.is_castlist_commitment_tape_row + lv.is_event_commitment_tape_row).is_binary());
+    constraint.always(lv.is_oracle_tape_row.is_binary());
+    constraint.always(lv.is_private_tape_disclosure_row.is_binary());
+    constraint.always(lv.is_private_tape_commitment_tape_row.is_binary());
+    constraint.always(
+        (lv.is_castlist_commitment_tape_row
+            + lv.is_event_commitment_tape_row
+            + lv.is_oracle_tape_row
+            + lv.is_private_tape_disclosure_row
+            + lv.is_private_tape_commitment_tape_row)
+            .is_binary(),
+    );
     constraint
         .always(lv.event_commitment_tape_multiplicity * (1 - lv.is_event_commitment_tape_row));
     constraint.always(
         lv.castlist_commitment_tape_multiplicity * (1 - lv.is_castlist_commitment_tape_row),
     );
+    constraint.always(lv.oracle_tape_multiplicity * (1 - lv.is_oracle_tape_row));
+    constraint.always(
+        lv.private_tape_commitment_tape_multiplicity * (1 - lv.is_private_tape_commitment_tape_row),
+    );
+    // Each byte of the private tape is read only once, so it is disclosed at
+    // most once.  A zero multiplicity marks an unused disclosure slot.
+    constraint.always(lv.private_tape_disclosure_multiplicity.is_binary());
+    constraint
+        .always(lv.private_tape_disclosure_multiplicity * (1 - lv.is_private_tape_disclosure_row));
     constraint
 }
 
@@ -82,7 +100,7 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for TapeCommitmen
 
 #[cfg(test)]
 mod tests {
-    use itertools::chain;
+    use itertools::{chain, Itertools};
     use mozak_runner::code;
     use mozak_runner::decode::ECALL;
     use mozak_runner::instruction::{Args, Instruction, Op};
@@ -99,12 +117,15 @@ mod tests {
     use starky::stark_testing::test_stark_circuit_constraints;
 
     use super::TapeCommitmentsStark;
-    use crate::stark::mozak_stark::{MozakStark, PublicInputs};
+    use crate::stark::mozak_stark::{MozakStark, PublicInputs, TableKind};
     use crate::stark::prover::prove;
     use crate::stark::recursive_verifier::{
-        recursive_mozak_stark_circuit, VMRecursiveProofPublicInputs, VM_PUBLIC_INPUT_SIZE,
+        public_sub_table_values_from_public_inputs, recursive_mozak_stark_circuit,
+        VMRecursiveProofPublicInputs, VM_PUBLIC_INPUT_SIZE,
     };
     use crate::stark::verifier::verify_proof;
+    use crate::system_tape::private_tape_commitment;
+    use crate::tape_commitments::columns::PRIVATE_TAPE_DISCLOSURE_BYTES;
     use crate::test_utils::ProveAndVerify;
     use crate::utils::from_u32;
 
@@ -115,37 +136,41 @@ mod tests {
 
     const CAST_LIST_COMMITMENT_ADDRESS: u32 = 0x100;
     const EVENTS_COMMITMENT_ADDRESS: u32 = 0x200;
+    const ORACLE_TAPE_ADDRESS: u32 = 0x300;
+    const PRIVATE_TAPE_ADDRESS: u32 = 0x400;
+    const PRIVATE_TAPE_COMMITMENT_ADDRESS: u32 = 0x500;
 
-    fn read_tape_commitments_code() -> Vec<Instruction> {
-        fn read_ecall_code(ecall: u32, address: u32, num_bytes_read: usize) -> Vec<Instruction> {
-            vec![
-                Instruction {
-                    op: Op::ADD,
-                    args: Args {
-                        rd: REG_A0,
-                        imm: ecall,
-                        ..Default::default()
-                    },
+    fn read_ecall_code(ecall: u32, address: u32, num_bytes_read: usize) -> Vec<Instruction> {
+        vec![
+            Instruction {
+                op: Op::ADD,
+                args: Args {
+                    rd: REG_A0,
+                    imm: ecall,
+                    ..Default::default()
                 },
-                Instruction {
-                    op: Op::ADD,
-                    args: Args {
-                        rd: REG_A1,
-                        imm: address,
-                        ..Default::default()
-                    },
+            },
+            Instruction {
+                op: Op::ADD,
+                args: Args {
+                    rd: REG_A1,
+                    imm: address,
+                    ..Default::default()
                 },
-                Instruction {
-                    op: Op::ADD,
-                    args: Args {
-                        rd: REG_A2,
-                        imm: u32::try_from(num_bytes_read).expect("casting to u32 should not fail"),
-                        ..Default::default()
-                    },
+            },
+            Instruction {
+                op: Op::ADD,
+                args: Args {
+                    rd: REG_A2,
+                    imm: u32::try_from(num_bytes_read).expect("casting to u32 should not fail"),
+                    ..Default::default()
                 },
-                ECALL,
-            ]
-        }
+            },
+            ECALL,
+        ]
+    }
+
+    fn read_tape_commitments_code() -> Vec<Instruction> {
         let code_ecall_cast_list_commitment_tape = read_ecall_code(
             ecall::CAST_LIST_COMMITMENT_TAPE,
             CAST_LIST_COMMITMENT_ADDRESS,
@@ -156,9 +181,12 @@ mod tests {
             EVENTS_COMMITMENT_ADDRESS,
             DIGEST_BYTES,
         );
+        let code_ecall_oracle_tape =
+            read_ecall_code(ecall::ORACLE_TAPE, ORACLE_TAPE_ADDRESS, DIGEST_BYTES);
         chain!(
             code_ecall_cast_list_commitment_tape,
-            code_ecall_events_commitment_tape
+            code_ecall_events_commitment_tape,
+            code_ecall_oracle_tape
         )
         .collect()
     }
@@ -169,10 +197,12 @@ mod tests {
         // generate tapes with random bytes
         let cast_list_commitment_tape: [u8; DIGEST_BYTES] = rng.gen();
         let events_commitment_tape: [u8; DIGEST_BYTES] = rng.gen();
+        let oracle_tape: [u8; DIGEST_BYTES] = rng.gen();
         let code = read_tape_commitments_code();
         let (program, record) = code::execute_code_with_ro_memory(code, &[], &[], &[], RawTapes {
             events_commitment_tape,
             cast_list_commitment_tape,
+            oracle_tape,
             ..Default::default()
         });
         TapeCommitmentsStark::prove_and_verify(&program, &record)
@@ -183,10 +213,12 @@ mod tests {
         // generate tapes with random bytes
         let cast_list_commitment_tape: [u8; DIGEST_BYTES] = rng.gen();
         let events_commitment_tape: [u8; DIGEST_BYTES] = rng.gen();
+        let oracle_tape: [u8; DIGEST_BYTES] = rng.gen();
         let code = read_tape_commitments_code();
         let (program, record) = code::execute_code_with_ro_memory(code, &[], &[], &[], RawTapes {
             events_commitment_tape,
             cast_list_commitment_tape,
+            oracle_tape,
             ..Default::default()
         });
         MozakStark::prove_and_verify(&program, &record)
@@ -198,10 +230,12 @@ mod tests {
         // generate tapes with random bytes
         let cast_list_commitment_tape: [u8; DIGEST_BYTES] = rng.gen();
         let events_commitment_tape: [u8; DIGEST_BYTES] = rng.gen();
+        let oracle_tape: [u8; DIGEST_BYTES] = rng.gen();
         let code = read_tape_commitments_code();
         let (program, record) = code::execute_code_with_ro_memory(code, &[], &[], &[], RawTapes {
             events_commitment_tape,
             cast_list_commitment_tape,
+            oracle_tape,
             ..Default::default()
         });
         let stark = MozakStark::<F, D>::default();
@@ -244,9 +278,104 @@ mod tests {
             cast_list_commitment_tape.map(F::from_canonical_u8),
             "Mismatch in cast list commitment tape in public inputs"
         );
+        assert_eq!(
+            recursive_proof_public_inputs.oracle_tape,
+            oracle_tape.map(F::from_canonical_u8),
+            "Mismatch in oracle tape in public inputs"
+        );
+        // The same values, read back via the public sub tables.
+        let public_sub_table_values =
+            public_sub_table_values_from_public_inputs(&stark, &recursive_proof.public_inputs);
+        assert_eq!(
+            public_sub_table_values[2],
+            oracle_tape.map(|byte| vec![F::from_canonical_u8(byte)])
+        );
         mozak_stark_circuit.circuit.verify(recursive_proof)
     }
 
+    #[test]
+    fn test_private_tape_disclosure() -> Result<(), anyhow::Error> {
+        let private_tape = b"secret key|nonce".to_vec();
+        let private_tape_commitment_tape = private_tape_commitment(&private_tape).inner();
+        let code = chain!(
+            read_ecall_code(
+                ecall::PRIVATE_TAPE,
+                PRIVATE_TAPE_ADDRESS,
+                private_tape.len(),
+            ),
+            read_ecall_code(
+                ecall::PRIVATE_TAPE_COMMITMENT_TAPE,
+                PRIVATE_TAPE_COMMITMENT_ADDRESS,
+                DIGEST_BYTES,
+            )
+        )
+        .collect_vec();
+        let (program, record) = code::execute_code_with_ro_memory(code, &[], &[], &[], RawTapes {
+            private_tape: private_tape.clone(),
+            private_tape_commitment_tape,
+            private_tape_disclosures: vec![11..16],
+            ..Default::default()
+        });
+        let stark = MozakStark::<F, D>::default();
+        let config = StarkConfig::standard_fast_config();
+        let public_inputs = PublicInputs {
+            entry_point: from_u32(program.entry_point),
+        };
+        let mozak_proof = prove::<F, C, D>(
+            &program,
+            &record,
+            &stark,
+            &config,
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+
+        // Only the nonce is public, and the unused slots are marked as such.
+        let expected = chain!(
+            (11..16).map(|i| [1, i, usize::from(private_tape[i])]),
+            [[0, 0, 0]; PRIVATE_TAPE_DISCLOSURE_BYTES - 5]
+        )
+        .map(|row| row.map(F::from_canonical_usize).to_vec())
+        .collect_vec();
+        assert_eq!(
+            mozak_proof.public_sub_table_values[TableKind::TapeCommitments][3],
+            expected
+        );
+        // Along with the commitment to the whole private tape.
+        assert_eq!(
+            mozak_proof.public_sub_table_values[TableKind::TapeCommitments][4],
+            private_tape_commitment_tape.map(|byte| vec![F::from_canonical_u8(byte)])
+        );
+        verify_proof(&stark, mozak_proof, &config)
+    }
+
+    #[test]
+    fn too_many_private_tape_disclosures_are_an_error() {
+        let private_tape = vec![7; PRIVATE_TAPE_DISCLOSURE_BYTES + 1];
+        let code = read_ecall_code(
+            ecall::PRIVATE_TAPE,
+            PRIVATE_TAPE_ADDRESS,
+            private_tape.len(),
+        );
+        let (program, record) = code::execute_code_with_ro_memory(code, &[], &[], &[], RawTapes {
+            private_tape_disclosures: vec![0..private_tape.len()],
+            private_tape,
+            ..Default::default()
+        });
+        let public_inputs = PublicInputs {
+            entry_point: from_u32(program.entry_point),
+        };
+        assert!(prove::<F, C, D>(
+            &program,
+            &record,
+            &MozakStark::default(),
+            &StarkConfig::standard_fast_config(),
+            public_inputs,
+            &mut TimingTree::default(),
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_circuit() -> anyhow::Result<()> {
         let stark = S::default();
@@ -5,8 +5,9 @@ use mozak_runner::decode::ECALL;
 use mozak_runner::elf::Program;
 use mozak_runner::instruction::{Args, Instruction, Op};
 use mozak_runner::vm::ExecutionRecord;
-use mozak_sdk::core::ecall;
-use mozak_sdk::core::reg_abi::{REG_A0, REG_A1, REG_A2, REG_A3};
+use mozak_sdk::core::reg_abi::{REG_A0, REG_A1, REG_A2, REG_A3, REG_A4, REG_A5, REG_A6, REG_A7};
+use mozak_sdk::core::sha256::{BLOCK_BYTES, STATE_BYTES};
+use mozak_sdk::core::{blake3, ecall};
 use plonky2::field::goldilocks_field::GoldilocksField;
 use plonky2::field::types::Field;
 use plonky2::fri::FriConfig;
@@ -23,6 +24,7 @@ use starky::verifier::verify_stark_proof;
 
 use crate::bitshift::generation::generate_shift_amount_trace;
 use crate::bitshift::stark::BitshiftStark;
+use crate::blake3::generation::generate_blake3_trace;
 use crate::cpu::generation::generate_cpu_trace;
 use crate::cpu::stark::CpuStark;
 use crate::memory::generation::generate_memory_trace;
@@ -41,6 +43,7 @@ use crate::rangecheck::stark::RangeCheckStark;
 use crate::register::general::stark::RegisterStark;
 use crate::register::generation::{generate_register_init_trace, generate_register_trace};
 use crate::register::init::stark::RegisterInitStark;
+use crate::sha256::generation::generate_sha256_trace;
 use crate::stark::batch_prover::batch_prove;
 use crate::stark::batch_verifier::batch_verify_proof;
 use crate::stark::mozak_stark::{MozakStark, PublicInputs, PUBLIC_TABLE_KINDS};
@@ -49,8 +52,9 @@ use crate::stark::utils::trace_rows_to_poly_values;
 use crate::stark::verifier::verify_proof;
 use crate::storage_device::generation::{
     generate_call_tape_trace, generate_cast_list_commitment_tape_trace, generate_event_tape_trace,
-    generate_events_commitment_tape_trace, generate_private_tape_trace, generate_public_tape_trace,
-    generate_self_prog_id_tape_trace,
+    generate_events_commitment_tape_trace, generate_oracle_tape_trace,
+    generate_private_tape_commitment_tape_trace, generate_private_tape_trace,
+    generate_public_tape_trace, generate_self_prog_id_tape_trace,
 };
 use crate::storage_device::stark::StorageDeviceStark;
 use crate::tape_commitments::generation::generate_tape_commitments_trace;
@@ -165,7 +169,12 @@ impl ProveAndVerify for RangeCheckStark<F, D> {
         let cast_list_commitment_tape_rows =
             generate_cast_list_commitment_tape_trace(&record.executed);
         let self_prog_id_tape_rows = generate_self_prog_id_tape_trace(&record.executed);
+        let oracle_tape_rows = generate_oracle_tape_trace(&record.executed);
+        let private_tape_commitment_tape_rows =
+            generate_private_tape_commitment_tape_trace(&record.executed);
         let poseidon2_sponge_trace = generate_poseidon2_sponge_trace(&record.executed);
+        let sha256_rows = generate_sha256_trace(&record.executed);
+        let blake3_rows = generate_blake3_trace(&record.executed);
         let poseidon2_output_bytes = generate_poseidon2_output_bytes_trace(&poseidon2_sponge_trace);
         let memory_trace = generate_memory_trace::<F>(
             &record.executed,
@@ -180,8 +189,12 @@ impl ProveAndVerify for RangeCheckStark<F, D> {
             &events_commitment_tape_rows,
             &cast_list_commitment_tape_rows,
             &self_prog_id_tape_rows,
+            &oracle_tape_rows,
+            &private_tape_commitment_tape_rows,
             &poseidon2_sponge_trace,
             &poseidon2_output_bytes,
+            &sha256_rows,
+            &blake3_rows,
         );
         let register_init = generate_register_init_trace(record);
         let (_, _, register_trace) = generate_register_trace(
@@ -189,6 +202,8 @@ impl ProveAndVerify for RangeCheckStark<F, D> {
             &add_trace,
             &blt_trace,
             &poseidon2_sponge_trace,
+            &sha256_rows,
+            &blake3_rows,
             &private_tape,
             &public_tape,
             &call_tape_rows,
@@ -196,6 +211,8 @@ impl ProveAndVerify for RangeCheckStark<F, D> {
             &events_commitment_tape_rows,
             &cast_list_commitment_tape_rows,
             &self_prog_id_tape_rows,
+            &oracle_tape_rows,
+            &private_tape_commitment_tape_rows,
             &register_init,
         );
         let trace_poly_values = trace_rows_to_poly_values(generate_rangecheck_trace(
@@ -258,7 +275,12 @@ impl ProveAndVerify for MemoryStark<F, D> {
         let cast_list_commitment_tape_rows =
             generate_cast_list_commitment_tape_trace(&record.executed);
         let self_prog_id_tape_rows = generate_self_prog_id_tape_trace(&record.executed);
+        let oracle_tape_rows = generate_oracle_tape_trace(&record.executed);
+        let private_tape_commitment_tape_rows =
+            generate_private_tape_commitment_tape_trace(&record.executed);
         let poseidon2_sponge_trace = generate_poseidon2_sponge_trace(&record.executed);
+        let sha256_rows = generate_sha256_trace(&record.executed);
+        let blake3_rows = generate_blake3_trace(&record.executed);
         let poseidon2_output_bytes = generate_poseidon2_output_bytes_trace(&poseidon2_sponge_trace);
         let trace_poly_values = trace_rows_to_poly_values(generate_memory_trace(
             &record.executed,
@@ -273,8 +295,12 @@ impl ProveAndVerify for MemoryStark<F, D> {
             &events_commitment_tape_rows,
             &cast_list_commitment_tape_rows,
             &self_prog_id_tape_rows,
+            &oracle_tape_rows,
+            &private_tape_commitment_tape_rows,
             &poseidon2_sponge_trace,
             &poseidon2_output_bytes,
+            &sha256_rows,
+            &blake3_rows,
         ));
         let proof = prove_table::<F, C, S, D>(
             stark,
@@ -406,7 +432,12 @@ impl ProveAndVerify for RegisterStark<F, D> {
         let cast_list_commitment_tape_rows =
             generate_cast_list_commitment_tape_trace(&record.executed);
         let self_prog_id_tape_rows = generate_self_prog_id_tape_trace(&record.executed);
+        let oracle_tape_rows = generate_oracle_tape_trace(&record.executed);
+        let private_tape_commitment_tape_rows =
+            generate_private_tape_commitment_tape_trace(&record.executed);
         let poseidon2_sponge_rows = generate_poseidon2_sponge_trace(&record.executed);
+        let sha256_rows = generate_sha256_trace(&record.executed);
+        let blake3_rows = generate_blake3_trace(&record.executed);
 
         let register_init = generate_register_init_trace(record);
         let (_, _, trace) = generate_register_trace(
@@ -414,6 +445,8 @@ impl ProveAndVerify for RegisterStark<F, D> {
             &add_trace,
             &blt_trace,
             &poseidon2_sponge_rows,
+            &sha256_rows,
+            &blake3_rows,
             &private_tape,
             &public_tape,
             &call_tape,
@@ -421,6 +454,8 @@ impl ProveAndVerify for RegisterStark<F, D> {
             &events_commitment_tape_rows,
             &cast_list_commitment_tape_rows,
             &self_prog_id_tape_rows,
+            &oracle_tape_rows,
+            &private_tape_commitment_tape_rows,
             &register_init,
         );
         let trace_poly_values = trace_rows_to_poly_values(trace);
@@ -441,7 +476,7 @@ impl ProveAndVerify for TapeCommitmentsStark<F, D> {
         type S = TapeCommitmentsStark<F, D>;
         let stark = S::default();
         let config = fast_test_config();
-        let trace = generate_tape_commitments_trace(record);
+        let trace = generate_tape_commitments_trace(record)?;
         let trace_poly_values = trace_rows_to_poly_values(trace);
         let proof = prove_table::<F, C, S, D>(
             stark,
@@ -588,6 +623,94 @@ pub fn create_poseidon2_test(
     code::execute(instructions, memory.as_slice(), &[])
 }
 
+pub struct Sha256Test {
+    pub state: [u8; STATE_BYTES],
+    pub block: [u8; BLOCK_BYTES],
+    pub state_addr: u32,
+    pub block_addr: u32,
+    pub output_addr: u32,
+}
+
+/// Executes one `SHA256` ecall per test case, with its state and block
+/// loaded into memory.
+#[must_use]
+pub fn create_sha256_test(test_data: &[Sha256Test]) -> (Program, ExecutionRecord<GoldilocksField>) {
+    let mut instructions = vec![];
+    let mut memory: Vec<(u32, u8)> = vec![];
+
+    for test_datum in test_data {
+        memory.extend(izip!(test_datum.state_addr.., test_datum.state));
+        memory.extend(izip!(test_datum.block_addr.., test_datum.block));
+        instructions.extend(
+            [
+                (REG_A0, ecall::SHA256),
+                (REG_A1, test_datum.state_addr),
+                (REG_A2, test_datum.block_addr),
+                (REG_A3, test_datum.output_addr),
+            ]
+            .map(|(rd, imm)| Instruction {
+                op: Op::ADD,
+                args: Args {
+                    rd,
+                    imm,
+                    ..Args::default()
+                },
+            }),
+        );
+        instructions.push(ECALL);
+    }
+
+    code::execute(instructions, memory.as_slice(), &[])
+}
+
+pub struct Blake3Test {
+    pub cv: [u8; blake3::CV_BYTES],
+    pub block: [u8; blake3::BLOCK_BYTES],
+    pub counter: u64,
+    pub block_len: u32,
+    pub flags: u32,
+    pub cv_addr: u32,
+    pub block_addr: u32,
+    pub output_addr: u32,
+}
+
+/// Executes one `BLAKE3` ecall per test case, with its chaining value and
+/// block loaded into memory.
+#[must_use]
+pub fn create_blake3_test(test_data: &[Blake3Test]) -> (Program, ExecutionRecord<GoldilocksField>) {
+    let mut instructions = vec![];
+    let mut memory: Vec<(u32, u8)> = vec![];
+
+    for test_datum in test_data {
+        memory.extend(izip!(test_datum.cv_addr.., test_datum.cv));
+        memory.extend(izip!(test_datum.block_addr.., test_datum.block));
+        let [counter_lo, counter_hi] = blake3::words(&test_datum.counter.to_le_bytes());
+        instructions.extend(
+            [
+                (REG_A0, ecall::BLAKE3),
+                (REG_A1, test_datum.cv_addr),
+                (REG_A2, test_datum.block_addr),
+                (REG_A3, test_datum.output_addr),
+                (REG_A4, counter_lo),
+                (REG_A5, counter_hi),
+                (REG_A6, test_datum.block_len),
+                (REG_A7, test_datum.flags),
+            ]
+            .map(|(rd, imm)| Instruction {
+                op: Op::ADD,
+                args: Args {
+                    rd,
+                    imm,
+                    ..Args::default()
+                },
+            }),
+        );
+        instructions.push(ECALL);
+    }
+
+    code::execute(instructions, memory.as_slice(), &[])
+}
+
 pub fn hash_str(v: &str) -> HashOut<F> {
     let v: Vec<_> = v.bytes().map(F::from_canonical_u8).collect();
     Poseidon2Hash::hash_no_pad(&v)
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use expr::{Expr, ExprBuilder, StarkFrameTyped};
+use expr::{ConstraintCollector, Expr, ExprBuilder, StarkFrameTyped};
 use itertools::{chain, izip};
 use mozak_circuits_derive::StarkNameDisplay;
 use plonky2::field::extension::{Extendable, FieldExtension};
@@ -14,7 +14,7 @@ use starky::stark::Stark;
 
 use super::columns::XorColumnsView;
 use crate::columns_view::{HasNamedColumns, NumberOfColumns};
-use crate::expr::{build_ext, build_packed, ConstraintBuilder};
+use crate::expr::{build_ext, build_packed};
 use crate::unstark::NoColumns;
 
 #[derive(Clone, Copy, Default, StarkNameDisplay)]
@@ -32,9 +32,9 @@ const PUBLIC_INPUTS: usize = 0;
 
 fn generate_constraints<'a, T: Copy>(
     vars: &StarkFrameTyped<XorColumnsView<Expr<'a, T>>, NoColumns<Expr<'a, T>>>,
-) -> ConstraintBuilder<Expr<'a, T>> {
+) -> ConstraintCollector<'a, T> {
     let lv = vars.local_values;
-    let mut constraints = ConstraintBuilder::default();
+    let mut constraints = ConstraintCollector::default();
 
     // We first convert both input and output to bit representation
     // We then work with the bit representations to check the Xor result.
@@ -96,8 +96,10 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for XorStark<F, D
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
+    use itertools::Itertools;
     use mozak_runner::code;
     use mozak_runner::instruction::{Args, Instruction, Op};
+    use plonky2::field::types::Field;
     use plonky2::timed;
     use plonky2::util::timing::TimingTree;
     use starky::prover::prove as prove_table;
@@ -105,6 +107,8 @@ mod tests {
     use starky::verifier::verify_stark_proof;
 
     use crate::cpu::generation::generate_cpu_trace;
+    use crate::generation::debug_single_trace;
+    use crate::stark::mozak_stark::TableKind;
     use crate::stark::utils::trace_rows_to_poly_values;
     use crate::test_utils::{fast_test_config, C, D, F};
     use crate::xor::generation::generate_xor_trace;
@@ -188,6 +192,49 @@ mod tests {
             }
     }
 
+    #[test]
+    fn debug_names_the_failing_columns() {
+        let (_program, record) = code::execute(
+            [Instruction {
+                op: Op::XOR,
+                args: Args {
+                    rs1: 5,
+                    rs2: 6,
+                    rd: 7,
+                    ..Args::default()
+                },
+            }],
+            &[],
+            &[(5, 0b1010), (6, 0b0110)],
+        );
+        let mut trace = generate_xor_trace(&generate_cpu_trace(&record));
+        trace[0].limbs.out[0] = F::ONE - trace[0].limbs.out[0];
+        let error = debug_single_trace::<F, D, S>(
+            &S::default(),
+            TableKind::Xor,
+            &trace_rows_to_poly_values(trace),
+            &[],
+        )
+        .unwrap_err();
+
+        assert_eq!((error.kind, error.row), (TableKind::Xor, 0));
+        assert!(error
+            .constraint
+            .unwrap()
+            .location
+            .file()
+            .ends_with("stark.rs"));
+        let names = error
+            .columns
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect_vec();
+        assert!(names.contains(&"limbs.out[0]"));
+        assert!(names.contains(&"execution.out"));
+        assert!(!names.contains(&"limbs.a[0]"));
+        assert!(!names.iter().any(|name| name.starts_with("next.")));
+    }
+
     #[test]
     fn test_circuit() -> anyhow::Result<()> {
         let stark = S::default();
@@ -0,0 +1,288 @@
+//! Checkpoints of the intermediate proofs of a `prove` run, so that a run
+//! which gets interrupted can pick up where it left off.
+//!
+//! Every stage of the run is stored as its own JSON file in the checkpoint
+//! directory, next to a manifest that records the hash of each file and of
+//! the inputs of the run. Checkpoints are only ever loaded for the same
+//! inputs, and only if their contents are intact.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Context, Result};
+use log::debug;
+use mozak_sdk::common::types::Poseidon2Hash;
+use mozak_sdk::native::poseidon::poseidon2_hash_with_pad;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+const MANIFEST: &str = "manifest.json";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Manifest {
+    /// Hash of the inputs of the run the checkpoints belong to.
+    inputs: Option<Poseidon2Hash>,
+    /// Hash of the file of each stage that has been checkpointed.
+    stages: BTreeMap<String, Poseidon2Hash>,
+}
+
+pub struct Checkpoints {
+    dir: PathBuf,
+    manifest: Manifest,
+}
+
+/// Hashes the inputs of a run, which identify the run its checkpoints belong
+/// to.
+#[must_use]
+pub fn inputs_hash(inputs: &[&[u8]]) -> Poseidon2Hash {
+    let length_prefixed: Vec<u8> = inputs
+        .iter()
+        .flat_map(|input| {
+            input
+                .len()
+                .to_le_bytes()
+                .into_iter()
+                .chain(input.iter().copied())
+        })
+        .collect();
+    poseidon2_hash_with_pad(&length_prefixed)
+}
+
+impl Checkpoints {
+    /// Starts checkpointing a run in `dir`.  Checkpoints already there are
+    /// kept if they are of a run with the same inputs, and discarded
+    /// otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Errors if `dir` can not be created or cleaned up.
+    pub fn create(dir: &Path, inputs: Poseidon2Hash) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        if let Ok(existing) = Self::open(dir) {
+            if existing.manifest.inputs == Some(inputs) {
+                debug!(
+                    "Keeping checkpoints {:?} of the same inputs",
+                    existing.manifest.stages.keys()
+                );
+                return Ok(existing);
+            }
+            existing.remove()?;
+            fs::create_dir_all(dir)?;
+        }
+        let mut checkpoints = Self {
+            dir: dir.to_path_buf(),
+            manifest: Manifest::default(),
+        };
+        checkpoints.manifest.inputs = Some(inputs);
+        checkpoints.write_manifest()?;
+        Ok(checkpoints)
+    }
+
+    /// Picks up the checkpoints in `dir` of an earlier run with the same
+    /// inputs.
+    ///
+    /// # Errors
+    ///
+    /// Errors if `dir` has no checkpoints, or if they belong to a run with
+    /// different inputs.
+    pub fn resume(dir: &Path, inputs: Poseidon2Hash) -> Result<Self> {
+        let checkpoints = Self::open(dir)?;
+        ensure!(
+            checkpoints.manifest.inputs == Some(inputs),
+            "checkpoints in {dir:?} are from a run with different inputs"
+        );
+        debug!(
+            "Resuming from checkpoints {:?}",
+            checkpoints.manifest.stages.keys()
+        );
+        Ok(checkpoints)
+    }
+
+    fn open(dir: &Path) -> Result<Self> {
+        let manifest = fs::read(dir.join(MANIFEST))
+            .with_context(|| format!("no checkpoints found in {dir:?}"))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            manifest: serde_json::from_slice(&manifest)?,
+        })
+    }
+
+    fn stage_path(&self, stage: &str) -> PathBuf { self.dir.join(format!("{stage}.json")) }
+
+    /// Loads the checkpoint of `stage`, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the checkpoint was tampered with or is corrupted.
+    pub fn load<T: DeserializeOwned>(&self, stage: &str) -> Result<Option<T>> {
+        let Some(expected) = self.manifest.stages.get(stage) else {
+            return Ok(None);
+        };
+        let path = self.stage_path(stage);
+        let bytes = fs::read(&path)?;
+        ensure!(
+            poseidon2_hash_with_pad(&bytes) == *expected,
+            "checkpoint {path:?} is corrupted, remove it to prove this stage again"
+        );
+        debug!("Loaded checkpoint {path:?}");
+        Ok(Some(serde_json::from_slice(&bytes)?))
+    }
+
+    /// Saves the checkpoint of `stage`.
+    ///
+    /// The file is written before the manifest refers to it, so an
+    /// interruption at any point leaves consistent checkpoints behind.
+    ///
+    /// # Errors
+    ///
+    /// Errors if writing to the checkpoint directory fails.
+    pub fn save<T: Serialize>(&mut self, stage: &str, value: &T) -> Result<()> {
+        let bytes = serde_json::to_vec(value)?;
+        let path = self.stage_path(stage);
+        write_atomically(&path, &bytes)?;
+        self.manifest
+            .stages
+            .insert(stage.to_string(), poseidon2_hash_with_pad(&bytes));
+        self.write_manifest()?;
+        debug!("Saved checkpoint {path:?}");
+        Ok(())
+    }
+
+    fn write_manifest(&self) -> Result<()> {
+        write_atomically(
+            &self.dir.join(MANIFEST),
+            &serde_json::to_vec(&self.manifest)?,
+        )
+    }
+
+    /// Removes all checkpoints, once they are no longer needed.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the checkpoint files can not be removed.
+    pub fn remove(self) -> Result<()> {
+        for stage in self.manifest.stages.keys() {
+            let path = self.stage_path(stage);
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        fs::remove_file(self.dir.join(MANIFEST))?;
+        // Only remove the directory if we were the only ones using it.
+        let _ = fs::remove_dir(&self.dir);
+        Ok(())
+    }
+}
+
+/// Loads the checkpoint of `stage`, if there is one, or else runs `run` and
+/// checkpoints what it returns.  Without checkpoints, just runs `run`.
+///
+/// # Errors
+///
+/// Errors if the checkpoint is corrupted, if `run` fails, or if the result
+/// can not be saved.
+pub fn load_or_run<T: Serialize + DeserializeOwned>(
+    checkpoints: Option<&mut Checkpoints>,
+    stage: &str,
+    run: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    let Some(checkpoints) = checkpoints else {
+        return run();
+    };
+    if let Some(value) = checkpoints.load(stage)? {
+        return Ok(value);
+    }
+    let value = run()?;
+    checkpoints.save(stage, &value)?;
+    Ok(value)
+}
+
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let inputs = inputs_hash(&[b"elf", b"tapes"]);
+
+        let mut checkpoints = Checkpoints::create(dir.path(), inputs).unwrap();
+        checkpoints.save("first", &vec![1u64, 2, 3]).unwrap();
+        checkpoints.save("second", &"proof".to_string()).unwrap();
+
+        let resumed = Checkpoints::resume(dir.path(), inputs).unwrap();
+        assert_eq!(
+            resumed.load::<Vec<u64>>("first").unwrap(),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(
+            resumed.load::<String>("second").unwrap(),
+            Some("proof".to_string())
+        );
+        assert_eq!(resumed.load::<String>("third").unwrap(), None);
+
+        resumed.remove().unwrap();
+        assert!(!dir.path().exists());
+    }
+
+    #[test]
+    fn stale_inputs_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let inputs = inputs_hash(&[b"elf", b"tapes"]);
+        let mut checkpoints = Checkpoints::create(dir.path(), inputs).unwrap();
+        checkpoints.save("first", &1u64).unwrap();
+
+        // The same bytes, split differently, are different inputs.
+        let changed = inputs_hash(&[b"elft", b"apes"]);
+        assert_ne!(changed, inputs);
+        assert!(Checkpoints::resume(dir.path(), changed).is_err());
+
+        // Starting afresh discards the stale checkpoints.
+        let fresh = Checkpoints::create(dir.path(), changed).unwrap();
+        assert_eq!(fresh.load::<u64>("first").unwrap(), None);
+        assert!(!dir.path().join("first.json").exists());
+    }
+
+    #[test]
+    fn creating_keeps_checkpoints_of_the_same_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let inputs = inputs_hash(&[b"elf"]);
+        let mut checkpoints = Checkpoints::create(dir.path(), inputs).unwrap();
+        checkpoints.save("first", &1u64).unwrap();
+
+        let mut recreated = Checkpoints::create(dir.path(), inputs).unwrap();
+        assert_eq!(recreated.load::<u64>("first").unwrap(), Some(1));
+
+        // Stages that are checkpointed are not run again.
+        let second = load_or_run(Some(&mut recreated), "second", || Ok(2u64)).unwrap();
+        let rerun = load_or_run(Some(&mut recreated), "second", || -> Result<u64> {
+            panic!("second stage was run again")
+        });
+        assert_eq!((second, rerun.unwrap()), (2, 2));
+    }
+
+    #[test]
+    fn corrupted_checkpoints_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let inputs = inputs_hash(&[b"elf"]);
+        let mut checkpoints = Checkpoints::create(dir.path(), inputs).unwrap();
+        checkpoints.save("first", &1u64).unwrap();
+
+        fs::write(dir.path().join("first.json"), b"2").unwrap();
+        let resumed = Checkpoints::resume(dir.path(), inputs).unwrap();
+        assert!(resumed.load::<u64>("first").is_err());
+    }
+
+    #[test]
+    fn resuming_needs_checkpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Checkpoints::resume(dir.path(), inputs_hash(&[])).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+//! Disassembly of the code of a `MozakVM` ELF, for the `decode` command.
+use std::fmt;
+use std::ops::Range;
+
+use anyhow::{anyhow, ensure, Context, Result};
+use elf::endian::LittleEndian;
+use elf::ElfBytes;
+use itertools::Itertools;
+use mozak_runner::elf::Program;
+use mozak_runner::instruction::{Args, DecodingError, Instruction};
+use serde::Serialize;
+
+/// Output formats of the `decode` command.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DecodeFormat {
+    /// One instruction per line.
+    #[default]
+    Text,
+    /// A JSON array of instruction records.
+    Json,
+}
+
+/// A single decoded instruction.
+#[derive(Clone, Debug, Serialize)]
+pub struct DecodedInstruction {
+    pub pc: u32,
+    /// The instruction word, as stored in memory.
+    pub bytes: u32,
+    /// Lower case name of the operation, or `unknown` if the word does not
+    /// decode to an instruction supported by `MozakVM`.
+    pub mnemonic: String,
+    /// Operands of the instruction, absent if it could not be decoded.
+    pub operands: Option<Args>,
+}
+
+impl fmt::Display for DecodedInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:08x}: {:08x}  {:<6}",
+            self.pc, self.bytes, self.mnemonic
+        )?;
+        if let Some(Args { rd, rs1, rs2, imm }) = self.operands {
+            write!(f, " rd=x{rd}, rs1=x{rs1}, rs2=x{rs2}, imm={imm:#x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl DecodedInstruction {
+    fn new(pc: u32, instruction: &Result<Instruction, DecodingError>, program: &Program) -> Self {
+        match instruction {
+            Ok(Instruction { op, args }) => Self {
+                pc,
+                bytes: load_u32(program, pc),
+                mnemonic: format!("{op:?}").to_lowercase(),
+                operands: Some(*args),
+            },
+            Err(DecodingError { instruction, .. }) => Self {
+                pc,
+                bytes: *instruction,
+                mnemonic: "unknown".to_string(),
+                operands: None,
+            },
+        }
+    }
+}
+
+fn load_u32(program: &Program, addr: u32) -> u32 {
+    let byte = |addr| {
+        program
+            .ro_memory
+            .get(&addr)
+            .or_else(|| program.rw_memory.get(&addr))
+            .copied()
+            .unwrap_or_default()
+    };
+    u32::from_le_bytes([byte(addr), byte(addr + 1), byte(addr + 2), byte(addr + 3)])
+}
+
+/// Decodes the code of `program` in `range`, ordered by address.
+#[must_use]
+pub fn decode(program: &Program, range: Option<&Range<u32>>) -> Vec<DecodedInstruction> {
+    program
+        .ro_code
+        .iter()
+        .filter(|(pc, _)| range.map_or(true, |range| range.contains(*pc)))
+        .sorted_by_key(|(pc, _)| **pc)
+        .map(|(pc, instruction)| DecodedInstruction::new(*pc, instruction, program))
+        .collect()
+}
+
+/// Returns the address range occupied by the section called `name` of the
+/// ELF in `elf_bytes`.
+///
+/// # Errors
+///
+/// Errors if the ELF can not be parsed, or has no such section.
+pub fn section_range(elf_bytes: &[u8], name: &str) -> Result<Range<u32>> {
+    let elf = ElfBytes::<LittleEndian>::minimal_parse(elf_bytes)?;
+    let (headers, strtab) = elf.section_headers_with_strtab()?;
+    let (headers, strtab) = headers.zip(strtab).context("ELF has no section headers")?;
+    let header = headers
+        .iter()
+        .find(|header| strtab.get(header.sh_name as usize).is_ok_and(|n| n == name))
+        .ok_or_else(|| anyhow!("ELF has no section {name:?}"))?;
+    let start = u32::try_from(header.sh_addr)?;
+    let end = u32::try_from(header.sh_addr + header.sh_size)?;
+    Ok(start..end)
+}
+
+/// Parses an address range like `0x1000..0x2000`, in hex or decimal.
+///
+/// # Errors
+///
+/// Errors if the range is malformed or reversed.
+pub fn parse_address_range(s: &str) -> Result<Range<u32>> {
+    fn parse_address(s: &str) -> Result<u32> {
+        Ok(match s.strip_prefix("0x") {
+            Some(hex) => u32::from_str_radix(hex, 16)?,
+            None => s.parse()?,
+        })
+    }
+    let (start, end) = s
+        .split_once("..")
+        .context("expected a range like `<start>..<end>`")?;
+    let range = parse_address(start)?..parse_address(end)?;
+    ensure!(range.start <= range.end, "range {s:?} is reversed");
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `nop`, `add x5, x5, x6` and a word that does not decode.
+    fn program() -> Program {
+        let words: im::HashMap<u32, u32> = [(0, 0x0000_0013), (4, 0x0062_82b3), (8, 0xffff_ffff)]
+            .into_iter()
+            .collect();
+        Program::from(words)
+    }
+
+    #[test]
+    fn decodes_in_address_order() {
+        let decoded = decode(&program(), None);
+        assert_eq!(decoded.iter().map(|i| i.pc).collect_vec(), [0, 4, 8]);
+        assert_eq!(decoded.iter().map(|i| i.bytes).collect_vec(), [
+            0x0000_0013,
+            0x0062_82b3,
+            0xffff_ffff
+        ]);
+
+        let add = &decoded[1];
+        assert_eq!(add.mnemonic, "add");
+        assert_eq!(
+            add.operands.map(|Args { rd, rs1, rs2, .. }| (rd, rs1, rs2)),
+            Some((5, 5, 6))
+        );
+
+        let unknown = &decoded[2];
+        assert_eq!(unknown.mnemonic, "unknown");
+        assert_eq!(unknown.operands, None);
+        assert_eq!(unknown.to_string(), "00000008: ffffffff  unknown");
+    }
+
+    #[test]
+    fn decodes_only_the_range() {
+        let decoded = decode(&program(), Some(&(4..8)));
+        assert_eq!(decoded.iter().map(|i| i.pc).collect_vec(), [4]);
+        assert!(decode(&program(), Some(&(12..16))).is_empty());
+    }
+
+    #[test]
+    fn finds_sections() {
+        let elf = mozak_examples::EMPTY_ELF;
+        let text = section_range(elf, ".text").unwrap();
+        assert!(text.start < text.end);
+        let program = Program::mozak_load_program(elf).unwrap();
+        assert!(!decode(&program, Some(&text)).is_empty());
+
+        assert!(section_range(elf, ".no-such-section").is_err());
+        assert!(section_range(b"not an ELF", ".text").is_err());
+    }
+
+    #[test]
+    fn parses_address_ranges() {
+        assert_eq!(
+            parse_address_range("0x1000..0x1100").unwrap(),
+            0x1000..0x1100
+        );
+        assert_eq!(parse_address_range("16..0x20").unwrap(), 16..32);
+        assert_eq!(parse_address_range("16..16").unwrap(), 16..16);
+
+        for malformed in [
+            "0x1000",
+            "0x1000..",
+            "..0x1000",
+            "0x..0x10",
+            "a..b",
+            "0x100000000..0",
+        ] {
+            assert!(parse_address_range(malformed).is_err(), "{malformed}");
+        }
+        assert!(parse_address_range("0x1100..0x1000").is_err());
+    }
+}
@@ -1,6 +1,8 @@
+pub mod checkpoint;
 #[cfg(feature = "bench")]
 pub mod cli_benches;
+pub mod decode;
+pub mod repro;
 pub mod runner;
 #[cfg(test)]
 mod tests;
-mod trace_utils;
@@ -3,49 +3,72 @@
 
 use std::collections::HashMap;
 use std::io::{Read, Write};
+use std::ops::Range;
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{bail, ensure, Context, Result};
 use clap::{Parser, Subcommand};
 use clap_derive::Args;
 use clio::{Input, Output};
-use itertools::Itertools;
+use itertools::{chain, Itertools};
 use log::debug;
 use mozak_circuits::memoryinit::generation::generate_elf_memory_init_trace;
 use mozak_circuits::program::generation::generate_program_rom_trace;
 use mozak_circuits::stark::batch_prover::batch_prove;
+use mozak_circuits::stark::batch_verifier::batch_verify_proof;
 use mozak_circuits::stark::mozak_stark::{
     MozakStark, PublicInputs, TableKindArray, PUBLIC_TABLE_KINDS,
 };
-use mozak_circuits::stark::proof::{AllProof, BatchProof};
+use mozak_circuits::stark::proof::{
+    circuit_config_digest, stark_config_digest, AllProof, BatchProof, BinaryProofKind,
+    ProofContainer, ProofHeader, ProofPayload,
+};
 use mozak_circuits::stark::prover::prove;
 use mozak_circuits::stark::recursive_verifier::{
     circuit_data_for_recursion, recursive_batch_stark_circuit, recursive_mozak_stark_circuit,
-    shrink_to_target_degree_bits_circuit, VMRecursiveProofPublicInputs, VM_PUBLIC_INPUT_SIZE,
-    VM_RECURSION_CONFIG, VM_RECURSION_THRESHOLD_DEGREE_BITS,
+    shrink_to_target_degree_bits_circuit, VMProofWrapperCircuit, VMRecursiveProofPublicInputs,
+    VM_PROOF_WRAPPER_CONFIG, VM_PUBLIC_INPUT_SIZE, VM_RECURSION_CONFIG,
+    VM_RECURSION_THRESHOLD_DEGREE_BITS,
 };
 use mozak_circuits::stark::utils::trace_rows_to_poly_values;
 use mozak_circuits::stark::verifier::verify_proof;
 use mozak_circuits::storage_device::generation::generate_call_tape_trace;
 use mozak_circuits::test_utils::{prove_and_verify_mozak_stark, C, D, F, S};
+use mozak_cli::checkpoint::{inputs_hash, load_or_run, Checkpoints};
 #[cfg(feature = "bench")]
 use mozak_cli::cli_benches::benches::BenchArgs;
+use mozak_cli::decode::{decode, parse_address_range, section_range, DecodeFormat};
+use mozak_cli::repro::repro_check;
 use mozak_cli::runner::{
-    deserialize_system_tape, get_self_prog_id, load_program, raw_tapes_from_system_tape,
+    deserialize_system_tape, detect_proof_kind, get_self_prog_id, load_program,
+    raw_tapes_from_system_tape, tape_commitment, CommitmentTape, ProofKind,
 };
 use mozak_node::types::{Attestation, Transaction};
-use mozak_runner::state::State;
+use mozak_runner::state::{RawTapes, State};
 use mozak_runner::vm::step;
-use mozak_sdk::common::types::{CrossProgramCall, ProgramIdentifier, SystemTape};
+use mozak_sdk::common::types::{CrossProgramCall, Poseidon2Hash, ProgramIdentifier, SystemTape};
 use plonky2::field::types::Field;
 use plonky2::fri::oracle::PolynomialBatch;
 use plonky2::plonk::circuit_data::VerifierOnlyCircuitData;
+use plonky2::plonk::config::PoseidonGoldilocksConfig;
 use plonky2::plonk::proof::ProofWithPublicInputs;
 use plonky2::util::timing::TimingTree;
 use starky::config::StarkConfig;
 
 const PROGRAMS_MAP_JSON: &str = "examples/programs_map.json";
 
+/// Config of the wrapped proofs.
+// TODO: switch to a config that commits with Poseidon over the BN254 scalar
+// field once our plonky2 fork has one, so that wrapped proofs can be verified
+// in an EVM contract.  Poseidon over Goldilocks is already supported by the
+// gnark based plonky2 verifiers.
+type WrapperC = PoseidonGoldilocksConfig;
+
+/// Files `export-wrapper-verifier` writes, named like the gnark based plonky2
+/// verifiers expect them.
+const COMMON_CIRCUIT_DATA_JSON: &str = "common_circuit_data.json";
+const VERIFIER_ONLY_CIRCUIT_DATA_JSON: &str = "verifier_only_circuit_data.json";
+
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
@@ -73,27 +96,124 @@ pub struct ProveArgs {
     batch_proof: Option<Output>,
     #[arg(long)]
     system_tape: Option<Input>,
+    /// Make these bytes of the private tape public in the proof, eg `4..8`.
+    /// Offsets count from the start of the private tape, including its
+    /// 4-byte length prefix.  Can be given more than once.
+    #[arg(long, value_parser = parse_address_range)]
+    disclose: Vec<Range<u32>>,
     recursive_proof: Option<Output>,
+    /// Write the STARK proofs as JSON, rather than in the compact binary
+    /// format.
+    #[arg(long)]
+    json: bool,
+    /// Compress proofs in the binary format with zstd.
+    #[arg(long, conflicts_with = "json")]
+    compress: bool,
+    /// Also wrap the recursive proof with the wrapper circuit, and write it
+    /// here as JSON.  See `export-wrapper-verifier` for its verifier.
+    #[arg(long, requires = "recursive_proof")]
+    wrapped_proof: Option<Output>,
+    /// Save intermediate proofs to this directory, so that an interrupted
+    /// run can be resumed with `--resume`.  Checkpoints already there are
+    /// reused if they are of the same inputs.
+    #[arg(long, conflicts_with = "resume")]
+    checkpoint_dir: Option<PathBuf>,
+    /// Resume an interrupted run from the checkpoints in this directory.
+    #[arg(long)]
+    resume: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct TapeCommitmentArgs {
+    /// The tape to commit to.
+    #[arg(value_enum)]
+    tape: CommitmentTape,
+    /// System tape generated from native execution.
+    #[arg(long, required = true)]
+    system_tape: Input,
+    /// Program whose view of the tape is committed to, eg `MZK-<hex>`.
+    /// Defaults to the entrypoint program of the system tape.
+    #[arg(long)]
+    program_id: Option<ProgramIdentifier>,
+}
+
+impl TapeCommitmentArgs {
+    fn commitment(self) -> Result<Poseidon2Hash> {
+        let system_tape = deserialize_system_tape(self.system_tape)?;
+        let program_id = match self.program_id {
+            Some(program_id) => program_id,
+            None =>
+                system_tape
+                    .call_tape
+                    .writer
+                    .first()
+                    .context("the system tape has no calls, pass --program-id")?
+                    .callee,
+        };
+        Ok(tape_commitment(&system_tape, self.tape, program_id))
+    }
 }
 
 #[derive(Clone, Debug, Subcommand)]
 enum Command {
-    /// Decode a given ELF and prints the program
-    Decode { elf: Input },
+    /// Decode a given ELF and print its instructions
+    Decode {
+        elf: Input,
+        #[arg(long, value_enum, default_value_t)]
+        format: DecodeFormat,
+        /// Only print instructions in this address range, eg `0x1000..0x1100`.
+        #[arg(long, value_parser = parse_address_range)]
+        range: Option<Range<u32>>,
+        /// Only print instructions in this ELF section, eg `.text`.
+        #[arg(long)]
+        section: Option<String>,
+    },
     /// Decode and execute a given ELF. Prints the final state of
     /// the registers
     Run(RunArgs),
     /// Prove and verify the execution of a given ELF
     ProveAndVerify(RunArgs),
+    /// Execute a given ELF, and print the number of rows and degree bits of
+    /// each table it would be proven with, without proving it.
+    DryRun {
+        #[clap(flatten)]
+        run: RunArgs,
+        /// Fail if any table needs more than this many degree bits.
+        #[arg(long)]
+        max_degree_bits: Option<usize>,
+    },
     /// Prove the execution of given ELF and write proof to file.
     Prove(ProveArgs),
     /// Verify the given proof from file.
-    Verify { proof: Input },
+    ///
+    /// The kind of proof is detected from the file. A batch proof needs the
+    /// `.degree_bits` file and a recursive proof the `.vk` file that `prove`
+    /// writes next to it.
+    Verify {
+        proof: Input,
+        /// Verifier key of a recursive proof, if not next to the proof.
+        #[arg(long)]
+        verifier_key: Option<Input>,
+        /// Program id a recursive proof is expected to be for.
+        #[arg(long)]
+        program_id: Option<ProgramIdentifier>,
+    },
     /// Verify the given recursive proof from file.
     VerifyRecursiveProof {
         proof: Input,
         verifier_key: Input,
-        program_id: String,
+        program_id: ProgramIdentifier,
+    },
+    /// Write the verifier data of the circuit that wraps recursive proofs,
+    /// from which a gnark based plonky2 verifier is generated.
+    ///
+    /// The circuit is the same for every program; the verifier key of the
+    /// recursive proof it wraps is part of its public inputs.
+    ExportWrapperVerifier {
+        /// Directory to write the common and the verifier only circuit data
+        /// to.
+        #[arg(default_value = ".")]
+        output_dir: PathBuf,
     },
     /// Builds a transaction bundle.
     BundleTransaction {
@@ -103,7 +223,30 @@ enum Command {
         /// Output file path of the serialized bundle.
         #[arg(long, default_value = "bundle")]
         bundle: Output,
+        /// ELF of a program in the cast list, as `<program-id>=<elf-path>`.
+        /// Takes precedence over `examples/programs_map.json`.
+        #[arg(
+            long = "program",
+            env = "MOZAK_PROGRAMS",
+            value_delimiter = ',',
+            value_parser = parse_program_path
+        )]
+        programs: Vec<(ProgramIdentifier, PathBuf)>,
     },
+    /// Compute the commitment to a tape, as exposed publicly by the
+    /// `TapeCommitments` table.
+    CommitTape(TapeCommitmentArgs),
+    /// Check that a tape hashes to the given commitment.
+    VerifyTapeCommitment {
+        #[clap(flatten)]
+        tape: TapeCommitmentArgs,
+        /// Expected commitment, as a hex string.
+        commitment: String,
+    },
+    /// Build a `MozakVM` guest crate twice, once from scratch and once with
+    /// its usual target directory, and check that both builds give the same
+    /// ELF and Program Id.
+    ReproCheck { crate_dir: PathBuf },
     /// Compute the Program Rom Hash of the given ELF.
     ProgramRomHash { elf: Input },
     /// Compute the Memory Init Hash of the given ELF.
@@ -115,6 +258,76 @@ enum Command {
     Bench(BenchArgs),
 }
 
+/// Parses a `<program-id>=<elf-path>` pair, eg `MZK-0b7114fb...=wallet.elf`.
+fn parse_program_path(s: &str) -> Result<(ProgramIdentifier, PathBuf)> {
+    let (program_id, path) = s
+        .split_once('=')
+        .context("expected `<program-id>=<elf-path>`")?;
+    Ok((program_id.parse()?, PathBuf::from(path)))
+}
+
+/// Verifies a recursive VM proof, in the binary format or in plonky2's byte
+/// format, against the given verifier key, and, if given, checks that it
+/// proves the execution of `program_id`.  The program id in the header of the
+/// binary format is checked if none is given.
+fn verify_recursive_proof(
+    proof_buffer: Vec<u8>,
+    mut verifier_key: Input,
+    program_id: Option<ProgramIdentifier>,
+) -> Result<()> {
+    let (proof_buffer, program_id) = match ProofHeader::read(&proof_buffer)? {
+        Some(header) => {
+            ensure!(
+                header.config_digest == circuit_config_digest::<F, C, D>(&VM_RECURSION_CONFIG)?,
+                "recursive proof was made with a different circuit config"
+            );
+            let ProofPayload::Recursive(bytes) =
+                ProofContainer::<F, C, D>::from_bytes(&proof_buffer)?.payload
+            else {
+                bail!("expected a recursive proof, got a STARK proof");
+            };
+            (bytes, program_id.or(Some(header.program_id)))
+        }
+        None => (proof_buffer, program_id),
+    };
+    let mut circuit = circuit_data_for_recursion::<F, C, D>(
+        &VM_RECURSION_CONFIG,
+        VM_RECURSION_THRESHOLD_DEGREE_BITS,
+        VM_PUBLIC_INPUT_SIZE,
+    );
+
+    let mut vk_buffer: Vec<u8> = vec![];
+    verifier_key.read_to_end(&mut vk_buffer)?;
+    circuit.verifier_only = VerifierOnlyCircuitData::from_bytes(vk_buffer)
+        .map_err(|_| anyhow::Error::msg("VerifierOnlyCircuitData deserialization failed."))?;
+
+    let proof: ProofWithPublicInputs<F, C, D> =
+        ProofWithPublicInputs::from_bytes(proof_buffer, &circuit.common).map_err(|_| {
+            anyhow::Error::msg(
+                "File is neither a STARK proof nor a recursive proof for this verifier key.",
+            )
+        })?;
+    let public_inputs = VMRecursiveProofPublicInputs::from_public_inputs(&proof.public_inputs)
+        .context("the proof does not have the public inputs of a recursive VM proof")?;
+    if let Some(program_id) = program_id {
+        ensure!(
+            public_inputs.program_hash_as_bytes.to_vec()
+                == program_id
+                    .inner()
+                    .into_iter()
+                    .map(F::from_canonical_u8)
+                    .collect_vec(),
+            "recursive proof is for a different program"
+        );
+    }
+    println!("Public Inputs: {:?}", proof.public_inputs);
+    println!("Verifier Key: {:?}", circuit.verifier_only);
+
+    circuit.verify(proof)?;
+    println!("Recursive VM proof verified successfully!");
+    Ok(())
+}
+
 /// Run me eg like `cargo run -- -vvv run vm/tests/testdata/rv32ui-p-addi
 /// iotape.txt`
 #[allow(clippy::too_many_lines)]
@@ -125,9 +338,35 @@ fn main() -> Result<()> {
         .filter_level(cli.verbose.log_level_filter())
         .init();
     match cli.command {
-        Command::Decode { elf } => {
-            let program = load_program(elf)?;
+        Command::Decode {
+            mut elf,
+            format,
+            range,
+            section,
+        } => {
+            let mut elf_bytes = vec![];
+            elf.read_to_end(&mut elf_bytes)?;
+            let program = load_program(elf_bytes.as_slice())?;
             debug!("{program:?}");
+
+            let range = match (range, section) {
+                (range, None) => range,
+                (range, Some(section)) => {
+                    let section = section_range(&elf_bytes, &section)?;
+                    Some(match range {
+                        Some(range) => range.start.max(section.start)..range.end.min(section.end),
+                        None => section,
+                    })
+                }
+            };
+            let instructions = decode(&program, range.as_ref());
+            match format {
+                DecodeFormat::Text =>
+                    for instruction in instructions {
+                        println!("{instruction}");
+                    },
+                DecodeFormat::Json => println!("{}", serde_json::to_string_pretty(&instructions)?),
+            }
         }
         Command::Run(RunArgs { elf, system_tape }) => {
             let program = load_program(elf).unwrap();
@@ -146,16 +385,80 @@ fn main() -> Result<()> {
             let record = step(&program, state)?;
             prove_and_verify_mozak_stark(&program, &record, &config)?;
         }
+        Command::DryRun {
+            run: RunArgs { elf, system_tape },
+            max_degree_bits,
+        } => {
+            let program = load_program(elf).unwrap();
+            let self_prog_id = get_self_prog_id::<F, C, D>(&program, &config);
+            let raw_tapes = raw_tapes_from_system_tape(system_tape, self_prog_id);
+            let state = State::new(program.clone(), raw_tapes);
+            let record = step(&program, state)?;
+            let sizes = S::default().estimate_degrees(&program, &record)?;
+            for (size, kind) in sizes.with_kind().0 {
+                println!(
+                    "{:<24} {:>10} rows, degree bits {:>2}",
+                    format!("{kind:?}"),
+                    size.rows,
+                    size.degree_bits
+                );
+            }
+            let largest = sizes.iter().map(|size| size.degree_bits).max().unwrap();
+            println!("Largest table: 2^{largest} rows");
+            if let Some(max_degree_bits) = max_degree_bits {
+                ensure!(
+                    largest <= max_degree_bits,
+                    "largest table needs {largest} degree bits, more than {max_degree_bits}"
+                );
+            }
+        }
         Command::Prove(ProveArgs {
-            elf,
+            mut elf,
             system_tape,
+            disclose,
             mut proof,
             recursive_proof,
+            json,
+            compress,
+            wrapped_proof,
             batch_proof,
+            checkpoint_dir,
+            resume,
         }) => {
-            let program = load_program(elf).unwrap();
+            // Read the inputs up front, as they also identify the checkpoints of
+            // this run.
+            let mut elf_bytes = vec![];
+            elf.read_to_end(&mut elf_bytes)?;
+            let system_tape_bytes = system_tape
+                .map(|mut system_tape| {
+                    let mut bytes = vec![];
+                    system_tape.read_to_end(&mut bytes).map(|_| bytes)
+                })
+                .transpose()?;
+            let inputs = inputs_hash(&[
+                elf_bytes.as_slice(),
+                system_tape_bytes.as_deref().unwrap_or_default(),
+                &[u8::from(cli.debug), u8::from(batch_proof.is_some())],
+                &disclose
+                    .iter()
+                    .flat_map(|range| chain!(range.start.to_le_bytes(), range.end.to_le_bytes()))
+                    .collect_vec(),
+            ]);
+            let mut checkpoints = match (checkpoint_dir, resume) {
+                (Some(dir), _) => Some(Checkpoints::create(&dir, inputs)?),
+                (None, Some(dir)) => Some(Checkpoints::resume(&dir, inputs)?),
+                (None, None) => None,
+            };
+
+            let program = load_program(elf_bytes.as_slice()).unwrap();
             let self_prog_id = get_self_prog_id::<F, C, D>(&program, &config);
-            let raw_tapes = raw_tapes_from_system_tape(system_tape, self_prog_id);
+            let raw_tapes = RawTapes {
+                private_tape_disclosures: disclose
+                    .iter()
+                    .map(|range| range.start as usize..range.end as usize)
+                    .collect(),
+                ..raw_tapes_from_system_tape(system_tape_bytes.as_deref(), self_prog_id)
+            };
             let state = State::new(program.clone(), raw_tapes);
             let record = step(&program, state)?;
             let stark = if cli.debug {
@@ -167,115 +470,178 @@ fn main() -> Result<()> {
                 entry_point: F::from_canonical_u32(program.entry_point),
             };
 
-            let all_proof = prove::<F, C, D>(
-                &program,
-                &record,
-                &stark,
-                &config,
-                public_inputs,
-                &mut TimingTree::default(),
-            )?;
-
-            let serialized = serde_json::to_string(&all_proof).unwrap();
-            proof.write_all(serialized.as_bytes())?;
-
-            let mut batch_all_proof: Option<BatchProof<F, C, D>> = None;
-            let mut batch_degree_bits: Option<TableKindArray<usize>> = None;
-            if let Some(mut batch_proof_output) = batch_proof {
-                let (proof, degree_bits) = batch_prove::<F, C, D>(
+            let all_proof = load_or_run(checkpoints.as_mut(), "all_proof", || {
+                prove::<F, C, D>(
                     &program,
                     &record,
                     &stark,
-                    &PUBLIC_TABLE_KINDS,
                     &config,
                     public_inputs,
                     &mut TimingTree::default(),
-                )?;
+                )
+            })?;
+
+            let serialized = if json {
+                serde_json::to_vec(&all_proof)?
+            } else {
+                all_proof
+                    .clone()
+                    .into_container(&config)?
+                    .to_bytes(compress)?
+            };
+            proof.write_all(&serialized)?;
+
+            let mut batch_all_proof: Option<BatchProof<F, C, D>> = None;
+            let mut batch_degree_bits: Option<TableKindArray<usize>> = None;
+            if let Some(mut batch_proof_output) = batch_proof {
+                let (proof, degree_bits) =
+                    load_or_run(checkpoints.as_mut(), "batch_proof", || {
+                        batch_prove::<F, C, D>(
+                            &program,
+                            &record,
+                            &stark,
+                            &PUBLIC_TABLE_KINDS,
+                            &config,
+                            public_inputs,
+                            &mut TimingTree::default(),
+                        )
+                    })?;
+                let serialized = if json {
+                    serde_json::to_vec(&proof)?
+                } else {
+                    proof.clone().into_container(&config)?.to_bytes(compress)?
+                };
+                batch_proof_output.write_all(&serialized)?;
                 batch_all_proof = Some(proof);
                 batch_degree_bits = Some(degree_bits);
-                let serialized = serde_json::to_string(&batch_all_proof).unwrap();
-                batch_proof_output.write_all(serialized.as_bytes())?;
+
+                // The degree bits of the batched tables can not be recovered from
+                // the proof, so the verifier needs them on the side.
+                let mut degree_bits_output_path = batch_proof_output.path().clone();
+                degree_bits_output_path.set_extension("degree_bits");
+                let degree_bits_output = degree_bits_output_path.create()?;
+                serde_json::to_writer(degree_bits_output, &degree_bits)?;
             }
 
             // Generate recursive proof
             if let Some(mut recursive_proof_output) = recursive_proof {
-                let (verifier_only, common, recursive_all_proof) =
-                    if let Some(batch_proof) = batch_all_proof {
-                        let degree_bits = batch_degree_bits.unwrap();
-                        let recursive_circuit = recursive_batch_stark_circuit(
-                            &stark,
-                            &degree_bits,
-                            &PUBLIC_TABLE_KINDS,
-                            &VM_RECURSION_CONFIG,
-                            &config,
-                        );
-                        let verifier_only = recursive_circuit.circuit.verifier_only.clone();
-                        let common = recursive_circuit.circuit.common.clone();
-                        let recursive_proof = recursive_circuit.prove(&batch_proof).unwrap();
-                        (verifier_only, common, recursive_proof)
-                    } else {
-                        let degree_bits = all_proof.degree_bits(&config);
-                        let recursive_circuit = recursive_mozak_stark_circuit::<F, C, D>(
-                            &stark,
-                            &degree_bits,
-                            &VM_RECURSION_CONFIG,
-                            &config,
-                        );
-                        let verifier_only = recursive_circuit.circuit.verifier_only.clone();
-                        let common = recursive_circuit.circuit.common.clone();
-                        let recursive_proof = recursive_circuit.prove(&all_proof).unwrap();
-                        (verifier_only, common, recursive_proof)
+                // The shrunk proof is checkpointed with the verifier key of the
+                // circuit it is for, so that neither circuit has to be built
+                // again on resuming.
+                let shrunk: Option<(Vec<u8>, ProofWithPublicInputs<F, C, D>)> =
+                    match checkpoints.as_ref() {
+                        Some(checkpoints) => checkpoints.load("shrunk_proof")?,
+                        None => None,
                     };
-
-                println!(
-                    "Recursive proof size: {}",
-                    recursive_all_proof.to_bytes().len()
-                );
-                let public_inputs_array: [F; VM_PUBLIC_INPUT_SIZE] = recursive_all_proof
-                    .public_inputs
-                    .clone()
-                    .try_into()
+                let (vk_bytes, final_proof) = if let Some(shrunk) = shrunk {
+                    shrunk
+                } else {
+                    let (verifier_only, common, recursive_all_proof) =
+                        if let Some(batch_proof) = batch_all_proof {
+                            let degree_bits = batch_degree_bits.unwrap();
+                            let recursive_circuit = recursive_batch_stark_circuit(
+                                &stark,
+                                &degree_bits,
+                                &PUBLIC_TABLE_KINDS,
+                                &VM_RECURSION_CONFIG,
+                                &config,
+                            );
+                            let recursive_proof =
+                                load_or_run(checkpoints.as_mut(), "recursive_proof", || {
+                                    recursive_circuit.prove(&batch_proof)
+                                })?;
+                            let verifier_only = recursive_circuit.circuit.verifier_only;
+                            let common = recursive_circuit.circuit.common;
+                            (verifier_only, common, recursive_proof)
+                        } else {
+                            let degree_bits = all_proof.degree_bits(&config);
+                            let recursive_circuit = recursive_mozak_stark_circuit::<F, C, D>(
+                                &stark,
+                                &degree_bits,
+                                &VM_RECURSION_CONFIG,
+                                &config,
+                            );
+                            let recursive_proof =
+                                load_or_run(checkpoints.as_mut(), "recursive_proof", || {
+                                    recursive_circuit.prove(&all_proof)
+                                })?;
+                            let verifier_only = recursive_circuit.circuit.verifier_only;
+                            let common = recursive_circuit.circuit.common;
+                            (verifier_only, common, recursive_proof)
+                        };
+
+                    println!(
+                        "Recursive proof size: {}",
+                        recursive_all_proof.to_bytes().len()
+                    );
+                    let public_inputs = VMRecursiveProofPublicInputs::from_public_inputs(
+                        &recursive_all_proof.public_inputs,
+                    )
                     .unwrap();
+                    debug_assert_eq!(
+                        public_inputs.program_hash_as_bytes.to_vec(),
+                        self_prog_id
+                            .inner()
+                            .into_iter()
+                            .map(F::from_canonical_u8)
+                            .collect_vec()
+                    );
 
-                let public_inputs: VMRecursiveProofPublicInputs<F> = public_inputs_array.into();
-                debug_assert_eq!(
-                    public_inputs.program_hash_as_bytes.to_vec(),
-                    self_prog_id
-                        .inner()
-                        .into_iter()
-                        .map(F::from_canonical_u8)
-                        .collect_vec()
-                );
-
-                let (final_circuit, final_proof) = shrink_to_target_degree_bits_circuit(
-                    &verifier_only,
-                    &common,
-                    &VM_RECURSION_CONFIG,
-                    VM_RECURSION_THRESHOLD_DEGREE_BITS,
-                    &recursive_all_proof,
-                )?;
-                assert_eq!(
-                    final_circuit.circuit.common.num_public_inputs,
-                    VM_PUBLIC_INPUT_SIZE
-                );
+                    let (final_circuit, final_proof) = shrink_to_target_degree_bits_circuit(
+                        &verifier_only,
+                        &common,
+                        &VM_RECURSION_CONFIG,
+                        VM_RECURSION_THRESHOLD_DEGREE_BITS,
+                        &recursive_all_proof,
+                    )?;
+                    assert_eq!(
+                        final_circuit.circuit.common.num_public_inputs,
+                        VM_PUBLIC_INPUT_SIZE
+                    );
+                    let shrunk = (
+                        final_circuit.circuit.verifier_only.to_bytes().unwrap(),
+                        final_proof,
+                    );
+                    if let Some(checkpoints) = checkpoints.as_mut() {
+                        checkpoints.save("shrunk_proof", &shrunk)?;
+                    }
+                    shrunk
+                };
 
-                let s = final_proof.to_bytes();
-                recursive_proof_output.write_all(&s)?;
+                let container = ProofContainer::<F, C, D> {
+                    program_id: self_prog_id,
+                    config_digest: circuit_config_digest::<F, C, D>(&VM_RECURSION_CONFIG)?,
+                    payload: ProofPayload::Recursive(final_proof.to_bytes()),
+                };
+                recursive_proof_output.write_all(&container.to_bytes(compress)?)?;
 
                 // Generate the verifier key file
                 let mut vk_output_path = recursive_proof_output.path().clone();
                 vk_output_path.set_extension("vk");
                 let mut vk_output = vk_output_path.create()?;
-
-                let bytes = final_circuit.circuit.verifier_only.to_bytes().unwrap();
-                vk_output.write_all(&bytes)?;
+                vk_output.write_all(&vk_bytes)?;
+
+                if let Some(wrapped_proof_output) = wrapped_proof {
+                    let final_verifier_only = VerifierOnlyCircuitData::from_bytes(vk_bytes)
+                        .map_err(|_| {
+                            anyhow::Error::msg("VerifierOnlyCircuitData deserialization failed.")
+                        })?;
+                    let wrapper =
+                        VMProofWrapperCircuit::<F, C, WrapperC, D>::new(VM_PROOF_WRAPPER_CONFIG);
+                    let wrapped_proof = wrapper.prove(&final_verifier_only, &final_proof)?;
+                    serde_json::to_writer(wrapped_proof_output, &wrapped_proof)?;
+                }
             }
 
+            if let Some(checkpoints) = checkpoints {
+                checkpoints.remove()?;
+            }
             debug!("proof generated successfully!");
         }
         Command::BundleTransaction {
             system_tape: system_tape_path,
             bundle,
+            programs,
         } => {
             /// Returns mapping of program ID to elf path.
             ///
@@ -283,6 +649,7 @@ fn main() -> Result<()> {
             fn ids_and_paths_from_cast_list(
                 entrypoint_program_id: ProgramIdentifier,
                 cast_list: &[ProgramIdentifier],
+                overrides: &HashMap<ProgramIdentifier, PathBuf>,
             ) -> Vec<(ProgramIdentifier, PathBuf)> {
                 /// A `MappedProgram` is a (name, path) tuple of a `MozakVM`
                 /// program, where the name
@@ -296,18 +663,29 @@ fn main() -> Result<()> {
                 }
 
                 let curr_dir = std::env::current_dir().unwrap();
-                let mapping = std::fs::File::open(curr_dir.join(PROGRAMS_MAP_JSON))
-                    .expect("could not open programs map");
-                let mapping: Vec<MappedProgram> = serde_json::from_reader(mapping)
-                    .expect("Could not deserialize Vec<MappedProgram> from programs map");
-                let mapping: HashMap<ProgramIdentifier, String> = mapping
-                    .into_iter()
-                    .map(|mp| (ProgramIdentifier::from(mp.name), mp.path))
-                    .collect();
+                // The programs map is only needed for programs that were not given
+                // explicitly, which lets bundles be built outside the examples
+                // workspace.
+                let mapping: HashMap<ProgramIdentifier, PathBuf> =
+                    if cast_list.iter().all(|id| overrides.contains_key(id)) {
+                        HashMap::new()
+                    } else {
+                        let mapping = std::fs::File::open(curr_dir.join(PROGRAMS_MAP_JSON))
+                            .expect("could not open programs map");
+                        let mapping: Vec<MappedProgram> = serde_json::from_reader(mapping)
+                            .expect("Could not deserialize Vec<MappedProgram> from programs map");
+                        mapping
+                            .into_iter()
+                            .map(|mp| (ProgramIdentifier::from(mp.name), curr_dir.join(mp.path)))
+                            .collect()
+                    };
                 cast_list
                     .iter()
                     .filter_map(|id: &ProgramIdentifier| {
-                        mapping.get(id).map(|path| (*id, curr_dir.join(path)))
+                        overrides
+                            .get(id)
+                            .or_else(|| mapping.get(id))
+                            .map(|path| (*id, path.clone()))
                     })
                     .sorted_by_key(|(id, _)| id != &entrypoint_program_id)
                     .collect()
@@ -331,7 +709,9 @@ fn main() -> Result<()> {
                 .dedup()
                 .collect();
 
-            let ids_and_paths = ids_and_paths_from_cast_list(entrypoint_program_id, &cast_list);
+            let overrides: HashMap<ProgramIdentifier, PathBuf> = programs.into_iter().collect();
+            let ids_and_paths =
+                ids_and_paths_from_cast_list(entrypoint_program_id, &cast_list, &overrides);
 
             let mut attestations: Vec<Attestation> = vec![];
             let mut call_tape_hash = None;
@@ -341,6 +721,13 @@ fn main() -> Result<()> {
                     Input::try_from(elf)
                         .unwrap_or_else(|_| panic!("Elf filepath {elf:?} not found")),
                 )?;
+                if overrides.contains_key(program_id) {
+                    let elf_program_id = get_self_prog_id::<F, C, D>(&program, &config);
+                    ensure!(
+                        elf_program_id == *program_id,
+                        "{elf:?} is the ELF of {elf_program_id:?}, not of {program_id:?}"
+                    );
+                }
 
                 let raw_tapes =
                     raw_tapes_from_system_tape(Some(system_tape_path.clone()), *program_id);
@@ -397,52 +784,106 @@ fn main() -> Result<()> {
             println!("Transaction bundled: {transaction:?}");
         }
 
-        Command::Verify { mut proof } => {
-            let stark = S::default();
+        Command::Verify {
+            mut proof,
+            verifier_key,
+            program_id,
+        } => {
             let mut buffer: Vec<u8> = vec![];
             proof.read_to_end(&mut buffer)?;
-            let all_proof: AllProof<F, C, D> = serde_json::from_slice(&buffer)?;
-            verify_proof(&stark, all_proof, &config)?;
-            println!("proof verified successfully!");
+            let sidecar = |extension: &str| {
+                let mut path = proof.path().to_path_buf();
+                path.set_extension(extension);
+                Input::try_from(&path).with_context(|| {
+                    format!("could not open {path:?}, which should accompany the proof")
+                })
+            };
+            // The header of the binary format records the config the proof
+            // was made with, so a mismatch is reported as such, rather than as
+            // a failed verification.
+            let header = ProofHeader::read(&buffer)?;
+            if let Some(header) = header.filter(|h| h.kind != BinaryProofKind::Recursive) {
+                ensure!(
+                    header.config_digest == stark_config_digest::<F, C, D>(&config)?,
+                    "proof was made with a different STARK config"
+                );
+            }
+            match detect_proof_kind(&buffer)? {
+                ProofKind::All => {
+                    let stark = S::default();
+                    let all_proof = AllProof::<F, C, D>::decode(&buffer)?;
+                    verify_proof(&stark, all_proof, &config)?;
+                    println!("proof verified successfully!");
+                }
+                ProofKind::Batch => {
+                    let stark = S::default();
+                    let batch_proof = BatchProof::<F, C, D>::decode(&buffer)?;
+                    let degree_bits: TableKindArray<usize> =
+                        serde_json::from_reader(sidecar("degree_bits")?)?;
+                    batch_verify_proof(
+                        &stark,
+                        &PUBLIC_TABLE_KINDS,
+                        batch_proof,
+                        &config,
+                        &degree_bits,
+                    )?;
+                    println!("batch proof verified successfully!");
+                }
+                ProofKind::Recursive => {
+                    let verifier_key = match verifier_key {
+                        Some(verifier_key) => verifier_key,
+                        None => sidecar("vk")?,
+                    };
+                    verify_recursive_proof(buffer, verifier_key, program_id)?;
+                }
+            }
         }
         Command::VerifyRecursiveProof {
             mut proof,
-            mut verifier_key,
+            verifier_key,
             program_id,
         } => {
-            let mut circuit = circuit_data_for_recursion::<F, C, D>(
-                &VM_RECURSION_CONFIG,
-                VM_RECURSION_THRESHOLD_DEGREE_BITS,
-                VM_PUBLIC_INPUT_SIZE,
-            );
-
-            let mut vk_buffer: Vec<u8> = vec![];
-            verifier_key.read_to_end(&mut vk_buffer)?;
-            circuit.verifier_only = VerifierOnlyCircuitData::from_bytes(vk_buffer).unwrap();
-
             let mut proof_buffer: Vec<u8> = vec![];
             proof.read_to_end(&mut proof_buffer)?;
-            let proof: ProofWithPublicInputs<F, C, D> =
-                ProofWithPublicInputs::from_bytes(proof_buffer, &circuit.common).map_err(|_| {
-                    anyhow::Error::msg("ProofWithPublicInputs deserialization failed.")
-                })?;
-            let public_inputs_array: [F; VM_PUBLIC_INPUT_SIZE] =
-                proof.public_inputs.clone().try_into().unwrap();
-
-            let public_inputs: VMRecursiveProofPublicInputs<F> = public_inputs_array.into();
-            assert_eq!(
-                public_inputs.program_hash_as_bytes.to_vec(),
-                ProgramIdentifier::from(program_id)
-                    .inner()
-                    .into_iter()
-                    .map(F::from_canonical_u8)
-                    .collect_vec()
+            verify_recursive_proof(proof_buffer, verifier_key, Some(program_id))?;
+        }
+        Command::CommitTape(tape) => {
+            let commitment = tape.commitment()?;
+            println!("0x{}", hex::encode(commitment.inner()));
+        }
+        Command::VerifyTapeCommitment { tape, commitment } => {
+            let expected = hex::decode(commitment.trim_start_matches("0x"))?;
+            let actual = tape.commitment()?;
+            ensure!(
+                expected == actual.inner(),
+                "tape commitment mismatch: expected 0x{}, computed 0x{}",
+                hex::encode(expected),
+                hex::encode(actual.inner())
+            );
+            println!("tape commitment verified successfully!");
+        }
+        Command::ExportWrapperVerifier { output_dir } => {
+            let wrapper = VMProofWrapperCircuit::<F, C, WrapperC, D>::new(VM_PROOF_WRAPPER_CONFIG);
+            std::fs::create_dir_all(&output_dir)?;
+            let common_path = output_dir.join(COMMON_CIRCUIT_DATA_JSON);
+            serde_json::to_writer(
+                std::fs::File::create(&common_path)?,
+                &wrapper.circuit.common,
+            )?;
+            let verifier_only_path = output_dir.join(VERIFIER_ONLY_CIRCUIT_DATA_JSON);
+            serde_json::to_writer(
+                std::fs::File::create(&verifier_only_path)?,
+                &wrapper.circuit.verifier_only,
+            )?;
+            println!("Verifier data written to {common_path:?} and {verifier_only_path:?}");
+        }
+        Command::ReproCheck { crate_dir } => {
+            let report = repro_check::<F, C, D>(&crate_dir, &config)?;
+            print!("{report}");
+            ensure!(
+                report.is_reproducible(),
+                "{crate_dir:?} does not build reproducibly"
             );
-            println!("Public Inputs: {:?}", proof.public_inputs);
-            println!("Verifier Key: {:?}", circuit.verifier_only);
-
-            circuit.verify(proof.clone())?;
-            println!("Recursive VM proof verified successfully!");
         }
         Command::ProgramRomHash { elf } => {
             let program = load_program(elf)?;
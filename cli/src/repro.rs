@@ -0,0 +1,219 @@
+//! Checks that a `MozakVM` guest crate builds reproducibly.
+//!
+//! A program's [`ProgramIdentifier`] is derived from its ELF, so two parties
+//! building the same sources have to end up with byte-identical ELFs to agree
+//! on which program a proof is about.
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use itertools::Itertools;
+use log::debug;
+use mozak_sdk::common::types::{Poseidon2Hash, ProgramIdentifier};
+use mozak_sdk::native::poseidon::poseidon2_hash_with_pad;
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
+use starky::config::StarkConfig;
+
+use crate::runner::{get_self_prog_id, load_program};
+
+/// An ELF built from a guest crate.
+pub struct Build {
+    /// Target directory the ELF was built in.
+    pub target_dir: PathBuf,
+    /// Path of the built ELF.
+    pub elf_path: PathBuf,
+    pub elf: Vec<u8>,
+    pub elf_hash: Poseidon2Hash,
+    pub program_id: ProgramIdentifier,
+}
+
+/// Outcome of building a guest crate twice.
+pub struct ReproReport {
+    /// Build in a fresh target directory.
+    pub clean: Build,
+    /// Build in the crate's own, possibly warm, target directory.
+    pub cached: Build,
+    /// Likely sources of nondeterminism found in the ELFs.
+    pub findings: Vec<String>,
+}
+
+impl ReproReport {
+    #[must_use]
+    pub fn is_reproducible(&self) -> bool { self.clean.elf == self.cached.elf }
+}
+
+impl std::fmt::Display for ReproReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, build) in [("clean", &self.clean), ("cached", &self.cached)] {
+            writeln!(f, "{name} build: {}", build.elf_path.display())?;
+            writeln!(f, "  ELF hash:   {:?}", build.elf_hash)?;
+            writeln!(f, "  Program ID: {:?}", build.program_id)?;
+        }
+        if self.is_reproducible() {
+            writeln!(f, "builds are reproducible")?;
+        } else {
+            writeln!(
+                f,
+                "builds differ: {}",
+                describe_diff(&self.clean.elf, &self.cached.elf)
+            )?;
+        }
+        for finding in &self.findings {
+            writeln!(f, "warning: {finding}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the guest crate at `crate_dir` once in a fresh target directory and
+/// once in its own target directory, and compares the results.
+///
+/// # Errors
+///
+/// Errors if either build fails.
+pub fn repro_check<F, C, const D: usize>(
+    crate_dir: &Path,
+    config: &StarkConfig,
+) -> Result<ReproReport>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>, {
+    let crate_dir = crate_dir
+        .canonicalize()
+        .with_context(|| format!("could not find crate {crate_dir:?}"))?;
+
+    let fresh_target_dir = tempfile::tempdir()?;
+    let clean = build::<F, C, D>(&crate_dir, fresh_target_dir.path(), config)?;
+    let cached = build::<F, C, D>(&crate_dir, &crate_dir.join("target"), config)?;
+
+    let findings = find_nondeterminism(&crate_dir, &[&clean, &cached]);
+    Ok(ReproReport {
+        clean,
+        cached,
+        findings,
+    })
+}
+
+fn build<F, C, const D: usize>(
+    crate_dir: &Path,
+    target_dir: &Path,
+    config: &StarkConfig,
+) -> Result<Build>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>, {
+    debug!("Building {crate_dir:?} in {target_dir:?}");
+    // Same environment as `examples-builder`, so that we check the builds we
+    // actually ship.
+    let output = Command::new("cargo")
+        .args(["mozakvm-build", "--message-format=json", "--target-dir"])
+        .arg(target_dir)
+        .current_dir(crate_dir)
+        .env_clear()
+        .envs(std::env::vars().filter(|x| !x.0.starts_with("CARGO_")))
+        .output()
+        .context("cargo command failed to run")?;
+    if !output.status.success() {
+        bail!(
+            "cargo mozakvm-build in {crate_dir:?} failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let elf_path = String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|msg| msg["reason"] == "compiler-artifact")
+        .filter_map(|msg| msg["executable"].as_str().map(PathBuf::from))
+        .exactly_one()
+        .map_err(|_| anyhow::anyhow!("expected {crate_dir:?} to build exactly one ELF"))?;
+
+    let elf = std::fs::read(&elf_path)?;
+    let program = load_program(elf.as_slice())?;
+    Ok(Build {
+        target_dir: target_dir.to_path_buf(),
+        elf_hash: poseidon2_hash_with_pad(&elf),
+        program_id: get_self_prog_id::<F, C, D>(&program, config),
+        elf_path,
+        elf,
+    })
+}
+
+/// Looks for the usual suspects of nondeterministic builds: absolute paths of
+/// the build machine baked into the binary.
+fn find_nondeterminism(crate_dir: &Path, builds: &[&Build]) -> Vec<String> {
+    let cargo_home = std::env::var_os("CARGO_HOME").map_or_else(
+        || PathBuf::from(std::env::var_os("HOME").unwrap_or_default()).join(".cargo"),
+        PathBuf::from,
+    );
+    let mut suspects = vec![
+        ("crate directory", crate_dir.to_path_buf()),
+        ("cargo home", cargo_home),
+    ];
+    suspects.extend(
+        builds
+            .iter()
+            .map(|build| ("target directory", build.target_dir.clone())),
+    );
+
+    let mut findings = builds
+        .iter()
+        .cartesian_product(&suspects)
+        .filter(|(build, (_, path))| contains(&build.elf, path.to_string_lossy().as_bytes()))
+        .map(|(build, (what, path))| {
+            format!(
+                "{} embeds the {what} path {path:?}; consider `--remap-path-prefix`",
+                build.elf_path.display()
+            )
+        })
+        .collect_vec();
+    if findings.is_empty() && !builds.iter().map(|build| &build.elf).all_equal() {
+        findings.push(
+            "no build paths are embedded, so the difference likely comes from timestamps or \
+             from dependencies that are not pinned by Cargo.lock"
+                .to_string(),
+        );
+    }
+    findings
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    !needle.is_empty() && haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+fn describe_diff(a: &[u8], b: &[u8]) -> String {
+    if a.len() != b.len() {
+        return format!("sizes are {} and {} bytes", a.len(), b.len());
+    }
+    let differing = a.iter().zip(b).positions(|(x, y)| x != y).collect_vec();
+    match differing.first() {
+        Some(first) => format!(
+            "{} bytes differ, first at offset {first:#x}",
+            differing.len()
+        ),
+        None => "contents are identical".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::describe_diff;
+
+    #[test]
+    fn describes_diffs() {
+        assert_eq!(
+            describe_diff(&[1, 2, 3, 4], &[1, 0, 3, 0]),
+            "2 bytes differ, first at offset 0x1"
+        );
+        assert_eq!(
+            describe_diff(&[1, 2, 3], &[1, 2]),
+            "sizes are 3 and 2 bytes"
+        );
+        assert_eq!(describe_diff(&[], &[1]), "sizes are 0 and 1 bytes");
+        assert_eq!(describe_diff(&[1, 2], &[1, 2]), "contents are identical");
+    }
+}
@@ -1,168 +1,167 @@
 //! Utility functions that helps the CLI to interact with the
 //! [Mozak runner crate](mozak_runner).
-use std::collections::BTreeSet;
-
-use anyhow::Result;
-use itertools::{izip, Itertools};
-use log::debug;
-use mozak_circuits::memoryinit::generation::generate_elf_memory_init_trace;
-use mozak_circuits::program::generation::generate_program_rom_trace;
-use mozak_circuits::stark::prover::get_program_id;
-use mozak_runner::elf::Program;
-use mozak_runner::state::RawTapes;
-use mozak_sdk::common::merkle::merkleize;
-use mozak_sdk::common::types::{
-    CanonicalOrderedTemporalHints, Poseidon2Hash, ProgramIdentifier, SystemTape,
+use anyhow::{bail, Result};
+use mozak_circuits::stark::proof::BinaryProofKind;
+pub use mozak_circuits::stark::prover::get_self_prog_id;
+pub use mozak_circuits::system_tape::{
+    canonical_order_temporal_hints, cast_list_commitment, cast_list_from_system_tape,
+    deserialize_system_tape, events_commitment, private_tape_commitment,
+    raw_tapes_from_system_tape,
 };
-use plonky2::field::extension::Extendable;
-use plonky2::hash::hash_types::RichField;
-use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
-use rkyv::rancor::{Panic, Strategy};
-use rkyv::ser::AllocSerializer;
-use starky::config::StarkConfig;
+pub use mozak_runner::elf::load_program;
+use mozak_sdk::common::types::{Poseidon2Hash, ProgramIdentifier, SystemTape};
+
+/// The tapes whose commitments are exposed as public inputs through the
+/// `TapeCommitments` table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum CommitmentTape {
+    /// The events emitted by a program.
+    Events,
+    /// The list of programs taking part in the transaction.
+    CastList,
+    /// The private input of a program.
+    Private,
+}
 
-use crate::trace_utils::get_trace_merkle_cap;
+/// Computes the commitment to `tape` that the program `self_prog_id` would
+/// read, and hence expose publicly, when proven against `sys`.
+#[must_use]
+pub fn tape_commitment(
+    sys: &SystemTape,
+    tape: CommitmentTape,
+    self_prog_id: ProgramIdentifier,
+) -> Poseidon2Hash {
+    match tape {
+        CommitmentTape::Events =>
+            events_commitment(&canonical_order_temporal_hints(sys, self_prog_id)),
+        CommitmentTape::CastList => cast_list_commitment(&cast_list_from_system_tape(sys)),
+        CommitmentTape::Private => private_tape_commitment(
+            &sys.private_input_tape
+                .writer
+                .get(&self_prog_id)
+                .cloned()
+                .unwrap_or_default()
+                .0,
+        ),
+    }
+}
 
-pub fn load_program<F: std::io::Read>(mut elf: F) -> Result<Program> {
-    let mut elf_bytes = Vec::new();
-    let bytes_read = elf.read_to_end(&mut elf_bytes)?;
-    debug!("Read {bytes_read} of ELF data.");
-    Program::mozak_load_program(&elf_bytes)
+/// The kinds of proof files written by the `prove` command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofKind {
+    /// An `AllProof`, in the binary format or serde JSON serialized.
+    All,
+    /// A `BatchProof`, in the binary format or serde JSON serialized.
+    Batch,
+    /// A plonky2 `ProofWithPublicInputs` of the recursive verifier, in the
+    /// binary format or in plonky2's byte format.
+    Recursive,
 }
 
-/// Deserializes a serde JSON serialized system tape binary file into a
-/// [`SystemTape`].
+/// Detects which kind of proof `bytes` holds.
+///
+/// Proofs in the binary format record their kind in their header. As JSON
+/// objects, a `BatchProof` is told apart from an `AllProof` by its
+/// `batch_stark_proof` field. Anything else is taken to be a recursive proof;
+/// it is only validated once its verifier key is known.
 ///
 /// # Errors
 ///
-/// Errors if reading from the binary file fails.
-pub fn deserialize_system_tape<F: std::io::Read>(mut bin: F) -> Result<SystemTape> {
-    let mut sys_tapes_bytes = Vec::new();
-    let bytes_read = bin.read_to_end(&mut sys_tapes_bytes)?;
-    debug!("Read {bytes_read} of system tape data.");
-    let deserialized: SystemTape = serde_json::from_slice(&sys_tapes_bytes)?;
-    Ok(deserialized)
-}
-
-fn length_prefixed_bytes(data: Vec<u8>, dgb_string: &str) -> Vec<u8> {
-    let data_len = data.len();
-    let mut len_prefix_bytes = Vec::with_capacity(data_len + 4);
-    len_prefix_bytes.extend_from_slice(
-        &(u32::try_from(data.len()))
-            .expect("length of data's max size shouldn't be more than u32")
-            .to_le_bytes(),
-    );
-    len_prefix_bytes.extend(data);
-    debug!(
-        "Length-Prefixed {:<15} of byte len: {:>5}, on-mem bytes: {:>5}",
-        dgb_string,
-        data_len,
-        len_prefix_bytes.len()
-    );
-    len_prefix_bytes
-}
-
-pub fn raw_tapes_from_system_tape<F: std::io::Read>(
-    sys: Option<F>,
-    self_prog_id: ProgramIdentifier,
-) -> RawTapes {
-    if sys.is_none() {
-        return RawTapes::default();
+/// Errors if `bytes` is a JSON value that is neither kind of STARK proof, or
+/// in an unsupported version of the binary format.
+pub fn detect_proof_kind(bytes: &[u8]) -> Result<ProofKind> {
+    match BinaryProofKind::detect(bytes)? {
+        Some(BinaryProofKind::All) => return Ok(ProofKind::All),
+        Some(BinaryProofKind::Batch) => return Ok(ProofKind::Batch),
+        Some(BinaryProofKind::Recursive) => return Ok(ProofKind::Recursive),
+        None => {}
     }
+    let Ok(value) = serde_json::from_slice::<serde_json::Value>(bytes) else {
+        return Ok(ProofKind::Recursive);
+    };
+    match value.as_object() {
+        Some(fields) if fields.contains_key("batch_stark_proof") => Ok(ProofKind::Batch),
+        Some(fields) if fields.contains_key("proofs") => Ok(ProofKind::All),
+        _ => bail!("JSON file is neither an `AllProof` nor a `BatchProof`"),
+    }
+}
 
-    let sys = &deserialize_system_tape(sys.unwrap()).unwrap();
-
-    let cast_list = sys
-        .call_tape
-        .writer
-        .iter()
-        .map(|msg| msg.callee)
-        .collect::<BTreeSet<_>>()
-        .into_iter()
-        .collect_vec();
-
-    let canonical_order_temporal_hints: Vec<CanonicalOrderedTemporalHints> = sys
-        .event_tape
-        .writer
-        .get(&self_prog_id)
-        .cloned()
-        .unwrap_or_default()
-        .get_canonical_order_temporal_hints();
-
-    let events_commitment_tape = merkleize(
-        canonical_order_temporal_hints
-            .iter()
-            .map(|x| {
-                (
-                    // May not be the best idea if
-                    // `addr` > goldilock's prime, cc
-                    // @Kapil
-                    u64::from_le_bytes(x.0.address.inner()),
-                    x.0.canonical_hash(),
-                )
-            })
-            .collect::<Vec<(u64, Poseidon2Hash)>>(),
-    )
-    .0;
+#[cfg(test)]
+mod tests {
+    use mozak_sdk::common::types::{CrossProgramCall, Event, EventType, StateAddress, StateObject};
+    use mozak_sdk::native::OrderedEvents;
 
-    let cast_list_commitment_tape =
-        merkleize(izip!(0.., &cast_list).map(|(idx, x)| (idx, x.0)).collect()).0;
+    use super::*;
 
-    debug!("Self Prog ID: {self_prog_id:#?}");
-    debug!("Found events: {:#?}", canonical_order_temporal_hints.len());
+    fn id(byte: u8) -> ProgramIdentifier { ProgramIdentifier(Poseidon2Hash::from([byte; 32])) }
 
-    {
-        fn serialise<T>(tape: &T, dgb_string: &str) -> Vec<u8>
-        where
-            T: rkyv::Archive + rkyv::Serialize<Strategy<AllocSerializer<256>, Panic>>, {
-            let tape_bytes = rkyv::to_bytes::<_, 256, _>(tape).unwrap().into();
-            length_prefixed_bytes(tape_bytes, dgb_string)
+    fn event(address: u8, type_: EventType) -> Event {
+        Event {
+            object: StateObject {
+                address: StateAddress([address; 8]),
+                constraint_owner: id(2),
+                data: vec![address],
+            },
+            type_,
         }
+    }
 
-        RawTapes {
-            private_tape: length_prefixed_bytes(
-                sys.private_input_tape
-                    .writer
-                    .get(&self_prog_id)
-                    .cloned()
-                    .unwrap_or_default()
-                    .0,
-                "PRIVATE_TAPE",
-            ),
-            public_tape: length_prefixed_bytes(
-                sys.public_input_tape
-                    .writer
-                    .get(&self_prog_id)
-                    .cloned()
-                    .unwrap_or_default()
-                    .0,
-                "PUBLIC_TAPE",
-            ),
-            call_tape: serialise(&sys.call_tape.writer, "CALL_TAPE"),
-            event_tape: serialise(&canonical_order_temporal_hints, "EVENT_TAPE"),
-            self_prog_id_tape: self_prog_id.0 .0,
-            events_commitment_tape,
-            cast_list_commitment_tape,
+    fn events(events: Vec<Event>) -> SystemTape {
+        let mut sys = SystemTape::default();
+        for (caller, callee) in [(1, 3), (3, 2), (1, 3)] {
+            sys.call_tape.writer.push(CrossProgramCall {
+                caller: id(caller),
+                callee: id(callee),
+                ..CrossProgramCall::default()
+            });
         }
+        sys.event_tape
+            .writer
+            .insert(id(2), OrderedEvents::new(events));
+        sys
     }
-}
 
-/// Computes `[ProgramIdentifer]` from hash of entry point and merkle caps
-/// of `ElfMemoryInit` and `ProgramRom` tables.
-pub fn get_self_prog_id<F, C, const D: usize>(
-    program: &Program,
-    config: &StarkConfig,
-) -> ProgramIdentifier
-where
-    F: RichField + Extendable<D>,
-    C: GenericConfig<D, F = F>,
-    C::Hasher: AlgebraicHasher<F>, {
-    let entry_point = F::from_canonical_u32(program.entry_point);
+    fn system_tape() -> SystemTape {
+        events(vec![event(7, EventType::Write), event(3, EventType::Read)])
+    }
 
-    let elf_memory_init_trace = generate_elf_memory_init_trace::<F>(program);
-    let program_rom_trace = generate_program_rom_trace::<F>(program);
+    #[test]
+    fn cast_list_commitment_covers_each_callee_once() {
+        let sys = system_tape();
+        assert_eq!(cast_list_from_system_tape(&sys), [id(2), id(3)]);
+
+        let commitment = tape_commitment(&sys, CommitmentTape::CastList, id(2));
+        assert_eq!(commitment, cast_list_commitment(&[id(2), id(3)]));
+        assert_eq!(
+            commitment,
+            tape_commitment(&sys, CommitmentTape::CastList, id(3))
+        );
+        assert_ne!(commitment, cast_list_commitment(&[id(3), id(2)]));
+        assert_ne!(commitment, cast_list_commitment(&[id(2)]));
+    }
 
-    let elf_memory_init_cap = get_trace_merkle_cap::<F, C, D, _>(elf_memory_init_trace, config);
-    let program_cap = get_trace_merkle_cap::<F, C, D, _>(program_rom_trace, config);
-    get_program_id::<F, C, D>(entry_point, &program_cap, &elf_memory_init_cap)
+    #[test]
+    fn events_commitment_is_per_program() {
+        let sys = system_tape();
+        let emitted = tape_commitment(&sys, CommitmentTape::Events, id(2));
+        let none = tape_commitment(&sys, CommitmentTape::Events, id(3));
+        assert_eq!(none, events_commitment(&[]));
+        assert_ne!(emitted, none);
+        assert_eq!(
+            emitted,
+            events_commitment(&canonical_order_temporal_hints(&sys, id(2)))
+        );
+
+        // The events are committed to in canonical order, not as emitted.
+        let reordered = events(vec![event(3, EventType::Read), event(7, EventType::Write)]);
+        assert_eq!(
+            tape_commitment(&reordered, CommitmentTape::Events, id(2)),
+            emitted
+        );
+        let changed = events(vec![event(7, EventType::Write), event(4, EventType::Read)]);
+        assert_ne!(
+            tape_commitment(&changed, CommitmentTape::Events, id(2)),
+            emitted
+        );
+    }
 }
@@ -70,3 +70,49 @@ fn test_prove_and_verify_recursive_proof_command() {
         "Verify recursive proof command failed"
     );
 }
+
+#[test]
+fn test_verify_detects_proof_kind() {
+    let temp_dir = TempDir::new().expect("Failed to create a temporary directory");
+    let temp_path = temp_dir.path();
+
+    let proof_file = temp_path.join("proof.bin");
+    let batch_proof_file = temp_path.join("batch_proof.bin");
+    let recursive_proof_file = temp_path.join("recursive_proof.bin");
+
+    let elf_file: &str =
+        "../examples/fibonacci/mozakvm/target/riscv32im-mozak-mozakvm-elf/mozak-release/fibonacci-mozakvm";
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "prove",
+            elf_file,
+            &proof_file.to_string_lossy(),
+            &recursive_proof_file.to_string_lossy(),
+            "--batch-proof",
+            &batch_proof_file.to_string_lossy(),
+        ])
+        .output()
+        .expect("Failed to execute prove command");
+    assert!(
+        output.status.success(),
+        "Prove command failed: {:?}",
+        output
+    );
+
+    // The same `verify` command handles every kind of proof.
+    for file in &[&proof_file, &batch_proof_file, &recursive_proof_file] {
+        let output = Command::new("cargo")
+            .args(["run", "--", "verify", &file.to_string_lossy()])
+            .output()
+            .expect("Failed to execute verify command");
+        assert!(
+            output.status.success(),
+            "Verify command failed for {:?}: {:?}",
+            file,
+            output
+        );
+    }
+}
@@ -1,9 +1,13 @@
-use std::fs::File;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 struct Crate {
+    name: &'static str,
     crate_path: &'static str,
     elf_path: &'static str,
     glob_name: &'static str,
@@ -16,6 +20,7 @@ macro_rules! ecrate {
     };
     ($name:literal, $file:literal, $glob:literal) => {
         Crate {
+            name: $name,
             crate_path: concat!("../examples/", $name, "/mozakvm"),
             elf_path: concat!(
                 "../examples/",
@@ -47,24 +52,132 @@ const CRATES: &[Crate] = &[
     ecrate!("vector-alloc", "VECTOR_ALLOC_ELF"),
 ];
 const CARGO_MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+/// Set to rebuild the examples even if their fingerprints did not change.
+const REBUILD_VAR: &str = "MOZAK_EXAMPLES_REBUILD";
+/// Set to use empty ELFs instead of building the examples, eg for docs or
+/// `cargo check` on machines without the mozakvm toolchain.
+const STUB_VAR: &str = "MOZAK_EXAMPLES_STUB";
+const TARGET_SPEC: &str = "../.cargo/riscv32im-mozak-mozakvm-elf.json";
+
+/// Hashes the files under `path`, skipping build outputs.
+fn hash_dir(hasher: &mut DefaultHasher, path: &Path) {
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    let mut entries: Vec<_> = entries.filter_map(Result::ok).collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            if entry.file_name() != "target" {
+                hash_dir(hasher, &path);
+            }
+        } else if let Ok(contents) = fs::read(&path) {
+            path.hash(hasher);
+            contents.hash(hasher);
+        }
+    }
+}
+
+/// The local crates `crate_path` depends on, directly or not, found from the
+/// `path = "..."` dependencies in their manifests.
+fn path_dependencies(crate_path: &str) -> BTreeSet<PathBuf> {
+    let mut found = BTreeSet::new();
+    let mut pending = vec![PathBuf::from(crate_path)];
+    while let Some(dir) = pending.pop() {
+        let Ok(manifest) = fs::read_to_string(dir.join("Cargo.toml")) else {
+            continue;
+        };
+        let deps = manifest
+            .split("path = \"")
+            .skip(1)
+            .filter_map(|rest| rest.split_once('"'))
+            .filter_map(|(path, _)| dir.join(path).canonicalize().ok())
+            .filter(|dep| dep.join("Cargo.toml").exists());
+        for dep in deps {
+            if found.insert(dep.clone()) {
+                pending.push(dep);
+            }
+        }
+    }
+    found
+}
+
+/// A fingerprint of everything an example's ELF is built from: its sources,
+/// including its lock file, the sources of its path dependencies, and the
+/// toolchain.
+fn fingerprint(crate_path: &str, dependencies: &BTreeSet<PathBuf>) -> String {
+    let mut hasher = DefaultHasher::new();
+    hash_dir(&mut hasher, Path::new(crate_path));
+    for dependency in dependencies {
+        hash_dir(&mut hasher, dependency);
+    }
+    let toolchain = Command::new("rustc")
+        .arg("-vV")
+        .output()
+        .map(|output| output.stdout)
+        .unwrap_or_default();
+    toolchain.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Whether the ELF was built from the same fingerprint before.
+fn is_fresh(elf_path: &str, fingerprint_path: &str, fingerprint: &str) -> bool {
+    std::env::var_os(REBUILD_VAR).is_none()
+        && Path::new(elf_path).exists()
+        && fs::read_to_string(fingerprint_path).is_ok_and(|previous| previous == fingerprint)
+}
+
+/// Checks that the toolchain in `crate_path` can build for the mozakvm
+/// target, which needs a nightly compiler with the `rust-src` component to
+/// build the standard library, and the target's spec.
+fn check_toolchain(crate_path: &str) -> Result<(), String> {
+    let rustc = |arg: &str| {
+        Command::new("rustc")
+            .arg(arg)
+            .current_dir(crate_path)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let version = rustc("--version")
+        .ok_or("`rustc` could not be run. Install rustup from https://rustup.rs")?;
+    if !version.contains("nightly") {
+        return Err(format!(
+            "the examples need a nightly toolchain, but {crate_path} uses {version}. \
+             Run `rustup show` in the repository to install the one in rust-toolchain.toml"
+        ));
+    }
+    let sysroot = rustc("--print=sysroot").unwrap_or_default();
+    if !Path::new(&sysroot)
+        .join("lib/rustlib/src/rust/library")
+        .exists()
+    {
+        return Err(
+            "the examples build the standard library, which needs the `rust-src` \
+             component. Run `rustup component add rust-src` in the repository"
+                .to_string(),
+        );
+    }
+    if !Path::new(TARGET_SPEC).exists() {
+        return Err(format!("the mozakvm target spec {TARGET_SPEC} is missing"));
+    }
+    Ok(())
+}
 
 fn build_elf(dest: &mut File, crate_path: &str, elf_path: &str, glob_name: &str) {
     // Use a dummy array for clippy, since not building the elf is faster than
     // building the elf
-    if cfg!(feature = "cargo-clippy") {
+    let dependencies = path_dependencies(crate_path);
+    if cfg!(feature = "cargo-clippy") || std::env::var_os(STUB_VAR).is_some() {
         writeln!(dest, r#"pub const {glob_name}: &[u8] = &[];"#)
     } else {
-        let output = Command::new("cargo")
-            .args(["mozakvm-build"])
-            .current_dir(crate_path)
-            .env_clear()
-            .envs(std::env::vars().filter(|x| !x.0.starts_with("CARGO_")))
-            .output()
-            .expect("cargo command failed to run");
-        if !output.status.success() {
-            io::stdout().write_all(&output.stdout).unwrap();
-            io::stderr().write_all(&output.stderr).unwrap();
-            panic!("cargo build {crate_path} failed.");
+        let fingerprint_path = format!("{elf_path}.fingerprint");
+        let fingerprint = fingerprint(crate_path, &dependencies);
+        if !is_fresh(elf_path, &fingerprint_path, &fingerprint) {
+            build(crate_path);
+            fs::write(&fingerprint_path, fingerprint).expect("failed to write fingerprint");
         }
         writeln!(
             dest,
@@ -76,18 +189,58 @@ fn build_elf(dest: &mut File, crate_path: &str, elf_path: &str, glob_name: &str)
 
     println!("cargo:rerun-if-changed={crate_path}");
     println!("cargo:rerun-if-changed={elf_path}");
+    for dependency in &dependencies {
+        println!("cargo:rerun-if-changed={}", dependency.display());
+    }
+}
+
+fn build(crate_path: &str) {
+    if let Err(problem) = check_toolchain(crate_path) {
+        panic!(
+            "can not build {crate_path}: {problem}.\n\nTo use empty ELFs instead, eg for docs \
+             or `cargo check`, set {STUB_VAR}=1."
+        );
+    }
+    let output = Command::new("cargo")
+        .args(["mozakvm-build"])
+        .current_dir(crate_path)
+        .env_clear()
+        .envs(std::env::vars().filter(|x| !x.0.starts_with("CARGO_")))
+        .output()
+        .expect("cargo command failed to run");
+    if !output.status.success() {
+        io::stdout().write_all(&output.stdout).unwrap();
+        io::stderr().write_all(&output.stderr).unwrap();
+        panic!("cargo build {crate_path} failed.");
+    }
 }
 
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed={REBUILD_VAR}");
+    println!("cargo:rerun-if-env-changed={STUB_VAR}");
 
     let out_dir = std::env::var_os("OUT_DIR").unwrap();
     let dest_path = Path::new(&out_dir).join("vars.rs");
     let mut dest = File::create(dest_path).expect("failed to create vars.rs");
 
-    for c in CRATES {
-        if c.enabled {
-            build_elf(&mut dest, c.crate_path, c.elf_path, c.glob_name)
-        }
+    let enabled: Vec<&Crate> = CRATES.iter().filter(|c| c.enabled).collect();
+    for c in &enabled {
+        build_elf(&mut dest, c.crate_path, c.elf_path, c.glob_name);
+    }
+
+    writeln!(
+        dest,
+        "pub static EXAMPLES: ExampleRegistry = ExampleRegistry(&["
+    )
+    .expect("failed to write vars.rs");
+    for c in &enabled {
+        writeln!(
+            dest,
+            r#"    Example {{ name: "{}", elf: {}, features: &[] }},"#,
+            c.name, c.glob_name
+        )
+        .expect("failed to write vars.rs");
     }
+    writeln!(dest, "]);").expect("failed to write vars.rs");
 }
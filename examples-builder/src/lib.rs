@@ -1 +1,7 @@
 include!(concat!(env!("OUT_DIR"), "/vars.rs"));
+
+pub mod on_demand;
+pub mod registry;
+
+pub use on_demand::{build_example, BuiltExample};
+pub use registry::{Example, ExampleRegistry};
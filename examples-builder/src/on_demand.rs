@@ -0,0 +1,87 @@
+//! Builds examples when they are needed, with a choice of cargo features and
+//! profile, unlike the ELFs baked in at build time, which are built once with
+//! the default features.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, fs};
+
+const EXAMPLES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../examples");
+const TARGET: &str = "riscv32im-mozak-mozakvm-elf";
+
+/// An example built by [`build_example`].
+#[derive(Clone, Debug)]
+pub struct BuiltExample {
+    pub path: PathBuf,
+    pub elf: Vec<u8>,
+}
+
+/// Builds the mozakvm binary of the example `name` with `features` enabled,
+/// in `profile`, eg `"mozak-release"`.
+///
+/// Every combination of features and profile is built into a target
+/// directory of its own, so it does not clobber the ELFs that are baked in,
+/// and rebuilding it is incremental.
+///
+/// # Errors
+///
+/// Returns an error if the example does not exist, or cargo fails to build
+/// it. Cargo's output is forwarded in that case.
+pub fn build_example(name: &str, features: &[&str], profile: &str) -> io::Result<BuiltExample> {
+    let crate_path = Path::new(EXAMPLES_DIR).join(name).join("mozakvm");
+    if !crate_path.join("Cargo.toml").exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no example {name:?} in {EXAMPLES_DIR}"),
+        ));
+    }
+    let mut features = features.to_vec();
+    features.sort_unstable();
+    features.dedup();
+    let target_dir = crate_path.join("target").join("on-demand").join(format!(
+        "{profile}{}",
+        features.iter().map(|f| format!("+{f}")).collect::<String>()
+    ));
+
+    let output = Command::new("cargo")
+        .args([
+            "build",
+            "--profile",
+            profile,
+            "--target",
+            TARGET,
+            "-Zbuild-std=alloc,core,compiler_builtins,std,panic_abort,proc_macro",
+            "-Zbuild-std-features=compiler-builtins-mem",
+            "--features",
+            &features.join(","),
+        ])
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .current_dir(&crate_path)
+        .env_clear()
+        .envs(env::vars().filter(|x| !x.0.starts_with("CARGO_")))
+        .output()?;
+    if !output.status.success() {
+        io::stdout().write_all(&output.stdout)?;
+        io::stderr().write_all(&output.stderr)?;
+        return Err(io::Error::other(format!(
+            "cargo build {} failed",
+            crate_path.display()
+        )));
+    }
+
+    // Cargo names the output directories of its built-in profiles after
+    // their old names.
+    let profile_dir = match profile {
+        "dev" => "debug",
+        "bench" => "release",
+        profile => profile,
+    };
+    let path = target_dir
+        .join(TARGET)
+        .join(profile_dir)
+        .join(format!("{name}-mozakvm"));
+    let elf = fs::read(&path)?;
+    Ok(BuiltExample { path, elf })
+}
@@ -0,0 +1,33 @@
+//! Lookup of the examples baked into this crate by name.
+
+/// An example ELF baked in at build time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Example {
+    pub name: &'static str,
+    pub elf: &'static [u8],
+    /// Cargo features the example was built with, besides its default ones.
+    pub features: &'static [&'static str],
+}
+
+/// The examples enabled by this crate's features. See [`EXAMPLES`].
+///
+/// [`EXAMPLES`]: crate::EXAMPLES
+#[derive(Clone, Copy, Debug)]
+pub struct ExampleRegistry(pub(crate) &'static [Example]);
+
+impl ExampleRegistry {
+    /// Looks up an example by the name of its directory in `examples/`.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&'static Example> {
+        self.0.iter().find(|example| example.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'static Example> { self.0.iter() }
+}
+
+impl IntoIterator for ExampleRegistry {
+    type IntoIter = std::slice::Iter<'static, Example>;
+    type Item = &'static Example;
+
+    fn into_iter(self) -> Self::IntoIter { self.0.iter() }
+}
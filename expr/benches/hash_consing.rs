@@ -0,0 +1,48 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use expr::{Cached, Evaluator, Expr, ExprBuilder, HashConsing, PureEvaluator};
+
+/// Constraints in the style of the CPU table: each one rebuilds the same
+/// sums of limbs, as the helpers that build them are called once per
+/// constraint.
+fn constraints<'a>(eb: &'a ExprBuilder, limbs: &[Expr<'a, i64>; 8]) -> Vec<Expr<'a, i64>> {
+    let value = |limbs: &[Expr<'a, i64>]| Expr::reduce_with_powers(limbs.iter().copied(), 256);
+    (0..64)
+        .map(|i| {
+            let selector = eb.lit(i % 2);
+            selector * (value(&limbs[..4]) - value(&limbs[4..]))
+        })
+        .collect()
+}
+
+fn hash_consing_benchmark(c: &mut Criterion) {
+    let eb = ExprBuilder::default();
+    let limbs = [0, 1, 2, 3, 4, 5, 6, 7].map(|limb| eb.lit(limb));
+    let constraints = constraints(&eb, &limbs);
+
+    let mut group = c.benchmark_group("hash_consing");
+    group.bench_function("cached", |b| {
+        b.iter(|| {
+            let mut evaluator = Cached::from(PureEvaluator::default());
+            for &constraint in &constraints {
+                black_box(evaluator.eval(constraint));
+            }
+        });
+    });
+    group.bench_function("hash_consed_and_cached", |b| {
+        b.iter(|| {
+            let mut hash_consing = HashConsing::default();
+            let mut evaluator = Cached::from(PureEvaluator::default());
+            for &constraint in &constraints {
+                black_box(evaluator.eval(hash_consing.hash_cons(constraint)));
+            }
+        });
+    });
+    group.finish();
+}
+
+criterion_group![
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = hash_consing_benchmark
+];
+criterion_main!(benches);
@@ -0,0 +1,202 @@
+//! Evaluation of expressions over many frames.
+//!
+//! [`BatchEvaluator`] compiles expressions built over the [`FrameVar`]s of a
+//! symbolic frame into a straight-line program once, and then runs that
+//! program on each frame, instead of walking the expression DAG per row.
+
+use std::collections::HashMap;
+
+use plonky2_maybe_rayon::*;
+use starky::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
+
+use crate::{BinOp, CompoundExpr, Evaluator, Expr, ExprBuilder, ExprTree, StarkFrameTyped, UnaOp};
+
+/// A variable of a [`StarkFrame`], by position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameVar {
+    Local(usize),
+    Next(usize),
+    PublicInput(usize),
+}
+
+impl ExprBuilder {
+    /// A frame of [`FrameVar`]s, to build expressions for a
+    /// [`BatchEvaluator`] with.
+    pub fn symbolic_starkframe<'a, View, PublicInputs, const N: usize, const N2: usize>(
+        &'a self,
+    ) -> StarkFrameTyped<View, PublicInputs>
+    where
+        View: FromIterator<Expr<'a, FrameVar>>,
+        PublicInputs: FromIterator<Expr<'a, FrameVar>>, {
+        StarkFrameTyped {
+            local_values: (0..N).map(|i| self.lit(FrameVar::Local(i))).collect(),
+            next_values: (0..N).map(|i| self.lit(FrameVar::Next(i))).collect(),
+            public_inputs: (0..N2)
+                .map(|i| self.lit(FrameVar::PublicInput(i)))
+                .collect(),
+        }
+    }
+}
+
+/// One step of the program of a [`BatchEvaluator`], whose result goes to the
+/// next slot.  Operands refer to the slots of earlier steps.
+#[derive(Debug, Clone, Copy)]
+enum Instruction {
+    Var(FrameVar),
+    Constant(i64),
+    BigConstant([u64; 4]),
+    BinOp(BinOp, usize, usize),
+    UnaOp(UnaOp, usize),
+}
+
+/// Evaluates expressions over many frames, in parallel with the `parallel`
+/// feature.
+///
+/// Nodes shared between the expressions are evaluated once per frame.
+#[derive(Debug, Clone)]
+pub struct BatchEvaluator {
+    instructions: Vec<Instruction>,
+    /// The slot of each expression's result.
+    outputs: Vec<usize>,
+}
+
+impl BatchEvaluator {
+    /// Compiles `exprs`.
+    ///
+    /// # Panics
+    ///
+    /// Panics on conjunctions, which have to be split with
+    /// [`Expr::conjuncts`] first.
+    pub fn new<'a>(exprs: impl IntoIterator<Item = Expr<'a, FrameVar>>) -> Self {
+        let mut batch = BatchEvaluator {
+            instructions: Vec::new(),
+            outputs: Vec::new(),
+        };
+        let mut slots = HashMap::new();
+        for expr in exprs {
+            let slot = match expr {
+                Expr::Basic { value } => batch.push(Instruction::Constant(value)),
+                Expr::Compound { expr, .. } => batch.compound_expr(expr, &mut slots),
+            };
+            batch.outputs.push(slot);
+        }
+        batch
+    }
+
+    fn push(&mut self, instruction: Instruction) -> usize {
+        self.instructions.push(instruction);
+        self.instructions.len() - 1
+    }
+
+    fn compound_expr<'a>(
+        &mut self,
+        expr: CompoundExpr<'a, FrameVar>,
+        slots: &mut HashMap<*const ExprTree<'a, FrameVar>, usize>,
+    ) -> usize {
+        let k = expr.0 as *const ExprTree<'_, FrameVar>;
+        if let Some(&slot) = slots.get(&k) {
+            return slot;
+        }
+        let instruction = match *expr.0 {
+            ExprTree::BinOp { op, left, right } => {
+                let left = self.compound_expr(left, slots);
+                let right = self.compound_expr(right, slots);
+                Instruction::BinOp(op, left, right)
+            }
+            ExprTree::UnaOp { op, expr } => Instruction::UnaOp(op, self.compound_expr(expr, slots)),
+            ExprTree::Literal { value } => Instruction::Var(value),
+            ExprTree::Constant { value } => Instruction::Constant(value),
+            ExprTree::BigConstant { value } => Instruction::BigConstant(value),
+            ExprTree::And { .. } =>
+                panic!("split conjunctions with `Expr::conjuncts` before evaluating them"),
+            ExprTree::Annotated { expr, .. } => {
+                let slot = self.compound_expr(expr, slots);
+                slots.insert(k, slot);
+                return slot;
+            }
+        };
+        let slot = self.push(instruction);
+        slots.insert(k, slot);
+        slot
+    }
+
+    /// Evaluates the expressions on each of `frames`, with a clone of
+    /// `evaluator` per frame.  Returns the values of the expressions, in
+    /// order, for each frame.
+    pub fn eval<'a, T, U, E, const N: usize, const N2: usize>(
+        &self,
+        evaluator: &E,
+        frames: &[StarkFrame<T, U, N, N2>],
+    ) -> Vec<Vec<T>>
+    where
+        T: Copy + Clone + Default + From<U> + Send + Sync,
+        U: Copy + Clone + Default + Sync,
+        E: Evaluator<'a, T> + Clone + Sync, {
+        frames
+            .par_iter()
+            .map(|frame| self.eval_frame(&mut evaluator.clone(), frame))
+            .collect()
+    }
+
+    fn eval_frame<'a, T, U, E, const N: usize, const N2: usize>(
+        &self,
+        evaluator: &mut E,
+        frame: &StarkFrame<T, U, N, N2>,
+    ) -> Vec<T>
+    where
+        T: Copy + Clone + Default + From<U>,
+        U: Copy + Clone + Default,
+        E: Evaluator<'a, T>, {
+        let mut slots: Vec<T> = Vec::with_capacity(self.instructions.len());
+        for instruction in &self.instructions {
+            let value = match *instruction {
+                Instruction::Var(FrameVar::Local(i)) => frame.get_local_values()[i],
+                Instruction::Var(FrameVar::Next(i)) => frame.get_next_values()[i],
+                Instruction::Var(FrameVar::PublicInput(i)) => T::from(frame.get_public_inputs()[i]),
+                Instruction::Constant(value) => evaluator.constant(value),
+                Instruction::BigConstant(value) => evaluator.big_constant(value),
+                Instruction::BinOp(op, left, right) =>
+                    evaluator.bin_op(op, slots[left], slots[right]),
+                Instruction::UnaOp(op, expr) => evaluator.una_op(op, slots[expr]),
+            };
+            slots.push(value);
+        }
+        self.outputs.iter().map(|&slot| slots[slot]).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use starky::evaluation_frame::StarkFrame;
+
+    use super::{BatchEvaluator, FrameVar};
+    use crate::{Evaluator, Expr, ExprBuilder, PureEvaluator, StarkFrameTyped};
+
+    #[test]
+    fn evaluates_like_the_pure_evaluator() {
+        let eb = ExprBuilder::default();
+        let frame: StarkFrameTyped<Vec<Expr<'_, FrameVar>>, Vec<Expr<'_, FrameVar>>> =
+            eb.symbolic_starkframe::<_, _, 2, 1>();
+        let (lv, nv, pi) = (
+            &frame.local_values,
+            &frame.next_values,
+            &frame.public_inputs,
+        );
+        let shared = lv[0] * lv[1];
+        let exprs = [shared - nv[0], shared * pi[0] + 1, Expr::from(7)];
+        let batch = BatchEvaluator::new(exprs);
+
+        let frames: Vec<_> = (0..100i64)
+            .map(|i| StarkFrame::<i64, i64, 2, 1>::from_values(&[i, i + 1], &[i * i, 0], &[3]))
+            .collect();
+        let values = batch.eval(&PureEvaluator::default(), &frames);
+
+        for (i, values) in (0..100i64).zip(values) {
+            let rows = ExprBuilder::default();
+            let (a, b) = (rows.lit(i), rows.lit(i + 1));
+            let mut p = PureEvaluator::default();
+            let expected = [a * b - i * i, a * b * 3 + 1, Expr::from(7)].map(|e| p.eval(e));
+            assert_eq!(values, expected);
+        }
+    }
+}
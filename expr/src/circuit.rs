@@ -0,0 +1,62 @@
+//! Evaluation of expressions to plonky2 recursive circuits, so that the typed
+//! constraints of a STARK can be reused verbatim in `eval_ext_circuit`.
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::{BinOp, Evaluator, UnaOp};
+
+/// Evaluator that adds the gates computing an [`Expr`](crate::Expr) over
+/// [`ExtensionTarget`]s to a [`CircuitBuilder`].
+pub struct CircuitBuilderEvaluator<'a, F, const D: usize>
+where
+    F: RichField,
+    F: Extendable<D>, {
+    pub builder: &'a mut CircuitBuilder<F, D>,
+}
+
+impl<'a, F, const D: usize> Evaluator<'a, ExtensionTarget<D>> for CircuitBuilderEvaluator<'a, F, D>
+where
+    F: RichField,
+    F: Extendable<D>,
+{
+    fn bin_op(
+        &mut self,
+        op: BinOp,
+        left: ExtensionTarget<D>,
+        right: ExtensionTarget<D>,
+    ) -> ExtensionTarget<D> {
+        match op {
+            BinOp::Add => self.builder.add_extension(left, right),
+            BinOp::Sub => self.builder.sub_extension(left, right),
+            BinOp::Mul => self.builder.mul_extension(left, right),
+            BinOp::Div => self.builder.div_extension(left, right),
+        }
+    }
+
+    fn una_op(&mut self, op: UnaOp, expr: ExtensionTarget<D>) -> ExtensionTarget<D> {
+        match op {
+            UnaOp::Neg => {
+                let neg_one = self.builder.neg_one();
+                self.builder.scalar_mul_ext(neg_one, expr)
+            }
+        }
+    }
+
+    fn constant(&mut self, value: i64) -> ExtensionTarget<D> {
+        let f = F::from_noncanonical_i64(value);
+        self.builder.constant_extension(f.into())
+    }
+
+    /// Reduced in the field, to add a single constant to the circuit.
+    fn big_constant(&mut self, value: [u64; 4]) -> ExtensionTarget<D> {
+        let shift = F::from_noncanonical_u128(1 << 64);
+        let f = value.iter().rev().fold(F::ZERO, |acc, &limb| {
+            acc * shift + F::from_noncanonical_u64(limb)
+        });
+        self.builder.constant_extension(f.into())
+    }
+}
@@ -0,0 +1,236 @@
+//! A typed front-end for collecting the constraints of a table, tagged with
+//! the rows they apply to.
+//!
+//! Turning the collected constraints into a proof is up to the prover, eg
+//! `build_packed` and `build_ext` in `mozak-circuits`.
+
+use core::panic::Location;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::degree::{DegreeLowering, Witness};
+use crate::owned::OwnedExprTree;
+use crate::Expr;
+
+/// The rows a [`Constraint`] applies to.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Default, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ConstraintType {
+    FirstRow,
+    #[default]
+    Always,
+    Transition,
+    LastRow,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Constraint<E> {
+    pub constraint_type: ConstraintType,
+    /// Where the constraint was added.
+    pub location: &'static Location<'static>,
+    pub term: E,
+}
+
+impl<E> Constraint<E> {
+    pub fn map<B, F>(self, mut f: F) -> Constraint<B>
+    where
+        F: FnMut(E) -> B, {
+        Constraint {
+            constraint_type: self.constraint_type,
+            location: self.location,
+            term: f(self.term),
+        }
+    }
+}
+
+impl<'a, V> Constraint<Expr<'a, V>>
+where
+    V: Copy,
+{
+    /// Splits a constraint built with `&` into the constraints it is made of,
+    /// see [`Expr::conjuncts`].
+    pub fn split(self) -> impl Iterator<Item = Self> {
+        let Constraint {
+            constraint_type,
+            location,
+            term,
+        } = self;
+        term.conjuncts().into_iter().map(move |term| Constraint {
+            constraint_type,
+            location,
+            term,
+        })
+    }
+}
+
+/// A [`Constraint`] copied out of its expression builder, eg to snapshot the
+/// constraints of a table and diff them across commits.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct OwnedConstraint<V> {
+    pub constraint_type: ConstraintType,
+    /// Where the constraint was added, as `file:line`.
+    pub location: String,
+    pub term: OwnedExprTree<V>,
+}
+
+/// Collects the constraints of a table.
+pub struct ConstraintCollector<'a, V> {
+    constraints: Vec<Constraint<Expr<'a, V>>>,
+}
+
+impl<'a, V> Default for ConstraintCollector<'a, V> {
+    fn default() -> Self {
+        Self {
+            constraints: Vec::default(),
+        }
+    }
+}
+
+impl<'a, V> From<Vec<Constraint<Expr<'a, V>>>> for ConstraintCollector<'a, V> {
+    fn from(constraints: Vec<Constraint<Expr<'a, V>>>) -> Self { Self { constraints } }
+}
+
+impl<'a, V> IntoIterator for ConstraintCollector<'a, V> {
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+    type Item = Constraint<Expr<'a, V>>;
+
+    fn into_iter(self) -> Self::IntoIter { self.constraints.into_iter() }
+}
+
+impl<'a, V> ConstraintCollector<'a, V> {
+    #[track_caller]
+    fn constraint(&mut self, term: Expr<'a, V>, constraint_type: ConstraintType) {
+        self.constraints.push(Constraint {
+            constraint_type,
+            location: Location::caller(),
+            term,
+        });
+    }
+
+    #[track_caller]
+    pub fn first_row(&mut self, constraint: Expr<'a, V>) {
+        self.constraint(constraint, ConstraintType::FirstRow);
+    }
+
+    #[track_caller]
+    pub fn last_row(&mut self, constraint: Expr<'a, V>) {
+        self.constraint(constraint, ConstraintType::LastRow);
+    }
+
+    #[track_caller]
+    pub fn always(&mut self, constraint: Expr<'a, V>) {
+        self.constraint(constraint, ConstraintType::Always);
+    }
+
+    #[track_caller]
+    pub fn transition(&mut self, constraint: Expr<'a, V>) {
+        self.constraint(constraint, ConstraintType::Transition);
+    }
+}
+
+impl<'a, V> ConstraintCollector<'a, V>
+where
+    V: Copy,
+{
+    /// [`Expr::select`], which also constrains `cond` to be binary.
+    #[track_caller]
+    pub fn select(
+        &mut self,
+        cond: Expr<'a, V>,
+        if_true: Expr<'a, V>,
+        if_false: Expr<'a, V>,
+    ) -> Expr<'a, V> {
+        self.always(cond.is_binary());
+        Expr::select(cond, if_true, if_false)
+    }
+
+    /// Lowers all constraints to at most `max_degree`, see [`DegreeLowering`].
+    /// Returns the lowered constraints, and the witness columns that the
+    /// trace generation has to fill in.
+    ///
+    /// `fresh` allocates a new witness column.  The constraints defining a
+    /// witness are added where the constraint that needed it was, with the
+    /// same type.
+    pub fn lower_degree(
+        self,
+        max_degree: usize,
+        mut fresh: impl FnMut() -> V,
+    ) -> (Self, Vec<Witness<'a, V>>) {
+        let mut lowerings = BTreeMap::new();
+        let mut constraints = vec![];
+        for Constraint {
+            constraint_type,
+            location,
+            term,
+        } in self.constraints
+        {
+            let lowering = lowerings
+                .entry(constraint_type)
+                .or_insert_with(|| DegreeLowering::new(max_degree));
+            constraints.extend(lowering.lower(term, &mut fresh).into_iter().map(|term| {
+                Constraint {
+                    constraint_type,
+                    location,
+                    term,
+                }
+            }));
+        }
+        let witnesses = lowerings
+            .into_values()
+            .flat_map(DegreeLowering::into_witnesses)
+            .collect();
+        (Self { constraints }, witnesses)
+    }
+
+    #[must_use]
+    pub fn to_owned_constraints(&self) -> Vec<OwnedConstraint<V>> {
+        self.constraints
+            .iter()
+            .map(|constraint| OwnedConstraint {
+                constraint_type: constraint.constraint_type,
+                location: format!(
+                    "{}:{}",
+                    constraint.location.file(),
+                    constraint.location.line()
+                ),
+                term: constraint.term.to_owned_tree(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConstraintCollector, ConstraintType};
+    use crate::{Evaluator, ExprBuilder, PureEvaluator};
+
+    #[test]
+    fn collects_tagged_constraints() {
+        let eb = ExprBuilder::default();
+        let (a, b) = (eb.lit(2i64), eb.lit(3i64));
+
+        let mut constraints = ConstraintCollector::default();
+        constraints.first_row(a);
+        let line = line!() + 1;
+        constraints.transition(a & b);
+        let selected = constraints.select(eb.lit(1), a, b);
+        constraints.last_row(selected);
+
+        let owned = constraints.to_owned_constraints();
+        assert_eq!(owned[1].location, format!("{}:{line}", file!()));
+
+        let mut p = PureEvaluator::default();
+        let collected = constraints
+            .into_iter()
+            .flat_map(super::Constraint::split)
+            .map(|c| (c.constraint_type, p.eval(c.term)))
+            .collect::<Vec<_>>();
+        assert_eq!(collected, vec![
+            (ConstraintType::FirstRow, 2),
+            (ConstraintType::Transition, 2),
+            (ConstraintType::Transition, 3),
+            (ConstraintType::Always, 0),
+            (ConstraintType::LastRow, 2),
+        ]);
+    }
+}
@@ -0,0 +1,365 @@
+//! Lowering the degree of constraints, by naming high degree subexpressions
+//! with fresh witness columns.
+//!
+//! Eg with a maximum degree of 3, the constraint `a * b * c * d` becomes
+//! `w * d`, and `w - a * b * c` defines the witness `w`, which the trace
+//! generation fills in with the value of `a * b * c`.
+
+use std::collections::HashMap;
+
+use crate::{BinOp, CompoundExpr, Expr, ExprBuilder, ExprTree};
+
+impl<'a, V> Expr<'a, V> {
+    /// The degree of `self` as a polynomial in its literals.
+    ///
+    /// # Panics
+    ///
+    /// Panics on division by a non-constant, or on a conjunction, which have
+    /// no degree.
+    #[must_use]
+    pub fn degree(&self) -> usize {
+        match self {
+            Expr::Basic { .. } => 0,
+            Expr::Compound { expr, .. } => degree(expr, &mut HashMap::new()),
+        }
+    }
+}
+
+/// See [`Expr::degree`].  `memo` holds the degrees of the nodes seen so far,
+/// by address.
+fn degree<V>(expr: &CompoundExpr<'_, V>, memo: &mut HashMap<usize, usize>) -> usize {
+    if let Some(&degree) = memo.get(&expr.address()) {
+        return degree;
+    }
+    let d = match expr.0 {
+        ExprTree::Literal { .. } => 1,
+        ExprTree::Constant { .. } | ExprTree::BigConstant { .. } => 0,
+        ExprTree::BinOp {
+            op: BinOp::Add | BinOp::Sub,
+            left,
+            right,
+        } => degree(left, memo).max(degree(right, memo)),
+        ExprTree::BinOp {
+            op: BinOp::Mul,
+            left,
+            right,
+        } => degree(left, memo) + degree(right, memo),
+        ExprTree::BinOp {
+            op: BinOp::Div,
+            left,
+            right,
+        } => {
+            assert_eq!(
+                degree(right, memo),
+                0,
+                "division by a non-constant is not a polynomial"
+            );
+            degree(left, memo)
+        }
+        ExprTree::UnaOp { expr, .. } | ExprTree::Annotated { expr, .. } => degree(expr, memo),
+        ExprTree::And { .. } => panic!("split conjunctions with `Expr::conjuncts` first"),
+    };
+    memo.insert(expr.address(), d);
+    d
+}
+
+/// A witness column introduced by [`DegreeLowering`].
+#[derive(Debug, Clone, Copy)]
+pub struct Witness<'a, V> {
+    pub column: V,
+    /// The value to fill the column with, in terms of the original columns.
+    /// For transition constraints, this can refer to the next row.
+    pub value: Expr<'a, V>,
+}
+
+/// Splits constraints of a degree higher than `max_degree` into several
+/// constraints, linked by witness columns.
+///
+/// Subexpressions shared between the constraints lowered by one instance
+/// share their witnesses, so use one instance per constraint type: the
+/// constraints defining a witness only hold on the rows of the constraint
+/// that introduced it.
+pub struct DegreeLowering<'a, V> {
+    max_degree: usize,
+    /// The degree of each node seen so far, by address.
+    degrees: HashMap<usize, usize>,
+    /// The lowered version of each node seen so far, with its degree.
+    lowered: HashMap<*const ExprTree<'a, V>, (Expr<'a, V>, usize)>,
+    /// The witness standing in for each node that was replaced.
+    replaced: HashMap<*const ExprTree<'a, V>, Expr<'a, V>>,
+    witnesses: Vec<Witness<'a, V>>,
+    /// The constraints defining the witnesses introduced by the current call
+    /// to [`DegreeLowering::lower`].
+    definitions: Vec<Expr<'a, V>>,
+}
+
+impl<'a, V> DegreeLowering<'a, V>
+where
+    V: Copy,
+{
+    /// # Panics
+    ///
+    /// Panics if `max_degree` is less than 2, as products of two witnesses
+    /// can not be lowered any further.
+    #[must_use]
+    pub fn new(max_degree: usize) -> Self {
+        assert!(max_degree >= 2, "cannot lower constraints below degree 2");
+        DegreeLowering {
+            max_degree,
+            degrees: HashMap::default(),
+            lowered: HashMap::default(),
+            replaced: HashMap::default(),
+            witnesses: Vec::default(),
+            definitions: Vec::default(),
+        }
+    }
+
+    /// Lowers `constraint` to at most the maximum degree.  Returns the
+    /// lowered constraints, followed by the constraints defining the
+    /// witnesses they introduced.
+    ///
+    /// `fresh` allocates a new witness column.
+    pub fn lower(
+        &mut self,
+        constraint: Expr<'a, V>,
+        fresh: &mut impl FnMut() -> V,
+    ) -> Vec<Expr<'a, V>> {
+        let mut constraints = constraint
+            .conjuncts()
+            .into_iter()
+            .map(|conjunct| match conjunct {
+                Expr::Basic { .. } => conjunct,
+                Expr::Compound { expr, builder } => self.compound_expr(expr, builder, fresh).0,
+            })
+            .collect::<Vec<_>>();
+        constraints.append(&mut self.definitions);
+        constraints
+    }
+
+    /// The witnesses introduced so far, in the order they were introduced.
+    #[must_use]
+    pub fn witnesses(&self) -> &[Witness<'a, V>] { &self.witnesses }
+
+    #[must_use]
+    pub fn into_witnesses(self) -> Vec<Witness<'a, V>> { self.witnesses }
+
+    fn compound_expr(
+        &mut self,
+        expr: CompoundExpr<'a, V>,
+        builder: &'a ExprBuilder,
+        fresh: &mut impl FnMut() -> V,
+    ) -> (Expr<'a, V>, usize) {
+        let k = expr.0 as *const ExprTree<'_, V>;
+        if let Some(&lowered) = self.lowered.get(&k) {
+            return lowered;
+        }
+        let d = degree(&expr, &mut self.degrees);
+        let lowered = if d <= self.max_degree {
+            (builder.wrap(expr), d)
+        } else {
+            match *expr.0 {
+                ExprTree::BinOp {
+                    op: BinOp::Mul,
+                    left,
+                    right,
+                } => {
+                    let mut l = self.compound_expr(left, builder, fresh);
+                    let mut r = self.compound_expr(right, builder, fresh);
+                    while l.1 + r.1 > self.max_degree {
+                        if l.1 >= r.1 {
+                            l = (self.witness(left, l.0, builder, fresh), 1);
+                        } else {
+                            r = (self.witness(right, r.0, builder, fresh), 1);
+                        }
+                    }
+                    (l.0 * r.0, l.1 + r.1)
+                }
+                ExprTree::BinOp { op, left, right } => {
+                    let (l, dl) = self.compound_expr(left, builder, fresh);
+                    let (r, dr) = self.compound_expr(right, builder, fresh);
+                    (
+                        Expr::bin_op(op, l, r),
+                        if op == BinOp::Div { dl } else { dl.max(dr) },
+                    )
+                }
+                ExprTree::UnaOp { op, expr } => {
+                    let (x, dx) = self.compound_expr(expr, builder, fresh);
+                    (Expr::una_op(op, x), dx)
+                }
+                ExprTree::Annotated {
+                    label,
+                    location,
+                    expr,
+                } => match self.compound_expr(expr, builder, fresh) {
+                    (Expr::Compound { expr, .. }, dx) => (
+                        builder.wrap(builder.intern(ExprTree::Annotated {
+                            label,
+                            location,
+                            expr,
+                        })),
+                        dx,
+                    ),
+                    basic => basic,
+                },
+                // Literals and constants are of degree at most 1, and
+                // conjunctions are split up front.
+                ExprTree::Literal { .. }
+                | ExprTree::Constant { .. }
+                | ExprTree::BigConstant { .. }
+                | ExprTree::And { .. } => unreachable!(),
+            }
+        };
+        self.lowered.insert(k, lowered);
+        lowered
+    }
+
+    /// The witness standing in for `original`, whose lowered version is
+    /// `lowered`.
+    fn witness(
+        &mut self,
+        original: CompoundExpr<'a, V>,
+        lowered: Expr<'a, V>,
+        builder: &'a ExprBuilder,
+        fresh: &mut impl FnMut() -> V,
+    ) -> Expr<'a, V> {
+        let k = original.0 as *const ExprTree<'_, V>;
+        if let Some(&witness) = self.replaced.get(&k) {
+            return witness;
+        }
+        let column = fresh();
+        let witness = builder.lit(column);
+        self.witnesses.push(Witness {
+            column,
+            value: builder.wrap(original),
+        });
+        self.definitions.push(witness - lowered);
+        self.replaced.insert(k, witness);
+        witness
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::DegreeLowering;
+    use crate::owned::OwnedExprTree;
+    use crate::{Evaluator, Expr, ExprBuilder, PureEvaluator};
+
+    /// Replaces the literals in `tree` that are keys of `values`.
+    fn substitute(tree: OwnedExprTree<i64>, values: &HashMap<i64, i64>) -> OwnedExprTree<i64> {
+        let sub = |tree: Box<OwnedExprTree<i64>>| Box::new(substitute(*tree, values));
+        match tree {
+            OwnedExprTree::Literal { value } => OwnedExprTree::Literal {
+                value: *values.get(&value).unwrap_or(&value),
+            },
+            OwnedExprTree::BinOp { op, left, right } => OwnedExprTree::BinOp {
+                op,
+                left: sub(left),
+                right: sub(right),
+            },
+            OwnedExprTree::UnaOp { op, expr } => OwnedExprTree::UnaOp {
+                op,
+                expr: sub(expr),
+            },
+            OwnedExprTree::And { left, right } => OwnedExprTree::And {
+                left: sub(left),
+                right: sub(right),
+            },
+            OwnedExprTree::Annotated { label, expr } => OwnedExprTree::Annotated {
+                label,
+                expr: sub(expr),
+            },
+            OwnedExprTree::Constant { value } => OwnedExprTree::Constant { value },
+            OwnedExprTree::BigConstant { value } => OwnedExprTree::BigConstant { value },
+        }
+    }
+
+    #[test]
+    fn degree() {
+        let eb = ExprBuilder::default();
+        let (a, b) = (eb.lit(2i64), eb.lit(3i64));
+
+        assert_eq!(Expr::<'_, i64>::from(5).degree(), 0);
+        assert_eq!((a + 1).degree(), 1);
+        assert_eq!((a * b - a).degree(), 2);
+        assert_eq!((-(a * b) * a / 2).degree(), 3);
+        assert_eq!(a.pow(10).degree(), 10);
+        assert_eq!((eb.constant(2) * 3 + a).degree(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "division by a non-constant is not a polynomial")]
+    fn division_by_a_non_constant_has_no_degree() {
+        let eb = ExprBuilder::default();
+        let a = eb.lit(2i64);
+        let _ = (a / a).degree();
+    }
+
+    #[test]
+    fn lowers_to_max_degree() {
+        let eb = ExprBuilder::default();
+        let (a, b, c) = (eb.lit(2i64), eb.lit(3i64), eb.lit(5i64));
+        let square = a * b * c * (a * b * c);
+        let constraints = [square - 900, ((a + b) * c.pow(4)) & (a.pow(3) * 3)];
+
+        for max_degree in 2..=4 {
+            // Witness columns are numbered from 100, to not clash with the
+            // values of `a`, `b` and `c`.
+            let mut next = 100;
+            let mut fresh = || {
+                next += 1;
+                next
+            };
+            let mut lowering = DegreeLowering::new(max_degree);
+            let lowered = constraints.map(|constraint| lowering.lower(constraint, &mut fresh));
+
+            let mut p = PureEvaluator::default();
+            let values = lowering
+                .witnesses()
+                .iter()
+                .map(|witness| (witness.column, p.eval(witness.value)))
+                .collect::<HashMap<_, _>>();
+            assert!(!values.is_empty());
+
+            let other = ExprBuilder::default();
+            let [square, conjunction] = lowered.map(|constraints| {
+                constraints
+                    .into_iter()
+                    .inspect(|constraint| assert!(constraint.degree() <= max_degree))
+                    .map(|constraint| substitute(constraint.to_owned_tree(), &values))
+                    .map(|tree| p.eval(other.from_owned_tree(tree)))
+                    .collect::<Vec<_>>()
+            });
+            // The lowered constraints come first, then the definitions of the
+            // witnesses, which hold.
+            assert_eq!(square[0], 0);
+            assert_eq!(&conjunction[..2], &[5 * 625, 24]);
+            assert!(square[1..]
+                .iter()
+                .chain(&conjunction[2..])
+                .all(|&value| value == 0));
+        }
+    }
+
+    #[test]
+    fn low_degree_constraints_are_unchanged() {
+        let eb = ExprBuilder::default();
+        let (a, b) = (eb.lit(2i64), eb.lit(3i64));
+        let constraint = a * b - 6;
+
+        let mut lowering = DegreeLowering::new(2);
+        let lowered = lowering.lower(constraint, &mut || unreachable!());
+        assert!(lowering.witnesses().is_empty());
+        let (
+            [Expr::Compound { expr: lowered, .. }],
+            Expr::Compound {
+                expr: constraint, ..
+            },
+        ) = (lowered.as_slice(), constraint)
+        else {
+            panic!("expected one compound expression")
+        };
+        assert!(core::ptr::eq(lowered.0, constraint.0));
+    }
+}
@@ -0,0 +1,132 @@
+//! Trait implementations of [`Display`], to render expressions as algebra,
+//! eg `lv.op_add * (lv.dst - lv.rs1 - lv.rs2)`.
+//!
+//! Shared nodes are rendered each time they occur, so the output can be
+//! exponentially larger than the expression.  Annotations are not rendered.
+
+use core::fmt::{self, Display, Formatter};
+
+use crate::{BinOp, CompoundExpr, Expr, ExprTree, UnaOp};
+
+/// Binding strength of the operators, higher binds tighter.
+fn precedence<V>(expr_tree: &ExprTree<'_, V>) -> u8 {
+    match expr_tree {
+        ExprTree::And { .. } => 0,
+        ExprTree::BinOp {
+            op: BinOp::Add | BinOp::Sub,
+            ..
+        } => 1,
+        ExprTree::BinOp {
+            op: BinOp::Mul | BinOp::Div,
+            ..
+        } => 2,
+        ExprTree::UnaOp { .. } => 3,
+        ExprTree::Annotated { expr, .. } => precedence(expr.0),
+        ExprTree::Literal { .. } | ExprTree::Constant { .. } | ExprTree::BigConstant { .. } => 4,
+    }
+}
+
+/// Writes `operand`, in parentheses if it binds looser than `min_precedence`.
+fn operand<V: Display>(
+    f: &mut Formatter<'_>,
+    operand: &CompoundExpr<'_, V>,
+    min_precedence: u8,
+) -> fmt::Result {
+    if precedence(operand.0) < min_precedence {
+        write!(f, "({})", operand.0)
+    } else {
+        write!(f, "{}", operand.0)
+    }
+}
+
+impl<'a, V: Display> Display for ExprTree<'a, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let own = precedence(self);
+        match self {
+            ExprTree::BinOp { op, left, right } => {
+                let symbol = match op {
+                    BinOp::Add => "+",
+                    BinOp::Sub => "-",
+                    BinOp::Mul => "*",
+                    BinOp::Div => "/",
+                };
+                // The operators are left associative, so only the right operand
+                // needs parentheses at the same precedence, and only if the
+                // operator does not commute.
+                let right_precedence = match op {
+                    BinOp::Sub | BinOp::Div => own + 1,
+                    BinOp::Add | BinOp::Mul => own,
+                };
+                operand(f, left, own)?;
+                write!(f, " {symbol} ")?;
+                operand(f, right, right_precedence)
+            }
+            ExprTree::UnaOp {
+                op: UnaOp::Neg,
+                expr,
+            } => {
+                write!(f, "-")?;
+                operand(f, expr, own)
+            }
+            ExprTree::And { left, right } => {
+                operand(f, left, own)?;
+                write!(f, " & ")?;
+                operand(f, right, own)
+            }
+            ExprTree::Literal { value } => write!(f, "{value}"),
+            ExprTree::Constant { value } => write!(f, "{value}"),
+            ExprTree::BigConstant { value } => {
+                // In hex, so that the limbs can be written one after the other.
+                let mut limbs = value.iter().rev().skip_while(|&&limb| limb == 0);
+                write!(f, "0x{:x}", limbs.next().unwrap_or(&0))?;
+                limbs.try_for_each(|limb| write!(f, "{limb:016x}"))
+            }
+            ExprTree::Annotated { expr, .. } => write!(f, "{}", expr.0),
+        }
+    }
+}
+
+impl<'a, V: Display> Display for Expr<'a, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Basic { value } => write!(f, "{value}"),
+            Expr::Compound { expr, .. } => write!(f, "{}", expr.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Expr, ExprBuilder};
+
+    #[test]
+    fn renders_with_minimal_parentheses() {
+        let eb = ExprBuilder::default();
+        let [op_add, dst, rs1, rs2] =
+            ["lv.op_add", "lv.dst", "lv.rs1", "lv.rs2"].map(|v| eb.lit(v));
+
+        assert_eq!(
+            (op_add * (dst - rs1 - rs2)).to_string(),
+            "lv.op_add * (lv.dst - lv.rs1 - lv.rs2)"
+        );
+        assert_eq!(
+            (dst - (rs1 - rs2)).to_string(),
+            "lv.dst - (lv.rs1 - lv.rs2)"
+        );
+        assert_eq!((dst + (rs1 + rs2)).to_string(), "lv.dst + lv.rs1 + lv.rs2");
+        assert_eq!(
+            (dst * rs1 + rs2 * 2).to_string(),
+            "lv.dst * lv.rs1 + lv.rs2 * 2"
+        );
+        assert_eq!(
+            (dst / (rs1 * rs2)).to_string(),
+            "lv.dst / (lv.rs1 * lv.rs2)"
+        );
+        assert_eq!((-(dst + rs1)).to_string(), "-(lv.dst + lv.rs1)");
+        assert_eq!(
+            ((dst & rs1) * rs2).to_string(),
+            "(lv.dst & lv.rs1) * lv.rs2"
+        );
+        assert_eq!(Expr::<'_, &str>::from(3).to_string(), "3");
+    }
+}
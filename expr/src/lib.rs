@@ -20,8 +20,20 @@
 //!   - [`Expr`] * [`Expr`]
 //!   - [`i64`] * [`Expr`]
 //!   - [`Expr`] * [`i64`]
+//! - [`Div`]
+//!   - [`Expr`] / [`Expr`]
+//!   - [`i64`] / [`Expr`]
+//!   - [`Expr`] / [`i64`]
 //! - [`Neg`]
 //!   - (- [`Expr`])
+//! - [`BitOr`](core::ops::BitOr), for binary values
+//!   - [`Expr`] | [`Expr`]
+//!   - [`i64`] | [`Expr`]
+//!   - [`Expr`] | [`i64`]
+//! - [`BitAnd`](core::ops::BitAnd), for constraints
+//!   - [`Expr`] & [`Expr`]
+//!   - [`i64`] & [`Expr`]
+//!   - [`Expr`] & [`i64`]
 //!
 //! Then, the current API for Expr builder was pretty much the ability to inject
 //! `V` and i64 into Exprs
@@ -51,21 +63,39 @@
 //! Finally, there is the case of Evaluators, because they do form a contract
 //! with internal ExprTree, as they provide the semantics for the operations.
 //!
+//! To see what an expression does, [`Display`](core::fmt::Display) renders it
+//! as algebra, see [`display`].
+//!
 //! # TODO
 //!
-//! - [ ] TODO: support `|` via multiplication.
-//! - [ ] TODO support `&` via distributive law, and integration with constraint
-//! builder. (a & b) | c == (a | c) & (b | c) == [(a | c), (b | c)] where [..]
-//! means split into multiple constraints.
-
+//! - [x] support `|` via multiplication.
+//! - [x] support `&` via distributive law, and integration with constraint
+//!   builder. (a & b) * c == (a * c) & (b * c) == [(a * c), (b * c)] where [..]
+//!   means split into multiple constraints, see [`Expr::conjuncts`].
+
+pub mod batch;
+#[cfg(feature = "circuit-builder")]
+pub mod circuit;
+pub mod constraints;
+pub mod degree;
+pub mod display;
 pub mod ops;
+pub mod owned;
+#[cfg(any(feature = "test", test))]
+pub mod test_utils;
 
-use core::ops::{Add, Mul, Neg, Sub};
-use std::collections::HashMap;
+#[cfg(debug_assertions)]
+use core::cell::RefCell;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+use core::panic::Location;
+use std::collections::{HashMap, HashSet};
 
 use bumpalo::Bump;
+use serde::{Deserialize, Serialize};
 use starky::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
 
+pub use crate::constraints::ConstraintCollector;
+
 /// Contains a reference to [`ExprTree`] that is managed by [`ExprBuilder`].
 #[derive(Clone, Copy, Debug)]
 pub enum Expr<'a, V> {
@@ -91,8 +121,17 @@ impl<'a, V> Default for Expr<'a, V> {
 impl<'a, V> Expr<'a, V> {
     fn bin_op(op: BinOp, lhs: Expr<'a, V>, rhs: Expr<'a, V>) -> Expr<'a, V> {
         match (lhs, rhs) {
-            (Expr::Basic { value: left }, Expr::Basic { value: right }) =>
-                Expr::from(PureEvaluator::default().bin_op(op, left, right)),
+            (Expr::Basic { value: left }, Expr::Basic { value: right }) => {
+                // Constants are folded over the integers, which only agrees with
+                // division in the field when the division is exact.
+                if op == BinOp::Div {
+                    assert!(
+                        right != 0 && left % right == 0,
+                        "cannot fold inexact division of constants {left} / {right}"
+                    );
+                }
+                Expr::from(PureEvaluator::default().bin_op(op, left, right))
+            }
             (left @ Expr::Compound { builder, .. }, right)
             | (left, right @ Expr::Compound { builder, .. }) => builder.wrap(builder.bin_op(
                 op,
@@ -111,10 +150,117 @@ impl<'a, V> Expr<'a, V> {
 }
 
 impl<'a, V> Expr<'a, V> {
+    /// The constraint that `self` is binary.
+    ///
+    /// Also flags `self` as binary, for the debug checks of `|`.
     pub fn is_binary(self) -> Self
     where
         V: Copy, {
-        self * (1 - self)
+        self.assume_binary() * (1 - self)
+    }
+
+    /// Flags `self` as binary, for the debug checks of `|`, without
+    /// constraining it.  Use this for values that are binary by construction.
+    #[must_use]
+    pub fn assume_binary(self) -> Self {
+        if let Expr::Compound { ref expr, builder } = self {
+            builder.flag_binary(expr);
+        }
+        self
+    }
+
+    /// The conjunction of the constraints `lhs` and `rhs`.
+    ///
+    /// Conjunctions of constants are folded, to a constraint that is
+    /// satisfied exactly when both are.
+    fn and(lhs: Self, rhs: Self) -> Self {
+        match (lhs, rhs) {
+            (Expr::Basic { value: left }, Expr::Basic { value: right }) =>
+                Expr::from(i64::from(left != 0 || right != 0)),
+            (left @ Expr::Compound { builder, .. }, right)
+            | (left, right @ Expr::Compound { builder, .. }) =>
+                builder.wrap(builder.intern(ExprTree::And {
+                    left: builder.ensure_interned(left),
+                    right: builder.ensure_interned(right),
+                })),
+        }
+    }
+
+    /// Splits a conjunction of constraints, built with `&`, into the
+    /// constraints it is made of.
+    ///
+    /// Multiplication and negation distribute over `&`, eg `(a & b) * c`
+    /// splits into `a * c` and `b * c`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a conjunction is an operand of any other operation.
+    #[must_use]
+    pub fn conjuncts(self) -> Vec<Self>
+    where
+        V: Copy, {
+        match self {
+            Expr::Basic { .. } => vec![self],
+            Expr::Compound { expr, builder } => builder
+                .conjuncts(expr, &mut HashMap::new())
+                .into_iter()
+                .map(|expr| builder.wrap(expr))
+                .collect(),
+        }
+    }
+
+    /// Boolean OR of binary values, as `a + b - a * b`.
+    ///
+    /// In debug builds, panics if either operand is not flagged as binary.
+    #[track_caller]
+    fn bit_or(lhs: Self, rhs: Self) -> Self
+    where
+        V: Copy, {
+        debug_assert!(
+            lhs.is_flagged_binary() && rhs.is_flagged_binary(),
+            "operands of `|` have to be flagged as binary, see `Expr::is_binary`"
+        );
+        (lhs + rhs - lhs * rhs).assume_binary()
+    }
+
+    fn is_flagged_binary(&self) -> bool {
+        match *self {
+            Expr::Basic { value } => value == 0 || value == 1,
+            Expr::Compound { ref expr, builder } => builder.is_flagged_binary(expr),
+        }
+    }
+
+    /// Raise to the power of `exp` by repeated squaring, eg for the s-boxes of
+    /// Poseidon.
+    ///
+    /// The squares are shared nodes, so evaluating with [`Cached`] computes
+    /// each of them only once.
+    #[must_use]
+    pub fn pow(self, mut exp: u64) -> Self
+    where
+        V: Copy, {
+        let mut square = self;
+        let mut acc: Option<Self> = None;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                acc = Some(acc.map_or(square, |acc| acc * square));
+            }
+            exp >>= 1;
+            if exp > 0 {
+                square = square * square;
+            }
+        }
+        acc.unwrap_or(Expr::from(1))
+    }
+
+    /// `if_true` where `cond` is 1, and `if_false` where it is 0, as
+    /// `cond * if_true + (1 - cond) * if_false`.
+    ///
+    /// `cond` has to be constrained to be binary, eg with [`Expr::is_binary`].
+    pub fn select(cond: Self, if_true: Self, if_false: Self) -> Self
+    where
+        V: Copy, {
+        cond * if_true + (1 - cond) * if_false
     }
 
     /// Reduce a sequence of terms into a single term using powers of `base`.
@@ -127,6 +273,75 @@ impl<'a, V> Expr<'a, V> {
             .rev()
             .fold(Expr::from(0), |acc, term| acc * base + term)
     }
+
+    /// Labels `self` with `label` and the source location of the caller, so
+    /// that a failing constraint can be traced back to the code that built
+    /// it.  See also [`constraint!`].
+    ///
+    /// Constants are returned as they are, as they have no node to label.
+    #[must_use]
+    #[track_caller]
+    pub fn annotate(self, label: &'static str) -> Self {
+        match self {
+            Expr::Basic { .. } => self,
+            Expr::Compound { expr, builder } => builder.wrap(builder.intern(ExprTree::Annotated {
+                label,
+                location: Location::caller(),
+                expr,
+            })),
+        }
+    }
+
+    /// The labels and source locations of all the annotated subexpressions
+    /// of `self`, outermost first.
+    #[must_use]
+    pub fn annotations(self) -> Vec<(&'static str, &'static Location<'static>)> {
+        fn go<V>(
+            expr: &CompoundExpr<'_, V>,
+            seen: &mut HashSet<usize>,
+            annotations: &mut Vec<(&'static str, &'static Location<'static>)>,
+        ) {
+            if !seen.insert(expr.address()) {
+                return;
+            }
+            match expr.0 {
+                ExprTree::BinOp { left, right, .. } | ExprTree::And { left, right } => {
+                    go(left, seen, annotations);
+                    go(right, seen, annotations);
+                }
+                ExprTree::UnaOp { expr, .. } => go(expr, seen, annotations),
+                ExprTree::Annotated {
+                    label,
+                    location,
+                    expr,
+                } => {
+                    annotations.push((label, location));
+                    go(expr, seen, annotations);
+                }
+                ExprTree::Literal { .. }
+                | ExprTree::Constant { .. }
+                | ExprTree::BigConstant { .. } => {}
+            }
+        }
+        let mut annotations = vec![];
+        if let Expr::Compound { ref expr, .. } = self {
+            go(expr, &mut HashSet::new(), &mut annotations);
+        }
+        annotations
+    }
+}
+
+/// Labels a constraint with the source location it is built at, see
+/// [`Expr::annotate`].
+///
+/// ```ignore
+/// constraints.always(constraint!("is_running is binary", lv.is_running.is_binary()));
+/// ```
+#[macro_export]
+macro_rules! constraint {
+    ($label:expr, $expr:expr $(,)?) => {
+        $crate::Expr::annotate($expr, $label)
+    };
 }
 
 /// Expression Builder.  Contains a [`Bump`] memory arena that will allocate and
@@ -134,6 +349,9 @@ impl<'a, V> Expr<'a, V> {
 #[derive(Debug, Default)]
 pub struct ExprBuilder {
     bump: Bump,
+    /// Addresses of the [`ExprTree`]s flagged as binary.
+    #[cfg(debug_assertions)]
+    binary: RefCell<HashSet<usize>>,
 }
 
 impl ExprBuilder {
@@ -150,6 +368,80 @@ impl ExprBuilder {
         }
     }
 
+    /// See [`Expr::conjuncts`].  `memo` holds the conjuncts of the nodes seen
+    /// so far, as shared nodes would otherwise be split over and over.
+    fn conjuncts<'a, V: Copy>(
+        &'a self,
+        expr: CompoundExpr<'a, V>,
+        memo: &mut HashMap<usize, Vec<CompoundExpr<'a, V>>>,
+    ) -> Vec<CompoundExpr<'a, V>> {
+        if let Some(conjuncts) = memo.get(&expr.address()) {
+            return conjuncts.clone();
+        }
+        let conjuncts = match *expr.0 {
+            ExprTree::And { left, right } =>
+                [self.conjuncts(left, memo), self.conjuncts(right, memo)].concat(),
+            ExprTree::BinOp { op, left, right } => {
+                let lefts = self.conjuncts(left, memo);
+                let rights = self.conjuncts(right, memo);
+                match (op, lefts.len(), rights.len()) {
+                    (_, 1, 1) => vec![expr],
+                    (BinOp::Mul, _, _) => lefts
+                        .iter()
+                        .flat_map(|&left| rights.iter().map(move |&right| (left, right)))
+                        .map(|(left, right)| self.bin_op(BinOp::Mul, left, right))
+                        .collect(),
+                    _ => panic!("`&` only distributes over `*` and negation, not over {op:?}"),
+                }
+            }
+            ExprTree::UnaOp { op, expr: inner } => match self.conjuncts(inner, memo) {
+                inners if inners.len() == 1 => vec![expr],
+                inners => inners
+                    .into_iter()
+                    .map(|inner| self.una_op(op, inner))
+                    .collect(),
+            },
+            ExprTree::Annotated {
+                label,
+                location,
+                expr: inner,
+            } => match self.conjuncts(inner, memo) {
+                inners if inners.len() == 1 => vec![expr],
+                inners => inners
+                    .into_iter()
+                    .map(|inner| {
+                        self.intern(ExprTree::Annotated {
+                            label,
+                            location,
+                            expr: inner,
+                        })
+                    })
+                    .collect(),
+            },
+            ExprTree::Literal { .. } | ExprTree::Constant { .. } | ExprTree::BigConstant { .. } =>
+                vec![expr],
+        };
+        memo.insert(expr.address(), conjuncts.clone());
+        conjuncts
+    }
+
+    /// Flags `expr` as binary, in debug builds.
+    #[allow(unused_variables)]
+    fn flag_binary<V>(&self, expr: &CompoundExpr<'_, V>) {
+        #[cfg(debug_assertions)]
+        self.binary.borrow_mut().insert(expr.address());
+    }
+
+    /// Whether `expr` is flagged as binary.  Always true in release builds,
+    /// which do not keep track.
+    #[allow(unused_variables)]
+    fn is_flagged_binary<V>(&self, expr: &CompoundExpr<'_, V>) -> bool {
+        #[cfg(debug_assertions)]
+        return self.binary.borrow().contains(&expr.address());
+        #[cfg(not(debug_assertions))]
+        true
+    }
+
     /// Wrap [`ExprTree`] reference with an [`Expr`] wrapper
     fn wrap<'a, V>(&'a self, expr: CompoundExpr<'a, V>) -> Expr<'a, V> {
         Expr::Compound {
@@ -187,9 +479,60 @@ impl ExprBuilder {
     /// Create a `Constant` expression
     pub fn constant<V>(&self, value: i64) -> Expr<'_, V> { self.wrap(self.constant_tree(value)) }
 
+    /// Create a constant from little endian `u64` limbs, eg a constant related
+    /// to the order of the field, which does not fit in an `i64`.
+    ///
+    /// Constants that fit are created with [`ExprBuilder::constant`].
+    pub fn big_constant<V>(&self, value: [u64; 4]) -> Expr<'_, V> {
+        if let [low, 0, 0, 0] = value {
+            if let Ok(low) = i64::try_from(low) {
+                return self.constant(low);
+            }
+        }
+        self.wrap(self.intern(ExprTree::BigConstant { value }))
+    }
+
+    /// The sum of `terms`, as a balanced tree, see [`Sum`](core::iter::Sum)
+    /// for [`Expr`].
+    pub fn sum<'a, V>(&'a self, terms: impl IntoIterator<Item = Expr<'a, V>>) -> Expr<'a, V> {
+        terms.into_iter().sum()
+    }
+
+    /// The product of `terms`, as a balanced tree, see
+    /// [`Product`](core::iter::Product) for [`Expr`].
+    pub fn product<'a, V>(&'a self, terms: impl IntoIterator<Item = Expr<'a, V>>) -> Expr<'a, V> {
+        terms.into_iter().product()
+    }
+
     /// Create a `Literal` expression
     pub fn lit<V>(&self, value: V) -> Expr<'_, V> { self.wrap(self.lit_tree(value)) }
 
+    /// Free all expressions built so far, keeping the arena's memory to build
+    /// new ones.
+    ///
+    /// Taking `&mut self` ensures no expressions of this builder are alive.
+    pub fn reset(&mut self) {
+        self.bump.reset();
+        // The addresses will be reused.
+        #[cfg(debug_assertions)]
+        self.binary.get_mut().clear();
+    }
+
+    /// Build and use expressions in `f`, then [`reset`](Self::reset) the
+    /// builder, eg to reuse one builder across evaluation frames.
+    pub fn with_arena<R>(&mut self, f: impl FnOnce(&ExprBuilder) -> R) -> R {
+        let result = f(self);
+        self.reset();
+        result
+    }
+
+    /// Simplify `expr`, see [`Simplifier`].
+    pub fn simplify<'a, V>(&'a self, expr: Expr<'a, V>) -> Expr<'a, V>
+    where
+        V: Copy, {
+        Simplifier::default().simplify(expr)
+    }
+
     /// Convert from untyped `StarkFrame` to a typed representation.
     ///
     /// We ignore public inputs for now, and leave them as is.
@@ -236,15 +579,23 @@ pub struct StarkFrameTyped<Row, PublicInputs> {
 }
 
 /// Enum for binary operations
-#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum BinOp {
     Add,
     Sub,
     Mul,
+    /// Multiplication by the inverse of the right operand.
+    ///
+    /// Division by zero is an error: evaluating to values panics, and
+    /// evaluating to a recursive circuit makes the circuit unsatisfiable.
+    ///
+    /// Note that constraints must stay low-degree polynomials, so in
+    /// constraints only divide by constants.
+    Div,
 }
 
 /// Unary operations
-#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum UnaOp {
     Neg,
 }
@@ -252,6 +603,10 @@ pub enum UnaOp {
 #[derive(Debug, Clone, Copy)]
 pub struct CompoundExpr<'a, V>(&'a ExprTree<'a, V>);
 
+impl<'a, V> CompoundExpr<'a, V> {
+    fn address(&self) -> usize { core::ptr::from_ref(self.0) as usize }
+}
+
 impl<'a, V> From<&'a ExprTree<'a, V>> for CompoundExpr<'a, V> {
     fn from(value: &'a ExprTree<'a, V>) -> Self { CompoundExpr(value) }
 }
@@ -278,6 +633,24 @@ pub enum ExprTree<'a, V> {
     Constant {
         value: i64,
     },
+    /// A constant beyond `i64`, as little endian `u64` limbs, see
+    /// [`ExprBuilder::big_constant`].
+    BigConstant {
+        value: [u64; 4],
+    },
+    /// A conjunction of constraints, which has no value of its own, see
+    /// [`Expr::conjuncts`].
+    And {
+        left: CompoundExpr<'a, V>,
+        right: CompoundExpr<'a, V>,
+    },
+    /// A labelled subexpression, with the same value as `expr`, see
+    /// [`Expr::annotate`].
+    Annotated {
+        label: &'static str,
+        location: &'static Location<'static>,
+        expr: CompoundExpr<'a, V>,
+    },
 }
 
 /// Evaluator that can evaluate [`Expr`] to `V`.
@@ -287,6 +660,31 @@ where
     fn bin_op(&mut self, op: BinOp, left: V, right: V) -> V;
     fn una_op(&mut self, op: UnaOp, expr: V) -> V;
     fn constant(&mut self, value: i64) -> V;
+    /// A constant given as little endian `u64` limbs.
+    ///
+    /// By default, it is put together from 32 bit pieces, so override this
+    /// where `V` can represent it directly.
+    fn big_constant(&mut self, value: [u64; 4]) -> V {
+        let pieces = value
+            .iter()
+            .rev()
+            .flat_map(|&limb| [limb >> 32, limb & 0xffff_ffff])
+            .skip_while(|&piece| piece == 0)
+            .map(|piece| i64::try_from(piece).expect("pieces have 32 bits"));
+        let mut acc: Option<V> = None;
+        for piece in pieces {
+            let piece = self.constant(piece);
+            acc = Some(match acc {
+                None => piece,
+                Some(acc) => {
+                    let shift = self.constant(1 << 32);
+                    let shifted = self.bin_op(BinOp::Mul, acc, shift);
+                    self.bin_op(BinOp::Add, shifted, piece)
+                }
+            });
+        }
+        acc.unwrap_or_else(|| self.constant(0))
+    }
     fn expr_tree(&mut self, expr_tree: &'a ExprTree<'a, V>) -> V {
         match expr_tree {
             ExprTree::BinOp { op, left, right } => {
@@ -300,6 +698,10 @@ where
             }
             ExprTree::Literal { value } => *value,
             ExprTree::Constant { value } => self.constant(*value),
+            ExprTree::BigConstant { value } => self.big_constant(*value),
+            ExprTree::And { .. } =>
+                panic!("split conjunctions with `Expr::conjuncts` before evaluating them"),
+            ExprTree::Annotated { expr, .. } => self.compound_expr(*expr),
         }
     }
     fn compound_expr(&mut self, expr: CompoundExpr<'a, V>) -> V { self.expr_tree(expr.0) }
@@ -312,17 +714,24 @@ where
 }
 
 /// Default evaluator for pure values.
+#[derive(Clone, Copy)]
 pub struct PureEvaluator<P>(pub fn(i64) -> P);
 
 impl<'a, V> Evaluator<'a, V> for PureEvaluator<V>
 where
-    V: Copy + Add<Output = V> + Neg<Output = V> + Mul<Output = V> + Sub<Output = V>,
+    V: Copy
+        + Add<Output = V>
+        + Neg<Output = V>
+        + Mul<Output = V>
+        + Sub<Output = V>
+        + Div<Output = V>,
 {
     fn bin_op(&mut self, op: BinOp, left: V, right: V) -> V {
         match op {
             BinOp::Add => left + right,
             BinOp::Sub => left - right,
             BinOp::Mul => left * right,
+            BinOp::Div => left / right,
         }
     }
 
@@ -337,7 +746,13 @@ where
 
 impl<V> Default for PureEvaluator<V>
 where
-    V: Copy + Add<Output = V> + Neg<Output = V> + Mul<Output = V> + Sub<Output = V> + From<i64>,
+    V: Copy
+        + Add<Output = V>
+        + Neg<Output = V>
+        + Mul<Output = V>
+        + Sub<Output = V>
+        + Div<Output = V>
+        + From<i64>,
 {
     fn default() -> Self { Self(V::from) }
 }
@@ -381,6 +796,8 @@ where
             .or_insert_with(|| self.evaluator.constant(k))
     }
 
+    fn big_constant(&mut self, value: [u64; 4]) -> V { self.evaluator.big_constant(value) }
+
     // NOTE: We disable clippy warning about map entry becasue it is impossible
     // to implement the following function using entry(k).or_insert_with, due to
     // the closue argument to or_insert_with needing to mutably borrow self for
@@ -400,6 +817,296 @@ where
     }
 }
 
+/// Algebraic simplification of expressions.
+///
+/// Folds constant subtrees and removes operations that do nothing, like `x *
+/// 1`, `x + 0` and `--x`.  Only the nodes that change are allocated anew, in
+/// the builder of the expression.
+///
+/// Division by zero is kept, so that evaluating still reports it.  For the same
+/// reason, multiplying by zero is only folded if the other factor does not
+/// divide.
+///
+/// Reuse one simplifier for expressions that share nodes, so that their
+/// simplifications share nodes as well.
+pub struct Simplifier<'a, V> {
+    memo: HashMap<*const ExprTree<'a, V>, Expr<'a, V>>,
+    divides: HashMap<*const ExprTree<'a, V>, bool>,
+}
+
+impl<'a, V> Default for Simplifier<'a, V> {
+    fn default() -> Self {
+        Simplifier {
+            memo: HashMap::default(),
+            divides: HashMap::default(),
+        }
+    }
+}
+
+impl<'a, V> Simplifier<'a, V>
+where
+    V: Copy,
+{
+    pub fn simplify(&mut self, expr: Expr<'a, V>) -> Expr<'a, V> {
+        match expr {
+            Expr::Basic { .. } => expr,
+            Expr::Compound { expr, builder } => self.compound_expr(expr, builder),
+        }
+    }
+
+    fn compound_expr(
+        &mut self,
+        expr: CompoundExpr<'a, V>,
+        builder: &'a ExprBuilder,
+    ) -> Expr<'a, V> {
+        let k = expr.0 as *const ExprTree<'_, V>;
+        if let Some(&simplified) = self.memo.get(&k) {
+            return simplified;
+        }
+        let simplified = self.expr_tree(expr, builder);
+        self.memo.insert(k, simplified);
+        simplified
+    }
+
+    fn expr_tree(&mut self, expr: CompoundExpr<'a, V>, builder: &'a ExprBuilder) -> Expr<'a, V> {
+        let unchanged = |left: Expr<'a, V>, right: Expr<'a, V>, l, r| {
+            matches!((left, right), (
+                Expr::Compound { expr: left, .. },
+                Expr::Compound { expr: right, .. },
+            ) if core::ptr::eq(left.0, l) && core::ptr::eq(right.0, r))
+        };
+        match *expr.0 {
+            ExprTree::Literal { .. } | ExprTree::BigConstant { .. } => builder.wrap(expr),
+            ExprTree::Constant { value } => Expr::from(value),
+            ExprTree::BinOp { op, left, right } => {
+                let l = self.compound_expr(left, builder);
+                let r = self.compound_expr(right, builder);
+                match (op, l, r) {
+                    (BinOp::Add, Expr::Basic { value: 0 }, x)
+                    | (BinOp::Add | BinOp::Sub, x, Expr::Basic { value: 0 })
+                    | (BinOp::Mul | BinOp::Div, x, Expr::Basic { value: 1 })
+                    | (BinOp::Mul, Expr::Basic { value: 1 }, x) => x,
+                    (BinOp::Sub, Expr::Basic { value: 0 }, x) => self.neg(x, builder),
+                    (BinOp::Mul, Expr::Basic { value: 0 }, x)
+                    | (BinOp::Mul, x, Expr::Basic { value: 0 })
+                        if !self.divides(x) =>
+                        Expr::from(0),
+                    (BinOp::Mul, Expr::Basic { value: -1 }, x)
+                    | (BinOp::Mul | BinOp::Div, x, Expr::Basic { value: -1 }) =>
+                        self.neg(x, builder),
+                    (BinOp::Div, Expr::Basic { value: left }, Expr::Basic { value: right })
+                        if right == 0 || left % right != 0 =>
+                        builder.wrap(builder.bin_op(
+                            op,
+                            builder.constant_tree(left),
+                            builder.constant_tree(right),
+                        )),
+                    _ if unchanged(l, r, left.0, right.0) => builder.wrap(expr),
+                    _ => Expr::bin_op(op, l, r),
+                }
+            }
+            ExprTree::UnaOp {
+                op: UnaOp::Neg,
+                expr: inner,
+            } => match self.compound_expr(inner, builder) {
+                Expr::Compound { expr: x, .. } if core::ptr::eq(x.0, inner.0) => builder.wrap(expr),
+                x => self.neg(x, builder),
+            },
+            ExprTree::And { left, right } => {
+                let l = self.compound_expr(left, builder);
+                let r = self.compound_expr(right, builder);
+                match (l, r) {
+                    // A zero constraint always holds.
+                    (Expr::Basic { value: 0 }, x) | (x, Expr::Basic { value: 0 }) => x,
+                    _ if unchanged(l, r, left.0, right.0) => builder.wrap(expr),
+                    _ => l & r,
+                }
+            }
+            ExprTree::Annotated {
+                label,
+                location,
+                expr: inner,
+            } => match self.compound_expr(inner, builder) {
+                Expr::Compound { expr: x, .. } if core::ptr::eq(x.0, inner.0) => builder.wrap(expr),
+                Expr::Compound { expr: x, .. } =>
+                    builder.wrap(builder.intern(ExprTree::Annotated {
+                        label,
+                        location,
+                        expr: x,
+                    })),
+                // Constants have no node to label.
+                x @ Expr::Basic { .. } => x,
+            },
+        }
+    }
+
+    /// Whether evaluating `x` divides, and so might divide by zero.
+    fn divides(&mut self, x: Expr<'a, V>) -> bool {
+        match x {
+            Expr::Basic { .. } => false,
+            Expr::Compound { expr, .. } => self.tree_divides(expr),
+        }
+    }
+
+    fn tree_divides(&mut self, expr: CompoundExpr<'a, V>) -> bool {
+        let k = expr.0 as *const ExprTree<'_, V>;
+        if let Some(&divides) = self.divides.get(&k) {
+            return divides;
+        }
+        let divides = match *expr.0 {
+            ExprTree::BinOp { op, left, right } =>
+                op == BinOp::Div || self.tree_divides(left) || self.tree_divides(right),
+            ExprTree::UnaOp { expr, .. } | ExprTree::Annotated { expr, .. } =>
+                self.tree_divides(expr),
+            ExprTree::And { left, right } => self.tree_divides(left) || self.tree_divides(right),
+            ExprTree::Literal { .. } | ExprTree::Constant { .. } | ExprTree::BigConstant { .. } =>
+                false,
+        };
+        self.divides.insert(k, divides);
+        divides
+    }
+
+    /// Negates `x`, cancelling a double negation.
+    fn neg(&mut self, x: Expr<'a, V>, builder: &'a ExprBuilder) -> Expr<'a, V> {
+        match x {
+            Expr::Compound { expr, .. } => match *expr.0 {
+                ExprTree::UnaOp {
+                    op: UnaOp::Neg,
+                    expr: inner,
+                } => builder.wrap(inner),
+                _ => -x,
+            },
+            Expr::Basic { .. } => -x,
+        }
+    }
+}
+
+/// The structure of an [`ExprTree`] node, with its children by address.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum NodeKey {
+    BinOp(BinOp, usize, usize),
+    UnaOp(UnaOp, usize),
+    Constant(i64),
+    BigConstant([u64; 4]),
+    And(usize, usize),
+    Annotated(&'static str, usize, usize),
+}
+
+impl NodeKey {
+    /// Literals are not compared, as `V` need not be hashable.
+    fn of<V>(expr_tree: &ExprTree<'_, V>) -> Option<Self> {
+        match expr_tree {
+            ExprTree::BinOp { op, left, right } =>
+                Some(NodeKey::BinOp(*op, left.address(), right.address())),
+            ExprTree::UnaOp { op, expr } => Some(NodeKey::UnaOp(*op, expr.address())),
+            ExprTree::Constant { value } => Some(NodeKey::Constant(*value)),
+            ExprTree::BigConstant { value } => Some(NodeKey::BigConstant(*value)),
+            ExprTree::And { left, right } => Some(NodeKey::And(left.address(), right.address())),
+            ExprTree::Annotated {
+                label,
+                location,
+                expr,
+            } => Some(NodeKey::Annotated(
+                label,
+                core::ptr::from_ref(*location) as usize,
+                expr.address(),
+            )),
+            ExprTree::Literal { .. } => None,
+        }
+    }
+}
+
+/// Hash-consing of expressions: structurally equal subexpressions are
+/// replaced by a single node, so that [`Cached`] evaluates them once, even if
+/// they were built separately.
+///
+/// Literals are only shared if they already are, ie built by one call to
+/// [`ExprBuilder::lit`].
+///
+/// Reuse one instance for all the expressions that are evaluated by the same
+/// [`Cached`] evaluator.
+pub struct HashConsing<'a, V> {
+    /// The deduplicated node for each node seen so far.
+    seen: HashMap<*const ExprTree<'a, V>, CompoundExpr<'a, V>>,
+    /// The deduplicated nodes by structure.
+    nodes: HashMap<NodeKey, CompoundExpr<'a, V>>,
+}
+
+impl<'a, V> Default for HashConsing<'a, V> {
+    fn default() -> Self {
+        HashConsing {
+            seen: HashMap::default(),
+            nodes: HashMap::default(),
+        }
+    }
+}
+
+impl<'a, V> HashConsing<'a, V>
+where
+    V: Copy,
+{
+    pub fn hash_cons(&mut self, expr: Expr<'a, V>) -> Expr<'a, V> {
+        match expr {
+            Expr::Basic { .. } => expr,
+            Expr::Compound { expr, builder } => builder.wrap(self.compound_expr(expr, builder)),
+        }
+    }
+
+    fn compound_expr(
+        &mut self,
+        expr: CompoundExpr<'a, V>,
+        builder: &'a ExprBuilder,
+    ) -> CompoundExpr<'a, V> {
+        let k = expr.0 as *const ExprTree<'_, V>;
+        if let Some(&deduplicated) = self.seen.get(&k) {
+            return deduplicated;
+        }
+        let expr_tree = match *expr.0 {
+            ExprTree::BinOp { op, left, right } => Some(ExprTree::BinOp {
+                op,
+                left: self.compound_expr(left, builder),
+                right: self.compound_expr(right, builder),
+            }),
+            ExprTree::UnaOp { op, expr } => Some(ExprTree::UnaOp {
+                op,
+                expr: self.compound_expr(expr, builder),
+            }),
+            ExprTree::Constant { value } => Some(ExprTree::Constant { value }),
+            ExprTree::BigConstant { value } => Some(ExprTree::BigConstant { value }),
+            ExprTree::And { left, right } => Some(ExprTree::And {
+                left: self.compound_expr(left, builder),
+                right: self.compound_expr(right, builder),
+            }),
+            ExprTree::Annotated {
+                label,
+                location,
+                expr,
+            } => Some(ExprTree::Annotated {
+                label,
+                location,
+                expr: self.compound_expr(expr, builder),
+            }),
+            ExprTree::Literal { .. } => None,
+        };
+        let deduplicated = match expr_tree {
+            None => expr,
+            Some(expr_tree) => {
+                let key = NodeKey::of(&expr_tree).expect("only literals have no key");
+                let unchanged = NodeKey::of(expr.0).as_ref() == Some(&key);
+                *self.nodes.entry(key).or_insert_with(|| {
+                    if unchanged {
+                        expr
+                    } else {
+                        builder.intern(expr_tree)
+                    }
+                })
+            }
+        };
+        self.seen.insert(k, deduplicated);
+        deduplicated
+    }
+}
+
 #[derive(Default)]
 pub struct Counting<E> {
     count: u64,
@@ -433,6 +1140,11 @@ where
         self.inc();
         self.evaluator.constant(value)
     }
+
+    fn big_constant(&mut self, value: [u64; 4]) -> V {
+        self.inc();
+        self.evaluator.big_constant(value)
+    }
 }
 
 #[cfg(test)]
@@ -451,6 +1163,352 @@ mod tests {
         assert_eq!(p.eval(a + b), 12);
         assert_eq!(p.eval(a - b), 2);
         assert_eq!(p.eval(a * b), 35);
+        assert_eq!(p.eval(a * b / b), 7);
+    }
+
+    #[test]
+    fn folds_exact_division_of_constants() {
+        let a: Expr<'_, i64> = Expr::from(35);
+
+        let mut p = PureEvaluator::default();
+
+        assert_eq!(p.eval(a / 5), 7);
+        assert_eq!(p.eval(-a / 7), -5);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot fold inexact division of constants 7 / 2")]
+    fn refuses_inexact_division_of_constants() {
+        let a: Expr<'_, i64> = Expr::from(7);
+        let _ = a / 2;
+    }
+
+    #[test]
+    fn bit_or_works() {
+        let expr = ExprBuilder::default();
+
+        let mut p = PureEvaluator::default();
+
+        for a in [0, 1] {
+            for b in [0, 1] {
+                let (x, y) = (expr.lit(a), expr.lit(b));
+                let _ = (x.is_binary(), y.is_binary());
+                assert_eq!(p.eval(x | y), a | b);
+                assert_eq!(p.eval(x | b), a | b);
+                assert_eq!(p.eval(a | y), a | b);
+                assert_eq!(p.eval(x | y | x), a | b);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "operands of `|` have to be flagged as binary")]
+    fn bit_or_checks_operands_are_binary() {
+        let expr = ExprBuilder::default();
+        let x = expr.lit(1i64);
+        let y = expr.lit(0i64).assume_binary();
+        let _ = x | y;
+    }
+
+    #[test]
+    fn conjuncts_distribute() {
+        let expr = ExprBuilder::default();
+
+        let a = expr.lit(2i64);
+        let b = expr.lit(3i64);
+        let c = expr.lit(5i64);
+
+        let mut p = PureEvaluator::default();
+        let mut eval = |e: Expr<'_, i64>| {
+            e.conjuncts()
+                .into_iter()
+                .map(|e| p.eval(e))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(eval(a + b), vec![5]);
+        assert_eq!(eval(a & b), vec![2, 3]);
+        assert_eq!(eval((a & b) * c), vec![10, 15]);
+        assert_eq!(eval(-(a & 7) * (b & c)), vec![-6, -10, -21, -35]);
+        assert_eq!(eval(Expr::from(0) & Expr::from(0)), vec![0]);
+        assert_eq!(eval(Expr::from(0) & Expr::from(2)), vec![1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "`&` only distributes over `*` and negation, not over Add")]
+    fn conjuncts_do_not_distribute_over_add() {
+        let expr = ExprBuilder::default();
+        let a = expr.lit(2i64);
+        let _ = ((a & a) + 1).conjuncts();
+    }
+
+    #[test]
+    fn conjuncts_of_shared_nodes() {
+        let eb = ExprBuilder::default();
+        let mut one = eb.lit(1i64);
+        // This should timeout without memoisation, like the evaluation in
+        // `avoids_exponential_blowup` without caching.
+        for _ in 0..64 {
+            one = one * one;
+        }
+
+        let mut p = Cached::from(PureEvaluator::default());
+        let conjuncts = (one & (one - 1)).conjuncts();
+        assert_eq!(
+            conjuncts.into_iter().map(|c| p.eval(c)).collect::<Vec<_>>(),
+            vec![1, 0]
+        );
+    }
+
+    #[test]
+    fn simplify_folds_and_removes_no_ops() {
+        let eb = ExprBuilder::default();
+        let x = eb.lit(5i64);
+        let (zero, one) = (eb.constant(0), eb.constant(1));
+
+        let mut c = Counting::<PureEvaluator<_>>::default();
+        let mut count = |e| {
+            c.reset();
+            let v = c.eval(e);
+            (v, c.count())
+        };
+
+        assert_eq!(count(eb.simplify((x + zero) * one)), (5, 0));
+        assert_eq!(count(eb.simplify(-(-(x * one)))), (5, 0));
+        assert_eq!(count(eb.simplify(x * (one - one))), (0, 1));
+        assert_eq!(count(eb.simplify(zero - x)), (-5, 1));
+        assert_eq!(count(eb.simplify(x * (eb.constant(2) + 3))), (25, 2));
+        assert_eq!(count(eb.simplify(x * x)), (25, 1));
+    }
+
+    #[test]
+    fn simplify_keeps_division_by_zero() {
+        let eb = ExprBuilder::default();
+        let x = eb.lit(5i64);
+        let zero = eb.constant(0);
+
+        let mut c = Counting::<PureEvaluator<_>>::default();
+        assert_eq!(c.eval(eb.simplify(x / (eb.constant(3) / 2))), 5);
+        assert!(matches!(eb.simplify(x / zero), Expr::Compound { .. }));
+        assert!(matches!(
+            eb.simplify(eb.constant(1) / zero),
+            Expr::Compound { .. }
+        ));
+        assert!(matches!(
+            eb.simplify(zero * (x / zero)),
+            Expr::Compound { .. }
+        ));
+        assert!(matches!(
+            eb.simplify((x / (x - 5)) * zero),
+            Expr::Compound { .. }
+        ));
+    }
+
+    #[test]
+    fn simplify_keeps_sharing() {
+        let eb = ExprBuilder::default();
+        let mut x = eb.lit(1i64);
+        for _ in 0..64 {
+            x = x * x + eb.constant(0);
+        }
+
+        let mut p = Cached::<i64, Counting<PureEvaluator<_>>>::default();
+        assert_eq!(p.eval(eb.simplify(x)), 1);
+        assert_eq!(p.evaluator.count(), 64);
+    }
+
+    #[test]
+    fn hash_consing_shares_equal_subexpressions() {
+        let eb = ExprBuilder::default();
+        let limbs = [eb.lit(1i64), eb.lit(2i64), eb.lit(3i64), eb.lit(4i64)];
+
+        // Built separately, so the two sums share no nodes.
+        let a = Expr::reduce_with_powers(limbs, 256);
+        let b = Expr::reduce_with_powers(limbs, 256);
+        let expected = PureEvaluator::default().eval(a) * 2;
+
+        // Each sum takes seven operations, and the constants 0 and 256 are
+        // cached by value.
+        let mut c = Cached::from(Counting::<PureEvaluator<_>>::default());
+        assert_eq!(c.eval(a + b), expected);
+        assert_eq!(c.evaluator.count(), 7 + 7 + 1 + 2);
+
+        let mut hc = HashConsing::default();
+        let mut c = Cached::from(Counting::<PureEvaluator<_>>::default());
+        assert_eq!(c.eval(hc.hash_cons(a + b)), expected);
+        assert_eq!(c.evaluator.count(), 7 + 1 + 2);
+    }
+
+    #[test]
+    fn hash_consing_across_expressions() {
+        let eb = ExprBuilder::default();
+        let x = eb.lit(3i64);
+
+        let mut hc = HashConsing::default();
+        let a = hc.hash_cons(x * x + 1);
+        let b = hc.hash_cons(x * x + 1);
+        let (Expr::Compound { expr: a, .. }, Expr::Compound { expr: b, .. }) = (a, b) else {
+            panic!("expected compound expressions")
+        };
+        assert!(core::ptr::eq(a.0, b.0));
+    }
+
+    #[test]
+    fn select() {
+        let eb = ExprBuilder::default();
+        let (t, f) = (eb.lit(7i64), eb.lit(11i64));
+
+        let mut p = PureEvaluator::default();
+        assert_eq!(p.eval(Expr::select(eb.lit(1), t, f)), 7);
+        assert_eq!(p.eval(Expr::select(eb.lit(0), t, f)), 11);
+        assert_eq!(p.eval(Expr::select(Expr::from(1), t, Expr::from(0))), 7);
+    }
+
+    #[test]
+    fn sum_and_product() {
+        let eb = ExprBuilder::default();
+        let mut p = PureEvaluator::default();
+
+        let terms = (1..=10).map(|i| eb.lit(i)).collect::<Vec<_>>();
+        assert_eq!(p.eval(eb.sum(terms.iter().copied())), 55);
+        assert_eq!(p.eval(terms.iter().product()), 3_628_800);
+        assert_eq!(p.eval(eb.sum::<i64>([])), 0);
+        assert_eq!(p.eval(eb.product::<i64>([])), 1);
+        assert_eq!(
+            terms[..4].iter().sum::<Expr<'_, i64>>().to_string(),
+            "1 + 2 + 3 + 4"
+        );
+    }
+
+    #[test]
+    fn sums_are_shallow() {
+        let eb = ExprBuilder::default();
+        let n = 1 << 20;
+        // A left leaning chain this long overflows the stack when evaluated.
+        let sum = eb.sum((0..n).map(|_| eb.lit(1i64)));
+        assert_eq!(PureEvaluator::default().eval(sum), n);
+    }
+
+    #[test]
+    fn big_constants() {
+        let eb = ExprBuilder::default();
+        let x = eb.lit(3i128);
+        let mut p = PureEvaluator::default();
+
+        let big = eb.big_constant([u64::MAX, 1, 0, 0]);
+        assert_eq!(p.eval(big * x), 3 * ((1 << 64) + i128::from(u64::MAX)));
+        assert_eq!(p.eval(eb.big_constant([0, 0, 0, 0])), 0);
+        assert!(matches!(
+            eb.big_constant::<i128>([5, 0, 0, 0]),
+            Expr::Compound {
+                expr: CompoundExpr(ExprTree::Constant { value: 5 }),
+                ..
+            }
+        ));
+
+        assert_eq!(
+            eb.big_constant::<i128>([0xffff_ffff_0000_0001, 0, 0, 0])
+                .to_string(),
+            "0xffffffff00000001"
+        );
+        assert_eq!(big.to_string(), "0x1ffffffffffffffff");
+        let owned = (big + x).to_owned_tree();
+        assert_eq!(
+            ExprBuilder::default()
+                .from_owned_tree(owned.clone())
+                .to_owned_tree(),
+            owned
+        );
+
+        let mut hc = HashConsing::default();
+        let (Expr::Compound { expr: a, .. }, Expr::Compound { expr: b, .. }) = (
+            hc.hash_cons(eb.big_constant::<i128>([0, 0, 1, 0])),
+            hc.hash_cons(eb.big_constant::<i128>([0, 0, 1, 0])),
+        ) else {
+            panic!("expected compound expressions")
+        };
+        assert!(core::ptr::eq(a.0, b.0));
+    }
+
+    #[test]
+    fn annotations_are_transparent() {
+        let eb = ExprBuilder::default();
+        let x = eb.lit(3i64);
+        let line = line!() + 1;
+        let labelled = crate::constraint!("square", x * x) - 1;
+
+        assert_eq!(PureEvaluator::default().eval(labelled), 8);
+        assert_eq!(labelled.to_string(), "3 * 3 - 1");
+        let annotations = labelled.annotations();
+        assert_eq!(annotations.len(), 1);
+        let (label, location) = annotations[0];
+        assert_eq!(label, "square");
+        assert_eq!((location.file(), location.line()), (file!(), line));
+        assert!(Expr::<'_, i64>::from(1)
+            .annotate("one")
+            .annotations()
+            .is_empty());
+    }
+
+    #[test]
+    fn annotations_survive_passes() {
+        let eb = ExprBuilder::default();
+        let (a, b) = (eb.lit(2i64), eb.lit(3i64));
+        let expr = ((a & b).annotate("both") * 1 + 0).annotate("outer");
+
+        let labels = |e: Expr<'_, i64>| {
+            e.annotations()
+                .into_iter()
+                .map(|(label, _)| label)
+                .collect::<Vec<_>>()
+        };
+        let simplified = eb.simplify((a * 1).annotate("a") + 0);
+        assert_eq!(labels(simplified), vec!["a"]);
+        assert_eq!(labels(HashConsing::default().hash_cons(expr)), vec![
+            "outer", "both"
+        ]);
+
+        let conjuncts = eb.simplify(expr.annotate("kept") * b).conjuncts();
+        assert_eq!(conjuncts.len(), 2);
+        for conjunct in conjuncts {
+            assert_eq!(labels(conjunct), vec!["kept", "outer", "both"]);
+        }
+        // Constants are not labelled.
+        assert!(eb
+            .simplify((eb.constant::<i64>(1) + 1).annotate("two"))
+            .annotations()
+            .is_empty());
+    }
+
+    #[test]
+    fn arena_is_reused() {
+        let mut eb = ExprBuilder::default();
+        let mut p = PureEvaluator::default();
+
+        for frame in 0..3i64 {
+            let value = eb.with_arena(|eb| {
+                let x = eb.lit(frame).assume_binary();
+                p.eval(x * x + 1)
+            });
+            assert_eq!(value, frame * frame + 1);
+        }
+
+        eb.reset();
+        let x = eb.lit(2i64);
+        assert_eq!(p.eval(x * x), 4);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "operands of `|` have to be flagged as binary")]
+    fn reset_forgets_binary_flags() {
+        let mut eb = ExprBuilder::default();
+        eb.with_arena(|eb| {
+            let _ = eb.lit(1i64).is_binary();
+        });
+        let x = eb.lit(1i64);
+        let _ = x | x;
     }
 
     #[test]
@@ -537,6 +1595,31 @@ mod tests {
         assert_eq!(c.evaluator.count(), 10);
     }
 
+    #[test]
+    fn pow_works() {
+        let eb = ExprBuilder::default();
+        let x = eb.lit(3i64);
+
+        let mut p = PureEvaluator::default();
+
+        assert_eq!(p.eval(x.pow(0)), 1);
+        assert_eq!(p.eval(x.pow(1)), 3);
+        assert_eq!(p.eval(x.pow(5)), 243);
+        assert_eq!(p.eval(x.pow(7)), 2187);
+        assert_eq!(p.eval(Expr::<'_, i64>::from(2).pow(10)), 1024);
+    }
+
+    #[test]
+    fn pow_shares_squares() {
+        let eb = ExprBuilder::default();
+        let x = eb.lit(2i64);
+
+        // x^7 = x * x^2 * x^4 takes two squarings and two multiplications.
+        let mut c = Cached::from(Counting::<PureEvaluator<_>>::default());
+        assert_eq!(c.eval(x.pow(7)), 128);
+        assert_eq!(c.evaluator.count(), 4);
+    }
+
     #[test]
     fn avoids_exponential_blowup() {
         let eb = ExprBuilder::default();
@@ -1,8 +1,10 @@
-//! Trait implementations for traits defined in `std::ops` and
-//! `core::iter::Sum`.
+//! Trait implementations for traits defined in `std::ops`, and
+//! `core::iter::Sum` and `core::iter::Product`.
 
-use core::iter::Sum;
-use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use core::iter::{Product, Sum};
+use std::ops::{
+    Add, AddAssign, BitAnd, BitOr, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign,
+};
 
 use crate::{BinOp, Expr, UnaOp};
 
@@ -45,6 +47,7 @@ macro_rules! binop_instances {
 binop_instances!(Add, add);
 binop_instances!(Sub, sub);
 binop_instances!(Mul, mul);
+binop_instances!(Div, div);
 
 impl<'a, V> Neg for Expr<'a, V> {
     type Output = Expr<'a, V>;
@@ -61,6 +64,54 @@ where
     fn neg(self) -> Self::Output { Self::Output::una_op(UnaOp::Neg, *self) }
 }
 
+impl<'a, V> BitOr<Self> for Expr<'a, V>
+where
+    V: Copy,
+{
+    type Output = Self;
+
+    #[track_caller]
+    fn bitor(self, rhs: Self) -> Self::Output { Self::bit_or(self, rhs) }
+}
+
+impl<'a, V> BitOr<i64> for Expr<'a, V>
+where
+    V: Copy,
+{
+    type Output = Self;
+
+    #[track_caller]
+    fn bitor(self, rhs: i64) -> Self::Output { Self::bit_or(self, Expr::from(rhs)) }
+}
+
+impl<'a, V> BitOr<Expr<'a, V>> for i64
+where
+    V: Copy,
+{
+    type Output = Expr<'a, V>;
+
+    #[track_caller]
+    fn bitor(self, rhs: Expr<'a, V>) -> Self::Output { Expr::bit_or(Expr::from(self), rhs) }
+}
+
+impl<'a, V> BitAnd<Self> for Expr<'a, V> {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output { Self::and(self, rhs) }
+}
+
+impl<'a, V> BitAnd<i64> for Expr<'a, V> {
+    type Output = Self;
+
+    fn bitand(self, rhs: i64) -> Self::Output { Self::and(self, Expr::from(rhs)) }
+}
+
+impl<'a, V> BitAnd<Expr<'a, V>> for i64 {
+    type Output = Expr<'a, V>;
+
+    fn bitand(self, rhs: Expr<'a, V>) -> Self::Output { Expr::and(Expr::from(self), rhs) }
+}
+
 macro_rules! assign_instances {
     ($trait:ident, $op:ident, $fun: ident) => {
         impl<'a, V> $trait<Self> for Expr<'a, V>
@@ -84,16 +135,48 @@ macro_rules! assign_instances {
 assign_instances!(AddAssign, Add, add_assign);
 assign_instances!(MulAssign, Mul, mul_assign);
 assign_instances!(SubAssign, Sub, sub_assign);
+assign_instances!(DivAssign, Div, div_assign);
+
+/// Combines `terms` with `op` as a balanced tree, as evaluating a left
+/// leaning chain recurses once per term.  Returns `identity` if there are no
+/// terms.
+fn balanced<'a, V>(
+    terms: impl Iterator<Item = Expr<'a, V>>,
+    op: BinOp,
+    identity: i64,
+) -> Expr<'a, V> {
+    let mut layer = terms.collect::<Vec<_>>();
+    while layer.len() > 1 {
+        let mut terms = layer.into_iter();
+        layer = Vec::with_capacity(terms.len().div_ceil(2));
+        while let Some(left) = terms.next() {
+            layer.push(match terms.next() {
+                Some(right) => Expr::bin_op(op, left, right),
+                None => left,
+            });
+        }
+    }
+    layer.pop().unwrap_or(Expr::from(identity))
+}
 
 impl<'a, V> Sum<Self> for Expr<'a, V> {
-    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self { iter.fold(Expr::from(0), Add::add) }
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self { balanced(iter, BinOp::Add, 0) }
 }
 
 impl<'a, 'b, V> Sum<&'b Expr<'a, V>> for Expr<'a, V>
 where
     V: Copy,
 {
-    fn sum<I: Iterator<Item = &'b Expr<'a, V>>>(iter: I) -> Self {
-        iter.fold(Expr::from(0), Add::add)
-    }
+    fn sum<I: Iterator<Item = &'b Expr<'a, V>>>(iter: I) -> Self { iter.copied().sum() }
+}
+
+impl<'a, V> Product<Self> for Expr<'a, V> {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self { balanced(iter, BinOp::Mul, 1) }
+}
+
+impl<'a, 'b, V> Product<&'b Expr<'a, V>> for Expr<'a, V>
+where
+    V: Copy,
+{
+    fn product<I: Iterator<Item = &'b Expr<'a, V>>>(iter: I) -> Self { iter.copied().product() }
 }
@@ -0,0 +1,166 @@
+//! An owned mirror of [`ExprTree`], free of the arena's lifetime, eg to
+//! serialize constraints and diff them across commits.
+//!
+//! Shared nodes are copied for each use, so the owned tree can be
+//! exponentially larger than the expression.
+//!
+//! Annotations keep their label, but not their source location, so that
+//! moving code around does not show up in the diff.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{BinOp, CompoundExpr, Expr, ExprBuilder, ExprTree, UnaOp};
+
+/// Owned version of [`ExprTree`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OwnedExprTree<V> {
+    BinOp {
+        op: BinOp,
+        left: Box<OwnedExprTree<V>>,
+        right: Box<OwnedExprTree<V>>,
+    },
+    UnaOp {
+        op: UnaOp,
+        expr: Box<OwnedExprTree<V>>,
+    },
+    Literal {
+        value: V,
+    },
+    Constant {
+        value: i64,
+    },
+    BigConstant {
+        value: [u64; 4],
+    },
+    And {
+        left: Box<OwnedExprTree<V>>,
+        right: Box<OwnedExprTree<V>>,
+    },
+    Annotated {
+        label: String,
+        expr: Box<OwnedExprTree<V>>,
+    },
+}
+
+impl<'a, V> From<&ExprTree<'a, V>> for OwnedExprTree<V>
+where
+    V: Copy,
+{
+    fn from(expr_tree: &ExprTree<'a, V>) -> Self {
+        let owned = |expr: &CompoundExpr<'a, V>| Box::new(OwnedExprTree::from(expr.0));
+        match expr_tree {
+            ExprTree::BinOp { op, left, right } => OwnedExprTree::BinOp {
+                op: *op,
+                left: owned(left),
+                right: owned(right),
+            },
+            ExprTree::UnaOp { op, expr } => OwnedExprTree::UnaOp {
+                op: *op,
+                expr: owned(expr),
+            },
+            ExprTree::Literal { value } => OwnedExprTree::Literal { value: *value },
+            ExprTree::Constant { value } => OwnedExprTree::Constant { value: *value },
+            ExprTree::BigConstant { value } => OwnedExprTree::BigConstant { value: *value },
+            ExprTree::And { left, right } => OwnedExprTree::And {
+                left: owned(left),
+                right: owned(right),
+            },
+            ExprTree::Annotated { label, expr, .. } => OwnedExprTree::Annotated {
+                label: (*label).to_owned(),
+                expr: owned(expr),
+            },
+        }
+    }
+}
+
+impl<'a, V> Expr<'a, V>
+where
+    V: Copy,
+{
+    /// Copy the expression out of its builder.
+    #[must_use]
+    pub fn to_owned_tree(&self) -> OwnedExprTree<V> {
+        match self {
+            Expr::Basic { value } => OwnedExprTree::Constant { value: *value },
+            Expr::Compound { expr, .. } => OwnedExprTree::from(expr.0),
+        }
+    }
+}
+
+impl ExprBuilder {
+    /// Allocate `tree` in the builder, the inverse of
+    /// [`Expr::to_owned_tree`].
+    ///
+    /// Annotations are dropped, as their labels would have to be `'static`.
+    pub fn from_owned_tree<V>(&self, tree: OwnedExprTree<V>) -> Expr<'_, V> {
+        self.wrap(self.owned_tree(tree))
+    }
+
+    fn owned_tree<V>(&self, tree: OwnedExprTree<V>) -> CompoundExpr<'_, V> {
+        let expr_tree = match tree {
+            OwnedExprTree::Annotated { expr, .. } => return self.owned_tree(*expr),
+            OwnedExprTree::BinOp { op, left, right } => ExprTree::BinOp {
+                op,
+                left: self.owned_tree(*left),
+                right: self.owned_tree(*right),
+            },
+            OwnedExprTree::UnaOp { op, expr } => ExprTree::UnaOp {
+                op,
+                expr: self.owned_tree(*expr),
+            },
+            OwnedExprTree::Literal { value } => ExprTree::Literal { value },
+            OwnedExprTree::Constant { value } => ExprTree::Constant { value },
+            OwnedExprTree::BigConstant { value } => ExprTree::BigConstant { value },
+            OwnedExprTree::And { left, right } => ExprTree::And {
+                left: self.owned_tree(*left),
+                right: self.owned_tree(*right),
+            },
+        };
+        self.intern(expr_tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OwnedExprTree;
+    use crate::{Evaluator, Expr, ExprBuilder, PureEvaluator};
+
+    #[test]
+    fn round_trips() {
+        let eb = ExprBuilder::default();
+        let a = eb.lit(3i64);
+        let b = eb.lit(5i64);
+        let expr = (-(a * b) + 2 - a / b) & (a - 7);
+
+        let owned = expr.to_owned_tree();
+        let other = ExprBuilder::default();
+        let rebuilt = other.from_owned_tree(owned.clone());
+        assert_eq!(rebuilt.to_owned_tree(), owned);
+
+        let mut p = PureEvaluator::default();
+        let conjuncts = rebuilt.conjuncts().into_iter().map(|e| p.eval(e));
+        assert_eq!(conjuncts.collect::<Vec<_>>(), vec![-13, -4]);
+    }
+
+    #[test]
+    fn annotations_keep_their_label() {
+        let eb = ExprBuilder::default();
+        let a = eb.lit(3i64);
+        let owned = (a * 2).annotate("double").to_owned_tree();
+        assert!(matches!(
+            &owned,
+            OwnedExprTree::Annotated { label, .. } if label == "double"
+        ));
+
+        let other = ExprBuilder::default();
+        let rebuilt = other.from_owned_tree(owned);
+        assert_eq!(PureEvaluator::default().eval(rebuilt), 6);
+        assert!(rebuilt.annotations().is_empty());
+    }
+
+    #[test]
+    fn basic_expressions_become_constants() {
+        let expr: Expr<'_, i64> = Expr::from(7);
+        assert_eq!(expr.to_owned_tree(), OwnedExprTree::Constant { value: 7 });
+    }
+}
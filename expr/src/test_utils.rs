@@ -0,0 +1,129 @@
+#![cfg(any(feature = "test", test))]
+//! Utilities for tests, eg to check that refactoring constraints did not
+//! change what they mean.
+
+use core::fmt::Debug;
+use core::hash::Hash;
+use std::collections::HashMap;
+
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::{Field, Field64};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::{BinOp, CompoundExpr, Expr, ExprTree, UnaOp};
+
+type F = GoldilocksField;
+
+/// The number of random points [`assert_exprs_equivalent`] evaluates at.
+///
+/// Distinct polynomials of degree `d` agree on a random point with
+/// probability at most `d / |F|`, so a few points make a false positive
+/// practically impossible.
+const POINTS: usize = 16;
+
+/// Asserts that `a` and `b` evaluate to the same value, with each literal
+/// assigned the same random field element in both, at a number of random
+/// points.
+///
+/// Literals are told apart by their value, eg the column they stand for.
+///
+/// # Panics
+///
+/// Panics if the expressions differ at any of the points, or contain a
+/// conjunction, see [`Expr::conjuncts`].
+#[track_caller]
+pub fn assert_exprs_equivalent<V>(a: Expr<'_, V>, b: Expr<'_, V>)
+where
+    V: Copy + Eq + Hash + Debug, {
+    // Seeded, so that failures can be reproduced.
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..POINTS {
+        let mut point = HashMap::new();
+        let mut value = |literal: V| {
+            *point
+                .entry(literal)
+                .or_insert_with(|| F::from_canonical_u64(rng.gen_range(0..F::ORDER)))
+        };
+        let (left, right) = (eval(a, &mut value), eval(b, &mut value));
+        assert_eq!(left, right, "expressions differ at {point:?}");
+    }
+}
+
+/// Evaluates `expr` in the field, with `value` giving the values of the
+/// literals.
+fn eval<V>(expr: Expr<'_, V>, value: &mut impl FnMut(V) -> F) -> F
+where
+    V: Copy, {
+    match expr {
+        Expr::Basic { value: constant } => F::from_noncanonical_i64(constant),
+        Expr::Compound { expr, .. } => compound_expr(expr, value, &mut HashMap::new()),
+    }
+}
+
+fn compound_expr<V>(
+    expr: CompoundExpr<'_, V>,
+    value: &mut impl FnMut(V) -> F,
+    memo: &mut HashMap<usize, F>,
+) -> F
+where
+    V: Copy, {
+    if let Some(&v) = memo.get(&expr.address()) {
+        return v;
+    }
+    let v = match *expr.0 {
+        ExprTree::Literal { value: literal } => value(literal),
+        ExprTree::Constant { value: constant } => F::from_noncanonical_i64(constant),
+        ExprTree::BigConstant { value: limbs } => limbs.iter().rev().fold(F::ZERO, |acc, &limb| {
+            acc * F::from_noncanonical_u128(1 << 64) + F::from_noncanonical_u64(limb)
+        }),
+        ExprTree::BinOp { op, left, right } => {
+            let left = compound_expr(left, value, memo);
+            let right = compound_expr(right, value, memo);
+            match op {
+                BinOp::Add => left + right,
+                BinOp::Sub => left - right,
+                BinOp::Mul => left * right,
+                BinOp::Div => left / right,
+            }
+        }
+        ExprTree::UnaOp {
+            op: UnaOp::Neg,
+            expr,
+        } => -compound_expr(expr, value, memo),
+        ExprTree::Annotated { expr, .. } => compound_expr(expr, value, memo),
+        ExprTree::And { .. } =>
+            panic!("split conjunctions with `Expr::conjuncts` before evaluating them"),
+    };
+    memo.insert(expr.address(), v);
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::assert_exprs_equivalent;
+    use crate::ExprBuilder;
+
+    #[test]
+    fn equivalent_expressions() {
+        let eb = ExprBuilder::default();
+        let (a, b) = (eb.lit("a"), eb.lit("b"));
+
+        assert_exprs_equivalent((a + b) * (a - b), a * a - b * b);
+        assert_exprs_equivalent(eb.simplify((a * 1 + 0).annotate("a")), a);
+        assert_exprs_equivalent(
+            eb.big_constant::<&str>([0, 1, 0, 0]),
+            eb.constant(1 << 32).pow(2),
+        );
+        // Literals with equal values are the same variable.
+        assert_exprs_equivalent(eb.lit("a") - a, eb.constant(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "expressions differ")]
+    fn different_expressions() {
+        let eb = ExprBuilder::default();
+        let (a, b) = (eb.lit("a"), eb.lit("b"));
+        assert_exprs_equivalent((a + b) * (a + b), a * a + b * b);
+    }
+}
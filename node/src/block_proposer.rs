@@ -11,6 +11,7 @@ use plonky2::field::types::Field;
 
 use crate::F;
 
+pub mod batch;
 pub mod block;
 pub mod matches;
 pub mod state;
@@ -274,6 +275,7 @@ pub fn convert_event_type(ty: SdkEventType) -> ProofEventType {
         SdkEventType::Read => ProofEventType::Read,
         SdkEventType::GiveOwner => ProofEventType::GiveOwner,
         SdkEventType::TakeOwner => ProofEventType::TakeOwner,
+        SdkEventType::CreditDelta => ProofEventType::CreditDelta,
     }
 }
 
@@ -0,0 +1,273 @@
+//! Packs transactions into a block.
+//!
+//! Transactions are proven into the block as they are pushed, so the cost of
+//! sealing a block does not grow with its size. Each transaction brings the
+//! state changes it makes, which are checked against its events by the block
+//! circuit.
+//!
+//! The tapes of the attestations of a transaction are not part of its proofs,
+//! so they are checked against the proofs before anything is proven: the
+//! events of each attestation have to hash to the event root in the public
+//! inputs of the proof of its program. Program proofs carry no commitment to
+//! the public tape, so it can not be checked this way yet.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{ensure, Result};
+use itertools::Itertools;
+use mozak_sdk::common::types::{CanonicalEvent, ProgramIdentifier};
+use plonky2::plonk::circuit_data::VerifierOnlyCircuitData;
+use plonky2::plonk::proof::ProofWithPublicInputs;
+use serde::{Deserialize, Serialize};
+
+use super::block::Blocks;
+use super::matches::{AuxMatchesData, Matches};
+use super::state::{Operation, State};
+use super::transactions::{AuxTransactionData, TransactionAccumulator};
+use super::Address;
+use crate::fees::CreditChanges;
+use crate::mempool::{Tx, TxId};
+use crate::rpc::ObjectState;
+use crate::{C, D, F};
+
+/// The proof of one member of the cast list of a transaction.
+pub struct ProvenProgram {
+    /// Position of the program in the cast list.
+    pub cast_index: usize,
+    pub verifier: VerifierOnlyCircuitData<C, D>,
+    pub proof: ProofWithPublicInputs<F, C, D>,
+    pub events: Vec<CanonicalEvent>,
+}
+
+/// A transaction together with the proofs of all of its programs.
+pub struct ProvenTx {
+    pub id: TxId,
+    pub cast_list: Vec<ProgramIdentifier>,
+    pub call_tape: [F; 4],
+    pub programs: Vec<ProvenProgram>,
+    /// The state changes the events of the transaction result in.
+    pub operations: Vec<(Address, Operation)>,
+}
+
+impl ProvenTx {
+    /// Checks that this is a proof of `tx`: that it has the same cast list,
+    /// and that each program was proven with the events on the tape of its
+    /// attestation. Programs without an attestation can't have events.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first mismatch.
+    pub fn check_attestations(&self, tx: &Tx) -> Result<()> {
+        ensure!(
+            TxId::of(tx) == self.id,
+            "proofs are of transaction {:?}",
+            self.id.0
+        );
+        ensure!(
+            tx.cast_list == self.cast_list,
+            "proofs are for another cast list"
+        );
+        for program in &self.programs {
+            let id = self.cast_list.get(program.cast_index);
+            let attested = tx
+                .constituent_zs
+                .iter()
+                .find(|z| Some(&z.id) == id)
+                .map(|z| {
+                    z.event_tape
+                        .get_canonical_order_temporal_hints()
+                        .into_iter()
+                        .map(|hint| hint.0)
+                        .collect_vec()
+                })
+                .unwrap_or_default();
+            ensure!(
+                attested == program.events,
+                "events of {id:?} differ from its attestation"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// When to stop adding transactions to a block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockLimits {
+    pub max_transactions: usize,
+    /// How long a block may stay open after its first transaction.
+    pub max_duration: Duration,
+}
+
+impl Default for BlockLimits {
+    fn default() -> Self {
+        Self {
+            max_transactions: 256,
+            max_duration: Duration::from_secs(10),
+        }
+    }
+}
+
+impl BlockLimits {
+    /// Whether a block with `transactions`, open for `elapsed`, is done.
+    #[must_use]
+    pub fn is_reached(&self, transactions: usize, elapsed: Duration) -> bool {
+        transactions >= self.max_transactions || elapsed >= self.max_duration
+    }
+}
+
+pub struct Batch<'a> {
+    tx_aux: &'a AuxTransactionData,
+    limits: BlockLimits,
+    opened: Option<Instant>,
+    transactions: TransactionAccumulator<'a>,
+    matches: Matches<'a>,
+    included: Vec<TxId>,
+    has_events: bool,
+}
+
+impl<'a> Batch<'a> {
+    /// Create an empty batch
+    #[must_use]
+    pub fn new(tx: &'a AuxTransactionData, md: &'a AuxMatchesData, limits: BlockLimits) -> Self {
+        Self {
+            tx_aux: tx,
+            limits,
+            opened: None,
+            transactions: TransactionAccumulator::new(tx),
+            matches: Matches::new(md),
+            included: Vec::new(),
+            has_events: false,
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize { self.included.len() }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.included.is_empty() }
+
+    /// Whether the block has reached one of its limits, and should be sealed.
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        let elapsed = self
+            .opened
+            .as_ref()
+            .map_or(Duration::ZERO, Instant::elapsed);
+        self.limits.is_reached(self.len(), elapsed)
+    }
+
+    /// Proves `tx` into the batch, and applies its operations to `state`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the batch is full, if `tx` does not have exactly
+    /// one proof for every member of its cast list, if a proof is not of
+    /// its program and events, or if `tx` does not settle its fees. Errors from
+    /// the proofs themselves leave the batch unusable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit logic has a bug.
+    pub fn push(&mut self, tx: ProvenTx, state: &mut State) -> Result<()> {
+        ensure!(!self.is_full(), "block is full");
+        ensure!(
+            tx.programs
+                .iter()
+                .map(|p| p.cast_index)
+                .sorted()
+                .eq(0..tx.cast_list.len()),
+            "transaction {:?} needs exactly one proof per cast list member",
+            tx.id.0
+        );
+        for program in &tx.programs {
+            self.tx_aux.check_binding(
+                &tx.cast_list[program.cast_index],
+                &program.proof,
+                &program.events,
+            )?;
+        }
+
+        // Settle the fees before anything is proven: the objects the credits
+        // are taken from have to hold them, and the new balances have to be
+        // among the operations, which the block circuit checks against the
+        // `CreditDelta` events.
+        let events = tx
+            .programs
+            .iter()
+            .flat_map(|program| program.events.iter().copied())
+            .collect_vec();
+        CreditChanges::from_events(&events)?.check_settled(&tx.operations, |address| {
+            state
+                .get_state(Address::from_state(address))
+                .1
+                .map(ObjectState::from)
+        })?;
+
+        let mut completed = None;
+        for program in &tx.programs {
+            let (key, done) = self.transactions.ingest_program(
+                program.cast_index,
+                &program.verifier,
+                &program.proof,
+                &tx.cast_list,
+                &program.events,
+                tx.call_tape,
+            )?;
+            self.matches
+                .ingest_events(key, &tx.cast_list[program.cast_index], &program.events);
+            self.has_events |= !program.events.is_empty();
+            if done {
+                completed = Some(key);
+            }
+        }
+        // Every cast member was ingested, so the transaction is complete.
+        self.matches.ready_tx(completed.unwrap())?;
+
+        for (addr, op) in tx.operations {
+            state.apply_operation(addr, op);
+        }
+        self.opened.get_or_insert_with(Instant::now);
+        self.included.push(tx.id);
+        Ok(())
+    }
+
+    /// Proves the batch as the next block, moving `state` to its new root.
+    /// Returns the transactions in the block, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the batch has no events, or if the operations of
+    /// the transactions don't match their events. In that case `state` still
+    /// holds the operations, and has to be discarded.
+    pub fn seal(mut self, blocks: &mut Blocks, state: &mut State) -> Result<Vec<TxId>> {
+        ensure!(self.has_events, "a block needs at least one event");
+
+        let tx_proof = self.transactions.finalize()?;
+        let match_proof = self.matches.finalize(blocks.height() + 1, |addr| {
+            let (old, new) = state.get_state(addr);
+            (
+                old.copied().unwrap_or_default(),
+                new.copied().unwrap_or_default(),
+            )
+        });
+        blocks.increment(&tx_proof, &match_proof, state.proof())?;
+        state.finalize();
+        Ok(self.included)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn limits() {
+        let limits = BlockLimits {
+            max_transactions: 2,
+            max_duration: Duration::from_secs(1),
+        };
+        assert!(!limits.is_reached(1, Duration::ZERO));
+        assert!(limits.is_reached(2, Duration::ZERO));
+        assert!(limits.is_reached(1, Duration::from_secs(1)));
+    }
+}
@@ -141,6 +141,9 @@ impl AuxStateData {
         }
     }
 
+    #[must_use]
+    pub fn max_tree_depth(&self) -> usize { self.max_tree_depth }
+
     fn apply_operation(&self, root: &mut SparseMerkleBranch, addr: Address, new: Operation) {
         let (path, dir) = addr.next(root.height);
         let _ = self.apply_operation_helper(root, addr, path, dir, new);
@@ -560,6 +563,10 @@ impl<'a> State<'a> {
 
     pub fn finalize(&mut self) { self.aux.finalize(&mut self.root); }
 
+    /// The proof of the operations applied since the last `finalize`.
+    #[must_use]
+    pub fn proof(&self) -> &BranchProof { &self.root.proof }
+
     #[must_use]
     pub fn get_state(&self, addr: Address) -> (Option<&Object>, Option<&Object>) {
         let (path, dir) = addr.next(self.root.height);
@@ -2,7 +2,7 @@ use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::ops::Deref;
 
-use anyhow::{bail, Result};
+use anyhow::{bail, ensure, Result};
 use hashbrown::hash_map::Entry;
 use hashbrown::HashMap;
 use itertools::{merge_join_by, Either, EitherOrBoth};
@@ -61,6 +61,8 @@ pub struct AuxTransactionData {
 
     empty_merge_leaf: MergeLeafProof,
     empty_merge_branch: MergeBranchProof,
+
+    program_indices: ProgramPublicIndices,
 }
 
 impl AuxTransactionData {
@@ -112,7 +114,54 @@ impl AuxTransactionData {
             tx_branch_circuit,
             empty_merge_leaf,
             empty_merge_branch,
+            program_indices: *program_indices,
+        }
+    }
+
+    /// Checks that `program_proof` is a proof of the program `id` that
+    /// emitted exactly `events`, by recomputing the commitments in its public
+    /// inputs. This catches transactions that pair a proof with tapes it was
+    /// not made with, before anything is proven.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the proof is of another program, or of other
+    /// events.
+    pub fn check_binding(
+        &self,
+        id: &ProgramIdentifier,
+        program_proof: &ProofWithPublicInputs<F, C, D>,
+        events: &[CanonicalEvent],
+    ) -> Result<()> {
+        let public_inputs = &program_proof.public_inputs;
+        let program_hash = self.program_indices.program_hash.get_field(public_inputs);
+        ensure!(
+            program_hash == id.0.to_u64s().map(F::from_noncanonical_u64),
+            "proof is not of program {id:?}"
+        );
+
+        let events_present = self.program_indices.events_present.get_field(public_inputs);
+        let event_root = reduce_tree(
+            events.iter().map(CanonicalEvent::canonical_hash),
+            |x| x,
+            |x| x,
+            Poseidon2Hash::two_to_one,
+        );
+        match event_root {
+            None => ensure!(
+                !events_present,
+                "proof of {id:?} has events, but none were given"
+            ),
+            Some(root) => {
+                let root = HashOut::from(root.to_u64s().map(F::from_canonical_u64));
+                ensure!(
+                    events_present
+                        && self.program_indices.event_root.get_field(public_inputs) == root,
+                    "events of {id:?} are not the ones it was proven with"
+                );
+            }
         }
+        Ok(())
     }
 
     fn insert_program(
@@ -842,6 +891,12 @@ pub mod test {
 
         let tx_proof = txs.finalize();
         assert!(tx_proof.is_ok());
+
+        AUX.check_binding(&PROGRAM_M.pid(), &proof, &[]).unwrap();
+        assert!(AUX
+            .check_binding(&PROGRAM_M.pid(), &proof, &SIMPLE_EVENTS)
+            .is_err());
+        assert!(AUX.check_binding(&PROGRAM_0.pid(), &proof, &[]).is_err());
     }
 
     #[test]
@@ -930,6 +985,13 @@ pub mod test {
             )
             .unwrap();
 
+        AUX.check_binding(&PROGRAM_0.pid(), &proof, &SIMPLE_EVENTS)
+            .unwrap();
+        assert!(AUX
+            .check_binding(&PROGRAM_0.pid(), &proof, &SIMPLE_EVENTS[1..])
+            .is_err());
+        assert!(AUX.check_binding(&PROGRAM_0.pid(), &proof, &[]).is_err());
+
         let mut txs = TransactionAccumulator::new(*AUX);
         let (_k, complete) = txs
             .ingest_program(
@@ -0,0 +1,413 @@
+//! Configuration of a node, read from a `node.toml` when it starts.
+//!
+//! ```toml
+//! [network]
+//! listen = "0.0.0.0:30333"
+//! peers = ["10.0.0.2:30333"]
+//! chain_spec = "genesis.toml"
+//!
+//! [storage]
+//! data_dir = "data"
+//! retention = { mode = "pruned", blocks = 10000 }
+//!
+//! [prover]
+//! threads = 8
+//! max_block_transactions = 256
+//! max_block_seconds = 10
+//!
+//! [rpc]
+//! bind = "127.0.0.1:9944"
+//! ws_bind = "127.0.0.1:9945"
+//! metrics_bind = "127.0.0.1:9615"
+//! ```
+//!
+//! Every field has a default, and can be overridden by an environment variable
+//! named after its section and key, eg `MOZAK_NODE_PROVER_THREADS=4`. Sending
+//! the node a `SIGHUP` reads the file and the environment again, and applies
+//! the fields that can change while running; see [`NodeConfig::reloaded`].
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::{fs, thread};
+
+use anyhow::{ensure, Context, Result};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use signal_hook::consts::SIGHUP;
+#[cfg(not(target_arch = "wasm32"))]
+use signal_hook::iterator::Signals;
+
+use crate::block_proposer::batch::BlockLimits;
+use crate::storage::Retention;
+
+/// Prefix of the environment variables that override the config file.
+pub const ENV_PREFIX: &str = "MOZAK_NODE_";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    pub listen: SocketAddr,
+    /// Peers to connect to on startup. Reloadable.
+    pub peers: Vec<String>,
+    pub chain_spec: PathBuf,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            listen: ([0, 0, 0, 0], 30333).into(),
+            peers: vec![],
+            chain_spec: "genesis.toml".into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// Where blocks, proofs and the state are kept.
+    pub data_dir: PathBuf,
+    /// Reloadable.
+    pub retention: Retention,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: "data".into(),
+            retention: Retention::Archive,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProverConfig {
+    /// Threads used for proving. Reloadable.
+    pub threads: usize,
+    /// Reloadable.
+    pub max_block_transactions: usize,
+    /// How long a block may stay open after its first transaction. Reloadable.
+    pub max_block_seconds: u64,
+}
+
+impl Default for ProverConfig {
+    fn default() -> Self {
+        let limits = BlockLimits::default();
+        Self {
+            threads: thread::available_parallelism().map_or(1, usize::from),
+            max_block_transactions: limits.max_transactions,
+            max_block_seconds: limits.max_duration.as_secs(),
+        }
+    }
+}
+
+impl ProverConfig {
+    #[must_use]
+    pub fn block_limits(&self) -> BlockLimits {
+        BlockLimits {
+            max_transactions: self.max_block_transactions,
+            max_duration: Duration::from_secs(self.max_block_seconds),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcConfig {
+    pub bind: SocketAddr,
+    /// Where to serve the WebSocket API, if anywhere.
+    pub ws_bind: Option<SocketAddr>,
+    /// Where to serve `GET /metrics`, if anywhere.
+    pub metrics_bind: Option<SocketAddr>,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            bind: ([127, 0, 0, 1], 9944).into(),
+            ws_bind: Some(([127, 0, 0, 1], 9945).into()),
+            metrics_bind: Some(([127, 0, 0, 1], 9615).into()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeConfig {
+    pub network: NetworkConfig,
+    pub storage: StorageConfig,
+    pub prover: ProverConfig,
+    pub rpc: RpcConfig,
+}
+
+impl NodeConfig {
+    /// Reads `path`, applies the overrides from the environment, and
+    /// validates the result.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can not be read, or the config is invalid.
+    pub fn load(path: &Path) -> Result<Self> {
+        let config = fs::read_to_string(path).with_context(|| format!("reading {path:?}"))?;
+        Self::from_sources(&config, std::env::vars())
+            .with_context(|| format!("invalid node config {path:?}"))
+    }
+
+    /// Parses `config`, applies the overrides among `env`, and validates the
+    /// result.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the config is invalid, or an override does not
+    /// name a known field.
+    pub fn from_sources(
+        config: &str,
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self> {
+        let mut config: toml::Table = toml::from_str(config)?;
+        for (name, value) in env {
+            let Some(field) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let field = field.to_lowercase();
+            let (section, key) = field
+                .split_once('_')
+                .with_context(|| format!("{name} does not name a field"))?;
+            // Values are TOML, so that numbers and lists can be overridden,
+            // but plain strings don't need to be quoted.
+            let value = format!("v = {value}")
+                .parse::<toml::Table>()
+                .ok()
+                .and_then(|mut v| v.remove("v"))
+                .unwrap_or(toml::Value::String(value));
+            config
+                .entry(section)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .with_context(|| format!("{name} does not name a field"))?
+                .insert(key.to_string(), value);
+        }
+        let config: Self = toml::Value::Table(config).try_into()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error describing the first problem found.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.prover.threads > 0,
+            "the prover needs at least one thread"
+        );
+        ensure!(
+            self.prover.max_block_transactions > 0,
+            "blocks need room for at least one transaction"
+        );
+        ensure!(
+            !self.storage.data_dir.as_os_str().is_empty(),
+            "the data directory is not set"
+        );
+        ensure!(
+            self.storage.retention != Retention::Pruned { blocks: 0 },
+            "a pruned node has to keep at least one block"
+        );
+        let binds = [
+            ("network.listen", Some(self.network.listen)),
+            ("rpc.bind", Some(self.rpc.bind)),
+            ("rpc.ws_bind", self.rpc.ws_bind),
+            ("rpc.metrics_bind", self.rpc.metrics_bind),
+        ];
+        let binds = binds
+            .iter()
+            .filter_map(|(name, bind)| Some((name, (*bind)?)));
+        for ((name, bind), (other, other_bind)) in binds.tuple_combinations() {
+            ensure!(bind != other_bind, "{name} and {other} are both {bind}");
+        }
+        Ok(())
+    }
+
+    /// Takes the reloadable fields from `new`, and keeps the others. Also
+    /// returns the fields that differ but only take effect after a restart.
+    #[must_use]
+    pub fn reloaded(&self, new: &Self) -> (Self, Vec<&'static str>) {
+        let mut needs_restart = vec![];
+        let mut check = |name, changed| {
+            if changed {
+                needs_restart.push(name);
+            }
+        };
+        check("network.listen", self.network.listen != new.network.listen);
+        check(
+            "network.chain_spec",
+            self.network.chain_spec != new.network.chain_spec,
+        );
+        check(
+            "storage.data_dir",
+            self.storage.data_dir != new.storage.data_dir,
+        );
+        check("rpc", self.rpc != new.rpc);
+
+        let config = Self {
+            network: NetworkConfig {
+                peers: new.network.peers.clone(),
+                ..self.network.clone()
+            },
+            storage: StorageConfig {
+                retention: new.storage.retention,
+                ..self.storage.clone()
+            },
+            prover: new.prover.clone(),
+            ..self.clone()
+        };
+        (config, needs_restart)
+    }
+}
+
+/// The config of a running node, shared between the threads that use it.
+#[derive(Debug)]
+pub struct SharedConfig {
+    path: PathBuf,
+    current: RwLock<Arc<NodeConfig>>,
+}
+
+impl SharedConfig {
+    /// # Errors
+    ///
+    /// See [`NodeConfig::load`].
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let config = NodeConfig::load(&path)?;
+        Ok(Self {
+            path,
+            current: RwLock::new(Arc::new(config)),
+        })
+    }
+
+    /// The config as of now. Holding on to it does not block reloads.
+    #[must_use]
+    pub fn get(&self) -> Arc<NodeConfig> {
+        Arc::clone(&self.current.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Loads the config again and applies its reloadable fields. Returns the
+    /// changed fields that need a restart.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, and keeps the current config, if the new one is
+    /// invalid.
+    pub fn reload(&self) -> Result<Vec<&'static str>> {
+        let new = NodeConfig::load(&self.path)?;
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        let (config, needs_restart) = current.reloaded(&new);
+        *current = Arc::new(config);
+        Ok(needs_restart)
+    }
+}
+
+/// Reloads `config` every time the process receives `SIGHUP`.
+///
+/// # Errors
+///
+/// Returns an error if the signal handler can not be installed.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn reload_on_sighup(config: Arc<SharedConfig>) -> std::io::Result<JoinHandle<()>> {
+    let mut signals = Signals::new([SIGHUP])?;
+    Ok(thread::spawn(move || {
+        for _ in signals.forever() {
+            match config.reload() {
+                Ok(needs_restart) if !needs_restart.is_empty() => log::warn!(
+                    "reloaded {:?}, but changes to {needs_restart:?} need a restart",
+                    config.path
+                ),
+                Ok(_) => log::info!("reloaded {:?}", config.path),
+                Err(e) => log::error!("keeping the old config: {e:#}"),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        [network]
+        peers = ["10.0.0.2:30333"]
+
+        [prover]
+        threads = 2
+    "#;
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn defaults_and_overrides() {
+        let config = NodeConfig::from_sources(CONFIG, env(&[])).unwrap();
+        assert_eq!(config.prover.threads, 2);
+        assert_eq!(config.storage, StorageConfig::default());
+        assert_eq!(config.prover.block_limits(), BlockLimits::default());
+
+        let config = NodeConfig::from_sources(
+            CONFIG,
+            env(&[
+                ("MOZAK_NODE_PROVER_THREADS", "4"),
+                ("MOZAK_NODE_STORAGE_DATA_DIR", "/var/lib/mozak"),
+                ("MOZAK_NODE_NETWORK_PEERS", r#"["a:1", "b:2"]"#),
+                (
+                    "MOZAK_NODE_STORAGE_RETENTION",
+                    r#"{ mode = "pruned", blocks = 5 }"#,
+                ),
+                ("PATH", "/usr/bin"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.prover.threads, 4);
+        assert_eq!(config.storage.data_dir, PathBuf::from("/var/lib/mozak"));
+        assert_eq!(config.network.peers, vec!["a:1", "b:2"]);
+        assert_eq!(config.storage.retention, Retention::Pruned { blocks: 5 });
+
+        assert!(
+            NodeConfig::from_sources(CONFIG, env(&[("MOZAK_NODE_PROVER_SPEED", "1")])).is_err()
+        );
+        assert!(NodeConfig::from_sources("[prover]\nspeed = 1", env(&[])).is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        let mut config = NodeConfig::default();
+        config.validate().unwrap();
+
+        config.rpc.metrics_bind = Some(config.rpc.bind);
+        assert!(config.validate().is_err());
+
+        config.rpc.metrics_bind = None;
+        config.prover.threads = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn reload() {
+        let old = NodeConfig::default();
+        let mut new = NodeConfig::default();
+        new.prover.threads += 1;
+        new.network.peers = vec!["a:1".to_string()];
+        new.rpc.bind = ([127, 0, 0, 1], 1).into();
+
+        let (config, needs_restart) = old.reloaded(&new);
+        assert_eq!(config.prover, new.prover);
+        assert_eq!(config.network.peers, new.network.peers);
+        assert_eq!(config.rpc, old.rpc);
+        assert_eq!(needs_restart, vec!["rpc"]);
+    }
+}
@@ -0,0 +1,237 @@
+//! Credits and fees, as moved around by `CreditDelta` events.
+//!
+//! Every object in the state tree holds a balance of credits. A transaction
+//! pays its fee by taking more credits from its fee payer than it gives to
+//! other objects: credits can be burned, but never created. The new balances
+//! are part of the state changes of the transaction, so the block proof checks
+//! them against the `CreditDelta` events like any other change.
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, ensure, Result};
+use itertools::Itertools;
+use mozak_sdk::common::types::{CanonicalEvent, EventType, StateAddress};
+
+use crate::block_proposer::state::Operation;
+use crate::block_proposer::Address;
+use crate::mempool::Tx;
+use crate::rpc::ObjectState;
+
+/// The largest amount a single `CreditDelta` event can move, as enforced by
+/// the event accumulation circuit.
+pub const MAX_CREDIT_DELTA: u64 = (1 << 40) - 1;
+
+/// Returns the signed amount of credits of a `CreditDelta` event, and `None`
+/// for other events.
+///
+/// # Errors
+///
+/// Returns an error if the value of the event is malformed.
+pub fn credit_delta(event: &CanonicalEvent) -> Result<Option<i64>> {
+    if event.type_ != EventType::CreditDelta {
+        return Ok(None);
+    }
+    let [amount, _, _, sign] = event.value.to_u64s();
+    ensure!(
+        amount <= MAX_CREDIT_DELTA,
+        "credit delta of {amount} is too large"
+    );
+    let amount = i64::try_from(amount)?;
+    Ok(Some(match sign {
+        0 => amount,
+        1 => -amount,
+        _ => bail!("credit delta has an invalid sign {sign}"),
+    }))
+}
+
+/// The net change in credits a transaction makes to each object.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CreditChanges(pub BTreeMap<StateAddress, i64>);
+
+impl CreditChanges {
+    /// Adds up the `CreditDelta` events of all the attestations of `tx`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an event is malformed, or the transaction creates
+    /// credits.
+    pub fn of(tx: &Tx) -> Result<Self> {
+        let events = tx
+            .constituent_zs
+            .iter()
+            .flat_map(|z| z.event_tape.get_canonical_order_temporal_hints())
+            .map(|hint| hint.0)
+            .collect_vec();
+        Self::from_events(&events)
+    }
+
+    /// Adds up the `CreditDelta` events among `events`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an event is malformed, or the events create
+    /// credits.
+    pub fn from_events(events: &[CanonicalEvent]) -> Result<Self> {
+        let mut changes = BTreeMap::<_, i64>::new();
+        for event in events {
+            if let Some(delta) = credit_delta(event)? {
+                *changes.entry(event.address).or_default() += delta;
+            }
+        }
+        changes.retain(|_, delta| *delta != 0);
+        let changes = Self(changes);
+        ensure!(
+            changes.net() <= 0,
+            "events create {} credits",
+            changes.net()
+        );
+        Ok(changes)
+    }
+
+    /// The sum of all changes.
+    #[must_use]
+    pub fn net(&self) -> i64 { self.0.values().sum() }
+
+    /// The credits the transaction burns.
+    #[must_use]
+    pub fn fee(&self) -> u64 { self.net().unsigned_abs() }
+
+    /// Applies the changes to the current objects, as returned by `object`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an object the credits are taken from does not exist,
+    /// or does not have enough of them.
+    pub fn apply(
+        &self,
+        mut object: impl FnMut(StateAddress) -> Option<ObjectState>,
+    ) -> Result<Vec<(StateAddress, ObjectState)>> {
+        self.0
+            .iter()
+            .map(|(&address, &delta)| {
+                let Some(mut state) = object(address) else {
+                    bail!("object {address:?} does not exist");
+                };
+                let Some(credits) = state.credits.checked_add_signed(delta) else {
+                    bail!(
+                        "object {address:?} has {} credits, and can not pay {}",
+                        state.credits,
+                        delta.unsigned_abs()
+                    );
+                };
+                state.credits = credits;
+                Ok((address, state))
+            })
+            .collect()
+    }
+
+    /// Checks that `operations` settle the changes: that every object the
+    /// credits are taken from can pay them, and that its new balance is
+    /// upserted by `operations`.
+    ///
+    /// # Errors
+    ///
+    /// See [`apply`](Self::apply), and returns an error if a new balance is
+    /// missing from `operations`.
+    pub fn check_settled(
+        &self,
+        operations: &[(Address, Operation)],
+        object: impl FnMut(StateAddress) -> Option<ObjectState>,
+    ) -> Result<()> {
+        for (address, state) in self.apply(object)? {
+            let settled = operations.iter().any(|(addr, op)| {
+                *addr == Address::from_state(address)
+                    && matches!(op, Operation::Upsert(new) if ObjectState::from(new).credits == state.credits)
+            });
+            ensure!(settled, "the credits of {address:?} are not settled");
+        }
+        Ok(())
+    }
+
+    /// Like [`apply`](Self::apply), but returns the new objects as operations
+    /// on the state tree.
+    ///
+    /// # Errors
+    ///
+    /// See [`apply`](Self::apply).
+    pub fn operations(
+        &self,
+        object: impl FnMut(StateAddress) -> Option<ObjectState>,
+    ) -> Result<Vec<(Address, Operation)>> {
+        Ok(self
+            .apply(object)?
+            .into_iter()
+            .map(|(address, state)| {
+                (
+                    Address::from_state(address),
+                    Operation::Upsert(state.into()),
+                )
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use mozak_sdk::common::types::Poseidon2Hash;
+
+    use super::*;
+
+    fn delta(address: u8, amount: i64) -> CanonicalEvent {
+        let sign = u64::from(amount < 0);
+        CanonicalEvent {
+            address: StateAddress([address; 8]),
+            type_: EventType::CreditDelta,
+            value: Poseidon2Hash::from([amount.unsigned_abs(), 0, 0, sign]),
+        }
+    }
+
+    fn object(credits: u64) -> ObjectState {
+        ObjectState {
+            credits,
+            ..ObjectState::default()
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(credit_delta(&delta(1, 5)).unwrap(), Some(5));
+        assert_eq!(credit_delta(&delta(1, -5)).unwrap(), Some(-5));
+
+        let mut bad_sign = delta(1, 5);
+        bad_sign.value = Poseidon2Hash::from([5, 0, 0, 2]);
+        assert!(credit_delta(&bad_sign).is_err());
+
+        let too_large = i64::try_from(MAX_CREDIT_DELTA).unwrap() + 1;
+        assert!(credit_delta(&delta(1, too_large)).is_err());
+    }
+
+    #[test]
+    fn settlement() {
+        let changes = CreditChanges::from_events(&[delta(1, -10), delta(2, 7)]).unwrap();
+        assert_eq!(changes.fee(), 3);
+        assert_eq!(changes.apply(|_| Some(object(10))).unwrap(), vec![
+            (StateAddress([1; 8]), object(0)),
+            (StateAddress([2; 8]), object(17))
+        ]);
+        assert!(changes.apply(|_| Some(object(9))).is_err());
+        assert!(changes.apply(|_| None).is_err());
+
+        assert!(CreditChanges::from_events(&[delta(1, 1)]).is_err());
+    }
+
+    #[test]
+    fn settled() {
+        let changes = CreditChanges::from_events(&[delta(1, -10), delta(2, 7)]).unwrap();
+        let operations = changes.operations(|_| Some(object(10))).unwrap();
+        assert!(changes
+            .check_settled(&operations, |_| Some(object(10)))
+            .is_ok());
+        assert!(changes
+            .check_settled(&operations[..1], |_| Some(object(10)))
+            .is_err());
+        assert!(changes
+            .check_settled(&operations, |_| Some(object(9)))
+            .is_err());
+    }
+}
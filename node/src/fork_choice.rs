@@ -0,0 +1,240 @@
+//! Tracks the tree of known blocks and picks the canonical chain among them.
+//!
+//! The fork-choice rule is longest chain, with the block that was received
+//! first winning ties. The state of the canonical chain is kept here, along
+//! with what each canonical block changed, so that a reorg can roll the state
+//! back to the common ancestor and then apply the new branch.
+
+use std::collections::BTreeMap;
+
+use anyhow::{ensure, Context, Result};
+use hashbrown::HashMap;
+use mozak_sdk::common::types::{Poseidon2Hash, StateAddress};
+use serde::{Deserialize, Serialize};
+
+use crate::rpc::ObjectState;
+use crate::storage::BlockCommit;
+
+/// The hash of the [`BlockHeader`](crate::types::BlockHeader) of a block.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BlockId(pub Poseidon2Hash);
+
+/// A change of the head of the canonical chain.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeadChange {
+    /// The last block the old and new chains share.
+    pub ancestor: BlockId,
+    /// Blocks that are no longer canonical, newest first.
+    pub reverted: Vec<BlockId>,
+    /// Blocks that became canonical, oldest first.
+    pub applied: Vec<BlockId>,
+}
+
+impl HeadChange {
+    /// Whether canonical blocks were reverted, rather than the chain just
+    /// growing.
+    #[must_use]
+    pub fn is_reorg(&self) -> bool { !self.reverted.is_empty() }
+}
+
+struct Node {
+    parent: Option<BlockId>,
+    height: u64,
+    commit: Option<BlockCommit>,
+}
+
+type Undo = Vec<(StateAddress, Option<ObjectState>)>;
+
+pub struct ForkChoice {
+    blocks: HashMap<BlockId, Node>,
+    head: BlockId,
+    objects: BTreeMap<StateAddress, ObjectState>,
+    /// What applying each canonical block replaced.
+    undo: HashMap<BlockId, Undo>,
+}
+
+impl ForkChoice {
+    /// Starts from the block `root` at `height`, with state `objects`. The
+    /// root can not be reorged.
+    #[must_use]
+    pub fn new(root: BlockId, height: u64, objects: BTreeMap<StateAddress, ObjectState>) -> Self {
+        let node = Node {
+            parent: None,
+            height,
+            commit: None,
+        };
+        Self {
+            blocks: HashMap::from([(root, node)]),
+            head: root,
+            objects,
+            undo: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn head(&self) -> BlockId { self.head }
+
+    #[must_use]
+    pub fn height(&self) -> u64 { self.blocks[&self.head].height }
+
+    #[must_use]
+    pub fn contains(&self, id: &BlockId) -> bool { self.blocks.contains_key(id) }
+
+    /// The state at the head of the canonical chain.
+    #[must_use]
+    pub fn object(&self, address: &StateAddress) -> Option<ObjectState> {
+        self.objects.get(address).copied()
+    }
+
+    /// Adds a block received from a proposer, and switches to its branch if
+    /// it is now the longest.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the block is already known, its parent is not, or
+    /// its height does not follow its parent.
+    pub fn insert(
+        &mut self,
+        id: BlockId,
+        parent: BlockId,
+        commit: BlockCommit,
+    ) -> Result<Option<HeadChange>> {
+        ensure!(!self.contains(&id), "block {:?} is already known", id.0);
+        let parent_height = self
+            .blocks
+            .get(&parent)
+            .with_context(|| format!("parent {:?} is not known", parent.0))?
+            .height;
+        let height = commit.header.height;
+        ensure!(
+            height == parent_height + 1,
+            "block at height {height} can not follow block at height {parent_height}"
+        );
+        self.blocks.insert(id, Node {
+            parent: Some(parent),
+            height,
+            commit: Some(commit),
+        });
+
+        if height <= self.height() {
+            return Ok(None);
+        }
+        Ok(Some(self.switch_to(id)))
+    }
+
+    fn switch_to(&mut self, new_head: BlockId) -> HeadChange {
+        let mut reverted = Vec::new();
+        let mut applied = Vec::new();
+        let (mut old, mut new) = (self.head, new_head);
+        while old != new {
+            if self.blocks[&new].height > self.blocks[&old].height {
+                applied.push(new);
+                new = self.blocks[&new]
+                    .parent
+                    .expect("the root is the lowest block");
+            } else {
+                reverted.push(old);
+                old = self.blocks[&old]
+                    .parent
+                    .expect("the root is the lowest block");
+            }
+        }
+        applied.reverse();
+
+        for id in &reverted {
+            for (address, object) in self.undo.remove(id).unwrap_or_default().into_iter().rev() {
+                self.set(address, object);
+            }
+        }
+        for id in &applied {
+            let changes = self.blocks[id]
+                .commit
+                .as_ref()
+                .map(|commit| commit.objects.clone())
+                .unwrap_or_default();
+            let undo = changes
+                .into_iter()
+                .map(|(address, object)| (address, self.set(address, object)))
+                .collect();
+            self.undo.insert(*id, undo);
+        }
+        self.head = new_head;
+
+        HeadChange {
+            ancestor: old,
+            reverted,
+            applied,
+        }
+    }
+
+    fn set(&mut self, address: StateAddress, object: Option<ObjectState>) -> Option<ObjectState> {
+        match object {
+            Some(object) => self.objects.insert(address, object),
+            None => self.objects.remove(&address),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::storage::test::block;
+
+    fn id(n: u64) -> BlockId { BlockId(Poseidon2Hash::from([n; 4])) }
+
+    /// A block at `height` that sets the credits of one object to `credits`.
+    fn commit(height: u64, credits: u64) -> BlockCommit {
+        let mut commit = block(height);
+        commit.objects = vec![(
+            StateAddress([0; 8]),
+            Some(ObjectState {
+                credits,
+                ..ObjectState::default()
+            }),
+        )];
+        commit
+    }
+
+    fn credits(fork_choice: &ForkChoice) -> Option<u64> {
+        fork_choice
+            .object(&StateAddress([0; 8]))
+            .map(|object| object.credits)
+    }
+
+    #[test]
+    fn reorg() {
+        let mut fork_choice = ForkChoice::new(id(0), 0, BTreeMap::new());
+        assert!(fork_choice.insert(id(1), id(9), commit(1, 1)).is_err());
+        assert!(fork_choice.insert(id(1), id(0), commit(2, 1)).is_err());
+
+        let change = fork_choice.insert(id(1), id(0), commit(1, 1)).unwrap();
+        assert!(!change.unwrap().is_reorg());
+        assert_eq!(credits(&fork_choice), Some(1));
+
+        // Ties go to the block seen first.
+        assert_eq!(
+            fork_choice.insert(id(2), id(0), commit(1, 2)).unwrap(),
+            None
+        );
+        assert_eq!(fork_choice.head(), id(1));
+
+        let change = fork_choice.insert(id(3), id(2), commit(2, 3)).unwrap();
+        assert_eq!(
+            change,
+            Some(HeadChange {
+                ancestor: id(0),
+                reverted: vec![id(1)],
+                applied: vec![id(2), id(3)],
+            })
+        );
+        assert_eq!(fork_choice.head(), id(3));
+        assert_eq!(credits(&fork_choice), Some(3));
+
+        let mut delete = block(2);
+        delete.objects = vec![(StateAddress([0; 8]), None)];
+        fork_choice.insert(id(4), id(1), delete).unwrap();
+        let change = fork_choice.insert(id(5), id(4), block(3)).unwrap().unwrap();
+        assert_eq!(change.reverted, vec![id(3), id(2)]);
+        assert_eq!(credits(&fork_choice), None);
+    }
+}
@@ -0,0 +1,238 @@
+//! The chain spec: everything needed to start a chain from scratch, read from
+//! a `genesis.toml` when the node starts.
+//!
+//! ```toml
+//! chain_id = 1
+//! tree_depth = 63
+//!
+//! [[objects]]
+//! address = "0x0100000000000000"
+//! constraint_owner = "0x0100000000000000020000000000000003000000000000000400000000000000"
+//! credits = 1000
+//!
+//! [[programs]]
+//! name = "token"
+//! id = "0x0100000000000000020000000000000003000000000000000400000000000000"
+//!
+//! [circuit_digests]
+//! block = "0x0000000000000000000000000000000000000000000000000000000000000000"
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, ensure, Context, Result};
+use itertools::Itertools;
+use mozak_sdk::common::types::{Poseidon2Hash, ProgramIdentifier, StateAddress};
+use plonky2::field::types::PrimeField64;
+use plonky2::plonk::circuit_data::VerifierOnlyCircuitData;
+use serde::{Deserialize, Serialize};
+
+use crate::rpc::ObjectState;
+use crate::sync::Snapshot;
+use crate::{C, D};
+
+/// An object in the initial state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisObject {
+    pub address: StateAddress,
+    #[serde(default)]
+    pub constraint_owner: ProgramIdentifier,
+    #[serde(default)]
+    pub credits: u64,
+    #[serde(default)]
+    pub data: [u64; 4],
+}
+
+impl GenesisObject {
+    #[must_use]
+    pub fn state(&self) -> ObjectState {
+        ObjectState {
+            constraint_owner: self.constraint_owner.0.to_u64s(),
+            last_updated: 0,
+            credits: self.credits,
+            data: self.data,
+        }
+    }
+}
+
+/// A program known from the start.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisProgram {
+    pub name: String,
+    pub id: ProgramIdentifier,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainSpec {
+    pub chain_id: u64,
+    /// Depth of the state tree.
+    pub tree_depth: usize,
+    #[serde(default)]
+    pub objects: Vec<GenesisObject>,
+    #[serde(default)]
+    pub programs: Vec<GenesisProgram>,
+    /// Digests of the circuits every node has to prove and verify with, by
+    /// name, so nodes with mismatched proving setups fail at startup.
+    #[serde(default)]
+    pub circuit_digests: BTreeMap<String, Poseidon2Hash>,
+}
+
+impl ChainSpec {
+    /// A spec for a test network with no objects or programs.
+    #[must_use]
+    pub fn template(chain_id: u64) -> Self {
+        Self {
+            chain_id,
+            tree_depth: 63,
+            objects: vec![],
+            programs: vec![],
+            circuit_digests: BTreeMap::new(),
+        }
+    }
+
+    /// Reads and validates a `genesis.toml`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can not be read, or is invalid.
+    pub fn load(path: &Path) -> Result<Self> {
+        let spec = fs::read_to_string(path).with_context(|| format!("reading {path:?}"))?;
+        Self::from_toml(&spec).with_context(|| format!("invalid chain spec {path:?}"))
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if `spec` is not a valid chain spec.
+    pub fn from_toml(spec: &str) -> Result<Self> {
+        let spec: Self = toml::from_str(spec)?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the spec can not be represented in TOML.
+    pub fn to_toml(&self) -> Result<String> { Ok(toml::to_string_pretty(self)?) }
+
+    /// # Errors
+    ///
+    /// Returns an error describing the first problem found.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(self.tree_depth <= 63, "the state tree is at most 63 deep");
+        if let Some(address) = self.objects.iter().map(|o| o.address).duplicates().next() {
+            bail!("object {address:?} is defined more than once");
+        }
+        if let Some(name) = self.programs.iter().map(|p| &p.name).duplicates().next() {
+            bail!("program {name:?} is defined more than once");
+        }
+        // Checks that all addresses fit in the tree.
+        self.snapshot().root()?;
+        Ok(())
+    }
+
+    /// The initial state, as if synced at height 0.
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            height: 0,
+            tree_depth: self.tree_depth,
+            objects: self
+                .objects
+                .iter()
+                .map(|o| (o.address, o.state()))
+                .sorted_by_key(|(address, _)| *address)
+                .collect(),
+        }
+    }
+
+    /// Looks up a program of the registry by name.
+    #[must_use]
+    pub fn program(&self, name: &str) -> Option<ProgramIdentifier> {
+        self.programs.iter().find(|p| p.name == name).map(|p| p.id)
+    }
+
+    /// Checks that the circuit this node uses for `name` is the one in the
+    /// spec. Circuits that are not in the spec are not checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the digests differ.
+    pub fn check_circuit(
+        &self,
+        name: &str,
+        verifier: &VerifierOnlyCircuitData<C, D>,
+    ) -> Result<()> {
+        let Some(expected) = self.circuit_digests.get(name) else {
+            return Ok(());
+        };
+        let actual = circuit_digest(verifier);
+        ensure!(
+            *expected == actual,
+            "circuit {name:?} has digest {actual:?}, but the chain spec expects {expected:?}"
+        );
+        Ok(())
+    }
+}
+
+/// The digest of a circuit, as written in the chain spec.
+#[must_use]
+pub fn circuit_digest(verifier: &VerifierOnlyCircuitData<C, D>) -> Poseidon2Hash {
+    Poseidon2Hash::from(
+        verifier
+            .circuit_digest
+            .elements
+            .map(|x| x.to_canonical_u64()),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SPEC: &str = r#"
+        chain_id = 7
+        tree_depth = 8
+
+        [[objects]]
+        address = "0x0100000000000000"
+        credits = 10
+
+        [[programs]]
+        name = "token"
+        id = "0x0100000000000000020000000000000003000000000000000400000000000000"
+    "#;
+
+    #[test]
+    fn round_trip() {
+        let spec = ChainSpec::from_toml(SPEC).unwrap();
+        assert_eq!(spec.chain_id, 7);
+        assert_eq!(spec.objects[0].state().credits, 10);
+        assert_eq!(
+            spec.program("token"),
+            Some(ProgramIdentifier(Poseidon2Hash::from([1, 2, 3, 4])))
+        );
+        assert_eq!(
+            ChainSpec::from_toml(&spec.to_toml().unwrap()).unwrap(),
+            spec
+        );
+
+        let template = ChainSpec::template(1);
+        assert_eq!(
+            ChainSpec::from_toml(&template.to_toml().unwrap()).unwrap(),
+            template
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let mut spec = ChainSpec::from_toml(SPEC).unwrap();
+        spec.objects.push(spec.objects[0]);
+        assert!(spec.validate().is_err());
+
+        let mut spec = ChainSpec::from_toml(SPEC).unwrap();
+        spec.objects[0].address = StateAddress([1; 8]);
+        assert!(spec.validate().is_err());
+    }
+}
@@ -6,7 +6,18 @@
 use plonky2::plonk::config::{GenericConfig, Poseidon2GoldilocksConfig};
 
 pub mod block_proposer;
+pub mod config;
+pub mod fees;
+pub mod fork_choice;
+pub mod genesis;
+pub mod mempool;
+pub mod metrics;
+pub mod rpc;
+pub mod sequencer;
+pub mod storage;
+pub mod sync;
 pub mod types;
+pub mod verification;
 
 pub const D: usize = 2;
 pub type C = Poseidon2GoldilocksConfig;
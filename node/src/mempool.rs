@@ -0,0 +1,338 @@
+//! A pool of [`Transaction`]s waiting to be picked up by the block proposer.
+//!
+//! Transactions are validated on the way in, without looking at the state,
+//! and are handed out in the order they arrived.
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, ensure, Result};
+use hashbrown::HashMap;
+use itertools::Itertools;
+use mozak_sdk::common::merkle::merkleize;
+use mozak_sdk::common::types::{Poseidon2Hash, ProgramIdentifier};
+use mozak_sdk::native::poseidon::poseidon2_hash_with_pad;
+use plonky2::field::types::PrimeField64;
+use serde::{Deserialize, Serialize};
+
+use crate::fees::CreditChanges;
+use crate::types::{Attestation, Transaction};
+use crate::{C, D, F};
+
+pub type Tx = Transaction<F, C, D>;
+
+/// Identifies a transaction by the hash of its contents.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TxId(pub Poseidon2Hash);
+
+impl TxId {
+    /// Computes the id of `tx`, which commits to its cast list, call tape and
+    /// the tapes of all of its attestations.
+    #[must_use]
+    pub fn of(tx: &Tx) -> Self {
+        let cast_list = tx.cast_list.iter().flat_map(ProgramIdentifier::inner);
+        let call_tape = tx
+            .call_tape_hash
+            .0
+            .iter()
+            .flat_map(|hash| hash.elements)
+            .flat_map(|x| x.to_canonical_u64().to_le_bytes());
+        let attestations = tx.constituent_zs.iter().flat_map(|z| {
+            let (public_tape, event_tape) = tape_hashes(z);
+            [z.id.inner(), public_tape.inner(), event_tape.inner()]
+                .into_iter()
+                .flatten()
+        });
+        let bytes = cast_list.chain(call_tape).chain(attestations).collect_vec();
+        Self(poseidon2_hash_with_pad(&bytes))
+    }
+}
+
+/// Returns the hashes of the public tape and the event tape of an
+/// [`Attestation`].
+///
+/// The event tape is hashed the same way as the events commitment tape of the
+/// program that emitted the events.
+#[must_use]
+pub fn tape_hashes(attestation: &Attestation) -> (Poseidon2Hash, Poseidon2Hash) {
+    let public_tape = poseidon2_hash_with_pad(&attestation.public_tape);
+    let event_tape = merkleize(
+        attestation
+            .event_tape
+            .get_canonical_order_temporal_hints()
+            .iter()
+            .map(|x| {
+                (
+                    u64::from_le_bytes(x.0.address.inner()),
+                    x.0.canonical_hash(),
+                )
+            })
+            .collect(),
+    );
+    (public_tape, event_tape)
+}
+
+/// Checks the parts of a transaction that do not depend on the state.
+///
+/// # Errors
+///
+/// Returns an error describing the first inconsistency found.
+pub fn validate(tx: &Tx) -> Result<()> {
+    ensure!(!tx.cast_list.is_empty(), "cast list is empty");
+    ensure!(
+        tx.cast_list.iter().tuple_windows().all(|(a, b)| a < b),
+        "cast list is not sorted, or has duplicates"
+    );
+    ensure!(
+        !tx.cast_list.iter().any(ProgramIdentifier::is_null_program),
+        "cast list contains the null program"
+    );
+
+    ensure!(
+        !tx.constituent_zs.is_empty(),
+        "transaction has no attestations"
+    );
+    if let Some(id) = tx.constituent_zs.iter().map(|z| z.id).duplicates().next() {
+        bail!("{id:?} is attested to more than once");
+    }
+    if let Some(z) = tx
+        .constituent_zs
+        .iter()
+        .find(|z| tx.cast_list.binary_search(&z.id).is_err())
+    {
+        bail!("{:?} is attested to, but is not in the cast list", z.id);
+    }
+    // Whether the fee payers hold the credits is checked against the state
+    // when the transaction is pushed into a block.
+    CreditChanges::of(tx)?;
+    Ok(())
+}
+
+/// Verifies the proofs carried by a transaction.
+pub trait ProofVerifier {
+    /// # Errors
+    ///
+    /// Returns an error if any proof does not verify.
+    fn verify(&self, tx: &Tx) -> Result<()>;
+}
+
+impl<T: Fn(&Tx) -> Result<()>> ProofVerifier for T {
+    fn verify(&self, tx: &Tx) -> Result<()> { self(tx) }
+}
+
+/// What to do with a new transaction when the mempool is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvictionPolicy {
+    /// Reject the new transaction.
+    #[default]
+    RejectNew,
+    /// Make room by dropping the transaction that has waited the longest.
+    EvictOldest,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MempoolConfig {
+    /// The maximum number of transactions held at once.
+    pub max_transactions: usize,
+    pub eviction: EvictionPolicy,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            max_transactions: 10_000,
+            eviction: EvictionPolicy::default(),
+        }
+    }
+}
+
+pub struct Mempool<V> {
+    config: MempoolConfig,
+    verifier: V,
+    /// Arrival number of the next transaction.
+    next_seq: u64,
+    txs: HashMap<TxId, (u64, Tx)>,
+    /// Transactions by arrival.
+    order: BTreeMap<u64, TxId>,
+}
+
+impl<V: ProofVerifier> Mempool<V> {
+    #[must_use]
+    pub fn new(config: MempoolConfig, verifier: V) -> Self {
+        Self {
+            config,
+            verifier,
+            next_seq: 0,
+            txs: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize { self.txs.len() }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.txs.is_empty() }
+
+    #[must_use]
+    pub fn contains(&self, id: &TxId) -> bool { self.txs.contains_key(id) }
+
+    #[must_use]
+    pub fn get(&self, id: &TxId) -> Option<&Tx> { self.txs.get(id).map(|(_, tx)| tx) }
+
+    /// Validates `tx` and adds it to the pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `tx` is invalid, already in the pool, or if the
+    /// pool is full and the eviction policy is to reject new transactions.
+    pub fn insert(&mut self, tx: Tx) -> Result<TxId> {
+        let id = TxId::of(&tx);
+        ensure!(
+            !self.contains(&id),
+            "transaction {:?} is already pending",
+            id.0
+        );
+        validate(&tx)?;
+        self.verifier.verify(&tx)?;
+        self.insert_verified(id, tx)
+    }
+
+    /// Adds `tx`, with id `id`, to the pool without validating it, for
+    /// transactions that were checked elsewhere, eg by a
+    /// [`VerificationPool`](crate::verification::VerificationPool).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `tx` is already in the pool, or if the pool is full
+    /// and the eviction policy is to reject new transactions.
+    pub fn insert_verified(&mut self, id: TxId, tx: Tx) -> Result<TxId> {
+        ensure!(
+            !self.contains(&id),
+            "transaction {:?} is already pending",
+            id.0
+        );
+        if self.len() >= self.config.max_transactions {
+            match self.config.eviction {
+                EvictionPolicy::RejectNew => bail!("mempool is full"),
+                EvictionPolicy::EvictOldest => {
+                    let Some((_, oldest)) = self.order.pop_first() else {
+                        bail!("mempool can not hold any transactions")
+                    };
+                    self.txs.remove(&oldest);
+                }
+            }
+        }
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.txs.insert(id, (seq, tx));
+        self.order.insert(seq, id);
+        Ok(id)
+    }
+
+    /// Removes a transaction, eg because it was included in a block.
+    pub fn remove(&mut self, id: &TxId) -> Option<Tx> {
+        let (seq, tx) = self.txs.remove(id)?;
+        self.order.remove(&seq);
+        Some(tx)
+    }
+
+    /// Iterates over the pending transactions, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = (TxId, &Tx)> {
+        self.order.values().map(|id| (*id, &self.txs[id].1))
+    }
+
+    /// Removes and returns up to `n` of the oldest transactions, for the
+    /// block proposer.
+    pub fn take(&mut self, n: usize) -> Vec<(TxId, Tx)> {
+        let ids = self.order.values().take(n).copied().collect_vec();
+        ids.into_iter()
+            .filter_map(|id| self.remove(&id).map(|tx| (id, tx)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use mozak_sdk::native::OrderedEvents;
+    use plonky2::hash::hash_types::HashOut;
+    use plonky2::hash::merkle_tree::MerkleCap;
+
+    use super::*;
+
+    fn program(n: u64) -> ProgramIdentifier { ProgramIdentifier(Poseidon2Hash::from([n; 4])) }
+
+    fn tx(cast_list: &[u64], public_tape: &[u8]) -> Tx {
+        Transaction {
+            cast_list: cast_list.iter().copied().map(program).collect(),
+            call_tape_hash: MerkleCap(vec![HashOut::ZERO]),
+            constituent_zs: vec![Attestation {
+                id: program(cast_list[0]),
+                public_tape: public_tape.to_vec(),
+                event_tape: OrderedEvents::default(),
+            }],
+        }
+    }
+
+    fn accept_all(_: &Tx) -> Result<()> { Ok(()) }
+
+    #[test]
+    fn validation() {
+        assert!(validate(&tx(&[1, 2], b"")).is_ok());
+        assert!(validate(&tx(&[2, 1], b"")).is_err());
+        assert!(validate(&tx(&[1, 1], b"")).is_err());
+        assert!(validate(&tx(&[0, 1], b"")).is_err());
+
+        let mut not_cast = tx(&[1, 2], b"");
+        not_cast.constituent_zs[0].id = program(3);
+        assert!(validate(&not_cast).is_err());
+    }
+
+    #[test]
+    fn dedup_and_order() {
+        let mut mempool = Mempool::new(MempoolConfig::default(), accept_all);
+        let a = mempool.insert(tx(&[1], b"a")).unwrap();
+        let b = mempool.insert(tx(&[1], b"b")).unwrap();
+        assert_ne!(a, b);
+        assert!(mempool.insert(tx(&[1], b"a")).is_err());
+
+        assert_eq!(mempool.iter().map(|(id, _)| id).collect_vec(), vec![a, b]);
+        assert_eq!(
+            mempool.take(1).into_iter().map(|(id, _)| id).collect_vec(),
+            vec![a]
+        );
+        assert_eq!(mempool.len(), 1);
+        assert!(mempool.contains(&b));
+    }
+
+    #[test]
+    fn eviction() {
+        let config = MempoolConfig {
+            max_transactions: 1,
+            eviction: EvictionPolicy::RejectNew,
+        };
+        let mut mempool = Mempool::new(config, accept_all);
+        let a = mempool.insert(tx(&[1], b"a")).unwrap();
+        assert!(mempool.insert(tx(&[1], b"b")).is_err());
+        assert!(mempool.contains(&a));
+
+        let config = MempoolConfig {
+            eviction: EvictionPolicy::EvictOldest,
+            ..config
+        };
+        let mut mempool = Mempool::new(config, accept_all);
+        let a = mempool.insert(tx(&[1], b"a")).unwrap();
+        let b = mempool.insert(tx(&[1], b"b")).unwrap();
+        assert!(!mempool.contains(&a));
+        assert!(mempool.contains(&b));
+    }
+
+    #[test]
+    fn proof_verification() {
+        let mut mempool = Mempool::new(MempoolConfig::default(), |_: &Tx| -> Result<()> {
+            bail!("bad proof")
+        });
+        assert!(mempool.insert(tx(&[1], b"")).is_err());
+        assert!(mempool.is_empty());
+    }
+}
@@ -0,0 +1,234 @@
+//! Metrics for operators, exported in the Prometheus text format over
+//! `GET /metrics`.
+//!
+//! The metrics are plain atomics, so the node can update them from any thread
+//! without coordinating with the exporter.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    pub fn set(&self, value: i64) { self.0.store(value, Ordering::Relaxed); }
+
+    pub fn add(&self, delta: i64) { self.0.fetch_add(delta, Ordering::Relaxed); }
+
+    #[must_use]
+    pub fn get(&self) -> i64 { self.0.load(Ordering::Relaxed) }
+}
+
+/// Upper bounds of the histogram buckets, in seconds. Proving is slow, so they
+/// go up to several minutes.
+pub const BUCKETS: [f64; 12] = [
+    0.001, 0.01, 0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+/// A histogram of durations.
+#[derive(Debug, Default)]
+pub struct Histogram {
+    /// Observations per bucket, with the last one for those above all bounds.
+    counts: [AtomicU64; BUCKETS.len() + 1],
+    /// Sum of all observations, in microseconds.
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    pub fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let bucket = BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(BUCKETS.len());
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+        let micros = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+        self.sum_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    /// Writes the histogram `name`, with the extra `labels`, which are either
+    /// empty or end with a comma.
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut count = 0;
+        for (i, bound) in BUCKETS.iter().enumerate() {
+            count += self.counts[i].load(Ordering::Relaxed);
+            let _ = writeln!(out, "{name}_bucket{{{labels}le=\"{bound}\"}} {count}");
+        }
+        count += self.counts[BUCKETS.len()].load(Ordering::Relaxed);
+        let _ = writeln!(out, "{name}_bucket{{{labels}le=\"+Inf\"}} {count}");
+        #[allow(clippy::cast_precision_loss)]
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        let labels = match labels.trim_end_matches(',') {
+            "" => String::new(),
+            labels => format!("{{{labels}}}"),
+        };
+        let _ = writeln!(out, "{name}_sum{labels} {sum}");
+        let _ = writeln!(out, "{name}_count{labels} {count}");
+    }
+}
+
+/// Metrics that are split by a label, eg by the kind of table a proof is of.
+#[derive(Debug)]
+pub struct Family<M> {
+    label: &'static str,
+    metrics: Mutex<BTreeMap<String, Arc<M>>>,
+}
+
+impl<M: Default> Family<M> {
+    #[must_use]
+    pub fn new(label: &'static str) -> Self {
+        Self {
+            label,
+            metrics: Mutex::default(),
+        }
+    }
+
+    /// Returns the metric for `value` of the label, creating it if needed.
+    #[must_use]
+    pub fn get(&self, value: &str) -> Arc<M> {
+        let mut metrics = self.metrics.lock().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(metrics.entry(value.to_string()).or_default())
+    }
+
+    fn snapshot(&self) -> Vec<(String, Arc<M>)> {
+        let metrics = self.metrics.lock().unwrap_or_else(PoisonError::into_inner);
+        metrics
+            .iter()
+            .map(|(value, metric)| (value.clone(), Arc::clone(metric)))
+            .collect()
+    }
+}
+
+/// Everything the node reports.
+#[derive(Debug)]
+pub struct Metrics {
+    /// Transactions waiting in the mempool.
+    pub mempool_depth: Gauge,
+    /// Time from opening a block to its proof being done.
+    pub block_production: Histogram,
+    /// Time spent verifying proofs, by the kind of proof.
+    pub proof_verification: Family<Histogram>,
+    /// Objects in the state tree.
+    pub state_objects: Gauge,
+    pub peers: Gauge,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            mempool_depth: Gauge::default(),
+            block_production: Histogram::default(),
+            proof_verification: Family::new("kind"),
+            state_objects: Gauge::default(),
+            peers: Gauge::default(),
+        }
+    }
+}
+
+impl Metrics {
+    /// Renders all metrics in the Prometheus text format.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let gauges = [
+            (
+                "mozak_mempool_depth",
+                "Transactions waiting in the mempool.",
+                &self.mempool_depth,
+            ),
+            (
+                "mozak_state_objects",
+                "Objects in the state tree.",
+                &self.state_objects,
+            ),
+            ("mozak_peers", "Connected peers.", &self.peers),
+        ];
+        for (name, help, gauge) in gauges {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} gauge");
+            let _ = writeln!(out, "{name} {}", gauge.get());
+        }
+
+        let name = "mozak_block_production_seconds";
+        let _ = writeln!(out, "# HELP {name} Time to produce a block.");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        self.block_production.render(&mut out, name, "");
+
+        let name = "mozak_proof_verification_seconds";
+        let _ = writeln!(out, "# HELP {name} Time to verify a proof.");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        let label = self.proof_verification.label;
+        for (value, histogram) in self.proof_verification.snapshot() {
+            histogram.render(&mut out, name, &format!("{label}=\"{value}\","));
+        }
+        out
+    }
+}
+
+/// Serves `GET /metrics` on `listener` forever.
+///
+/// # Errors
+///
+/// Returns an error if accepting a connection fails.
+pub fn serve(listener: &TcpListener, metrics: &Arc<Metrics>) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let metrics = Arc::clone(metrics);
+        thread::spawn(move || {
+            // A broken connection only affects its own client.
+            let _ = handle_connection(stream, &metrics);
+        });
+    }
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, metrics: &Metrics) -> io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&mut stream).read_line(&mut request_line)?;
+    let (status, body) = if request_line.starts_with("GET /metrics ") {
+        ("200 OK", metrics.render())
+    } else {
+        ("404 Not Found", String::new())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: \
+         {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn render() {
+        let metrics = Metrics::default();
+        metrics.mempool_depth.set(3);
+        metrics.block_production.observe(Duration::from_secs(2));
+        let cpu = metrics.proof_verification.get("cpu");
+        cpu.observe(Duration::from_millis(5));
+        cpu.observe(Duration::from_secs(1000));
+
+        let out = metrics.render();
+        assert!(out.contains("mozak_mempool_depth 3\n"));
+        assert!(out.contains("mozak_block_production_seconds_bucket{le=\"1\"} 0\n"));
+        assert!(out.contains("mozak_block_production_seconds_bucket{le=\"2.5\"} 1\n"));
+        assert!(out.contains("mozak_block_production_seconds_sum 2\n"));
+        assert!(
+            out.contains("mozak_proof_verification_seconds_bucket{kind=\"cpu\",le=\"0.01\"} 1\n")
+        );
+        assert!(
+            out.contains("mozak_proof_verification_seconds_bucket{kind=\"cpu\",le=\"+Inf\"} 2\n")
+        );
+        assert!(out.contains("mozak_proof_verification_seconds_count{kind=\"cpu\"} 2\n"));
+    }
+}
@@ -0,0 +1,692 @@
+//! A JSON-RPC 2.0 interface to the node, for wallets and dApp frontends.
+//!
+//! [`Rpc`] decodes requests and dispatches them to a [`NodeApi`], which is
+//! implemented by whatever holds the chain. Event subscriptions are kept by
+//! [`Rpc`] itself: clients subscribe with a filter and then poll for the
+//! events that were published since. Clients can subscribe to reorgs of the
+//! canonical chain the same way. Over a WebSocket, clients can instead have
+//! the events of finalized blocks pushed to them; see [`ws`].
+//!
+//! | Method                    | Params                  | Result                       |
+//! |---------------------------|-------------------------|------------------------------|
+//! | `mozak_submitTransaction` | `[Transaction]`         | `TxId`                       |
+//! | `mozak_getObject`         | `[StateAddress]`        | `ObjectState` or `null`      |
+//! | `mozak_getBlock`          | `[height]`              | `BlockSummary` or `null`     |
+//! | `mozak_getReceipt`        | `[TxId]`                | `Receipt` or `null`          |
+//! | `mozak_getSnapshot`       | `[]`                    | `Snapshot` or `null`         |
+//! | `mozak_listBlocks`        | `[from, limit]`         | `[BlockSummary]`, newest first |
+//! | `mozak_getTransaction`    | `[TxId]`                | `TxRecord` or `null`         |
+//! | `mozak_getEventsByAddress`| `[StateAddress]`        | `[[height, TxId]]`           |
+//! | `mozak_getEventsByProgram`| `[ProgramIdentifier]`   | `[[height, TxId]]`           |
+//! | `mozak_getObjectHistory`  | `[StateAddress]`        | `[[height, ObjectState or null]]` |
+//! | `mozak_hasBlockProof`     | `[height]`              | `true` if the proof is kept  |
+//! | `mozak_subscribeEvents`   | `[EventFilter]`         | subscription id              |
+//! | `mozak_pollEvents`        | `[subscription id]`     | `[EventNotification]`        |
+//! | `mozak_subscribeReorgs`   | `[]`                    | subscription id              |
+//! | `mozak_pollReorgs`        | `[subscription id]`     | `[HeadChange]`               |
+//! | `mozak_unsubscribe`       | `[subscription id]`     | `true` if it was subscribed  |
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::mpsc::Sender;
+
+use anyhow::Result;
+use mozak_sdk::common::types::{CanonicalEvent, EventType, ProgramIdentifier, StateAddress};
+use plonky2::field::types::{Field, PrimeField64};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::fork_choice::HeadChange;
+use crate::mempool::{Tx, TxId};
+use crate::storage::{Storage, TxRecord};
+use crate::sync::Snapshot;
+use crate::F;
+
+pub mod http;
+pub mod ws;
+
+/// The state of an object, as returned by `mozak_getObject`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectState {
+    pub constraint_owner: [u64; 4],
+    pub last_updated: u64,
+    pub credits: u64,
+    pub data: [u64; 4],
+}
+
+impl From<&mozak_recproofs::Object<F>> for ObjectState {
+    fn from(object: &mozak_recproofs::Object<F>) -> Self {
+        Self {
+            constraint_owner: object.constraint_owner.map(|x| x.to_canonical_u64()),
+            last_updated: object.last_updated.to_canonical_u64(),
+            credits: object.credits.to_canonical_u64(),
+            data: object.data.map(|x| x.to_canonical_u64()),
+        }
+    }
+}
+
+impl From<ObjectState> for mozak_recproofs::Object<F> {
+    fn from(object: ObjectState) -> Self {
+        Self {
+            constraint_owner: object.constraint_owner.map(F::from_noncanonical_u64),
+            last_updated: F::from_noncanonical_u64(object.last_updated),
+            credits: F::from_noncanonical_u64(object.credits),
+            data: object.data.map(F::from_noncanonical_u64),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockSummary {
+    pub height: u64,
+    /// Transactions included in the block, in order.
+    pub transactions: Vec<TxId>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Receipt {
+    /// Waiting in the mempool.
+    Pending,
+    /// Included in the block at `height`.
+    Included { height: u64 },
+    /// Dropped, and will not be included.
+    Rejected { reason: String },
+}
+
+/// The chain, as seen by the RPC interface.
+pub trait NodeApi {
+    /// Submits a transaction bundle for inclusion.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transaction is not accepted.
+    fn submit_transaction(&mut self, tx: Tx) -> Result<TxId>;
+
+    /// Returns the current state of the object at `address`, if it exists.
+    fn object(&self, address: StateAddress) -> Option<ObjectState>;
+
+    fn block(&self, height: u64) -> Option<BlockSummary>;
+
+    fn receipt(&self, tx: &TxId) -> Option<Receipt>;
+
+    /// Returns a snapshot of the state at the latest block, for new nodes to
+    /// sync from. Nodes that don't serve snapshots return `None`.
+    fn snapshot(&self) -> Option<Snapshot> { None }
+
+    /// The chain as seen by explorers. Nodes that don't keep the history of
+    /// the chain return `None`.
+    fn explorer(&self) -> Option<&dyn Storage> { None }
+}
+
+/// The most blocks `mozak_listBlocks` returns at once.
+pub const MAX_BLOCKS_PER_PAGE: usize = 100;
+
+/// Selects the events a subscription is notified of. Unset fields match
+/// everything.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventFilter {
+    pub program: Option<ProgramIdentifier>,
+    pub address: Option<StateAddress>,
+    pub event_type: Option<EventType>,
+}
+
+impl EventFilter {
+    #[must_use]
+    pub fn matches(&self, program: &ProgramIdentifier, event: &CanonicalEvent) -> bool {
+        self.program.map_or(true, |p| p == *program)
+            && self.address.map_or(true, |a| a == event.address)
+            && self.event_type.map_or(true, |t| t == event.type_)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventNotification {
+    pub tx: TxId,
+    pub program: ProgramIdentifier,
+    pub event: CanonicalEvent,
+}
+
+/// An event of a finalized block, as pushed to WebSocket subscribers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FinalizedEvent {
+    pub height: u64,
+    #[serde(flatten)]
+    pub event: EventNotification,
+}
+
+/// Events are dropped, oldest first, once this many are waiting to be polled
+/// by a single subscription.
+pub const MAX_PENDING_NOTIFICATIONS: usize = 1024;
+
+struct Subscription {
+    filter: EventFilter,
+    pending: VecDeque<EventNotification>,
+}
+
+/// A JSON-RPC error object.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub const INTERNAL_ERROR: i64 = -32603;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const PARSE_ERROR: i64 = -32700;
+    /// The node refused to do what was asked, eg to accept a transaction.
+    pub const REJECTED: i64 = -32000;
+
+    fn new(code: i64, message: String) -> Self { Self { code, message } }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    /// Absent for notifications, which get no response.
+    id: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+    id: Value,
+}
+
+pub struct Rpc<N> {
+    node: N,
+    next_subscription: u64,
+    subscriptions: BTreeMap<u64, Subscription>,
+    reorg_subscriptions: BTreeMap<u64, VecDeque<HeadChange>>,
+    /// Subscriptions of WebSocket clients, with where to send their
+    /// notifications to.
+    push_subscriptions: BTreeMap<u64, (EventFilter, Sender<String>)>,
+}
+
+impl<N: NodeApi> Rpc<N> {
+    #[must_use]
+    pub fn new(node: N) -> Self {
+        Self {
+            node,
+            next_subscription: 0,
+            subscriptions: BTreeMap::new(),
+            reorg_subscriptions: BTreeMap::new(),
+            push_subscriptions: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn node(&self) -> &N { &self.node }
+
+    pub fn node_mut(&mut self) -> &mut N { &mut self.node }
+
+    /// Notifies subscribers of the events of `tx`. To be called by the node
+    /// once `tx` is included in a block.
+    pub fn publish(&mut self, id: TxId, tx: &Tx) {
+        for z in &tx.constituent_zs {
+            for hint in z.event_tape.get_canonical_order_temporal_hints() {
+                let event = hint.0;
+                for subscription in self.subscriptions.values_mut() {
+                    if !subscription.filter.matches(&z.id, &event) {
+                        continue;
+                    }
+                    if subscription.pending.len() == MAX_PENDING_NOTIFICATIONS {
+                        subscription.pending.pop_front();
+                    }
+                    subscription.pending.push_back(EventNotification {
+                        tx: id,
+                        program: z.id,
+                        event,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Notifies subscribers of a reorg. To be called by the node when the
+    /// fork choice reverts canonical blocks.
+    pub fn publish_reorg(&mut self, change: &HeadChange) {
+        for pending in self.reorg_subscriptions.values_mut() {
+            if pending.len() == MAX_PENDING_NOTIFICATIONS {
+                pending.pop_front();
+            }
+            pending.push_back(change.clone());
+        }
+    }
+
+    /// Pushes the events of the transactions of a block to the WebSocket
+    /// subscribers whose filters match. To be called by the node once the
+    /// block at `height` is final. Subscribers that went away are dropped.
+    pub fn publish_block(&mut self, height: u64, transactions: &[TxRecord]) {
+        self.push_subscriptions.retain(|id, (filter, sink)| {
+            transactions
+                .iter()
+                .flat_map(|tx| {
+                    tx.events
+                        .iter()
+                        .map(move |(program, event)| (tx.id, program, event))
+                })
+                .filter(|(_, program, event)| filter.matches(program, event))
+                .all(|(tx, program, event)| {
+                    let notification = FinalizedEvent {
+                        height,
+                        event: EventNotification {
+                            tx,
+                            program: *program,
+                            event: *event,
+                        },
+                    };
+                    sink.send(subscription_message(*id, &notification)).is_ok()
+                })
+        });
+    }
+
+    /// Registers a WebSocket client for the events matching `filter`, which
+    /// are sent to `sink` as JSON-RPC notifications.
+    pub fn subscribe_push(&mut self, filter: EventFilter, sink: Sender<String>) -> u64 {
+        let id = self.new_subscription_id();
+        self.push_subscriptions.insert(id, (filter, sink));
+        id
+    }
+
+    /// Returns whether `id` was subscribed.
+    pub fn unsubscribe(&mut self, id: u64) -> bool {
+        self.subscriptions.remove(&id).is_some()
+            || self.reorg_subscriptions.remove(&id).is_some()
+            || self.push_subscriptions.remove(&id).is_some()
+    }
+
+    fn new_subscription_id(&mut self) -> u64 {
+        let id = self.next_subscription;
+        self.next_subscription += 1;
+        id
+    }
+
+    /// Handles a serialized JSON-RPC request, or batch of requests, and
+    /// returns the serialized response, if any.
+    pub fn handle(&mut self, request: &str) -> Option<String> {
+        let response = match serde_json::from_str::<Value>(request) {
+            Err(e) => Some(error_response(
+                Value::Null,
+                RpcError::new(RpcError::PARSE_ERROR, e.to_string()),
+            )),
+            Ok(Value::Array(requests)) if !requests.is_empty() => {
+                let responses: Vec<_> = requests
+                    .into_iter()
+                    .filter_map(|request| self.handle_value(request))
+                    .collect();
+                (!responses.is_empty()).then(|| serde_json::to_value(responses).unwrap())
+            }
+            Ok(request) => self.handle_value(request),
+        };
+        response.map(|response| response.to_string())
+    }
+
+    fn handle_value(&mut self, request: Value) -> Option<Value> {
+        let request: Request = match serde_json::from_value(request) {
+            Ok(request) => request,
+            Err(e) =>
+                return Some(error_response(
+                    Value::Null,
+                    RpcError::new(RpcError::INVALID_REQUEST, e.to_string()),
+                )),
+        };
+        if request.jsonrpc != "2.0" {
+            return Some(error_response(
+                request.id.unwrap_or_default(),
+                RpcError::new(
+                    RpcError::INVALID_REQUEST,
+                    "only JSON-RPC 2.0 is supported".to_string(),
+                ),
+            ));
+        }
+        let result = self.dispatch(&request.method, request.params);
+        let id = request.id?;
+        Some(
+            serde_json::to_value(match result {
+                Ok(result) => Response {
+                    jsonrpc: "2.0",
+                    result: Some(result),
+                    error: None,
+                    id,
+                },
+                Err(error) => Response {
+                    jsonrpc: "2.0",
+                    result: None,
+                    error: Some(error),
+                    id,
+                },
+            })
+            .unwrap(),
+        )
+    }
+
+    fn dispatch(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "mozak_submitTransaction" => {
+                let (tx,): (Tx,) = parse_params(params)?;
+                let id = self
+                    .node
+                    .submit_transaction(tx)
+                    .map_err(|e| RpcError::new(RpcError::REJECTED, format!("{e:#}")))?;
+                to_value(id)
+            }
+            "mozak_getObject" => {
+                let (address,): (StateAddress,) = parse_params(params)?;
+                to_value(self.node.object(address))
+            }
+            "mozak_getBlock" => {
+                let (height,): (u64,) = parse_params(params)?;
+                to_value(self.node.block(height))
+            }
+            "mozak_getReceipt" => {
+                let (tx,): (TxId,) = parse_params(params)?;
+                to_value(self.node.receipt(&tx))
+            }
+            "mozak_getSnapshot" => to_value(self.node.snapshot()),
+            "mozak_listBlocks" => {
+                let (from, limit): (u64, usize) = parse_params(params)?;
+                let limit = limit.min(MAX_BLOCKS_PER_PAGE);
+                to_value(self.explorer()?.blocks(from, limit))
+            }
+            "mozak_getTransaction" => {
+                let (id,): (TxId,) = parse_params(params)?;
+                to_value(self.explorer()?.transaction(&id))
+            }
+            "mozak_getEventsByAddress" => {
+                let (address,): (StateAddress,) = parse_params(params)?;
+                to_value(self.explorer()?.events_at(address))
+            }
+            "mozak_getEventsByProgram" => {
+                let (program,): (ProgramIdentifier,) = parse_params(params)?;
+                to_value(self.explorer()?.events_of(&program))
+            }
+            "mozak_getObjectHistory" => {
+                let (address,): (StateAddress,) = parse_params(params)?;
+                to_value(self.explorer()?.history(address))
+            }
+            "mozak_hasBlockProof" => {
+                let (height,): (u64,) = parse_params(params)?;
+                to_value(self.explorer()?.has_proof(height))
+            }
+            "mozak_subscribeEvents" => {
+                let (filter,): (EventFilter,) = parse_params(params)?;
+                let id = self.new_subscription_id();
+                self.subscriptions.insert(id, Subscription {
+                    filter,
+                    pending: VecDeque::new(),
+                });
+                to_value(id)
+            }
+            "mozak_pollEvents" => {
+                let (id,): (u64,) = parse_params(params)?;
+                let subscription = self.subscriptions.get_mut(&id).ok_or_else(|| {
+                    RpcError::new(RpcError::INVALID_PARAMS, format!("no subscription {id}"))
+                })?;
+                to_value(subscription.pending.drain(..).collect::<Vec<_>>())
+            }
+            "mozak_unsubscribe" => {
+                let (id,): (u64,) = parse_params(params)?;
+                to_value(self.unsubscribe(id))
+            }
+            "mozak_subscribeReorgs" => {
+                let id = self.new_subscription_id();
+                self.reorg_subscriptions.insert(id, VecDeque::new());
+                to_value(id)
+            }
+            "mozak_pollReorgs" => {
+                let (id,): (u64,) = parse_params(params)?;
+                let pending = self.reorg_subscriptions.get_mut(&id).ok_or_else(|| {
+                    RpcError::new(RpcError::INVALID_PARAMS, format!("no subscription {id}"))
+                })?;
+                to_value(pending.drain(..).collect::<Vec<_>>())
+            }
+            _ => Err(RpcError::new(
+                RpcError::METHOD_NOT_FOUND,
+                format!("unknown method {method:?}"),
+            )),
+        }
+    }
+}
+
+impl<N: NodeApi> Rpc<N> {
+    fn explorer(&self) -> Result<&dyn Storage, RpcError> {
+        self.node.explorer().ok_or_else(|| {
+            RpcError::new(
+                RpcError::METHOD_NOT_FOUND,
+                "this node does not serve the explorer API".to_string(),
+            )
+        })
+    }
+}
+
+/// The notification a WebSocket subscriber gets for `result`.
+fn subscription_message(subscription: u64, result: &impl Serialize) -> String {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "mozak_subscription",
+        "params": { "subscription": subscription, "result": result },
+    })
+    .to_string()
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params)
+        .map_err(|e| RpcError::new(RpcError::INVALID_PARAMS, e.to_string()))
+}
+
+fn to_value(value: impl Serialize) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::new(RpcError::INTERNAL_ERROR, e.to_string()))
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    serde_json::to_value(Response {
+        jsonrpc: "2.0",
+        result: None,
+        error: Some(error),
+        id,
+    })
+    .unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::bail;
+    use mozak_sdk::common::types::Poseidon2Hash;
+    use serde_json::json;
+
+    use super::*;
+    use crate::fork_choice::BlockId;
+    use crate::storage::test::{block, PROGRAM};
+    use crate::storage::MemoryStorage;
+
+    #[derive(Default)]
+    struct MockNode {
+        objects: BTreeMap<StateAddress, ObjectState>,
+        storage: Option<MemoryStorage>,
+    }
+
+    impl NodeApi for MockNode {
+        fn submit_transaction(&mut self, _tx: Tx) -> Result<TxId> { bail!("not accepting") }
+
+        fn object(&self, address: StateAddress) -> Option<ObjectState> {
+            self.objects.get(&address).copied()
+        }
+
+        fn block(&self, height: u64) -> Option<BlockSummary> {
+            (height == 0).then(|| BlockSummary {
+                height,
+                transactions: vec![],
+            })
+        }
+
+        fn receipt(&self, _tx: &TxId) -> Option<Receipt> { None }
+
+        fn explorer(&self) -> Option<&dyn Storage> {
+            self.storage.as_ref().map(|s| s as &dyn Storage)
+        }
+    }
+
+    fn call(rpc: &mut Rpc<MockNode>, method: &str, params: Value) -> Value {
+        let request = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1});
+        serde_json::from_str(&rpc.handle(&request.to_string()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn queries() {
+        let address = StateAddress([1; 8]);
+        let object = ObjectState {
+            credits: 7,
+            ..ObjectState::default()
+        };
+        let mut rpc = Rpc::new(MockNode {
+            objects: BTreeMap::from([(address, object)]),
+            ..MockNode::default()
+        });
+
+        let response = call(&mut rpc, "mozak_getObject", json!([address]));
+        assert_eq!(response["result"], serde_json::to_value(object).unwrap());
+
+        let response = call(&mut rpc, "mozak_getBlock", json!([1]));
+        assert_eq!(response["result"], Value::Null);
+
+        let response = call(&mut rpc, "mozak_getBlock", json!([0]));
+        assert_eq!(response["result"]["height"], 0);
+
+        let response = call(&mut rpc, "mozak_getSnapshot", json!([]));
+        assert_eq!(response["result"], Value::Null);
+    }
+
+    #[test]
+    fn explorer() {
+        let mut rpc = Rpc::new(MockNode::default());
+        let response = call(&mut rpc, "mozak_listBlocks", json!([0, 10]));
+        assert_eq!(response["error"]["code"], RpcError::METHOD_NOT_FOUND);
+
+        let mut storage = MemoryStorage::default();
+        storage.commit(block(0)).unwrap();
+        storage.commit(block(1)).unwrap();
+        let mut rpc = Rpc::new(MockNode {
+            storage: Some(storage),
+            ..MockNode::default()
+        });
+
+        let response = call(&mut rpc, "mozak_listBlocks", json!([10, 10]));
+        assert_eq!(response["result"][0]["height"], 1);
+        assert_eq!(response["result"][1]["height"], 0);
+
+        let tx = block(1).transactions.remove(0);
+        let response = call(&mut rpc, "mozak_getTransaction", json!([tx.id]));
+        assert_eq!(response["result"], serde_json::to_value(&tx).unwrap());
+
+        let response = call(&mut rpc, "mozak_getEventsByProgram", json!([PROGRAM]));
+        assert_eq!(
+            response["result"],
+            json!([[0, block(0).header.transactions[0]], [1, tx.id]])
+        );
+
+        let address = block(1).objects[0].0;
+        let response = call(&mut rpc, "mozak_getObjectHistory", json!([address]));
+        assert_eq!(response["result"], json!([[1, block(1).objects[0].1]]));
+
+        let response = call(&mut rpc, "mozak_hasBlockProof", json!([1]));
+        assert_eq!(response["result"], true);
+    }
+
+    #[test]
+    fn errors() {
+        let mut rpc = Rpc::new(MockNode::default());
+
+        let response = call(&mut rpc, "mozak_nope", json!([]));
+        assert_eq!(response["error"]["code"], RpcError::METHOD_NOT_FOUND);
+
+        let response = call(&mut rpc, "mozak_getBlock", json!(["zero"]));
+        assert_eq!(response["error"]["code"], RpcError::INVALID_PARAMS);
+
+        let response: Value = serde_json::from_str(&rpc.handle("{").unwrap()).unwrap();
+        assert_eq!(response["error"]["code"], RpcError::PARSE_ERROR);
+
+        // Notifications get no response.
+        let notification = json!({"jsonrpc": "2.0", "method": "mozak_getBlock", "params": [0]});
+        assert_eq!(rpc.handle(&notification.to_string()), None);
+    }
+
+    #[test]
+    fn subscriptions() {
+        let mut rpc = Rpc::new(MockNode::default());
+        let response = call(&mut rpc, "mozak_subscribeEvents", json!([{}]));
+        let id = response["result"].clone();
+
+        let response = call(&mut rpc, "mozak_pollEvents", json!([id]));
+        assert_eq!(response["result"], json!([]));
+
+        let response = call(&mut rpc, "mozak_unsubscribe", json!([id]));
+        assert_eq!(response["result"], true);
+
+        let response = call(&mut rpc, "mozak_pollEvents", json!([id]));
+        assert_eq!(response["error"]["code"], RpcError::INVALID_PARAMS);
+    }
+
+    #[test]
+    fn push_subscriptions() {
+        let mut rpc = Rpc::new(MockNode::default());
+        let (sink, notifications) = std::sync::mpsc::channel();
+        let id = rpc.subscribe_push(
+            EventFilter {
+                event_type: Some(EventType::Write),
+                ..EventFilter::default()
+            },
+            sink.clone(),
+        );
+        let all = rpc.subscribe_push(EventFilter::default(), sink);
+
+        let tx = block(1).transactions.remove(0);
+        rpc.publish_block(1, &[tx.clone()]);
+        let notification: Value = serde_json::from_str(&notifications.recv().unwrap()).unwrap();
+        assert_eq!(notification["method"], "mozak_subscription");
+        assert_eq!(notification["params"]["subscription"], all);
+        assert_eq!(notification["params"]["result"]["height"], 1);
+        assert_eq!(notification["params"]["result"]["tx"], json!(tx.id));
+        assert!(notifications.try_recv().is_err());
+
+        let response = call(&mut rpc, "mozak_unsubscribe", json!([id]));
+        assert_eq!(response["result"], true);
+        // Subscribers that went away are dropped.
+        drop(notifications);
+        rpc.publish_block(2, &[tx]);
+        assert!(!rpc.unsubscribe(all));
+    }
+
+    #[test]
+    fn reorg_subscriptions() {
+        let mut rpc = Rpc::new(MockNode::default());
+        let response = call(&mut rpc, "mozak_subscribeReorgs", json!([]));
+        let id = response["result"].clone();
+
+        let block = |n| BlockId(Poseidon2Hash::from([n; 4]));
+        let change = HeadChange {
+            ancestor: block(0),
+            reverted: vec![block(1)],
+            applied: vec![block(2)],
+        };
+        rpc.publish_reorg(&change);
+
+        let response = call(&mut rpc, "mozak_pollReorgs", json!([id]));
+        assert_eq!(response["result"], json!([change]));
+        let response = call(&mut rpc, "mozak_pollReorgs", json!([id]));
+        assert_eq!(response["result"], json!([]));
+
+        let response = call(&mut rpc, "mozak_unsubscribe", json!([id]));
+        assert_eq!(response["result"], true);
+    }
+}
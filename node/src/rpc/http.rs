@@ -0,0 +1,89 @@
+//! Serves [`Rpc`] over HTTP/1.1, as JSON-RPC clients expect.
+//!
+//! This is deliberately minimal: one request per connection, `POST` only,
+//! and bodies must come with a `Content-Length`.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use super::{NodeApi, Rpc};
+
+/// Requests with larger bodies are refused.
+pub const MAX_BODY_SIZE: usize = 16 << 20;
+
+/// Accepts connections on `listener` forever, handling each on its own
+/// thread.
+///
+/// # Errors
+///
+/// Returns an error if accepting a connection fails.
+pub fn serve<N: NodeApi + Send + 'static>(
+    listener: &TcpListener,
+    rpc: &Arc<Mutex<Rpc<N>>>,
+) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let rpc = Arc::clone(rpc);
+        thread::spawn(move || {
+            // A broken connection only affects its own client.
+            let _ = handle_connection(stream, &rpc);
+        });
+    }
+    Ok(())
+}
+
+fn handle_connection<N: NodeApi>(stream: TcpStream, rpc: &Mutex<Rpc<N>>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut stream = stream;
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    if !request_line.starts_with("POST ") {
+        return respond(&mut stream, "405 Method Not Allowed", "");
+    }
+    let Some(content_length) = content_length.filter(|len| *len <= MAX_BODY_SIZE) else {
+        return respond(&mut stream, "411 Length Required", "");
+    };
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    let Ok(body) = String::from_utf8(body) else {
+        return respond(&mut stream, "400 Bad Request", "");
+    };
+
+    let response = rpc
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .handle(&body);
+    match response {
+        Some(response) => respond(&mut stream, "200 OK", &response),
+        None => respond(&mut stream, "204 No Content", ""),
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: \
+         close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
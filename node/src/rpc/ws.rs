@@ -0,0 +1,391 @@
+//! Serves [`Rpc`] over WebSocket, so that clients can have events pushed to
+//! them instead of polling.
+//!
+//! Every text message is a JSON-RPC request, answered like over HTTP, except
+//! for `mozak_subscribe`, which takes an [`EventFilter`] and returns a
+//! subscription id. The events of finalized blocks that match the filter are
+//! then sent as `mozak_subscription` notifications until the client calls
+//! `mozak_unsubscribe` or disconnects.
+//!
+//! Like the HTTP server this is deliberately minimal: no extensions, and
+//! messages larger than [`MAX_MESSAGE_SIZE`] close the connection.
+
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+
+use super::{EventFilter, NodeApi, Rpc, RpcError};
+
+/// Messages with larger payloads close the connection.
+pub const MAX_MESSAGE_SIZE: usize = 16 << 20;
+
+/// Appended to the key of the client to accept a connection, per RFC 6455.
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// Accepts connections on `listener` forever, handling each on its own
+/// thread.
+///
+/// # Errors
+///
+/// Returns an error if accepting a connection fails.
+pub fn serve<N: NodeApi + Send + 'static>(
+    listener: &TcpListener,
+    rpc: &Arc<Mutex<Rpc<N>>>,
+) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let rpc = Arc::clone(rpc);
+        thread::spawn(move || {
+            // A broken connection only affects its own client.
+            let _ = handle_connection(stream, &rpc);
+        });
+    }
+    Ok(())
+}
+
+fn handle_connection<N: NodeApi>(stream: TcpStream, rpc: &Mutex<Rpc<N>>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut key = None;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("sec-websocket-key") {
+                key = Some(value.trim().to_string());
+            }
+        }
+    }
+    let (true, Some(key)) = (request_line.starts_with("GET "), key) else {
+        write!(
+            writer,
+            "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        )?;
+        return writer.flush();
+    };
+    write!(
+        writer,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: \
+         Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(&key)
+    )?;
+    writer.flush()?;
+
+    // Responses and notifications are written by their own thread, so that
+    // notifications go out while waiting for the next request.
+    let (sink, outgoing) = mpsc::channel::<Frame>();
+    let writer = thread::spawn(move || -> io::Result<()> {
+        for frame in outgoing {
+            write_frame(&mut writer, &frame)?;
+            if frame.opcode == OP_CLOSE {
+                break;
+            }
+        }
+        Ok(())
+    });
+
+    let mut subscriptions = vec![];
+    let (text_sink, texts) = mpsc::channel::<String>();
+    let forward_sink = sink.clone();
+    // Notifications come in as text, from the `Rpc`.
+    let forwarder = thread::spawn(move || {
+        for text in texts {
+            if forward_sink.send(Frame::text(text)).is_err() {
+                break;
+            }
+        }
+    });
+
+    let result = read_messages(&mut reader, &sink, |message| {
+        let mut rpc = rpc.lock().unwrap_or_else(PoisonError::into_inner);
+        let response = match subscribe_request(&message) {
+            Some((id, Ok(filter))) => {
+                let subscription = rpc.subscribe_push(filter, text_sink.clone());
+                subscriptions.push(subscription);
+                Some(json!({ "jsonrpc": "2.0", "result": subscription, "id": id }).to_string())
+            }
+            Some((id, Err(e))) => Some(
+                json!({
+                    "jsonrpc": "2.0",
+                    "error": { "code": RpcError::INVALID_PARAMS, "message": e.to_string() },
+                    "id": id,
+                })
+                .to_string(),
+            ),
+            None => rpc.handle(&message),
+        };
+        response.map(Frame::text)
+    });
+
+    {
+        let mut rpc = rpc.lock().unwrap_or_else(PoisonError::into_inner);
+        for subscription in subscriptions {
+            rpc.unsubscribe(subscription);
+        }
+    }
+    drop(text_sink);
+    let _ = forwarder.join();
+    drop(sink);
+    let _ = writer.join();
+    result
+}
+
+/// Returns the id and filter of a `mozak_subscribe` request, or `None` for
+/// other messages.
+fn subscribe_request(message: &str) -> Option<(Value, serde_json::Result<EventFilter>)> {
+    let request: Value = serde_json::from_str(message).ok()?;
+    if request["method"] != "mozak_subscribe" {
+        return None;
+    }
+    let id = request.get("id").cloned().unwrap_or_default();
+    let filter = serde_json::from_value::<(EventFilter,)>(request["params"].clone());
+    Some((id, filter.map(|(filter,)| filter)))
+}
+
+/// Reads messages until the client closes the connection, answering pings
+/// and passing the text of each message to `handle`, whose response is sent
+/// back.
+fn read_messages(
+    reader: &mut impl Read,
+    sink: &Sender<Frame>,
+    mut handle: impl FnMut(String) -> Option<Frame>,
+) -> io::Result<()> {
+    let mut message = Vec::new();
+    loop {
+        let frame = read_frame(reader)?;
+        match frame.opcode {
+            OP_TEXT | OP_CONTINUATION => {
+                if message.len() + frame.payload.len() > MAX_MESSAGE_SIZE {
+                    let _ = sink.send(Frame::close());
+                    return Err(io::Error::new(ErrorKind::InvalidData, "message too large"));
+                }
+                message.extend(frame.payload);
+                if !frame.fin {
+                    continue;
+                }
+                let text = String::from_utf8(std::mem::take(&mut message))
+                    .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+                if let Some(response) = handle(text) {
+                    let _ = sink.send(response);
+                }
+            }
+            OP_PING => {
+                let _ = sink.send(Frame {
+                    fin: true,
+                    opcode: OP_PONG,
+                    payload: frame.payload,
+                });
+            }
+            OP_PONG => {}
+            OP_CLOSE => {
+                let _ = sink.send(Frame::close());
+                return Ok(());
+            }
+            // Binary frames are not part of the protocol.
+            _ => {
+                let _ = sink.send(Frame::close());
+                return Err(io::Error::new(ErrorKind::InvalidData, "unexpected frame"));
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+impl Frame {
+    fn text(text: String) -> Self {
+        Self {
+            fin: true,
+            opcode: OP_TEXT,
+            payload: text.into_bytes(),
+        }
+    }
+
+    fn close() -> Self {
+        Self {
+            fin: true,
+            opcode: OP_CLOSE,
+            payload: vec![],
+        }
+    }
+}
+
+fn accept_key(key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(key.as_bytes());
+    hasher.update(ACCEPT_GUID.as_bytes());
+    BASE64.encode(hasher.finalize())
+}
+
+/// Reads a frame sent by a client, which are always masked.
+fn read_frame(reader: &mut impl Read) -> io::Result<Frame> {
+    let mut header = [0; 2];
+    reader.read_exact(&mut header)?;
+    let fin = header[0] & 0x80 != 0;
+    let opcode = header[0] & 0x0F;
+    if header[1] & 0x80 == 0 {
+        return Err(io::Error::new(ErrorKind::InvalidData, "unmasked frame"));
+    }
+    let len = match header[1] & 0x7F {
+        126 => {
+            let mut len = [0; 2];
+            reader.read_exact(&mut len)?;
+            u64::from(u16::from_be_bytes(len))
+        }
+        127 => {
+            let mut len = [0; 8];
+            reader.read_exact(&mut len)?;
+            u64::from_be_bytes(len)
+        }
+        len => u64::from(len),
+    };
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|len| *len <= MAX_MESSAGE_SIZE)
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "frame too large"))?;
+    let mut mask = [0; 4];
+    reader.read_exact(&mut mask)?;
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok(Frame {
+        fin,
+        opcode,
+        payload,
+    })
+}
+
+/// Writes a frame from the server, which are never masked.
+fn write_frame(writer: &mut impl Write, frame: &Frame) -> io::Result<()> {
+    let first = (u8::from(frame.fin) << 7) | frame.opcode;
+    let len = frame.payload.len();
+    match (u8::try_from(len), u16::try_from(len)) {
+        (Ok(len), _) if len < 126 => writer.write_all(&[first, len])?,
+        (_, Ok(len)) => {
+            writer.write_all(&[first, 126])?;
+            writer.write_all(&len.to_be_bytes())?;
+        }
+        _ => {
+            writer.write_all(&[first, 127])?;
+            writer.write_all(&(len as u64).to_be_bytes())?;
+        }
+    }
+    writer.write_all(&frame.payload)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Encodes `frame` as a client would.
+    fn client_frame(frame: &Frame) -> Vec<u8> {
+        let mut bytes = vec![];
+        write_frame(&mut bytes, frame).unwrap();
+        // Set the mask bit, and insert a mask after the length.
+        bytes[1] |= 0x80;
+        let header_len = bytes.len() - frame.payload.len();
+        let mask = [1, 2, 3, 4];
+        let payload = frame
+            .payload
+            .iter()
+            .enumerate()
+            .map(|(i, b)| b ^ mask[i % 4]);
+        bytes[..header_len]
+            .iter()
+            .copied()
+            .chain(mask)
+            .chain(payload)
+            .collect()
+    }
+
+    #[test]
+    fn handshake() {
+        // The example of RFC 6455.
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGj5f+Y0rK8xQ="
+        );
+    }
+
+    #[test]
+    fn frames() {
+        for len in [0, 125, 126, 70_000] {
+            let frame = Frame::text("x".repeat(len));
+            let bytes = client_frame(&frame);
+            assert_eq!(read_frame(&mut bytes.as_slice()).unwrap(), frame);
+        }
+
+        let mut unmasked = vec![];
+        write_frame(&mut unmasked, &Frame::text("hi".to_string())).unwrap();
+        assert!(read_frame(&mut unmasked.as_slice()).is_err());
+    }
+
+    #[test]
+    fn messages() {
+        let mut first = Frame::text("hel".to_string());
+        first.fin = false;
+        let rest = Frame {
+            fin: true,
+            opcode: OP_CONTINUATION,
+            payload: b"lo".to_vec(),
+        };
+        let ping = Frame {
+            fin: true,
+            opcode: OP_PING,
+            payload: b"?".to_vec(),
+        };
+        let input = [first, ping, rest, Frame::close()]
+            .iter()
+            .flat_map(client_frame)
+            .collect::<Vec<_>>();
+
+        let (sink, sent) = mpsc::channel();
+        let mut received = vec![];
+        read_messages(&mut input.as_slice(), &sink, |message| {
+            received.push(message);
+            Some(Frame::text("ok".to_string()))
+        })
+        .unwrap();
+        assert_eq!(received, vec!["hello"]);
+        assert_eq!(sent.try_iter().map(|f| f.opcode).collect::<Vec<_>>(), vec![
+            OP_PONG, OP_TEXT, OP_CLOSE
+        ]);
+
+        assert_eq!(
+            subscribe_request(
+                r#"{"jsonrpc":"2.0","method":"mozak_subscribe","params":[{}],"id":1}"#
+            )
+            .map(|(id, filter)| (id, filter.unwrap())),
+            Some((json!(1), EventFilter::default()))
+        );
+        assert!(subscribe_request(r#"{"jsonrpc":"2.0","method":"mozak_getBlock"}"#).is_none());
+    }
+}
@@ -0,0 +1,268 @@
+//! The sequencer decides which pending transactions go into which block, and
+//! in what order, without proving anything.
+//!
+//! Each sequenced block is handed off as a [`BlockSummary`] together with its
+//! transactions, so the block proposer can prove it on another machine. The
+//! position of every transaction is recorded in an [`OrderingReceipt`].
+
+use std::collections::BTreeMap;
+
+use hashbrown::HashMap;
+use itertools::Itertools;
+use mozak_sdk::common::types::StateAddress;
+use serde::{Deserialize, Serialize};
+
+use crate::mempool::{Mempool, ProofVerifier, Tx, TxId};
+use crate::rpc::BlockSummary;
+
+/// Picks the transactions of the next block.
+pub trait OrderingPolicy {
+    /// Returns up to `max` of the `pending` transactions, which are given
+    /// oldest first, in the order they should be in the block. The others
+    /// stay pending.
+    fn order(&mut self, pending: &[(TxId, &Tx)], max: usize) -> Vec<TxId>;
+}
+
+/// First come, first served.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fifo;
+
+impl OrderingPolicy for Fifo {
+    fn order(&mut self, pending: &[(TxId, &Tx)], max: usize) -> Vec<TxId> {
+        pending.iter().take(max).map(|(id, _)| *id).collect()
+    }
+}
+
+/// Highest fee first, as reported by the fee function. Transactions with
+/// equal fees are in order of arrival.
+pub struct FeePriority<Fee>(pub Fee);
+
+impl<Fee: Fn(&Tx) -> u64> OrderingPolicy for FeePriority<Fee> {
+    fn order(&mut self, pending: &[(TxId, &Tx)], max: usize) -> Vec<TxId> {
+        pending
+            .iter()
+            .enumerate()
+            .sorted_by_key(|(arrival, (_, tx))| (std::cmp::Reverse((self.0)(tx)), *arrival))
+            .take(max)
+            .map(|(_, (id, _))| *id)
+            .collect()
+    }
+}
+
+/// Orders the transactions of each address by nonce, as reported by the nonce
+/// function, and only lets a transaction in once all lower nonces of its
+/// address have been. Transactions without a nonce are in order of arrival.
+pub struct AddressNonce<Nonce> {
+    nonce: Nonce,
+    next: BTreeMap<StateAddress, u64>,
+}
+
+impl<Nonce> AddressNonce<Nonce> {
+    #[must_use]
+    pub fn new(nonce: Nonce) -> Self {
+        Self {
+            nonce,
+            next: BTreeMap::new(),
+        }
+    }
+
+    /// The nonce the next transaction from `address` needs to have.
+    #[must_use]
+    pub fn next_nonce(&self, address: &StateAddress) -> u64 {
+        self.next.get(address).copied().unwrap_or_default()
+    }
+}
+
+impl<Nonce: Fn(&Tx) -> Option<(StateAddress, u64)>> OrderingPolicy for AddressNonce<Nonce> {
+    fn order(&mut self, pending: &[(TxId, &Tx)], max: usize) -> Vec<TxId> {
+        let mut by_nonce = BTreeMap::new();
+        for (id, tx) in pending {
+            if let Some(key) = (self.nonce)(tx) {
+                // Of two transactions with the same nonce, the first one wins.
+                by_nonce.entry(key).or_insert(*id);
+            }
+        }
+
+        let mut ordered = Vec::new();
+        for (id, tx) in pending {
+            if ordered.len() == max {
+                break;
+            }
+            let Some((address, _)) = (self.nonce)(tx) else {
+                ordered.push(*id);
+                continue;
+            };
+            // Let in the run of consecutive nonces of the address that starts
+            // at its next nonce, when reaching its first transaction.
+            loop {
+                let next = self.next_nonce(&address);
+                let Some(id) = by_nonce.remove(&(address, next)) else {
+                    break;
+                };
+                if ordered.len() == max {
+                    break;
+                }
+                ordered.push(id);
+                self.next.insert(address, next + 1);
+            }
+        }
+        ordered
+    }
+}
+
+/// Where a transaction was put.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderingReceipt {
+    pub height: u64,
+    /// Position of the transaction in the block.
+    pub index: usize,
+}
+
+pub struct Sequencer<V, P> {
+    mempool: Mempool<V>,
+    policy: P,
+    next_height: u64,
+    receipts: HashMap<TxId, OrderingReceipt>,
+}
+
+impl<V: ProofVerifier, P: OrderingPolicy> Sequencer<V, P> {
+    /// Creates a sequencer whose next block is at `next_height`.
+    #[must_use]
+    pub fn new(mempool: Mempool<V>, policy: P, next_height: u64) -> Self {
+        Self {
+            mempool,
+            policy,
+            next_height,
+            receipts: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn mempool(&self) -> &Mempool<V> { &self.mempool }
+
+    pub fn mempool_mut(&mut self) -> &mut Mempool<V> { &mut self.mempool }
+
+    #[must_use]
+    pub fn receipt(&self, id: &TxId) -> Option<OrderingReceipt> { self.receipts.get(id).copied() }
+
+    /// Assigns up to `max` pending transactions to the next block, and
+    /// removes them from the mempool. Returns `None` if the policy picked no
+    /// transactions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the policy picks a transaction that is not pending.
+    pub fn sequence(&mut self, max: usize) -> Option<(BlockSummary, Vec<Tx>)> {
+        let pending = self.mempool.iter().collect_vec();
+        let ordered = self.policy.order(&pending, max);
+        if ordered.is_empty() {
+            return None;
+        }
+
+        let height = self.next_height;
+        self.next_height += 1;
+        let txs = ordered
+            .iter()
+            .enumerate()
+            .map(|(index, id)| {
+                self.receipts.insert(*id, OrderingReceipt { height, index });
+                self.mempool
+                    .remove(id)
+                    .expect("the policy only picks pending transactions")
+            })
+            .collect();
+        Some((
+            BlockSummary {
+                height,
+                transactions: ordered,
+            },
+            txs,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use mozak_sdk::common::types::{Poseidon2Hash, ProgramIdentifier};
+    use mozak_sdk::native::OrderedEvents;
+    use plonky2::hash::hash_types::HashOut;
+    use plonky2::hash::merkle_tree::MerkleCap;
+
+    use super::*;
+    use crate::mempool::MempoolConfig;
+    use crate::types::{Attestation, Transaction};
+
+    /// A transaction whose public tape is `[sender, nonce, fee]`.
+    fn tx(sender: u8, nonce: u8, fee: u8) -> Tx {
+        let id = ProgramIdentifier(Poseidon2Hash::from([1; 4]));
+        Transaction {
+            cast_list: vec![id],
+            call_tape_hash: MerkleCap(vec![HashOut::ZERO]),
+            constituent_zs: vec![Attestation {
+                id,
+                public_tape: vec![sender, nonce, fee],
+                event_tape: OrderedEvents::default(),
+            }],
+        }
+    }
+
+    fn accept_all(_: &Tx) -> anyhow::Result<()> { Ok(()) }
+
+    fn sequencer<P: OrderingPolicy>(
+        policy: P,
+        txs: &[(u8, u8, u8)],
+    ) -> (Sequencer<impl ProofVerifier, P>, Vec<TxId>) {
+        let mut mempool = Mempool::new(MempoolConfig::default(), accept_all);
+        let ids = txs
+            .iter()
+            .map(|&(sender, nonce, fee)| mempool.insert(tx(sender, nonce, fee)).unwrap())
+            .collect();
+        (Sequencer::new(mempool, policy, 1), ids)
+    }
+
+    fn tape(tx: &Tx) -> &[u8] { &tx.constituent_zs[0].public_tape }
+
+    #[test]
+    fn fifo() {
+        let (mut sequencer, ids) = sequencer(Fifo, &[(0, 0, 0), (0, 1, 0), (0, 2, 0)]);
+        let (block, txs) = sequencer.sequence(2).unwrap();
+        assert_eq!(block.height, 1);
+        assert_eq!(block.transactions, ids[..2]);
+        assert_eq!(txs.len(), 2);
+        assert_eq!(
+            sequencer.receipt(&ids[1]),
+            Some(OrderingReceipt {
+                height: 1,
+                index: 1
+            })
+        );
+        assert_eq!(sequencer.receipt(&ids[2]), None);
+
+        let (block, _) = sequencer.sequence(2).unwrap();
+        assert_eq!(block.height, 2);
+        assert_eq!(block.transactions, ids[2..]);
+        assert!(sequencer.sequence(2).is_none());
+    }
+
+    #[test]
+    fn fee_priority() {
+        let policy = FeePriority(|tx: &Tx| u64::from(tape(tx)[2]));
+        let (mut sequencer, ids) = sequencer(policy, &[(0, 0, 1), (0, 1, 5), (0, 2, 5)]);
+        let (block, _) = sequencer.sequence(3).unwrap();
+        assert_eq!(block.transactions, vec![ids[1], ids[2], ids[0]]);
+    }
+
+    #[test]
+    fn address_nonce() {
+        let policy = AddressNonce::new(|tx: &Tx| {
+            let tape = tape(tx);
+            Some((StateAddress([tape[0]; 8]), u64::from(tape[1])))
+        });
+        let (mut sequencer, ids) = sequencer(policy, &[(0, 1, 0), (1, 0, 0), (0, 0, 0), (0, 3, 0)]);
+        let (block, _) = sequencer.sequence(10).unwrap();
+        assert_eq!(block.transactions, vec![ids[2], ids[0], ids[1]]);
+        // Nonce 2 of address 0 is still missing.
+        assert!(sequencer.sequence(10).is_none());
+        assert_eq!(sequencer.mempool().len(), 1);
+    }
+}
@@ -0,0 +1,432 @@
+//! Persistence of the chain: block headers and proofs, the state objects, the
+//! transactions, and indexes of the events touching each address and each
+//! program, and of the history of each object.
+//!
+//! Blocks are committed one at a time through [`Storage::commit`], which has
+//! to either apply a whole [`BlockCommit`] or none of it.
+//!
+//! A node either keeps everything, or prunes the proofs and past object states
+//! of old blocks according to its [`Retention`]. Block headers, which hold the
+//! state roots, and the transaction and event indexes are always kept.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use anyhow::{ensure, Result};
+use itertools::Itertools;
+use mozak_sdk::common::types::{CanonicalEvent, ProgramIdentifier, StateAddress};
+use serde::{Deserialize, Serialize};
+
+use crate::mempool::{Tx, TxId};
+use crate::rpc::{BlockSummary, ObjectState};
+
+pub mod file;
+
+/// Everything a block changes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockCommit {
+    pub header: BlockSummary,
+    /// The serialized block proof.
+    #[serde(skip)]
+    pub proof: Vec<u8>,
+    /// New state of every object the block touched, `None` if it was deleted.
+    pub objects: Vec<(StateAddress, Option<ObjectState>)>,
+    /// The addresses the events of each transaction of the block touched.
+    pub events: Vec<(StateAddress, TxId)>,
+    #[serde(default)]
+    pub transactions: Vec<TxRecord>,
+}
+
+/// What is kept of an included transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxRecord {
+    pub id: TxId,
+    pub height: u64,
+    pub cast_list: Vec<ProgramIdentifier>,
+    /// The events of each program, in canonical order.
+    pub events: Vec<(ProgramIdentifier, CanonicalEvent)>,
+}
+
+impl TxRecord {
+    #[must_use]
+    pub fn new(id: TxId, height: u64, tx: &Tx) -> Self {
+        let events = tx
+            .constituent_zs
+            .iter()
+            .flat_map(|z| {
+                z.event_tape
+                    .get_canonical_order_temporal_hints()
+                    .into_iter()
+                    .map(|hint| (z.id, hint.0))
+            })
+            .collect();
+        Self {
+            id,
+            height,
+            cast_list: tx.cast_list.clone(),
+            events,
+        }
+    }
+}
+
+/// How much of the past a node keeps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Retention {
+    /// Keep the proof and past object states of every block.
+    #[default]
+    Archive,
+    /// Keep the proofs and past object states of the latest `blocks` blocks.
+    Pruned { blocks: u64 },
+}
+
+impl Retention {
+    /// The lowest height to keep, when the latest block is at `latest`.
+    #[must_use]
+    pub fn keep_from(self, latest: u64) -> u64 {
+        match self {
+            Self::Archive => 0,
+            Self::Pruned { blocks } => (latest + 1).saturating_sub(blocks),
+        }
+    }
+}
+
+pub trait Storage {
+    /// Persists `block`, which has to be the successor of the latest block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `block` is not the next block, or if writing it
+    /// fails, in which case nothing of it was persisted.
+    fn commit(&mut self, block: BlockCommit) -> Result<()>;
+
+    /// Height of the latest committed block.
+    fn latest_height(&self) -> Option<u64>;
+
+    fn block(&self, height: u64) -> Option<BlockSummary>;
+
+    /// Up to `limit` blocks, starting at `from` and going down.
+    fn blocks(&self, from: u64, limit: usize) -> Vec<BlockSummary> {
+        let Some(latest) = self.latest_height() else {
+            return vec![];
+        };
+        (0..=from.min(latest))
+            .rev()
+            .map_while(|height| self.block(height))
+            .take(limit)
+            .collect()
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the proof can not be read.
+    fn block_proof(&self, height: u64) -> Result<Option<Vec<u8>>>;
+
+    fn object(&self, address: StateAddress) -> Option<ObjectState>;
+
+    /// All the objects in the state, sorted by address.
+    fn objects(&self) -> Vec<(StateAddress, ObjectState)>;
+
+    /// Blocks and transactions with events at `address`, oldest first.
+    fn events_at(&self, address: StateAddress) -> Vec<(u64, TxId)>;
+
+    fn transaction(&self, id: &TxId) -> Option<TxRecord>;
+
+    /// Blocks and transactions with events of `program`, oldest first.
+    fn events_of(&self, program: &ProgramIdentifier) -> Vec<(u64, TxId)>;
+
+    /// Every state the object at `address` was in, and the block it was set
+    /// in, oldest first. `None` means it was deleted. States that were
+    /// replaced before [`pruned_below`](Self::pruned_below) are left out.
+    fn history(&self, address: StateAddress) -> Vec<(u64, Option<ObjectState>)>;
+
+    /// Height of the oldest block whose proof and past object states are kept.
+    fn pruned_below(&self) -> u64;
+
+    /// Drops the proofs of the blocks below `height`, and the object states
+    /// that were replaced before it. The latest block is never pruned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if deleting fails. What was pruned before that stays
+    /// pruned.
+    fn prune(&mut self, height: u64) -> Result<()>;
+
+    /// Whether the proof of the block at `height` can still be served.
+    fn has_proof(&self, height: u64) -> bool {
+        height >= self.pruned_below() && self.latest_height().is_some_and(|latest| height <= latest)
+    }
+
+    /// Prunes everything `retention` does not keep.
+    ///
+    /// # Errors
+    ///
+    /// See [`prune`](Self::prune).
+    fn compact(&mut self, retention: Retention) -> Result<()> {
+        match self.latest_height() {
+            Some(latest) => self.prune(retention.keep_from(latest)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Compacts `storage` according to `retention` every `interval`, forever.
+pub fn compact_every<S: Storage + Send + 'static>(
+    storage: Arc<Mutex<S>>,
+    retention: Retention,
+    interval: Duration,
+) -> JoinHandle<()> {
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        let mut storage = storage.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = storage.compact(retention) {
+            log::warn!("compaction failed: {e:#}");
+        }
+    })
+}
+
+/// Keeps everything in memory, and forgets it on restart.
+#[derive(Default)]
+pub struct MemoryStorage {
+    /// Headers and proofs, which are `None` once pruned.
+    blocks: BTreeMap<u64, (BlockSummary, Option<Vec<u8>>)>,
+    objects: BTreeMap<StateAddress, ObjectState>,
+    events: BTreeMap<StateAddress, Vec<(u64, TxId)>>,
+    transactions: BTreeMap<TxId, TxRecord>,
+    programs: BTreeMap<ProgramIdentifier, Vec<(u64, TxId)>>,
+    history: BTreeMap<StateAddress, Vec<(u64, Option<ObjectState>)>>,
+    pruned_below: u64,
+}
+
+impl MemoryStorage {
+    fn check_next(&self, block: &BlockCommit) -> Result<()> {
+        let expected = self.latest_height().map_or(0, |height| height + 1);
+        ensure!(
+            block.header.height == expected,
+            "expected block {expected}, got block {}",
+            block.header.height
+        );
+        Ok(())
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn commit(&mut self, block: BlockCommit) -> Result<()> {
+        self.check_next(&block)?;
+        let height = block.header.height;
+        for (address, object) in block.objects {
+            self.history
+                .entry(address)
+                .or_default()
+                .push((height, object));
+            match object {
+                Some(object) => self.objects.insert(address, object),
+                None => self.objects.remove(&address),
+            };
+        }
+        for (address, tx) in block.events {
+            self.events.entry(address).or_default().push((height, tx));
+        }
+        for tx in block.transactions {
+            for program in tx.events.iter().map(|(program, _)| *program).dedup() {
+                self.programs
+                    .entry(program)
+                    .or_default()
+                    .push((height, tx.id));
+            }
+            self.transactions.insert(tx.id, tx);
+        }
+        self.blocks
+            .insert(height, (block.header, Some(block.proof)));
+        Ok(())
+    }
+
+    fn latest_height(&self) -> Option<u64> { self.blocks.keys().next_back().copied() }
+
+    fn block(&self, height: u64) -> Option<BlockSummary> {
+        self.blocks.get(&height).map(|(header, _)| header.clone())
+    }
+
+    fn block_proof(&self, height: u64) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .blocks
+            .get(&height)
+            .and_then(|(_, proof)| proof.clone()))
+    }
+
+    fn object(&self, address: StateAddress) -> Option<ObjectState> {
+        self.objects.get(&address).copied()
+    }
+
+    fn objects(&self) -> Vec<(StateAddress, ObjectState)> {
+        self.objects.iter().map(|(a, o)| (*a, *o)).collect()
+    }
+
+    fn events_at(&self, address: StateAddress) -> Vec<(u64, TxId)> {
+        self.events.get(&address).cloned().unwrap_or_default()
+    }
+
+    fn transaction(&self, id: &TxId) -> Option<TxRecord> { self.transactions.get(id).cloned() }
+
+    fn events_of(&self, program: &ProgramIdentifier) -> Vec<(u64, TxId)> {
+        self.programs.get(program).cloned().unwrap_or_default()
+    }
+
+    fn history(&self, address: StateAddress) -> Vec<(u64, Option<ObjectState>)> {
+        self.history.get(&address).cloned().unwrap_or_default()
+    }
+
+    fn pruned_below(&self) -> u64 { self.pruned_below }
+
+    fn prune(&mut self, height: u64) -> Result<()> {
+        let Some(latest) = self.latest_height() else {
+            return Ok(());
+        };
+        let height = height.min(latest);
+        if height <= self.pruned_below {
+            return Ok(());
+        }
+        for (_, proof) in self.blocks.range_mut(..height).map(|(_, block)| block) {
+            *proof = None;
+        }
+        self.history.retain(|_, history| {
+            // Keeps the states from the one that was current at `height` on.
+            let current = history.partition_point(|(set_at, _)| *set_at <= height);
+            history.drain(..current.saturating_sub(1));
+            !matches!(history.as_slice(), [(set_at, None)] if *set_at <= height)
+        });
+        self.pruned_below = height;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use mozak_sdk::common::types::Poseidon2Hash;
+
+    use super::*;
+
+    pub const PROGRAM: ProgramIdentifier = ProgramIdentifier(Poseidon2Hash([1; 32]));
+
+    pub fn block(height: u64) -> BlockCommit {
+        let tx = TxId(Poseidon2Hash::from([height; 4]));
+        let byte = u8::try_from(height).unwrap();
+        BlockCommit {
+            header: BlockSummary {
+                height,
+                transactions: vec![tx],
+            },
+            proof: vec![1, 2, 3, byte],
+            objects: vec![(
+                StateAddress([byte; 8]),
+                Some(ObjectState {
+                    credits: height,
+                    ..ObjectState::default()
+                }),
+            )],
+            events: vec![(StateAddress([0; 8]), tx)],
+            transactions: vec![TxRecord {
+                id: tx,
+                height,
+                cast_list: vec![PROGRAM],
+                events: vec![(PROGRAM, CanonicalEvent {
+                    address: StateAddress([0; 8]),
+                    ..CanonicalEvent::default()
+                })],
+            }],
+        }
+    }
+
+    /// Checks the behaviour every [`Storage`] has to have.
+    pub fn check_storage(storage: &mut impl Storage) {
+        assert_eq!(storage.latest_height(), None);
+        assert!(storage.commit(block(1)).is_err());
+
+        storage.commit(block(0)).unwrap();
+        storage.commit(block(1)).unwrap();
+        assert!(storage.commit(block(1)).is_err());
+        assert_eq!(storage.latest_height(), Some(1));
+
+        assert_eq!(storage.block(1), Some(block(1).header));
+        assert_eq!(storage.block_proof(1).unwrap(), Some(block(1).proof));
+        assert_eq!(storage.block(2), None);
+        assert_eq!(storage.object(StateAddress([1; 8])).unwrap().credits, 1);
+        assert_eq!(storage.events_at(StateAddress([0; 8])), vec![
+            (0, block(0).header.transactions[0]),
+            (1, block(1).header.transactions[0])
+        ]);
+
+        let mut delete = block(2);
+        delete.objects = vec![(StateAddress([1; 8]), None)];
+        storage.commit(delete).unwrap();
+        assert_eq!(storage.object(StateAddress([1; 8])), None);
+        assert_eq!(storage.history(StateAddress([1; 8])), vec![
+            (1, block(1).objects[0].1),
+            (2, None)
+        ]);
+
+        let tx = block(1).transactions.remove(0);
+        assert_eq!(storage.transaction(&tx.id), Some(tx.clone()));
+        assert_eq!(storage.events_of(&PROGRAM).len(), 3);
+        assert_eq!(storage.events_of(&PROGRAM)[1], (1, tx.id));
+        let heights = |from, limit| {
+            storage
+                .blocks(from, limit)
+                .into_iter()
+                .map(|b| b.height)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(heights(5, 2), vec![2, 1]);
+        assert_eq!(heights(0, 2), vec![0]);
+    }
+
+    /// Checks that pruning keeps what it has to, on a fresh `storage`.
+    pub fn check_pruning(storage: &mut impl Storage) {
+        storage.compact(Retention::Pruned { blocks: 1 }).unwrap();
+        let mut overwrite = block(2);
+        overwrite.objects[0] = (StateAddress([1; 8]), block(0).objects[0].1);
+        for commit in [block(0), block(1), overwrite.clone(), block(3)] {
+            storage.commit(commit).unwrap();
+        }
+        assert!(storage.has_proof(0));
+        assert!(!storage.has_proof(4));
+
+        storage.compact(Retention::Archive).unwrap();
+        assert_eq!(storage.pruned_below(), 0);
+        storage.compact(Retention::Pruned { blocks: 2 }).unwrap();
+        assert_eq!(storage.pruned_below(), 2);
+        assert!(!storage.has_proof(1));
+        assert_eq!(storage.block_proof(1).unwrap(), None);
+        assert_eq!(storage.block(1), Some(block(1).header));
+        assert_eq!(storage.block_proof(2).unwrap(), Some(block(2).proof));
+        // The state set at 1 was replaced at 2, but the one set at 0 is still
+        // current.
+        assert_eq!(storage.history(StateAddress([1; 8])), vec![(
+            2,
+            overwrite.objects[0].1
+        )]);
+        assert_eq!(storage.history(StateAddress([0; 8])).len(), 1);
+
+        // The latest block is always kept.
+        storage.prune(10).unwrap();
+        assert_eq!(storage.pruned_below(), 3);
+        assert!(storage.has_proof(3));
+        storage.prune(1).unwrap();
+        assert_eq!(storage.pruned_below(), 3);
+    }
+
+    #[test]
+    fn memory_storage() {
+        check_storage(&mut MemoryStorage::default());
+        check_pruning(&mut MemoryStorage::default());
+    }
+
+    #[test]
+    fn retention() {
+        assert_eq!(Retention::Archive.keep_from(10), 0);
+        assert_eq!(Retention::Pruned { blocks: 3 }.keep_from(10), 8);
+        assert_eq!(Retention::Pruned { blocks: 30 }.keep_from(10), 0);
+    }
+}
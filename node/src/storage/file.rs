@@ -0,0 +1,164 @@
+//! A [`Storage`] that keeps each block in its own files in a directory.
+//!
+//! A block is written as `<height>.proof` followed by `<height>.block.json`.
+//! Each file is written to a temporary path, synced and then renamed into
+//! place, so the appearance of the `.block.json` file is the point at which a
+//! block is committed. On startup, the blocks are replayed to rebuild the
+//! indexes in memory.
+//!
+//! Pruning deletes the `.proof` files, after recording the new
+//! [`pruned_below`](Storage::pruned_below) height in a `pruned` file. The
+//! `.block.json` files are kept, as the state is rebuilt from them.
+
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use mozak_sdk::common::types::{ProgramIdentifier, StateAddress};
+
+use super::{BlockCommit, MemoryStorage, Storage, TxRecord};
+use crate::mempool::TxId;
+use crate::rpc::{BlockSummary, ObjectState};
+
+pub struct FileStorage {
+    dir: PathBuf,
+    index: MemoryStorage,
+}
+
+impl FileStorage {
+    /// Opens the storage in `dir`, creating it if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `dir` can not be read, or holds a corrupted block.
+    pub fn open(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut storage = Self {
+            dir: dir.to_path_buf(),
+            index: MemoryStorage::default(),
+        };
+        for height in 0.. {
+            let path = storage.block_path(height);
+            if !path.exists() {
+                break;
+            }
+            let block: BlockCommit = serde_json::from_slice(&fs::read(&path)?)
+                .with_context(|| format!("corrupted block {path:?}"))?;
+            storage.index.commit(block)?;
+        }
+        let pruned = storage.dir.join("pruned");
+        if pruned.exists() {
+            let height = fs::read_to_string(&pruned)?
+                .trim()
+                .parse()
+                .with_context(|| format!("corrupted {pruned:?}"))?;
+            storage.index.prune(height)?;
+        }
+        Ok(storage)
+    }
+
+    fn block_path(&self, height: u64) -> PathBuf { self.dir.join(format!("{height}.block.json")) }
+
+    fn proof_path(&self, height: u64) -> PathBuf { self.dir.join(format!("{height}.proof")) }
+
+    fn write_durably(&self, path: &Path, bytes: &[u8]) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)?;
+        // Make the rename itself durable.
+        File::open(&self.dir)?.sync_all()?;
+        Ok(())
+    }
+}
+
+impl Storage for FileStorage {
+    fn commit(&mut self, mut block: BlockCommit) -> Result<()> {
+        self.index.check_next(&block)?;
+        let height = block.header.height;
+        let proof = std::mem::take(&mut block.proof);
+        self.write_durably(&self.proof_path(height), &proof)?;
+        self.write_durably(&self.block_path(height), &serde_json::to_vec(&block)?)?;
+        self.index.commit(block)
+    }
+
+    fn latest_height(&self) -> Option<u64> { self.index.latest_height() }
+
+    fn block(&self, height: u64) -> Option<BlockSummary> { self.index.block(height) }
+
+    fn block_proof(&self, height: u64) -> Result<Option<Vec<u8>>> {
+        if !self.has_proof(height) {
+            return Ok(None);
+        }
+        Ok(Some(fs::read(self.proof_path(height))?))
+    }
+
+    fn object(&self, address: StateAddress) -> Option<ObjectState> { self.index.object(address) }
+
+    fn objects(&self) -> Vec<(StateAddress, ObjectState)> { self.index.objects() }
+
+    fn events_at(&self, address: StateAddress) -> Vec<(u64, TxId)> { self.index.events_at(address) }
+
+    fn transaction(&self, id: &TxId) -> Option<TxRecord> { self.index.transaction(id) }
+
+    fn events_of(&self, program: &ProgramIdentifier) -> Vec<(u64, TxId)> {
+        self.index.events_of(program)
+    }
+
+    fn history(&self, address: StateAddress) -> Vec<(u64, Option<ObjectState>)> {
+        self.index.history(address)
+    }
+
+    fn pruned_below(&self) -> u64 { self.index.pruned_below() }
+
+    fn prune(&mut self, height: u64) -> Result<()> {
+        let from = self.pruned_below();
+        self.index.prune(height)?;
+        let to = self.pruned_below();
+        if to == from {
+            return Ok(());
+        }
+        self.write_durably(&self.dir.join("pruned"), to.to_string().as_bytes())?;
+        for height in from..to {
+            match fs::remove_file(self.proof_path(height)) {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::storage::test::{block, check_pruning, check_storage};
+
+    #[test]
+    fn file_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        check_storage(&mut FileStorage::open(dir.path()).unwrap());
+
+        // Survives a restart, but not a missing block.
+        let storage = FileStorage::open(dir.path()).unwrap();
+        assert_eq!(storage.latest_height(), Some(2));
+        assert_eq!(storage.block_proof(1).unwrap(), Some(block(1).proof));
+
+        fs::remove_file(storage.block_path(1)).unwrap();
+        let storage = FileStorage::open(dir.path()).unwrap();
+        assert_eq!(storage.latest_height(), Some(0));
+    }
+
+    #[test]
+    fn pruning() {
+        let dir = tempfile::tempdir().unwrap();
+        check_pruning(&mut FileStorage::open(dir.path()).unwrap());
+
+        let storage = FileStorage::open(dir.path()).unwrap();
+        assert_eq!(storage.pruned_below(), 3);
+        assert!(!storage.proof_path(2).exists());
+        assert_eq!(storage.block(2), Some(block(2).header));
+    }
+}
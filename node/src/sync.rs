@@ -0,0 +1,186 @@
+//! State sync: a new node can start from a snapshot of the state at some
+//! block instead of replaying every block before it.
+//!
+//! The snapshot is checked against the state root of the block it was taken
+//! at, which the new node takes from a block proof it has verified.
+
+use std::collections::BTreeMap;
+
+use anyhow::{ensure, Result};
+use mozak_recproofs::Object;
+use mozak_sdk::common::types::StateAddress;
+use plonky2::hash::hash_types::HashOut;
+use plonky2::hash::poseidon2::Poseidon2Hash;
+use plonky2::plonk::config::Hasher;
+use serde::{Deserialize, Serialize};
+
+use crate::block_proposer::state::{AuxStateData, Operation, State};
+use crate::block_proposer::Address;
+use crate::rpc::ObjectState;
+use crate::storage::Storage;
+use crate::F;
+
+/// All the objects in the state tree after the block at `height`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub height: u64,
+    /// Depth of the state tree, see [`State::new`].
+    pub tree_depth: usize,
+    /// Sorted by address.
+    pub objects: Vec<(StateAddress, ObjectState)>,
+}
+
+impl Snapshot {
+    /// Takes a snapshot of the latest block in `storage`.
+    #[must_use]
+    pub fn from_storage(storage: &impl Storage, tree_depth: usize) -> Option<Self> {
+        Some(Self {
+            height: storage.latest_height()?,
+            tree_depth,
+            objects: storage.objects(),
+        })
+    }
+
+    /// Computes the root of the state tree holding the objects.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the objects are not sorted, or an address does not
+    /// fit in the tree.
+    pub fn root(&self) -> Result<HashOut<F>> {
+        let bits = self.tree_depth + 1;
+        ensure!(
+            self.objects.windows(2).all(|w| w[0].0 < w[1].0),
+            "objects are not sorted by address"
+        );
+        let leaves: BTreeMap<u64, HashOut<F>> = self
+            .objects
+            .iter()
+            .map(|(address, object)| {
+                let address = Address::from_state(*address).0;
+                ensure!(
+                    u128::from(address) < 1 << bits,
+                    "address {address} does not fit in a tree of depth {}",
+                    self.tree_depth
+                );
+                Ok((address, Object::<F>::from(*object).hash()))
+            })
+            .collect::<Result<_>>()?;
+
+        let mut empty = vec![Object::<F>::default().hash()];
+        for i in 0..bits {
+            empty.push(hash_branch(empty[i], empty[i]));
+        }
+        Ok(subtree_root(&leaves, 0, bits, &empty))
+    }
+
+    /// Checks the snapshot against a block that is already trusted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the snapshot is not of the block at `height` with
+    /// `state_root`.
+    pub fn verify(&self, height: u64, state_root: HashOut<F>) -> Result<()> {
+        ensure!(
+            self.height == height,
+            "snapshot is of block {}, not block {height}",
+            self.height
+        );
+        ensure!(
+            self.root()? == state_root,
+            "snapshot does not match the state root of block {height}"
+        );
+        Ok(())
+    }
+
+    /// Rebuilds the state tree from the snapshot, for the block proposer to
+    /// continue from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `aux` does not support the depth of the tree.
+    pub fn restore<'a>(&self, aux: &'a AuxStateData) -> Result<State<'a>> {
+        ensure!(
+            self.tree_depth <= aux.max_tree_depth(),
+            "tree depth {} is not supported",
+            self.tree_depth
+        );
+        let mut state = State::new(aux, self.tree_depth);
+        for (address, object) in &self.objects {
+            state.apply_operation(
+                Address::from_state(*address),
+                Operation::Upsert((*object).into()),
+            );
+        }
+        state.finalize();
+        Ok(state)
+    }
+}
+
+fn hash_branch(left: HashOut<F>, right: HashOut<F>) -> HashOut<F> {
+    let [l0, l1, l2, l3] = left.elements;
+    let [r0, r1, r2, r3] = right.elements;
+    Poseidon2Hash::hash_no_pad(&[l0, l1, l2, l3, r0, r1, r2, r3])
+}
+
+/// Root of the subtree of height `bits` whose leaves start at `start`.
+fn subtree_root(
+    leaves: &BTreeMap<u64, HashOut<F>>,
+    start: u64,
+    bits: usize,
+    empty: &[HashOut<F>],
+) -> HashOut<F> {
+    if bits == 0 {
+        return leaves.get(&start).copied().unwrap_or(empty[0]);
+    }
+    let half = 1 << (bits - 1);
+    let last = start + (half - 1) * 2 + 1;
+    if leaves.range(start..=last).next().is_none() {
+        return empty[bits];
+    }
+    hash_branch(
+        subtree_root(leaves, start, bits - 1, empty),
+        subtree_root(leaves, start + half, bits - 1, empty),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::block_proposer::state::test::AUX_8;
+    use crate::storage::test::block;
+    use crate::storage::MemoryStorage;
+
+    #[test]
+    fn snapshot_round_trip() {
+        let mut storage = MemoryStorage::default();
+        assert_eq!(Snapshot::from_storage(&storage, 8), None);
+        let mut genesis = block(0);
+        genesis.objects = [3, 200]
+            .map(|a| {
+                let object = ObjectState {
+                    credits: a,
+                    ..ObjectState::default()
+                };
+                (Address(a).to_state(), Some(object))
+            })
+            .to_vec();
+        storage.commit(genesis).unwrap();
+        let mut next = block(1);
+        next.objects = vec![(Address(7).to_state(), None)];
+        storage.commit(next).unwrap();
+
+        let snapshot = Snapshot::from_storage(&storage, 8).unwrap();
+        assert_eq!(snapshot.height, 1);
+        assert_eq!(snapshot.objects.len(), 2);
+
+        let state = snapshot.restore(*AUX_8).unwrap();
+        let root = state.proof().new();
+        snapshot.verify(1, root).unwrap();
+        assert!(snapshot.verify(0, root).is_err());
+
+        let mut tampered = snapshot.clone();
+        tampered.objects[0].1.credits += 1;
+        assert!(tampered.verify(1, root).is_err());
+    }
+}
@@ -1,6 +1,8 @@
 #![allow(dead_code)]
 
-use mozak_sdk::common::types::ProgramIdentifier;
+use mozak_sdk::common::merkle::merkleize;
+use mozak_sdk::common::types::{Poseidon2Hash, ProgramIdentifier};
+use mozak_sdk::native::poseidon::poseidon2_hash_with_pad;
 use mozak_sdk::native::OrderedEvents;
 use plonky2::field::extension::Extendable;
 use plonky2::hash::hash_types::RichField;
@@ -8,6 +10,9 @@ use plonky2::hash::merkle_tree::MerkleCap;
 use plonky2::plonk::config::GenericConfig;
 use serde::{Deserialize, Serialize};
 
+use crate::fork_choice::BlockId;
+use crate::mempool::TxId;
+
 /// An attestion to the correct execution of a `MozakVM` program, denoted by its
 /// [`ProgramIdentifier`](mozak_sdk::coretypes::ProgramIdentifier).
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,3 +45,140 @@ pub struct Transaction<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>,
     /// involved in this `Transaction`.
     pub constituent_zs: Vec<Attestation>,
 }
+
+/// The header of a block. Its hash is the id of the block, so everything
+/// about a block is committed to through its header.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    /// Hash of the header of the previous block, zero for the first block.
+    pub parent: Poseidon2Hash,
+    /// Root of the state tree after the block.
+    pub state_root: Poseidon2Hash,
+    /// Root of the transactions of the block, see
+    /// [`transactions_root`](Self::transactions_root).
+    pub transactions_root: Poseidon2Hash,
+    /// Hash of the public inputs of the block proof.
+    pub proof_commitment: Poseidon2Hash,
+    pub height: u64,
+    /// Seconds since the Unix epoch, as set by the proposer.
+    pub timestamp: u64,
+}
+
+impl BlockHeader {
+    /// Length of the canonical encoding.
+    pub const ENCODED_LEN: usize = 4 * 32 + 2 * 8;
+
+    /// The canonical encoding: the hashes in the order of the fields, followed
+    /// by the height and timestamp as little-endian `u64`s. This is what the
+    /// block hash is computed over, by the node as well as by light clients
+    /// and circuits.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut bytes = [0; Self::ENCODED_LEN];
+        let hashes = [
+            self.parent,
+            self.state_root,
+            self.transactions_root,
+            self.proof_commitment,
+        ];
+        let integers = [self.height, self.timestamp];
+        for (chunk, hash) in bytes.chunks_exact_mut(32).zip(hashes) {
+            chunk.copy_from_slice(&hash.inner());
+        }
+        for (chunk, integer) in bytes[4 * 32..].chunks_exact_mut(8).zip(integers) {
+            chunk.copy_from_slice(&integer.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes a header from its canonical encoding.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8; Self::ENCODED_LEN]) -> Self {
+        let hash = |i: usize| {
+            let mut hash = [0; 32];
+            hash.copy_from_slice(&bytes[i * 32..(i + 1) * 32]);
+            Poseidon2Hash(hash)
+        };
+        let integer = |i: usize| {
+            let mut integer = [0; 8];
+            integer.copy_from_slice(&bytes[4 * 32 + i * 8..][..8]);
+            u64::from_le_bytes(integer)
+        };
+        Self {
+            parent: hash(0),
+            state_root: hash(1),
+            transactions_root: hash(2),
+            proof_commitment: hash(3),
+            height: integer(0),
+            timestamp: integer(1),
+        }
+    }
+
+    /// The Poseidon2 hash of the canonical encoding.
+    #[must_use]
+    pub fn hash(&self) -> Poseidon2Hash { poseidon2_hash_with_pad(&self.to_bytes()) }
+
+    #[must_use]
+    pub fn id(&self) -> BlockId { BlockId(self.hash()) }
+
+    /// Whether this is a header of a block that directly follows `parent`.
+    #[must_use]
+    pub fn follows(&self, parent: &Self) -> bool {
+        self.parent == parent.hash() && self.height == parent.height + 1
+    }
+
+    /// The root of the Merkle tree with the ids of `transactions` as leaves,
+    /// in block order.
+    #[must_use]
+    pub fn transactions_root(transactions: &[TxId]) -> Poseidon2Hash {
+        merkleize((0..).zip(transactions.iter().map(|id| id.0)).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn header() -> BlockHeader {
+        BlockHeader {
+            parent: Poseidon2Hash([1; 32]),
+            state_root: Poseidon2Hash([2; 32]),
+            transactions_root: BlockHeader::transactions_root(&[
+                TxId(Poseidon2Hash([3; 32])),
+                TxId(Poseidon2Hash([4; 32])),
+            ]),
+            proof_commitment: Poseidon2Hash([5; 32]),
+            height: 6,
+            timestamp: 7,
+        }
+    }
+
+    #[test]
+    fn encoding() {
+        let header = header();
+        let bytes = header.to_bytes();
+        assert_eq!(bytes[..32], [1; 32]);
+        assert_eq!(bytes[128..136], 6u64.to_le_bytes());
+        assert_eq!(bytes[136..], 7u64.to_le_bytes());
+        assert_eq!(BlockHeader::from_bytes(&bytes), header);
+    }
+
+    #[test]
+    fn hashing() {
+        let header = header();
+        assert_eq!(header.hash(), header.hash());
+        let later = BlockHeader {
+            timestamp: 8,
+            ..header
+        };
+        assert_ne!(later.hash(), header.hash());
+
+        let child = BlockHeader {
+            parent: header.hash(),
+            height: 7,
+            ..header
+        };
+        assert!(child.follows(&header));
+        assert!(!child.follows(&later));
+    }
+}
@@ -0,0 +1,259 @@
+//! Verifies incoming transactions on a pool of worker threads.
+//!
+//! Verifying proofs is what a syncing node spends most of its time on, and the
+//! transactions are independent of each other, so they are checked
+//! concurrently. The queue in front of the workers is bounded: when it is
+//! full, [`VerificationPool::try_submit`] hands the transaction back, and the
+//! network layer should stop reading from its peers until there is room.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+use anyhow::Result;
+
+use crate::mempool::{validate, Mempool, ProofVerifier, Tx, TxId};
+use crate::metrics::Metrics;
+
+/// The outcome of verifying a transaction.
+pub struct Verified {
+    pub id: TxId,
+    pub tx: Tx,
+    pub result: Result<()>,
+}
+
+impl Verified {
+    /// Adds the transaction to `mempool` if it verified.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it did not verify, or the mempool rejects it.
+    pub fn insert_into<V: ProofVerifier>(self, mempool: &mut Mempool<V>) -> Result<TxId> {
+        self.result?;
+        mempool.insert_verified(self.id, self.tx)
+    }
+}
+
+pub struct VerificationPool {
+    queue: Option<SyncSender<Tx>>,
+    results: Receiver<Verified>,
+    /// Transactions submitted whose results were not received yet.
+    in_flight: Arc<AtomicUsize>,
+    capacity: usize,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl VerificationPool {
+    /// Starts `workers` threads that validate transactions, compute their ids
+    /// and attestation hashes, and check their proofs with `verifier`. At most
+    /// `capacity` transactions wait for a worker.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is zero.
+    #[must_use]
+    pub fn new<V>(
+        verifier: V,
+        workers: usize,
+        capacity: usize,
+        metrics: Option<Arc<Metrics>>,
+    ) -> Self
+    where
+        V: ProofVerifier + Send + Sync + 'static, {
+        assert!(workers > 0, "a verification pool needs workers");
+        let (queue, jobs) = mpsc::sync_channel::<Tx>(capacity);
+        let (done, results) = mpsc::channel();
+        let jobs = Arc::new(Mutex::new(jobs));
+        let verifier = Arc::new(verifier);
+        let workers = (0..workers)
+            .map(|_| {
+                let jobs = Arc::clone(&jobs);
+                let verifier = Arc::clone(&verifier);
+                let done = done.clone();
+                let metrics = metrics.clone();
+                thread::spawn(move || loop {
+                    // Only hold the lock while waiting, not while verifying.
+                    let job = jobs.lock().unwrap_or_else(PoisonError::into_inner).recv();
+                    let Ok(tx) = job else {
+                        // The pool was dropped.
+                        break;
+                    };
+                    let started = Instant::now();
+                    let id = TxId::of(&tx);
+                    let result = validate(&tx).and_then(|()| verifier.verify(&tx));
+                    if let Some(metrics) = &metrics {
+                        metrics
+                            .proof_verification
+                            .get("transaction")
+                            .observe(started.elapsed());
+                    }
+                    if done.send(Verified { id, tx, result }).is_err() {
+                        break;
+                    }
+                })
+            })
+            .collect();
+        Self {
+            queue: Some(queue),
+            results,
+            in_flight: Arc::default(),
+            capacity,
+            workers,
+        }
+    }
+
+    /// Queues `tx` for verification, or hands it back if the queue is full.
+    ///
+    /// # Errors
+    ///
+    /// Returns `tx` if there is no room for it.
+    pub fn try_submit(&self, tx: Tx) -> Result<(), Tx> {
+        let Some(queue) = &self.queue else {
+            return Err(tx);
+        };
+        match queue.try_send(tx) {
+            Ok(()) => {
+                self.in_flight.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(TrySendError::Full(tx) | TrySendError::Disconnected(tx)) => Err(tx),
+        }
+    }
+
+    /// Queues `tx` for verification, waiting for room if the queue is full.
+    pub fn submit(&self, tx: Tx) {
+        // The workers only stop once the queue is dropped, so sending can not
+        // fail.
+        if let Some(queue) = &self.queue {
+            if queue.send(tx).is_ok() {
+                self.in_flight.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Whether new transactions would have to wait for room in the queue.
+    #[must_use]
+    pub fn is_saturated(&self) -> bool {
+        self.in_flight.load(Ordering::Relaxed) >= self.capacity + self.workers.len()
+    }
+
+    /// Transactions submitted whose results were not received yet.
+    #[must_use]
+    pub fn in_flight(&self) -> usize { self.in_flight.load(Ordering::Relaxed) }
+
+    /// Returns the results that are ready, in the order they finished.
+    pub fn ready(&self) -> impl Iterator<Item = Verified> + '_ {
+        self.results.try_iter().inspect(|_| {
+            self.in_flight.fetch_sub(1, Ordering::Relaxed);
+        })
+    }
+
+    /// Waits for the next result. Returns `None` if nothing is in flight.
+    pub fn recv(&self) -> Option<Verified> {
+        if self.in_flight() == 0 {
+            return None;
+        }
+        let verified = self.results.recv().ok()?;
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+        Some(verified)
+    }
+}
+
+impl Drop for VerificationPool {
+    fn drop(&mut self) {
+        self.queue = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Barrier;
+
+    use anyhow::bail;
+    use itertools::Itertools;
+    use mozak_sdk::common::types::{Poseidon2Hash, ProgramIdentifier};
+    use mozak_sdk::native::OrderedEvents;
+    use plonky2::hash::hash_types::HashOut;
+    use plonky2::hash::merkle_tree::MerkleCap;
+
+    use super::*;
+    use crate::mempool::MempoolConfig;
+    use crate::types::{Attestation, Transaction};
+
+    fn tx(public_tape: &[u8]) -> Tx {
+        let id = ProgramIdentifier(Poseidon2Hash::from([1; 4]));
+        Transaction {
+            cast_list: vec![id],
+            call_tape_hash: MerkleCap(vec![HashOut::ZERO]),
+            constituent_zs: vec![Attestation {
+                id,
+                public_tape: public_tape.to_vec(),
+                event_tape: OrderedEvents::default(),
+            }],
+        }
+    }
+
+    fn reject_empty(tx: &Tx) -> Result<()> {
+        if tx.constituent_zs[0].public_tape.is_empty() {
+            bail!("bad proof");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn verify_into_mempool() {
+        let metrics = Arc::new(Metrics::default());
+        let pool = VerificationPool::new(reject_empty, 4, 8, Some(Arc::clone(&metrics)));
+        for tape in [&b"a"[..], b"", b"b"] {
+            pool.submit(tx(tape));
+        }
+        let results = std::iter::from_fn(|| pool.recv()).collect_vec();
+        assert_eq!(results.len(), 3);
+        assert_eq!(pool.in_flight(), 0);
+        assert!(pool.recv().is_none());
+
+        let mut mempool = Mempool::new(MempoolConfig::default(), reject_empty);
+        let accepted = results
+            .into_iter()
+            .filter_map(|verified| verified.insert_into(&mut mempool).ok())
+            .collect_vec();
+        assert_eq!(accepted.len(), 2);
+        assert!(mempool.contains(&TxId::of(&tx(b"a"))));
+        assert!(metrics
+            .render()
+            .contains("mozak_proof_verification_seconds_count{kind=\"transaction\"} 3\n"));
+    }
+
+    #[test]
+    fn backpressure() {
+        // Holds the only worker until the queue has been filled.
+        let barrier = Arc::new(Barrier::new(2));
+        let worker_barrier = Arc::clone(&barrier);
+        let pool = VerificationPool::new(
+            move |_: &Tx| -> Result<()> {
+                worker_barrier.wait();
+                Ok(())
+            },
+            1,
+            1,
+            None,
+        );
+        // The worker takes the first transaction, and the second one waits
+        // in the queue.
+        pool.submit(tx(b"a"));
+        pool.submit(tx(b"b"));
+        assert!(pool.is_saturated());
+        let rejected = pool.try_submit(tx(b"c")).unwrap_err();
+        assert_eq!(TxId::of(&rejected), TxId::of(&tx(b"c")));
+
+        barrier.wait();
+        barrier.wait();
+        assert_eq!(std::iter::from_fn(|| pool.recv()).count(), 2);
+        assert!(!pool.is_saturated());
+    }
+}
@@ -6,11 +6,20 @@ tests for examples on cross-program-calls among other things.
 """
 
 # TODO: set up formatting and linting for Python files in CI.
+import argparse
+import difflib
+import graphlib
+import itertools
+import json
 import os
 import re
 import shlex
 import subprocess
+import sys
+import time
 import unittest
+import xml.etree.ElementTree as ET
+from dataclasses import asdict, dataclass
 
 import toml
 from colorama import Fore, Style
@@ -21,8 +30,175 @@ os_environ = os.environ
 os_environ["MOZAK_STARK_DEBUG"] = "true"
 # Turn the following to `True` if you do not want output capturing
 capture_output = False
-# Running timeout per prove-and-verify (in seconds)
+# Running timeout per step (in seconds), unless overridden in `step_timeouts`
 timeout = 600
+# Running timeouts of individual steps (in seconds), set from the command line
+step_timeouts: dict[str, float] = {}
+# How often a failed or timed out step is run again before giving up
+retries = 0
+# Number of lines of output kept in the reports for each step
+output_tail_lines = 50
+
+
+@dataclass
+class StepResult:
+    """Outcome of one command run for an example."""
+
+    example: str
+    step: str
+    command: str
+    # One of "passed", "failed" or "timeout", for the last attempt
+    status: str
+    # Total over all attempts
+    duration: float
+    attempts: int
+    stdout_tail: str
+    stderr_tail: str
+    # How the output differed from what the step expects, if it did
+    mismatch: str = ""
+
+
+step_results: list[StepResult] = []
+
+# Examples and steps to run, set from the command line. `None` runs all of them.
+selected_examples = None
+selected_steps = None
+
+
+def tail(output) -> str:
+    """Returns the last lines of the output of a command, if it was captured."""
+    if output is None:
+        return ""
+    if isinstance(output, bytes):
+        output = output.decode("utf-8", errors="replace")
+    return "\n".join(output.splitlines()[-output_tail_lines:])
+
+
+def step_timeout(step: str) -> float:
+    """Returns the running timeout of a step, in seconds"""
+    return step_timeouts.get(step.split()[0], timeout)
+
+
+class ExpectedOutputError(Exception):
+    """A step exited successfully, but did not print what it was expected to."""
+
+
+def run_step(
+    example: str, step: str, command: str, cwd=None, env=None, check_output=None
+):
+    """Runs a command for an example and records its outcome for the reports.
+    A command that fails or times out is run again up to `retries` times.
+    Raises like `subprocess.run` with `check=True` if the last attempt fails
+    or times out. Steps that were not selected are skipped.
+
+    If `check_output` is given, the output is captured and passed to it, and
+    the step fails with `ExpectedOutputError` if it returns a mismatch."""
+    if selected_steps is not None and step.split()[0] not in selected_steps:
+        print(f"{Fore.YELLOW}Skipping {step} for {example}{Style.RESET_ALL}")
+        return
+    start = time.monotonic()
+    for attempt in range(1, retries + 2):
+        error = None
+        mismatch = ""
+        try:
+            completed = subprocess.run(
+                args=shlex.split(command),
+                cwd=cwd,
+                capture_output=capture_output or check_output is not None,
+                timeout=step_timeout(step),
+                env=os_environ if env is None else {**os_environ, **env},
+                check=False,
+            )
+            stdout, stderr = completed.stdout, completed.stderr
+            if check_output is not None and not capture_output:
+                sys.stdout.buffer.write(stdout)
+                sys.stderr.buffer.write(stderr)
+            if completed.returncode == 0:
+                status = "passed"
+                if check_output is not None:
+                    mismatch = check_output(stdout.decode("utf-8", errors="replace"))
+                    if mismatch:
+                        status = "failed"
+                        error = ExpectedOutputError(
+                            f"{step} for {example} printed unexpected output:\n{mismatch}"
+                        )
+            else:
+                status = "failed"
+                error = subprocess.CalledProcessError(
+                    completed.returncode, completed.args, stdout, stderr
+                )
+        except subprocess.TimeoutExpired as e:
+            stdout, stderr = e.stdout, e.stderr
+            status = "timeout"
+            error = e
+        if error is None:
+            break
+        if attempt <= retries:
+            print(
+                f"{Fore.RED}{step} for {example} {'timed out' if status == 'timeout' else 'failed'}, "
+                f"retrying ({attempt}/{retries}){Style.RESET_ALL}"
+            )
+
+    step_results.append(
+        StepResult(
+            example=example,
+            step=step,
+            command=command,
+            status=status,
+            duration=time.monotonic() - start,
+            attempts=attempt,
+            stdout_tail=tail(stdout),
+            stderr_tail=tail(stderr),
+            mismatch=mismatch,
+        )
+    )
+    if error is not None:
+        raise error
+
+
+def write_reports(directory: str):
+    """Writes the recorded steps as `examples.json` and as a JUnit XML
+    `examples.xml` in `directory`, with one test suite per example."""
+    os.makedirs(directory, exist_ok=True)
+    with open(os.path.join(directory, "examples.json"), "w", encoding="utf-8") as f:
+        json.dump([asdict(result) for result in step_results], f, indent=2)
+
+    suites = ET.Element("testsuites")
+    examples = sorted({result.example for result in step_results})
+    for example in examples:
+        results = [result for result in step_results if result.example == example]
+        suite = ET.SubElement(
+            suites,
+            "testsuite",
+            name=example,
+            tests=str(len(results)),
+            failures=str(sum(result.status == "failed" for result in results)),
+            errors=str(sum(result.status == "timeout" for result in results)),
+            time=f"{sum(result.duration for result in results):.3f}",
+        )
+        for result in results:
+            case = ET.SubElement(
+                suite,
+                "testcase",
+                classname=example,
+                name=result.step,
+                time=f"{result.duration:.3f}",
+            )
+            if result.status == "failed":
+                ET.SubElement(case, "failure", message=result.command).text = (
+                    result.mismatch or result.stderr_tail
+                )
+            elif result.status == "timeout":
+                ET.SubElement(
+                    case,
+                    "error",
+                    message=f"timed out after {step_timeout(result.step)}s: {result.command}",
+                ).text = result.stderr_tail
+            ET.SubElement(case, "system-out").text = result.stdout_tail
+            ET.SubElement(case, "system-err").text = result.stderr_tail
+    ET.ElementTree(suites).write(
+        os.path.join(directory, "examples.xml"), encoding="utf-8", xml_declaration=True
+    )
 
 
 class ReadTomlError(Exception):
@@ -62,6 +238,234 @@ def has_sdk_dependency_beyond_core_features(cargo_file: str) -> bool:
     )
 
 
+@dataclass
+class Step:
+    """A step declared in `package.metadata.mozak.steps` of an example"""
+
+    name: str
+    command: str
+    # Relative to the example's directory
+    cwd: str
+    env: dict[str, str]
+    after: list[str]
+    # Regular expressions that each have to match somewhere in the output
+    expect_stdout: list[str]
+    # File with the exact expected output, relative to the example's directory
+    golden: str | None
+
+
+def check_expected_output(example: str, step: Step):
+    """Returns a function that compares the output of a step with what it
+    expects, or `None` if it does not expect anything. The function returns
+    a readable description of the differences, or an empty string."""
+    if not step.expect_stdout and step.golden is None:
+        return None
+
+    def check(stdout: str) -> str:
+        problems = [
+            f"no match for {pattern!r}"
+            for pattern in step.expect_stdout
+            if re.search(pattern, stdout, re.MULTILINE) is None
+        ]
+        if step.golden is not None:
+            golden = os.path.join("examples", example, step.golden)
+            with open(golden, "r", encoding="utf-8") as f:
+                expected = f.read()
+            problems.extend(
+                difflib.unified_diff(
+                    expected.splitlines(),
+                    stdout.splitlines(),
+                    fromfile=golden,
+                    tofile="actual",
+                    lineterm="",
+                )
+            )
+        return "\n".join(problems)
+
+    return check
+
+
+class StepManifestError(Exception):
+    """Invalid steps declared by an example."""
+
+
+def read_steps(example: str):
+    """Reads the steps declared by an example in its `Cargo.toml`, in an order
+    that runs every step after the ones it depends on. Returns `None` if there
+    are none, in which case the default pipeline is used.
+
+    ```toml
+    [[package.metadata.mozak.steps]]
+    name = "system-tape"
+    command = "cargo run --release"
+    cwd = "native"
+    env = { RUST_LOG = "info" }
+    after = ["build"]
+    expect_stdout = ["^Tape written"]
+    golden = "native/expected.txt"
+    ```
+
+    With a `matrix`, e.g. `matrix = { profile = ["dev", "release"] }`, the
+    steps are run once per combination of values; see `expand_matrix`.
+    """
+    cargo_file = f"examples/{example}/mozakvm/Cargo.toml"
+    declared = (
+        read_toml_file(cargo_file)["package"]
+        .get("metadata", {})
+        .get("mozak", {})
+        .get("steps")
+    )
+    if declared is None:
+        return None
+
+    steps = {}
+    for i, entry in enumerate(declared):
+        where = f"{cargo_file}: steps[{i}]"
+
+        def field(key, kind, default=None):
+            value = entry.get(key, default)
+            if value is None:
+                raise StepManifestError(f"{where}: missing `{key}`")
+            if not isinstance(value, kind):
+                raise StepManifestError(
+                    f"{where}: `{key}` should be a {kind.__name__}, not {value!r}"
+                )
+            return value
+
+        if not isinstance(entry, dict):
+            raise StepManifestError(f"{where}: should be a table")
+        keys = {"name", "command", "cwd", "env", "after", "expect_stdout", "golden"}
+        if unknown := set(entry) - keys:
+            raise StepManifestError(f"{where}: unknown keys {sorted(unknown)}")
+        step = Step(
+            name=field("name", str),
+            command=field("command", str),
+            cwd=field("cwd", str, "."),
+            env=field("env", dict, {}),
+            after=field("after", list, []),
+            expect_stdout=field("expect_stdout", list, []),
+            golden=entry.get("golden"),
+        )
+        where = f"{where} ({step.name})"
+        if step.name in steps:
+            raise StepManifestError(f"{where}: another step has the same name")
+        if not all(isinstance(value, str) for value in step.env.values()):
+            raise StepManifestError(f"{where}: `env` values should be strings")
+        for pattern in step.expect_stdout:
+            try:
+                re.compile(pattern)
+            except (TypeError, re.error) as e:
+                raise StepManifestError(
+                    f"{where}: invalid `expect_stdout` pattern {pattern!r}: {e}"
+                ) from e
+        if step.golden is not None and not os.path.isfile(
+            os.path.join("examples", example, str(step.golden))
+        ):
+            raise StepManifestError(f"{where}: `golden` {step.golden!r} is not a file")
+        # Directories that depend on the matrix are only known once it is expanded.
+        if "{" not in step.cwd and not os.path.isdir(
+            os.path.join("examples", example, step.cwd)
+        ):
+            raise StepManifestError(f"{where}: `cwd` {step.cwd!r} is not a directory")
+        steps[step.name] = step
+
+    for step in steps.values():
+        if unknown := [name for name in step.after if name not in steps]:
+            raise StepManifestError(
+                f"{cargo_file}: step {step.name!r} runs after unknown steps {unknown}"
+            )
+    try:
+        order = graphlib.TopologicalSorter(
+            {step.name: step.after for step in steps.values()}
+        ).static_order()
+        return [steps[name] for name in order]
+    except graphlib.CycleError as e:
+        raise StepManifestError(f"{cargo_file}: steps depend on each other: {e.args[1]}") from e
+
+
+def read_matrix(example: str) -> list[dict[str, str]]:
+    """Reads the `package.metadata.mozak.matrix` of an example and returns
+    every combination of its values. Without a matrix there is a single, empty
+    combination."""
+    cargo_file = f"examples/{example}/mozakvm/Cargo.toml"
+    matrix = (
+        read_toml_file(cargo_file)["package"]
+        .get("metadata", {})
+        .get("mozak", {})
+        .get("matrix", {})
+    )
+    for key, values in matrix.items():
+        if not (
+            isinstance(values, list)
+            and values
+            and all(isinstance(value, str) for value in values)
+        ):
+            raise StepManifestError(
+                f"{cargo_file}: matrix.{key} should be a non-empty list of strings"
+            )
+    return [
+        dict(zip(matrix.keys(), values))
+        for values in itertools.product(*matrix.values())
+    ]
+
+
+def expand_matrix(example: str, steps: list[Step], combination: dict[str, str]):
+    """Substitutes `{key}` in the commands, working directories and
+    environments of the steps with the values of a matrix combination. Each
+    combination also gets its own scratch directory, `{scratch}`, and the
+    combination is appended to the names of the steps so they are reported
+    apart."""
+    if not combination:
+        return steps
+    label = ",".join(f"{key}={value}" for key, value in combination.items())
+    scratch = os.path.join(
+        "target", "matrix", re.sub(r"[^A-Za-z0-9_.-]", "_", label)
+    )
+    os.makedirs(os.path.join("examples", example, scratch), exist_ok=True)
+    values = {
+        **combination,
+        "scratch": os.path.abspath(os.path.join("examples", example, scratch)),
+    }
+
+    def substitute(text: str) -> str:
+        return re.sub(
+            r"\{(\w+)\}", lambda m: values.get(m.group(1), m.group(0)), text
+        )
+
+    return [
+        Step(
+            name=f"{step.name} [{label}]",
+            command=substitute(step.command),
+            cwd=substitute(step.cwd),
+            env={key: substitute(value) for key, value in step.env.items()},
+            after=step.after,
+            expect_stdout=step.expect_stdout,
+            golden=step.golden,
+        )
+        for step in steps
+    ]
+
+
+def run_declared_steps(example: str, steps: list[Step]):
+    """Runs the steps an example declared instead of the default pipeline, once
+    per combination of its matrix"""
+    print(
+        f"{Style.BRIGHT}{Fore.BLUE}{example}{Style.RESET_ALL} declares its own steps"
+    )
+    for combination in read_matrix(example):
+        for step in expand_matrix(example, steps, combination):
+            print(f"{step.name}: {Fore.BLUE}{step.command}{Style.RESET_ALL}")
+            run_step(
+                example,
+                step.name,
+                step.command,
+                cwd=os.path.join("examples", example, step.cwd),
+                env=step.env,
+                check_output=check_expected_output(example, step),
+            )
+    print()
+
+
 def has_no_native_target(example_dir: str) -> bool:
     """Checks if the example directory doesn't have native directory inside.
     So we also require that the crate shouldn't have sdk dependency beyond core features
@@ -73,6 +477,16 @@ def has_no_native_target(example_dir: str) -> bool:
 
 
 
+def list_examples():
+    """Lists the examples to run, i.e. all of them unless some were selected
+    on the command line"""
+    return sorted(
+        example
+        for example in list_cargo_projects("examples")
+        if selected_examples is None or example in selected_examples
+    )
+
+
 class ExamplesTester(unittest.TestCase):
     """Test class for running examples"""
 
@@ -82,10 +496,14 @@ class ExamplesTester(unittest.TestCase):
         """
         prove_and_verify_exceptions = {"panic"}  # TODO: check why `panic` doesn't work
 
-        for example in set(list_cargo_projects("examples")):
+        for example in list_examples():
             if has_no_native_target(
                 f"examples/{example}"
             ):
+                if (steps := read_steps(example)) is not None:
+                    run_declared_steps(example, steps)
+                    continue
+
                 print(
                     f"{Style.BRIGHT}{Fore.BLUE}{example}{Style.RESET_ALL} is detected core-only example"
                 )
@@ -93,13 +511,11 @@ class ExamplesTester(unittest.TestCase):
                 build_command = "cargo mozakvm-build"
                 print(f"Testing build: {Fore.BLUE}{build_command}{Style.RESET_ALL}")
 
-                subprocess.run(
-                    args=shlex.split(build_command),
+                run_step(
+                    example,
+                    "build",
+                    build_command,
                     cwd=os.path.join("examples", example, "mozakvm"),
-                    capture_output=capture_output,
-                    timeout=timeout,
-                    env=os_environ,
-                    check=True,
                 )
 
                 if example in prove_and_verify_exceptions:
@@ -114,13 +530,7 @@ class ExamplesTester(unittest.TestCase):
                         f"ZK prove and verify: {Fore.BLUE}{prove_and_verify_command}{Style.RESET_ALL}"
                     )
 
-                    subprocess.run(
-                        args=shlex.split(prove_and_verify_command),
-                        capture_output=capture_output,
-                        timeout=timeout,
-                        env=os_environ,
-                        check=True,
-                    )
+                    run_step(example, "prove-and-verify", prove_and_verify_command)
                 print()
 
     def test_full_featured_examples(self):
@@ -130,8 +540,11 @@ class ExamplesTester(unittest.TestCase):
         """
         prove_and_verify_exceptions = {}
 
-        for example in set(list_cargo_projects("examples")):
+        for example in list_examples():
             if not has_no_native_target(f"examples/{example}"):
+                if (steps := read_steps(example)) is not None:
+                    run_declared_steps(example, steps)
+                    continue
                 print(
                     f"{Style.BRIGHT}{Fore.BLUE}{example}{Style.RESET_ALL} is detected fully-featured example, building",
                 )
@@ -141,18 +554,16 @@ class ExamplesTester(unittest.TestCase):
                     f"Testing build: {Fore.BLUE}{build_command}{Style.RESET_ALL}",
                 )
 
-                subprocess.run(
-                    args=shlex.split(build_command),
+                run_step(
+                    example,
+                    "build",
+                    build_command,
                     cwd=os.path.join("examples", example, "mozakvm"),
-                    capture_output=capture_output,
-                    timeout=timeout,
-                    env=os_environ,
-                    check=True,
                 )
                 print()
 
-        for example in set(list_cargo_projects("examples")):
-            if not has_no_native_target(f"examples/{example}"):
+        for example in list_examples():
+            if not has_no_native_target(f"examples/{example}") and read_steps(example) is None:
                 print(
                     f"{Style.BRIGHT}{Fore.BLUE}{example}{Style.RESET_ALL} is detected fully-featured example, ZK prove and verify",
                 )
@@ -181,13 +592,11 @@ class ExamplesTester(unittest.TestCase):
                         f"System tape generation: {Fore.BLUE}{system_tape_generation_command}{Style.RESET_ALL}",
                     )
 
-                    subprocess.run(
-                        args=shlex.split(system_tape_generation_command),
+                    run_step(
+                        example,
+                        "system-tape",
+                        system_tape_generation_command,
                         cwd=f"examples/{example}/native",
-                        capture_output=capture_output,
-                        timeout=timeout,
-                        env=os_environ,
-                        check=True,
                     )
 
                     print()
@@ -212,16 +621,58 @@ class ExamplesTester(unittest.TestCase):
                             f"ZK prove and verify (sub-proof): {Fore.BLUE}{execution_command}{Style.RESET_ALL}",
                         )
 
-                        subprocess.run(
-                            args=shlex.split(execution_command),
-                            capture_output=capture_output,
-                            timeout=timeout,
-                            env=os_environ,
-                            check=True,
+                        run_step(
+                            example,
+                            f"prove-and-verify {os.path.basename(elf)}",
+                            execution_command,
                         )
 
                 print()
 
 
 if __name__ == "__main__":
-    unittest.main()
+    parser = argparse.ArgumentParser(description=__doc__, add_help=False)
+    parser.add_argument(
+        "--example",
+        action="append",
+        help="only run this example; can be given more than once. Examples listed in "
+        "`example_dependents` are not selected automatically",
+    )
+    parser.add_argument(
+        "--step",
+        action="append",
+        help="only run this step, e.g. build, system-tape, prove-and-verify or one "
+        "declared by an example; can be given more than once",
+    )
+    parser.add_argument(
+        "--timeout",
+        action="append",
+        default=[],
+        metavar="[STEP=]SECONDS",
+        help="running timeout of every step, or of the given step; can be given more than once",
+    )
+    parser.add_argument(
+        "--retries",
+        type=int,
+        default=0,
+        help="how often to run a failed or timed out step again",
+    )
+    parser.add_argument(
+        "--report-dir",
+        help="write a JSON and a JUnit XML report of every step to this directory",
+    )
+    args, unittest_args = parser.parse_known_args()
+    selected_examples = args.example
+    selected_steps = args.step
+    retries = args.retries
+    for value in args.timeout:
+        step, _, seconds = value.rpartition("=")
+        if step:
+            step_timeouts[step] = float(seconds)
+        else:
+            timeout = float(seconds)
+
+    program = unittest.main(argv=sys.argv[:1] + unittest_args, exit=False)
+    if args.report_dir:
+        write_reports(args.report_dir)
+    sys.exit(not program.result.wasSuccessful())
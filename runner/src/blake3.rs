@@ -0,0 +1,151 @@
+use itertools::{chain, izip};
+use mozak_sdk::core::blake3::{compress, BLOCK_BYTES, CV_BYTES};
+use mozak_sdk::core::reg_abi::{REG_A1, REG_A2, REG_A3, REG_A4, REG_A5, REG_A6, REG_A7};
+use plonky2::hash::hash_types::RichField;
+
+use crate::state::{Aux, State};
+
+/// A record of one call to the BLAKE3 compression function, for the
+/// circuits to replay.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub cv_addr: u32,
+    pub block_addr: u32,
+    pub output_addr: u32,
+    pub counter_lo: u32,
+    pub counter_hi: u32,
+    pub block_len: u32,
+    pub flags: u32,
+    pub cv: [u8; CV_BYTES],
+    pub block: [u8; BLOCK_BYTES],
+}
+
+impl Entry {
+    #[must_use]
+    pub fn counter(&self) -> u64 { (u64::from(self.counter_hi) << 32) | u64::from(self.counter_lo) }
+}
+
+impl<F: RichField> State<F> {
+    #[must_use]
+    /// Compresses the block at `a2` into the chaining value at `a1`, and
+    /// writes the new chaining value to `a3`.  The counter is in `a4` and
+    /// `a5`, low word first, followed by the block length in `a6` and the
+    /// flags in `a7`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the output can not be stored.
+    pub fn ecall_blake3(self) -> (Aux<F>, Self) {
+        let [cv_addr, block_addr, output_addr, counter_lo, counter_hi, block_len, flags] =
+            [REG_A1, REG_A2, REG_A3, REG_A4, REG_A5, REG_A6, REG_A7]
+                .map(|reg| self.get_register_value(reg));
+        let load = |addr: u32, i: usize| {
+            self.load_u8(addr.wrapping_add(u32::try_from(i).expect("i < 2^32")))
+        };
+        let cv: [u8; CV_BYTES] = core::array::from_fn(|i| load(cv_addr, i));
+        let block: [u8; BLOCK_BYTES] = core::array::from_fn(|i| load(block_addr, i));
+        let entry = Entry {
+            cv_addr,
+            block_addr,
+            output_addr,
+            counter_lo,
+            counter_hi,
+            block_len,
+            flags,
+            cv,
+            block,
+        };
+        let output = compress(&cv, &block, entry.counter(), block_len, flags);
+
+        let mem_addresses_used: Vec<u32> = chain!(
+            izip!(0.., &cv).map(|(i, _)| cv_addr.wrapping_add(i)),
+            izip!(0.., &block).map(|(i, _)| block_addr.wrapping_add(i)),
+            izip!(0.., &output).map(|(i, _)| output_addr.wrapping_add(i)),
+        )
+        .collect();
+        (
+            Aux {
+                mem_addresses_used,
+                blake3: Some(entry),
+                ..Default::default()
+            },
+            izip!(0.., output)
+                .fold(self, |updated_self, (i, byte)| {
+                    updated_self
+                        .store_u8(output_addr.wrapping_add(i), byte)
+                        .unwrap()
+                })
+                .bump_pc(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mozak_sdk::core::blake3::{compress, BLOCK_BYTES, CHUNK_START, CV_BYTES};
+    use mozak_sdk::core::ecall;
+    use mozak_sdk::core::reg_abi::{
+        REG_A0, REG_A1, REG_A2, REG_A3, REG_A4, REG_A5, REG_A6, REG_A7,
+    };
+
+    use crate::code;
+    use crate::decode::ECALL;
+    use crate::instruction::{Args, Instruction, Op};
+
+    #[test]
+    fn ecall_blake3() {
+        let (cv_addr, block_addr, output_addr) = (1024, 2048, 4096);
+        let cv: [u8; CV_BYTES] = core::array::from_fn(|i| u8::try_from(i).unwrap());
+        let block: [u8; BLOCK_BYTES] =
+            core::array::from_fn(|i| u8::try_from((3 * i + 1) % 256).unwrap());
+        let counter = 0x0000_0001_0000_0002;
+        let memory: Vec<(u32, u8)> = (cv_addr..)
+            .zip(cv)
+            .chain((block_addr..).zip(block))
+            .collect();
+        let set = |rd, imm| Instruction {
+            op: Op::ADD,
+            args: Args {
+                rd,
+                imm,
+                ..Args::default()
+            },
+        };
+        let (_program, record) = code::execute(
+            [
+                set(REG_A0, ecall::BLAKE3),
+                set(REG_A1, cv_addr),
+                set(REG_A2, block_addr),
+                set(REG_A3, output_addr),
+                set(REG_A4, 2),
+                set(REG_A5, 1),
+                set(REG_A6, 64),
+                set(REG_A7, CHUNK_START),
+                ECALL,
+            ],
+            &memory,
+            &[],
+        );
+
+        let expected = compress(&cv, &block, counter, 64, CHUNK_START);
+        let last_state = &record.last_state;
+        let output: Vec<u8> = (output_addr..)
+            .take(CV_BYTES)
+            .map(|addr| last_state.load_u8(addr))
+            .collect();
+        assert_eq!(output, expected);
+
+        let entry = record
+            .executed
+            .iter()
+            .find_map(|row| row.aux.blake3.clone())
+            .expect("the ecall is recorded");
+        assert_eq!(entry.cv, cv);
+        assert_eq!(entry.block, block);
+        assert_eq!(entry.counter(), counter);
+        assert_eq!(
+            (entry.cv_addr, entry.block_addr, entry.output_addr),
+            (cv_addr, block_addr, output_addr)
+        );
+    }
+}
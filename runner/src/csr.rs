@@ -0,0 +1,48 @@
+//! The Control and Status Registers (CSRs) we support.
+//!
+//! Most CSRs only matter to an operating system, so accesses to them are
+//! decoded as no-ops.  But guest code compiled for riscv32 reads the cycle
+//! counter, eg for benchmarking, and uses the scratch registers:
+//!
+//! - `cycle` and `instret` read the clock.  As every instruction takes a single
+//!   cycle, they are the same.  `cycleh` and `instreth` are their upper 32
+//!   bits.
+//! - `mscratch` and `sscratch` are kept in the register file, right after the
+//!   32 general purpose registers, so they are read and written like any other
+//!   register.
+
+/// Cycle counter
+pub const CYCLE: u16 = 0xC00;
+/// Instructions-retired counter
+pub const INSTRET: u16 = 0xC02;
+/// Upper 32 bits of [`CYCLE`]
+pub const CYCLEH: u16 = 0xC80;
+/// Upper 32 bits of [`INSTRET`]
+pub const INSTRETH: u16 = 0xC82;
+/// Scratch register for supervisor trap handlers
+pub const SSCRATCH: u16 = 0x140;
+/// Scratch register for machine trap handlers
+pub const MSCRATCH: u16 = 0x340;
+
+/// Register that holds [`MSCRATCH`]
+pub const REG_MSCRATCH: u8 = 32;
+/// Register that holds [`SSCRATCH`].  This is the last register.
+pub const REG_SSCRATCH: u8 = 33;
+/// Number of registers, including the ones that hold CSRs.
+pub const NUM_REGISTERS: usize = 34;
+
+/// The register that holds `csr`, if we keep it in the register file.
+#[must_use]
+pub fn register(csr: u16) -> Option<u8> {
+    match csr {
+        MSCRATCH => Some(REG_MSCRATCH),
+        SSCRATCH => Some(REG_SSCRATCH),
+        _ => None,
+    }
+}
+
+/// Whether we support `csr`.  Accesses to the others are no-ops.
+#[must_use]
+pub fn is_supported(csr: u16) -> bool {
+    matches!(csr, CYCLE | INSTRET | CYCLEH | INSTRETH) || register(csr).is_some()
+}
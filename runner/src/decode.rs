@@ -2,6 +2,7 @@ use bitfield::{bitfield, BitRange};
 use log::warn;
 use mozak_sdk::core::reg_abi::{REG_A0, REG_A1, REG_ZERO};
 
+use crate::csr;
 use crate::instruction::{Args, DecodingError, Instruction, Op, NOP};
 
 /// Extract a u32 that represents the immediate from segments with zeros right
@@ -101,6 +102,14 @@ pub fn decode_instruction(pc: u32, word: u32) -> Result<Instruction, DecodingErr
         rs2,
         ..Default::default()
     };
+    // Atomics read from and write to memory like stores, and also write to
+    // `rd`.  So we swap rs1 and rs2 for them, too.
+    let stype_atomic = Args {
+        rd,
+        rs1: rs2,
+        rs2: rs1,
+        ..Default::default()
+    };
     let itype = Args {
         rs1,
         rd,
@@ -171,6 +180,8 @@ pub fn decode_instruction(pc: u32, word: u32) -> Result<Instruction, DecodingErr
             (0x1, 0x01) => (Op::MULH, rtype),
             (0x2, 0x01) => (Op::MULHSU, rtype),
             (0x3, 0x01) => (Op::MULHU, rtype),
+            (0x1, 0x30) => (Op::ROL, rtype),
+            (0x5, 0x30) => (Op::ROR, rtype),
             _ => return default(),
         },
         0b000_0011 => match bf.funct3() {
@@ -219,6 +230,13 @@ pub fn decode_instruction(pc: u32, word: u32) -> Result<Instruction, DecodingErr
                         imm: 1 << itype.imm,
                         ..itype
                     }),
+                    // For RISC-V it's RORI, but we handle it as ROL by the
+                    // complementary amount.
+                    0b011_0000 => (Op::ROL, Args {
+                        imm: 32_u32.wrapping_sub(itype.imm) & 0b1_1111,
+                        ..itype
+                    }),
+                    0b011_0100 if itype.imm == 0b1_1000 => (Op::REV8, Args { imm: 0, ..itype }),
                     _ => return default(),
                 }
             }
@@ -231,6 +249,11 @@ pub fn decode_instruction(pc: u32, word: u32) -> Result<Instruction, DecodingErr
         #[allow(clippy::match_same_arms)]
         0b111_0011 => match (bf.funct3(), bf.funct12()) {
             (0x0, 0x0) => (ECALL.op, ECALL.args),
+            (funct3, csr) if funct3 & 0b11 != 0 && csr::is_supported(csr) =>
+                match decode_csr(funct3, csr, rd, rs1) {
+                    Some(decoded) => decoded,
+                    None => return default(),
+                },
             // For RISC-V this would be MRET,
             // but so far we implemented it as a no-op.
             (0x0, 0x302) => nop,
@@ -272,12 +295,103 @@ pub fn decode_instruction(pc: u32, word: u32) -> Result<Instruction, DecodingErr
         // For RISC-V this would be (Op::FENCE, itype)
         // but so far we implemented it as a no-op.
         0b000_1111 => nop,
+        // Atomics.  The `aq` and `rl` bits only order memory accesses between
+        // harts, and there is only a single one.
+        0b010_1111 => match (bf.funct3(), bf.funct7() >> 2) {
+            // For RISC-V it's LR.W, but we handle it as LW.
+            (0x2, 0b00010) if rs2 == 0 => (Op::LW, Args {
+                imm: 0,
+                ..itype_load
+            }),
+            (0x2, 0b00011) => (Op::SC, stype_atomic),
+            (0x2, 0b00001) => (Op::AMOSWAP, stype_atomic),
+            (0x2, 0b00000) => (Op::AMOADD, stype_atomic),
+            (0x2, 0b00100) => (Op::AMOXOR, stype_atomic),
+            (0x2, 0b01100) => (Op::AMOAND, stype_atomic),
+            (0x2, 0b01000) => (Op::AMOOR, stype_atomic),
+            (0x2, 0b10000) => (Op::AMOMIN, stype_atomic),
+            (0x2, 0b10100) => (Op::AMOMAX, stype_atomic),
+            (0x2, 0b11000) => (Op::AMOMINU, stype_atomic),
+            (0x2, 0b11100) => (Op::AMOMAXU, stype_atomic),
+            _ => return default(),
+        },
         _ => return default(),
     };
 
     Ok(Instruction::new(op, args))
 }
 
+/// Decodes an access to one of the CSRs we support, see [`csr`].
+///
+/// Where we can, we handle it as an existing op on the register that holds the
+/// CSR.  Returns `None` for writes to the read-only counters.
+fn decode_csr(funct3: u8, csr: u16, rd: u8, rs1: u8) -> Option<(Op, Args)> {
+    // The immediate variants encode an unsigned 5-bit immediate in place of
+    // rs1.
+    let (src, uimm) = if funct3 & 0b100 == 0 {
+        (rs1, 0)
+    } else {
+        (REG_ZERO, u32::from(rs1))
+    };
+    let is_write = funct3 & 0b11 == 0b01;
+    // CSRRS and CSRRC don't write, when they neither set nor clear any bits.
+    let is_read_only = !is_write && src == REG_ZERO && uimm == 0;
+    let Some(reg) = csr::register(csr) else {
+        return is_read_only.then(|| match csr {
+            csr::CYCLEH | csr::INSTRETH => (Op::RDCYCLEH, Args {
+                rd,
+                ..Args::default()
+            }),
+            _ => (Op::RDCYCLE, Args {
+                rd,
+                ..Args::default()
+            }),
+        });
+    };
+    Some(match funct3 & 0b11 {
+        // For RISC-V it's CSRR, but we handle it as ADD.
+        _ if is_read_only => (Op::ADD, Args {
+            rd,
+            rs1: reg,
+            ..Args::default()
+        }),
+        // For RISC-V it's CSRW or CSRWI, but we handle it as ADD.
+        0b01 if rd == REG_ZERO => (Op::ADD, Args {
+            rd: reg,
+            rs1: src,
+            imm: uimm,
+            ..Args::default()
+        }),
+        // For RISC-V it's CSRS or CSRSI, but we handle it as OR.
+        0b10 if rd == REG_ZERO => (Op::OR, Args {
+            rd: reg,
+            rs1: reg,
+            rs2: src,
+            imm: uimm,
+        }),
+        // For RISC-V it's CSRCI, but we handle it as AND.
+        0b11 if rd == REG_ZERO && src == REG_ZERO => (Op::AND, Args {
+            rd: reg,
+            rs1: reg,
+            imm: !uimm,
+            ..Args::default()
+        }),
+        kind => (
+            match kind {
+                0b01 => Op::CSRRW,
+                0b10 => Op::CSRRS,
+                _ => Op::CSRRC,
+            },
+            Args {
+                rd,
+                rs1: reg,
+                rs2: src,
+                imm: uimm,
+            },
+        ),
+    })
+}
+
 /// ECALL in Risc-V doesn't officially have rs1 and rs2, but we find it
 /// convenient to pretend that it does; and it doesn't make any difference to
 /// which executions are valid or invalid.
@@ -388,6 +502,53 @@ mod tests {
         assert_eq!(ins, match_ins);
     }
 
+    #[test_case(0x60c5_9533, Op::ROL; "rol a0, a1, a2")]
+    #[test_case(0x60c5_d533, Op::ROR; "ror a0, a1, a2")]
+    fn rotate(word: u32, op: Op) {
+        let ins: Instruction = decode_instruction(0, word);
+        let match_ins = Instruction {
+            op,
+            args: Args {
+                rd: 10,
+                rs1: 11,
+                rs2: 12,
+                ..Default::default()
+            },
+        };
+        assert_eq!(ins, match_ins);
+    }
+
+    #[test_case(0x6075_d513, 25; "rori a0, a1, 7")]
+    #[test_case(0x6005_d513, 0; "rori a0, a1, 0")]
+    #[test_case(0x61f5_d513, 1; "rori a0, a1, 31")]
+    fn rori(word: u32, rotate_left: u32) {
+        let ins: Instruction = decode_instruction(0, word);
+        let match_ins = Instruction {
+            op: Op::ROL,
+            args: Args {
+                rd: 10,
+                rs1: 11,
+                imm: rotate_left,
+                ..Default::default()
+            },
+        };
+        assert_eq!(ins, match_ins);
+    }
+
+    #[test_case(0x6985_d513; "rev8 a0, a1")]
+    fn rev8(word: u32) {
+        let ins: Instruction = decode_instruction(0, word);
+        let match_ins = Instruction {
+            op: Op::REV8,
+            args: Args {
+                rd: 10,
+                rs1: 11,
+                ..Default::default()
+            },
+        };
+        assert_eq!(ins, match_ins);
+    }
+
     #[test_case(0x0139_52b3, 5, 18, 19; "srl r5, r18, r19")]
     fn srl(word: u32, rd: u8, rs1: u8, rs2: u8) {
         let ins: Instruction = decode_instruction(0, word);
@@ -1062,4 +1223,72 @@ mod tests {
         let ins: Instruction = decode_instruction(0, word);
         assert_eq!(ins, NOP);
     }
+
+    #[test_case(0xc000_2573, Op::RDCYCLE, 10, 0, 0, 0; "rdcycle a0")]
+    #[test_case(0xc020_2573, Op::RDCYCLE, 10, 0, 0, 0; "rdinstret a0")]
+    #[test_case(0xc800_2573, Op::RDCYCLEH, 10, 0, 0, 0; "rdcycleh a0")]
+    #[test_case(0xc820_2573, Op::RDCYCLEH, 10, 0, 0, 0; "rdinstreth a0")]
+    #[test_case(0x3400_2573, Op::ADD, 10, 32, 0, 0; "csrr a0, mscratch")]
+    #[test_case(0x1400_7573, Op::ADD, 10, 33, 0, 0; "csrrci a0, sscratch, 0")]
+    #[test_case(0x3405_9073, Op::ADD, 32, 11, 0, 0; "csrw mscratch, a1")]
+    #[test_case(0x1402_d073, Op::ADD, 33, 0, 0, 5; "csrwi sscratch, 5")]
+    #[test_case(0x3405_a073, Op::OR, 32, 32, 11, 0; "csrs mscratch, a1")]
+    #[test_case(0x3401_f073, Op::AND, 32, 32, 0, !3; "csrci mscratch, 3")]
+    #[test_case(0x3405_9573, Op::CSRRW, 10, 32, 11, 0; "csrrw a0, mscratch, a1")]
+    #[test_case(0x1402_6573, Op::CSRRS, 10, 33, 0, 4; "csrrsi a0, sscratch, 4")]
+    #[test_case(0x3405_b073, Op::CSRRC, 0, 32, 11, 0; "csrc mscratch, a1")]
+    fn csr(word: u32, op: Op, rd: u8, rs1: u8, rs2: u8, imm: u32) {
+        let ins: Instruction = decode_instruction(0, word);
+        let match_ins = Instruction {
+            op,
+            args: Args { rd, rs1, rs2, imm },
+        };
+        assert_eq!(ins, match_ins);
+    }
+
+    #[test]
+    fn csr_write_to_cycle_counter() {
+        // csrw cycle, a1
+        assert!(super::decode_instruction(0, 0xc005_9073).is_err());
+    }
+
+    #[test_case(0x1006_252f, 10, 12; "lr.w a0, (a2)")]
+    #[test_case(0x1606_252f, 10, 12; "lr.w.aqrl a0, (a2)")]
+    fn lr_w(word: u32, rd: u8, rs1: u8) {
+        let ins: Instruction = decode_instruction(0, word);
+        let match_ins = Instruction {
+            op: Op::LW,
+            args: Args {
+                rd,
+                rs2: rs1,
+                ..Default::default()
+            },
+        };
+        assert_eq!(ins, match_ins);
+    }
+
+    #[test_case(0x18b6_252f, Op::SC; "sc.w a0, a1, (a2)")]
+    #[test_case(0x08b6_252f, Op::AMOSWAP; "amoswap.w a0, a1, (a2)")]
+    #[test_case(0x00b6_252f, Op::AMOADD; "amoadd.w a0, a1, (a2)")]
+    #[test_case(0x06b6_252f, Op::AMOADD; "amoadd.w.aqrl a0, a1, (a2)")]
+    #[test_case(0x20b6_252f, Op::AMOXOR; "amoxor.w a0, a1, (a2)")]
+    #[test_case(0x60b6_252f, Op::AMOAND; "amoand.w a0, a1, (a2)")]
+    #[test_case(0x40b6_252f, Op::AMOOR; "amoor.w a0, a1, (a2)")]
+    #[test_case(0x80b6_252f, Op::AMOMIN; "amomin.w a0, a1, (a2)")]
+    #[test_case(0xa0b6_252f, Op::AMOMAX; "amomax.w a0, a1, (a2)")]
+    #[test_case(0xc0b6_252f, Op::AMOMINU; "amominu.w a0, a1, (a2)")]
+    #[test_case(0xe0b6_252f, Op::AMOMAXU; "amomaxu.w a0, a1, (a2)")]
+    fn amo_w(word: u32, op: Op) {
+        let ins: Instruction = decode_instruction(0, word);
+        let match_ins = Instruction {
+            op,
+            args: Args {
+                rd: 10,
+                rs1: 11,
+                rs2: 12,
+                ..Default::default()
+            },
+        };
+        assert_eq!(ins, match_ins);
+    }
 }
@@ -30,6 +30,7 @@ impl<F: RichField> State<F> {
         let num_bytes_requested = self.get_register_value(REG_A2);
         log::trace!("ECALL {:?}", op);
 
+        let private_tape_index = self.private_tape.read_index;
         let data = match op {
             StorageDeviceOpcode::StorePublic => read_bytes(
                 &self.public_tape.data,
@@ -66,8 +67,26 @@ impl<F: RichField> State<F> {
                 &mut 0,
                 num_bytes_requested as usize,
             ),
+            StorageDeviceOpcode::StoreOracleTape =>
+                read_bytes(&self.oracle_tape.0, &mut 0, num_bytes_requested as usize),
+            StorageDeviceOpcode::StorePrivateTapeCommitmentTape => read_bytes(
+                &self.private_tape_commitment_tape.0,
+                &mut 0,
+                num_bytes_requested as usize,
+            ),
             StorageDeviceOpcode::None => panic!(),
         };
+        let disclosed = if op == StorageDeviceOpcode::StorePrivate {
+            (private_tape_index..private_tape_index + data.len())
+                .map(|index| {
+                    self.private_tape_disclosures
+                        .iter()
+                        .any(|range| range.contains(&index))
+                })
+                .collect()
+        } else {
+            vec![]
+        };
         let data_len = u32::try_from(data.len()).expect("cannot fit data.len() into u32");
         let mem_addresses_used: Vec<u32> = (0..data_len)
             .map(|i| buffer_start.wrapping_add(i))
@@ -80,6 +99,7 @@ impl<F: RichField> State<F> {
                     addr: buffer_start,
                     op,
                     data: data.clone(),
+                    disclosed,
                 }),
                 ..Default::default()
             },
@@ -148,8 +168,13 @@ impl<F: RichField> State<F> {
             ecall::CAST_LIST_COMMITMENT_TAPE =>
                 self.ecall_read(StorageDeviceOpcode::StoreCastListCommitmentTape),
             ecall::SELF_PROG_ID_TAPE => self.ecall_read(StorageDeviceOpcode::StoreSelfProgIdTape),
+            ecall::ORACLE_TAPE => self.ecall_read(StorageDeviceOpcode::StoreOracleTape),
+            ecall::PRIVATE_TAPE_COMMITMENT_TAPE =>
+                self.ecall_read(StorageDeviceOpcode::StorePrivateTapeCommitmentTape),
             ecall::PANIC => self.ecall_panic(),
             ecall::POSEIDON2 => self.ecall_poseidon2(),
+            ecall::SHA256 => self.ecall_sha256(),
+            ecall::BLAKE3 => self.ecall_blake3(),
             ecall::VM_TRACE_LOG => self.ecall_trace_log(),
             _ => (Aux::default(), self.bump_pc()),
         }
@@ -248,6 +248,18 @@ impl Program {
     }
 }
 
+/// Reads an ELF from `elf` and loads it as a [`Program`].
+///
+/// # Errors
+/// Will return `Err` if reading fails, or see
+/// [`Program::mozak_load_program`].
+pub fn load_program<R: std::io::Read>(mut elf: R) -> Result<Program> {
+    let mut elf_bytes = Vec::new();
+    let bytes_read = elf.read_to_end(&mut elf_bytes)?;
+    log::debug!("Read {bytes_read} of ELF data.");
+    Program::mozak_load_program(&elf_bytes)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1,4 +1,7 @@
-//! RV32I Base Integer Instructions + RV32M Multiply Extension
+//! RV32I Base Integer Instructions + RV32M Multiply Extension + RV32A Atomic
+//! Extension + the rotations and byte-reversal of the Zbb Basic
+//! Bit-Manipulation Extension + the Zicsr Control and Status Register
+//! Extension, for the CSRs in [`crate::csr`]
 use serde::{Deserialize, Serialize};
 
 /// Arguments of a RISC-V instruction
@@ -14,7 +17,7 @@ pub struct Args {
     pub imm: u32,
 }
 
-/// Operands of RV32I + RV32M
+/// Operands of RV32I + RV32M + RV32A + Zbb + Zicsr
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Ord, PartialOrd)]
 #[repr(u8)]
 pub enum Op {
@@ -95,6 +98,56 @@ pub enum Op {
     REM,
     /// Remainder (U): rd = unsigned rs1 % unsigned rs2
     REMU,
+
+    // RV32A Atomic Extension
+    // Load Reserved is handled as `LW`.  As there is only a single hart, no
+    // reservation is ever lost.  Like stores, the atomics keep the address in
+    // `rs2`.
+    // The other atomics are only executed by the runner: the CPU table has no
+    // constraints for them yet, so proving an execution that uses them fails.
+    /// Store Conditional: M[rs2] = rs1; rd = 0
+    SC,
+    /// Atomic Swap: rd = M[rs2]; M[rs2] = rs1
+    AMOSWAP,
+    /// Atomic Add: rd = M[rs2]; M[rs2] = M[rs2] + rs1
+    AMOADD,
+    /// Atomic Xor: rd = M[rs2]; M[rs2] = M[rs2] ^ rs1
+    AMOXOR,
+    /// Atomic And: rd = M[rs2]; M[rs2] = M[rs2] & rs1
+    AMOAND,
+    /// Atomic Or: rd = M[rs2]; M[rs2] = M[rs2] | rs1
+    AMOOR,
+    /// Atomic Min: rd = M[rs2]; M[rs2] = min(signed M[rs2], signed rs1)
+    AMOMIN,
+    /// Atomic Max: rd = M[rs2]; M[rs2] = max(signed M[rs2], signed rs1)
+    AMOMAX,
+    /// Atomic Min (U): rd = M[rs2]; M[rs2] = min(M[rs2], rs1)
+    AMOMINU,
+    /// Atomic Max (U): rd = M[rs2]; M[rs2] = max(M[rs2], rs1)
+    AMOMAXU,
+
+    // Zbb Basic Bit-Manipulation Extension
+    /// Rotate Left: rd = rs1 rotated left by (rs2 + imm)
+    /// Rotate Right Immediate is handled as `ROL`
+    ROL,
+    /// Rotate Right: rd = rs1 rotated right by rs2
+    ROR,
+    /// Byte-Reverse: rd = rs1 with its bytes in reverse order
+    REV8,
+
+    // Zicsr Control and Status Register Extension
+    // Where possible, accesses to the registers that hold CSRs are handled as
+    // `ADD`, `OR` and `AND`.
+    /// Read Cycle Counter: rd = clk
+    RDCYCLE,
+    /// Read upper 32 bits of the Cycle Counter: rd = clk >> 32
+    RDCYCLEH,
+    /// CSR Read and Write: rd = rs1; rs1 = rs2 + imm
+    CSRRW,
+    /// CSR Read and Set: rd = rs1; rs1 = rs1 | (rs2 + imm)
+    CSRRS,
+    /// CSR Read and Clear: rd = rs1; rs1 = rs1 & !(rs2 + imm)
+    CSRRC,
 }
 
 /// NOP Instruction in RISC-V is encoded as ADDI x0, x0, 0.
@@ -9,12 +9,15 @@ use mimalloc::MiMalloc;
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
+pub mod blake3;
 pub mod code;
+pub mod csr;
 pub mod decode;
 pub mod ecall;
 pub mod elf;
 pub mod instruction;
 pub mod poseidon2;
+pub mod sha256;
 pub mod state;
 #[cfg(any(feature = "test", test))]
 pub mod test_utils;
@@ -0,0 +1,127 @@
+use itertools::{chain, izip};
+use mozak_sdk::core::reg_abi::{REG_A1, REG_A2, REG_A3};
+use mozak_sdk::core::sha256::{compress, BLOCK_BYTES, STATE_BYTES};
+use plonky2::hash::hash_types::RichField;
+
+use crate::state::{Aux, State};
+
+/// A record of one call to the SHA-256 compression function, for the
+/// circuits to replay.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub state_addr: u32,
+    pub block_addr: u32,
+    pub output_addr: u32,
+    pub state: [u8; STATE_BYTES],
+    pub block: [u8; BLOCK_BYTES],
+}
+
+impl<F: RichField> State<F> {
+    #[must_use]
+    /// Compresses the block at `a2` into the state at `a1`, and writes the new
+    /// state to `a3`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the output can not be stored.
+    pub fn ecall_sha256(self) -> (Aux<F>, Self) {
+        let state_addr = self.get_register_value(REG_A1);
+        let block_addr = self.get_register_value(REG_A2);
+        let output_addr = self.get_register_value(REG_A3);
+        let load = |addr: u32, i: usize| {
+            self.load_u8(addr.wrapping_add(u32::try_from(i).expect("i < 2^32")))
+        };
+        let state: [u8; STATE_BYTES] = core::array::from_fn(|i| load(state_addr, i));
+        let block: [u8; BLOCK_BYTES] = core::array::from_fn(|i| load(block_addr, i));
+        let output = compress(&state, &block);
+
+        let mem_addresses_used: Vec<u32> = chain!(
+            izip!(0.., &state).map(|(i, _)| state_addr.wrapping_add(i)),
+            izip!(0.., &block).map(|(i, _)| block_addr.wrapping_add(i)),
+            izip!(0.., &output).map(|(i, _)| output_addr.wrapping_add(i)),
+        )
+        .collect();
+        (
+            Aux {
+                mem_addresses_used,
+                sha256: Some(Entry {
+                    state_addr,
+                    block_addr,
+                    output_addr,
+                    state,
+                    block,
+                }),
+                ..Default::default()
+            },
+            izip!(0.., output)
+                .fold(self, |updated_self, (i, byte)| {
+                    updated_self
+                        .store_u8(output_addr.wrapping_add(i), byte)
+                        .unwrap()
+                })
+                .bump_pc(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mozak_sdk::core::ecall;
+    use mozak_sdk::core::reg_abi::{REG_A0, REG_A1, REG_A2, REG_A3};
+    use mozak_sdk::core::sha256::{compress, BLOCK_BYTES, STATE_BYTES};
+
+    use crate::code;
+    use crate::decode::ECALL;
+    use crate::instruction::{Args, Instruction, Op};
+
+    #[test]
+    fn ecall_sha256() {
+        let (state_addr, block_addr, output_addr) = (1024, 2048, 4096);
+        let state: [u8; STATE_BYTES] = core::array::from_fn(|i| u8::try_from(i).unwrap());
+        let block: [u8; BLOCK_BYTES] =
+            core::array::from_fn(|i| u8::try_from((3 * i + 1) % 256).unwrap());
+        let memory: Vec<(u32, u8)> = (state_addr..)
+            .zip(state)
+            .chain((block_addr..).zip(block))
+            .collect();
+        let set = |rd, imm| Instruction {
+            op: Op::ADD,
+            args: Args {
+                rd,
+                imm,
+                ..Args::default()
+            },
+        };
+        let (_program, record) = code::execute(
+            [
+                set(REG_A0, ecall::SHA256),
+                set(REG_A1, state_addr),
+                set(REG_A2, block_addr),
+                set(REG_A3, output_addr),
+                ECALL,
+            ],
+            &memory,
+            &[],
+        );
+
+        let expected = compress(&state, &block);
+        let last_state = &record.last_state;
+        let output: Vec<u8> = (output_addr..)
+            .take(STATE_BYTES)
+            .map(|addr| last_state.load_u8(addr))
+            .collect();
+        assert_eq!(output, expected);
+
+        let entry = record
+            .executed
+            .iter()
+            .find_map(|row| row.aux.sha256.clone())
+            .expect("the ecall is recorded");
+        assert_eq!(entry.state, state);
+        assert_eq!(entry.block, block);
+        assert_eq!(
+            (entry.state_addr, entry.block_addr, entry.output_addr),
+            (state_addr, block_addr, output_addr)
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use std::iter::once;
 use std::marker::PhantomData;
-use std::rc::Rc;
+use std::ops::Range;
+use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
 use im::hashmap::HashMap;
@@ -11,9 +12,10 @@ use plonky2::hash::hash_types::RichField;
 use serde::{Deserialize, Serialize};
 
 use crate::code::Code;
+use crate::csr::NUM_REGISTERS;
 use crate::elf::{Data, Program};
 use crate::instruction::{Args, DecodingError, Instruction};
-use crate::poseidon2;
+use crate::{blake3, poseidon2, sha256};
 
 #[derive(Debug, Clone)]
 pub struct CommitmentTape(pub [u8; DIGEST_BYTES]);
@@ -65,7 +67,9 @@ pub struct State<F: RichField> {
     /// Also used to avoid infinite loop
     pub clk: u64,
     pub halted: bool,
-    pub registers: [u32; 32],
+    /// The general purpose registers, followed by the registers that hold
+    /// CSRs, see [`crate::csr`].
+    pub registers: [u32; NUM_REGISTERS],
     pub pc: u32,
     pub memory: StateMemory,
     pub private_tape: StorageDeviceTape,
@@ -75,6 +79,16 @@ pub struct State<F: RichField> {
     pub events_commitment_tape: CommitmentTape,
     pub cast_list_commitment_tape: CommitmentTape,
     pub self_prog_id_tape: [u8; DIGEST_BYTES],
+    /// Externally attested data supplied by the node, eg a block header
+    /// hash or the digest of a price feed.  It is made public in the proof.
+    pub oracle_tape: CommitmentTape,
+    /// Commitment to the whole private tape.  It is made public in the proof,
+    /// so that the bytes disclosed from the private tape, see
+    /// [`State::private_tape_disclosures`], are tied to it.
+    pub private_tape_commitment_tape: CommitmentTape,
+    /// Byte ranges of the private tape that the prover chose to make public.
+    /// The rest of the private tape stays hidden.
+    pub private_tape_disclosures: Arc<[Range<usize>]>,
     _phantom: PhantomData<F>,
 }
 
@@ -104,7 +118,7 @@ impl StateMemory {
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StorageDeviceTape {
-    pub data: Rc<[u8]>,
+    pub data: Arc<[u8]>,
     pub read_index: usize,
 }
 
@@ -122,7 +136,7 @@ impl Default for StorageDeviceTape {
 impl From<Data> for StorageDeviceTape {
     fn from(data: Data) -> Self {
         Self {
-            data: data.0.values().copied().collect::<Rc<[u8]>>(),
+            data: data.0.values().copied().collect::<Arc<[u8]>>(),
             read_index: 0,
         }
     }
@@ -137,7 +151,7 @@ impl<F: RichField> Default for State<F> {
         Self {
             clk: 2,
             halted: Default::default(),
-            registers: Default::default(),
+            registers: [0; NUM_REGISTERS],
             pc: Default::default(),
             memory: StateMemory::default(),
             private_tape: StorageDeviceTape::default(),
@@ -147,6 +161,9 @@ impl<F: RichField> Default for State<F> {
             events_commitment_tape: CommitmentTape([0; DIGEST_BYTES]),
             cast_list_commitment_tape: CommitmentTape([0; DIGEST_BYTES]),
             self_prog_id_tape: [0; 32],
+            oracle_tape: CommitmentTape([0; DIGEST_BYTES]),
+            private_tape_commitment_tape: CommitmentTape([0; DIGEST_BYTES]),
+            private_tape_disclosures: [].into(),
             _phantom: PhantomData,
         }
     }
@@ -190,6 +207,8 @@ pub enum StorageDeviceOpcode {
     StoreEventsCommitmentTape,
     StoreCastListCommitmentTape,
     StoreSelfProgIdTape,
+    StoreOracleTape,
+    StorePrivateTapeCommitmentTape,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -197,6 +216,9 @@ pub struct StorageDeviceEntry {
     pub addr: u32,
     pub op: StorageDeviceOpcode,
     pub data: Vec<u8>,
+    /// Which bytes of `data` are disclosed, see
+    /// [`State::private_tape_disclosures`].  Empty if none are.
+    pub disclosed: Vec<bool>,
 }
 
 /// Auxiliary information about the instruction execution
@@ -213,6 +235,8 @@ pub struct Aux<F: RichField> {
     pub op2: u32,
     pub op2_raw: u32,
     pub poseidon2: Option<poseidon2::Entry<F>>,
+    pub sha256: Option<sha256::Entry>,
+    pub blake3: Option<blake3::Entry>,
     pub storage_device_entry: Option<StorageDeviceEntry>,
 }
 
@@ -225,6 +249,9 @@ pub struct RawTapes {
     pub events_commitment_tape: [u8; DIGEST_BYTES],
     pub cast_list_commitment_tape: [u8; DIGEST_BYTES],
     pub self_prog_id_tape: [u8; 32],
+    pub oracle_tape: [u8; DIGEST_BYTES],
+    pub private_tape_commitment_tape: [u8; DIGEST_BYTES],
+    pub private_tape_disclosures: Vec<Range<usize>>,
 }
 
 impl<F: RichField> State<F> {
@@ -264,6 +291,9 @@ impl<F: RichField> State<F> {
             cast_list_commitment_tape: CommitmentTape(raw_tapes.cast_list_commitment_tape),
             events_commitment_tape: CommitmentTape(raw_tapes.events_commitment_tape),
             self_prog_id_tape: raw_tapes.self_prog_id_tape,
+            oracle_tape: CommitmentTape(raw_tapes.oracle_tape),
+            private_tape_commitment_tape: CommitmentTape(raw_tapes.private_tape_commitment_tape),
+            private_tape_disclosures: raw_tapes.private_tape_disclosures.into(),
             ..Default::default()
         }
     }
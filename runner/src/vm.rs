@@ -133,6 +133,71 @@ impl<F: RichField> State<F> {
         )
     }
 
+    /// Executes `SC.W`.  The store always succeeds, and `rd` is set to zero,
+    /// as there is no other hart that could take away the reservation of a
+    /// preceding `LR.W`.
+    ///
+    /// # Panics
+    ///
+    /// Panics in case we intend to store to a read-only location, like
+    /// [`State::store`].
+    #[must_use]
+    pub fn store_conditional(self, inst: &Args) -> (Aux<F>, Self) {
+        let (aux, state) = self.store(inst, 4);
+        (
+            Aux { dst_val: 0, ..aux },
+            state.set_register_value(inst.rd, 0),
+        )
+    }
+
+    /// Executes a CSR access that we can not handle as an existing op: puts the
+    /// CSR held in register `rs1` into `rd`, and replaces it with `op` of its
+    /// old value and `rs2 + imm`.
+    #[must_use]
+    pub fn csr_op(self, inst: &Args, op: impl FnOnce(u32, u32) -> u32) -> (Aux<F>, Self) {
+        let csr = self.get_register_value(inst.rs1);
+        let src = self.get_register_value(inst.rs2).wrapping_add(inst.imm);
+        (
+            Aux {
+                dst_val: csr,
+                ..Default::default()
+            },
+            self.set_register_value(inst.rs1, op(csr, src))
+                .set_register_value(inst.rd, csr)
+                .bump_pc(),
+        )
+    }
+
+    /// Executes an atomic memory operation: loads the word at the address
+    /// in `rs2`, stores `op` of that word and the value of `rs1` in its
+    /// place, and puts the loaded word into `rd`.
+    ///
+    /// # Panics
+    ///
+    /// Panics in case we intend to store to a read-only location, like
+    /// [`State::store`].
+    #[must_use]
+    pub fn atomic(self, inst: &Args, op: impl FnOnce(u32, u32) -> u32) -> (Aux<F>, Self) {
+        let addr = self.get_register_value(inst.rs2).wrapping_add(inst.imm);
+        let mem_addresses_used: Vec<u32> = (0..4).map(|i| addr.wrapping_add(i)).collect();
+        let loaded = u32::from_le_bytes([0, 1, 2, 3].map(|i| self.load_u8(addr.wrapping_add(i))));
+        let raw_value = op(loaded, self.get_register_value(inst.rs1));
+        (
+            Aux {
+                dst_val: loaded,
+                mem: Some(MemEntry { addr, raw_value }),
+                mem_addresses_used,
+                ..Default::default()
+            },
+            (0..4)
+                .map(|i| addr.wrapping_add(i))
+                .zip(raw_value.to_le_bytes())
+                .fold(self, |acc, (i, byte)| acc.store_u8(i, byte).unwrap())
+                .set_register_value(inst.rd, loaded)
+                .bump_pc(),
+        )
+    }
+
     #[allow(clippy::cast_sign_loss)]
     #[allow(clippy::cast_possible_truncation)]
     #[allow(clippy::cast_possible_wrap)]
@@ -167,8 +232,11 @@ impl<F: RichField> State<F> {
             Op::BEQ | Op::BNE | Op::BLT | Op::BLTU | Op::BGE | Op::BGEU
         ) {
             rs2_raw
-        } else if matches!(inst.op, Op::SRL | Op::SLL | Op::SRA) {
+        } else if matches!(inst.op, Op::SRL | Op::SLL | Op::SRA | Op::ROL) {
             1u32 << (rs2_raw.wrapping_add(inst.args.imm) & 0b1_1111)
+        } else if inst.op == Op::ROR {
+            // Rotating right is rotating left by the complementary amount.
+            1u32 << (rs2_raw.wrapping_add(inst.args.imm).wrapping_neg() & 0b1_1111)
         } else {
             rs2_raw.wrapping_add(inst.args.imm)
         };
@@ -181,6 +249,22 @@ impl<F: RichField> State<F> {
             Op::SRL => rop!(|a, b| a >> (b & 0b1_1111)),
             // Only use lower 5 bits of rs2 or imm
             Op::SRA => rop!(|a, b| (a as i32 >> (b & 0b1_1111) as i32) as u32),
+            // `rotate_*` already only use the lower 5 bits of rs2 or imm
+            Op::ROL => rop!(u32::rotate_left),
+            Op::ROR => rop!(u32::rotate_right),
+            Op::REV8 => rop!(|a, _| a.swap_bytes()),
+            // The cycle counter wraps around, like `cycle` without `cycleh`.
+            Op::RDCYCLE => {
+                let cycle = self.clk as u32;
+                rop!(|_, _| cycle)
+            }
+            Op::RDCYCLEH => {
+                let cycleh = (self.clk >> 32) as u32;
+                rop!(|_, _| cycleh)
+            }
+            Op::CSRRW => self.csr_op(&inst.args, |_, src| src),
+            Op::CSRRS => self.csr_op(&inst.args, core::ops::BitOr::bitor),
+            Op::CSRRC => self.csr_op(&inst.args, |csr, src| csr & !src),
             Op::SLT => rop!(|a, b| u32::from((a as i32) < (b as i32))),
             Op::SLTU => rop!(|a, b| u32::from(a < b)),
             Op::AND => rop!(core::ops::BitAnd::bitand),
@@ -215,6 +299,17 @@ impl<F: RichField> State<F> {
             Op::DIVU => rop!(divu),
             Op::REM => rop!(rem),
             Op::REMU => rop!(remu),
+            // atomics
+            Op::SC => self.store_conditional(&inst.args),
+            Op::AMOSWAP => self.atomic(&inst.args, |_, b| b),
+            Op::AMOADD => self.atomic(&inst.args, u32::wrapping_add),
+            Op::AMOXOR => self.atomic(&inst.args, core::ops::BitXor::bitxor),
+            Op::AMOAND => self.atomic(&inst.args, core::ops::BitAnd::bitand),
+            Op::AMOOR => self.atomic(&inst.args, core::ops::BitOr::bitor),
+            Op::AMOMIN => self.atomic(&inst.args, |a, b| (a as i32).min(b as i32) as u32),
+            Op::AMOMAX => self.atomic(&inst.args, |a, b| (a as i32).max(b as i32) as u32),
+            Op::AMOMINU => self.atomic(&inst.args, Ord::min),
+            Op::AMOMAXU => self.atomic(&inst.args, Ord::max),
         };
         Ok((
             Aux {
@@ -337,6 +432,7 @@ mod tests {
 
     use super::*;
     use crate::code;
+    use crate::csr::REG_MSCRATCH;
     use crate::decode::ECALL;
     use crate::test_utils::{i16_extra, i32_extra, i8_extra, reg, u16_extra, u32_extra, u8_extra};
 
@@ -444,6 +540,70 @@ mod tests {
             );
         }
 
+        #[test]
+        fn rol_proptest(rd in reg(), rs1 in reg(), rs2 in reg(), rs1_value in u32_extra(), rs2_value in u32_extra()) {
+            prop_assume!(rs1 != rs2);
+            let e = simple_test_code(
+                [Instruction::new(
+                    Op::ROL,
+                    Args {
+                        rd,
+                        rs1,
+                        rs2,
+                        ..Args::default()
+                    }
+                )],
+                &[],
+                &[(rs1, rs1_value), (rs2, rs2_value)]
+            );
+            assert_eq!(
+                e.state_before_final().get_register_value(rd),
+                rs1_value.rotate_left(rs2_value & 0b1_1111)
+            );
+        }
+
+        #[test]
+        fn ror_proptest(rd in reg(), rs1 in reg(), rs2 in reg(), rs1_value in u32_extra(), rs2_value in u32_extra()) {
+            prop_assume!(rs1 != rs2);
+            let e = simple_test_code(
+                [Instruction::new(
+                    Op::ROR,
+                    Args {
+                        rd,
+                        rs1,
+                        rs2,
+                        ..Args::default()
+                    }
+                )],
+                &[],
+                &[(rs1, rs1_value), (rs2, rs2_value)]
+            );
+            assert_eq!(
+                e.state_before_final().get_register_value(rd),
+                rs1_value.rotate_right(rs2_value & 0b1_1111)
+            );
+        }
+
+        #[test]
+        fn rev8_proptest(rd in reg(), rs1 in reg(), rs1_value in u32_extra()) {
+            let e = simple_test_code(
+                [Instruction::new(
+                    Op::REV8,
+                    Args {
+                        rd,
+                        rs1,
+                        ..Args::default()
+                    }
+                )],
+                &[],
+                &[(rs1, rs1_value)]
+            );
+            assert_eq!(
+                e.state_before_final().get_register_value(rd),
+                rs1_value.swap_bytes()
+            );
+        }
+
         #[test]
         fn and_proptest(rd in reg(), rs1 in reg(), rs2 in reg(), rs1_value in u32_extra(), rs2_value in u32_extra()) {
             prop_assume!(rs1 != rs2);
@@ -1391,6 +1551,126 @@ mod tests {
     #[test]
     fn ecall() { let _ = simple_test_code([ECALL], &[], &[]); }
 
+    #[test]
+    fn atomics() {
+        let address = 0x0010_0000;
+        let old = 0x8000_0001;
+        let value = 7;
+        for (op, expected) in [
+            (Op::AMOSWAP, value),
+            (Op::AMOADD, 0x8000_0008),
+            (Op::AMOXOR, 0x8000_0006),
+            (Op::AMOAND, 1),
+            (Op::AMOOR, 0x8000_0007),
+            (Op::AMOMIN, old),
+            (Op::AMOMAX, value),
+            (Op::AMOMINU, value),
+            (Op::AMOMAXU, old),
+        ] {
+            let e = simple_test_code(
+                [Instruction::new(op, Args {
+                    rd: 10,
+                    rs1: 11,
+                    rs2: 12,
+                    ..Args::default()
+                })],
+                &(address..).zip(u32::to_le_bytes(old)).collect_vec(),
+                &[(11, value), (12, address)],
+            );
+            let state = e.state_before_final();
+            assert_eq!(state.get_register_value(10), old, "{op:?}");
+            let (_, memory_value) = lw(&[0, 1, 2, 3].map(|i| state.load_u8(address + i)));
+            assert_eq!(memory_value, expected, "{op:?}");
+        }
+    }
+
+    #[test]
+    fn rdcycle() {
+        let e = simple_test_code(
+            [10, 11].map(|rd| {
+                Instruction::new(Op::RDCYCLE, Args {
+                    rd,
+                    ..Args::default()
+                })
+            }),
+            &[],
+            &[],
+        );
+        let state = e.state_before_final();
+        assert_eq!(
+            state.get_register_value(11),
+            state.get_register_value(10) + 1
+        );
+    }
+
+    #[test]
+    fn rdcycleh() {
+        let (program, record) = code::execute(
+            [Instruction::new(Op::RDCYCLEH, Args {
+                rd: 10,
+                ..Args::default()
+            })],
+            &[],
+            &[],
+        );
+        let mut state = record.executed[0].state.clone();
+        state.clk = (5 << 32) + 7;
+        let (_, _, state) = state.execute_instruction(&program).unwrap();
+        assert_eq!(state.get_register_value(10), 5);
+    }
+
+    #[test]
+    fn csr_read_modify_write() {
+        let e = simple_test_code(
+            [
+                Instruction::new(Op::CSRRW, Args {
+                    rd: 10,
+                    rs1: REG_MSCRATCH,
+                    rs2: 11,
+                    ..Args::default()
+                }),
+                Instruction::new(Op::CSRRS, Args {
+                    rd: 12,
+                    rs1: REG_MSCRATCH,
+                    imm: 0b1000,
+                    ..Args::default()
+                }),
+                Instruction::new(Op::CSRRC, Args {
+                    rd: 13,
+                    rs1: REG_MSCRATCH,
+                    rs2: 11,
+                    ..Args::default()
+                }),
+            ],
+            &[],
+            &[(10, 1), (11, 0b0111)],
+        );
+        let state = e.state_before_final();
+        assert_eq!(state.get_register_value(10), 0);
+        assert_eq!(state.get_register_value(12), 0b0111);
+        assert_eq!(state.get_register_value(13), 0b1111);
+        assert_eq!(state.get_register_value(REG_MSCRATCH), 0b1000);
+    }
+
+    #[test]
+    fn store_conditional_always_succeeds() {
+        let address = 0x0010_0000;
+        let e = simple_test_code(
+            [Instruction::new(Op::SC, Args {
+                rd: 10,
+                rs1: 11,
+                rs2: 12,
+                ..Args::default()
+            })],
+            &(address..).zip([0; 4]).collect_vec(),
+            &[(10, 1), (11, 0xDEAD_BEEF), (12, address)],
+        );
+        let state = e.state_before_final();
+        assert_eq!(state.get_register_value(10), 0);
+        let (_, memory_value) = lw(&[0, 1, 2, 3].map(|i| state.load_u8(address + i)));
+        assert_eq!(memory_value, 0xDEAD_BEEF);
+    }
+
     #[test]
     fn lui() {
         // at 0 address instruction lui
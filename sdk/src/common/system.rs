@@ -255,5 +255,24 @@ pub fn ensure_clean_shutdown() {
         .0;
 
         assert!(claimed_commitment_cl == calculated_commitment_cl);
+
+        // Assert that private tape commitment tape has the same bytes as
+        // the commitment to the whole private tape, which ties the bytes
+        // disclosed from it to the commitment.  Whatever the program left
+        // unread is read here, so that every byte is committed to.
+        let mut claimed_commitment_pt: [u8; 32] = [0; 32];
+        crate::core::ecall::private_tape_commitment_tape_read(&mut claimed_commitment_pt);
+
+        let private_input_tape = &mut SYSTEM_TAPE.private_input_tape;
+        let read_bytes = private_input_tape.internal_buf.len();
+        let len = private_input_tape.len();
+        private_input_tape.internal_buf.resize(len, 0);
+        ioread_private(&mut private_input_tape.internal_buf[read_bytes..]);
+
+        let calculated_commitment_pt =
+            crate::mozakvm::poseidon::poseidon2_hash_with_pad(&private_input_tape.internal_buf)
+                .inner();
+
+        assert!(claimed_commitment_pt == calculated_commitment_pt);
     }
 }
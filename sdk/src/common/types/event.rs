@@ -28,6 +28,10 @@ pub enum EventType {
     Read,
     GiveOwner,
     TakeOwner,
+    /// Adds credits to, or takes credits from, the object. The value is
+    /// `[d, _, _, s]` as `u64`s, where `d` is the amount and `s` is `0` to
+    /// add or `1` to subtract.
+    CreditDelta,
 }
 
 impl Default for EventType {
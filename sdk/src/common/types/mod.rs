@@ -10,6 +10,8 @@ pub(crate) mod system_tape;
 pub use cross_program_call::CrossProgramCall;
 pub use event::{CanonicalEvent, CanonicalOrderedTemporalHints, Event, EventType};
 pub use poseidon2hash::Poseidon2Hash;
+#[cfg(not(target_os = "mozakvm"))]
+pub use program_identifier::ParseProgramIdentifierError;
 pub use program_identifier::ProgramIdentifier;
 pub use raw_message::RawMessage;
 pub use state_address::StateAddress;
@@ -75,15 +75,84 @@ impl std::fmt::Debug for ProgramIdentifier {
     }
 }
 
+/// Error returned when a string is not of the form `MZK-<64 hex digits>`.
+#[cfg(not(target_os = "mozakvm"))]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseProgramIdentifierError {
+    MissingPrefix,
+    InvalidHex(hex::FromHexError),
+    WrongLength(usize),
+}
+
+#[cfg(not(target_os = "mozakvm"))]
+impl std::fmt::Display for ParseProgramIdentifierError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingPrefix => write!(f, "program id must start with `MZK-`"),
+            Self::InvalidHex(err) => write!(f, "program id is not valid hex: {err}"),
+            Self::WrongLength(len) => write!(
+                f,
+                "program id must be {DIGEST_BYTES} bytes long, but it is {len} bytes long"
+            ),
+        }
+    }
+}
+
+#[cfg(not(target_os = "mozakvm"))]
+impl std::error::Error for ParseProgramIdentifierError {}
+
+#[cfg(not(target_os = "mozakvm"))]
+impl std::str::FromStr for ProgramIdentifier {
+    type Err = ParseProgramIdentifierError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s
+            .strip_prefix("MZK-")
+            .ok_or(ParseProgramIdentifierError::MissingPrefix)?;
+        let bytes = hex::decode(digits).map_err(ParseProgramIdentifierError::InvalidHex)?;
+        let bytes: [u8; DIGEST_BYTES] = bytes
+            .try_into()
+            .map_err(|bytes: Vec<u8>| ParseProgramIdentifierError::WrongLength(bytes.len()))?;
+        Ok(ProgramIdentifier(super::Poseidon2Hash::from(bytes)))
+    }
+}
+
+/// # Panics
+///
+/// Panics if `value` is not a valid program id; use [`str::parse`] to handle
+/// malformed input.
 #[cfg(not(target_os = "mozakvm"))]
 impl From<String> for ProgramIdentifier {
     fn from(value: String) -> ProgramIdentifier {
-        let components: Vec<&str> = value.split('-').collect();
-        assert_eq!(components.len(), 2);
-        assert_eq!(components[0], "MZK");
+        value
+            .parse()
+            .unwrap_or_else(|err| panic!("invalid program id {value:?}: {err}"))
+    }
+}
+
+#[cfg(all(test, not(target_os = "mozakvm")))]
+mod tests {
+    use super::{ParseProgramIdentifierError, ProgramIdentifier};
+
+    #[test]
+    fn parses_debug_output() {
+        let id = ProgramIdentifier::new_from_rand_seed(7);
+        assert_eq!(format!("{id:?}").parse(), Ok(id));
+    }
 
-        ProgramIdentifier(super::Poseidon2Hash::from(
-            hex::decode(components[1]).unwrap(),
-        ))
+    #[test]
+    fn rejects_malformed_ids() {
+        assert_eq!(
+            "0x00".parse::<ProgramIdentifier>(),
+            Err(ParseProgramIdentifierError::MissingPrefix)
+        );
+        assert!(matches!(
+            "MZK-zz".parse::<ProgramIdentifier>(),
+            Err(ParseProgramIdentifierError::InvalidHex(_))
+        ));
+        assert_eq!(
+            "MZK-00ff".parse::<ProgramIdentifier>(),
+            Err(ParseProgramIdentifierError::WrongLength(2))
+        );
     }
 }
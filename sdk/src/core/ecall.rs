@@ -16,6 +16,16 @@ pub const CAST_LIST_COMMITMENT_TAPE: u32 = 8;
 pub const SELF_PROG_ID_TAPE: u32 = 9;
 /// Syscall to output the VM trace log at `clk`. Useful for debugging.
 pub const VM_TRACE_LOG: u32 = 10;
+/// Syscall for one application of the SHA-256 compression function.
+pub const SHA256: u32 = 11;
+/// Syscall for one application of the BLAKE3 compression function.
+pub const BLAKE3: u32 = 12;
+/// Syscall to read the oracle tape: externally attested data that the node
+/// supplies, and that is made public in the proof.
+pub const ORACLE_TAPE: u32 = 13;
+/// Syscall to read the commitment to the whole private tape, which is made
+/// public in the proof.
+pub const PRIVATE_TAPE_COMMITMENT_TAPE: u32 = 14;
 
 #[must_use]
 pub fn log<'a>(raw_id: u32) -> &'a str {
@@ -31,6 +41,10 @@ pub fn log<'a>(raw_id: u32) -> &'a str {
         CAST_LIST_COMMITMENT_TAPE => "ioread cast list commitment tape",
         SELF_PROG_ID_TAPE => "self prog id tape",
         VM_TRACE_LOG => "vm trace log",
+        SHA256 => "sha256",
+        BLAKE3 => "blake3",
+        ORACLE_TAPE => "ioread oracle tape",
+        PRIVATE_TAPE_COMMITMENT_TAPE => "ioread private tape commitment tape",
         _ => "",
     }
 }
@@ -48,6 +62,49 @@ pub fn poseidon2(input_ptr: *const u8, input_len: usize, output_ptr: *mut u8) {
     }
 }
 
+/// Compresses the 64 byte block at `block_ptr` into the 32 byte state at
+/// `state_ptr`, and writes the new state to `output_ptr`.
+#[cfg(target_os = "mozakvm")]
+pub fn sha256(state_ptr: *const u8, block_ptr: *const u8, output_ptr: *mut u8) {
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            in ("a0") SHA256,
+            in ("a1") state_ptr,
+            in ("a2") block_ptr,
+            in ("a3") output_ptr,
+        );
+    }
+}
+
+/// Compresses the 64 byte block at `block_ptr` into the 32 byte chaining
+/// value at `cv_ptr`, and writes the new chaining value to `output_ptr`.
+/// The 64 bit `counter` is passed as two words, low word first.
+#[cfg(target_os = "mozakvm")]
+pub fn blake3(
+    cv_ptr: *const u8,
+    block_ptr: *const u8,
+    output_ptr: *mut u8,
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) {
+    let [counter_lo, counter_hi]: [u32; 2] = crate::core::blake3::words(&counter.to_le_bytes());
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            in ("a0") BLAKE3,
+            in ("a1") cv_ptr,
+            in ("a2") block_ptr,
+            in ("a3") output_ptr,
+            in ("a4") counter_lo,
+            in ("a5") counter_hi,
+            in ("a6") block_len,
+            in ("a7") flags,
+        );
+    }
+}
+
 #[cfg(target_os = "mozakvm")]
 pub fn ioread_private(buf: &mut [u8]) {
     unsafe {
@@ -135,6 +192,32 @@ pub fn self_prog_id_tape_read(buf: &mut [u8]) {
     }
 }
 
+#[cfg(target_os = "mozakvm")]
+pub fn oracle_tape_read(buf: &mut [u8]) {
+    assert!(buf.len() == DIGEST_BYTES);
+    unsafe {
+        core::arch::asm!(
+        "ecall",
+        in ("a0") ORACLE_TAPE,
+        in ("a1") buf.as_mut_ptr(),
+        in ("a2") buf.len(),
+        );
+    }
+}
+
+#[cfg(target_os = "mozakvm")]
+pub fn private_tape_commitment_tape_read(buf: &mut [u8]) {
+    assert!(buf.len() == DIGEST_BYTES);
+    unsafe {
+        core::arch::asm!(
+        "ecall",
+        in ("a0") PRIVATE_TAPE_COMMITMENT_TAPE,
+        in ("a1") buf.as_mut_ptr(),
+        in ("a2") buf.len(),
+        );
+    }
+}
+
 #[cfg(target_os = "mozakvm")]
 pub fn panic(msg: &str) {
     unsafe {
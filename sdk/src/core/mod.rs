@@ -1,10 +1,12 @@
 #[cfg(target_os = "mozakvm")]
 mod alloc;
+pub mod blake3;
 #[cfg(target_os = "mozakvm")]
 pub mod debug_macros;
 pub mod ecall;
 pub mod env;
 pub mod reg_abi;
+pub mod sha256;
 
 pub mod constants {
     /// The size of a `Poseidon2Hash` digest in bytes.
@@ -0,0 +1,215 @@
+//! The SHA-256 compression function, as specified in FIPS 180-4.
+//!
+//! This is the reference the `SHA256` ecall of the VM, and the circuits that
+//! prove it, are checked against.  States and blocks are passed around as
+//! big-endian bytes, the same way they are laid out in VM memory.
+
+/// The size of a SHA-256 state in bytes.
+pub const STATE_BYTES: usize = 32;
+
+/// The size of a SHA-256 message block in bytes.
+pub const BLOCK_BYTES: usize = 64;
+
+/// The number of rounds of the compression function.
+pub const ROUNDS: usize = 64;
+
+/// The initial hash value.
+pub const IV: [u32; 8] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+/// [`IV`] as the bytes [`compress`] takes.
+pub const INITIAL_STATE: [u8; STATE_BYTES] = {
+    let mut state = [0; STATE_BYTES];
+    let mut i = 0;
+    while i < STATE_BYTES {
+        state[i] = IV[i / 4].to_be_bytes()[i % 4];
+        i += 1;
+    }
+    state
+};
+
+/// The round constants.
+pub const K: [u32; ROUNDS] = [
+    0x428a_2f98,
+    0x7137_4491,
+    0xb5c0_fbcf,
+    0xe9b5_dba5,
+    0x3956_c25b,
+    0x59f1_11f1,
+    0x923f_82a4,
+    0xab1c_5ed5,
+    0xd807_aa98,
+    0x1283_5b01,
+    0x2431_85be,
+    0x550c_7dc3,
+    0x72be_5d74,
+    0x80de_b1fe,
+    0x9bdc_06a7,
+    0xc19b_f174,
+    0xe49b_69c1,
+    0xefbe_4786,
+    0x0fc1_9dc6,
+    0x240c_a1cc,
+    0x2de9_2c6f,
+    0x4a74_84aa,
+    0x5cb0_a9dc,
+    0x76f9_88da,
+    0x983e_5152,
+    0xa831_c66d,
+    0xb003_27c8,
+    0xbf59_7fc7,
+    0xc6e0_0bf3,
+    0xd5a7_9147,
+    0x06ca_6351,
+    0x1429_2967,
+    0x27b7_0a85,
+    0x2e1b_2138,
+    0x4d2c_6dfc,
+    0x5338_0d13,
+    0x650a_7354,
+    0x766a_0abb,
+    0x81c2_c92e,
+    0x9272_2c85,
+    0xa2bf_e8a1,
+    0xa81a_664b,
+    0xc24b_8b70,
+    0xc76c_51a3,
+    0xd192_e819,
+    0xd699_0624,
+    0xf40e_3585,
+    0x106a_a070,
+    0x19a4_c116,
+    0x1e37_6c08,
+    0x2748_774c,
+    0x34b0_bcb5,
+    0x391c_0cb3,
+    0x4ed8_aa4a,
+    0x5b9c_ca4f,
+    0x682e_6ff3,
+    0x748f_82ee,
+    0x78a5_636f,
+    0x84c8_7814,
+    0x8cc7_0208,
+    0x90be_fffa,
+    0xa450_6ceb,
+    0xbef9_a3f7,
+    0xc671_78f2,
+];
+
+#[must_use]
+pub const fn big_sigma0(x: u32) -> u32 {
+    x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22)
+}
+
+#[must_use]
+pub const fn big_sigma1(x: u32) -> u32 {
+    x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25)
+}
+
+#[must_use]
+pub const fn small_sigma0(x: u32) -> u32 { x.rotate_right(7) ^ x.rotate_right(18) ^ (x >> 3) }
+
+#[must_use]
+pub const fn small_sigma1(x: u32) -> u32 { x.rotate_right(17) ^ x.rotate_right(19) ^ (x >> 10) }
+
+#[must_use]
+pub const fn ch(x: u32, y: u32, z: u32) -> u32 { (x & y) ^ (!x & z) }
+
+#[must_use]
+pub const fn maj(x: u32, y: u32, z: u32) -> u32 { (x & y) ^ (x & z) ^ (y & z) }
+
+/// Reads big-endian words from `bytes`.
+#[must_use]
+pub fn words<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    assert_eq!(bytes.len(), 4 * N);
+    core::array::from_fn(|i| {
+        u32::from_be_bytes([
+            bytes[4 * i],
+            bytes[4 * i + 1],
+            bytes[4 * i + 2],
+            bytes[4 * i + 3],
+        ])
+    })
+}
+
+/// Expands `block` into the first `N` words of the message schedule.
+///
+/// The compression function only needs the first [`ROUNDS`] words, but the
+/// schedule is well defined beyond that.
+#[must_use]
+pub fn message_schedule<const N: usize>(block: &[u8; BLOCK_BYTES]) -> [u32; N] {
+    let block: [u32; 16] = words(block);
+    let mut w = [0; N];
+    for t in 0..N {
+        w[t] = if t < 16 {
+            block[t]
+        } else {
+            small_sigma1(w[t - 2])
+                .wrapping_add(w[t - 7])
+                .wrapping_add(small_sigma0(w[t - 15]))
+                .wrapping_add(w[t - 16])
+        };
+    }
+    w
+}
+
+/// One round of the compression function, on the working variables
+/// `a..=h`, with round constant `k` and message word `w`.
+#[must_use]
+#[allow(clippy::many_single_char_names)]
+pub fn round([a, b, c, d, e, f, g, h]: [u32; 8], k: u32, w: u32) -> [u32; 8] {
+    let t1 = h
+        .wrapping_add(big_sigma1(e))
+        .wrapping_add(ch(e, f, g))
+        .wrapping_add(k)
+        .wrapping_add(w);
+    let t2 = big_sigma0(a).wrapping_add(maj(a, b, c));
+    [t1.wrapping_add(t2), a, b, c, d.wrapping_add(t1), e, f, g]
+}
+
+/// Compresses `block` into `state`, and returns the new state.
+///
+/// Hashing a padded message is a fold of this function over its blocks,
+/// starting from [`IV`].
+#[must_use]
+pub fn compress(state: &[u8; STATE_BYTES], block: &[u8; BLOCK_BYTES]) -> [u8; STATE_BYTES] {
+    let initial: [u32; 8] = words(state);
+    let schedule: [u32; ROUNDS] = message_schedule(block);
+    let mut working = initial;
+    for (&k, &w) in K.iter().zip(&schedule) {
+        working = round(working, k, w);
+    }
+    let mut output = [0; STATE_BYTES];
+    for (i, (x, y)) in initial.into_iter().zip(working).enumerate() {
+        output[4 * i..4 * i + 4].copy_from_slice(&x.wrapping_add(y).to_be_bytes());
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, BLOCK_BYTES, INITIAL_STATE};
+
+    #[test]
+    fn sha256_of_abc() {
+        // "abc", padded to a single block.
+        let mut block = [0; BLOCK_BYTES];
+        block[..3].copy_from_slice(b"abc");
+        block[3] = 0x80;
+        block[BLOCK_BYTES - 1] = 24;
+
+        assert_eq!(compress(&INITIAL_STATE, &block), [
+            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+            0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+            0xf2, 0x00, 0x15, 0xad,
+        ]);
+    }
+}
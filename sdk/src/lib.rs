@@ -23,22 +23,31 @@ pub mod mozakvm;
 #[cfg(all(feature = "std", not(target_os = "mozakvm")))]
 pub mod native;
 
+#[cfg(all(feature = "std", target_os = "mozakvm"))]
+pub use crate::mozakvm::blake3::blake3_compress;
 /// Provides the length of tape available to read
 #[cfg(all(feature = "std", target_os = "mozakvm"))]
 pub use crate::mozakvm::inputtape::input_tape_len;
 /// Reads utmost given number of raw bytes from an input tape
 #[cfg(all(feature = "std", feature = "stdread", target_os = "mozakvm"))]
 pub use crate::mozakvm::inputtape::read;
+/// Reads the externally attested data the node supplied on the oracle tape
+#[cfg(all(feature = "std", target_os = "mozakvm"))]
+pub use crate::mozakvm::oracle::read_oracle;
 #[cfg(all(feature = "std", target_os = "mozakvm"))]
 pub use crate::mozakvm::poseidon::poseidon2_hash_no_pad;
 #[cfg(all(feature = "std", target_os = "mozakvm"))]
 pub use crate::mozakvm::poseidon::poseidon2_hash_with_pad;
+#[cfg(all(feature = "std", target_os = "mozakvm"))]
+pub use crate::mozakvm::sha256::sha256_compress;
 /// Manually add a `ProgramIdentifier` onto `IdentityStack`. Useful
 /// when one want to escape automatic management of `IdentityStack`
 /// via cross-program-calls sends (ideally temporarily).
 /// CAUTION: Manual function for `IdentityStack`, misuse may lead
 /// to system tape generation failure.
 #[cfg(all(feature = "std", not(target_os = "mozakvm")))]
+pub use crate::native::blake3::blake3_compress;
+#[cfg(all(feature = "std", not(target_os = "mozakvm")))]
 pub use crate::native::identity::add_identity;
 /// Manually remove a `ProgramIdentifier` from `IdentityStack`.
 /// Useful when one want to escape automatic management of `IdentityStack`
@@ -50,6 +59,8 @@ pub use crate::native::identity::rm_identity;
 /// Writes raw bytes to an input tape. Infallible
 #[cfg(all(feature = "std", not(target_os = "mozakvm")))]
 pub use crate::native::inputtape::write;
+#[cfg(all(feature = "std", not(target_os = "mozakvm")))]
+pub use crate::native::sha256::sha256_compress;
 
 pub enum InputTapeType {
     PublicTape,
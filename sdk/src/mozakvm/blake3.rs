@@ -0,0 +1,28 @@
+// This file contains code snippets used in mozakvm execution
+
+use crate::core::blake3::{BLOCK_BYTES, CV_BYTES};
+
+/// Compresses `block` into the chaining value `cv` with the BLAKE3
+/// compression function, and returns the new chaining value.
+///
+/// This is proven by the VM's BLAKE3 precompile, which is a lot cheaper
+/// than proving the same function compiled to RISC-V.
+#[must_use]
+pub fn blake3_compress(
+    cv: &[u8; CV_BYTES],
+    block: &[u8; BLOCK_BYTES],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u8; CV_BYTES] {
+    let mut output = [0; CV_BYTES];
+    crate::core::ecall::blake3(
+        cv.as_ptr(),
+        block.as_ptr(),
+        output.as_mut_ptr(),
+        counter,
+        block_len,
+        flags,
+    );
+    output
+}
@@ -1,4 +1,7 @@
+pub(crate) mod blake3;
 pub(crate) mod calltape;
 pub(crate) mod eventtape;
 pub(crate) mod inputtape;
+pub(crate) mod oracle;
 pub(crate) mod poseidon;
+pub(crate) mod sha256;
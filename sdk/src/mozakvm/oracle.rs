@@ -0,0 +1,17 @@
+// This file contains code snippets used in mozakvm execution
+
+use crate::core::constants::DIGEST_BYTES;
+
+/// Reads the oracle tape: the externally attested data that the node
+/// supplied for this run, eg a block header hash.
+///
+/// The oracle tape is exposed as a public input of the proof, so a verifier
+/// can check that the guest saw the data the node claims it attested to.
+/// Bulkier data can be passed via the private tape, and checked against a
+/// digest read from here.
+#[must_use]
+pub fn read_oracle() -> [u8; DIGEST_BYTES] {
+    let mut oracle = [0; DIGEST_BYTES];
+    crate::core::ecall::oracle_tape_read(&mut oracle);
+    oracle
+}
@@ -0,0 +1,15 @@
+// This file contains code snippets used in mozakvm execution
+
+use crate::core::sha256::{BLOCK_BYTES, STATE_BYTES};
+
+/// Compresses `block` into `state` with the SHA-256 compression function,
+/// and returns the new state.
+///
+/// This is proven by the VM's SHA-256 precompile, which is a lot cheaper
+/// than proving the same function compiled to RISC-V.
+#[must_use]
+pub fn sha256_compress(state: &[u8; STATE_BYTES], block: &[u8; BLOCK_BYTES]) -> [u8; STATE_BYTES] {
+    let mut output = [0; STATE_BYTES];
+    crate::core::ecall::sha256(state.as_ptr(), block.as_ptr(), output.as_mut_ptr());
+    output
+}
@@ -0,0 +1,18 @@
+//! This file contains code snippets used in native execution
+
+use crate::core::blake3::{compress, BLOCK_BYTES, CV_BYTES};
+
+/// Compresses `block` into the chaining value `cv` with the BLAKE3
+/// compression function, and returns the new chaining value.
+///
+/// Native counterpart of the VM's BLAKE3 precompile.
+#[must_use]
+pub fn blake3_compress(
+    cv: &[u8; CV_BYTES],
+    block: &[u8; BLOCK_BYTES],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u8; CV_BYTES] {
+    compress(cv, block, counter, block_len, flags)
+}
@@ -1,8 +1,10 @@
+pub mod blake3;
 pub(crate) mod calltape;
 pub(crate) mod eventtape;
 pub mod identity;
 pub(crate) mod inputtape;
 pub mod poseidon;
+pub mod sha256;
 pub mod systemtape;
 
 pub use eventtape::OrderedEvents;
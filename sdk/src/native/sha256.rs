@@ -0,0 +1,12 @@
+//! This file contains code snippets used in native execution
+
+use crate::core::sha256::{compress, BLOCK_BYTES, STATE_BYTES};
+
+/// Compresses `block` into `state` with the SHA-256 compression function,
+/// and returns the new state.
+///
+/// Native counterpart of the VM's SHA-256 precompile.
+#[must_use]
+pub fn sha256_compress(state: &[u8; STATE_BYTES], block: &[u8; BLOCK_BYTES]) -> [u8; STATE_BYTES] {
+    compress(state, block)
+}
@@ -0,0 +1,14 @@
+"""Serves the demo with the headers that enable `SharedArrayBuffer`, which
+builds with the `parallel` feature need for their thread pool."""
+from http.server import SimpleHTTPRequestHandler, ThreadingHTTPServer
+
+
+class CrossOriginIsolatedHandler(SimpleHTTPRequestHandler):
+    def end_headers(self):
+        self.send_header("Cross-Origin-Opener-Policy", "same-origin")
+        self.send_header("Cross-Origin-Embedder-Policy", "require-corp")
+        super().end_headers()
+
+
+if __name__ == "__main__":
+    ThreadingHTTPServer(("", 8000), CrossOriginIsolatedHandler).serve_forever()
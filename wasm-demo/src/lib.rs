@@ -1,9 +1,21 @@
 #![allow(dead_code, unused_imports)]
-use mozak_circuits::test_utils::prove_and_verify_mozak_stark;
+use mozak_circuits::generation::generate_traces;
+use mozak_circuits::stark::mozak_stark::{MozakStark, PublicInputs};
+use mozak_circuits::stark::prover::{get_self_prog_id, prove_with_traces};
+use mozak_circuits::stark::verifier::verify_proof;
+use mozak_circuits::system_tape::raw_tapes_from_system_tape;
+use mozak_circuits::test_utils::{prove_and_verify_mozak_stark, C, D, F};
 use mozak_runner::code;
+use mozak_runner::elf::load_program;
 use mozak_runner::instruction::{Args, Instruction, Op};
+use mozak_runner::state::State;
+use mozak_runner::vm::step;
+use plonky2::field::types::Field;
+use plonky2::util::timing::TimingTree;
 use starky::config::StarkConfig;
 use wasm_bindgen::prelude::*;
+#[cfg(feature = "parallel")]
+pub use wasm_bindgen_rayon::init_thread_pool;
 
 extern crate console_error_panic_hook;
 use std::panic;
@@ -52,3 +64,61 @@ pub fn wasm_demo_(a: u32, b: u32) {
     let proving_res = prove_and_verify_mozak_stark(&e.0, &e.1, &config);
     println!("Proving :{}", proving_res.is_ok());
 }
+
+/// Runs the ELF in `elf`, optionally with the serde JSON serialized system
+/// tape in `system_tape`, and proves its execution.
+///
+/// `progress` is called with the name of each stage as it starts: `execute`,
+/// `traces`, `prove`, `verify` and finally `done`. Returns the serde JSON
+/// serialized `AllProof`, the same format as written by `mozak-cli prove`.
+///
+/// # Errors
+///
+/// Errors if the ELF does not load, the program fails to execute, or proving
+/// or verifying fails.
+#[wasm_bindgen]
+pub fn prove_elf(
+    elf: &[u8],
+    system_tape: Option<Vec<u8>>,
+    progress: &js_sys::Function,
+) -> Result<Vec<u8>, JsError> {
+    panic::set_hook(Box::new(console_error_panic_hook::hook));
+    let report = |stage: &str| {
+        // A failing progress callback should not abort the proof.
+        let _ = progress.call1(&JsValue::NULL, &JsValue::from_str(stage));
+    };
+    let config = StarkConfig::standard_fast_config();
+    let mut timing = TimingTree::default();
+
+    report("execute");
+    let program = load_program(elf).map_err(|e| JsError::new(&e.to_string()))?;
+    let self_prog_id = get_self_prog_id::<F, C, D>(&program, &config);
+    let raw_tapes = raw_tapes_from_system_tape(system_tape.as_deref(), self_prog_id);
+    let state = State::new(program.clone(), raw_tapes);
+    let record = step(&program, state).map_err(|e| JsError::new(&e.to_string()))?;
+
+    report("traces");
+    let traces_poly_values = generate_traces(&program, &record, &mut timing)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+
+    report("prove");
+    let stark = MozakStark::default();
+    let public_inputs = PublicInputs {
+        entry_point: F::from_canonical_u32(program.entry_point),
+    };
+    let all_proof = prove_with_traces::<F, C, D>(
+        &stark,
+        &config,
+        public_inputs,
+        &traces_poly_values,
+        &mut timing,
+    )
+    .map_err(|e| JsError::new(&e.to_string()))?;
+    let proof_bytes = serde_json::to_vec(&all_proof)?;
+
+    report("verify");
+    verify_proof(&stark, all_proof, &config).map_err(|e| JsError::new(&e.to_string()))?;
+
+    report("done");
+    Ok(proof_bytes)
+}
@@ -0,0 +1,25 @@
+// Proves an ELF off the main thread, so the page stays responsive.
+//
+// Expects a message `{ elf, systemTape }` of `Uint8Array`s, `systemTape` being
+// optional. Answers with `{ threads }` once ready, then with `{ stage }`
+// messages as proving progresses, followed by either `{ proof }` or `{ error }`.
+import init, * as wasm from "./pkg/wasm_demo.js";
+
+// Builds with the `parallel` feature export `initThreadPool`, and prove on a
+// pool of one worker per core.
+const ready = init().then(async () => {
+  const threads = wasm.initThreadPool ? navigator.hardwareConcurrency : 1;
+  if (wasm.initThreadPool) await wasm.initThreadPool(threads);
+  self.postMessage({ threads });
+});
+
+self.onmessage = async ({ data: { elf, systemTape } }) => {
+  await ready;
+  try {
+    const proof = wasm.prove_elf(elf, systemTape, (stage) => self.postMessage({ stage }));
+    // Hand the proof over instead of copying it.
+    self.postMessage({ proof }, [proof.buffer]);
+  } catch (error) {
+    self.postMessage({ error: error.toString() });
+  }
+};
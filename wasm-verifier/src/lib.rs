@@ -0,0 +1,89 @@
+//! Verification of Mozak proofs from JS, without shipping the prover.
+//!
+//! Proofs are in the formats written by `mozak-cli prove`.
+use std::panic;
+
+use mozak_circuits::stark::mozak_stark::MozakStark;
+use mozak_circuits::stark::proof::{AllProof, ProofContainer, ProofPayload, PROOF_MAGIC};
+use mozak_circuits::stark::recursive_verifier::{
+    circuit_data_for_recursion, VMRecursiveProofPublicInputs, VM_PUBLIC_INPUT_SIZE,
+    VM_RECURSION_CONFIG, VM_RECURSION_THRESHOLD_DEGREE_BITS,
+};
+use mozak_circuits::stark::verifier::verify_proof;
+use mozak_sdk::common::types::ProgramIdentifier;
+use plonky2::field::types::Field;
+use plonky2::plonk::circuit_data::VerifierOnlyCircuitData;
+use plonky2::plonk::config::{GenericConfig, Poseidon2GoldilocksConfig};
+use plonky2::plonk::proof::ProofWithPublicInputs;
+use starky::config::StarkConfig;
+use wasm_bindgen::prelude::*;
+
+const D: usize = 2;
+type C = Poseidon2GoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+fn parse_program_id(program_id: &str) -> Result<ProgramIdentifier, JsError> {
+    program_id
+        .parse()
+        .map_err(|e| JsError::new(&format!("invalid program id {program_id:?}: {e}")))
+}
+
+/// Verifies a recursive VM proof, in the uncompressed binary format or in
+/// plonky2's byte format, against the verifier key in `vk_bytes`, and that it
+/// proves the execution of `program_id`, eg `MZK-<hex>`.
+///
+/// # Errors
+///
+/// Errors if either `proof_bytes` or `vk_bytes` does not deserialize, or if
+/// `program_id` is malformed.
+#[wasm_bindgen]
+pub fn verify(proof_bytes: Vec<u8>, vk_bytes: Vec<u8>, program_id: &str) -> Result<bool, JsError> {
+    panic::set_hook(Box::new(console_error_panic_hook::hook));
+    let program_id = parse_program_id(program_id)?;
+    let mut circuit = circuit_data_for_recursion::<F, C, D>(
+        &VM_RECURSION_CONFIG,
+        VM_RECURSION_THRESHOLD_DEGREE_BITS,
+        VM_PUBLIC_INPUT_SIZE,
+    );
+    circuit.verifier_only = VerifierOnlyCircuitData::from_bytes(vk_bytes)
+        .map_err(|_| JsError::new("VerifierOnlyCircuitData deserialization failed."))?;
+    let proof_bytes = if proof_bytes.starts_with(&PROOF_MAGIC) {
+        match ProofContainer::<F, C, D>::from_bytes(&proof_bytes).map(|c| c.payload) {
+            Ok(ProofPayload::Recursive(bytes)) => bytes,
+            _ => return Err(JsError::new("not a recursive proof in the binary format")),
+        }
+    } else {
+        proof_bytes
+    };
+    let proof = ProofWithPublicInputs::<F, C, D>::from_bytes(proof_bytes, &circuit.common)
+        .map_err(|_| JsError::new("not a recursive proof for this verifier key"))?;
+    let public_inputs = VMRecursiveProofPublicInputs::from_public_inputs(&proof.public_inputs)
+        .ok_or_else(|| JsError::new("not the public inputs of a recursive VM proof"))?;
+    let is_for_program = public_inputs
+        .program_hash_as_bytes
+        .iter()
+        .eq(program_id.inner().map(F::from_canonical_u8).iter());
+    Ok(is_for_program && circuit.verify(proof).is_ok())
+}
+
+/// Verifies an `AllProof` of the VM, serialized as JSON.  The binary format
+/// needs the `container` feature of `mozak-circuits`, which this crate does
+/// not enable to stay free of native dependencies.  The proof must be of the
+/// execution of `program_id`.
+///
+/// # Errors
+///
+/// Errors if `proof_bytes` does not deserialize, or if `program_id` is
+/// malformed.
+#[wasm_bindgen(js_name = verifyStark)]
+pub fn verify_stark(proof_bytes: &[u8], program_id: &str) -> Result<bool, JsError> {
+    panic::set_hook(Box::new(console_error_panic_hook::hook));
+    let program_id = parse_program_id(program_id)?;
+    let all_proof =
+        AllProof::<F, C, D>::decode(proof_bytes).map_err(|e| JsError::new(&e.to_string()))?;
+    if all_proof.program_id != program_id {
+        return Ok(false);
+    }
+    let config = StarkConfig::standard_fast_config();
+    Ok(verify_proof(&MozakStark::default(), all_proof, &config).is_ok())
+}